@@ -0,0 +1,124 @@
+//! `ALTER TABLE`, whose operations Cassandra restricts by the columns' part in the primary key:
+//! the primary key can't lose a column, so only regular columns are dropped, and since renaming
+//! a regular column would break the data already written under its name, only primary key
+//! columns are renamed.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    lexer::Token,
+    schema::Table,
+    statement::{Statement, StatementKind, matching_bracket, parse_qualified_name, skip_if_exists},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Add,
+    Drop,
+    Rename,
+    With,
+}
+
+/// An `ALTER TABLE` statement, as far as it's been typed
+#[derive(Debug)]
+pub struct AlterTable<'a> {
+    /// The index of the token after the table's name
+    pub body: usize,
+    /// The operation, with the index of the tokens after it and an `IF EXISTS` following it
+    pub operation: Option<(Operation, usize)>,
+    /// The columns dropped, or renamed from
+    pub columns: Vec<&'a Token<'a>>,
+}
+
+/// Parses `ALTER TABLE [IF EXISTS] t operation ...`, returning `None` for other statements or
+/// before the table is named
+pub fn parse<'a>(statement: &Statement<'a>) -> Option<AlterTable<'a>> {
+    if statement.kind() != StatementKind::AlterTable {
+        return None;
+    }
+
+    let tokens = statement.tokens;
+    let mut i = skip_if_exists(tokens, 2);
+    parse_qualified_name(tokens, &mut i)?;
+
+    let operation = tokens.get(i).and_then(|token| {
+        let operation = if token.is_keyword("ADD") {
+            Operation::Add
+        } else if token.is_keyword("DROP") {
+            Operation::Drop
+        } else if token.is_keyword("RENAME") {
+            Operation::Rename
+        } else if token.is_keyword("WITH") {
+            Operation::With
+        } else {
+            return None;
+        };
+
+        Some((operation, skip_if_exists(tokens, i + 1)))
+    });
+
+    let columns = match operation {
+        // `DROP a` or `DROP (a, b)`
+        Some((Operation::Drop, start)) => match tokens.get(start) {
+            Some(token) if token.is_punctuation("(") => {
+                let end = matching_bracket(tokens, start).unwrap_or(tokens.len());
+
+                tokens[start + 1..end]
+                    .iter()
+                    .filter(|t| t.is_identifier())
+                    .collect()
+            }
+            Some(token) if token.is_identifier() => vec![token],
+            _ => vec![],
+        },
+        // `a TO b AND c TO d`
+        Some((Operation::Rename, start)) => tokens[start.min(tokens.len())..]
+            .chunks(4)
+            .take_while(|rename| rename.get(1).is_some_and(|t| t.is_keyword("TO")))
+            .map(|rename| &rename[0])
+            .collect(),
+        _ => vec![],
+    };
+
+    Some(AlterTable {
+        body: i,
+        operation,
+        columns,
+    })
+}
+
+/// Checks the columns an `ALTER TABLE` of `table` drops or renames: dropping a primary key
+/// column or renaming another column fails
+pub fn check(alter: &AlterTable, table: &Table) -> Vec<Problem> {
+    let Some((operation, _)) = alter.operation else {
+        return vec![];
+    };
+
+    alter
+        .columns
+        .iter()
+        .filter_map(|token| {
+            let column = token.identifier()?;
+            table.column(&column)?;
+
+            let message = match (operation, table.is_primary_key(&column)) {
+                (Operation::Drop, true) => format!(
+                    "Cannot drop `{column}`: it's part of the primary key of `{}`",
+                    table.name
+                ),
+                (Operation::Rename, false) => {
+                    format!("Cannot rename `{column}`: only primary key columns can be renamed")
+                }
+                _ => return None,
+            };
+
+            Some(Problem {
+                span: token.start..token.end,
+                severity: DiagnosticSeverity::ERROR,
+                message,
+                lint: None,
+            })
+        })
+        .collect()
+}
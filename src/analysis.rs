@@ -0,0 +1,483 @@
+//! A static "explain" of DML statements, which Cassandra lacks: from the restrictions of the
+//! `WHERE` clause and the schema, the partitions and rows a statement reaches, whether it needs
+//! `ALLOW FILTERING` or uses an index, and what its consistency level means for it.
+
+use crate::{
+    cluster::consistency::{Consistencies, Consistency},
+    lexer::Token,
+    schema::Table,
+    statement::{Statement, StatementKind, matching_bracket},
+};
+
+/// How a relation of the `WHERE` clause restricts its column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Restriction {
+    Equal,
+    /// `IN`, with its number of values when they're listed
+    In(Option<usize>),
+    Range,
+    Contains,
+    /// `!=`, `LIKE` and the like, which only filtering or an index can serve
+    Other,
+}
+
+impl Restriction {
+    /// Whether the column is restricted to a set of values, as the keys of a partition or row
+    /// must be
+    fn is_values(self) -> bool {
+        matches!(self, Restriction::Equal | Restriction::In(_))
+    }
+}
+
+/// The relations of the `WHERE` clause, as the columns they restrict and how, and whether the
+/// partition key is restricted with `token(...)`
+#[derive(Debug, Default)]
+struct Relations {
+    columns: Vec<(String, Restriction)>,
+    token: bool,
+}
+
+impl Relations {
+    fn get(&self, column: &str) -> Option<Restriction> {
+        self.columns
+            .iter()
+            .find(|(name, _)| name == column)
+            .map(|(_, restriction)| *restriction)
+    }
+}
+
+/// The restriction of the operator starting `tokens`
+fn restriction(tokens: &[Token]) -> Option<Restriction> {
+    let operator = tokens.first()?;
+
+    if operator.is_keyword("IN") {
+        let values = tokens
+            .get(1)
+            .filter(|open| open.is_punctuation("("))
+            .and_then(|_| {
+                let close = matching_bracket(tokens, 1)?;
+                let values = &tokens[2..close];
+
+                Some(match values.is_empty() {
+                    true => 0,
+                    false => 1 + values.iter().filter(|t| t.is_punctuation(",")).count(),
+                })
+            });
+
+        return Some(Restriction::In(values));
+    }
+
+    if operator.is_keyword("CONTAINS") {
+        return Some(Restriction::Contains);
+    }
+
+    Some(match operator.text {
+        "=" => Restriction::Equal,
+        "<" | "<=" | ">" | ">=" => Restriction::Range,
+        _ => Restriction::Other,
+    })
+}
+
+/// Parses the relations of the `WHERE` clause of `statement`
+fn relations(statement: &Statement) -> Relations {
+    let mut relations = Relations::default();
+
+    let Some(r#where) = statement.find_keyword("WHERE") else {
+        return relations;
+    };
+
+    let end = ["GROUP", "ORDER", "PER", "LIMIT", "ALLOW", "IF"]
+        .iter()
+        .filter_map(|keyword| statement.find_keyword_from(keyword, r#where))
+        .min()
+        .unwrap_or(statement.tokens.len());
+
+    let tokens = &statement.tokens[r#where + 1..end];
+
+    // Relations are joined by top-level `AND`s
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut parts = vec![];
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token.text {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => depth = depth.saturating_sub(1),
+            _ if depth == 0 && token.is_keyword("AND") => {
+                parts.push(&tokens[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&tokens[start..]);
+
+    for relation in parts {
+        match relation {
+            [function, open, ..] if function.is_keyword("TOKEN") && open.is_punctuation("(") => {
+                relations.token = true;
+            }
+            // `(a, b) > (1, 2)`
+            [open, ..] if open.is_punctuation("(") => {
+                let Some(close) = matching_bracket(relation, 0) else {
+                    continue;
+                };
+
+                let Some(restriction) = restriction(&relation[close + 1..]) else {
+                    continue;
+                };
+
+                relations.columns.extend(
+                    relation[1..close]
+                        .iter()
+                        .filter_map(Token::identifier)
+                        .map(|column| (column, restriction)),
+                );
+            }
+            [column, operator @ ..] => {
+                if let Some(column) = column.identifier()
+                    && let Some(restriction) = restriction(operator)
+                {
+                    relations.columns.push((column, restriction));
+                }
+            }
+            [] => {}
+        }
+    }
+
+    relations
+}
+
+fn names(columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|column| format!("`{column}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The partitions the statement reaches, and whether they're given by the partition key rather
+/// than scanned for
+fn partitions(statement: &Statement, table: &Table, relations: &Relations) -> (String, bool) {
+    if statement.kind() == StatementKind::Insert {
+        return (
+            "A single partition, given by the inserted key.".into(),
+            true,
+        );
+    }
+
+    let restrictions = table
+        .partition_key
+        .iter()
+        .map(|column| relations.get(column))
+        .collect::<Vec<_>>();
+
+    if restrictions
+        .iter()
+        .all(|restriction| restriction.is_some_and(Restriction::is_values))
+    {
+        let counts = restrictions
+            .iter()
+            .map(|restriction| match restriction {
+                Some(Restriction::In(count)) => *count,
+                _ => Some(1),
+            })
+            .collect::<Option<Vec<_>>>();
+
+        let description = match counts.map(|counts| counts.iter().product::<usize>()) {
+            Some(1) => "A single partition, the whole partition key being restricted with `=`."
+                .to_string(),
+            Some(count) => format!(
+                "{count} partitions, from the `IN` values of the partition key, each of which may be on other replicas: the coordinator queries them all."
+            ),
+            None => "Several partitions, from the `IN` values of the partition key, each of which may be on other replicas: the coordinator queries them all."
+                .to_string(),
+        };
+
+        return (description, true);
+    }
+
+    let description = match relations.token {
+        true => {
+            "A range of tokens, scanned partition by partition across the replicas owning them."
+                .to_string()
+        }
+        false => format!(
+            "Every partition, the partition key ({}) not being restricted with `=` or `IN`: a scan of the whole table, across the cluster.",
+            names(&table.partition_key)
+        ),
+    };
+
+    (description, false)
+}
+
+/// The rows the statement reaches within each partition
+fn rows(statement: &Statement, table: &Table, relations: &Relations) -> String {
+    if statement.kind() == StatementKind::Insert {
+        return "A single row, given by the inserted primary key.".into();
+    }
+
+    if table.clustering_key.is_empty() {
+        return "The single row of each partition, the table having no clustering columns.".into();
+    }
+
+    let prefix = table
+        .clustering_key
+        .iter()
+        .take_while(|column| relations.get(column).is_some_and(Restriction::is_values))
+        .count();
+
+    if prefix == table.clustering_key.len() {
+        return "A single row per partition, every clustering column being restricted with `=` or `IN`."
+            .into();
+    }
+
+    let next = &table.clustering_key[prefix];
+
+    match relations.get(next) {
+        Some(Restriction::Range) => format!(
+            "A slice of each partition, the rows whose `{next}` is within the range, read in clustering order."
+        ),
+        _ if prefix > 0 => format!(
+            "The rows of each partition under {}, read in clustering order.",
+            names(&table.clustering_key[..prefix])
+        ),
+        _ => "Every row of each partition.".into(),
+    }
+}
+
+/// The reasons a `SELECT` needs `ALLOW FILTERING`, and the indexes it uses instead
+fn filtering(table: &Table, relations: &Relations, single: bool) -> (Vec<String>, Vec<String>) {
+    let mut reasons = vec![];
+    let mut indexes = vec![];
+
+    let indexed = |column: &str| {
+        table
+            .indexes
+            .iter()
+            .find(|index| index.column == column)
+            .map(|index| match &index.name {
+                Some(name) => format!("Index `{name}`"),
+                None => "The index".to_string(),
+            })
+    };
+
+    let partition_restricted = table
+        .partition_key
+        .iter()
+        .filter(|column| relations.get(column).is_some())
+        .count();
+
+    // A partition key restricted in part can't be hashed into a token
+    if partition_restricted > 0 && !single && !relations.token {
+        reasons.push(format!(
+            "The partition key is only restricted in part: {} must all be restricted with `=` or `IN`.",
+            names(&table.partition_key)
+        ));
+    }
+
+    // A restriction the primary key can't serve is served by an index on its column, if any
+    let mut serve = |column: &str, reason: String| match indexed(column) {
+        Some(index) => {
+            let scope = match single {
+                true => "within the partition",
+                false => "on every node, the partition key not being restricted",
+            };
+
+            indexes.push(format!("{index} on `{column}`, queried {scope}."));
+        }
+        None => reasons.push(reason),
+    };
+
+    let mut previous_restricted = true;
+
+    for (i, column) in table.clustering_key.iter().enumerate() {
+        let restriction = relations.get(column);
+
+        if let Some(restriction) = restriction {
+            if !single {
+                serve(
+                    column,
+                    format!(
+                        "Clustering column `{column}` is restricted without the partition key being restricted with `=` or `IN`."
+                    ),
+                );
+            } else if !previous_restricted {
+                serve(
+                    column,
+                    format!(
+                        "Clustering column `{column}` is restricted without `{}` before it being restricted with `=` or `IN`.",
+                        table.clustering_key[i - 1]
+                    ),
+                );
+            } else if matches!(restriction, Restriction::Contains | Restriction::Other) {
+                serve(
+                    column,
+                    format!(
+                        "Clustering column `{column}` is restricted with an operator the clustering order can't serve."
+                    ),
+                );
+            }
+        }
+
+        previous_restricted =
+            previous_restricted && restriction.is_some_and(Restriction::is_values);
+    }
+
+    for (column, _) in &relations.columns {
+        if !table.is_primary_key(column) {
+            serve(
+                column,
+                format!(
+                    "`{column}` is neither part of the primary key nor indexed: the rows are read and filtered on it."
+                ),
+            );
+        }
+    }
+
+    (reasons, indexes)
+}
+
+/// What reads or writes at `level` wait for
+fn level_behavior(level: Consistency, write: bool) -> &'static str {
+    match (level, write) {
+        (Consistency::Any, true) => {
+            "the write succeeds once stored anywhere, even as a hint for replicas that are down, and may not be readable until the hint is delivered"
+        }
+        (Consistency::Any, false) => {
+            "`ANY` is only accepted for writes: Cassandra rejects the read"
+        }
+        (Consistency::One, _) => {
+            "a single replica must respond, so a read may miss writes that haven't reached it yet"
+        }
+        (Consistency::LocalOne, _) => {
+            "a single replica of the local datacenter must respond, so a read may miss writes that haven't reached it yet"
+        }
+        (Consistency::Two, _) => "two replicas must respond",
+        (Consistency::Three, _) => "three replicas must respond",
+        (Consistency::Quorum, _) => {
+            "a majority of the replicas across datacenters must respond, so reads at `QUORUM` see the writes made at `QUORUM`"
+        }
+        (Consistency::LocalQuorum, _) => {
+            "a majority of the local datacenter's replicas must respond, so reads at `LOCAL_QUORUM` see the writes made at `LOCAL_QUORUM` in the same datacenter"
+        }
+        (Consistency::EachQuorum, true) => {
+            "a majority of the replicas of every datacenter must respond"
+        }
+        (Consistency::EachQuorum, false) => {
+            "`EACH_QUORUM` is only accepted for writes: Cassandra rejects the read"
+        }
+        (Consistency::All, _) => {
+            "every replica must respond, so the statement fails if any replica is down"
+        }
+        (Consistency::Serial | Consistency::LocalSerial, true) => {
+            "a serial level only applies to the Paxos phase of lightweight transactions: Cassandra rejects the write"
+        }
+        (Consistency::Serial | Consistency::LocalSerial, false) => {
+            "the read completes any lightweight transaction in progress first, at the cost of a Paxos round"
+        }
+    }
+}
+
+/// The consistency the statement runs with, and what it means for it
+fn consistency(statement: &Statement, table: &Table, consistencies: Consistencies) -> String {
+    let write = statement.kind() != StatementKind::Select;
+    let level = consistencies.consistency;
+
+    let mut description = format!("`{}`: {}.", level.name(), level_behavior(level, write));
+
+    // `INSERT ... IF NOT EXISTS`, `UPDATE ... IF ...`, `DELETE ... IF ...`
+    if write && statement.find_keyword("IF").is_some() {
+        let serial = consistencies.serial.unwrap_or(Consistency::Serial);
+
+        description.push_str(&format!(
+            " A lightweight transaction: its condition is checked in a Paxos round among the replicas at `{}`, about four round trips instead of one, before the write is committed at `{}`.",
+            serial.name(),
+            level.name()
+        ));
+    }
+
+    if statement.kind() == StatementKind::Update
+        && table
+            .columns
+            .iter()
+            .any(|column| column.data_type.is_counter())
+    {
+        description.push_str(
+            " Counter updates aren't idempotent, so a timed out update may have been applied.",
+        );
+    }
+
+    description
+}
+
+/// Describes what `statement`, a DML statement of `table`, reaches and how it's served, in
+/// Markdown. `consistencies` are those it runs with, as set by the directives before it.
+pub fn analyze(statement: &Statement, table: &Table, consistencies: Consistencies) -> String {
+    let kind = statement.kind();
+    let relations = relations(statement);
+    let (partitions, single) = partitions(statement, table, &relations);
+
+    let keyword = statement
+        .tokens
+        .first()
+        .map(|token| token.text.to_ascii_uppercase())
+        .unwrap_or_default();
+
+    let mut report = format!(
+        "**{keyword}** on `{}.{}` (partition key: {}",
+        table.keyspace,
+        table.name,
+        names(&table.partition_key)
+    );
+
+    if !table.clustering_key.is_empty() {
+        report.push_str(&format!(
+            "; clustering columns: {}",
+            names(&table.clustering_key)
+        ));
+    }
+
+    report.push_str(")\n\n");
+    report.push_str(&format!("- **Partitions:** {partitions}\n"));
+    report.push_str(&format!(
+        "- **Rows:** {}\n",
+        rows(statement, table, &relations)
+    ));
+
+    match kind {
+        StatementKind::Select => {
+            let (reasons, indexes) = filtering(table, &relations, single);
+            let allowed = statement.find_keyword("ALLOW").is_some();
+
+            let filtering = match (reasons.is_empty(), allowed) {
+                (true, false) => "Not needed.".to_string(),
+                (true, true) => "`ALLOW FILTERING` is given but not needed.".to_string(),
+                (false, true) => "Needed, and given with `ALLOW FILTERING`: the rows read are filtered, which reads more than it returns.".to_string(),
+                (false, false) => "Needed: Cassandra rejects the statement without `ALLOW FILTERING`, or an index on the columns below.".to_string(),
+            };
+
+            report.push_str(&format!("- **Filtering:** {filtering}\n"));
+            report.extend(reasons.iter().map(|reason| format!("  - {reason}\n")));
+
+            if !indexes.is_empty() {
+                report.push_str("- **Indexes:**\n");
+                report.extend(indexes.iter().map(|index| format!("  - {index}\n")));
+            }
+        }
+        StatementKind::Update | StatementKind::Delete if !single => {
+            report.push_str(
+                "- **Rejected:** updates and deletes must restrict every partition key column with `=` or `IN`.\n",
+            );
+        }
+        _ => {}
+    }
+
+    report.push_str(&format!(
+        "- **Consistency:** {}\n",
+        consistency(statement, table, consistencies)
+    ));
+
+    report
+}
@@ -0,0 +1,286 @@
+//! The assignments of the `SET` clause of `UPDATE`: values replacing a column's, collections
+//! updated relative to their value (`tags = tags + {'new'}`, `items[0] = 'first'`) and counters
+//! incremented or decremented (`views = views + 1`).
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    cql_type::CqlType,
+    diagnostics::Problem,
+    generate::escape_snippet,
+    lexer::{Token, quote_identifier},
+    literal,
+    schema::{Column, Schema, Table},
+    statement::{Statement, matching_bracket, split_comma_ranges},
+};
+
+/// The ways a column is assigned a value
+enum Operation<'a> {
+    /// `column = value`
+    Replace(&'a [Token<'a>]),
+    /// `column = other + value` or `column = other - value`, where `other` should be the column
+    Relative {
+        other: &'a Token<'a>,
+        operator: &'a Token<'a>,
+        operand: &'a [Token<'a>],
+    },
+    /// `column = value + column`, prepending to a list
+    Prepend {
+        operand: &'a [Token<'a>],
+        other: &'a Token<'a>,
+    },
+    /// `column[index] = value`
+    Element {
+        index: &'a [Token<'a>],
+        value: &'a [Token<'a>],
+    },
+    /// `column.field = value`
+    Field {
+        field: &'a Token<'a>,
+        value: &'a [Token<'a>],
+    },
+}
+
+struct Assignment<'a> {
+    column: &'a Token<'a>,
+    operation: Operation<'a>,
+}
+
+/// Parses an assignment, as far as it can be told apart from the others
+fn parse_assignment<'a>(tokens: &'a [Token<'a>]) -> Option<Assignment<'a>> {
+    let column = tokens.first()?;
+    column.identifier()?;
+
+    let operation = match &tokens[1..] {
+        [equals, value @ ..] if equals.is_punctuation("=") => match value {
+            [other, operator, operand @ ..]
+                if other.is_identifier()
+                    && (operator.is_punctuation("+") || operator.is_punctuation("-"))
+                    && !operand.is_empty() =>
+            {
+                Operation::Relative {
+                    other,
+                    operator,
+                    operand,
+                }
+            }
+            [operand @ .., plus, other]
+                if plus.is_punctuation("+") && other.is_identifier() && !operand.is_empty() =>
+            {
+                Operation::Prepend { operand, other }
+            }
+            _ => Operation::Replace(value),
+        },
+        [open, ..] if open.is_punctuation("[") => {
+            let close = matching_bracket(tokens, 1)?;
+
+            match &tokens[close + 1..] {
+                [equals, value @ ..] if equals.is_punctuation("=") => Operation::Element {
+                    index: &tokens[2..close],
+                    value,
+                },
+                _ => return None,
+            }
+        }
+        [dot, field, equals, value @ ..]
+            if dot.is_punctuation(".") && equals.is_punctuation("=") =>
+        {
+            Operation::Field { field, value }
+        }
+        _ => return None,
+    };
+
+    Some(Assignment { column, operation })
+}
+
+/// The assignments of an `UPDATE`'s `SET` clause
+fn assignments<'a>(statement: &Statement<'a>) -> Vec<Assignment<'a>> {
+    let tokens = statement.tokens;
+
+    let Some(set) = statement.find_keyword("SET") else {
+        return vec![];
+    };
+
+    let end = statement
+        .find_keyword_from("WHERE", set)
+        .unwrap_or(tokens.len());
+    let list = &tokens[set + 1..end];
+
+    split_comma_ranges(list)
+        .into_iter()
+        .filter_map(|part| parse_assignment(&list[part]))
+        .collect()
+}
+
+fn error(tokens: &[Token], message: String) -> Problem {
+    Problem {
+        span: tokens.first().map_or(0, |t| t.start)..tokens.last().map_or(0, |t| t.end),
+        severity: DiagnosticSeverity::ERROR,
+        message,
+        lint: None,
+    }
+}
+
+/// Checks an assignment to `column`, a column of `table`
+fn check_assignment(
+    assignment: &Assignment,
+    column: &Column,
+    table: &Table,
+    schema: &Schema,
+) -> Vec<Problem> {
+    let name = &column.name;
+    let data_type = &column.data_type;
+    let check = |tokens: &[Token], data_type: &CqlType| {
+        literal::check_value(tokens, data_type, schema, &table.keyspace)
+    };
+
+    match &assignment.operation {
+        Operation::Replace(value) if data_type.is_counter() => vec![error(
+            value,
+            format!(
+                "`{name}` is a counter: it can only be incremented or decremented, e.g. `{name} = {name} + 1`"
+            ),
+        )],
+        Operation::Replace(_) => vec![],
+        Operation::Relative {
+            other,
+            operator,
+            operand,
+        } => {
+            if other.identifier().as_deref() != Some(name.as_str()) {
+                return vec![error(
+                    std::slice::from_ref(*other),
+                    format!(
+                        "Expected `{name}`: a column can only be updated relative to its own value"
+                    ),
+                )];
+            }
+
+            match data_type {
+                _ if data_type.is_counter() => check(operand, &CqlType::Native("bigint".into())),
+                CqlType::List(_) | CqlType::Set(_) => check(operand, data_type),
+                // Entries are added, and removed by their keys
+                CqlType::Map(key, _) => match operator.text {
+                    "+" => check(operand, data_type),
+                    _ => check(operand, &CqlType::Set(key.clone())),
+                },
+                CqlType::Frozen(_) => vec![error(
+                    std::slice::from_ref(*operator),
+                    format!("`{name}` is frozen: it can only be replaced as a whole"),
+                )],
+                _ => vec![error(
+                    std::slice::from_ref(*operator),
+                    format!(
+                        "`{name}` is of type `{data_type}`: only counters and non-frozen collections can be updated relative to their value"
+                    ),
+                )],
+            }
+        }
+        Operation::Prepend { operand, other } => {
+            if other.identifier().as_deref() != Some(name.as_str()) {
+                return vec![error(
+                    std::slice::from_ref(*other),
+                    format!(
+                        "Expected `{name}`: a column can only be updated relative to its own value"
+                    ),
+                )];
+            }
+
+            match data_type {
+                CqlType::List(_) => check(operand, data_type),
+                _ => vec![error(
+                    operand,
+                    format!(
+                        "Only lists can be prepended to, and `{name}` is of type `{data_type}`"
+                    ),
+                )],
+            }
+        }
+        Operation::Element { index, value } => match data_type {
+            CqlType::List(element) => {
+                let mut problems = check(index, &CqlType::Native("int".into()));
+                problems.extend(check(value, element));
+                problems
+            }
+            CqlType::Map(key, element) => {
+                let mut problems = check(index, key);
+                problems.extend(check(value, element));
+                problems
+            }
+            _ => vec![error(
+                std::slice::from_ref(assignment.column),
+                format!(
+                    "`{name}` is of type `{data_type}`: only the elements of non-frozen lists and maps can be set"
+                ),
+            )],
+        },
+        Operation::Field { field, value } => {
+            let user_type = match data_type {
+                CqlType::UserDefined { .. } => schema.resolve_user_type(data_type, &table.keyspace),
+                _ => {
+                    return vec![error(
+                        std::slice::from_ref(assignment.column),
+                        format!(
+                            "`{name}` is of type `{data_type}`: only the fields of non-frozen user-defined types can be set"
+                        ),
+                    )];
+                }
+            };
+
+            let Some(user_type) = user_type else {
+                return vec![];
+            };
+
+            match field.identifier().and_then(|field| user_type.field(&field)) {
+                Some(field) => check(value, &field.data_type),
+                None => vec![error(
+                    std::slice::from_ref(*field),
+                    format!("Type `{}` has no field `{}`", user_type.name, field.text),
+                )],
+            }
+        }
+    }
+}
+
+/// Checks the assignments of an `UPDATE` of `table`: counters must be incremented, and only
+/// counters and non-frozen collections updated relative to their value
+pub fn check(statement: &Statement, table: &Table, schema: &Schema) -> Vec<Problem> {
+    assignments(statement)
+        .iter()
+        .flat_map(|assignment| {
+            let column = assignment
+                .column
+                .identifier()
+                .and_then(|name| table.column(&name));
+
+            match column {
+                Some(column) => check_assignment(assignment, column, table, schema),
+                None => vec![],
+            }
+        })
+        .collect()
+}
+
+/// The updates of `column` relative to its value, as a label and the snippet of the value
+/// assigned, e.g. `views + 1` for a counter or `tags + {...}` for a set
+pub fn relative_updates(column: &Column) -> Vec<(String, String)> {
+    let name = quote_identifier(&column.name);
+    let escaped = escape_snippet(&name);
+
+    match &column.data_type {
+        data_type if data_type.is_counter() => vec![
+            (format!("{name} + 1"), format!("{escaped} + ${{1:1}}")),
+            (format!("{name} - 1"), format!("{escaped} - ${{1:1}}")),
+        ],
+        CqlType::List(_) => vec![
+            (format!("{name} + [...]"), format!("{escaped} + [$1]")),
+            (format!("[...] + {name}"), format!("[$1] + {escaped}")),
+            (format!("{name} - [...]"), format!("{escaped} - [$1]")),
+        ],
+        CqlType::Set(_) | CqlType::Map(..) => vec![
+            (format!("{name} + {{...}}"), format!("{escaped} + {{$1\\}}")),
+            (format!("{name} - {{...}}"), format!("{escaped} - {{$1\\}}")),
+        ],
+        _ => vec![],
+    }
+}
@@ -0,0 +1,234 @@
+//! `BEGIN BATCH ... APPLY BATCH` blocks, whose writes are split into statements of their own,
+//! and whose lightweight transactions Cassandra only accepts within a single partition.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    lexer::Token,
+    schema::Schema,
+    statement::{QualifiedName, Statement, StatementKind},
+};
+
+/// Where a statement stands in a batch
+pub enum Boundary<'a> {
+    /// `BEGIN BATCH`, with the first write of the batch when it's on the same line
+    Begin(Option<Statement<'a>>),
+    /// `APPLY BATCH`
+    Apply,
+}
+
+/// Tells whether `statement` begins or applies a batch
+pub fn boundary<'a>(statement: &Statement<'a>) -> Option<Boundary<'a>> {
+    if statement.kind() != StatementKind::Batch {
+        return None;
+    }
+
+    let tokens = statement.tokens;
+
+    if tokens.first()?.is_keyword("APPLY") {
+        return Some(Boundary::Apply);
+    }
+
+    // `BEGIN [UNLOGGED | COUNTER] BATCH [USING TIMESTAMP value]`
+    let mut i = statement
+        .find_keyword("BATCH")
+        .map_or(tokens.len(), |i| i + 1);
+    if tokens.get(i).is_some_and(|t| t.is_keyword("USING")) {
+        i += 3;
+    }
+
+    let write = tokens
+        .get(i..)
+        .filter(|write| !write.is_empty())
+        .map(|write| Statement {
+            tokens: write,
+            start: write[0].start,
+            end: statement.end,
+            terminated: statement.terminated,
+        });
+
+    Some(Boundary::Begin(write))
+}
+
+/// The `IF` condition of a write, from `IF` to the end of the condition
+fn condition<'a>(write: &Statement<'a>) -> Option<&'a [Token<'a>]> {
+    let start = write.find_keyword("IF")?;
+    let end = write
+        .find_keyword_from("USING", start)
+        .unwrap_or(write.tokens.len());
+
+    Some(&write.tokens[start..end])
+}
+
+/// The values of the columns restricted with `=` in the `WHERE` clause of an `UPDATE` or
+/// `DELETE`, or assigned by an `INSERT`, as the column name and the value's tokens
+fn key_values<'a>(write: &Statement<'a>) -> Vec<(String, &'a [Token<'a>])> {
+    let tokens = write.tokens;
+
+    if let Some(insert) = write.insert_values() {
+        return insert
+            .columns
+            .iter()
+            .zip(&insert.values)
+            .filter_map(|(column, value)| {
+                Some((
+                    tokens.get(column.start)?.identifier()?,
+                    &tokens[value.clone()],
+                ))
+            })
+            .collect();
+    }
+
+    let Some(r#where) = write.find_keyword("WHERE") else {
+        return vec![];
+    };
+
+    let end = write
+        .find_keyword_from("IF", r#where)
+        .unwrap_or(tokens.len());
+
+    let mut relations = vec![];
+    let mut start = r#where + 1;
+
+    while start < end {
+        let and = write
+            .find_keyword_from("AND", start)
+            .filter(|&and| and < end)
+            .unwrap_or(end);
+
+        if let [column, equals, value @ ..] = &tokens[start..and]
+            && equals.is_punctuation("=")
+            && !value.is_empty()
+            && let Some(column) = column.identifier()
+        {
+            relations.push((column, value));
+        }
+
+        start = and + 1;
+    }
+
+    relations
+}
+
+/// The partition a write is in, as far as it can be told
+struct Partition<'a> {
+    keyspace: Option<String>,
+    table: String,
+    /// The value of each partition key column, or `None` when the table or a value isn't known
+    key: Option<Vec<(String, &'a [Token<'a>])>>,
+}
+
+impl Partition<'_> {
+    /// The partition's table, e.g. `ks.users`
+    fn describe_table(&self) -> String {
+        match &self.keyspace {
+            Some(keyspace) => format!("{keyspace}.{}", self.table),
+            None => self.table.clone(),
+        }
+    }
+
+    /// Describes the partition's key for messages, e.g. `id = 1`
+    fn describe_key(&self) -> String {
+        self.key
+            .iter()
+            .flatten()
+            .map(|(column, value)| {
+                let value = value.iter().map(|t| t.text).collect::<Vec<_>>().join(" ");
+                format!("{column} = {value}")
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+
+    /// Whether both partitions' keys are known and differ
+    fn differs(&self, other: &Partition) -> bool {
+        let (Some(key), Some(other)) = (&self.key, &other.key) else {
+            return false;
+        };
+
+        key.iter().zip(other).any(|((_, value), (_, other))| {
+            value.len() != other.len() || value.iter().zip(*other).any(|(a, b)| a.text != b.text)
+        })
+    }
+}
+
+/// Tells the partition `write` is in, unqualified tables being in `current_keyspace`
+fn partition<'a>(
+    write: &Statement<'a>,
+    schema: &Schema,
+    current_keyspace: Option<&str>,
+) -> Option<Partition<'a>> {
+    let QualifiedName { keyspace, name, .. } = write.target_table()?;
+
+    let key = schema
+        .statement_table(write, current_keyspace)
+        .and_then(|definition| {
+            let values = key_values(write);
+
+            definition
+                .partition_key
+                .iter()
+                .map(|column| {
+                    values
+                        .iter()
+                        .find(|(name, _)| name == column)
+                        .map(|(_, value)| (column.clone(), *value))
+                })
+                .collect()
+        });
+
+    Some(Partition {
+        keyspace: keyspace.or_else(|| current_keyspace.map(str::to_string)),
+        table: name,
+        key,
+    })
+}
+
+/// Checks the writes of a batch: when some are lightweight transactions, all must be in the
+/// same table and partition
+pub fn check(
+    writes: &[Statement],
+    schema: &Schema,
+    current_keyspace: Option<&str>,
+) -> Vec<Problem> {
+    let partitions = writes
+        .iter()
+        .filter_map(|write| partition(write, schema, current_keyspace))
+        .collect::<Vec<_>>();
+
+    let Some(first) = partitions.first() else {
+        return vec![];
+    };
+
+    let message = if let Some(other) = partitions
+        .iter()
+        .find(|p| (&p.keyspace, &p.table) != (&first.keyspace, &first.table))
+    {
+        format!(
+            "Lightweight transactions can't span multiple tables, and this batch writes to both `{}` and `{}`",
+            first.describe_table(),
+            other.describe_table()
+        )
+    } else if let Some(other) = partitions.iter().find(|p| first.differs(p)) {
+        format!(
+            "Lightweight transactions can't span multiple partitions, and this batch writes to both `{}` and `{}` of `{}`",
+            first.describe_key(),
+            other.describe_key(),
+            first.describe_table()
+        )
+    } else {
+        return vec![];
+    };
+
+    writes
+        .iter()
+        .filter_map(condition)
+        .map(|condition| Problem {
+            span: condition[0].start..condition[condition.len() - 1].end,
+            severity: DiagnosticSeverity::WARNING,
+            message: message.clone(),
+            lint: None,
+        })
+        .collect()
+}
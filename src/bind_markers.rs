@@ -0,0 +1,290 @@
+//! Resolution of `?` and `:name` bind markers to the column or parameter they bind to.
+
+use std::ops::Range;
+
+use crate::{
+    cql_type::CqlType,
+    lexer::{Token, TokenKind},
+    schema::Table,
+    statement::{Statement, matching_bracket},
+};
+
+#[derive(Debug, Clone)]
+pub struct BindMarker {
+    /// The name of a `:name` marker, `None` for positional `?` markers
+    pub name: Option<String>,
+    /// Zero-based position of the marker among the markers of its statement
+    pub index: usize,
+    /// Byte range of the marker
+    pub span: Range<usize>,
+    /// The column (or pseudo-column, e.g. `[ttl]`) the marker binds to, named the way drivers
+    /// report it in prepared statement metadata
+    pub column: Option<String>,
+    pub data_type: Option<CqlType>,
+}
+
+pub fn is_marker(token: &Token) -> bool {
+    matches!(
+        token.kind,
+        TokenKind::BindMarker | TokenKind::NamedBindMarker
+    )
+}
+
+/// Returns the name of a named bind marker, following the same case rules as identifiers
+pub fn marker_name(token: &Token) -> Option<String> {
+    if token.kind != TokenKind::NamedBindMarker {
+        return None;
+    }
+
+    let name = &token.text[1..];
+
+    match name.strip_prefix('"') {
+        Some(quoted) => Some(
+            quoted
+                .strip_suffix('"')
+                .unwrap_or(quoted)
+                .replace("\"\"", "\""),
+        ),
+        None => Some(name.to_ascii_lowercase()),
+    }
+}
+
+fn column_type(table: Option<&Table>, column: &str) -> Option<CqlType> {
+    Some(table?.column(column)?.data_type.clone())
+}
+
+/// Maps the index of each marker token in an `INSERT ... VALUES (...)` tuple to the column it
+/// is inserted into
+fn insert_bindings(statement: &Statement) -> Vec<(usize, String)> {
+    let tokens = statement.tokens;
+
+    let Some(insert) = statement.insert_values() else {
+        return vec![];
+    };
+
+    insert
+        .columns
+        .into_iter()
+        .zip(insert.values)
+        .filter_map(|(column, value)| {
+            let column = tokens.get(column.start)?.identifier()?;
+
+            (value.len() == 1 && is_marker(&tokens[value.start])).then_some((value.start, column))
+        })
+        .collect()
+}
+
+/// Returns the index of the identifier naming the column restricted or assigned by an
+/// operator at `operator`, skipping over a subscript such as `col['key']`
+fn operand_column(tokens: &[Token], operator: usize) -> Option<(usize, bool)> {
+    let before = operator.checked_sub(1)?;
+    let token = tokens.get(before)?;
+
+    if token.is_punctuation("]") {
+        let open = (0..before).rev().find(|&i| tokens[i].is_punctuation("["))?;
+        let column = open.checked_sub(1)?;
+
+        return tokens[column].is_identifier().then_some((column, true));
+    }
+
+    token.is_identifier().then_some((before, false))
+}
+
+/// Determines what the marker at `tokens[i]` binds to
+fn resolve(tokens: &[Token], i: usize, table: Option<&Table>) -> (Option<String>, Option<CqlType>) {
+    let native = |name: &str| Some(CqlType::Native(name.into()));
+    let previous = |offset: usize| i.checked_sub(offset).and_then(|j| tokens.get(j));
+
+    let Some(before) = previous(1) else {
+        return (None, None);
+    };
+
+    if before.is_keyword("TTL") {
+        return (Some("[ttl]".into()), native("int"));
+    }
+
+    if before.is_keyword("TIMESTAMP") {
+        return (Some("[timestamp]".into()), native("bigint"));
+    }
+
+    if before.is_keyword("LIMIT") {
+        return (Some("[limit]".into()), native("int"));
+    }
+
+    // `col[?] = ...` binds a map key or list index
+    if before.is_punctuation("[")
+        && let Some(column) = previous(2).and_then(Token::identifier)
+    {
+        let data_type =
+            column_type(table, &column).and_then(|data_type| match data_type.unfrozen() {
+                CqlType::Map(key, _) => Some(key.as_ref().clone()),
+                CqlType::List(_) => native("int"),
+                _ => None,
+            });
+
+        return (Some(format!("key({column})")), data_type);
+    }
+
+    // `col IN (?, ?)` binds each element of the list
+    let in_list_open = (0..i)
+        .rev()
+        .find(|&j| {
+            tokens[j].is_punctuation("(")
+                && matching_bracket(tokens, j).is_none_or(|close| close > i)
+        })
+        .filter(|&open| open > 0 && tokens[open - 1].is_keyword("IN"));
+
+    if let Some(open) = in_list_open
+        && let Some((column, _)) = operand_column(tokens, open - 1)
+    {
+        let column = tokens[column].identifier();
+        let data_type = column
+            .as_deref()
+            .and_then(|column| column_type(table, column));
+
+        return (column, data_type);
+    }
+
+    // `col CONTAINS KEY ?`
+    if before.is_keyword("KEY")
+        && previous(2).is_some_and(|t| t.is_keyword("CONTAINS"))
+        && let Some(column) = previous(3).and_then(Token::identifier)
+    {
+        let data_type =
+            column_type(table, &column).and_then(|data_type| data_type.key_type().cloned());
+
+        return (Some(column), data_type);
+    }
+
+    // `col CONTAINS ?`
+    if before.is_keyword("CONTAINS")
+        && let Some(column) = previous(2).and_then(Token::identifier)
+    {
+        let data_type =
+            column_type(table, &column).and_then(|data_type| data_type.element_type().cloned());
+
+        return (Some(column), data_type);
+    }
+
+    // `col IN ?` binds the whole list
+    if before.is_keyword("IN")
+        && let Some((column, _)) = operand_column(tokens, i - 1)
+    {
+        let column = tokens[column].identifier();
+        let data_type = column
+            .as_deref()
+            .and_then(|column| column_type(table, column))
+            .map(|data_type| CqlType::List(Box::new(data_type)));
+
+        return (column, data_type);
+    }
+
+    // `col = col + ?` appends to a collection or increments a counter
+    if before.is_punctuation("+") || before.is_punctuation("-") {
+        let assigned = previous(3).is_some_and(|t| t.is_punctuation("="));
+
+        if let (true, Some(column)) = (assigned, previous(2).and_then(Token::identifier)) {
+            let data_type =
+                column_type(table, &column).map(|data_type| match data_type.is_counter() {
+                    true => CqlType::Native("bigint".into()),
+                    false => data_type,
+                });
+
+            return (Some(column), data_type);
+        }
+    }
+
+    // `col = ?`, `col > ?`, `col['key'] = ?`
+    let is_operator = ["=", "<", ">", "<=", ">=", "!="]
+        .iter()
+        .any(|operator| before.is_punctuation(operator));
+
+    if is_operator && let Some((column, subscripted)) = operand_column(tokens, i - 1) {
+        let column = tokens[column].identifier();
+        let data_type = column
+            .as_deref()
+            .and_then(|column| column_type(table, column))
+            .and_then(|data_type| match subscripted {
+                true => data_type.element_type().cloned(),
+                false => Some(data_type),
+            });
+
+        return (column, data_type);
+    }
+
+    (None, None)
+}
+
+/// Returns the type of the value starting at `tokens[i]` of `statement`, such as a literal,
+/// from the column it's inserted into, assigned to or compared with in `table`
+pub fn value_type(statement: &Statement, i: usize, table: Option<&Table>) -> Option<CqlType> {
+    let tokens = statement.tokens;
+
+    let inserted = statement.insert_values().and_then(|insert| {
+        let (column, _) = insert
+            .columns
+            .into_iter()
+            .zip(insert.values)
+            .find(|(_, value)| value.start == i)?;
+
+        tokens.get(column.start)?.identifier()
+    });
+
+    match inserted {
+        Some(column) => column_type(table, &column),
+        None => resolve(tokens, i, table).1,
+    }
+}
+
+/// Returns the bind markers of `statement`, resolved against `table`, the table the statement
+/// operates on, when it's known
+pub fn statement_bind_markers(statement: &Statement, table: Option<&Table>) -> Vec<BindMarker> {
+    let tokens = statement.tokens;
+
+    let insert_bindings = insert_bindings(statement);
+
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| is_marker(token))
+        .enumerate()
+        .map(|(index, (i, token))| {
+            let name = marker_name(token);
+
+            let (column, data_type) = match insert_bindings.iter().find(|(j, _)| *j == i) {
+                Some((_, column)) => (Some(column.clone()), column_type(table, column)),
+                None => resolve(tokens, i, table),
+            };
+
+            // Named markers that don't appear in a recognized position conventionally carry the
+            // name of the column they bind to
+            let (column, data_type) = match (&column, &name) {
+                (None, Some(name)) if column_type(table, name).is_some() => {
+                    (Some(name.clone()), column_type(table, name))
+                }
+                _ => (column, data_type),
+            };
+
+            BindMarker {
+                name,
+                index,
+                span: token.start..token.end,
+                column,
+                data_type,
+            }
+        })
+        .collect()
+}
+
+/// Returns the distinct names of the named bind markers in `tokens`, in order of appearance
+pub fn marker_names(tokens: &[Token]) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+
+    for name in tokens.iter().filter_map(marker_name) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    names
+}
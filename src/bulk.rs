@@ -0,0 +1,455 @@
+//! Validation of files too large to check as a whole, e.g. dumps of hundreds of megabytes of
+//! `INSERT`s. Their text is read in chunks of whole statements, each checked against the
+//! schema the statements before it built, then dropped, so that memory stays bounded by the
+//! chunk size and the schema while diagnostics are reported chunk by chunk. Nothing depends
+//! on the file's lines, so that dumps on a single line are read in chunks too.
+
+use std::io::{self, Read};
+
+use tower_lsp::lsp_types::{Diagnostic, Position, Range};
+
+use crate::{
+    batch::{self, Boundary},
+    config::Config,
+    diagnostics,
+    directives::{self, Directives},
+    document::PositionEncodingKind,
+    lexer,
+    schema::Schema,
+    statement::{self, Dialect, Statement},
+};
+
+/// The size of the chunks checked at once, which their last statement rounds up
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// The size of the blocks files are read in
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// The most diagnostics reported of a large document by the server, which sends them all again
+/// with each report, past which the file is likely checked against the wrong schema anyway
+pub const MAX_DIAGNOSTICS: usize = 1000;
+
+/// The size a single statement or batch may grow to before it's checked as read so far,
+/// bounding the memory of files that never end theirs, e.g. binary ones
+const MAX_PENDING: usize = 256 * CHUNK_SIZE;
+
+/// The index of the first statement of `statements` that may go on in the text after them: an
+/// unterminated last statement, or the `BEGIN BATCH` of a batch not applied yet
+fn incomplete(statements: &[Statement]) -> usize {
+    let mut start = match statements.last() {
+        Some(last) if !last.terminated => statements.len() - 1,
+        _ => statements.len(),
+    };
+
+    for (i, statement) in statements.iter().enumerate().rev() {
+        match batch::boundary(statement) {
+            Some(Boundary::Begin(_)) => {
+                start = start.min(i);
+                break;
+            }
+            Some(Boundary::Apply) => break,
+            None => {}
+        }
+    }
+
+    start
+}
+
+/// The next block of `reader`, after the bytes of a character `split` by the previous one,
+/// leaving in `split` those of a character it splits in turn
+fn next_block(reader: &mut impl Read, split: &mut Vec<u8>) -> Option<io::Result<String>> {
+    let mut block = std::mem::take(split);
+    let start = block.len();
+    block.resize(start + BLOCK_SIZE, 0);
+
+    let read = loop {
+        match reader.read(&mut block[start..]) {
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            read => break read,
+        }
+    };
+
+    match read {
+        Ok(read) => block.truncate(start + read),
+        Err(error) => return Some(Err(error)),
+    }
+
+    let invalid = |error| Some(Err(io::Error::new(io::ErrorKind::InvalidData, error)));
+
+    // At the end, with a character left incomplete if there are bytes left
+    if block.len() == start {
+        return match String::from_utf8(block) {
+            Ok(block) if block.is_empty() => None,
+            Ok(block) => Some(Ok(block)),
+            Err(error) => invalid(error.utf8_error()),
+        };
+    }
+
+    let valid = match std::str::from_utf8(&block) {
+        Ok(_) => block.len(),
+        Err(error) if error.error_len().is_none() => error.valid_up_to(),
+        Err(error) => return invalid(error),
+    };
+
+    *split = block.split_off(valid);
+
+    Some(Ok(
+        String::from_utf8(block).expect("The block was checked to be UTF-8")
+    ))
+}
+
+/// The text read from `reader` in blocks, a character split between two blocks going with the
+/// second one, ending after the first error, e.g. for text that isn't UTF-8
+pub fn blocks(mut reader: impl Read) -> impl Iterator<Item = io::Result<String>> {
+    let mut split = vec![];
+    let mut done = false;
+
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+
+        let block = next_block(&mut reader, &mut split);
+        done = !matches!(block, Some(Ok(_)));
+
+        block
+    })
+}
+
+/// The diagnostics of a file, read piece by piece from `text` and checked chunk by chunk, each
+/// chunk giving the diagnostics found in it
+pub struct Validation<'a, I> {
+    text: I,
+    schema: Schema,
+    keyspace: Option<String>,
+    config: &'a Config,
+    protected: bool,
+    position_encoding: PositionEncodingKind,
+    /// The dialect of the file, read from its first chunk
+    dialect: Option<Dialect>,
+    /// The lints disabled by the directives at the top of the file
+    disabled: Vec<String>,
+    /// The text read but not checked yet
+    pending: String,
+    /// Where `pending` starts in the file
+    start: Position,
+    /// The bytes of the file checked
+    checked: usize,
+    /// Whether `text` ran out
+    done: bool,
+}
+
+/// Moves `position` past `text`, its characters counted in the units of `position_encoding`
+/// and its lines ended by `\n`
+fn advance(position: &mut Position, text: &str, position_encoding: PositionEncodingKind) {
+    for character in text.chars() {
+        if character == '\n' {
+            position.line += 1;
+            position.character = 0;
+            continue;
+        }
+
+        position.character += match position_encoding {
+            PositionEncodingKind::UTF8 => character.len_utf8(),
+            PositionEncodingKind::UTF16 => character.len_utf16(),
+            PositionEncodingKind::UTF32 => 1,
+        } as u32;
+    }
+}
+
+impl<'a, I: Iterator<Item = String>> Validation<'a, I> {
+    /// Validates the file whose text is made of the pieces of `text`, of any size, e.g. the
+    /// chunks of a rope or the [`blocks`] of a file, on top of `schema`, e.g. the one the rest
+    /// of the workspace defines. `protected` is whether the file is meant for the cluster of a
+    /// protected profile.
+    pub fn new(
+        text: I,
+        schema: Schema,
+        config: &'a Config,
+        protected: bool,
+        position_encoding: PositionEncodingKind,
+    ) -> Self {
+        Self {
+            text,
+            keyspace: schema.used_keyspace.clone(),
+            schema,
+            config,
+            protected,
+            position_encoding,
+            dialect: None,
+            disabled: vec![],
+            pending: String::new(),
+            start: Position::default(),
+            checked: 0,
+            done: false,
+        }
+    }
+
+    /// The bytes of the file checked so far
+    pub fn checked(&self) -> usize {
+        self.checked
+    }
+
+    /// The schema the file defines on top of the one it was validated on, once it's checked,
+    /// using the keyspace the file was left using, like the cells of a notebook
+    pub fn into_schema(mut self) -> Schema {
+        self.schema.used_keyspace = self.keyspace;
+        self.schema
+    }
+
+    /// Reads text until `pending` holds `size` bytes or the file ends
+    fn read(&mut self, size: usize) {
+        while self.pending.len() < size {
+            let Some(piece) = self.text.next() else {
+                self.done = true;
+                return;
+            };
+
+            self.pending.push_str(&piece);
+        }
+    }
+
+    /// Checks the statements of `pending` that are complete, or all of them at the end of the
+    /// file, returning their diagnostics, or `None` when more text is needed
+    fn check_pending(&mut self) -> Option<Vec<Diagnostic>> {
+        let complete = self.done || self.pending.len() >= MAX_PENDING;
+        let tokens = lexer::tokenize(&self.pending);
+
+        let dialect = *self.dialect.get_or_insert_with(|| {
+            match directives::dialect(&self.pending, self.config.dialect) {
+                Dialect::Auto if Dialect::Auto.is_cqlsh(&tokens) => Dialect::Cqlsh,
+                Dialect::Auto => Dialect::Cql,
+                dialect => dialect,
+            }
+        });
+
+        let statements = statement::split(&tokens, dialect);
+        let count = match complete {
+            true => statements.len(),
+            false => incomplete(&statements),
+        };
+
+        if count == 0 && !complete {
+            return None;
+        }
+
+        // The text after the last statement checked, e.g. the comments before the next one,
+        // goes with the next chunk
+        let end = match count < statements.len() {
+            true => statements[count - 1].end,
+            false => self.pending.len(),
+        };
+        let text = &self.pending[..end];
+
+        let comments = lexer::tokenize_with_comments(text);
+        let mut directives = match self.checked {
+            0 => Directives::parse(text, &comments),
+            _ => Directives::parse_body(text, &comments),
+        };
+
+        if self.checked == 0 {
+            self.disabled = std::mem::take(&mut directives.disabled);
+        }
+
+        directives.disabled.extend(self.disabled.iter().cloned());
+
+        let mut problems = diagnostics::replay(
+            &statements,
+            count,
+            &mut self.schema,
+            &mut self.keyspace,
+            self.config,
+            self.protected,
+        );
+
+        problems.retain(|problem| {
+            !directives.is_disabled(&problem.span, problem.lint.map(|lint| lint.code))
+        });
+        problems.extend(directives.problems);
+
+        // Located in a single pass over the chunk, from the offsets of the spans in order
+        let mut offsets = problems
+            .iter()
+            .flat_map(|problem| [problem.span.start, problem.span.end])
+            .collect::<Vec<_>>();
+        offsets.sort_unstable();
+        offsets.dedup();
+
+        let mut position = self.start;
+        let mut offset = 0;
+        let positions = offsets
+            .iter()
+            .map(|&next| {
+                advance(&mut position, &text[offset..next], self.position_encoding);
+                offset = next;
+                position
+            })
+            .collect::<Vec<_>>();
+
+        let locate = |byte: usize| positions[offsets.partition_point(|&offset| offset < byte)];
+        let diagnostics = diagnostics::into_diagnostics(problems, |span| Range {
+            start: locate(span.start),
+            end: locate(span.end),
+        });
+
+        advance(&mut position, &text[offset..], self.position_encoding);
+        self.start = position;
+        self.checked += end;
+        self.pending.drain(..end);
+
+        Some(diagnostics)
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for Validation<'_, I> {
+    type Item = Vec<Diagnostic>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut size = CHUNK_SIZE;
+
+        loop {
+            self.read(size);
+
+            if self.done && self.pending.is_empty() {
+                return None;
+            }
+
+            if let Some(diagnostics) = self.check_pending() {
+                return Some(diagnostics);
+            }
+
+            // A statement or batch longer than the chunk, read on in ever larger steps so that
+            // it's tokenized again only a few times
+            size = self.pending.len() * 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::TextDocument;
+
+    /// Reads `bytes` a few at a time, as pipes may
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            let len = buffer.len().min(self.0.len()).min(3);
+            buffer[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn blocks_keep_characters_whole() {
+        let text = format!("{}é漢😀\n", "a".repeat(BLOCK_SIZE - 1));
+
+        let read = blocks(text.as_bytes())
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read.concat(), text);
+
+        let read = blocks(Trickle("é漢😀 SELECT".as_bytes()))
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read.concat(), "é漢😀 SELECT");
+    }
+
+    #[test]
+    fn blocks_end_at_invalid_text() {
+        let mut read = blocks(&b"SELECT \xFF;"[..]);
+        assert_eq!(
+            read.next().unwrap().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert!(read.next().is_none());
+
+        // The last character cut short
+        let read = blocks(&"SELECT é".as_bytes()[..8]).collect::<Vec<_>>();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].as_deref().unwrap(), "SELECT ");
+        assert!(read[1].is_err());
+    }
+
+    /// A dump of several chunks, with errors throughout, statements and batches across lines,
+    /// and characters of every width
+    fn dump() -> String {
+        let mut text = String::from(
+            "-- cql-lsp: disable tombstone-null\n\
+             CREATE TABLE ks.users (id int PRIMARY KEY, name text, tags set<text>);\n",
+        );
+
+        for i in 0..9000 {
+            match i % 5 {
+                0 => text.push_str(&format!(
+                    "INSERT INTO ks.users (id, name) VALUES ({i}, 'é漢😀');\n"
+                )),
+                1 => text.push_str(&format!(
+                    "INSERT INTO ks.users (id, nom)\n  VALUES ({i}, 'x');\n"
+                )),
+                2 => text.push_str(&format!(
+                    "BEGIN BATCH\n  UPDATE ks.users SET tags = ['😀'] WHERE id = {i};\n  \
+                     DELETE FROM ks.users WHERE id = {i};\nAPPLY BATCH;\n"
+                )),
+                3 => text.push_str(&format!(
+                    "/* 漢 */ INSERT INTO ks.users (id, name) VALUES ({i}, null);"
+                )),
+                _ => text.push_str(&format!("SELECT * FROM ks.users WHERE name = '{i}';\n")),
+            }
+        }
+
+        text
+    }
+
+    /// The diagnostics of `text`, given in pieces of `piece` characters, in order
+    fn validate(text: &str, piece: usize, config: &Config) -> Vec<(Range, String)> {
+        let chars = text.chars().collect::<Vec<_>>();
+        let pieces = chars.chunks(piece).map(|piece| piece.iter().collect());
+
+        Validation::new(
+            pieces,
+            Schema::default(),
+            config,
+            false,
+            PositionEncodingKind::UTF16,
+        )
+        .flatten()
+        .map(|diagnostic| (diagnostic.range, diagnostic.message))
+        .collect()
+    }
+
+    #[test]
+    fn chunks_find_what_the_whole_text_does() {
+        let text = dump();
+        let config = Config {
+            large_file_size: usize::MAX,
+            ..Default::default()
+        };
+        assert!(text.len() > 2 * CHUNK_SIZE);
+
+        let mut whole = diagnostics::diagnostics(
+            &TextDocument::new(&text),
+            Schema::default(),
+            &config,
+            false,
+            PositionEncodingKind::UTF16,
+        )
+        .into_iter()
+        .map(|diagnostic| (diagnostic.range, diagnostic.message))
+        .collect::<Vec<_>>();
+        whole.sort_by_key(|(range, _)| (range.start.line, range.start.character));
+        assert!(whole.len() > 1000);
+
+        for piece in [7, usize::MAX] {
+            let mut chunked = validate(&text, piece, &config);
+            chunked.sort_by_key(|(range, _)| (range.start.line, range.start.character));
+
+            assert_eq!(chunked.len(), whole.len(), "pieces of {piece}");
+            assert!(chunked == whole, "pieces of {piece}");
+        }
+    }
+}
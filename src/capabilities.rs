@@ -0,0 +1,196 @@
+//! What the client told `initialize` it supports, and the adaptation of responses to it:
+//! plain text documentation for clients not rendering Markdown, plain text insertions for
+//! those not expanding snippets, and no label details for those not showing them.
+
+use tower_lsp::lsp_types::{
+    ClientCapabilities, CompletionItem, CompletionTextEdit, Documentation, Hover, HoverContents,
+    InsertTextFormat, MarkupContent, MarkupKind,
+};
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ClientSupport {
+    /// Whether completion item documentation may be Markdown
+    pub completion_markdown: bool,
+    /// Whether hover contents may be Markdown
+    pub hover_markdown: bool,
+    /// Whether completion items may insert snippets
+    pub snippets: bool,
+    /// Whether completion items may have label details
+    pub label_details: bool,
+    /// Whether the server may register for `workspace/didChangeWatchedFiles` once initialized
+    pub watched_files_registration: bool,
+    /// Whether watched files may be matched relative to a base directory, e.g. outside the
+    /// workspace
+    pub watched_files_relative_patterns: bool,
+    /// Whether the server may register for `workspace/didChangeConfiguration` once initialized
+    pub configuration_registration: bool,
+    /// Whether the server may ask for the semantic tokens of every document again
+    pub semantic_tokens_refresh: bool,
+    /// Whether the server may ask for the code lenses of every document again
+    pub code_lens_refresh: bool,
+}
+
+impl ClientSupport {
+    pub fn new(capabilities: &ClientCapabilities) -> Self {
+        let text_document = capabilities.text_document.as_ref();
+        let completion_item = text_document
+            .and_then(|text_document| text_document.completion.as_ref())
+            .and_then(|completion| completion.completion_item.as_ref());
+        let workspace = capabilities.workspace.as_ref();
+
+        let markdown = |formats: Option<&Vec<MarkupKind>>| {
+            formats.is_some_and(|formats| formats.contains(&MarkupKind::Markdown))
+        };
+
+        Self {
+            completion_markdown: markdown(
+                completion_item.and_then(|item| item.documentation_format.as_ref()),
+            ),
+            hover_markdown: markdown(
+                text_document
+                    .and_then(|text_document| text_document.hover.as_ref())
+                    .and_then(|hover| hover.content_format.as_ref()),
+            ),
+            snippets: completion_item
+                .and_then(|item| item.snippet_support)
+                .unwrap_or(false),
+            label_details: completion_item
+                .and_then(|item| item.label_details_support)
+                .unwrap_or(false),
+            watched_files_registration: workspace
+                .and_then(|workspace| workspace.did_change_watched_files)
+                .and_then(|watched_files| watched_files.dynamic_registration)
+                .unwrap_or(false),
+            watched_files_relative_patterns: workspace
+                .and_then(|workspace| workspace.did_change_watched_files)
+                .and_then(|watched_files| watched_files.relative_pattern_support)
+                .unwrap_or(false),
+            configuration_registration: workspace
+                .and_then(|workspace| workspace.did_change_configuration)
+                .and_then(|configuration| configuration.dynamic_registration)
+                .unwrap_or(false),
+            semantic_tokens_refresh: workspace
+                .and_then(|workspace| workspace.semantic_tokens.as_ref())
+                .and_then(|semantic_tokens| semantic_tokens.refresh_support)
+                .unwrap_or(false),
+            code_lens_refresh: workspace
+                .and_then(|workspace| workspace.code_lens.as_ref())
+                .and_then(|code_lens| code_lens.refresh_support)
+                .unwrap_or(false),
+        }
+    }
+
+    /// Strips completion items of what the client doesn't support
+    pub fn adapt_completions(&self, items: &mut [CompletionItem]) {
+        for item in items {
+            if !self.completion_markdown
+                && let Some(Documentation::MarkupContent(content)) = &item.documentation
+                && content.kind == MarkupKind::Markdown
+            {
+                item.documentation = Some(Documentation::String(plain_text(&content.value)));
+            }
+
+            if !self.snippets && item.insert_text_format == Some(InsertTextFormat::SNIPPET) {
+                item.insert_text_format = Some(InsertTextFormat::PLAIN_TEXT);
+
+                if let Some(CompletionTextEdit::Edit(edit)) = &mut item.text_edit {
+                    edit.new_text = expand_snippet(&edit.new_text);
+                }
+
+                if let Some(text) = &mut item.insert_text {
+                    *text = expand_snippet(text);
+                }
+            }
+
+            if !self.label_details {
+                item.label_details = None;
+            }
+        }
+    }
+
+    /// Turns Markdown hover contents into plain text if the client doesn't render Markdown
+    pub fn adapt_hover(&self, hover: &mut Hover) {
+        if self.hover_markdown {
+            return;
+        }
+
+        if let HoverContents::Markup(content) = &hover.contents
+            && content.kind == MarkupKind::Markdown
+        {
+            hover.contents = HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::PlainText,
+                value: plain_text(&content.value),
+            });
+        }
+    }
+}
+
+/// Renders the Markdown of the documentation as plain text: code fences and backticks are
+/// dropped, and links written as `text: url`
+fn plain_text(markdown: &str) -> String {
+    markdown
+        .lines()
+        .filter(|line| !line.starts_with("```"))
+        .map(|line| {
+            let mut line = line.replace('`', "");
+
+            while let Some(open) = line.find('[')
+                && let Some(middle) = line[open..].find("](").map(|i| open + i)
+                && let Some(close) = line[middle..].find(')').map(|i| middle + i)
+            {
+                let link = format!("{}: {}", &line[open + 1..middle], &line[middle + 2..close]);
+                line.replace_range(open..=close, &link);
+            }
+
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The text a snippet inserts once its placeholders are left as their defaults: `${1:id}`
+/// becomes `id`, and tab stops such as `$0` nothing
+fn expand_snippet(snippet: &str) -> String {
+    let mut text = String::new();
+    let mut chars = snippet.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.extend(chars.next()),
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+
+                // The placeholder's number, then its default after `:`, unless it's a tab stop
+                // such as `${1}`
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+
+                if chars.next() != Some(':') {
+                    continue;
+                }
+
+                let mut depth = 0;
+
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            text.extend(chars.next());
+                            continue;
+                        }
+                        '{' => depth += 1,
+                        '}' if depth == 0 => break,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+
+                    text.push(c);
+                }
+            }
+            '$' if chars.peek().is_some_and(char::is_ascii_digit) => {
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+            }
+            c => text.push(c),
+        }
+    }
+
+    text
+}
@@ -0,0 +1,507 @@
+//! The `cql-lsp check` command, linting files from the command line, e.g. in CI. The files are
+//! validated in order, each on top of the schema those before it define, so that a schema file
+//! goes before the data and migrations using it. Each is read chunk by chunk, like large
+//! documents, so that dumps of any size are checked in bounded memory, and its diagnostics are
+//! printed as they're found: as text, as JSON for other tools, or as SARIF for code scanning
+//! services such as GitHub's.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Value, json};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+use crate::{
+    bulk::{self, Validation},
+    config::Config,
+    document::PositionEncodingKind,
+    schema::Schema,
+};
+
+/// The rule of the SARIF results of diagnostics found by no lint, e.g. a missing `;`
+const DEFAULT_RULE: &str = "cql";
+
+const USAGE: &str = "Usage: cql-lsp check [--format text|json|sarif] <file>...";
+
+/// How diagnostics are printed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A line per diagnostic, as compilers print them
+    #[default]
+    Text,
+    /// A JSON array of diagnostics
+    Json,
+    /// A SARIF 2.1.0 log, e.g. for GitHub code scanning
+    Sarif,
+}
+
+impl Format {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "sarif" => Ok(Format::Sarif),
+            _ => bail!("Unknown format `{name}`: expected `text`, `json` or `sarif`"),
+        }
+    }
+
+    /// The columns of the format: characters, as editors show them, but for SARIF, which
+    /// counts UTF-16 code units by default
+    fn position_encoding(self) -> PositionEncodingKind {
+        match self {
+            Format::Sarif => PositionEncodingKind::UTF16,
+            _ => PositionEncodingKind::UTF32,
+        }
+    }
+}
+
+fn severity(diagnostic: &Diagnostic) -> &'static str {
+    match diagnostic.severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::HINT) => "hint",
+        _ => "info",
+    }
+}
+
+fn code(diagnostic: &Diagnostic) -> Option<&str> {
+    match &diagnostic.code {
+        Some(NumberOrString::String(code)) => Some(code),
+        _ => None,
+    }
+}
+
+/// A diagnostic as compilers print them, e.g. `schema.cql:3:8: error: Unknown table `users``,
+/// with one-based lines and columns
+fn text(path: &Path, diagnostic: &Diagnostic) -> String {
+    let code = code(diagnostic).map_or(String::new(), |code| format!(" [{code}]"));
+
+    format!(
+        "{}:{}:{}: {}: {}{code}",
+        path.display(),
+        diagnostic.range.start.line + 1,
+        diagnostic.range.start.character + 1,
+        severity(diagnostic),
+        diagnostic.message
+    )
+}
+
+/// A diagnostic as an object of the `json` format, with one-based lines and columns, the end
+/// being exclusive
+fn json(path: &Path, diagnostic: &Diagnostic) -> Value {
+    let range = diagnostic.range;
+
+    json!({
+        "path": path.display().to_string(),
+        "range": {
+            "start": { "line": range.start.line + 1, "column": range.start.character + 1 },
+            "end": { "line": range.end.line + 1, "column": range.end.character + 1 },
+        },
+        "severity": severity(diagnostic),
+        "code": code(diagnostic),
+        "message": diagnostic.message,
+        "link": diagnostic.code_description.as_ref().map(|description| description.href.as_str()),
+    })
+}
+
+/// A diagnostic as a SARIF result, with one-based lines and columns, the end being exclusive
+fn sarif_result(path: &Path, diagnostic: &Diagnostic) -> Value {
+    let range = diagnostic.range;
+    let level = match diagnostic.severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        _ => "note",
+    };
+
+    json!({
+        "ruleId": code(diagnostic).unwrap_or(DEFAULT_RULE),
+        "level": level,
+        "message": { "text": diagnostic.message },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": path.to_string_lossy().replace('\\', "/") },
+                "region": {
+                    "startLine": range.start.line + 1,
+                    "startColumn": range.start.character + 1,
+                    "endLine": range.end.line + 1,
+                    "endColumn": range.end.character + 1,
+                },
+            },
+        }],
+    })
+}
+
+/// Prints diagnostics in a format as they come, e.g. the elements of a JSON array
+struct Printer<'a, W> {
+    format: Format,
+    out: &'a mut W,
+    /// The diagnostics printed
+    count: usize,
+    /// The lints of the diagnostics printed, with their links, which SARIF lists as rules
+    rules: BTreeMap<String, Option<String>>,
+}
+
+impl<'a, W: Write> Printer<'a, W> {
+    fn begin(format: Format, out: &'a mut W) -> Result<Self> {
+        match format {
+            Format::Text => {}
+            Format::Json => write!(out, "[")?,
+            // The tool goes after the results, once the rules they're of are known
+            Format::Sarif => write!(
+                out,
+                r#"{{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0","runs":[{{"results":["#
+            )?,
+        }
+
+        Ok(Self {
+            format,
+            out,
+            count: 0,
+            rules: BTreeMap::new(),
+        })
+    }
+
+    fn print(&mut self, path: &Path, diagnostic: &Diagnostic) -> Result<()> {
+        let separator = match self.count {
+            0 => "",
+            _ => ",",
+        };
+
+        match self.format {
+            Format::Text => writeln!(self.out, "{}", text(path, diagnostic))?,
+            Format::Json => write!(self.out, "{separator}\n{}", json(path, diagnostic))?,
+            Format::Sarif => {
+                let rule = code(diagnostic).unwrap_or(DEFAULT_RULE);
+                let link = diagnostic
+                    .code_description
+                    .as_ref()
+                    .map(|description| description.href.to_string());
+
+                self.rules.entry(rule.to_string()).or_insert(link);
+                write!(self.out, "{separator}\n{}", sarif_result(path, diagnostic))?;
+            }
+        }
+
+        self.count += 1;
+
+        Ok(())
+    }
+
+    /// Closes the output, `error` being the one that stopped the check early, if any, which
+    /// SARIF records as a notification of a failed run
+    fn end(self, error: Option<&anyhow::Error>) -> Result<()> {
+        match self.format {
+            Format::Text => {}
+            Format::Json => writeln!(self.out, "\n]")?,
+            Format::Sarif => {
+                let rules = self
+                    .rules
+                    .into_iter()
+                    .map(|(id, link)| match (id.as_str(), link) {
+                        (DEFAULT_RULE, _) => json!({
+                            "id": id,
+                            "shortDescription": { "text": "Errors in CQL found outside the lints, e.g. a missing `;`" },
+                        }),
+                        (_, Some(link)) => json!({ "id": id, "helpUri": link }),
+                        (_, None) => json!({ "id": id }),
+                    })
+                    .collect::<Vec<_>>();
+
+                let tool = json!({
+                    "driver": {
+                        "name": "cql-lsp",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": "https://github.com/CleverFlare/cql-lsp",
+                        "rules": rules,
+                    },
+                });
+
+                let invocation = match error {
+                    Some(error) => json!({
+                        "executionSuccessful": false,
+                        "toolExecutionNotifications": [{
+                            "level": "error",
+                            "message": { "text": format!("{error:#}") },
+                        }],
+                    }),
+                    None => json!({ "executionSuccessful": true }),
+                };
+
+                writeln!(
+                    self.out,
+                    "\n],\"tool\":{tool},\"invocations\":[{invocation}]}}]}}"
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks the files at `paths` in order with the settings of `config`, printing their
+/// diagnostics to `out` in `format`. Returns whether any is an error.
+pub fn check(
+    paths: &[impl AsRef<Path>],
+    config: &Config,
+    format: Format,
+    out: &mut impl Write,
+) -> Result<bool> {
+    let mut printer = Printer::begin(format, out)?;
+    let checked = check_files(paths, config, &mut printer);
+
+    // Closed even when a file couldn't be read, so that JSON and SARIF stay well-formed
+    printer.end(checked.as_ref().err())?;
+
+    checked
+}
+
+fn check_files<W: Write>(
+    paths: &[impl AsRef<Path>],
+    config: &Config,
+    printer: &mut Printer<W>,
+) -> Result<bool> {
+    let mut schema = Schema::default();
+    let mut errors = false;
+
+    for path in paths {
+        let path = path.as_ref();
+        let file = File::open(path).with_context(|| format!("Couldn't read {}", path.display()))?;
+        let mut blocks = bulk::blocks(file);
+
+        let mut error = None;
+        let text = std::iter::from_fn(|| match blocks.next()? {
+            Ok(block) => Some(block),
+            Err(read_error) => {
+                error = Some(read_error);
+                None
+            }
+        });
+
+        let mut validation = Validation::new(
+            text,
+            schema,
+            config,
+            false,
+            printer.format.position_encoding(),
+        );
+
+        for diagnostics in validation.by_ref() {
+            for diagnostic in diagnostics {
+                errors |= diagnostic.severity == Some(DiagnosticSeverity::ERROR);
+                printer.print(path, &diagnostic)?;
+            }
+        }
+
+        schema = validation.into_schema();
+
+        if let Some(error) = error {
+            return Err(error).with_context(|| format!("Couldn't read {}", path.display()));
+        }
+    }
+
+    Ok(errors)
+}
+
+/// Reads the arguments of `cql-lsp check`: the format, and the paths of the files to check
+fn parse_args(args: &[String]) -> Result<(Format, Vec<&String>)> {
+    let mut format = Format::default();
+    let mut paths = vec![];
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if let Some(name) = arg.strip_prefix("--format=") {
+            format = Format::parse(name)?;
+        } else if arg == "--format" {
+            let Some(name) = args.next() else {
+                bail!("`--format` needs a value: `text`, `json` or `sarif`");
+            };
+
+            format = Format::parse(name)?;
+        } else if arg.starts_with("--") {
+            bail!("Unknown option `{arg}`");
+        } else {
+            paths.push(arg);
+        }
+    }
+
+    if paths.is_empty() {
+        bail!("No file to check");
+    }
+
+    Ok((format, paths))
+}
+
+/// Runs `cql-lsp check` with the command line arguments after `check`, with the settings of
+/// the current directory, and tells the exit code: 1 when errors were found, 2 when the
+/// arguments were wrong or files couldn't be read
+pub fn run(args: &[String]) -> u8 {
+    let (format, paths) = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!("error: {error:#}\n{USAGE}");
+            return 2;
+        }
+    };
+
+    let roots = std::env::current_dir().into_iter().collect::<Vec<_>>();
+    let (config, config_errors) = Config::load(&Default::default(), &roots);
+
+    for error in config_errors {
+        eprintln!("warning: {error:#}");
+    }
+
+    match check(&paths, &config, format, &mut io::stdout().lock()) {
+        Ok(true) => 1,
+        Ok(false) => 0,
+        Err(error) => {
+            eprintln!("error: {error:#}");
+            2
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+
+    const SCHEMA: &str = "CREATE TABLE ks.users (id int PRIMARY KEY, name text);\n";
+
+    /// A tombstone warning, then a missing `;` after a character of two UTF-16 code units
+    const DATA: &str = "INSERT INTO ks.users (id, name) VALUES (1, null);\n\
+                        /* 😀 */ SELECT * FROM ks.users\n\
+                        SELECT * FROM ks.users;\n";
+
+    /// Writes the schema and data files to a directory of their own, returning their paths
+    fn files(test: &str) -> Vec<PathBuf> {
+        let directory =
+            std::env::temp_dir().join(format!("cql-lsp-check-{}-{test}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        [("schema.cql", SCHEMA), ("data.cql", DATA)]
+            .into_iter()
+            .map(|(name, text)| {
+                let path = directory.join(name);
+                fs::write(&path, text).unwrap();
+                path
+            })
+            .collect()
+    }
+
+    /// Checks the files at `paths` in `format`, returning the result with the output parsed
+    fn check_paths(paths: &[PathBuf], format: Format) -> (Result<bool>, Value) {
+        let mut out = vec![];
+        let checked = check(paths, &Config::default(), format, &mut out);
+
+        (checked, serde_json::from_slice(&out).unwrap())
+    }
+
+    #[test]
+    fn json_lists_diagnostics() {
+        let paths = files("json");
+        let (checked, output) = check_paths(&paths, Format::Json);
+        let data = paths[1].display().to_string();
+
+        assert!(checked.unwrap());
+        assert_eq!(output[0]["path"], data);
+        assert_eq!(output[0]["code"], "tombstone-null");
+        assert_eq!(output[0]["severity"], "warning");
+        assert_eq!(
+            output[0]["range"],
+            json!({ "start": { "line": 1, "column": 44 }, "end": { "line": 1, "column": 48 } })
+        );
+        assert!(output[0]["link"].is_string());
+
+        // Columns count characters
+        assert_eq!(output[1]["severity"], "error");
+        assert_eq!(output[1]["code"], Value::Null);
+        assert_eq!(
+            output[1]["range"]["start"],
+            json!({ "line": 2, "column": 26 })
+        );
+        assert_eq!(output.as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn sarif_logs_results_and_rules() {
+        let paths = files("sarif");
+        let (checked, output) = check_paths(&paths, Format::Sarif);
+        let run = &output["runs"][0];
+
+        assert!(checked.unwrap());
+        assert_eq!(output["version"], "2.1.0");
+        assert_eq!(run["invocations"][0]["executionSuccessful"], true);
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "tombstone-null");
+        assert_eq!(results[0]["level"], "warning");
+        assert_eq!(results[1]["ruleId"], DEFAULT_RULE);
+        assert_eq!(results[1]["level"], "error");
+
+        // Columns count UTF-16 code units
+        assert_eq!(
+            results[1]["locations"][0]["physicalLocation"]["region"],
+            json!({ "startLine": 2, "startColumn": 27, "endLine": 2, "endColumn": 32 })
+        );
+
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0]["id"], DEFAULT_RULE);
+        assert_eq!(rules[1]["id"], "tombstone-null");
+        assert!(rules[1]["helpUri"].is_string());
+    }
+
+    #[test]
+    fn unreadable_files_keep_the_output_well_formed() {
+        let mut paths = files("unreadable");
+        paths.insert(1, paths[0].with_file_name("missing.cql"));
+
+        let (checked, output) = check_paths(&paths, Format::Json);
+        assert!(checked.is_err());
+        assert_eq!(output, json!([]));
+
+        let (checked, output) = check_paths(&paths, Format::Sarif);
+        let invocation = &output["runs"][0]["invocations"][0];
+
+        assert!(
+            checked
+                .unwrap_err()
+                .to_string()
+                .starts_with("Couldn't read")
+        );
+        assert_eq!(invocation["executionSuccessful"], false);
+        assert_eq!(
+            invocation["toolExecutionNotifications"][0]["level"],
+            "error"
+        );
+    }
+
+    #[test]
+    fn arguments() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        let parsed = args(&["--format", "sarif", "a.cql", "b.cql"]);
+        let (format, paths) = parse_args(&parsed).unwrap();
+        assert_eq!(format, Format::Sarif);
+        assert_eq!(paths, ["a.cql", "b.cql"]);
+
+        let parsed = args(&["--format=json", "a.cql"]);
+        assert_eq!(parse_args(&parsed).unwrap().0, Format::Json);
+
+        for wrong in [
+            &["a.cql", "--format"][..],
+            &["--format=xml", "a.cql"],
+            &["--fix", "a.cql"],
+            &[],
+        ] {
+            assert!(parse_args(&args(wrong)).is_err(), "{wrong:?}");
+        }
+    }
+}
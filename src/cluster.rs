@@ -0,0 +1,21 @@
+//! Connections to live clusters, described by named profiles, whose schema is read so that
+//! completion and diagnostics know the tables that only exist on the cluster. Connecting needs
+//! the `cluster` feature, as TCP and TLS aren't available to every target, e.g. WebAssembly.
+
+#[cfg(feature = "cluster")]
+mod astra;
+pub mod cache;
+#[cfg(feature = "cluster")]
+mod connection;
+pub mod consistency;
+pub mod history;
+#[cfg(feature = "cluster")]
+mod introspect;
+pub mod profile;
+#[cfg(feature = "cluster")]
+mod protocol;
+#[cfg(feature = "cluster")]
+pub mod value;
+
+#[cfg(feature = "cluster")]
+pub use connection::{Cluster, Endpoint};
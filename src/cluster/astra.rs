@@ -0,0 +1,222 @@
+//! DataStax Astra databases, reached through their secure connect bundle: a zip holding the
+//! address of the database's metadata service and the certificates to connect with. The
+//! metadata service lists the nodes, which are all behind an SNI proxy, each node being selected
+//! by giving its host id as the TLS server name.
+
+use std::{
+    fs::File,
+    io::{ErrorKind, Read},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use tokio_rustls::{
+    TlsConnector,
+    rustls::{
+        self, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+        client::{
+            WebPkiServerVerifier,
+            danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        },
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime, pem::PemObject},
+    },
+};
+
+use super::Endpoint;
+
+/// The `config.json` of a secure connect bundle
+#[derive(Debug, Deserialize)]
+struct BundleConfig {
+    host: String,
+    /// The port of the metadata service
+    port: u16,
+}
+
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    contact_info: ContactInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContactInfo {
+    /// The host ids of the nodes
+    contact_points: Vec<String>,
+    /// `host:port`
+    sni_proxy_address: String,
+}
+
+/// Checks certificates against a fixed name, since the server name sent through the SNI proxy
+/// is a host id rather than the name the proxy's certificate is issued for
+#[derive(Debug)]
+struct ProxyVerifier {
+    verifier: Arc<WebPkiServerVerifier>,
+    name: ServerName<'static>,
+}
+
+impl ServerCertVerifier for ProxyVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        _: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.verifier
+            .verify_server_cert(end_entity, intermediates, &self.name, ocsp_response, now)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.verifier.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.verifier.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.verifier.supported_verify_schemes()
+    }
+}
+
+/// The files of a secure connect bundle
+struct Bundle {
+    config: BundleConfig,
+    roots: Arc<RootCertStore>,
+    certificates: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+}
+
+impl Bundle {
+    fn read(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Couldn't open {}", path.display()))?;
+        let mut archive = zip::ZipArchive::new(file)
+            .with_context(|| format!("{} isn't a secure connect bundle", path.display()))?;
+
+        let mut read = |name: &str| -> Result<Vec<u8>> {
+            let mut entry = archive
+                .by_name(name)
+                .with_context(|| format!("No {name} in {}", path.display()))?;
+
+            let mut bytes = vec![];
+            entry.read_to_end(&mut bytes)?;
+
+            Ok(bytes)
+        };
+
+        let config = serde_json::from_slice(&read("config.json")?)?;
+
+        let mut roots = RootCertStore::empty();
+
+        for certificate in CertificateDer::pem_slice_iter(&read("ca.crt")?) {
+            roots.add(certificate?)?;
+        }
+
+        let certificates =
+            CertificateDer::pem_slice_iter(&read("cert")?).collect::<Result<_, _>>()?;
+        let key = PrivateKeyDer::from_pem_slice(&read("key")?)?;
+
+        Ok(Self {
+            config,
+            roots: Arc::new(roots),
+            certificates,
+            key,
+        })
+    }
+
+    /// Asks the metadata service for the nodes of the database
+    async fn metadata(&self) -> Result<Metadata> {
+        let config = ClientConfig::builder()
+            .with_root_certificates(self.roots.clone())
+            .with_client_auth_cert(self.certificates.clone(), self.key.clone_key())?;
+
+        let host = &self.config.host;
+        let tcp = TcpStream::connect((host.as_str(), self.config.port)).await?;
+        let mut tls = TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from(host.clone())?, tcp)
+            .await?;
+
+        // HTTP/1.0, so that the response is neither chunked nor kept alive
+        let request = format!("GET /metadata HTTP/1.0\r\nHost: {host}\r\n\r\n");
+        tls.write_all(request.as_bytes()).await?;
+
+        let mut response = vec![];
+
+        // Servers commonly close the connection without a TLS `close_notify`, which is fine as
+        // a truncated body would fail to parse anyway
+        if let Err(error) = tls.read_to_end(&mut response).await
+            && error.kind() != ErrorKind::UnexpectedEof
+        {
+            return Err(error.into());
+        }
+
+        let response = String::from_utf8_lossy(&response);
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .context("Invalid response from the metadata service")?;
+
+        let status = head.lines().next().unwrap_or_default();
+
+        if status.split_whitespace().nth(1) != Some("200") {
+            bail!("The metadata service answered {status}");
+        }
+
+        Ok(serde_json::from_str(body)?)
+    }
+}
+
+/// Reads the secure connect bundle at `path`, and returns the endpoints of the nodes of the
+/// database
+pub async fn endpoints(path: &Path) -> Result<Vec<Endpoint>> {
+    let bundle = Bundle::read(path)?;
+    let contact_info = bundle
+        .metadata()
+        .await
+        .context("Couldn't reach the metadata service")?
+        .contact_info;
+
+    let (proxy, port) = contact_info
+        .sni_proxy_address
+        .rsplit_once(':')
+        .and_then(|(host, port)| Some((host.to_string(), port.parse().ok()?)))
+        .ok_or_else(|| anyhow!("Invalid SNI proxy {}", contact_info.sni_proxy_address))?;
+
+    let verifier = ProxyVerifier {
+        verifier: WebPkiServerVerifier::builder(bundle.roots.clone()).build()?,
+        name: ServerName::try_from(proxy.clone())?,
+    };
+
+    let config = ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_client_auth_cert(bundle.certificates, bundle.key)?;
+    let tls = TlsConnector::from(Arc::new(config));
+
+    contact_info
+        .contact_points
+        .into_iter()
+        .map(|host_id| {
+            Ok(Endpoint {
+                host: proxy.clone(),
+                port,
+                tls: Some((tls.clone(), ServerName::try_from(host_id)?)),
+            })
+        })
+        .collect()
+}
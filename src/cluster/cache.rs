@@ -0,0 +1,74 @@
+//! The schema read from each cluster, kept in the user's cache directory so that completion
+//! knows the cluster's tables as soon as the server starts, before the connection (which may be
+//! slow, or wait for a password) is made.
+
+use std::{
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::Schema;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedSchema {
+    /// When the schema was read from the cluster
+    saved: SystemTime,
+    schema: Schema,
+}
+
+/// `$XDG_CACHE_HOME/cql-lsp`, falling back to `~/.cache/cql-lsp`, or `%LOCALAPPDATA%\cql-lsp`
+/// on Windows
+pub(super) fn cache_directory() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+
+    Some(base.join("cql-lsp"))
+}
+
+/// The cache file of the profile named `name` of the profiles file in `directory`, as profiles
+/// of different workspaces may share a name but not a cluster
+fn path(directory: &Path, name: &str) -> Option<PathBuf> {
+    let mut hasher = DefaultHasher::new();
+    directory.hash(&mut hasher);
+    name.hash(&mut hasher);
+
+    let file_name = name.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_");
+
+    Some(cache_directory()?.join(format!("{file_name}-{:016x}.json", hasher.finish())))
+}
+
+/// The cached schema of a profile, unless it's missing or older than `ttl`
+pub fn load(directory: &Path, name: &str, ttl: Duration) -> Option<Schema> {
+    let text = fs::read_to_string(path(directory, name)?).ok()?;
+    let cached: CachedSchema = serde_json::from_str(&text).ok()?;
+
+    match cached.saved.elapsed() {
+        Ok(age) if age <= ttl => Some(cached.schema),
+        _ => None,
+    }
+}
+
+/// Caches the schema just read from the cluster of a profile
+pub fn save(directory: &Path, name: &str, schema: Schema) -> Result<()> {
+    let path = path(directory, name).context("No cache directory")?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Couldn't create {}", parent.display()))?;
+    }
+
+    let cached = CachedSchema {
+        saved: SystemTime::now(),
+        schema,
+    };
+
+    fs::write(&path, serde_json::to_string(&cached)?)
+        .with_context(|| format!("Couldn't write {}", path.display()))
+}
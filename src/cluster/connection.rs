@@ -0,0 +1,529 @@
+//! Connections to a node of a cluster, over TCP and optionally TLS, through which statements
+//! are executed and the schema read.
+
+use std::{
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use tokio::{
+    net::TcpStream,
+    sync::Mutex,
+    time::{sleep, timeout},
+};
+use tokio_rustls::{
+    TlsConnector,
+    rustls::{
+        ClientConfig, RootCertStore,
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName, pem::PemObject},
+    },
+};
+use tower_lsp::Client;
+
+use crate::{
+    generate,
+    lexer::quote_identifier,
+    lsp_ext::{Trace, TraceEvent},
+    results::ResultSet,
+    schema::Schema,
+};
+
+use super::{
+    astra,
+    consistency::Consistencies,
+    introspect,
+    profile::{Profile, TlsSettings},
+    protocol::{Connection, Credentials, Rows, Stream},
+    value::Value,
+};
+
+/// How long a contact point gets to accept the connection and complete the handshake
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long reading the schema may take, which pages through every column of the cluster
+const INTROSPECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long the trace of a statement may take to be complete, as nodes write their events
+/// after responding
+const TRACE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A connection to a cluster through one of its nodes
+pub struct Cluster {
+    /// The name of the profile connected with
+    pub profile: String,
+    /// The `LIMIT` added to executed `SELECT`s that have none, 0 adding none
+    pub row_limit: u64,
+    /// Whether destructive statements need the user's confirmation
+    pub protected: bool,
+    /// The consistency levels of executed statements, unless the execution sets others
+    pub consistencies: Consistencies,
+    execute_timeout: Duration,
+    /// The node connected to, and what the connection was opened with, to open it again
+    endpoint: Endpoint,
+    credentials: Option<Credentials>,
+    datacenter: Option<String>,
+    connection: Mutex<Connection>,
+}
+
+/// Reads the PEM certificates of the file at `path`
+fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    CertificateDer::pem_file_iter(path)
+        .and_then(|certificates| certificates.collect())
+        .with_context(|| format!("Couldn't read certificates from {}", path.display()))
+}
+
+/// Builds the TLS configuration of a profile, whose relative paths are relative to `directory`
+fn tls_connector(settings: &TlsSettings, directory: &Path) -> Result<TlsConnector> {
+    let roots = match &settings.ca {
+        Some(ca) => {
+            let mut roots = RootCertStore::empty();
+
+            for certificate in read_certificates(&directory.join(ca))? {
+                roots.add(certificate)?;
+            }
+
+            roots
+        }
+        None => RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        },
+    };
+
+    let builder = ClientConfig::builder().with_root_certificates(roots);
+
+    let config = match (&settings.cert, &settings.key) {
+        (Some(cert), Some(key)) => {
+            let key = directory.join(key);
+            let key = PrivateKeyDer::from_pem_file(&key)
+                .with_context(|| format!("Couldn't read a private key from {}", key.display()))?;
+
+            builder.with_client_auth_cert(read_certificates(&directory.join(cert))?, key)?
+        }
+        (None, None) => builder.with_no_client_auth(),
+        _ => bail!("A client certificate needs both tls.cert and tls.key"),
+    };
+
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+/// Where a node is reached
+pub struct Endpoint {
+    pub(super) host: String,
+    pub(super) port: u16,
+    /// The TLS configuration, with the server name to send
+    pub(super) tls: Option<(TlsConnector, ServerName<'static>)>,
+}
+
+impl Endpoint {
+    /// Names the node in messages
+    fn name(&self) -> String {
+        match &self.tls {
+            Some((_, ServerName::DnsName(name))) if name.as_ref() != self.host => {
+                format!("{} ({})", self.host, name.as_ref())
+            }
+            _ => self.host.clone(),
+        }
+    }
+}
+
+/// The endpoints of the contact points of a self-hosted cluster
+fn contact_points(profile: &Profile, directory: &Path) -> Result<Vec<Endpoint>> {
+    let tls = profile
+        .tls
+        .as_ref()
+        .map(|settings| tls_connector(settings, directory))
+        .transpose()?;
+
+    profile
+        .contact_points
+        .iter()
+        .map(|host| {
+            let tls = match &tls {
+                Some(tls) => {
+                    let name = profile
+                        .tls
+                        .as_ref()
+                        .and_then(|tls| tls.server_name.clone())
+                        .unwrap_or_else(|| host.clone());
+                    let name = ServerName::try_from(name).context("Invalid TLS server name")?;
+
+                    Some((tls.clone(), name))
+                }
+                None => None,
+            };
+
+            Ok(Endpoint {
+                host: host.clone(),
+                port: profile.port,
+                tls,
+            })
+        })
+        .collect()
+}
+
+/// Opens a connection to `endpoint`, completing the handshake and checking that the node is in
+/// `datacenter`, if given
+async fn open(
+    endpoint: &Endpoint,
+    credentials: Option<&Credentials>,
+    datacenter: Option<&str>,
+) -> Result<Connection> {
+    let tcp = TcpStream::connect((endpoint.host.as_str(), endpoint.port)).await?;
+    tcp.set_nodelay(true)?;
+
+    let stream: Box<dyn Stream> = match &endpoint.tls {
+        Some((tls, name)) => Box::new(tls.connect(name.clone(), tcp).await?),
+        None => Box::new(tcp),
+    };
+
+    let mut connection = Connection::open(stream, credentials).await?;
+
+    if let Some(datacenter) = datacenter {
+        let rows = connection
+            .query("SELECT data_center FROM system.local")
+            .await?;
+
+        let local = rows
+            .rows
+            .first()
+            .and_then(|row| row.first()?.as_deref())
+            .map(String::from_utf8_lossy)
+            .unwrap_or_default();
+
+        if local != datacenter {
+            bail!("In datacenter {local}, not {datacenter}");
+        }
+    }
+
+    Ok(connection)
+}
+
+/// Decodes the values of `rows`
+fn decode(rows: &Rows) -> Result<Vec<Vec<Value>>> {
+    rows.rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(&rows.types)
+                .map(|(bytes, column_type)| match bytes {
+                    Some(bytes) => Value::decode(column_type, bytes),
+                    None => Ok(Value::Null),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Reads the trace of the tracing session `session` from `system_traces`, waiting for the
+/// session to be complete: the coordinator records its duration last
+async fn read_trace(connection: &mut Connection, session: [u8; 16]) -> Result<Trace> {
+    let session_id = Value::Uuid(session).to_string();
+
+    let started = Instant::now();
+    let session_row = loop {
+        let rows = connection
+            .query(&format!(
+                "SELECT coordinator, duration, started_at FROM system_traces.sessions WHERE session_id = {session_id}"
+            ))
+            .await?;
+
+        if let Some(row) = decode(&rows)?.into_iter().next()
+            && !matches!(row.get(1), None | Some(Value::Null))
+        {
+            break row;
+        }
+
+        // Give up on the duration, keeping the events recorded so far
+        if started.elapsed() > TRACE_TIMEOUT / 2 {
+            break vec![];
+        }
+
+        sleep(Duration::from_millis(100)).await;
+    };
+
+    let rows = connection
+        .query(&format!(
+            "SELECT activity, source, source_elapsed, thread FROM system_traces.events WHERE session_id = {session_id}"
+        ))
+        .await?;
+
+    let text = |value: Option<&Value>| match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::Text(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    };
+
+    let integer = |value: Option<&Value>| match value {
+        Some(Value::Integer(integer)) => Some(*integer),
+        _ => None,
+    };
+
+    let events = decode(&rows)?
+        .iter()
+        .map(|event| TraceEvent {
+            activity: text(event.first()),
+            source: text(event.get(1)),
+            elapsed_us: integer(event.get(2)),
+            thread: text(event.get(3)),
+        })
+        .collect::<Vec<_>>();
+
+    let coordinator = text(session_row.first());
+
+    let mut replicas = vec![];
+    for event in &events {
+        if event.source != coordinator && !replicas.contains(&event.source) {
+            replicas.push(event.source.clone());
+        }
+    }
+
+    Ok(Trace {
+        session_id,
+        coordinator,
+        replicas,
+        started_at: match session_row.get(2) {
+            Some(Value::Timestamp(milliseconds)) => *milliseconds,
+            _ => 0,
+        },
+        duration_us: integer(session_row.get(1)),
+        events,
+    })
+}
+
+impl Cluster {
+    /// Connects to the first node of `profile` that accepts the connection, where `directory`
+    /// is the directory of the profiles file. Secrets the profile doesn't store are asked to the
+    /// user through `client`.
+    pub async fn connect(
+        name: &str,
+        profile: &Profile,
+        directory: &Path,
+        client: &Client,
+    ) -> Result<Self> {
+        let endpoints = match &profile.secure_connect_bundle {
+            Some(bundle) => timeout(CONNECT_TIMEOUT, astra::endpoints(&directory.join(bundle)))
+                .await
+                .unwrap_or_else(|_| Err(anyhow!("Timed out reaching the metadata service")))?,
+            None => contact_points(profile, directory)?,
+        };
+
+        let credentials = match (&profile.username, &profile.password, &profile.token) {
+            (Some(username), Some(password), _) => Some(Credentials {
+                username: username.clone(),
+                password: password
+                    .resolve(
+                        directory,
+                        client,
+                        format!("Password of {username} ({name})"),
+                    )
+                    .await?,
+            }),
+            (Some(_), None, _) => bail!("Profile {name} has a username but no password"),
+            // Application tokens go with the username `token`
+            (None, _, Some(token)) => Some(Credentials {
+                username: "token".to_string(),
+                password: token
+                    .resolve(directory, client, format!("Token of {name}"))
+                    .await?,
+            }),
+            _ => None,
+        };
+
+        let mut errors = vec![];
+
+        for endpoint in endpoints {
+            let opened = timeout(
+                CONNECT_TIMEOUT,
+                open(
+                    &endpoint,
+                    credentials.as_ref(),
+                    profile.datacenter.as_deref(),
+                ),
+            )
+            .await
+            .unwrap_or_else(|_| Err(anyhow!("Timed out")));
+
+            match opened {
+                Ok(connection) => {
+                    return Ok(Self {
+                        profile: name.to_string(),
+                        row_limit: profile.row_limit,
+                        protected: profile.protected,
+                        consistencies: Consistencies {
+                            consistency: profile.consistency.unwrap_or_default(),
+                            serial: profile.serial_consistency,
+                        },
+                        execute_timeout: Duration::from_secs(profile.execute_timeout_seconds),
+                        endpoint,
+                        credentials,
+                        datacenter: profile.datacenter.clone(),
+                        connection: Mutex::new(connection),
+                    });
+                }
+                Err(error) => errors.push(format!("{}: {error:#}", endpoint.name())),
+            }
+        }
+
+        match errors.is_empty() {
+            true => bail!("Profile {name} has no contact points"),
+            false => bail!("Couldn't connect to {name} ({})", errors.join("; ")),
+        }
+    }
+
+    /// Returns the DDL of a keyspace, or of a table or view when `table` is given, as the
+    /// cluster describes it. Clusters older than Cassandra 4.0, which can't, get DDL built from
+    /// their schema instead.
+    pub async fn describe(&self, keyspace: &str, table: Option<&str>) -> Result<String> {
+        let name = match table {
+            Some(table) => format!("{}.{}", quote_identifier(keyspace), quote_identifier(table)),
+            None => quote_identifier(keyspace),
+        };
+
+        let described = {
+            let mut connection = self.connection.lock().await;
+
+            let described = timeout(
+                INTROSPECT_TIMEOUT,
+                connection.query(&format!("DESCRIBE {name}")),
+            )
+            .await;
+
+            if described.is_err() {
+                self.reopen(&mut connection).await?;
+            }
+
+            described
+        };
+
+        if let Ok(Ok(rows)) = described
+            && let Some(column) = rows
+                .columns
+                .iter()
+                .position(|column| column == "create_statement")
+        {
+            let statements = rows
+                .rows
+                .iter()
+                .filter_map(|row| row.get(column)?.as_deref())
+                .map(String::from_utf8_lossy)
+                .collect::<Vec<_>>();
+
+            return Ok(statements.join("\n\n"));
+        }
+
+        let schema = self.schema().await?;
+        let entry = schema
+            .keyspaces
+            .get(keyspace)
+            .ok_or_else(|| anyhow!("No keyspace {} on the cluster", quote_identifier(keyspace)))?;
+
+        match table {
+            None => Ok(generate::describe_keyspace(keyspace, entry)),
+            Some(table) => match (entry.tables.get(table), entry.views.contains_key(table)) {
+                (Some(table), _) => Ok(generate::describe_table(table)),
+                (None, true) => bail!("The cluster can't describe views, like {name}"),
+                (None, false) => bail!("No table {name} on the cluster"),
+            },
+        }
+    }
+
+    /// Runs `statement` with `consistencies`, in `keyspace` if given, and decodes the rows it
+    /// returns. With `trace`, the statement is traced and its trace read once complete, failing
+    /// to read it not failing the statement.
+    pub async fn execute(
+        &self,
+        statement: &str,
+        keyspace: Option<&str>,
+        consistencies: Consistencies,
+        trace: bool,
+    ) -> Result<(ResultSet, Option<Result<Trace>>)> {
+        if let Some(serial) = consistencies.serial
+            && !serial.is_serial()
+        {
+            bail!(
+                "The serial consistency is SERIAL or LOCAL_SERIAL, not {}",
+                serial.name()
+            );
+        }
+
+        let mut connection = self.connection.lock().await;
+
+        let rows = timeout(self.execute_timeout, async {
+            if let Some(keyspace) = keyspace {
+                connection
+                    .query(&format!("USE {}", quote_identifier(keyspace)))
+                    .await?;
+            }
+
+            connection.query_with(statement, consistencies, trace).await
+        })
+        .await;
+
+        let rows = match rows {
+            Ok(rows) => rows?,
+            Err(_) => {
+                self.reopen(&mut connection).await?;
+                bail!("Timed out after {}s", self.execute_timeout.as_secs());
+            }
+        };
+
+        let trace = match rows.tracing_id {
+            Some(session) => {
+                match timeout(TRACE_TIMEOUT, read_trace(&mut connection, session)).await {
+                    Ok(trace) => Some(trace),
+                    // The statement ran, so a failed reconnection only fails the trace
+                    Err(_) => Some(match self.reopen(&mut connection).await {
+                        Ok(()) => Err(anyhow!(
+                            "The trace wasn't complete after {}s",
+                            TRACE_TIMEOUT.as_secs()
+                        )),
+                        Err(error) => Err(error),
+                    }),
+                }
+            }
+            None => None,
+        };
+
+        let result = ResultSet {
+            rows: decode(&rows)?,
+            columns: rows.columns,
+        };
+
+        Ok((result, trace))
+    }
+
+    /// Reads the schema of the user keyspaces of the cluster
+    pub async fn schema(&self) -> Result<Schema> {
+        let mut connection = self.connection.lock().await;
+
+        match timeout(INTROSPECT_TIMEOUT, introspect::schema(&mut connection)).await {
+            Ok(schema) => schema,
+            Err(_) => {
+                self.reopen(&mut connection).await?;
+                bail!("Timed out reading the schema");
+            }
+        }
+    }
+
+    /// Replaces a connection whose request timed out, as the response may still come and be
+    /// taken for the response to the next request
+    async fn reopen(&self, connection: &mut Connection) -> Result<()> {
+        let opened = timeout(
+            CONNECT_TIMEOUT,
+            open(
+                &self.endpoint,
+                self.credentials.as_ref(),
+                self.datacenter.as_deref(),
+            ),
+        )
+        .await
+        .unwrap_or_else(|_| Err(anyhow!("Timed out")))
+        .context("Timed out, and couldn't reconnect")?;
+
+        *connection = opened;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+//! Consistency levels of executed statements, chosen per profile, per execution, or with
+//! cqlsh's `CONSISTENCY` and `SERIAL CONSISTENCY` directives in the document.
+
+use serde::{Deserialize, Serialize};
+
+use crate::statement::Statement;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Consistency {
+    Any,
+    /// The default, like in cqlsh
+    #[default]
+    One,
+    Two,
+    Three,
+    Quorum,
+    All,
+    LocalQuorum,
+    EachQuorum,
+    Serial,
+    LocalSerial,
+    LocalOne,
+}
+
+impl Consistency {
+    const LEVELS: [Consistency; 11] = [
+        Consistency::Any,
+        Consistency::One,
+        Consistency::Two,
+        Consistency::Three,
+        Consistency::Quorum,
+        Consistency::All,
+        Consistency::LocalQuorum,
+        Consistency::EachQuorum,
+        Consistency::Serial,
+        Consistency::LocalSerial,
+        Consistency::LocalOne,
+    ];
+
+    /// The `[consistency]` sent in requests
+    pub fn code(self) -> u16 {
+        Self::LEVELS
+            .iter()
+            .position(|&level| level == self)
+            .unwrap_or_default() as u16
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Consistency::Any => "ANY",
+            Consistency::One => "ONE",
+            Consistency::Two => "TWO",
+            Consistency::Three => "THREE",
+            Consistency::Quorum => "QUORUM",
+            Consistency::All => "ALL",
+            Consistency::LocalQuorum => "LOCAL_QUORUM",
+            Consistency::EachQuorum => "EACH_QUORUM",
+            Consistency::Serial => "SERIAL",
+            Consistency::LocalSerial => "LOCAL_SERIAL",
+            Consistency::LocalOne => "LOCAL_ONE",
+        }
+    }
+
+    /// Parses a level named like in CQL, ignoring case
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::LEVELS
+            .into_iter()
+            .find(|level| level.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Whether this is a level of the Paxos phase of lightweight transactions, the only ones
+    /// serial consistency takes
+    pub fn is_serial(self) -> bool {
+        matches!(self, Consistency::Serial | Consistency::LocalSerial)
+    }
+}
+
+/// The consistency levels a statement runs with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Consistencies {
+    pub consistency: Consistency,
+    /// The consistency of the Paxos phase of lightweight transactions, the server's default
+    /// (`SERIAL`) when `None`
+    pub serial: Option<Consistency>,
+}
+
+/// Returns the level set by a `CONSISTENCY level` directive, or by a `SERIAL CONSISTENCY level`
+/// one with `true`
+pub fn directive(statement: &Statement) -> Option<(bool, Consistency)> {
+    match statement.tokens {
+        [keyword, level] if keyword.is_keyword("CONSISTENCY") => {
+            Some((false, Consistency::parse(level.text)?))
+        }
+        [serial, keyword, level]
+            if serial.is_keyword("SERIAL") && keyword.is_keyword("CONSISTENCY") =>
+        {
+            Some((true, Consistency::parse(level.text)?))
+        }
+        _ => None,
+    }
+}
+
+/// Applies the `CONSISTENCY` and `SERIAL CONSISTENCY` directives before the statement at
+/// `index` to `consistencies`
+pub fn at(
+    statements: &[Statement],
+    index: usize,
+    mut consistencies: Consistencies,
+) -> Consistencies {
+    for statement in &statements[..index] {
+        match directive(statement) {
+            Some((false, level)) => consistencies.consistency = level,
+            Some((true, level)) => consistencies.serial = Some(level),
+            None => {}
+        }
+    }
+
+    consistencies
+}
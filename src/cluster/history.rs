@@ -0,0 +1,86 @@
+//! The statements run with `cql.execute`, kept per workspace in the user's cache directory so
+//! that earlier ad-hoc queries can be found and run again in later sessions.
+
+use std::{
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{cache::cache_directory, consistency::Consistency};
+
+/// How many statements are kept, the oldest being dropped first
+const KEPT_ENTRIES: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub statement: String,
+    /// The keyspace the statement was run in, from the `USE` before it
+    pub keyspace: Option<String>,
+    /// The name of the profile of the cluster the statement was run on
+    pub profile: String,
+    /// Absent from entries recorded before consistency levels could be chosen
+    #[serde(default)]
+    pub consistency: Consistency,
+    pub serial_consistency: Option<Consistency>,
+    /// When the statement was run, in milliseconds since the Unix epoch
+    pub executed_at: u64,
+    pub duration_ms: u64,
+    /// The number of rows returned, absent for statements that return none or failed
+    pub rows: Option<usize>,
+    pub error: Option<String>,
+}
+
+/// The statements run in a workspace, oldest first
+#[derive(Debug, Default)]
+pub struct History {
+    /// Where the history is saved, `None` when there's no cache directory
+    path: Option<PathBuf>,
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Loads the history of the workspace made of the folders `roots`
+    pub fn load(roots: &[PathBuf]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        roots.hash(&mut hasher);
+
+        let path = cache_directory()
+            .map(|directory| directory.join(format!("history-{:016x}.json", hasher.finish())));
+
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+
+        Self { path, entries }
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Adds a statement that was run, and saves the history
+    pub fn record(&mut self, entry: HistoryEntry) -> Result<()> {
+        if self.entries.len() >= KEPT_ENTRIES {
+            self.entries.drain(..=self.entries.len() - KEPT_ENTRIES);
+        }
+
+        self.entries.push(entry);
+
+        let path = self.path.as_ref().context("No cache directory")?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Couldn't create {}", parent.display()))?;
+        }
+
+        fs::write(path, serde_json::to_string(&self.entries)?)
+            .with_context(|| format!("Couldn't write {}", path.display()))
+    }
+}
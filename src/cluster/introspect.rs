@@ -0,0 +1,502 @@
+//! Reads the schema of a cluster from the `system_schema` keyspace (Cassandra 3.0 and later)
+//! into the same model DDL statements are replayed into.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, anyhow};
+
+use crate::{
+    cql_type::CqlType,
+    lexer::{self, quote_identifier},
+    schema::{Column, Function, Index, IndexTarget, Schema, Table, UserType},
+};
+
+use super::protocol::{Connection, Rows};
+
+/// Keyspaces of the cluster itself, which aren't worth completing or diffing
+const SYSTEM_KEYSPACES: &[&str] = &[
+    "system",
+    "system_auth",
+    "system_distributed",
+    "system_schema",
+    "system_traces",
+    "system_views",
+    "system_virtual_schema",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ColumnKind {
+    PartitionKey,
+    Clustering,
+    /// Regular and static columns
+    Regular,
+}
+
+/// A column read from `system_schema.columns`
+struct ColumnRow {
+    kind: ColumnKind,
+    /// The position of the column in the partition or clustering key
+    position: i32,
+    /// Whether a clustering column is in descending order
+    descending: bool,
+    /// Whether a regular column is static
+    is_static: bool,
+    column: Column,
+}
+
+/// How the value of a table option is serialized
+#[derive(Debug, Clone, Copy)]
+enum OptionType {
+    Boolean,
+    Int,
+    Double,
+    Text,
+    TextMap,
+}
+
+/// The columns of `system_schema.tables` that are options of the `WITH` clause, some of which
+/// only exist in some versions of Cassandra
+const TABLE_OPTIONS: &[(&str, OptionType)] = &[
+    ("additional_write_policy", OptionType::Text),
+    ("bloom_filter_fp_chance", OptionType::Double),
+    ("caching", OptionType::TextMap),
+    ("cdc", OptionType::Boolean),
+    ("comment", OptionType::Text),
+    ("compaction", OptionType::TextMap),
+    ("compression", OptionType::TextMap),
+    ("crc_check_chance", OptionType::Double),
+    ("dclocal_read_repair_chance", OptionType::Double),
+    ("default_time_to_live", OptionType::Int),
+    ("gc_grace_seconds", OptionType::Int),
+    ("max_index_interval", OptionType::Int),
+    ("memtable_flush_period_in_ms", OptionType::Int),
+    ("min_index_interval", OptionType::Int),
+    ("read_repair", OptionType::Text),
+    ("read_repair_chance", OptionType::Double),
+    ("speculative_retry", OptionType::Text),
+];
+
+/// A row of a query result, whose values are read by column name
+struct Row<'a> {
+    rows: &'a Rows,
+    values: &'a [Option<Vec<u8>>],
+}
+
+fn rows(rows: &Rows) -> impl Iterator<Item = Row<'_>> {
+    rows.rows.iter().map(move |values| Row { rows, values })
+}
+
+impl<'a> Row<'a> {
+    fn value(&self, column: &str) -> Result<Option<&'a [u8]>> {
+        let i = self
+            .rows
+            .columns
+            .iter()
+            .position(|name| name == column)
+            .ok_or_else(|| anyhow!("Missing column {column}"))?;
+
+        Ok(self.values.get(i).and_then(Option::as_deref))
+    }
+
+    /// Formats the value of a table option as it's written in DDL, `None` when it's `null` or
+    /// the cluster doesn't have the option
+    fn option(&self, column: &str, option_type: OptionType) -> Result<Option<String>> {
+        let Ok(Some(value)) = self.value(column) else {
+            return Ok(None);
+        };
+
+        let option = match option_type {
+            OptionType::Boolean => (value != [0]).to_string(),
+            OptionType::Int => i32::from_be_bytes(value.try_into()?).to_string(),
+            OptionType::Double => f64::from_be_bytes(value.try_into()?).to_string(),
+            OptionType::Text => format!("'{}'", String::from_utf8_lossy(value).replace('\'', "''")),
+            OptionType::TextMap => map_literal(&self.text_map(column)?),
+        };
+
+        Ok(Some(option))
+    }
+
+    fn text(&self, column: &str) -> Result<String> {
+        let value = self.value(column)?.unwrap_or_default();
+
+        Ok(String::from_utf8_lossy(value).into_owned())
+    }
+
+    fn int(&self, column: &str) -> Result<Option<i32>> {
+        self.value(column)?
+            .map(|value| Ok(i32::from_be_bytes(value.try_into()?)))
+            .transpose()
+    }
+
+    /// A `list<text>` or `frozen<list<text>>`
+    fn text_list(&self, column: &str) -> Result<Vec<String>> {
+        let Some(value) = self.value(column)? else {
+            return Ok(vec![]);
+        };
+
+        let mut elements = Elements { bytes: value };
+
+        (0..elements.int()?).map(|_| elements.next_text()).collect()
+    }
+
+    /// A `map<text, text>` or `frozen<map<text, text>>`
+    fn text_map(&self, column: &str) -> Result<BTreeMap<String, String>> {
+        let Some(value) = self.value(column)? else {
+            return Ok(BTreeMap::new());
+        };
+
+        let mut elements = Elements { bytes: value };
+
+        (0..elements.int()?)
+            .map(|_| Ok((elements.next_text()?, elements.next_text()?)))
+            .collect()
+    }
+}
+
+/// Reads the elements of a serialized collection
+struct Elements<'a> {
+    bytes: &'a [u8],
+}
+
+impl Elements<'_> {
+    fn int(&mut self) -> Result<i32> {
+        let (int, rest) = self
+            .bytes
+            .split_first_chunk::<4>()
+            .context("Truncated collection")?;
+        self.bytes = rest;
+
+        Ok(i32::from_be_bytes(*int))
+    }
+
+    fn next_text(&mut self) -> Result<String> {
+        let len = usize::try_from(self.int()?).unwrap_or(0);
+
+        if self.bytes.len() < len {
+            return Err(anyhow!("Truncated collection"));
+        }
+
+        let (text, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Ok(String::from_utf8_lossy(text).into_owned())
+    }
+}
+
+/// Parses a type as written in `system_schema`, e.g. `frozen<map<text, int>>`, keeping types
+/// the model doesn't know as custom types
+fn parse_type(text: &str) -> CqlType {
+    let tokens = lexer::tokenize(text);
+
+    CqlType::parse(&tokens, &mut 0).unwrap_or_else(|| CqlType::Custom(text.to_string()))
+}
+
+/// Formats a `map<text, text>` as the CQL map literal it's written as in DDL
+fn map_literal(map: &BTreeMap<String, String>) -> String {
+    let entries = map
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "'{}': '{}'",
+                key.replace('\'', "''"),
+                value.replace('\'', "''")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("{{{entries}}}")
+}
+
+/// Parses the `target` option of an index, e.g. `"Name"` or `keys(attributes)`
+fn parse_index_target(target: &str) -> Option<(String, IndexTarget)> {
+    let tokens = lexer::tokenize(target);
+
+    match tokens.as_slice() {
+        [column] => Some((column.identifier()?, IndexTarget::Column)),
+        [function, open, column, close]
+            if open.is_punctuation("(") && close.is_punctuation(")") =>
+        {
+            let target = match function.text.to_ascii_lowercase().as_str() {
+                "keys" => IndexTarget::Keys,
+                "values" => IndexTarget::Values,
+                "entries" => IndexTarget::Entries,
+                "full" => IndexTarget::Full,
+                _ => return None,
+            };
+
+            Some((column.identifier()?, target))
+        }
+        _ => None,
+    }
+}
+
+/// Reads the schema of the user keyspaces of the cluster
+pub async fn schema(connection: &mut Connection) -> Result<Schema> {
+    let keyspaces = connection
+        .query("SELECT keyspace_name, durable_writes, replication FROM system_schema.keyspaces")
+        .await?;
+    let tables = connection
+        .query("SELECT * FROM system_schema.tables")
+        .await?;
+    let views = connection
+        .query("SELECT keyspace_name, view_name FROM system_schema.views")
+        .await?;
+    let columns = connection
+        .query(
+            "SELECT keyspace_name, table_name, column_name, kind, position, clustering_order, \
+             type FROM system_schema.columns",
+        )
+        .await?;
+    let indexes = connection
+        .query("SELECT keyspace_name, table_name, index_name, kind, options FROM system_schema.indexes")
+        .await?;
+    let functions = connection
+        .query(
+            "SELECT keyspace_name, function_name, argument_types, return_type \
+             FROM system_schema.functions",
+        )
+        .await?;
+
+    let types = connection
+        .query("SELECT keyspace_name, type_name, field_names, field_types FROM system_schema.types")
+        .await?;
+
+    let mut schema = Schema::default();
+
+    for row in rows(&keyspaces) {
+        let name = row.text("keyspace_name")?;
+
+        if SYSTEM_KEYSPACES.contains(&name.as_str()) {
+            continue;
+        }
+
+        let keyspace = schema.keyspaces.entry(name).or_default();
+        keyspace.options.push((
+            "replication".to_string(),
+            map_literal(&row.text_map("replication")?),
+        ));
+
+        // `durable_writes` is only worth mentioning when it's been turned off
+        if row.value("durable_writes")? == Some(&[0]) {
+            keyspace
+                .options
+                .push(("durable_writes".to_string(), "false".to_string()));
+        }
+    }
+
+    // The columns of each table, by keyspace and table name
+    let mut table_columns: BTreeMap<(String, String), Vec<ColumnRow>> = BTreeMap::new();
+
+    for row in rows(&columns) {
+        let kind_name = row.text("kind")?;
+        let kind = match kind_name.as_str() {
+            "partition_key" => ColumnKind::PartitionKey,
+            "clustering" => ColumnKind::Clustering,
+            _ => ColumnKind::Regular,
+        };
+
+        let column = Column {
+            name: row.text("column_name")?,
+            data_type: parse_type(&row.text("type")?),
+        };
+
+        table_columns
+            .entry((row.text("keyspace_name")?, row.text("table_name")?))
+            .or_default()
+            .push(ColumnRow {
+                kind,
+                position: row.int("position")?.unwrap_or(-1),
+                descending: row.text("clustering_order")? == "desc",
+                is_static: kind_name == "static",
+                column,
+            });
+    }
+
+    let mut build_table = |keyspace: String, name: String| {
+        let mut columns = table_columns
+            .remove(&(keyspace.clone(), name.clone()))
+            .unwrap_or_default();
+
+        // Key columns in key order, then the others by name, like `DESCRIBE` lists them
+        columns.sort_by(|a, b| {
+            (a.kind, a.position, &a.column.name).cmp(&(b.kind, b.position, &b.column.name))
+        });
+
+        let clustering_order = columns
+            .iter()
+            .filter(|row| row.kind == ColumnKind::Clustering)
+            .map(|row| {
+                let order = match row.descending {
+                    true => "DESC",
+                    false => "ASC",
+                };
+
+                format!("{} {order}", quote_identifier(&row.column.name))
+            })
+            .collect::<Vec<_>>();
+
+        // Stored like the `CLUSTERING ORDER BY (...)` clause of DDL is
+        let options = match clustering_order.is_empty() {
+            true => vec![],
+            false => vec![(
+                "clustering".to_string(),
+                format!("ORDER BY ({})", clustering_order.join(", ")),
+            )],
+        };
+
+        let key = |kind| {
+            columns
+                .iter()
+                .filter(|row| row.kind == kind)
+                .map(|row| row.column.name.clone())
+                .collect()
+        };
+
+        Table {
+            partition_key: key(ColumnKind::PartitionKey),
+            clustering_key: key(ColumnKind::Clustering),
+            static_columns: columns
+                .iter()
+                .filter(|row| row.is_static)
+                .map(|row| row.column.name.clone())
+                .collect(),
+            columns: columns.into_iter().map(|row| row.column).collect(),
+            keyspace,
+            name,
+            options,
+            indexes: vec![],
+        }
+    };
+
+    for row in rows(&tables) {
+        let keyspace = row.text("keyspace_name")?;
+        let name = row.text("table_name")?;
+
+        if let Some(entry) = schema.keyspaces.get_mut(&keyspace) {
+            let mut table = build_table(keyspace, name.clone());
+
+            for &(option, option_type) in TABLE_OPTIONS {
+                if let Some(value) = row.option(option, option_type)? {
+                    table.options.push((option.to_string(), value));
+                }
+            }
+
+            entry.tables.insert(name, table);
+        }
+    }
+
+    for row in rows(&views) {
+        let keyspace = row.text("keyspace_name")?;
+        let name = row.text("view_name")?;
+
+        if let Some(entry) = schema.keyspaces.get_mut(&keyspace) {
+            let view = build_table(keyspace, name.clone());
+            entry.views.insert(name, view);
+        }
+    }
+
+    for row in rows(&indexes) {
+        let options = row.text_map("options")?;
+
+        let Some((column, target)) = options
+            .get("target")
+            .and_then(|target| parse_index_target(target))
+        else {
+            continue;
+        };
+
+        let index = Index {
+            name: Some(row.text("index_name")?),
+            column,
+            target,
+            class: match row.text("kind")?.as_str() {
+                "CUSTOM" => options.get("class_name").cloned(),
+                _ => None,
+            },
+        };
+
+        let (keyspace, table) = (row.text("keyspace_name")?, row.text("table_name")?);
+
+        if let Some(table) = schema
+            .keyspaces
+            .get_mut(&keyspace)
+            .and_then(|keyspace| keyspace.tables.get_mut(&table))
+        {
+            table.indexes.push(index);
+        }
+    }
+
+    for row in rows(&functions) {
+        let keyspace = row.text("keyspace_name")?;
+
+        let Some(entry) = schema.keyspaces.get_mut(&keyspace) else {
+            continue;
+        };
+
+        let argument_types = row
+            .text_list("argument_types")?
+            .iter()
+            .map(|data_type| parse_type(data_type))
+            .collect();
+
+        entry.functions.push(Function {
+            name: row.text("function_name")?,
+            keyspace,
+            argument_types,
+            return_type: Some(parse_type(&row.text("return_type")?)),
+        });
+    }
+
+    for row in rows(&types) {
+        let keyspace = row.text("keyspace_name")?;
+
+        let Some(entry) = schema.keyspaces.get_mut(&keyspace) else {
+            continue;
+        };
+
+        let fields = row
+            .text_list("field_names")?
+            .into_iter()
+            .zip(row.text_list("field_types")?)
+            .map(|(name, data_type)| Column {
+                name,
+                data_type: parse_type(&data_type),
+            })
+            .collect();
+
+        let name = row.text("type_name")?;
+        entry.types.insert(
+            name.clone(),
+            UserType {
+                keyspace,
+                name,
+                fields,
+            },
+        );
+    }
+
+    // Reading the roles takes permissions the user may not have, and clusters without
+    // authentication have none, in which case roles aren't checked
+    if let Ok(roles) = connection.query("SELECT role FROM system_auth.roles").await {
+        for row in rows(&roles) {
+            schema.roles.insert(row.text("role")?);
+        }
+
+        schema.cluster_roles = !schema.roles.is_empty();
+    }
+
+    // The node connected to and its peers together span every datacenter of the cluster
+    for query in [
+        "SELECT data_center FROM system.local",
+        "SELECT data_center FROM system.peers",
+    ] {
+        if let Ok(nodes) = connection.query(query).await {
+            for row in rows(&nodes) {
+                schema.datacenters.insert(row.text("data_center")?);
+            }
+        }
+    }
+
+    Ok(schema)
+}
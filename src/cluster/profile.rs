@@ -0,0 +1,237 @@
+//! Connection profiles, read from the `.cql-lsp.toml` file at the root of the workspace:
+//!
+//! ```toml
+//! default_profile = "dev"
+//!
+//! [profiles.dev]
+//! contact_points = ["127.0.0.1"]
+//!
+//! [profiles.staging]
+//! contact_points = ["10.0.0.1", "10.0.0.2"]
+//! port = 9142
+//! datacenter = "eu-west"
+//! username = "app"
+//! password = "prompt"
+//!
+//! [profiles.staging.tls]
+//! ca = "certs/staging-ca.pem"
+//! cert = "certs/client.pem"
+//! key = "certs/client.key"
+//!
+//! [profiles.astra]
+//! secure_connect_bundle = "secure-connect-db.zip"
+//! token = { env = "ASTRA_DB_APPLICATION_TOKEN" }
+//! schema_cache_hours = 168
+//!
+//! [profiles.production]
+//! contact_points = ["10.1.0.1"]
+//! protected = true
+//! files = ["migrations/production"]
+//! row_limit = 100
+//! execute_timeout_seconds = 10
+//! consistency = "LOCAL_QUORUM"
+//! serial_consistency = "LOCAL_SERIAL"
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use tower_lsp::Client;
+
+use super::consistency::Consistency;
+use crate::lsp_ext::{ShowInputBox, ShowInputBoxParams};
+
+/// The name of the file defining the connection profiles
+pub const PROFILES_FILE: &str = ".cql-lsp.toml";
+
+/// A secret kept out of the profiles file, which is likely committed
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Secret {
+    /// Read from an environment variable
+    Env(String),
+    /// Read from a file, relative to the profiles file
+    File(PathBuf),
+    /// Asked to the user through the client on every connection
+    Prompt,
+}
+
+impl Secret {
+    /// Reads the secret, where `prompt` describes it to the user when it's to be asked
+    pub async fn resolve(
+        &self,
+        directory: &Path,
+        client: &Client,
+        prompt: String,
+    ) -> Result<String> {
+        match self {
+            Secret::Env(name) => {
+                std::env::var(name).with_context(|| format!("Couldn't read ${name}"))
+            }
+            Secret::File(path) => {
+                let path = directory.join(path);
+                let secret = fs::read_to_string(&path)
+                    .with_context(|| format!("Couldn't read {}", path.display()))?;
+
+                Ok(secret.trim_end().to_string())
+            }
+            Secret::Prompt => {
+                let input = client
+                    .send_request::<ShowInputBox>(ShowInputBoxParams {
+                        prompt,
+                        password: true,
+                    })
+                    .await
+                    .map_err(|error| anyhow!("Couldn't prompt for the secret: {error}"))?;
+
+                match input {
+                    Some(input) => Ok(input),
+                    None => bail!("Cancelled by the user"),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsSettings {
+    /// The name the certificates of the nodes are checked against, when they aren't issued
+    /// for the contact points
+    pub server_name: Option<String>,
+    /// A PEM bundle of the certificate authorities the certificates of the nodes are checked
+    /// against, instead of the public ones
+    pub ca: Option<PathBuf>,
+    /// A PEM certificate chain authenticating the client, with its PEM private key
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Nodes tried in order until one accepts the connection
+    #[serde(default)]
+    pub contact_points: Vec<String>,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// When set, only nodes of this datacenter are connected to
+    pub datacenter: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<Secret>,
+    pub tls: Option<TlsSettings>,
+    /// The secure connect bundle of an Astra database, which replaces the contact points, port
+    /// and TLS settings
+    pub secure_connect_bundle: Option<PathBuf>,
+    /// An Astra application token, used when there's no username
+    pub token: Option<Secret>,
+    /// How long the schema read from the cluster is used from the cache before connecting, 0
+    /// turning the cache off
+    #[serde(default = "default_schema_cache_hours")]
+    pub schema_cache_hours: u64,
+    /// The `LIMIT` added to executed `SELECT`s that have none, 0 adding none
+    #[serde(default = "default_row_limit")]
+    pub row_limit: u64,
+    /// How long an executed statement may take
+    #[serde(default = "default_execute_timeout_seconds")]
+    pub execute_timeout_seconds: u64,
+    /// Whether executing `DROP`, `TRUNCATE` and unguarded `DELETE` statements needs the user's
+    /// confirmation, for clusters holding data that matters
+    #[serde(default)]
+    pub protected: bool,
+    /// The files and directories whose statements are meant for the cluster, relative to the
+    /// profiles file. Those of a protected profile have their `DROP` and `TRUNCATE` flagged.
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+    /// The consistency level of executed statements, `ONE` when unset
+    pub consistency: Option<Consistency>,
+    /// The consistency level of the Paxos phase of executed lightweight transactions
+    pub serial_consistency: Option<Consistency>,
+}
+
+fn default_port() -> u16 {
+    9042
+}
+
+fn default_schema_cache_hours() -> u64 {
+    24
+}
+
+fn default_row_limit() -> u64 {
+    1000
+}
+
+fn default_execute_timeout_seconds() -> u64 {
+    30
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfilesFile {
+    /// The profile connected with when the server starts
+    default_profile: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// Reads the profiles file of the first workspace folder having one, with its path
+fn read(roots: &[PathBuf]) -> Result<Option<(ProfilesFile, PathBuf)>> {
+    let Some(path) = roots
+        .iter()
+        .map(|root| root.join(PROFILES_FILE))
+        .find(|path| path.is_file())
+    else {
+        return Ok(None);
+    };
+
+    let text =
+        fs::read_to_string(&path).with_context(|| format!("Couldn't read {}", path.display()))?;
+    let file = toml::from_str(&text).with_context(|| format!("Invalid {}", path.display()))?;
+
+    Ok(Some((file, path)))
+}
+
+/// The name of the profile to connect with on startup, if the workspace has one
+pub fn default_profile(roots: &[PathBuf]) -> Result<Option<String>> {
+    Ok(read(roots)?.and_then(|(file, _)| file.default_profile))
+}
+
+/// The files and directories meant for the clusters of the protected profiles, if the workspace
+/// has a profiles file
+pub fn protected_paths(roots: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let Some((file, path)) = read(roots)? else {
+        return Ok(vec![]);
+    };
+
+    let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    Ok(file
+        .profiles
+        .into_values()
+        .filter(|profile| profile.protected)
+        .flat_map(|profile| profile.files)
+        .map(|file| directory.join(file))
+        .collect())
+}
+
+/// Looks up the profile named `name` in the profiles file of the first workspace folder having
+/// one, returning it with the directory of the file, which relative paths are resolved against
+pub fn load(roots: &[PathBuf], name: &str) -> Result<(Profile, PathBuf)> {
+    let (mut file, path) =
+        read(roots)?.ok_or_else(|| anyhow!("No {PROFILES_FILE} in the workspace"))?;
+
+    let profile = file.profiles.remove(name).ok_or_else(|| {
+        anyhow!(
+            "No profile named {name} in {PROFILES_FILE} (known profiles: {})",
+            file.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+        )
+    })?;
+
+    let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    Ok((profile, directory))
+}
@@ -0,0 +1,721 @@
+//! The parts of the Cassandra native protocol (version 4) the server needs to read from a
+//! cluster: the startup handshake, SASL plain text authentication, and paged queries.
+//!
+//! See <https://github.com/apache/cassandra/blob/trunk/doc/native_protocol_v4.spec>.
+
+use anyhow::{Result, anyhow, bail};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::consistency::Consistencies;
+
+const VERSION: u8 = 0x04;
+/// Set in the version byte of response frames
+const RESPONSE: u8 = 0x80;
+
+const ERROR: u8 = 0x00;
+const STARTUP: u8 = 0x01;
+const READY: u8 = 0x02;
+const AUTHENTICATE: u8 = 0x03;
+const QUERY: u8 = 0x07;
+const RESULT: u8 = 0x08;
+const AUTH_CHALLENGE: u8 = 0x0E;
+const AUTH_RESPONSE: u8 = 0x0F;
+const AUTH_SUCCESS: u8 = 0x10;
+
+/// Frame flags
+const TRACING: u8 = 0x02;
+const CUSTOM_PAYLOAD: u8 = 0x04;
+const WARNING: u8 = 0x08;
+
+/// Result kinds
+const ROWS: i32 = 0x0002;
+
+/// Query flags
+const PAGE_SIZE: u8 = 0x04;
+const WITH_PAGING_STATE: u8 = 0x08;
+const WITH_SERIAL_CONSISTENCY: u8 = 0x10;
+
+/// Rows metadata flags
+const GLOBAL_TABLES_SPEC: i32 = 0x0001;
+const HAS_MORE_PAGES: i32 = 0x0002;
+const NO_METADATA: i32 = 0x0004;
+
+/// Rows fetched per page
+const PAGE_ROWS: i32 = 5000;
+
+/// A connection to a node: a TCP stream, possibly wrapped in TLS
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send + Sync {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Sync> Stream for T {}
+
+/// Credentials for the `PasswordAuthenticator`
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// The type of a result column, as given by the `[option]` of its metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnType {
+    /// Custom types, named by their Java class, and types this version of the protocol doesn't
+    /// define, whose values are shown as blobs
+    Custom,
+    Ascii,
+    Bigint,
+    Blob,
+    Boolean,
+    Counter,
+    Decimal,
+    Double,
+    Float,
+    Int,
+    Timestamp,
+    Uuid,
+    Varchar,
+    Varint,
+    Timeuuid,
+    Inet,
+    Date,
+    Time,
+    Smallint,
+    Tinyint,
+    Duration,
+    List(Box<ColumnType>),
+    Map(Box<ColumnType>, Box<ColumnType>),
+    Set(Box<ColumnType>),
+    /// A user-defined type, by its fields
+    Udt(Vec<(String, ColumnType)>),
+    Tuple(Vec<ColumnType>),
+}
+
+/// A page of rows, with the value of each column as raw bytes, `None` being `null`
+#[derive(Debug, Default)]
+pub struct Rows {
+    pub columns: Vec<String>,
+    pub types: Vec<ColumnType>,
+    pub rows: Vec<Vec<Option<Vec<u8>>>>,
+    /// The tracing session of the query, when it was traced
+    pub tracing_id: Option<[u8; 16]>,
+    paging_state: Option<Vec<u8>>,
+}
+
+/// Reads the notations of the protocol from the body of a frame
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            bail!("Truncated response from the server");
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    fn short(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn int(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.short()? as usize;
+
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn string_list(&mut self) -> Result<Vec<String>> {
+        (0..self.short()?).map(|_| self.string()).collect()
+    }
+
+    /// `[bytes]`, where a negative length is `null`
+    fn bytes(&mut self) -> Result<Option<&'a [u8]>> {
+        let len = self.int()?;
+
+        match usize::try_from(len) {
+            Ok(len) => self.take(len).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn short_bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.short()? as usize;
+
+        self.take(len)
+    }
+
+    /// Reads an `[option]` describing the type of a column
+    fn column_type(&mut self) -> Result<ColumnType> {
+        let column_type = match self.short()? {
+            0x0000 => {
+                self.string()?;
+                ColumnType::Custom
+            }
+            0x0001 => ColumnType::Ascii,
+            0x0002 => ColumnType::Bigint,
+            0x0003 => ColumnType::Blob,
+            0x0004 => ColumnType::Boolean,
+            0x0005 => ColumnType::Counter,
+            0x0006 => ColumnType::Decimal,
+            0x0007 => ColumnType::Double,
+            0x0008 => ColumnType::Float,
+            0x0009 => ColumnType::Int,
+            0x000B => ColumnType::Timestamp,
+            0x000C => ColumnType::Uuid,
+            0x000D => ColumnType::Varchar,
+            0x000E => ColumnType::Varint,
+            0x000F => ColumnType::Timeuuid,
+            0x0010 => ColumnType::Inet,
+            0x0011 => ColumnType::Date,
+            0x0012 => ColumnType::Time,
+            0x0013 => ColumnType::Smallint,
+            0x0014 => ColumnType::Tinyint,
+            0x0015 => ColumnType::Duration,
+            0x0020 => ColumnType::List(Box::new(self.column_type()?)),
+            0x0021 => ColumnType::Map(Box::new(self.column_type()?), Box::new(self.column_type()?)),
+            0x0022 => ColumnType::Set(Box::new(self.column_type()?)),
+            0x0030 => {
+                // The keyspace and name of the type
+                self.string()?;
+                self.string()?;
+
+                let fields = (0..self.short()?)
+                    .map(|_| Ok((self.string()?, self.column_type()?)))
+                    .collect::<Result<_>>()?;
+
+                ColumnType::Udt(fields)
+            }
+            0x0031 => ColumnType::Tuple(
+                (0..self.short()?)
+                    .map(|_| self.column_type())
+                    .collect::<Result<_>>()?,
+            ),
+            _ => ColumnType::Custom,
+        };
+
+        Ok(column_type)
+    }
+
+    fn rows(&mut self) -> Result<Rows> {
+        let flags = self.int()?;
+        let column_count = usize::try_from(self.int()?)?;
+
+        let paging_state = match flags & HAS_MORE_PAGES != 0 {
+            true => self.bytes()?.map(<[u8]>::to_vec),
+            false => None,
+        };
+
+        let mut columns = vec![];
+        let mut types = vec![];
+
+        if flags & NO_METADATA == 0 {
+            if flags & GLOBAL_TABLES_SPEC != 0 {
+                self.string()?;
+                self.string()?;
+            }
+
+            for _ in 0..column_count {
+                if flags & GLOBAL_TABLES_SPEC == 0 {
+                    self.string()?;
+                    self.string()?;
+                }
+
+                columns.push(self.string()?);
+                types.push(self.column_type()?);
+            }
+        }
+
+        let row_count = self.int()?;
+        let mut rows = vec![];
+
+        for _ in 0..row_count {
+            let row = (0..column_count)
+                .map(|_| Ok(self.bytes()?.map(<[u8]>::to_vec)))
+                .collect::<Result<_>>()?;
+
+            rows.push(row);
+        }
+
+        Ok(Rows {
+            columns,
+            types,
+            rows,
+            tracing_id: None,
+            paging_state,
+        })
+    }
+}
+
+fn put_string(body: &mut Vec<u8>, string: &str) {
+    body.extend_from_slice(&(string.len() as u16).to_be_bytes());
+    body.extend_from_slice(string.as_bytes());
+}
+
+fn put_long_string(body: &mut Vec<u8>, string: &str) {
+    body.extend_from_slice(&(string.len() as i32).to_be_bytes());
+    body.extend_from_slice(string.as_bytes());
+}
+
+fn put_bytes(body: &mut Vec<u8>, bytes: &[u8]) {
+    body.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+    body.extend_from_slice(bytes);
+}
+
+pub struct Connection {
+    stream: Box<dyn Stream>,
+}
+
+impl Connection {
+    /// Performs the startup handshake over `stream`, authenticating with `credentials` if the
+    /// server requires it
+    pub async fn open(stream: Box<dyn Stream>, credentials: Option<&Credentials>) -> Result<Self> {
+        let mut connection = Self { stream };
+
+        let mut startup = vec![];
+        startup.extend_from_slice(&1u16.to_be_bytes());
+        put_string(&mut startup, "CQL_VERSION");
+        put_string(&mut startup, "3.0.0");
+
+        let (opcode, body) = connection.request(STARTUP, &startup).await?;
+
+        match opcode {
+            READY => return Ok(connection),
+            AUTHENTICATE => {}
+            opcode => bail!("Unexpected response 0x{opcode:02x} to STARTUP"),
+        }
+
+        let authenticator = Reader { bytes: &body }.string()?;
+
+        let Some(credentials) = credentials else {
+            bail!("The server requires authentication ({authenticator})");
+        };
+
+        // SASL PLAIN, as expected by `PasswordAuthenticator` and its derivatives
+        let token = format!("\0{}\0{}", credentials.username, credentials.password);
+        let mut response = vec![];
+        put_bytes(&mut response, token.as_bytes());
+
+        match connection.request(AUTH_RESPONSE, &response).await?.0 {
+            AUTH_SUCCESS => Ok(connection),
+            AUTH_CHALLENGE => bail!("Unsupported authentication challenge ({authenticator})"),
+            opcode => bail!("Unexpected response 0x{opcode:02x} to AUTH_RESPONSE"),
+        }
+    }
+
+    /// Sends a request frame and waits for its response, returning the response's opcode and
+    /// body, or the error the server answered with
+    async fn request(&mut self, opcode: u8, body: &[u8]) -> Result<(u8, Vec<u8>)> {
+        let (opcode, body, _) = self.traced_request(opcode, body, false).await?;
+
+        Ok((opcode, body))
+    }
+
+    /// Like [`Connection::request`], asking the server to trace the request if `tracing`, and
+    /// also returning the tracing session the server opened for it
+    async fn traced_request(
+        &mut self,
+        opcode: u8,
+        body: &[u8],
+        tracing: bool,
+    ) -> Result<(u8, Vec<u8>, Option<[u8; 16]>)> {
+        let mut frame = vec![VERSION, if tracing { TRACING } else { 0 }];
+        // Requests are sent one at a time, so they can all use the same stream id
+        frame.extend_from_slice(&0i16.to_be_bytes());
+        frame.push(opcode);
+        put_bytes(&mut frame, body);
+
+        self.stream.write_all(&frame).await?;
+        self.stream.flush().await?;
+
+        let mut header = [0; 9];
+        self.stream.read_exact(&mut header).await?;
+
+        if header[0] != RESPONSE | VERSION {
+            bail!("Unsupported protocol version 0x{:02x}", header[0]);
+        }
+
+        let flags = header[1];
+        let opcode = header[4];
+        let len = u32::from_be_bytes(header[5..9].try_into()?) as usize;
+
+        let mut body = vec![0; len];
+        self.stream.read_exact(&mut body).await?;
+
+        let mut reader = Reader { bytes: &body };
+
+        let tracing_id = match flags & TRACING != 0 {
+            true => Some(reader.take(16)?.try_into()?),
+            false => None,
+        };
+
+        if flags & WARNING != 0 {
+            reader.string_list()?;
+        }
+
+        if flags & CUSTOM_PAYLOAD != 0 {
+            for _ in 0..reader.short()? {
+                reader.string()?;
+                reader.short_bytes()?;
+            }
+        }
+
+        if opcode == ERROR {
+            let code = reader.int()?;
+            let message = reader.string()?;
+
+            return Err(anyhow!("{message} (error code 0x{code:04x})"));
+        }
+
+        let rest = reader.bytes.to_vec();
+
+        Ok((opcode, rest, tracing_id))
+    }
+
+    /// Runs `query`, returning the page of rows after `paging_state`, traced if `tracing`.
+    /// Statements that don't return rows give an empty page.
+    async fn query_page(
+        &mut self,
+        query: &str,
+        consistencies: Consistencies,
+        paging_state: Option<&[u8]>,
+        tracing: bool,
+    ) -> Result<Rows> {
+        let mut body = vec![];
+        put_long_string(&mut body, query);
+        body.extend_from_slice(&consistencies.consistency.code().to_be_bytes());
+
+        let mut flags = PAGE_SIZE;
+
+        if paging_state.is_some() {
+            flags |= WITH_PAGING_STATE;
+        }
+
+        if consistencies.serial.is_some() {
+            flags |= WITH_SERIAL_CONSISTENCY;
+        }
+
+        body.push(flags);
+        body.extend_from_slice(&PAGE_ROWS.to_be_bytes());
+
+        if let Some(paging_state) = paging_state {
+            put_bytes(&mut body, paging_state);
+        }
+
+        if let Some(serial) = consistencies.serial {
+            body.extend_from_slice(&serial.code().to_be_bytes());
+        }
+
+        let (opcode, body, tracing_id) = self.traced_request(QUERY, &body, tracing).await?;
+
+        if opcode != RESULT {
+            bail!("Unexpected response 0x{opcode:02x} to QUERY");
+        }
+
+        let mut reader = Reader { bytes: &body };
+
+        let rows = match reader.int()? {
+            ROWS => reader.rows()?,
+            _ => Rows::default(),
+        };
+
+        Ok(Rows { tracing_id, ..rows })
+    }
+
+    /// Runs `query` at consistency `ONE`, fetching every page of its rows
+    pub async fn query(&mut self, query: &str) -> Result<Rows> {
+        self.query_with(query, Consistencies::default(), false)
+            .await
+    }
+
+    /// Runs `query` with `consistencies`, fetching every page of its rows. The first page is
+    /// traced if `tracing`, the others being reads of the same partitions.
+    pub async fn query_with(
+        &mut self,
+        query: &str,
+        consistencies: Consistencies,
+        tracing: bool,
+    ) -> Result<Rows> {
+        let mut rows = self.query_page(query, consistencies, None, tracing).await?;
+
+        while let Some(paging_state) = rows.paging_state.take() {
+            let mut page = self
+                .query_page(query, consistencies, Some(&paging_state), false)
+                .await?;
+
+            rows.rows.append(&mut page.rows);
+            rows.paging_state = page.paging_state;
+        }
+
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::DuplexStream;
+
+    use super::*;
+
+    fn put_short(body: &mut Vec<u8>, n: u16) {
+        body.extend_from_slice(&n.to_be_bytes());
+    }
+
+    fn put_int(body: &mut Vec<u8>, n: i32) {
+        body.extend_from_slice(&n.to_be_bytes());
+    }
+
+    /// The body of a `ROWS` result with global table specs and `[int]` columns, continued
+    /// after `paging_state` if any
+    fn rows_body(
+        columns: &[&str],
+        rows: &[&[Option<i32>]],
+        paging_state: Option<&[u8]>,
+    ) -> Vec<u8> {
+        let mut body = vec![];
+        put_int(&mut body, ROWS);
+
+        let flags = match paging_state {
+            Some(_) => GLOBAL_TABLES_SPEC | HAS_MORE_PAGES,
+            None => GLOBAL_TABLES_SPEC,
+        };
+        put_int(&mut body, flags);
+        put_int(&mut body, columns.len() as i32);
+
+        if let Some(paging_state) = paging_state {
+            put_bytes(&mut body, paging_state);
+        }
+
+        put_string(&mut body, "ks");
+        put_string(&mut body, "t");
+
+        for column in columns {
+            put_string(&mut body, column);
+            put_short(&mut body, 0x0009);
+        }
+
+        put_int(&mut body, rows.len() as i32);
+
+        for value in rows.iter().flat_map(|row| row.iter()) {
+            match value {
+                Some(value) => put_bytes(&mut body, &value.to_be_bytes()),
+                None => put_int(&mut body, -1),
+            }
+        }
+
+        body
+    }
+
+    #[test]
+    fn notations() {
+        let mut body = vec![];
+        put_short(&mut body, 7);
+        put_int(&mut body, -5);
+        put_string(&mut body, "héllo");
+        put_short(&mut body, 2);
+        put_string(&mut body, "a");
+        put_string(&mut body, "b");
+        put_bytes(&mut body, &[1, 2]);
+        put_int(&mut body, -1);
+        put_short(&mut body, 1);
+        body.push(3);
+
+        let mut reader = Reader { bytes: &body };
+
+        assert_eq!(reader.short().unwrap(), 7);
+        assert_eq!(reader.int().unwrap(), -5);
+        assert_eq!(reader.string().unwrap(), "héllo");
+        assert_eq!(reader.string_list().unwrap(), ["a", "b"]);
+        assert_eq!(reader.bytes().unwrap(), Some(&[1, 2][..]));
+        assert_eq!(reader.bytes().unwrap(), None);
+        assert_eq!(reader.short_bytes().unwrap(), [3]);
+        assert!(reader.bytes.is_empty());
+    }
+
+    #[test]
+    fn truncated_notations() {
+        let mut body = vec![];
+        put_string(&mut body, "long");
+        body.truncate(4);
+
+        let mut reader = Reader { bytes: &body };
+
+        assert_eq!(
+            reader.string().unwrap_err().to_string(),
+            "Truncated response from the server"
+        );
+        assert!(Reader { bytes: &[0, 0, 0] }.int().is_err());
+    }
+
+    #[test]
+    fn column_types() {
+        let mut body = vec![];
+
+        // list<map<text, int>>
+        for id in [0x0020, 0x0021, 0x000D, 0x0009] {
+            put_short(&mut body, id);
+        }
+
+        // A user-defined type of ks.address
+        put_short(&mut body, 0x0030);
+        put_string(&mut body, "ks");
+        put_string(&mut body, "address");
+        put_short(&mut body, 2);
+        put_string(&mut body, "street");
+        put_short(&mut body, 0x000D);
+        put_string(&mut body, "zip");
+        put_short(&mut body, 0x0009);
+
+        // tuple<int, blob>
+        for id in [0x0031, 2, 0x0009, 0x0003] {
+            put_short(&mut body, id);
+        }
+
+        put_short(&mut body, 0x0000);
+        put_string(
+            &mut body,
+            "org.apache.cassandra.db.marshal.DynamicCompositeType",
+        );
+        put_short(&mut body, 0x00FF);
+
+        let mut reader = Reader { bytes: &body };
+
+        assert_eq!(
+            reader.column_type().unwrap(),
+            ColumnType::List(Box::new(ColumnType::Map(
+                Box::new(ColumnType::Varchar),
+                Box::new(ColumnType::Int)
+            )))
+        );
+        assert_eq!(
+            reader.column_type().unwrap(),
+            ColumnType::Udt(vec![
+                ("street".into(), ColumnType::Varchar),
+                ("zip".into(), ColumnType::Int),
+            ])
+        );
+        assert_eq!(
+            reader.column_type().unwrap(),
+            ColumnType::Tuple(vec![ColumnType::Int, ColumnType::Blob])
+        );
+        assert_eq!(reader.column_type().unwrap(), ColumnType::Custom);
+        assert_eq!(reader.column_type().unwrap(), ColumnType::Custom);
+        assert!(reader.bytes.is_empty());
+    }
+
+    #[test]
+    fn rows() {
+        let body = rows_body(
+            &["a", "b"],
+            &[&[Some(1), None], &[Some(2), Some(3)]],
+            Some(b"p"),
+        );
+
+        let mut reader = Reader { bytes: &body };
+        assert_eq!(reader.int().unwrap(), ROWS);
+        let rows = reader.rows().unwrap();
+
+        assert_eq!(rows.columns, ["a", "b"]);
+        assert_eq!(rows.types, [ColumnType::Int, ColumnType::Int]);
+        assert_eq!(
+            rows.rows,
+            [
+                vec![Some(1i32.to_be_bytes().to_vec()), None],
+                vec![
+                    Some(2i32.to_be_bytes().to_vec()),
+                    Some(3i32.to_be_bytes().to_vec())
+                ],
+            ]
+        );
+        assert_eq!(rows.paging_state.as_deref(), Some(&b"p"[..]));
+    }
+
+    #[test]
+    fn rows_with_table_specs_per_column() {
+        let mut body = vec![];
+        put_int(&mut body, 0);
+        put_int(&mut body, 1);
+        put_string(&mut body, "ks");
+        put_string(&mut body, "t");
+        put_string(&mut body, "name");
+        put_short(&mut body, 0x000D);
+        put_int(&mut body, 1);
+        put_bytes(&mut body, b"Ann");
+
+        let rows = Reader { bytes: &body }.rows().unwrap();
+
+        assert_eq!(rows.columns, ["name"]);
+        assert_eq!(rows.rows, [vec![Some(b"Ann".to_vec())]]);
+        assert_eq!(rows.paging_state, None);
+    }
+
+    /// Reads a request frame as a node does, returning its opcode and body
+    async fn read_request(stream: &mut DuplexStream) -> (u8, Vec<u8>) {
+        let mut header = [0; 9];
+        stream.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[0], VERSION);
+
+        let mut body = vec![0; u32::from_be_bytes(header[5..9].try_into().unwrap()) as usize];
+        stream.read_exact(&mut body).await.unwrap();
+
+        (header[4], body)
+    }
+
+    async fn respond(stream: &mut DuplexStream, opcode: u8, body: &[u8]) {
+        let mut frame = vec![RESPONSE | VERSION, 0, 0, 0, opcode];
+        put_bytes(&mut frame, body);
+        stream.write_all(&frame).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn queries_fetch_every_page() {
+        let (client, mut node) = tokio::io::duplex(4096);
+
+        let node = tokio::spawn(async move {
+            assert_eq!(read_request(&mut node).await.0, STARTUP);
+            respond(&mut node, READY, &[]).await;
+
+            let (opcode, body) = read_request(&mut node).await;
+            assert_eq!(opcode, QUERY);
+            assert_eq!(body[body.len() - 5], PAGE_SIZE);
+            respond(
+                &mut node,
+                RESULT,
+                &rows_body(&["a"], &[&[Some(1)]], Some(b"p")),
+            )
+            .await;
+
+            let (_, body) = read_request(&mut node).await;
+            assert!(body.ends_with(&[0, 0, 0, 1, b'p']));
+            respond(&mut node, RESULT, &rows_body(&["a"], &[&[Some(2)]], None)).await;
+
+            let mut error = vec![];
+            put_int(&mut error, 0x2200);
+            put_string(&mut error, "line 1:0 no viable alternative");
+            read_request(&mut node).await;
+            respond(&mut node, ERROR, &error).await;
+        });
+
+        let mut connection = Connection::open(Box::new(client), None).await.unwrap();
+        let rows = connection.query("SELECT a FROM ks.t").await.unwrap();
+
+        assert_eq!(
+            rows.rows,
+            [
+                vec![Some(1i32.to_be_bytes().to_vec())],
+                vec![Some(2i32.to_be_bytes().to_vec())],
+            ]
+        );
+        assert_eq!(
+            connection.query("SELEC").await.unwrap_err().to_string(),
+            "line 1:0 no viable alternative (error code 0x2200)"
+        );
+
+        node.await.unwrap();
+    }
+}
@@ -0,0 +1,421 @@
+//! Decodes the values of result columns, and formats them like cqlsh does.
+
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use anyhow::{Context, Result, bail};
+use serde_json::json;
+
+use super::protocol::ColumnType;
+use crate::{datetime, duration};
+
+/// The value of a result column
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    /// `ascii` and `text`
+    Text(String),
+    /// `tinyint`, `smallint`, `int`, `bigint` and `counter`
+    Integer(i64),
+    /// A `varint` or `decimal`, in decimal notation
+    Number(String),
+    Float(f32),
+    Double(f64),
+    Boolean(bool),
+    Blob(Vec<u8>),
+    /// `uuid` and `timeuuid`
+    Uuid([u8; 16]),
+    /// Milliseconds since the Unix epoch
+    Timestamp(i64),
+    /// Days since the Unix epoch
+    Date(i64),
+    /// Nanoseconds since midnight
+    Time(i64),
+    Inet(IpAddr),
+    Duration {
+        months: i64,
+        days: i64,
+        nanoseconds: i64,
+    },
+    List(Vec<Value>),
+    Set(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Udt(Vec<(String, Value)>),
+    Tuple(Vec<Value>),
+}
+
+/// Reads the elements of a serialized collection, tuple or user-defined type
+struct Elements<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Elements<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            bail!("Truncated value");
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    fn int(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    /// A serialized value, prefixed with its length, where a negative length is `null`
+    fn value(&mut self, column_type: &ColumnType) -> Result<Value> {
+        match usize::try_from(self.int()?) {
+            Ok(len) => Value::decode(column_type, self.take(len)?),
+            Err(_) => Ok(Value::Null),
+        }
+    }
+
+    /// A variable length integer, as used in durations, whose first byte tells how many bytes
+    /// follow with its leading ones
+    fn vint(&mut self) -> Result<i64> {
+        let first = self.take(1)?[0];
+        let extra = first.leading_ones() as usize;
+
+        let mut value = u64::from(first & 0xFF_u8.checked_shr(extra as u32).unwrap_or(0));
+
+        for &byte in self.take(extra)? {
+            value = value << 8 | u64::from(byte);
+        }
+
+        // Zigzag encoded
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+}
+
+/// Formats a two's complement big-endian integer of any length in decimal
+fn varint(bytes: &[u8]) -> String {
+    let negative = bytes.first().is_some_and(|byte| byte & 0x80 != 0);
+    let mut magnitude = bytes.to_vec();
+
+    if negative {
+        for byte in &mut magnitude {
+            *byte = !*byte;
+        }
+
+        for byte in magnitude.iter_mut().rev() {
+            let (sum, carry) = byte.overflowing_add(1);
+            *byte = sum;
+
+            if !carry {
+                break;
+            }
+        }
+    }
+
+    let mut digits = vec![];
+
+    while magnitude.iter().any(|&byte| byte != 0) {
+        let mut remainder = 0u32;
+
+        for byte in &mut magnitude {
+            let value = remainder << 8 | u32::from(*byte);
+            *byte = (value / 10) as u8;
+            remainder = value % 10;
+        }
+
+        digits.push(char::from(b'0' + remainder as u8));
+    }
+
+    if digits.is_empty() {
+        digits.push('0');
+    }
+
+    if negative {
+        digits.push('-');
+    }
+
+    digits.iter().rev().collect()
+}
+
+/// Formats a `decimal`, whose unscaled value is multiplied by 10 to the power of `-scale`
+fn decimal(scale: i32, unscaled: &[u8]) -> String {
+    let digits = varint(unscaled);
+    let (sign, digits) = match digits.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", digits.as_str()),
+    };
+
+    let Ok(scale) = usize::try_from(scale) else {
+        let zeros = "0".repeat(scale.unsigned_abs() as usize);
+        return format!("{sign}{digits}{zeros}");
+    };
+
+    if scale == 0 {
+        return format!("{sign}{digits}");
+    }
+
+    let digits = format!("{digits:0>width$}", width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+
+    format!("{sign}{integer}.{fraction}")
+}
+
+impl Value {
+    /// Decodes the serialized `bytes` of a value of `column_type`
+    pub fn decode(column_type: &ColumnType, bytes: &[u8]) -> Result<Value> {
+        let fixed = |len: usize| -> Result<&[u8]> {
+            match bytes.len() == len {
+                true => Ok(bytes),
+                false => bail!("Invalid {column_type:?} value of {} bytes", bytes.len()),
+            }
+        };
+
+        let value = match column_type {
+            ColumnType::Custom | ColumnType::Blob => Value::Blob(bytes.to_vec()),
+            ColumnType::Ascii | ColumnType::Varchar => {
+                Value::Text(String::from_utf8_lossy(bytes).into_owned())
+            }
+            ColumnType::Bigint | ColumnType::Counter | ColumnType::Timestamp => {
+                let value = i64::from_be_bytes(fixed(8)?.try_into()?);
+
+                match column_type {
+                    ColumnType::Timestamp => Value::Timestamp(value),
+                    _ => Value::Integer(value),
+                }
+            }
+            ColumnType::Int => Value::Integer(i32::from_be_bytes(fixed(4)?.try_into()?).into()),
+            ColumnType::Smallint => {
+                Value::Integer(i16::from_be_bytes(fixed(2)?.try_into()?).into())
+            }
+            ColumnType::Tinyint => Value::Integer(i8::from_be_bytes(fixed(1)?.try_into()?).into()),
+            ColumnType::Boolean => Value::Boolean(fixed(1)?[0] != 0),
+            ColumnType::Double => Value::Double(f64::from_be_bytes(fixed(8)?.try_into()?)),
+            ColumnType::Float => Value::Float(f32::from_be_bytes(fixed(4)?.try_into()?)),
+            ColumnType::Varint => Value::Number(varint(bytes)),
+            ColumnType::Decimal => {
+                let (scale, unscaled) = bytes
+                    .split_first_chunk::<4>()
+                    .context("Truncated decimal")?;
+
+                Value::Number(decimal(i32::from_be_bytes(*scale), unscaled))
+            }
+            ColumnType::Uuid | ColumnType::Timeuuid => Value::Uuid(fixed(16)?.try_into()?),
+            ColumnType::Inet => match bytes.len() {
+                4 => Value::Inet(IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes)?))),
+                16 => Value::Inet(IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes)?))),
+                len => bail!("Invalid inet value of {len} bytes"),
+            },
+            ColumnType::Date => {
+                // Days since the epoch, offset so that the epoch is 2^31
+                Value::Date(i64::from(u32::from_be_bytes(fixed(4)?.try_into()?)) - (1 << 31))
+            }
+            ColumnType::Time => Value::Time(i64::from_be_bytes(fixed(8)?.try_into()?)),
+            ColumnType::Duration => {
+                let mut elements = Elements { bytes };
+
+                Value::Duration {
+                    months: elements.vint()?,
+                    days: elements.vint()?,
+                    nanoseconds: elements.vint()?,
+                }
+            }
+            ColumnType::List(element) | ColumnType::Set(element) => {
+                let mut elements = Elements { bytes };
+                let values = (0..elements.int()?)
+                    .map(|_| elements.value(element))
+                    .collect::<Result<_>>()?;
+
+                match column_type {
+                    ColumnType::Set(_) => Value::Set(values),
+                    _ => Value::List(values),
+                }
+            }
+            ColumnType::Map(key, value) => {
+                let mut elements = Elements { bytes };
+
+                Value::Map(
+                    (0..elements.int()?)
+                        .map(|_| Ok((elements.value(key)?, elements.value(value)?)))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            // Values written before fields were added to the type lack them
+            ColumnType::Udt(fields) => {
+                let mut elements = Elements { bytes };
+
+                Value::Udt(
+                    fields
+                        .iter()
+                        .map(|(name, field_type)| {
+                            let value = match elements.bytes.is_empty() {
+                                true => Value::Null,
+                                false => elements.value(field_type)?,
+                            };
+
+                            Ok((name.clone(), value))
+                        })
+                        .collect::<Result<_>>()?,
+                )
+            }
+            ColumnType::Tuple(types) => {
+                let mut elements = Elements { bytes };
+
+                Value::Tuple(
+                    types
+                        .iter()
+                        .map(|element| elements.value(element))
+                        .collect::<Result<_>>()?,
+                )
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Converts the value to JSON, keeping numbers that don't fit a double, and types JSON
+    /// doesn't have, as strings
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Null => serde_json::Value::Null,
+            Value::Integer(value) => json!(value),
+            Value::Float(value) => json!(value),
+            Value::Double(value) => json!(value),
+            Value::Boolean(value) => json!(value),
+            Value::List(values) | Value::Set(values) | Value::Tuple(values) => {
+                values.iter().map(Value::to_json).collect()
+            }
+            // Maps with text keys become objects, others lists of pairs
+            Value::Map(entries) => {
+                match entries.iter().all(|(key, _)| matches!(key, Value::Text(_))) {
+                    true => entries
+                        .iter()
+                        .map(|(key, value)| (key.to_string(), value.to_json()))
+                        .collect::<serde_json::Map<_, _>>()
+                        .into(),
+                    false => entries
+                        .iter()
+                        .map(|(key, value)| json!([key.to_json(), value.to_json()]))
+                        .collect(),
+                }
+            }
+            Value::Udt(fields) => fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value.to_json()))
+                .collect::<serde_json::Map<_, _>>()
+                .into(),
+            value => json!(value.to_string()),
+        }
+    }
+
+    /// Formats the value as a CQL literal, which is how values nested in collections are shown
+    fn fmt_literal(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Text(text) => write!(f, "'{}'", text.replace('\'', "''")),
+            Value::Timestamp(_) | Value::Date(_) | Value::Time(_) | Value::Inet(_) => {
+                write!(f, "'{self}'")
+            }
+            value => write!(f, "{value}"),
+        }
+    }
+}
+
+/// Writes `values` separated by commas, as literals
+fn write_list<'a>(
+    f: &mut fmt::Formatter<'_>,
+    values: impl IntoIterator<Item = &'a Value>,
+) -> fmt::Result {
+    for (i, value) in values.into_iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+
+        value.fmt_literal(f)?;
+    }
+
+    Ok(())
+}
+
+/// Formats the value like cqlsh: text as is, and nested values as CQL literals
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Text(text) => write!(f, "{text}"),
+            Value::Integer(value) => write!(f, "{value}"),
+            Value::Number(value) => write!(f, "{value}"),
+            Value::Float(value) => write!(f, "{value}"),
+            Value::Double(value) => write!(f, "{value}"),
+            Value::Boolean(value) => write!(f, "{value}"),
+            Value::Blob(bytes) => {
+                write!(f, "0x")?;
+                bytes.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+            }
+            Value::Uuid(bytes) => {
+                for (i, byte) in bytes.iter().enumerate() {
+                    if matches!(i, 4 | 6 | 8 | 10) {
+                        write!(f, "-")?;
+                    }
+
+                    write!(f, "{byte:02x}")?;
+                }
+
+                Ok(())
+            }
+            Value::Timestamp(milliseconds) => {
+                write!(f, "{}", datetime::format_timestamp(*milliseconds))
+            }
+            Value::Date(days) => write!(f, "{}", datetime::format_date(*days)),
+            Value::Time(nanoseconds) => write!(f, "{}", datetime::format_time(*nanoseconds)),
+            Value::Inet(address) => write!(f, "{address}"),
+            Value::Duration {
+                months,
+                days,
+                nanoseconds,
+            } => write!(f, "{}", duration::format(*months, *days, *nanoseconds)),
+            Value::List(values) => {
+                write!(f, "[")?;
+                write_list(f, values)?;
+                write!(f, "]")
+            }
+            Value::Set(values) => {
+                write!(f, "{{")?;
+                write_list(f, values)?;
+                write!(f, "}}")
+            }
+            Value::Tuple(values) => {
+                write!(f, "(")?;
+                write_list(f, values)?;
+                write!(f, ")")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    key.fmt_literal(f)?;
+                    write!(f, ": ")?;
+                    value.fmt_literal(f)?;
+                }
+
+                write!(f, "}}")
+            }
+            Value::Udt(fields) => {
+                write!(f, "{{")?;
+
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{name}: ")?;
+                    value.fmt_literal(f)?;
+                }
+
+                write!(f, "}}")
+            }
+        }
+    }
+}
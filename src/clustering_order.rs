@@ -0,0 +1,125 @@
+//! `WITH CLUSTERING ORDER BY (...)` of `CREATE TABLE` and `CREATE MATERIALIZED VIEW`, which
+//! orders the rows of a partition by the clustering columns. Cassandra only accepts the
+//! clustering columns there, in their order in the primary key and from the first, e.g.
+//! `CLUSTERING ORDER BY (day DESC, time DESC)` for `PRIMARY KEY ((sensor), day, time)`.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    lexer::Token,
+    materialized_view,
+    schema::parse_create_table,
+    statement::{Statement, StatementKind, matching_bracket, split_commas},
+};
+
+/// A `CLUSTERING ORDER BY (...)`, as far as it's been typed
+#[derive(Debug)]
+pub struct ClusteringOrder<'a> {
+    /// The columns, each followed by its order
+    pub columns: Vec<&'a [Token<'a>]>,
+    /// Whether its parentheses are closed
+    pub closed: bool,
+}
+
+/// Parses the `CLUSTERING ORDER BY (...)` among the options of `statement`, if any
+pub fn parse<'a>(statement: &Statement<'a>) -> Option<ClusteringOrder<'a>> {
+    let tokens = statement.tokens;
+
+    let open = (0..tokens.len()).find(|&i| {
+        matches!(
+            &tokens[i..],
+            [clustering, order, by, open, ..]
+                if clustering.is_keyword("CLUSTERING")
+                    && order.is_keyword("ORDER")
+                    && by.is_keyword("BY")
+                    && open.is_punctuation("(")
+        )
+    })? + 3;
+
+    let close = matching_bracket(tokens, open);
+
+    Some(ClusteringOrder {
+        columns: split_commas(&tokens[open + 1..close.unwrap_or(tokens.len())]),
+        closed: close.is_some(),
+    })
+}
+
+/// The partition key and clustering columns of the table or view `statement` creates
+pub fn primary_key(statement: &Statement) -> Option<(Vec<String>, Vec<String>)> {
+    match statement.kind() {
+        StatementKind::CreateTable => {
+            let table = parse_create_table(statement, &None)?;
+            Some((table.partition_key, table.clustering_key))
+        }
+        StatementKind::CreateMaterializedView => {
+            let view = materialized_view::parse(statement)?;
+            let names = |tokens: &[&Token]| tokens.iter().filter_map(|t| t.identifier()).collect();
+
+            Some((names(&view.partition_key), names(&view.clustering_key)))
+        }
+        _ => None,
+    }
+}
+
+/// Checks the `CLUSTERING ORDER BY` of a table or view, reporting columns outside the
+/// clustering columns, and clustering columns out of their order or skipped
+pub fn check(statement: &Statement) -> Vec<Problem> {
+    let (Some(order), Some((partition_key, clustering_key))) =
+        (parse(statement), primary_key(statement))
+    else {
+        return vec![];
+    };
+
+    // Without a primary key, the columns can't be told apart
+    if partition_key.is_empty() {
+        return vec![];
+    }
+
+    let mut problems = vec![];
+    let mut next = 0;
+
+    for definition in order.columns {
+        let Some(token) = definition.first() else {
+            continue;
+        };
+
+        let Some(column) = token.identifier() else {
+            continue;
+        };
+
+        let error = |message: String| Problem {
+            span: token.start..token.end,
+            severity: DiagnosticSeverity::ERROR,
+            message,
+            lint: None,
+        };
+
+        let Some(position) = clustering_key.iter().position(|name| *name == column) else {
+            problems.push(error(match partition_key.contains(&column) {
+                true => format!(
+                    "Cannot order by `{column}`: it's part of the partition key, and CLUSTERING ORDER BY only accepts clustering columns"
+                ),
+                false => format!(
+                    "Cannot order by `{column}`: CLUSTERING ORDER BY only accepts clustering columns"
+                ),
+            }));
+            continue;
+        };
+
+        if position < next {
+            problems.push(error(format!(
+                "Cannot order by `{column}` here: CLUSTERING ORDER BY columns must follow the order of the clustering columns"
+            )));
+        } else if position > next {
+            problems.push(error(format!(
+                "Cannot order by `{column}` without ordering by `{}` first",
+                clustering_key[next]
+            )));
+        }
+
+        next = next.max(position + 1);
+    }
+
+    problems
+}
@@ -0,0 +1,532 @@
+//! Code actions offered on the statement under the cursor.
+
+use std::collections::HashMap;
+
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Diagnostic, NumberOrString, Range, TextEdit,
+    Url, WorkspaceEdit,
+};
+
+use crate::{
+    bind_markers,
+    config::KeywordCase,
+    directives::{self, DISABLE_NEXT_LINE, PREFIX},
+    document::{PositionEncodingKind, TextDocument},
+    fixtures, frozen, generate, keyword_case,
+    lexer::{self, TokenKind, quote_identifier},
+    materialized_view, reserved,
+    schema::{self, Schema, Table},
+    statement::{self, Dialect, Statement, StatementKind},
+    target::Target,
+};
+
+/// Builds an edit of the document at `uri` replacing `span` with `new_text`
+fn edit(
+    doc: &TextDocument,
+    uri: &Url,
+    span: std::ops::Range<usize>,
+    new_text: String,
+    position_encoding: PositionEncodingKind,
+) -> WorkspaceEdit {
+    WorkspaceEdit {
+        changes: Some(HashMap::from([(
+            uri.clone(),
+            vec![TextEdit {
+                range: doc.byte_range_to_range(span, position_encoding),
+                new_text,
+            }],
+        )])),
+        ..Default::default()
+    }
+}
+
+/// "Expand * to columns" for `SELECT *` and `SELECT JSON *`, including the select of a
+/// materialized view, replacing only the asterisk so the rest of the statement is untouched
+fn expand_star(
+    doc: &TextDocument,
+    uri: &Url,
+    statement: &Statement,
+    table: &Table,
+    position_encoding: PositionEncodingKind,
+) -> Option<CodeActionOrCommand> {
+    let select = statement.find_keyword("SELECT")?;
+    let tokens = statement.tokens;
+
+    let star = match tokens.get(select + 1)? {
+        json if json.is_keyword("JSON") => tokens.get(select + 2)?,
+        star => star,
+    };
+
+    if !star.is_punctuation("*") || table.columns.is_empty() {
+        return None;
+    }
+
+    let columns = table
+        .columns
+        .iter()
+        .map(|column| quote_identifier(&column.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: "Expand * to columns".to_string(),
+        kind: Some(CodeActionKind::REFACTOR_REWRITE),
+        edit: Some(edit(
+            doc,
+            uri,
+            star.start..star.end,
+            columns,
+            position_encoding,
+        )),
+        ..Default::default()
+    }))
+}
+
+/// "Quote reserved keyword" for each reserved keyword used unquoted as an identifier in `range`
+fn quote_reserved(
+    doc: &TextDocument,
+    uri: &Url,
+    statement: &Statement,
+    table: Option<&Table>,
+    range: std::ops::Range<usize>,
+    position_encoding: PositionEncodingKind,
+) -> Vec<CodeActionOrCommand> {
+    reserved::identifiers(statement, table)
+        .into_iter()
+        .filter(|token| token.start <= range.end && range.start <= token.end)
+        .map(|token| {
+            let quoted = reserved::quoted(token);
+
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Quote reserved keyword `{}` as {quoted}", token.text),
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(edit(
+                    doc,
+                    uri,
+                    token.start..token.end,
+                    quoted,
+                    position_encoding,
+                )),
+                is_preferred: Some(true),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// "Wrap in `frozen<>`" for each type in `range` that must be frozen where it's used
+fn wrap_frozen(
+    doc: &TextDocument,
+    uri: &Url,
+    statement: &Statement,
+    target: Option<Target>,
+    range: std::ops::Range<usize>,
+    position_encoding: PositionEncodingKind,
+) -> Vec<CodeActionOrCommand> {
+    frozen::fixes(statement, target)
+        .into_iter()
+        .filter(|span| span.start <= range.end && range.start <= span.end)
+        .map(|span| {
+            let data_type = &doc.text()[span.clone()];
+
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Wrap `{data_type}` in `frozen<>`"),
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(edit(
+                    doc,
+                    uri,
+                    span.clone(),
+                    format!("frozen<{data_type}>"),
+                    position_encoding,
+                )),
+                is_preferred: Some(true),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// "Insert random uuid literal", "Insert random timeuuid literal" and "Convert text to hex blob
+/// literal" on the value at `offset`, a literal, bind marker or call such as `uuid()`, for
+/// writing test data by hand. The actions offered depend on the type of the column the value is
+/// for, all of them being offered when it's unknown.
+fn literal_actions(
+    doc: &TextDocument,
+    uri: &Url,
+    statement: &Statement,
+    table: Option<&Table>,
+    offset: usize,
+    position_encoding: PositionEncodingKind,
+) -> Vec<CodeActionOrCommand> {
+    let tokens = statement.tokens;
+
+    let is_dml = matches!(
+        statement.kind(),
+        StatementKind::Select
+            | StatementKind::Insert
+            | StatementKind::Update
+            | StatementKind::Delete
+    );
+
+    let Some(i) = tokens
+        .iter()
+        .position(|token| is_dml && token.start <= offset && offset <= token.end)
+    else {
+        return vec![];
+    };
+
+    let token = &tokens[i];
+
+    // `uuid()` and `now()` are replaced as a whole
+    let call = token.kind == TokenKind::Word
+        && tokens.get(i + 1).is_some_and(|t| t.is_punctuation("("))
+        && tokens.get(i + 2).is_some_and(|t| t.is_punctuation(")"));
+
+    let span = match token.kind {
+        _ if call => token.start..tokens[i + 2].end,
+        TokenKind::String
+        | TokenKind::Number
+        | TokenKind::Hex
+        | TokenKind::Uuid
+        | TokenKind::BindMarker
+        | TokenKind::NamedBindMarker => token.start..token.end,
+        _ => return vec![],
+    };
+
+    let data_type = bind_markers::value_type(statement, i, table);
+    let is = |name: &str| data_type.as_ref().is_none_or(|t| t.is_native(name));
+
+    let mut literals = vec![];
+
+    if is("uuid") {
+        literals.push(("Insert random uuid literal", fixtures::uuid()));
+    }
+
+    if is("timeuuid") {
+        literals.push(("Insert random timeuuid literal", fixtures::timeuuid()));
+    }
+
+    if is("blob")
+        && token.kind == TokenKind::String
+        && let Some(text) = token.string_value()
+    {
+        literals.push((
+            "Convert text to hex blob literal",
+            fixtures::hex_blob(&text),
+        ));
+    }
+
+    literals
+        .into_iter()
+        .map(|(title, literal)| {
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: title.to_string(),
+                kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                edit: Some(edit(doc, uri, span.clone(), literal, position_encoding)),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Returns the code actions available for the statement at the start of `range`, `target`
+/// deciding which types must be frozen
+pub fn code_actions(
+    doc: &TextDocument,
+    uri: &Url,
+    range: Range,
+    dialect: Dialect,
+    target: Option<Target>,
+    position_encoding: PositionEncodingKind,
+) -> Vec<CodeActionOrCommand> {
+    let Ok(offset) = doc.position_to_byte_idx(&range.start, position_encoding) else {
+        return vec![];
+    };
+
+    let text = doc.text();
+    let tokens = lexer::tokenize(text);
+    let statements = statement::split(&tokens, dialect);
+    let schema = Schema::from_statements(&statements);
+
+    let Some(i) = statement::statement_at(&statements, offset) else {
+        return vec![];
+    };
+
+    let statement = &statements[i];
+    let keyspace = schema::keyspace_at(&statements, i);
+
+    let end = doc
+        .position_to_byte_idx(&range.end, position_encoding)
+        .unwrap_or(offset);
+    let mut actions = quote_reserved(
+        doc,
+        uri,
+        statement,
+        schema.statement_table(statement, keyspace.as_deref()),
+        offset..end,
+        position_encoding,
+    );
+    actions.extend(literal_actions(
+        doc,
+        uri,
+        statement,
+        schema.statement_table(statement, keyspace.as_deref()),
+        offset,
+        position_encoding,
+    ));
+    actions.extend(wrap_frozen(
+        doc,
+        uri,
+        statement,
+        target,
+        offset..end,
+        position_encoding,
+    ));
+
+    if statement::missing_semicolon(&statements, i) {
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Insert missing `;`".to_string(),
+            kind: Some(CodeActionKind::QUICKFIX),
+            edit: Some(edit(
+                doc,
+                uri,
+                statement.end..statement.end,
+                ";".into(),
+                position_encoding,
+            )),
+            is_preferred: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    // A view selects from its base table
+    let selected = match statement.kind() {
+        StatementKind::CreateMaterializedView => materialized_view::parse(statement)
+            .and_then(|view| view.base_table(&schema, keyspace.as_deref())),
+        _ => schema.statement_table(statement, keyspace.as_deref()),
+    };
+
+    if let Some(table) = selected
+        && let Some(action) = expand_star(doc, uri, statement, table, position_encoding)
+    {
+        actions.push(action);
+    }
+
+    let Some(table) = schema.statement_table(statement, keyspace.as_deref()) else {
+        return actions;
+    };
+
+    // Generated statements go right after the statement the action was invoked on
+    let insert_after = |generated: String| {
+        let separator = match statement.terminated {
+            true => "\n\n",
+            false => ";\n\n",
+        };
+
+        edit(
+            doc,
+            uri,
+            statement.end..statement.end,
+            format!("{separator}{generated}"),
+            position_encoding,
+        )
+    };
+
+    if let Some(insert) = generate::insert_statement(table, false) {
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Generate INSERT for {}", generate::qualified_name(table)),
+            kind: Some(CodeActionKind::REFACTOR),
+            edit: Some(insert_after(insert)),
+            ..Default::default()
+        }));
+    }
+
+    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+        title: format!("Generate SELECT for {}", generate::qualified_name(table)),
+        kind: Some(CodeActionKind::REFACTOR),
+        edit: Some(insert_after(generate::select_statement(table, false))),
+        ..Default::default()
+    }));
+
+    actions
+}
+
+/// "Suppress <lint> for this line" and "Suppress <lint> for this file" for each lint among
+/// `diagnostics`, inserting a directive comment before the line or at the top of the file. The
+/// lint is added to a `disable-next-line` comment already before the line.
+pub fn suppressions(
+    doc: &TextDocument,
+    uri: &Url,
+    diagnostics: &[Diagnostic],
+    position_encoding: PositionEncodingKind,
+) -> Vec<CodeActionOrCommand> {
+    let mut actions = vec![];
+
+    for diagnostic in diagnostics {
+        let Some(NumberOrString::String(lint)) = &diagnostic.code else {
+            continue;
+        };
+
+        let line = diagnostic.range.start.line as usize;
+
+        if diagnostic.source.as_deref() != Some("cql-lsp") || line >= doc.rope.len_lines() {
+            continue;
+        }
+
+        let start = doc.rope.line_to_byte(line);
+        let previous = line
+            .checked_sub(1)
+            .map(|previous| doc.rope.line(previous).to_string());
+        let previous_lints = previous
+            .as_deref()
+            .and_then(|previous| directives::next_line_lints(previous.trim()));
+
+        let line_edit = match previous_lints {
+            // The line is already suppressed entirely
+            Some(lints) if lints.is_empty() || lints.contains(lint) => None,
+            Some(_) => {
+                let previous = previous.unwrap_or_default();
+                let end = start - (previous.len() - previous.trim_end().len());
+
+                Some(edit(
+                    doc,
+                    uri,
+                    end..end,
+                    format!(", {lint}"),
+                    position_encoding,
+                ))
+            }
+            None => {
+                let text = doc.rope.line(line).to_string();
+                let indent = &text[..text.len() - text.trim_start().len()];
+
+                Some(edit(
+                    doc,
+                    uri,
+                    start..start,
+                    format!("{indent}-- {PREFIX}{DISABLE_NEXT_LINE} {lint}\n"),
+                    position_encoding,
+                ))
+            }
+        };
+
+        let suppress = |title: String, edit: WorkspaceEdit| {
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(edit),
+                ..Default::default()
+            })
+        };
+
+        if let Some(line_edit) = line_edit {
+            actions.push(suppress(
+                format!("Suppress {lint} for this line"),
+                line_edit,
+            ));
+        }
+
+        actions.push(suppress(
+            format!("Suppress {lint} for this file"),
+            edit(
+                doc,
+                uri,
+                0..0,
+                format!("-- {PREFIX} disable={lint}\n"),
+                position_encoding,
+            ),
+        ));
+    }
+
+    actions
+}
+
+/// "Uppercase keywords in statement" (or lowercase, as `case` has them) for each statement
+/// with keywords of another case among `diagnostics`, rewriting all its keywords at once
+pub fn keyword_case_fixes(
+    doc: &TextDocument,
+    uri: &Url,
+    diagnostics: &[Diagnostic],
+    case: KeywordCase,
+    dialect: Dialect,
+    position_encoding: PositionEncodingKind,
+) -> Vec<CodeActionOrCommand> {
+    let flagged = diagnostics
+        .iter()
+        .filter(|diagnostic| {
+            diagnostic.source.as_deref() == Some("cql-lsp")
+                && diagnostic.code == Some(NumberOrString::String("keyword-case".into()))
+        })
+        .collect::<Vec<_>>();
+
+    if flagged.is_empty() {
+        return vec![];
+    }
+
+    let text = doc.text();
+    let tokens = lexer::tokenize(text);
+    let statements = statement::split(&tokens, dialect);
+    let schema = Schema::from_statements(&statements);
+
+    let title = match case {
+        KeywordCase::Lower => "Lowercase keywords in statement",
+        _ => "Uppercase keywords in statement",
+    };
+
+    let mut fixed = vec![];
+
+    flagged
+        .iter()
+        .filter_map(|diagnostic| {
+            let offset = doc
+                .position_to_byte_idx(&diagnostic.range.start, position_encoding)
+                .ok()?;
+            let i = statement::statement_at(&statements, offset)?;
+
+            // One action per statement, whichever of its keywords it's asked for
+            if fixed.contains(&i) {
+                return None;
+            }
+
+            fixed.push(i);
+
+            let statement = &statements[i];
+            let keyspace = schema::keyspace_at(&statements, i);
+            let table = schema.statement_table(statement, keyspace.as_deref());
+
+            let edits = keyword_case::fixes(statement, table, case)
+                .into_iter()
+                .map(|(token, new_text)| TextEdit {
+                    range: doc.byte_range_to_range(token.start..token.end, position_encoding),
+                    new_text,
+                })
+                .collect::<Vec<_>>();
+
+            (!edits.is_empty()).then(|| {
+                CodeActionOrCommand::CodeAction(CodeAction {
+                    title: title.to_string(),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(
+                        flagged
+                            .iter()
+                            .filter(|diagnostic| {
+                                doc.position_to_byte_idx(&diagnostic.range.start, position_encoding)
+                                    .is_ok_and(|offset| {
+                                        statement.start <= offset && offset < statement.end
+                                    })
+                            })
+                            .map(|diagnostic| (*diagnostic).clone())
+                            .collect(),
+                    ),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(uri.clone(), edits)])),
+                        ..Default::default()
+                    }),
+                    is_preferred: Some(true),
+                    ..Default::default()
+                })
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,86 @@
+//! Code lenses above DML statements naming the table they touch, resolved from the `USE`
+//! before them and the schema, e.g. `→ analytics.events (partition key: tenant_id, day)`, so
+//! readers of long scripts needn't trace which keyspace is in use.
+
+use tower_lsp::lsp_types::{CodeLens, Command};
+
+use crate::{
+    document::{PositionEncodingKind, TextDocument},
+    lexer,
+    schema::{self, Schema},
+    statement::{self, Dialect, Statement, StatementKind},
+};
+
+/// The title of the lens of `statement`, the `index`th of `statements`
+fn title(
+    statements: &[Statement],
+    index: usize,
+    statement: &Statement,
+    schema: &Schema,
+) -> Option<String> {
+    if !matches!(
+        statement.kind(),
+        StatementKind::Select
+            | StatementKind::Insert
+            | StatementKind::Update
+            | StatementKind::Delete
+    ) {
+        return None;
+    }
+
+    let target = statement.target_table()?;
+    let keyspace = target
+        .keyspace
+        .clone()
+        .or_else(|| schema::keyspace_at(statements, index))
+        .or_else(|| schema.used_keyspace.clone());
+
+    let Some(table) = schema.statement_table(statement, keyspace.as_deref()) else {
+        let name = match &keyspace {
+            Some(keyspace) => format!("{keyspace}.{}", target.name),
+            None => format!("{} (no keyspace in use)", target.name),
+        };
+
+        return Some(format!("→ {name}, not in the schema"));
+    };
+
+    Some(format!(
+        "→ {}.{} (partition key: {})",
+        table.keyspace,
+        table.name,
+        table.partition_key.join(", ")
+    ))
+}
+
+/// The lenses of the DML statements of `doc`, checked against `schema`, each on the first line
+/// of its statement
+pub fn code_lenses(
+    doc: &TextDocument,
+    schema: Schema,
+    dialect: Dialect,
+    position_encoding: PositionEncodingKind,
+) -> Vec<CodeLens> {
+    let tokens = lexer::tokenize(doc.text());
+    let statements = statement::split(&tokens, dialect);
+    let schema = schema.with_statements(&statements);
+
+    statements
+        .iter()
+        .enumerate()
+        .filter_map(|(i, statement)| {
+            let title = title(&statements, i, statement, &schema)?;
+            let first = statement.tokens.first()?;
+
+            Some(CodeLens {
+                range: doc.byte_range_to_range(first.start..first.end, position_encoding),
+                // Without a command to run, clients only show the title
+                command: Some(Command {
+                    title,
+                    command: String::new(),
+                    arguments: None,
+                }),
+                data: None,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,822 @@
+mod alter_table;
+mod clustering_order;
+mod consistency;
+mod cqlsh;
+mod datacenter;
+mod dml;
+mod function;
+mod grant;
+mod index;
+mod materialized_view;
+mod role;
+mod table;
+
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionItemLabelDetails, CompletionTextEdit,
+    Documentation, InsertTextFormat, MarkupContent, MarkupKind, Position, Range, TextEdit,
+};
+
+use crate::{
+    bind_markers,
+    config::{Config, KeywordCase},
+    cql_type::NATIVE_TYPES,
+    document::{PositionEncodingKind, TextDocument},
+    keywords,
+    lexer::{self, Token, TokenKind},
+    schema::{self, NO_KEYSPACE, Schema, Table},
+    statement::{self, Statement},
+    system_keyspaces,
+    target::{Database, Target},
+};
+
+pub use crate::keywords::Keyword;
+
+/// The characters whose typing triggers completion: blanks and `.` before the next word or
+/// name, `:` before a named bind marker, `(`, `,` and `=` where a column, argument or value
+/// follows, and `'` where a string value is completed
+pub const TRIGGER_CHARACTERS: &[&str] = &[" ", "\n", ".", ":", "(", ",", "=", "'"];
+
+/// The trigger characters after which only the completions of the context they open are
+/// offered, rather than the next keywords, which follow blanks
+const PUNCTUATION_TRIGGERS: &[&str] = &["(", ",", "=", "'"];
+
+/// Returns the byte offsets into `line_prefix` at which a keyword the user is typing could
+/// start, nearest first. Only the trailing run of words (letters, digits, underscores and
+/// blanks) is considered, so `SELECT * FROM t WHERE na` yields the offsets of `na`, `WHERE na`
+/// and `t WHERE na`.
+fn candidate_starts(line_prefix: &str) -> Vec<usize> {
+    let bytes = line_prefix.as_bytes();
+    let mut starts = vec![];
+
+    // An empty prefix (cursor right after a blank or at the start of the line) can match
+    // anything
+    if bytes.last().is_none_or(|b| b.is_ascii_whitespace()) {
+        starts.push(bytes.len());
+    }
+
+    for i in (0..bytes.len()).rev() {
+        let b = bytes[i];
+
+        if !(b.is_ascii_alphanumeric() || b == b'_' || b == b' ' || b == b'\t') {
+            break;
+        }
+
+        if !b.is_ascii_whitespace() && (i == 0 || bytes[i - 1].is_ascii_whitespace()) {
+            starts.push(i);
+        }
+    }
+
+    starts
+}
+
+/// Case-insensitively checks whether `typed` is a prefix of `label`, treating any run of
+/// blanks in `typed` as a single space
+fn matches_prefix(label: &str, typed: &str) -> bool {
+    let mut normalized = typed.split_ascii_whitespace().collect::<Vec<_>>().join(" ");
+
+    if typed.ends_with([' ', '\t']) && !normalized.is_empty() {
+        normalized.push(' ');
+    }
+
+    label
+        .get(..normalized.len())
+        .is_some_and(|head| head.eq_ignore_ascii_case(&normalized))
+}
+
+/// The statements most commonly written, most common first, offered before the others at the
+/// start of a statement
+const COMMON_STATEMENTS: &[&str] = &[
+    "SELECT",
+    "INSERT INTO",
+    "UPDATE",
+    "DELETE",
+    "CREATE TABLE",
+    "ALTER TABLE",
+    "CREATE KEYSPACE",
+    "USE",
+    "CREATE TYPE",
+    "CREATE INDEX",
+    "DROP TABLE",
+    "BEGIN BATCH",
+    "APPLY BATCH",
+    "TRUNCATE",
+];
+
+/// The `sort_text` marking items that matched the typed word within their label rather than at
+/// its start, replaced by `rank` with the item's final position
+const INNER_WORD_MATCH: &str = "~";
+
+/// Finds the word of `label` after its first that `typed` is a prefix of, returning the label
+/// from that word on, so that `filt` finds `ALLOW FILTERING`
+fn matching_word<'a>(label: &'a str, typed: &str) -> Option<&'a str> {
+    if typed.is_empty() {
+        return None;
+    }
+
+    label
+        .match_indices(' ')
+        .map(|(i, _)| &label[i + 1..])
+        .find(|rest| matches_prefix(rest, typed))
+}
+
+/// Everything completion providers need to know about where completion was requested
+pub struct CompletionContext<'a> {
+    /// The text between the start of the line and the cursor
+    pub line_prefix: &'a str,
+    pub position: Position,
+    pub keyword_case: KeywordCase,
+    /// The word being typed at the cursor, possibly empty
+    pub typed: &'a str,
+    /// The statement under the cursor, limited to the tokens before the word being typed
+    pub statement: Statement<'a>,
+    /// The whole statement under the cursor, including what follows the cursor
+    pub full_statement: Statement<'a>,
+    pub schema: &'a Schema,
+    /// The keyspace selected with `USE` before the statement
+    pub keyspace: Option<String>,
+    /// Whether the document is read as a cqlsh script
+    pub cqlsh: bool,
+    /// The database and version the document is meant for, if known
+    pub target: Option<Target>,
+}
+
+impl CompletionContext<'_> {
+    pub fn tokens(&self) -> &[Token<'_>] {
+        self.statement.tokens
+    }
+
+    /// Whether the target is `database`, whose extensions are then offered
+    pub fn targets(&self, database: Database) -> bool {
+        self.target
+            .is_some_and(|target| target.database == database)
+    }
+
+    /// Builds the items for `keywords` matching what the user has typed on the current line.
+    ///
+    /// Keywords are matched case-insensitively and may span several words, so typing
+    /// `create ta` offers `CREATE TABLE`, replacing the whole typed text with the keyword in
+    /// the configured case. The word being typed may also match any later word of a keyword,
+    /// as `filt` does `ALLOW FILTERING`; these items are ranked after the others.
+    pub fn keywords(&self, keywords: &[Keyword]) -> Vec<CompletionItem> {
+        let starts = candidate_starts(self.line_prefix);
+
+        keywords
+            .iter()
+            .filter_map(|keyword| {
+                // Prefer the longest typed text that still matches, so `CREATE ` followed by
+                // the completion replaces `CREATE ` rather than duplicating it
+                let start = starts
+                    .iter()
+                    .rev()
+                    .find(|&&start| matches_prefix(keyword.label, &self.line_prefix[start..]));
+
+                let (start, filter_text) = match start {
+                    Some(&start) => (start, None),
+                    None => (
+                        self.line_prefix.len() - self.typed.len(),
+                        Some(matching_word(keyword.label, self.typed)?),
+                    ),
+                };
+
+                let typed = &self.line_prefix[start..];
+                let new_text = self.keyword_case.apply(keyword.label, typed);
+
+                Some(CompletionItem {
+                    label: keyword.label.into(),
+                    kind: Some(CompletionItemKind::KEYWORD),
+                    deprecated: keyword.deprecated.then_some(true),
+                    documentation: Some(Documentation::MarkupContent(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: keyword.markdown(),
+                    })),
+                    filter_text: Some(match filter_text {
+                        Some(rest) => self.keyword_case.apply(rest, typed),
+                        None => new_text.clone(),
+                    }),
+                    sort_text: filter_text.map(|_| INNER_WORD_MATCH.into()),
+                    // Spaces end single words, while they're typed within multi-word keywords
+                    commit_characters: (!keyword.label.contains(' ')).then(|| vec![" ".into()]),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                        range: Range {
+                            start: Position {
+                                line: self.position.line,
+                                // The typed text is ASCII so its length in bytes is its length
+                                // in any encoding
+                                character: self.position.character - typed.len() as u32,
+                            },
+                            end: self.position,
+                        },
+                        new_text,
+                    })),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    /// Builds items for schema object names (keyspaces, tables, roles, ...) matching the word
+    /// being typed, quoting the names where CQL requires it. Each name may be qualified, e.g.
+    /// with the keyspace of a table.
+    pub fn names(
+        &self,
+        names: impl IntoIterator<Item = (Option<String>, String)>,
+        kind: CompletionItemKind,
+        detail: &str,
+    ) -> Vec<CompletionItem> {
+        let range = Range {
+            start: Position {
+                line: self.position.line,
+                character: self.position.character - self.typed.encode_utf16().count() as u32,
+            },
+            end: self.position,
+        };
+
+        names
+            .into_iter()
+            .map(|(qualifier, name)| match qualifier {
+                Some(qualifier) => (
+                    format!("{qualifier}.{name}"),
+                    format!(
+                        "{}.{}",
+                        lexer::quote_identifier(&qualifier),
+                        lexer::quote_identifier(&name)
+                    ),
+                ),
+                None => (name.clone(), lexer::quote_identifier(&name)),
+            })
+            .filter_map(|(label, new_text)| {
+                let starts_with = |text: &str| {
+                    text.get(..self.typed.len())
+                        .is_some_and(|head| head.eq_ignore_ascii_case(self.typed))
+                };
+
+                // Qualified names also match on the name, so `us` finds `ks.users`
+                let filter_text = match label.split_once('.') {
+                    _ if starts_with(&label) => None,
+                    Some((_, name)) if starts_with(name) => Some(name.to_string()),
+                    _ => return None,
+                };
+
+                Some(CompletionItem {
+                    label,
+                    kind: Some(kind),
+                    detail: Some(detail.to_string()),
+                    label_details: Some(CompletionItemLabelDetails {
+                        detail: None,
+                        description: Some(detail.to_string()),
+                    }),
+                    sort_text: filter_text.as_ref().map(|_| INNER_WORD_MATCH.into()),
+                    filter_text,
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit { range, new_text })),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    /// Builds items for literal values (string options, enumerated values, ...) matching the
+    /// word being typed. Labels are inserted as-is.
+    pub fn literals(&self, literals: &[Keyword]) -> Vec<CompletionItem> {
+        let range = Range {
+            start: Position {
+                line: self.position.line,
+                character: self.position.character - self.typed.encode_utf16().count() as u32,
+            },
+            end: self.position,
+        };
+
+        literals
+            .iter()
+            .filter(|literal| {
+                let bare = literal.label.trim_start_matches('\'');
+
+                bare.get(..self.typed.len())
+                    .is_some_and(|head| head.eq_ignore_ascii_case(self.typed))
+            })
+            .map(|literal| CompletionItem {
+                label: literal.label.into(),
+                kind: Some(CompletionItemKind::VALUE),
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: literal.markdown(),
+                })),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: literal.label.into(),
+                })),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Keyspaces defined in the document
+    pub fn keyspace_names(&self) -> Vec<CompletionItem> {
+        let names = self
+            .schema
+            .keyspaces
+            .keys()
+            .filter(|name| name.as_str() != NO_KEYSPACE)
+            .map(|name| (None, name.clone()));
+
+        self.names(names, CompletionItemKind::MODULE, "keyspace")
+    }
+
+    /// Tables in `keyspace`, or all tables when no keyspace was typed. Tables outside the
+    /// keyspace selected with `USE` are offered qualified with their keyspace, and those of the
+    /// system keyspaces only once it's typed, virtual tables only for targets having them.
+    pub fn table_names(&self, keyspace: Option<String>) -> Vec<CompletionItem> {
+        let mut items = vec![];
+
+        if let Some(system) = keyspace
+            .as_deref()
+            .filter(|keyspace| !self.schema.keyspaces.contains_key(*keyspace))
+            .filter(|keyspace| {
+                !system_keyspaces::is_virtual(keyspace)
+                    || self.target.is_none_or(|target| target.has_virtual_tables())
+            })
+            .and_then(system_keyspaces::keyspace)
+        {
+            let names = system.tables.keys().map(|table| (None, table.clone()));
+
+            items.extend(self.names(names, CompletionItemKind::CLASS, "system table"));
+        }
+
+        for (name, tables) in &self.schema.keyspaces {
+            if keyspace.as_ref().is_some_and(|keyspace| keyspace != name) {
+                continue;
+            }
+
+            let qualify =
+                keyspace.is_none() && name != NO_KEYSPACE && self.keyspace.as_ref() != Some(name);
+
+            let names = tables
+                .tables
+                .keys()
+                .map(|table| (qualify.then(|| name.clone()), table.clone()));
+
+            items.extend(self.names(names, CompletionItemKind::CLASS, "table"));
+        }
+
+        items
+    }
+
+    /// Native and collection type names
+    pub fn type_names(&self) -> Vec<CompletionItem> {
+        let names = NATIVE_TYPES
+            .iter()
+            .chain(&["list", "set", "map", "tuple", "frozen", "vector"])
+            .map(|name| (None, name.to_string()));
+
+        self.names(names, CompletionItemKind::TYPE_PARAMETER, "type")
+    }
+
+    /// Functions defined in `keyspace`, or in the keyspace selected with `USE` when `None`
+    pub fn function_names(&self, keyspace: Option<&str>) -> Vec<CompletionItem> {
+        let keyspace = keyspace.or(self.keyspace.as_deref()).unwrap_or(NO_KEYSPACE);
+
+        let Some(keyspace) = self.schema.keyspaces.get(keyspace) else {
+            return vec![];
+        };
+
+        let mut names = keyspace
+            .functions
+            .iter()
+            .map(|function| function.name.clone())
+            .collect::<Vec<_>>();
+        names.dedup();
+
+        self.names(
+            names.into_iter().map(|name| (None, name)),
+            CompletionItemKind::FUNCTION,
+            "function",
+        )
+    }
+
+    /// Roles defined in the document
+    pub fn role_names(&self) -> Vec<CompletionItem> {
+        let roles = self.schema.roles.iter().map(|role| (None, role.clone()));
+
+        self.names(roles, CompletionItemKind::VALUE, "role")
+    }
+
+    /// The datacenters of the connected cluster but those `named` already, as string literals
+    pub fn datacenter_names(&self, named: &[String]) -> Vec<CompletionItem> {
+        let range = Range {
+            start: Position {
+                line: self.position.line,
+                character: self.position.character - self.typed.encode_utf16().count() as u32,
+            },
+            end: self.position,
+        };
+
+        self.schema
+            .datacenters
+            .iter()
+            .filter(|name| !named.contains(name))
+            .filter(|name| {
+                name.get(..self.typed.len())
+                    .is_some_and(|head| head.eq_ignore_ascii_case(self.typed))
+            })
+            .map(|name| {
+                let literal = format!("'{}'", name.replace('\'', "''"));
+
+                CompletionItem {
+                    label: literal.clone(),
+                    kind: Some(CompletionItemKind::VALUE),
+                    detail: Some("datacenter".into()),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                        range,
+                        new_text: literal,
+                    })),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// The columns of `table`, detailed with their type
+    pub fn column_names(&self, table: &Table) -> Vec<CompletionItem> {
+        table
+            .columns
+            .iter()
+            .flat_map(|column| {
+                self.names(
+                    [(None, column.name.clone())],
+                    CompletionItemKind::FIELD,
+                    &column.data_type.to_string(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Whether `offset` falls inside a string literal, quoted identifier or comment, where no
+/// completion should be offered
+fn inside_literal(tokens: &[Token], offset: usize) -> bool {
+    tokens.iter().any(|token| {
+        let unterminated = match token.kind {
+            TokenKind::String | TokenKind::QuotedIdentifier => {
+                let quote = &token.text[..1];
+                token.text.len() == 1 || !token.text.ends_with(quote)
+            }
+            TokenKind::Comment => !token.text.starts_with("/*") || !token.text.ends_with("*/"),
+            _ => return false,
+        };
+
+        token.start < offset && (offset < token.end || (offset == token.end && unterminated))
+    })
+}
+
+/// Computes the completion items at `position` in `doc`, whose statements are replayed on top
+/// of `schema`. `trigger` is the character whose typing triggered the completion, if any.
+pub fn completions(
+    doc: &TextDocument,
+    schema: Schema,
+    position: Position,
+    config: &Config,
+    trigger: Option<&str>,
+    position_encoding: PositionEncodingKind,
+) -> Vec<CompletionItem> {
+    let (Ok(line_prefix), Ok(offset)) = (
+        doc.line_prefix(&position, position_encoding),
+        doc.position_to_byte_idx(&position, position_encoding),
+    ) else {
+        return vec![];
+    };
+
+    let typed_len = line_prefix
+        .chars()
+        .rev()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .map(char::len_utf8)
+        .sum::<usize>();
+    let typed = &line_prefix[line_prefix.len() - typed_len..];
+
+    let text = doc.text();
+    let tokens_with_comments = lexer::tokenize_with_comments(text);
+
+    // A string opened right before the word being typed, e.g. `USING 'Sto`, whose value may be
+    // completed
+    let quote = (offset - typed_len).checked_sub(1).filter(|&quote| {
+        tokens_with_comments
+            .iter()
+            .any(|token| token.kind == TokenKind::String && token.start == quote)
+    });
+
+    if quote.is_none() && inside_literal(&tokens_with_comments, offset) {
+        return vec![];
+    }
+
+    let tokens = lexer::tokenize(text);
+    let marker_names = bind_markers::marker_names(&tokens);
+
+    if let Some(items) = bind_marker_completions(&line_prefix, position, &marker_names) {
+        return rank(items);
+    }
+
+    let word_start = quote.unwrap_or(offset - typed_len);
+
+    let dialect = doc.dialect(config.dialect);
+    let cqlsh = dialect.is_cqlsh(&tokens);
+    let statements = statement::split(&tokens, dialect);
+    let schema = schema.with_statements(&statements);
+
+    // Unlike `statement_at`, blanks after an unterminated statement still belong to it, since
+    // that's where the next word of the statement is typed, unless it's a cqlsh command ended
+    // by its line
+    let index = statements
+        .iter()
+        .rposition(|statement| statement.start <= word_start)
+        .filter(|&i| {
+            let statement = &statements[i];
+
+            word_start < statement.end
+                || !(statement.terminated
+                    || cqlsh
+                        && statement.cqlsh_command().is_some()
+                        && text[statement.end..word_start].contains('\n'))
+        });
+    let full_statement = match index {
+        Some(index) => statements[index],
+        None => Statement {
+            tokens: &[],
+            start: word_start,
+            end: word_start,
+            terminated: false,
+        },
+    };
+
+    let prior = full_statement
+        .tokens
+        .iter()
+        .take_while(|token| token.end <= word_start)
+        .count();
+
+    let statement = Statement {
+        tokens: &full_statement.tokens[..prior],
+        ..full_statement
+    };
+
+    let context = CompletionContext {
+        line_prefix: &line_prefix,
+        position,
+        keyword_case: config.keyword_case,
+        typed,
+        statement,
+        full_statement,
+        schema: &schema,
+        keyspace: schema::keyspace_at(&statements, index.unwrap_or(statements.len()))
+            .or_else(|| schema.used_keyspace.clone()),
+        cqlsh,
+        target: config.target,
+    };
+
+    let mut items = provide(&context, trigger);
+
+    if quote.is_some() {
+        items.retain(|item| item.label.starts_with('\''));
+
+        // The opening quote is replaced too, being part of the label
+        for item in &mut items {
+            if let Some(CompletionTextEdit::Edit(edit)) = &mut item.text_edit {
+                edit.range.start.character -= 1;
+            }
+        }
+    }
+
+    rank(items)
+}
+
+/// Computes the continuation of the word being typed at `position`, shown inline as gray text:
+/// the first completion, if it continues the typed text on the same line, e.g. `PRIMARY KEY
+/// ((id), created_at)` once `PRI` is typed in the body of a table. The edit replaces the typed
+/// text, which its text starts with as typed.
+pub fn inline_completion(
+    doc: &TextDocument,
+    schema: Schema,
+    position: Position,
+    config: &Config,
+    position_encoding: PositionEncodingKind,
+) -> Option<TextEdit> {
+    // Keywords continue in the case they're being typed in, as the typed text stays
+    let config = Config {
+        keyword_case: KeywordCase::Preserve,
+        ..config.clone()
+    };
+
+    let item = completions(doc, schema, position, &config, None, position_encoding)
+        .into_iter()
+        .next()?;
+
+    if item.insert_text_format == Some(InsertTextFormat::SNIPPET) {
+        return None;
+    }
+
+    let Some(CompletionTextEdit::Edit(mut edit)) = item.text_edit else {
+        return None;
+    };
+
+    let line_prefix = doc.line_prefix(&position, position_encoding).ok()?;
+    let before = doc.line_prefix(&edit.range.start, position_encoding).ok()?;
+    let typed = line_prefix.get(before.len()..)?;
+
+    let continues = edit
+        .new_text
+        .get(..typed.len())
+        .is_some_and(|head| head.eq_ignore_ascii_case(typed));
+
+    if typed.trim().is_empty()
+        || !continues
+        || edit.new_text.len() == typed.len()
+        || edit.new_text.contains('\n')
+    {
+        return None;
+    }
+
+    // Clients show the continuation only if the text starts with what's typed as it's typed
+    edit.new_text.replace_range(..typed.len(), typed);
+
+    Some(edit)
+}
+
+/// Orders `items` as given by the providers, which list the likeliest first, but for those
+/// matching within their label, which follow the others. The first item is preselected.
+fn rank(mut items: Vec<CompletionItem>) -> Vec<CompletionItem> {
+    items.sort_by_key(|item| item.sort_text.as_deref() == Some(INNER_WORD_MATCH));
+
+    for (i, item) in items.iter_mut().enumerate() {
+        // Clients otherwise sort alphabetically by label
+        item.sort_text = Some(format!("{i:04}"));
+        item.preselect = (i == 0).then_some(true);
+    }
+
+    items
+}
+
+/// Runs the completion providers in turn, the first that recognizes the context giving the
+/// items. Otherwise, the statements or next keywords are offered, unless completion was
+/// triggered by punctuation, or by a new line within a statement.
+fn provide(context: &CompletionContext, trigger: Option<&str>) -> Vec<CompletionItem> {
+    if let Some(items) = consistency::completions(context) {
+        return items;
+    }
+
+    if let Some(items) = cqlsh::completions(context) {
+        return items;
+    }
+
+    if let Some(items) = grant::completions(context) {
+        return items;
+    }
+
+    if let Some(items) = datacenter::completions(context) {
+        return items;
+    }
+
+    if let Some(items) = role::completions(context) {
+        return items;
+    }
+
+    if let Some(items) = materialized_view::completions(context) {
+        return items;
+    }
+
+    if let Some(items) = index::completions(context) {
+        return items;
+    }
+
+    if let Some(items) = function::completions(context) {
+        return items;
+    }
+
+    if let Some(items) = clustering_order::completions(context) {
+        return items;
+    }
+
+    if let Some(items) = alter_table::completions(context) {
+        return items;
+    }
+
+    if let Some(items) = table::completions(context) {
+        return items;
+    }
+
+    if let Some(items) = dml::completions(context) {
+        return items;
+    }
+
+    if trigger.is_some_and(|trigger| PUNCTUATION_TRIGGERS.contains(&trigger)) {
+        return vec![];
+    }
+
+    let mut items = statement_keywords(context);
+
+    if !items.is_empty() || context.tokens().is_empty() {
+        if context.cqlsh && context.tokens().is_empty() {
+            items.extend(context.keywords(cqlsh::COMMANDS));
+        }
+
+        return items;
+    }
+
+    if trigger == Some("\n") {
+        return vec![];
+    }
+
+    // Operators such as `=` are typed rather than completed
+    let clauses = keywords::CLAUSES
+        .iter()
+        .chain(keywords::OPERATORS)
+        .filter(|keyword| keyword.label.starts_with(|c: char| c.is_ascii_alphabetic()))
+        .copied()
+        .collect::<Vec<_>>();
+
+    context.keywords(&clauses)
+}
+
+/// The statements matching what's typed at the start of a statement, the common ones first, so
+/// that `CREATE ` offers `CREATE TABLE` before `CREATE KEYSPACE`. Those of DSE come last, when
+/// it's the target.
+fn statement_keywords(context: &CompletionContext) -> Vec<CompletionItem> {
+    let tokens = context.tokens();
+    let typed = tokens.iter().map(|t| t.text).collect::<Vec<_>>().join(" ") + " ";
+
+    let mut statements = keywords::STATEMENTS.to_vec();
+
+    if context.targets(Database::Dse) {
+        statements.extend(keywords::DSE_STATEMENTS);
+    }
+
+    if !tokens.is_empty()
+        && !statements
+            .iter()
+            .any(|statement| matches_prefix(statement.label, &typed))
+    {
+        return vec![];
+    }
+
+    statements.sort_by_key(|statement| {
+        COMMON_STATEMENTS
+            .iter()
+            .position(|common| *common == statement.label)
+            .unwrap_or(COMMON_STATEMENTS.len())
+    });
+
+    let mut items = context.keywords(&statements);
+
+    // Within a statement, only the statements it starts may be offered, rather than any
+    // statement inserted after its first words
+    if !tokens.is_empty() {
+        let typed = typed + context.typed;
+        items.retain(|item| item.sort_text.is_none() && matches_prefix(&item.label, &typed));
+    }
+
+    items
+}
+
+/// If the user is typing a named bind marker (`:na`), returns the named markers used elsewhere
+/// in the document. `names` are the marker names collected from the document.
+fn bind_marker_completions(
+    line_prefix: &str,
+    position: Position,
+    names: &[String],
+) -> Option<Vec<CompletionItem>> {
+    let typed_len = line_prefix
+        .chars()
+        .rev()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .map(char::len_utf8)
+        .sum::<usize>();
+
+    let (before, typed) = line_prefix.split_at(line_prefix.len() - typed_len);
+
+    // `::` never starts a bind marker
+    if !before.ends_with(':') || before.ends_with("::") {
+        return None;
+    }
+
+    let start = Position {
+        line: position.line,
+        character: position.character - typed.encode_utf16().count() as u32 - 1,
+    };
+
+    let items = names
+        .iter()
+        .filter(|name| name.as_str() != typed)
+        .map(|name| CompletionItem {
+            label: format!(":{name}"),
+            kind: Some(CompletionItemKind::VARIABLE),
+            detail: Some("named bind marker".into()),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                range: Range {
+                    start,
+                    end: position,
+                },
+                new_text: format!(":{name}"),
+            })),
+            ..Default::default()
+        })
+        .collect();
+
+    Some(items)
+}
@@ -0,0 +1,122 @@
+//! Completion for `ALTER TABLE`: the table, its operation, then the columns it may drop or
+//! rename, Cassandra only dropping regular columns and renaming primary key columns.
+
+use tower_lsp::lsp_types::{CompletionItem, CompletionItemKind};
+
+use super::{CompletionContext, Keyword};
+use crate::{
+    alter_table::{self, Operation},
+    statement::StatementKind,
+};
+
+const OPERATIONS: &[Keyword] = &[
+    Keyword::new(
+        "ADD",
+        "`ADD column type`\n\nAdds regular columns, which can't be part of the primary key.",
+    ),
+    Keyword::new(
+        "DROP",
+        "`DROP column`\n\nDrops regular columns, their data being removed at compaction.",
+    ),
+    Keyword::new(
+        "RENAME",
+        "`RENAME column TO new_name`\n\nRenames primary key columns, the only ones that can be renamed.",
+    ),
+    Keyword::new("WITH", "Changes the options of the table."),
+];
+
+const TO: &[Keyword] = &[Keyword::new("TO", "The new name of the column.")];
+
+const AND: &[Keyword] = &[Keyword::new("AND", "Another column to rename.")];
+
+/// Completes `ALTER TABLE t operation ...` up to its columns. Returns `None` for other
+/// statements, and for the options after `WITH`.
+pub fn completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    let statement = &context.statement;
+
+    if statement.kind() != StatementKind::AlterTable {
+        return None;
+    }
+
+    let Some(alter) = alter_table::parse(statement) else {
+        let items = match &statement.tokens[2..] {
+            [] => context.table_names(None),
+            [keyspace, dot] if dot.is_punctuation(".") => {
+                context.table_names(keyspace.identifier())
+            }
+            _ => vec![],
+        };
+
+        return Some(items);
+    };
+
+    let tokens = statement.tokens;
+
+    let Some((operation, start)) = alter.operation else {
+        // The operation follows the table's name
+        return Some(match alter.body == tokens.len() {
+            true => context.keywords(OPERATIONS),
+            false => vec![],
+        });
+    };
+
+    let table = context
+        .schema
+        .statement_table(statement, context.keyspace.as_deref());
+
+    // The columns not listed yet that are, or aren't, part of the primary key
+    let columns = |primary_key: bool| {
+        let Some(table) = table else {
+            return vec![];
+        };
+
+        table
+            .columns
+            .iter()
+            .filter(|column| {
+                table.is_primary_key(&column.name) == primary_key
+                    && !alter
+                        .columns
+                        .iter()
+                        .any(|t| t.identifier().as_ref() == Some(&column.name))
+            })
+            .flat_map(|column| {
+                context.names(
+                    [(None, column.name.clone())],
+                    CompletionItemKind::FIELD,
+                    &column.data_type.to_string(),
+                )
+            })
+            .collect()
+    };
+
+    let rest = &tokens[start.min(tokens.len())..];
+    let last = rest.last();
+    let items = match operation {
+        Operation::Drop => match rest {
+            [] => columns(false),
+            [open, ..] if open.is_punctuation("(") => {
+                if last.is_some_and(|t| t.is_punctuation("(") || t.is_punctuation(",")) {
+                    columns(false)
+                } else {
+                    vec![]
+                }
+            }
+            _ => vec![],
+        },
+        Operation::Rename => match rest.len() % 4 {
+            // Before a column, after `RENAME` or `AND`
+            0 => columns(true),
+            1 => context.keywords(TO),
+            3 => context.keywords(AND),
+            _ => vec![],
+        },
+        Operation::Add => match rest {
+            [name] if name.is_identifier() => context.type_names(),
+            _ => vec![],
+        },
+        Operation::With => return None,
+    };
+
+    Some(items)
+}
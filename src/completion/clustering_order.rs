@@ -0,0 +1,62 @@
+//! Completion within `CLUSTERING ORDER BY (...)`: the clustering columns, in their order in the
+//! primary key, then `ASC` or `DESC`.
+
+use tower_lsp::lsp_types::{CompletionItem, CompletionItemKind};
+
+use super::{CompletionContext, Keyword};
+use crate::{clustering_order, lexer::Token};
+
+const ORDERS: &[Keyword] = &[
+    Keyword::new(
+        "ASC",
+        "Rows are stored in ascending order of the column. The default.",
+    ),
+    Keyword::new(
+        "DESC",
+        "Rows are stored in descending order of the column, e.g. for the latest first.",
+    ),
+];
+
+/// Completes the columns of `CLUSTERING ORDER BY (` with the clustering columns following those
+/// already ordered, then the order of each. Returns `None` outside its parentheses.
+pub fn completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    let order = clustering_order::parse(&context.statement)?;
+
+    if order.closed {
+        return None;
+    }
+
+    let Some((current, ordered)) = order.columns.split_last() else {
+        return Some(clustering_columns(context, &[]));
+    };
+
+    let items = match current {
+        [] => clustering_columns(context, ordered),
+        [column] if column.is_identifier() => context.keywords(ORDERS),
+        _ => vec![],
+    };
+
+    Some(items)
+}
+
+/// The clustering columns of the table or view after the last of those `ordered`
+fn clustering_columns(context: &CompletionContext, ordered: &[&[Token]]) -> Vec<CompletionItem> {
+    // The primary key may follow the cursor in a view, whose options come last
+    let Some((_, clustering_key)) = clustering_order::primary_key(&context.full_statement) else {
+        return vec![];
+    };
+
+    let next = ordered
+        .iter()
+        .filter_map(|definition| definition.first()?.identifier())
+        .filter_map(|column| clustering_key.iter().position(|name| *name == column))
+        .max()
+        .map_or(0, |last| last + 1);
+
+    let names = clustering_key
+        .into_iter()
+        .skip(next)
+        .map(|name| (None, name));
+
+    context.names(names, CompletionItemKind::FIELD, "clustering column")
+}
@@ -0,0 +1,67 @@
+//! Completion for the levels of cqlsh's `CONSISTENCY` and `SERIAL CONSISTENCY` directives,
+//! which set the consistency of the statements executed after them.
+
+use tower_lsp::lsp_types::CompletionItem;
+
+use super::{CompletionContext, Keyword};
+
+const LEVELS: &[Keyword] = &[
+    Keyword::new(
+        "ONE",
+        "A single replica must respond. The default, favouring availability and latency over consistency.",
+    ),
+    Keyword::new(
+        "LOCAL_ONE",
+        "A single replica of the local datacenter must respond, so that no request crosses datacenters.",
+    ),
+    Keyword::new("TWO", "Two replicas must respond."),
+    Keyword::new("THREE", "Three replicas must respond."),
+    Keyword::new(
+        "QUORUM",
+        "A majority of the replicas across all datacenters must respond. Reads and writes at `QUORUM` always overlap, so reads see the latest write.",
+    ),
+    Keyword::new(
+        "LOCAL_QUORUM",
+        "A majority of the replicas of the local datacenter must respond: strong consistency within the datacenter, without the latency of reaching the others.",
+    ),
+    Keyword::new(
+        "EACH_QUORUM",
+        "A majority of the replicas of every datacenter must respond. Only valid for writes.",
+    ),
+    Keyword::new(
+        "ALL",
+        "Every replica must respond, so a single unavailable replica fails the request.",
+    ),
+    Keyword::new(
+        "ANY",
+        "The write succeeds once stored anywhere, even only as a hint for an unavailable replica. Only valid for writes.",
+    ),
+    Keyword::new(
+        "SERIAL",
+        "Lightweight transactions reach consensus among a majority of the replicas across all datacenters. Reads at `SERIAL` see uncommitted transactions.",
+    ),
+    Keyword::new(
+        "LOCAL_SERIAL",
+        "Lightweight transactions reach consensus among a majority of the replicas of the local datacenter.",
+    ),
+];
+
+const SERIAL_LEVELS: &[&str] = &["SERIAL", "LOCAL_SERIAL"];
+
+pub fn completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    let serial = match context.tokens() {
+        [keyword] if keyword.is_keyword("CONSISTENCY") => false,
+        [serial, keyword] if serial.is_keyword("SERIAL") && keyword.is_keyword("CONSISTENCY") => {
+            true
+        }
+        _ => return None,
+    };
+
+    let levels = LEVELS
+        .iter()
+        .filter(|level| !serial || SERIAL_LEVELS.contains(&level.label))
+        .copied()
+        .collect::<Vec<_>>();
+
+    Some(context.keywords(&levels))
+}
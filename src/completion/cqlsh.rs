@@ -0,0 +1,345 @@
+//! Completion for the commands of cqlsh scripts: the commands themselves at the start of a
+//! statement, and their options.
+
+use tower_lsp::lsp_types::CompletionItem;
+
+use super::{CompletionContext, Keyword};
+use crate::{lexer::Token, statement::parse_qualified_name};
+
+pub const COMMANDS: &[Keyword] = &[
+    Keyword::new(
+        "CAPTURE",
+        "`CAPTURE 'file'` | `CAPTURE OFF`\n\nAppends the output of the following queries to a file, rather than printing it.",
+    ),
+    Keyword::new(
+        "CONSISTENCY",
+        "`CONSISTENCY level`\n\nSets the consistency level of the following statements.",
+    ),
+    Keyword::new(
+        "COPY",
+        "`COPY table [(columns)] TO|FROM 'file' [WITH option = value [AND ...]]`\n\nExports a table to CSV, or imports CSV into a table.",
+    ),
+    Keyword::new(
+        "DESCRIBE",
+        "`DESCRIBE [KEYSPACE|TABLE|TYPE|...] name`\n\nPrints the schema of the cluster, or the DDL of a schema object.",
+    ),
+    Keyword::new(
+        "EXPAND",
+        "`EXPAND ON|OFF`\n\nPrints each row vertically, one column per line.",
+    ),
+    Keyword::new(
+        "PAGING",
+        "`PAGING ON|OFF|page_size`\n\nFetches the rows of queries one page at a time.",
+    ),
+    Keyword::new(
+        "SERIAL CONSISTENCY",
+        "`SERIAL CONSISTENCY level`\n\nSets the consistency of the Paxos phase of the following lightweight transactions.",
+    ),
+    Keyword::new(
+        "SHOW",
+        "`SHOW VERSION|HOST|SESSION id`\n\nPrints the version of cqlsh and the cluster, the host connected to, or a tracing session.",
+    ),
+    Keyword::new(
+        "SOURCE",
+        "`SOURCE 'file'`\n\nRuns the statements of another file.",
+    ),
+    Keyword::new(
+        "TRACING",
+        "`TRACING ON|OFF`\n\nPrints the trace of the following statements.",
+    ),
+];
+
+const SWITCH: &[Keyword] = &[
+    Keyword::new("ON", "Enables the setting."),
+    Keyword::new("OFF", "Disables the setting."),
+];
+
+const CAPTURE: &[Keyword] = &[Keyword::new("OFF", "Prints the output again.")];
+
+const SHOW: &[Keyword] = &[
+    Keyword::new(
+        "VERSION",
+        "The versions of cqlsh, Cassandra and the protocol.",
+    ),
+    Keyword::new("HOST", "The host cqlsh is connected to."),
+    Keyword::new(
+        "SESSION",
+        "`SESSION id`\n\nThe events of a tracing session.",
+    ),
+];
+
+const DESCRIBE_OBJECTS: &[Keyword] = &[
+    Keyword::new("CLUSTER", "The cluster name, partitioner and snitch."),
+    Keyword::new("SCHEMA", "The DDL of every user keyspace."),
+    Keyword::new(
+        "FULL SCHEMA",
+        "The DDL of every keyspace, system ones included.",
+    ),
+    Keyword::new("KEYSPACES", "The names of the keyspaces."),
+    Keyword::new("KEYSPACE", "The DDL of a keyspace and everything in it."),
+    Keyword::new("TABLES", "The names of the tables."),
+    Keyword::new("TABLE", "The DDL of a table, its indexes and views."),
+    Keyword::new("TYPES", "The names of the user-defined types."),
+    Keyword::new("TYPE", "The DDL of a user-defined type."),
+    Keyword::new("FUNCTIONS", "The names of the user-defined functions."),
+    Keyword::new("FUNCTION", "The DDL of a user-defined function."),
+    Keyword::new("AGGREGATES", "The names of the user-defined aggregates."),
+    Keyword::new("AGGREGATE", "The DDL of a user-defined aggregate."),
+    Keyword::new("MATERIALIZED VIEW", "The DDL of a materialized view."),
+    Keyword::new("INDEX", "The DDL of a secondary index."),
+];
+
+const DIRECTIONS: &[Keyword] = &[
+    Keyword::new("TO", "Exports the table to a CSV file."),
+    Keyword::new("FROM", "Imports a CSV file into the table."),
+];
+
+const STDOUT: &[Keyword] = &[Keyword::new(
+    "STDOUT",
+    "Prints the CSV rather than writing a file.",
+)];
+
+const STDIN: &[Keyword] = &[Keyword::new(
+    "STDIN",
+    "Reads the CSV from the standard input.",
+)];
+
+const WITH: &[Keyword] = &[Keyword::new(
+    "WITH",
+    "`WITH option = value [AND ...]`\n\nOptions of the CSV format and of the transfer.",
+)];
+
+const AND: &[Keyword] = &[Keyword::new("AND", "Another option.")];
+
+const COPY_OPTIONS: &[Keyword] = &[
+    Keyword::new(
+        "DELIMITER",
+        "The character separating fields. Defaults to `,`.",
+    ),
+    Keyword::new("QUOTE", "The character quoting fields. Defaults to `\"`."),
+    Keyword::new("ESCAPE", "The character escaping quotes. Defaults to `\\`."),
+    Keyword::new(
+        "HEADER",
+        "Whether the first line holds the column names. Defaults to `false`.",
+    ),
+    Keyword::new(
+        "NULL",
+        "The string standing for null values. Defaults to an empty string.",
+    ),
+    Keyword::new(
+        "DATETIMEFORMAT",
+        "The `strftime` format of timestamps. Defaults to `%Y-%m-%d %H:%M:%S%z`.",
+    ),
+    Keyword::new(
+        "MAXATTEMPTS",
+        "How many times a failed page or batch is retried. Defaults to 5.",
+    ),
+    Keyword::new(
+        "REPORTFREQUENCY",
+        "How often the progress is reported, in seconds. Defaults to 0.25.",
+    ),
+    Keyword::new("DECIMALSEP", "The decimal separator. Defaults to `.`."),
+    Keyword::new("THOUSANDSSEP", "The thousands separator. Defaults to none."),
+    Keyword::new(
+        "BOOLSTYLE",
+        "The representations of booleans, e.g. `'yes,no'`. Defaults to `'True,False'`.",
+    ),
+    Keyword::new(
+        "NUMPROCESSES",
+        "How many worker processes transfer the rows. Defaults to the number of cores minus one, up to 16.",
+    ),
+    Keyword::new(
+        "CONFIGFILE",
+        "A file with further options, in `cqlshrc` format.",
+    ),
+    Keyword::new("RATEFILE", "A file the transfer rates are written to."),
+];
+
+const COPY_TO_OPTIONS: &[Keyword] = &[
+    Keyword::new("ENCODING", "The encoding of the file. Defaults to `utf8`."),
+    Keyword::new(
+        "PAGESIZE",
+        "How many rows are fetched per page. Defaults to 1000.",
+    ),
+    Keyword::new(
+        "PAGETIMEOUT",
+        "The timeout of each page, in seconds. Defaults to 10.",
+    ),
+    Keyword::new("BEGINTOKEN", "The smallest token of the exported rows."),
+    Keyword::new("ENDTOKEN", "The largest token of the exported rows."),
+    Keyword::new(
+        "MAXREQUESTS",
+        "How many pages each worker fetches at once. Defaults to 6.",
+    ),
+    Keyword::new(
+        "MAXOUTPUTSIZE",
+        "How many lines each file holds before a new one is started. Defaults to unlimited.",
+    ),
+    Keyword::new(
+        "FLOATPRECISION",
+        "The digits of `float` values. Defaults to 5.",
+    ),
+    Keyword::new(
+        "DOUBLEPRECISION",
+        "The digits of `double` values. Defaults to 12.",
+    ),
+];
+
+const COPY_FROM_OPTIONS: &[Keyword] = &[
+    Keyword::new(
+        "CHUNKSIZE",
+        "How many rows are sent to each worker at once. Defaults to 5000.",
+    ),
+    Keyword::new(
+        "INGESTRATE",
+        "The most rows imported per second. Defaults to 100000.",
+    ),
+    Keyword::new("MAXBATCHSIZE", "The most rows per batch. Defaults to 20."),
+    Keyword::new("MINBATCHSIZE", "The fewest rows per batch. Defaults to 10."),
+    Keyword::new(
+        "MAXPARSEERRORS",
+        "How many rows may fail to parse before the import stops. Defaults to unlimited.",
+    ),
+    Keyword::new(
+        "MAXINSERTERRORS",
+        "How many rows may fail to insert before the import stops. Defaults to 1000.",
+    ),
+    Keyword::new("ERRFILE", "The file the rows that failed are written to."),
+    Keyword::new(
+        "PREPAREDSTATEMENTS",
+        "Whether rows are inserted with prepared statements. Defaults to `true`.",
+    ),
+    Keyword::new("TTL", "The time to live of the imported rows, in seconds."),
+    Keyword::new(
+        "SKIPROWS",
+        "How many lines are skipped at the start of the file.",
+    ),
+    Keyword::new("SKIPCOLS", "The columns of the file that aren't imported."),
+    Keyword::new("MAXROWS", "The most rows imported. Defaults to unlimited."),
+];
+
+/// Completes the options of cqlsh commands. Returns `None` for other statements.
+pub fn completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    let command = context.statement.cqlsh_command()?;
+    let tokens = context.tokens();
+    let words = command.split(' ').count();
+
+    let items = match (command, &tokens[words..]) {
+        ("PAGING" | "TRACING" | "EXPAND", []) => context.keywords(SWITCH),
+        ("CAPTURE", []) => context.keywords(CAPTURE),
+        ("SHOW", []) => context.keywords(SHOW),
+        ("DESC" | "DESCRIBE", rest) => describe(context, rest),
+        ("COPY", _) => copy(context, words),
+        _ => vec![],
+    };
+
+    Some(items)
+}
+
+/// Completes `DESCRIBE [object] [name]`, given the tokens after `DESCRIBE`
+fn describe(context: &CompletionContext, rest: &[Token]) -> Vec<CompletionItem> {
+    match rest {
+        [] => {
+            let mut items = context.keywords(DESCRIBE_OBJECTS);
+            items.extend(context.keyspace_names());
+            items.extend(context.table_names(None));
+            items
+        }
+        [object] if object.is_keyword("KEYSPACE") => context.keyspace_names(),
+        [object] if object.is_keyword("TABLE") || object.is_keyword("COLUMNFAMILY") => {
+            context.table_names(None)
+        }
+        [object, keyspace, dot]
+            if (object.is_keyword("TABLE") || object.is_keyword("COLUMNFAMILY"))
+                && dot.is_punctuation(".") =>
+        {
+            context.table_names(keyspace.identifier())
+        }
+        _ => vec![],
+    }
+}
+
+/// Completes `COPY table [(columns)] TO|FROM 'file' [WITH option = value [AND ...]]`, whose
+/// table starts at the token index `start`
+fn copy(context: &CompletionContext, start: usize) -> Vec<CompletionItem> {
+    let statement = &context.statement;
+    let tokens = statement.tokens;
+
+    match &tokens[start..] {
+        [] => return context.table_names(None),
+        [keyspace, dot] if dot.is_punctuation(".") => {
+            return context.table_names(keyspace.identifier());
+        }
+        _ => {}
+    }
+
+    let mut i = start;
+    let Some(name) = parse_qualified_name(tokens, &mut i) else {
+        return vec![];
+    };
+
+    let direction = statement
+        .find_keyword_from("TO", i)
+        .or_else(|| statement.find_keyword_from("FROM", i));
+
+    let Some(direction) = direction else {
+        let last = &tokens[tokens.len() - 1];
+
+        // Inside the column list
+        if tokens.get(i).is_some_and(|token| token.is_punctuation("("))
+            && !tokens[i..].iter().any(|token| token.is_punctuation(")"))
+        {
+            if !last.is_punctuation("(") && !last.is_punctuation(",") {
+                return vec![];
+            }
+
+            let keyspace = name.keyspace.as_deref().or(context.keyspace.as_deref());
+
+            return context
+                .schema
+                .table(keyspace, &name.name)
+                .map(|table| context.column_names(table))
+                .unwrap_or_default();
+        }
+
+        return match i == tokens.len() || last.is_punctuation(")") {
+            true => context.keywords(DIRECTIONS),
+            false => vec![],
+        };
+    };
+
+    let exporting = tokens[direction].is_keyword("TO");
+
+    if tokens.len() == direction + 1 {
+        return match exporting {
+            true => context.keywords(STDOUT),
+            false => context.keywords(STDIN),
+        };
+    }
+
+    let Some(with) = statement.find_keyword_from("WITH", direction) else {
+        return match tokens.len() == direction + 2 {
+            true => context.keywords(WITH),
+            false => vec![],
+        };
+    };
+
+    let last = &tokens[tokens.len() - 1];
+
+    if tokens.len() - 1 == with || last.is_keyword("AND") {
+        let mut items = context.keywords(COPY_OPTIONS);
+
+        items.extend(context.keywords(match exporting {
+            true => COPY_TO_OPTIONS,
+            false => COPY_FROM_OPTIONS,
+        }));
+
+        return items;
+    }
+
+    // After an option's value
+    match tokens.len() >= 2 && tokens[tokens.len() - 2].is_punctuation("=") {
+        true => context.keywords(AND),
+        false => vec![],
+    }
+}
@@ -0,0 +1,38 @@
+//! Completion of the datacenters of the connected cluster, as the keys of a
+//! `NetworkTopologyStrategy` replication map and within `ACCESS TO DATACENTERS { ... }`.
+
+use tower_lsp::lsp_types::CompletionItem;
+
+use super::CompletionContext;
+use crate::datacenter;
+
+/// Completes a datacenter at the start of an entry of a replication map or a role's access,
+/// leaving out those already named. Returns `None` outside their braces.
+pub fn completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    let datacenters = datacenter::parse(&context.statement)?;
+
+    if datacenters.closed {
+        return None;
+    }
+
+    // The class may follow the cursor, as may other datacenters
+    let full = datacenter::parse(&context.full_statement)?;
+
+    let starts_entry = datacenters
+        .entries
+        .last()
+        .is_none_or(|entry| entry.is_empty());
+    let keyed = !full.replication || full.class().as_deref() == Some("NetworkTopologyStrategy");
+
+    if !starts_entry || !keyed {
+        return Some(vec![]);
+    }
+
+    let named = full
+        .names()
+        .iter()
+        .filter_map(|token| token.string_value())
+        .collect::<Vec<_>>();
+
+    Some(context.datacenter_names(&named))
+}
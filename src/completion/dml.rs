@@ -0,0 +1,1016 @@
+//! Completion of the clauses of `SELECT`, `INSERT`, `UPDATE` and `DELETE` while they're being
+//! typed. These statements are invalid until complete, so where the parser only sees an error,
+//! the clauses already typed tell which ones may come next.
+
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionTextEdit, InsertTextFormat, Position, Range,
+    TextEdit,
+};
+
+use super::{CompletionContext, Keyword};
+use crate::{
+    assignment,
+    cql_type::CqlType,
+    features, generate, group_by, index, keywords,
+    lexer::{Token, TokenKind, quote_identifier},
+    literal,
+    schema::{Column, Table},
+    statement::{StatementKind, matching_bracket, split_comma_ranges},
+    target::Database,
+    using,
+};
+
+const AND: Keyword = Keyword::new("AND", "Another condition.");
+
+/// The pseudo-column DSE Search queries are given with, e.g. `solr_query = 'name:ali*'`
+const SOLR_QUERY: &str = "solr_query";
+
+const SELECT_MODIFIERS: &[Keyword] = &[
+    Keyword::new("DISTINCT", "Returns each partition key once."),
+    Keyword::new("JSON", "Returns each row as a single JSON-encoded map."),
+];
+
+const FROM: Keyword = Keyword::new("FROM", "The table or materialized view to query.");
+
+const AS: Keyword = Keyword::new("AS", "Names the selected column in the results.");
+
+const WHERE: Keyword = Keyword::new(
+    "WHERE",
+    "Restricts the rows by their primary key, or by indexed columns.",
+);
+
+const GROUP_BY: Keyword = Keyword::new(
+    "GROUP BY",
+    "Groups the rows by a prefix of the primary key, for aggregate functions.",
+);
+
+const ORDER_BY: Keyword = Keyword::new(
+    "ORDER BY",
+    "Orders the rows of a partition by clustering columns, in their order or its reverse.",
+);
+
+const PER_PARTITION_LIMIT: Keyword = Keyword::new(
+    "PER PARTITION LIMIT",
+    "The most rows returned per partition.",
+);
+
+const LIMIT: Keyword = Keyword::new("LIMIT", "The most rows returned.");
+
+const ALLOW_FILTERING: Keyword = Keyword::new(
+    "ALLOW FILTERING",
+    "Runs the query even though it filters rows server-side, reading more than it returns. Slow on large tables.",
+);
+
+const BYPASS_CACHE: Keyword = Keyword::new(
+    "BYPASS CACHE",
+    "Reads without going through ScyllaDB's row cache nor filling it, for scans that would evict the rows other queries read.",
+);
+
+const USING_TIMEOUT: Keyword = Keyword::new(
+    "USING TIMEOUT",
+    "`USING TIMEOUT 500ms`\n\nHow long ScyllaDB may take to run the statement, overriding the server's timeout.",
+);
+
+const TIMEOUT: Keyword = Keyword::new(
+    "TIMEOUT",
+    "`TIMEOUT 500ms`\n\nHow long ScyllaDB may take to run the statement, overriding the server's timeout.",
+);
+
+/// The clauses of a `SELECT` after its table, in the order they're written in, ScyllaDB's last
+const SELECT_CLAUSES: [Keyword; 8] = [
+    WHERE,
+    GROUP_BY,
+    ORDER_BY,
+    PER_PARTITION_LIMIT,
+    LIMIT,
+    ALLOW_FILTERING,
+    BYPASS_CACHE,
+    USING_TIMEOUT,
+];
+
+/// The clauses of `SELECT_CLAUSES` only ScyllaDB has
+const SCYLLA_SELECT_CLAUSES: &[&str] = &["BYPASS CACHE", "USING TIMEOUT"];
+
+/// The clauses of a `SELECT` by how commonly they're used, so that `LIMIT` is offered before
+/// `GROUP BY` though written after it, and the slow `ALLOW FILTERING` last
+const SELECT_CLAUSE_USAGE: &[&str] = &[
+    "WHERE",
+    "LIMIT",
+    "ORDER BY",
+    "GROUP BY",
+    "PER PARTITION LIMIT",
+    "ALLOW FILTERING",
+    "BYPASS CACHE",
+    "USING TIMEOUT",
+];
+
+/// Durations added to or subtracted from timestamps, e.g. `toTimestamp(now()) - 1d`
+const DURATIONS: &[Keyword] = &[
+    Keyword::new("1d", "One day."),
+    Keyword::new("1h", "One hour."),
+    Keyword::new("30m", "Thirty minutes."),
+    Keyword::new("1w", "One week, stored as 7 days."),
+    Keyword::new("1mo", "One month, whose length depends on the date."),
+    Keyword::new("1y", "One year, stored as 12 months."),
+];
+
+const SORT_ORDERS: &[Keyword] = &[
+    Keyword::new("ASC", "In ascending order."),
+    Keyword::new("DESC", "In descending order."),
+];
+
+const INTO: &[Keyword] = &[Keyword::new("INTO", "The table to insert the row into.")];
+
+const VALUES: &[Keyword] = &[Keyword::new(
+    "VALUES",
+    "The values of the columns, in the order of the column list.",
+)];
+
+const JSON: Keyword = Keyword::new(
+    "JSON",
+    "The row as a JSON object mapping column names to values: `JSON '{\"id\": 1}'`.",
+);
+
+const DEFAULTS: &[Keyword] = &[
+    Keyword::new(
+        "DEFAULT NULL",
+        "Sets the columns missing from the JSON object to null. The default.",
+    ),
+    Keyword::new(
+        "DEFAULT UNSET",
+        "Leaves the columns missing from the JSON object unchanged.",
+    ),
+];
+
+const DEFAULT_VALUES: &[Keyword] = &[
+    Keyword::new(
+        "NULL",
+        "Sets the columns missing from the JSON object to null. The default.",
+    ),
+    Keyword::new(
+        "UNSET",
+        "Leaves the columns missing from the JSON object unchanged.",
+    ),
+];
+
+const TO_JSON: &[Keyword] = &[Keyword::new(
+    "toJson",
+    "`toJson(column)`\n\nReturns the value of the column encoded as JSON.",
+)];
+
+const FROM_JSON: &[Keyword] = &[Keyword::new(
+    "fromJson",
+    "`fromJson('json')`\n\nDecodes a JSON-encoded value of the column's type.",
+)];
+
+const IF_NOT_EXISTS: Keyword = Keyword::new(
+    "IF NOT EXISTS",
+    "Only inserts the row if it doesn't exist, as a lightweight transaction.",
+);
+
+const USING_TTL: Keyword = Keyword::new(
+    "USING TTL",
+    "The time to live of the written values, in seconds, up to 20 years.",
+);
+
+const USING_TIMESTAMP: Keyword = Keyword::new(
+    "USING TIMESTAMP",
+    "The write timestamp, in microseconds since the Unix epoch.",
+);
+
+const TTL: Keyword = Keyword::new(
+    "TTL",
+    "The time to live of the written values, in seconds, up to 20 years.",
+);
+
+const TIMESTAMP: Keyword = Keyword::new(
+    "TIMESTAMP",
+    "The write timestamp, in microseconds since the Unix epoch.",
+);
+
+const SET: Keyword = Keyword::new("SET", "The columns to update and their new values.");
+
+const IF: Keyword = Keyword::new(
+    "IF",
+    "Only writes if the conditions hold, as a lightweight transaction.",
+);
+
+const IF_EXISTS: Keyword = Keyword::new(
+    "IF EXISTS",
+    "Only writes if the row exists, as a lightweight transaction.",
+);
+
+const EXISTS: &[Keyword] = &[Keyword::new(
+    "EXISTS",
+    "Only writes if the row exists, as a lightweight transaction.",
+)];
+
+/// The `USING` clauses of a write to `table`: counter tables can't be given a TTL, and only
+/// ScyllaDB takes a timeout
+fn using(context: &CompletionContext, table: Option<&Table>) -> Vec<Keyword> {
+    let mut clauses = match table.is_some_and(using::is_counter_table) {
+        true => vec![USING_TIMESTAMP],
+        false => vec![USING_TTL, USING_TIMESTAMP],
+    };
+
+    if context.targets(Database::Scylla) {
+        clauses.push(USING_TIMEOUT);
+    }
+
+    clauses
+}
+
+/// The options of the `USING` clause of a write to `table`
+fn using_options(context: &CompletionContext, table: Option<&Table>) -> Vec<Keyword> {
+    let mut options = match table.is_some_and(using::is_counter_table) {
+        true => vec![TIMESTAMP],
+        false => vec![TTL, TIMESTAMP],
+    };
+
+    if context.targets(Database::Scylla) {
+        options.push(TIMEOUT);
+    }
+
+    options
+}
+
+/// Whether `token` ends a value: a literal, a bind marker or a closing bracket
+fn is_value(token: &Token) -> bool {
+    match token.kind {
+        TokenKind::String
+        | TokenKind::Number
+        | TokenKind::Hex
+        | TokenKind::Uuid
+        | TokenKind::Duration
+        | TokenKind::BindMarker
+        | TokenKind::NamedBindMarker => true,
+        TokenKind::Punctuation => matches!(token.text, ")" | "]" | "}"),
+        TokenKind::Word => ["true", "false", "null", "NaN", "Infinity"]
+            .iter()
+            .any(|word| token.is_keyword(word)),
+        _ => false,
+    }
+}
+
+/// The index of the last of `keywords` found at the top level of `tokens`
+fn last_keyword(context: &CompletionContext, keywords: &[&str]) -> Option<usize> {
+    keywords
+        .iter()
+        .filter_map(|keyword| {
+            std::iter::successors(context.statement.find_keyword(keyword), |&i| {
+                context.statement.find_keyword_from(keyword, i + 1)
+            })
+            .last()
+        })
+        .max()
+}
+
+/// Builds items for the native functions `functions`
+fn functions(context: &CompletionContext, functions: &[Keyword]) -> Vec<CompletionItem> {
+    context
+        .literals(functions)
+        .into_iter()
+        .map(|item| CompletionItem {
+            kind: Some(CompletionItemKind::FUNCTION),
+            ..item
+        })
+        .collect()
+}
+
+/// A snippet replacing what's been typed of the current word
+fn snippet(
+    context: &CompletionContext,
+    label: String,
+    detail: String,
+    snippet: String,
+) -> CompletionItem {
+    let range = Range {
+        start: Position {
+            line: context.position.line,
+            character: context.position.character - context.typed.encode_utf16().count() as u32,
+        },
+        end: context.position,
+    };
+
+    CompletionItem {
+        label,
+        kind: Some(CompletionItemKind::SNIPPET),
+        detail: Some(detail),
+        insert_text_format: Some(InsertTextFormat::SNIPPET),
+        text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+            range,
+            new_text: snippet,
+        })),
+        ..Default::default()
+    }
+}
+
+/// A snippet of the JSON object of a row of `table`, with a placeholder per column
+fn json_row(context: &CompletionContext, table: Option<&Table>) -> Vec<CompletionItem> {
+    let Some(table) = table else {
+        return vec![];
+    };
+
+    vec![snippet(
+        context,
+        "'{...}'".into(),
+        format!("Row of {}", table.name),
+        generate::json_row(table, true),
+    )]
+}
+
+/// The updates of the counter or collection `column` of `table` relative to its value, such
+/// as `views + 1`
+fn relative_updates(
+    context: &CompletionContext,
+    table: Option<&Table>,
+    column: &Token,
+) -> Vec<CompletionItem> {
+    let Some(column) = column
+        .identifier()
+        .and_then(|column| table?.column(&column))
+    else {
+        return vec![];
+    };
+
+    assignment::relative_updates(column)
+        .into_iter()
+        .map(|(label, new_text)| snippet(context, label, column.data_type.to_string(), new_text))
+        .collect()
+}
+
+/// When a field name of a user-defined type literal is expected at the end of `value`, the
+/// value given to `column` of `table`, completes the fields not given yet
+fn field_names(
+    context: &CompletionContext,
+    table: &Table,
+    column: &str,
+    value: &[Token],
+) -> Option<Vec<CompletionItem>> {
+    let column = table.column(column)?;
+    let (user_type, given) =
+        literal::expected_field(value, &column.data_type, context.schema, &table.keyspace)?;
+
+    Some(
+        user_type
+            .fields
+            .iter()
+            .filter(|field| !given.contains(&field.name))
+            .flat_map(|field| {
+                context.names(
+                    [(None, field.name.clone())],
+                    CompletionItemKind::FIELD,
+                    &field.data_type.to_string(),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// The column names of `table`, or nothing when the table isn't known
+fn columns(context: &CompletionContext, table: Option<&Table>) -> Vec<CompletionItem> {
+    table
+        .map(|table| context.column_names(table))
+        .unwrap_or_default()
+}
+
+/// The columns of `table` outside the primary key: the only ones an `UPDATE` may set, a
+/// lightweight transaction's `IF` may test, and a `DELETE` may delete
+fn non_key_columns(context: &CompletionContext, table: Option<&Table>) -> Vec<CompletionItem> {
+    let Some(table) = table else {
+        return vec![];
+    };
+
+    let key = group_by::primary_key(table);
+
+    table
+        .columns
+        .iter()
+        .filter(|column| !key.contains(&&column.name))
+        .flat_map(|column| {
+            context.names(
+                [(None, column.name.clone())],
+                CompletionItemKind::FIELD,
+                &column.data_type.to_string(),
+            )
+        })
+        .collect()
+}
+
+/// The primary key columns of `table` not restricted yet by the `WHERE` at `start`, which are
+/// the only ones a `DELETE` may restrict
+fn key_columns(
+    context: &CompletionContext,
+    table: Option<&Table>,
+    start: usize,
+) -> Vec<CompletionItem> {
+    let Some(table) = table else {
+        return vec![];
+    };
+
+    let restricted = context.tokens()[start..]
+        .windows(2)
+        .filter(|pair| pair[0].is_keyword("WHERE") || pair[0].is_keyword("AND"))
+        .filter_map(|pair| pair[1].identifier())
+        .collect::<Vec<_>>();
+
+    group_by::primary_key(table)
+        .into_iter()
+        .filter(|name| !restricted.contains(name))
+        .filter_map(|name| table.column(name))
+        .flat_map(|column| {
+            context.names(
+                [(None, column.name.clone())],
+                CompletionItemKind::FIELD,
+                &column.data_type.to_string(),
+            )
+        })
+        .collect()
+}
+
+/// The columns of `table` a `DELETE` may delete, but those it already deletes: the columns
+/// outside the primary key, and the elements of its non-frozen lists and maps, e.g.
+/// `tags['key']`
+fn deleted_columns(context: &CompletionContext, table: Option<&Table>) -> Vec<CompletionItem> {
+    let Some(table) = table else {
+        return vec![];
+    };
+
+    let deleted = context
+        .tokens()
+        .iter()
+        .filter_map(|token| token.identifier())
+        .collect::<Vec<_>>();
+
+    let mut items = non_key_columns(context, Some(table));
+    items.retain(|item| !deleted.contains(&item.label));
+
+    let elements = table.columns.iter().filter_map(|column| {
+        let selector = match &column.data_type {
+            CqlType::List(_) => "[${1:index}]",
+            CqlType::Map(key, _) if is_text(key) => "['${1:key}']",
+            CqlType::Map(..) => "[${1:key}]",
+            _ => return None,
+        };
+
+        let name = quote_identifier(&column.name);
+
+        Some(snippet(
+            context,
+            format!("{name}[...]"),
+            format!("Element of {}", column.data_type),
+            format!("{name}{selector}"),
+        ))
+    });
+
+    items.extend(elements);
+    items
+}
+
+/// Completes a table name being typed in `rest`, the tokens after the keyword before it
+fn table_name(context: &CompletionContext, rest: &[Token]) -> Option<Vec<CompletionItem>> {
+    match rest {
+        [] => Some(context.table_names(None)),
+        [keyspace, dot] if dot.is_punctuation(".") => {
+            Some(context.table_names(keyspace.identifier()))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `rest` is a whole (possibly qualified) table name
+fn is_table_name(rest: &[Token]) -> bool {
+    match rest {
+        [name] => name.is_identifier(),
+        [keyspace, dot, name] => {
+            keyspace.is_identifier() && dot.is_punctuation(".") && name.is_identifier()
+        }
+        _ => false,
+    }
+}
+
+/// The operators labelled `labels` in the keyword database
+fn operators(labels: &[&str]) -> Vec<Keyword> {
+    keywords::OPERATORS
+        .iter()
+        .filter(|operator| labels.contains(&operator.label))
+        .copied()
+        .collect()
+}
+
+/// Whether values of `data_type` are strings
+fn is_text(data_type: &CqlType) -> bool {
+    matches!(data_type, CqlType::Native(name) if ["text", "varchar", "ascii"].contains(&name.as_str()))
+}
+
+/// The operators a `WHERE` relation on `column` of `table` may use: partition key columns are
+/// only matched by equality, clustering columns also by range, collections by what they
+/// contain, and text by pattern where an index supports it
+fn relation_operators(table: &Table, column: &Column) -> Vec<Keyword> {
+    if table.partition_key.contains(&column.name) {
+        return operators(&["=", "IN"]);
+    }
+
+    if table.clustering_key.contains(&column.name) {
+        return operators(&["=", "IN", "<", "<=", ">", ">="]);
+    }
+
+    let mut labels = match &column.data_type {
+        CqlType::List(_) | CqlType::Set(_) => vec!["CONTAINS"],
+        CqlType::Map(..) => vec!["CONTAINS", "CONTAINS KEY"],
+        // Vectors are searched with `ORDER BY column ANN OF [...]`
+        CqlType::Vector(..) => vec![],
+        CqlType::Frozen(_) => vec!["="],
+        _ => vec!["=", "<", "<=", ">", ">="],
+    };
+
+    let pattern_indexed = table.indexes.iter().any(|index| {
+        index.column == column.name
+            && index
+                .class
+                .as_deref()
+                .is_some_and(|class| index::is_sai_class(class) || index::is_sasi_class(class))
+    });
+
+    if pattern_indexed && is_text(&column.data_type) {
+        labels.push("LIKE");
+    }
+
+    operators(&labels)
+}
+
+/// Completes the conditions after `WHERE` or `IF` at `start`: columns to restrict, then
+/// operators, then once a value is given, `continuation`
+fn conditions(
+    context: &CompletionContext,
+    table: Option<&Table>,
+    start: usize,
+    continuation: &[Keyword],
+) -> Vec<CompletionItem> {
+    let tokens = context.tokens();
+    let last = &tokens[tokens.len() - 1];
+
+    if tokens.len() - 1 == start || last.is_keyword("AND") {
+        return match tokens[start].is_keyword("IF") {
+            true => non_key_columns(context, table),
+            // Deletes are only by primary key, whole partitions or ranges of rows
+            false if context.statement.kind() == StatementKind::Delete => {
+                key_columns(context, table, start)
+            }
+            // DSE Search queries go through the pseudo-column `solr_query`
+            false
+                if context.statement.kind() == StatementKind::Select
+                    && context.targets(Database::Dse) =>
+            {
+                let mut items = columns(context, table);
+                items.extend(context.names(
+                    [(None, SOLR_QUERY.into())],
+                    CompletionItemKind::FIELD,
+                    "DSE Search query",
+                ));
+                items
+            }
+            false => columns(context, table),
+        };
+    }
+
+    if is_value(last) {
+        return context.keywords(continuation);
+    }
+
+    let previous = &tokens[tokens.len() - 2];
+
+    // `ts > toTimestamp(now()) - 1d`, for targets with arithmetic operators
+    let arithmetic = (last.is_punctuation("+") || last.is_punctuation("-")) && is_value(previous);
+
+    if arithmetic
+        && context
+            .target
+            .is_none_or(|target| features::ARITHMETIC.is_supported(target))
+    {
+        return context.literals(DURATIONS);
+    }
+
+    let column = table.zip(last.identifier()).and_then(|(table, name)| {
+        let column = table.column(&name)?;
+
+        let starts_relation =
+            previous.is_keyword("WHERE") || previous.is_keyword("AND") || previous.is_keyword("IF");
+
+        starts_relation.then_some((table, column))
+    });
+
+    match column {
+        // Conditions of lightweight transactions compare any column
+        Some(_) if tokens[start].is_keyword("IF") => {
+            context.keywords(&operators(&["=", "!=", "<", "<=", ">", ">=", "IN"]))
+        }
+        Some((table, column)) => context.keywords(&relation_operators(table, column)),
+        None => vec![],
+    }
+}
+
+/// The primary key columns that may be grouped by next: those after the last one grouped by
+fn group_by_columns(context: &CompletionContext, table: Option<&Table>) -> Vec<CompletionItem> {
+    let Some(table) = table else {
+        return vec![];
+    };
+
+    let key = group_by::primary_key(table);
+    let next = group_by::columns(&context.statement)
+        .iter()
+        .filter_map(|token| token.identifier())
+        .filter_map(|column| key.iter().position(|name| **name == column))
+        .max()
+        .map_or(0, |position| position + 1);
+
+    key[next.min(key.len())..]
+        .iter()
+        .filter_map(|name| table.column(name))
+        .flat_map(|column| {
+            context.names(
+                [(None, column.name.clone())],
+                CompletionItemKind::FIELD,
+                &column.data_type.to_string(),
+            )
+        })
+        .collect()
+}
+
+/// The clauses of a `SELECT` that may follow the `SELECT_CLAUSES` before `next`, the most
+/// commonly used first, ScyllaDB's only when it's the target
+fn select_clauses(context: &CompletionContext, next: usize) -> Vec<Keyword> {
+    let scylla = context.targets(Database::Scylla);
+
+    let mut clauses = SELECT_CLAUSES[next..]
+        .iter()
+        .filter(|clause| scylla || !SCYLLA_SELECT_CLAUSES.contains(&clause.label))
+        .copied()
+        .collect::<Vec<_>>();
+    clauses.sort_by_key(|clause| {
+        SELECT_CLAUSE_USAGE
+            .iter()
+            .position(|label| *label == clause.label)
+    });
+
+    clauses
+}
+
+fn select(context: &CompletionContext, table: Option<&Table>) -> Vec<CompletionItem> {
+    let tokens = context.tokens();
+    let last = &tokens[tokens.len() - 1];
+
+    let Some(from) = context.statement.find_keyword("FROM") else {
+        if tokens.len() == 1 {
+            let mut items = context.keywords(SELECT_MODIFIERS);
+            items.extend(columns(context, table));
+            items.extend(functions(context, TO_JSON));
+            return items;
+        }
+
+        // `(` opens the arguments of a function, e.g. `count(`
+        return if last.is_punctuation(",")
+            || last.is_punctuation("(")
+            || last.is_keyword("DISTINCT")
+            || last.is_keyword("JSON")
+        {
+            let mut items = columns(context, table);
+            items.extend(functions(context, TO_JSON));
+            items
+        } else if last.is_keyword("AS") {
+            vec![]
+        } else if last.is_identifier() || last.is_punctuation("*") || last.is_punctuation(")") {
+            context.keywords(&[FROM, AS])
+        } else {
+            vec![]
+        };
+    };
+
+    if let Some(items) = table_name(context, &tokens[from + 1..]) {
+        return items;
+    }
+
+    let Some(clause) = last_keyword(
+        context,
+        &[
+            "WHERE", "GROUP", "ORDER", "PER", "LIMIT", "ALLOW", "BYPASS", "USING",
+        ],
+    ) else {
+        return match is_table_name(&tokens[from + 1..]) {
+            true => context.keywords(&select_clauses(context, 0)),
+            false => vec![],
+        };
+    };
+
+    // The `LIMIT` of `PER PARTITION LIMIT` is part of its clause
+    let clause = match tokens[clause].is_keyword("LIMIT")
+        && clause
+            .checked_sub(1)
+            .is_some_and(|partition| tokens[partition].is_keyword("PARTITION"))
+    {
+        true => clause - 2,
+        false => clause,
+    };
+
+    match tokens[clause].text.to_ascii_uppercase().as_str() {
+        "WHERE" => conditions(
+            context,
+            table,
+            clause,
+            &[[AND].as_slice(), &select_clauses(context, 1)].concat(),
+        ),
+        "GROUP" if last.is_keyword("BY") || last.is_punctuation(",") => {
+            group_by_columns(context, table)
+        }
+        "ORDER" if last.is_keyword("BY") || last.is_punctuation(",") => columns(context, table),
+        "GROUP" if last.is_identifier() => context.keywords(&select_clauses(context, 2)),
+        "ORDER" if last.is_keyword("ASC") || last.is_keyword("DESC") => {
+            context.keywords(&select_clauses(context, 3))
+        }
+        // Vectors are ordered by their similarity to another
+        "ORDER"
+            if table
+                .zip(last.identifier())
+                .and_then(|(table, name)| table.column(&name))
+                .is_some_and(|column| matches!(column.data_type, CqlType::Vector(..))) =>
+        {
+            context.keywords(&operators(&["ANN OF"]))
+        }
+        "ORDER" if last.is_identifier() => {
+            let mut items = context.keywords(SORT_ORDERS);
+            items.extend(context.keywords(&select_clauses(context, 3)));
+            items
+        }
+        "ORDER" if is_value(last) => context.keywords(&select_clauses(context, 3)),
+        "PER" if is_value(last) => context.keywords(&select_clauses(context, 4)),
+        "LIMIT" if is_value(last) => context.keywords(&select_clauses(context, 5)),
+        "BYPASS" if last.is_keyword("CACHE") => context.keywords(&select_clauses(context, 7)),
+        "USING" if last.is_keyword("USING") => context.keywords(&[TIMEOUT]),
+        _ => vec![],
+    }
+}
+
+fn insert(context: &CompletionContext, table: Option<&Table>) -> Vec<CompletionItem> {
+    let tokens = context.tokens();
+    let last = &tokens[tokens.len() - 1];
+
+    let Some(into) = context.statement.find_keyword("INTO") else {
+        return match tokens.len() {
+            1 => context.keywords(INTO),
+            _ => vec![],
+        };
+    };
+
+    if let Some(items) = table_name(context, &tokens[into + 1..]) {
+        return items;
+    }
+
+    if let Some(json) = context.statement.find_keyword_from("JSON", into) {
+        return match &tokens[json + 1..] {
+            [] => json_row(context, table),
+            [_] => {
+                let mut items = context.keywords(DEFAULTS);
+                items.extend(insert_options(context, table, json + 1));
+                items
+            }
+            [_, default] if default.is_keyword("DEFAULT") => context.keywords(DEFAULT_VALUES),
+            [_, default, ..] if default.is_keyword("DEFAULT") => {
+                insert_options(context, table, json + 3)
+            }
+            _ => insert_options(context, table, json + 1),
+        };
+    }
+
+    let Some(open) = (into + 1..tokens.len()).find(|&i| tokens[i].is_punctuation("(")) else {
+        return match is_table_name(&tokens[into + 1..]) {
+            true => context.keywords(&[JSON]),
+            false => vec![],
+        };
+    };
+
+    let Some(close) = matching_bracket(tokens, open) else {
+        return match last.is_punctuation("(") || last.is_punctuation(",") {
+            true => columns(context, table),
+            false => vec![],
+        };
+    };
+
+    let Some(values) = context.statement.find_keyword_from("VALUES", close) else {
+        return match tokens.len() - 1 == close {
+            true => context.keywords(VALUES),
+            false => vec![],
+        };
+    };
+
+    // Nothing follows the values but `IF NOT EXISTS` and `USING`
+    let Some(values_close) = tokens
+        .get(values + 1)
+        .and_then(|_| matching_bracket(tokens, values + 1))
+    else {
+        let names = &tokens[open + 1..close];
+        let values = &tokens[(values + 2).min(tokens.len())..];
+        let parts = split_comma_ranges(values);
+
+        let column = split_comma_ranges(names)
+            .get(parts.len().saturating_sub(1))
+            .and_then(|part| names.get(part.start)?.identifier());
+        let value = parts.last().map_or(&[][..], |part| &values[part.clone()]);
+
+        if let Some(items) = table
+            .zip(column)
+            .and_then(|(table, column)| field_names(context, table, &column, value))
+        {
+            return items;
+        }
+
+        return match last.is_punctuation("(") || last.is_punctuation(",") {
+            true => functions(context, FROM_JSON),
+            false => vec![],
+        };
+    };
+
+    insert_options(context, table, values_close)
+}
+
+/// Completes the options of an `INSERT` after the row, which ends at the token `row_end`
+fn insert_options(
+    context: &CompletionContext,
+    table: Option<&Table>,
+    row_end: usize,
+) -> Vec<CompletionItem> {
+    let tokens = context.tokens();
+    let last = &tokens[tokens.len() - 1];
+
+    match last_keyword(context, &["USING"]) {
+        Some(_) if last.is_keyword("USING") || last.is_keyword("AND") => {
+            context.keywords(&using_options(context, table))
+        }
+        Some(_) if is_value(last) => context.keywords(&[AND]),
+        Some(_) => vec![],
+        None if tokens.len() - 1 == row_end => {
+            let mut items = context.keywords(&[IF_NOT_EXISTS]);
+            items.extend(context.keywords(&using(context, table)));
+            items
+        }
+        None if last.is_keyword("EXISTS") => context.keywords(&using(context, table)),
+        None => vec![],
+    }
+}
+
+fn update(context: &CompletionContext, table: Option<&Table>) -> Vec<CompletionItem> {
+    let tokens = context.tokens();
+    let last = &tokens[tokens.len() - 1];
+
+    if let Some(items) = table_name(context, &tokens[1..]) {
+        return items;
+    }
+
+    let Some(clause) = last_keyword(context, &["USING", "SET", "WHERE", "IF"]) else {
+        return match is_table_name(&tokens[1..]) {
+            true => {
+                let mut items = context.keywords(&using(context, table));
+                items.extend(context.keywords(&[SET]));
+                items
+            }
+            false => vec![],
+        };
+    };
+
+    // Inside the literal assigned to a column of a user-defined type
+    if tokens[clause].is_keyword("SET")
+        && let Some(table) = table
+    {
+        let assignments = &tokens[clause + 1..];
+        let assignment = split_comma_ranges(assignments)
+            .last()
+            .map_or(&[][..], |part| &assignments[part.clone()]);
+
+        if let [column, equals, value @ ..] = assignment
+            && equals.is_punctuation("=")
+            && let Some(column) = column.identifier()
+            && let Some(items) = field_names(context, table, &column, value)
+        {
+            return items;
+        }
+    }
+
+    match tokens[clause].text.to_ascii_uppercase().as_str() {
+        "USING" if last.is_keyword("USING") || last.is_keyword("AND") => {
+            context.keywords(&using_options(context, table))
+        }
+        "USING" if is_value(last) => context.keywords(&[AND, SET]),
+        "SET" if last.is_keyword("SET") || last.is_punctuation(",") => {
+            non_key_columns(context, table)
+        }
+        "SET" if last.is_punctuation("=") => {
+            let mut items = relative_updates(context, table, &tokens[tokens.len() - 2]);
+            items.extend(functions(context, FROM_JSON));
+            items
+        }
+        "SET" if is_value(last) => context.keywords(&[WHERE]),
+        "WHERE" => conditions(context, table, clause, &[AND, IF_EXISTS, IF]),
+        "IF" if last.is_keyword("IF") => {
+            let mut items = context.keywords(EXISTS);
+            items.extend(non_key_columns(context, table));
+            items
+        }
+        "IF" => conditions(context, table, clause, &[AND]),
+        _ => vec![],
+    }
+}
+
+fn delete(context: &CompletionContext, table: Option<&Table>) -> Vec<CompletionItem> {
+    let tokens = context.tokens();
+    let last = &tokens[tokens.len() - 1];
+
+    let Some(from) = context.statement.find_keyword("FROM") else {
+        return if tokens.len() == 1 {
+            let mut items = context.keywords(&[FROM]);
+            items.extend(deleted_columns(context, table));
+            items
+        } else if last.is_punctuation(",") {
+            deleted_columns(context, table)
+        } else if last.is_identifier() || last.is_punctuation("]") {
+            context.keywords(&[FROM])
+        } else {
+            vec![]
+        };
+    };
+
+    if let Some(items) = table_name(context, &tokens[from + 1..]) {
+        return items;
+    }
+
+    let Some(clause) = last_keyword(context, &["USING", "WHERE", "IF"]) else {
+        return match is_table_name(&tokens[from + 1..]) {
+            true => {
+                let mut clauses = vec![USING_TIMESTAMP, WHERE];
+
+                if context.targets(Database::Scylla) {
+                    clauses.insert(1, USING_TIMEOUT);
+                }
+
+                context.keywords(&clauses)
+            }
+            false => vec![],
+        };
+    };
+
+    match tokens[clause].text.to_ascii_uppercase().as_str() {
+        // Deletes only take a timestamp, and a timeout on ScyllaDB
+        "USING" if last.is_keyword("USING") || last.is_keyword("AND") => {
+            match context.targets(Database::Scylla) {
+                true => context.keywords(&[TIMESTAMP, TIMEOUT]),
+                false => context.keywords(&[TIMESTAMP]),
+            }
+        }
+        "USING" if is_value(last) => match context.targets(Database::Scylla) {
+            true => context.keywords(&[AND, WHERE]),
+            false => context.keywords(&[WHERE]),
+        },
+        "WHERE" => conditions(context, table, clause, &[AND, IF_EXISTS, IF]),
+        "IF" if last.is_keyword("IF") => {
+            let mut items = context.keywords(EXISTS);
+            items.extend(non_key_columns(context, table));
+            items
+        }
+        "IF" => conditions(context, table, clause, &[AND]),
+        _ => vec![],
+    }
+}
+
+/// Completes the next clause of a `SELECT`, `INSERT`, `UPDATE` or `DELETE`, or the table,
+/// columns or keywords the current one expects. Returns `None` for other statements, and where
+/// no continuation is known, e.g. in the middle of a value.
+pub fn completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    let kind = context.statement.kind();
+
+    if context.tokens().is_empty() {
+        return None;
+    }
+
+    // The table may be named after the cursor, e.g. when completing the selected columns
+    let table = context
+        .schema
+        .statement_table(&context.full_statement, context.keyspace.as_deref());
+
+    // Only ScyllaDB's clauses follow `ALLOW FILTERING`, which otherwise ends a `SELECT`
+    if kind == StatementKind::Select
+        && context
+            .tokens()
+            .last()
+            .is_some_and(|last| last.is_keyword("FILTERING"))
+    {
+        return Some(context.keywords(&select_clauses(context, 6)));
+    }
+
+    let items = match kind {
+        StatementKind::Select => select(context, table),
+        StatementKind::Insert => insert(context, table),
+        StatementKind::Update => update(context, table),
+        StatementKind::Delete => delete(context, table),
+        _ => return None,
+    };
+
+    (!items.is_empty()).then_some(items)
+}
@@ -0,0 +1,205 @@
+//! Completion for `CREATE FUNCTION` and `CREATE AGGREGATE`.
+
+use tower_lsp::lsp_types::CompletionItem;
+
+use super::{CompletionContext, Keyword};
+use crate::{
+    cql_type::CqlType,
+    function,
+    lexer::Token,
+    statement::{StatementKind, matching_bracket, split_commas},
+};
+
+const NULL_INPUT: &[Keyword] = &[
+    Keyword::new(
+        "CALLED ON NULL INPUT",
+        "The function is called even when some of its arguments are null, and must handle them.",
+    ),
+    Keyword::new(
+        "RETURNS NULL ON NULL INPUT",
+        "The function isn't called when any of its arguments is null: the result is null.",
+    ),
+];
+
+const RETURNS: &[Keyword] = &[Keyword::new("RETURNS", "The return type of the function.")];
+
+const LANGUAGE: &[Keyword] = &[Keyword::new(
+    "LANGUAGE",
+    "The language of the function body. User-defined functions must be enabled in `cassandra.yaml`.",
+)];
+
+const LANGUAGES: &[Keyword] = &[
+    Keyword::new("java", "The body is the body of a Java method."),
+    Keyword::new(
+        "javascript",
+        "The body is a JavaScript expression. Removed in Cassandra 5.0.",
+    ),
+];
+
+const AS: &[Keyword] = &[Keyword::new(
+    "AS",
+    "The function body, usually given as a `$$ ... $$` string.",
+)];
+
+const SFUNC: &[Keyword] = &[Keyword::new(
+    "SFUNC",
+    "The state function, called for each row with the current state followed by the aggregate arguments, returning the new state.",
+)];
+
+const STYPE: &[Keyword] = &[Keyword::new(
+    "STYPE",
+    "The type of the state, the first argument and the return type of the state function.",
+)];
+
+const AFTER_STATE_TYPE: &[Keyword] = &[
+    Keyword::new(
+        "FINALFUNC",
+        "The final function, called with the last state to compute the result of the aggregate.",
+    ),
+    Keyword::new("INITCOND", "The initial state. Defaults to null."),
+];
+
+const INITCOND: &[Keyword] = &[Keyword::new(
+    "INITCOND",
+    "The initial state. Defaults to null.",
+)];
+
+/// Whether `tokens` are exactly one complete type
+fn is_complete_type(tokens: &[Token]) -> bool {
+    let mut i = 0;
+
+    CqlType::parse(tokens, &mut i).is_some() && i == tokens.len()
+}
+
+/// Whether the cursor is where a type (or a type parameter) is expected, given the tokens
+/// typed so far for it
+fn expects_type(tokens: &[Token]) -> bool {
+    tokens.is_empty()
+        || tokens
+            .last()
+            .is_some_and(|last| last.is_punctuation("<") || last.is_punctuation(","))
+}
+
+/// Completes `CREATE FUNCTION` and `CREATE AGGREGATE` clause by clause. Returns `None` for
+/// other statements.
+pub fn completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    let statement = &context.statement;
+    let tokens = statement.tokens;
+
+    let object = match statement.kind() {
+        StatementKind::CreateFunction => "FUNCTION",
+        StatementKind::CreateAggregate => "AGGREGATE",
+        _ => return None,
+    };
+
+    let object = statement.find_keyword(object)?;
+
+    let Some(open) = (object + 1..tokens.len()).find(|&i| tokens[i].is_punctuation("(")) else {
+        return Some(vec![]);
+    };
+
+    let Some(close) = matching_bracket(tokens, open) else {
+        // Arguments are `name type` for functions and just `type` for aggregates
+        let arguments = split_commas(&tokens[open + 1..]);
+        let argument = arguments.last().copied().unwrap_or_default();
+
+        let argument_type = match statement.kind() {
+            StatementKind::CreateFunction if argument.is_empty() => return Some(vec![]),
+            StatementKind::CreateFunction => &argument[1..],
+            _ => argument,
+        };
+
+        return Some(match expects_type(argument_type) {
+            true => context.type_names(),
+            false => vec![],
+        });
+    };
+
+    let rest = &tokens[close + 1..];
+
+    let items = match statement.kind() {
+        StatementKind::CreateFunction => function_clause(context, rest),
+        _ => aggregate_clause(context, rest),
+    };
+
+    Some(items)
+}
+
+/// Completes what follows the arguments of `CREATE FUNCTION`
+fn function_clause(context: &CompletionContext, rest: &[Token]) -> Vec<CompletionItem> {
+    let find = |keyword: &str| rest.iter().position(|token| token.is_keyword(keyword));
+
+    let Some(input) = find("INPUT") else {
+        return context.keywords(NULL_INPUT);
+    };
+
+    let Some(returns) = rest[input..]
+        .iter()
+        .position(|token| token.is_keyword("RETURNS"))
+        .map(|i| input + i)
+    else {
+        return context.keywords(RETURNS);
+    };
+
+    let Some(language) = find("LANGUAGE") else {
+        let return_type = &rest[returns + 1..];
+
+        return match is_complete_type(return_type) {
+            true => context.keywords(LANGUAGE),
+            false if expects_type(return_type) => context.type_names(),
+            false => vec![],
+        };
+    };
+
+    match &rest[language + 1..] {
+        [] => context.literals(LANGUAGES),
+        [_] => context.keywords(AS),
+        _ => vec![],
+    }
+}
+
+/// Completes what follows the arguments of `CREATE AGGREGATE`
+fn aggregate_clause(context: &CompletionContext, rest: &[Token]) -> Vec<CompletionItem> {
+    let find = |keyword: &str| rest.iter().position(|token| token.is_keyword(keyword));
+
+    // The state and final functions live in the keyspace of the aggregate
+    let aggregate = function::parse_aggregate(&context.full_statement);
+    let keyspace = aggregate
+        .as_ref()
+        .and_then(|aggregate| aggregate.name.keyspace.as_deref());
+
+    let Some(sfunc) = find("SFUNC") else {
+        return context.keywords(SFUNC);
+    };
+
+    let Some(stype) = find("STYPE") else {
+        return match &rest[sfunc + 1..] {
+            [] => context.function_names(keyspace),
+            [_] => context.keywords(STYPE),
+            _ => vec![],
+        };
+    };
+
+    let finalfunc = find("FINALFUNC");
+    let initcond = find("INITCOND");
+
+    if initcond.is_some() {
+        return vec![];
+    }
+
+    let Some(finalfunc) = finalfunc else {
+        let state_type = &rest[stype + 1..];
+
+        return match is_complete_type(state_type) {
+            true => context.keywords(AFTER_STATE_TYPE),
+            false if expects_type(state_type) => context.type_names(),
+            false => vec![],
+        };
+    };
+
+    match &rest[finalfunc + 1..] {
+        [] => context.function_names(keyspace),
+        [_] => context.keywords(INITCOND),
+        _ => vec![],
+    }
+}
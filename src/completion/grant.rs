@@ -0,0 +1,215 @@
+//! Completion for `GRANT`, `REVOKE` and `LIST`: permissions, resources and roles, and for
+//! DSE's `RESTRICT` and `UNRESTRICT` alike.
+
+use tower_lsp::lsp_types::CompletionItem;
+
+use super::{CompletionContext, Keyword};
+use crate::lexer::Token;
+
+const PERMISSIONS: &[Keyword] = &[
+    Keyword::new(
+        "ALL PERMISSIONS",
+        "All permissions applicable to the resource.",
+    ),
+    Keyword::new(
+        "SELECT",
+        "Read data from a table with `SELECT`, or call a function.",
+    ),
+    Keyword::new(
+        "MODIFY",
+        "Change data with `INSERT`, `UPDATE`, `DELETE` and `TRUNCATE`.",
+    ),
+    Keyword::new(
+        "CREATE",
+        "Create keyspaces, tables, functions or roles within the resource.",
+    ),
+    Keyword::new(
+        "ALTER",
+        "Alter keyspaces, tables, functions or roles within the resource.",
+    ),
+    Keyword::new(
+        "DROP",
+        "Drop keyspaces, tables, functions or roles within the resource.",
+    ),
+    Keyword::new(
+        "AUTHORIZE",
+        "Grant and revoke permissions on the resource to other roles.",
+    ),
+    Keyword::new(
+        "DESCRIBE",
+        "List roles, or describe the resource with `DESCRIBE`.",
+    ),
+    Keyword::new("EXECUTE", "Call functions, or invoke MBean operations."),
+];
+
+const RESOURCES: &[Keyword] = &[
+    Keyword::new("ALL KEYSPACES", "Every keyspace and the tables in them."),
+    Keyword::new("KEYSPACE", "A keyspace and the tables in it."),
+    Keyword::new("TABLE", "A single table."),
+    Keyword::new("ALL ROLES", "Every role."),
+    Keyword::new("ROLE", "A single role."),
+    Keyword::new("ALL FUNCTIONS", "Every function in every keyspace."),
+    Keyword::new("ALL FUNCTIONS IN KEYSPACE", "Every function in a keyspace."),
+    Keyword::new(
+        "FUNCTION",
+        "A single function, given with its argument types.",
+    ),
+    Keyword::new("ALL MBEANS", "Every MBean."),
+    Keyword::new("MBEAN", "A single MBean, or the MBeans matching a pattern."),
+];
+
+/// The words that may follow `ALL` in a resource, for when `ALL ` has already been typed
+const ALL_RESOURCES: &[Keyword] = &[
+    Keyword::new("KEYSPACES", "Every keyspace and the tables in them."),
+    Keyword::new("ROLES", "Every role."),
+    Keyword::new("FUNCTIONS", "Every function in every keyspace."),
+    Keyword::new("MBEANS", "Every MBean."),
+];
+
+const ALL_PERMISSIONS: &[Keyword] = &[Keyword::new(
+    "PERMISSIONS",
+    "All permissions applicable to the resource.",
+)];
+
+const ON: &[Keyword] = &[Keyword::new(
+    "ON",
+    "The resource the permission applies to.",
+)];
+
+const IN_KEYSPACE: &[Keyword] = &[Keyword::new("IN KEYSPACE", "Every function in a keyspace.")];
+
+const KEYSPACE: &[Keyword] = &[Keyword::new("KEYSPACE", "Every function in a keyspace.")];
+
+const TO: &[Keyword] = &[Keyword::new("TO", "The role receiving the permission.")];
+
+const FROM: &[Keyword] = &[Keyword::new("FROM", "The role losing the permission.")];
+
+const OF: &[Keyword] = &[Keyword::new(
+    "OF",
+    "The role whose permissions or roles are listed, including those it inherits.",
+)];
+
+const NORECURSIVE: &[Keyword] = &[Keyword::new(
+    "NORECURSIVE",
+    "Only what's granted to the role itself, not what it inherits from its roles.",
+)];
+
+const USING: &[Keyword] = &[Keyword::new(
+    "USING",
+    "The text column of the partition key matched against the filters of the rows granted.",
+)];
+
+/// What `LIST` lists besides permissions
+const LISTED: &[Keyword] = &[
+    Keyword::new("ROLES", "The roles, or those granted to a role with `OF`."),
+    Keyword::new("USERS", "The roles that can log in."),
+];
+
+/// Completes DSE's `RESTRICT ROWS ON <table> USING <column>` and `UNRESTRICT ROWS ON <table>`
+fn rows(context: &CompletionContext, restrict: bool) -> Vec<CompletionItem> {
+    let tokens = context.tokens();
+
+    let Some(on) = context.statement.find_keyword("ON") else {
+        return match tokens.len() {
+            2 => context.keywords(ON),
+            _ => vec![],
+        };
+    };
+
+    if let Some(using) = context.statement.find_keyword("USING") {
+        let table = context
+            .schema
+            .statement_table(&context.full_statement, context.keyspace.as_deref());
+
+        return match (tokens.len() - using, table) {
+            (1, Some(table)) => context.column_names(table),
+            _ => vec![],
+        };
+    }
+
+    match &tokens[on + 1..] {
+        [] => context.table_names(None),
+        [keyspace, dot] if dot.is_punctuation(".") => context.table_names(keyspace.identifier()),
+        [.., last] if restrict && last.is_identifier() => context.keywords(USING),
+        _ => vec![],
+    }
+}
+
+/// Completes `GRANT <permission> ON <resource> TO <role>`,
+/// `REVOKE <permission> ON <resource> FROM <role>` and
+/// `LIST <permission> [ON <resource>] [OF <role>]`, along with DSE's `RESTRICT` like `GRANT`
+/// and `UNRESTRICT` like `REVOKE`. Returns `None` for other statements.
+pub fn completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    let tokens = context.tokens();
+    let first = tokens.first()?;
+    let listing = first.is_keyword("LIST");
+    let restrict = first.is_keyword("RESTRICT");
+
+    if (restrict || first.is_keyword("UNRESTRICT"))
+        && tokens.get(1).is_some_and(|rows| rows.is_keyword("ROWS"))
+    {
+        return Some(rows(context, restrict));
+    }
+
+    let (target, target_keyword) = if first.is_keyword("GRANT") || restrict {
+        (TO, "TO")
+    } else if first.is_keyword("REVOKE") || first.is_keyword("UNRESTRICT") {
+        (FROM, "FROM")
+    } else if listing {
+        (OF, "OF")
+    } else {
+        return None;
+    };
+
+    if let Some(i) = context.statement.find_keyword(target_keyword) {
+        return Some(match (listing, tokens.len() - i - 1) {
+            (true, 1) => context.keywords(NORECURSIVE),
+            (true, 2..) => vec![],
+            _ => context.role_names(),
+        });
+    }
+
+    let Some(on) = context.statement.find_keyword("ON") else {
+        return Some(match &tokens[1..] {
+            [] if listing => [context.keywords(PERMISSIONS), context.keywords(LISTED)].concat(),
+            [] => context.keywords(PERMISSIONS),
+            [all] if all.is_keyword("ALL") => context.keywords(ALL_PERMISSIONS),
+            [roles] if listing && roles.is_keyword("ROLES") => {
+                [context.keywords(OF), context.keywords(NORECURSIVE)].concat()
+            }
+            [users] if listing && users.is_keyword("USERS") => vec![],
+            _ if listing => [context.keywords(ON), context.keywords(OF)].concat(),
+            _ => context.keywords(ON),
+        });
+    };
+
+    let resource = &tokens[on + 1..];
+    let is = |token: &Token, keyword: &str| token.is_keyword(keyword);
+
+    let items = match resource {
+        [] => context.keywords(RESOURCES),
+        [all] if is(all, "ALL") => context.keywords(ALL_RESOURCES),
+        [all, functions] if is(all, "ALL") && is(functions, "FUNCTIONS") => {
+            let mut items = context.keywords(IN_KEYSPACE);
+            items.extend(context.keywords(target));
+            items
+        }
+        [.., all, functions, r#in] if is(all, "ALL") && is(functions, "FUNCTIONS") => {
+            match is(r#in, "IN") {
+                true => context.keywords(KEYSPACE),
+                false => context.keywords(target),
+            }
+        }
+        [.., keyspace] if is(keyspace, "KEYSPACE") => context.keyspace_names(),
+        [table] if is(table, "TABLE") => context.table_names(None),
+        [table, keyspace, dot] if is(table, "TABLE") && dot.is_punctuation(".") => {
+            context.table_names(keyspace.identifier())
+        }
+        [role] if is(role, "ROLE") => context.role_names(),
+        [function] if is(function, "FUNCTION") => vec![],
+        [mbean] if is(mbean, "MBEAN") => vec![],
+        _ => context.keywords(target),
+    };
+
+    Some(items)
+}
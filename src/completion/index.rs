@@ -0,0 +1,256 @@
+//! Completion for `CREATE INDEX` and `CREATE CUSTOM INDEX`, including storage-attached index
+//! options.
+
+use tower_lsp::lsp_types::CompletionItem;
+
+use super::{CompletionContext, Keyword};
+use crate::{
+    index,
+    statement::{StatementKind, matching_bracket},
+};
+
+const AFTER_INDEX: &[Keyword] = &[
+    Keyword::new(
+        "IF NOT EXISTS",
+        "Does nothing, rather than failing, if an index with the same name exists.",
+    ),
+    Keyword::new("ON", "The table to index."),
+];
+
+const ON: &[Keyword] = &[Keyword::new("ON", "The table to index.")];
+
+const TARGETS: &[Keyword] = &[
+    Keyword::new(
+        "KEYS",
+        "Indexes the keys of a map column, for `CONTAINS KEY` queries.",
+    ),
+    Keyword::new(
+        "VALUES",
+        "Indexes the values of a collection column, for `CONTAINS` queries. This is the default for non-frozen collections.",
+    ),
+    Keyword::new(
+        "ENTRIES",
+        "Indexes the entries of a map column, for `map['key'] = value` queries.",
+    ),
+    Keyword::new(
+        "FULL",
+        "Indexes whole values of a frozen collection column, for `=` queries on the collection.",
+    ),
+];
+
+const USING: &[Keyword] = &[Keyword::new(
+    "USING",
+    "The index implementation, given as a class name or an alias such as `'sai'`.",
+)];
+
+const CLASSES: &[Keyword] = &[
+    Keyword::new(
+        "'sai'",
+        "Storage-attached index (Cassandra 5.0+): indexes are stored with the SSTables, and support high-cardinality columns, range queries and vector search.",
+    ),
+    Keyword::new(
+        "'StorageAttachedIndex'",
+        "Storage-attached index (Cassandra 5.0+), by class name.",
+    ),
+    Keyword::new(
+        "'org.apache.cassandra.index.sasi.SASIIndex'",
+        "SSTable-attached secondary index: supports `LIKE` prefix and contains queries. Experimental, superseded by SAI.",
+    ),
+];
+
+const WITH_OPTIONS: &[Keyword] = &[Keyword::new(
+    "WITH OPTIONS",
+    "`WITH OPTIONS = { 'option': 'value', ... }`\n\nOptions of the index implementation.",
+)];
+
+const OPTIONS: &[Keyword] = &[Keyword::new(
+    "OPTIONS",
+    "`OPTIONS = { 'option': 'value', ... }`\n\nOptions of the index implementation.",
+)];
+
+const SAI_OPTIONS: &[Keyword] = &[
+    Keyword::new(
+        "'case_sensitive'",
+        "Whether text matching is case-sensitive. Defaults to `true`.",
+    ),
+    Keyword::new(
+        "'normalize'",
+        "Whether text is Unicode-normalized before matching. Defaults to `false`.",
+    ),
+    Keyword::new(
+        "'ascii'",
+        "Whether non-ASCII characters are folded to their ASCII equivalents. Defaults to `false`.",
+    ),
+    Keyword::new(
+        "'similarity_function'",
+        "The similarity function of a vector index. Defaults to `'COSINE'`.",
+    ),
+    Keyword::new(
+        "'index_analyzer'",
+        "The analyzer applied to values when indexing, as a JSON analyzer configuration.",
+    ),
+    Keyword::new(
+        "'query_analyzer'",
+        "The analyzer applied to query terms, when different from the index analyzer.",
+    ),
+];
+
+const SASI_OPTIONS: &[Keyword] = &[
+    Keyword::new(
+        "'mode'",
+        "`'PREFIX'` (default) for prefix `LIKE` queries, `'CONTAINS'` for contains and suffix queries, or `'SPARSE'` for high-cardinality numeric columns.",
+    ),
+    Keyword::new("'analyzer_class'", "The class analyzing text values."),
+    Keyword::new(
+        "'case_sensitive'",
+        "Whether text matching is case-sensitive. Defaults to `true`.",
+    ),
+    Keyword::new(
+        "'analyzed'",
+        "Whether values are analyzed with the analyzer class.",
+    ),
+];
+
+const BOOLEAN_VALUES: &[Keyword] = &[
+    Keyword::new("'true'", "Enables the option."),
+    Keyword::new("'false'", "Disables the option."),
+];
+
+const SIMILARITY_FUNCTIONS: &[Keyword] = &[
+    Keyword::new(
+        "'COSINE'",
+        "Cosine similarity, insensitive to vector magnitude.",
+    ),
+    Keyword::new(
+        "'DOT_PRODUCT'",
+        "Dot product, for normalized vectors. Faster than cosine similarity.",
+    ),
+    Keyword::new("'EUCLIDEAN'", "Euclidean distance."),
+];
+
+const SASI_MODES: &[Keyword] = &[
+    Keyword::new("'PREFIX'", "Prefix `LIKE 'abc%'` queries."),
+    Keyword::new("'CONTAINS'", "Contains and suffix `LIKE '%abc%'` queries."),
+    Keyword::new("'SPARSE'", "High-cardinality numeric columns."),
+];
+
+/// Completes `CREATE [CUSTOM] INDEX [IF NOT EXISTS] [name] ON table (target) [USING 'class'
+/// [WITH OPTIONS = {...}]]`. Returns `None` for other statements.
+pub fn completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    let statement = &context.statement;
+    let tokens = statement.tokens;
+
+    if statement.kind() != StatementKind::CreateIndex {
+        return None;
+    }
+
+    let last = tokens.last()?;
+    let is = |keyword: &str| last.is_keyword(keyword);
+
+    let Some(on) = statement.find_keyword("ON") else {
+        let items = if is("INDEX") {
+            context.keywords(AFTER_INDEX)
+        } else if is("IF") || is("NOT") {
+            context.keywords(&AFTER_INDEX[..1])
+        } else if last.is_identifier() {
+            context.keywords(ON)
+        } else {
+            vec![]
+        };
+
+        return Some(items);
+    };
+
+    // The table may be named before the cursor, but the rest of the definition may follow it
+    let definition = index::parse(&context.full_statement);
+    let table = definition
+        .as_ref()
+        .and_then(|definition| definition.table.as_ref())
+        .and_then(|name| {
+            let keyspace = name.keyspace.as_deref().or(context.keyspace.as_deref());
+            context.schema.table(keyspace, &name.name)
+        });
+
+    let Some(open) = (on + 1..tokens.len()).find(|&i| tokens[i].is_punctuation("(")) else {
+        let items = match &tokens[on + 1..] {
+            [] => context.table_names(None),
+            [keyspace, dot] if dot.is_punctuation(".") => {
+                context.table_names(keyspace.identifier())
+            }
+            _ => vec![],
+        };
+
+        return Some(items);
+    };
+
+    let Some(close) = matching_bracket(tokens, open) else {
+        let items = match &tokens[open + 1..] {
+            [] => {
+                let mut items = table
+                    .map(|table| context.column_names(table))
+                    .unwrap_or_default();
+                items.extend(context.keywords(TARGETS));
+                items
+            }
+            [_, paren] if paren.is_punctuation("(") => table
+                .map(|table| {
+                    context
+                        .column_names(table)
+                        .into_iter()
+                        .filter(|item| {
+                            table
+                                .column(&item.label)
+                                .is_some_and(|column| column.data_type.is_collection())
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            _ => vec![],
+        };
+
+        return Some(items);
+    };
+
+    let Some(using) = statement.find_keyword_from("USING", close) else {
+        return Some(match tokens.len() - 1 == close {
+            true => context.keywords(USING),
+            false => vec![],
+        });
+    };
+
+    let class = tokens.get(using + 1).and_then(|token| token.string_value());
+
+    let Some(class) = class else {
+        return Some(context.literals(CLASSES));
+    };
+
+    let Some(with) = statement.find_keyword_from("WITH", using) else {
+        return Some(context.keywords(WITH_OPTIONS));
+    };
+
+    let options = if index::is_sai_class(&class) {
+        SAI_OPTIONS
+    } else if class.ends_with("SASIIndex") {
+        SASI_OPTIONS
+    } else {
+        &[]
+    };
+
+    let items = match &tokens[with + 1..] {
+        [] => context.keywords(OPTIONS),
+        [.., last] if last.is_punctuation("{") || last.is_punctuation(",") => {
+            context.literals(options)
+        }
+        [.., key, colon] if colon.is_punctuation(":") => match key.string_value().as_deref() {
+            Some("case_sensitive" | "normalize" | "ascii" | "analyzed") => {
+                context.literals(BOOLEAN_VALUES)
+            }
+            Some("similarity_function") => context.literals(SIMILARITY_FUNCTIONS),
+            Some("mode") => context.literals(SASI_MODES),
+            _ => vec![],
+        },
+        _ => vec![],
+    };
+
+    Some(items)
+}
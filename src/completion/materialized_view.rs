@@ -0,0 +1,156 @@
+//! Completion for `CREATE MATERIALIZED VIEW`.
+
+use tower_lsp::lsp_types::CompletionItem;
+
+use super::{CompletionContext, Keyword};
+use crate::{
+    materialized_view,
+    statement::{StatementKind, matching_bracket},
+};
+
+const VIEW: &[Keyword] = &[Keyword::new(
+    "VIEW",
+    "Creates a materialized view: a table maintained by the server from a base table, keyed differently.",
+)];
+
+const AS_SELECT: &[Keyword] = &[Keyword::new(
+    "AS SELECT",
+    "The columns of the base table the view includes.",
+)];
+
+const SELECT: &[Keyword] = &[Keyword::new(
+    "SELECT",
+    "The columns of the base table the view includes.",
+)];
+
+const FROM: &[Keyword] = &[Keyword::new("FROM", "The base table of the view.")];
+
+const WHERE: &[Keyword] = &[Keyword::new(
+    "WHERE",
+    "Every column of the view primary key must be restricted with `IS NOT NULL`.",
+)];
+
+const IS_NOT_NULL: &[Keyword] = &[Keyword::new(
+    "IS NOT NULL",
+    "Excludes base rows where the column is null, as required for view primary key columns.",
+)];
+
+const NOT_NULL: &[Keyword] = &[Keyword::new(
+    "NOT NULL",
+    "Excludes base rows where the column is null, as required for view primary key columns.",
+)];
+
+const NULL: &[Keyword] = &[Keyword::new(
+    "NULL",
+    "Excludes base rows where the column is null, as required for view primary key columns.",
+)];
+
+const AFTER_RESTRICTION: &[Keyword] = &[
+    Keyword::new("AND", "Restricts another column."),
+    Keyword::new(
+        "PRIMARY KEY",
+        concat!(
+            "The primary key of the view. It must include every primary key column of the base ",
+            "table, plus at most one other column of the base table."
+        ),
+    ),
+];
+
+const KEY: &[Keyword] = &[Keyword::new(
+    "KEY",
+    concat!(
+        "The primary key of the view. It must include every primary key column of the base ",
+        "table, plus at most one other column of the base table."
+    ),
+)];
+
+const WITH: &[Keyword] = &[Keyword::new("WITH", "Sets table options of the view.")];
+
+/// Completes `CREATE MATERIALIZED VIEW name AS SELECT ... FROM base WHERE ... PRIMARY KEY (...)`
+/// clause by clause, offering the base table columns where columns go. Returns `None` for
+/// other statements.
+pub fn completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    let statement = &context.statement;
+    let tokens = statement.tokens;
+
+    if statement.kind() != StatementKind::CreateMaterializedView {
+        return None;
+    }
+
+    // The base table may well be named after the cursor, e.g. when filling in the select list
+    let view = materialized_view::parse(&context.full_statement);
+    let base = view
+        .as_ref()
+        .and_then(|view| view.base_table(context.schema, context.keyspace.as_deref()));
+    let columns = || {
+        base.map(|base| context.column_names(base))
+            .unwrap_or_default()
+    };
+
+    let last = tokens.last()?;
+    let is = |keyword: &str| last.is_keyword(keyword);
+
+    let Some(select) = statement.find_keyword("SELECT") else {
+        let items = match statement.find_keyword("AS") {
+            Some(_) => context.keywords(SELECT),
+            None if tokens.len() == 2 => context.keywords(VIEW),
+            None if ["VIEW", "IF", "NOT", "EXISTS"].iter().any(|k| is(k)) => vec![],
+            None if last.is_punctuation(".") => vec![],
+            None => context.keywords(AS_SELECT),
+        };
+
+        return Some(items);
+    };
+
+    let Some(from) = statement.find_keyword("FROM") else {
+        let items = match tokens.len() - 1 == select || last.is_punctuation(",") {
+            true => columns(),
+            false => context.keywords(FROM),
+        };
+
+        return Some(items);
+    };
+
+    let Some(r#where) = statement.find_keyword("WHERE") else {
+        let items = match &tokens[from + 1..] {
+            [] => context.table_names(None),
+            [keyspace, dot] if dot.is_punctuation(".") => {
+                context.table_names(keyspace.identifier())
+            }
+            _ => context.keywords(WHERE),
+        };
+
+        return Some(items);
+    };
+
+    let Some(primary) = statement.find_keyword("PRIMARY") else {
+        let items = if tokens.len() - 1 == r#where || is("AND") {
+            columns()
+        } else if is("IS") {
+            context.keywords(NOT_NULL)
+        } else if is("NOT") {
+            context.keywords(NULL)
+        } else if is("NULL") {
+            context.keywords(AFTER_RESTRICTION)
+        } else if last.is_identifier() {
+            context.keywords(IS_NOT_NULL)
+        } else {
+            vec![]
+        };
+
+        return Some(items);
+    };
+
+    let items = match &tokens[primary + 1..] {
+        [] => context.keywords(KEY),
+        [key, ..] if !key.is_keyword("KEY") => vec![],
+        [_] => vec![],
+        _ if statement.find_keyword_from("WITH", primary).is_some() => vec![],
+        _ if last.is_punctuation("(") || last.is_punctuation(",") => columns(),
+        // The key is complete once its brackets are balanced
+        _ if matching_bracket(tokens, primary + 2).is_some() => context.keywords(WITH),
+        _ => vec![],
+    };
+
+    Some(items)
+}
@@ -0,0 +1,177 @@
+//! Completion for the `WITH` clause of `CREATE ROLE` and `ALTER ROLE`.
+
+use tower_lsp::lsp_types::CompletionItem;
+
+use super::{CompletionContext, Keyword};
+use crate::{lexer::Token, statement::StatementKind};
+
+const PASSWORD: &str = concat!(
+    "`PASSWORD = 'password'`\n",
+    "\nThe password the role logs in with when using internal authentication. ",
+    "Enclose the password in single quotation marks."
+);
+
+const HASHED_PASSWORD: &str = concat!(
+    "`HASHED PASSWORD = 'hash'`\n",
+    "\nSets the password from its bcrypt hash instead of the plain text, so the password ",
+    "never appears in the statement or in logs. Cannot be combined with `PASSWORD`."
+);
+
+const LOGIN: &str = concat!(
+    "`LOGIN = true | false`\n",
+    "\nWhether the role may log in, i.e. be used as the identity of a client connection. ",
+    "Defaults to `false`, which is typical of roles that only group permissions."
+);
+
+const SUPERUSER: &str = concat!(
+    "`SUPERUSER = true | false`\n",
+    "\nWhether the role is a superuser, bypassing all permission checks. Defaults to ",
+    "`false`. Only a superuser can create or alter a superuser role."
+);
+
+const CUSTOM_OPTIONS: &str = concat!(
+    "`OPTIONS = { 'option': 'value', ... }`\n",
+    "\nOptions passed as-is to a custom authenticator. Not supported by the built-in ",
+    "`PasswordAuthenticator`."
+);
+
+const ACCESS_TO_DATACENTERS: &str = concat!(
+    "`ACCESS TO DATACENTERS { 'dc1', 'dc2' }`\n",
+    "\nRestricts the datacenters the role may log in to when network authorization is ",
+    "enabled."
+);
+
+const ACCESS_TO_ALL_DATACENTERS: &str = concat!(
+    "`ACCESS TO ALL DATACENTERS`\n",
+    "\nLets the role log in to every datacenter, lifting any earlier restriction."
+);
+
+const OPTIONS: &[Keyword] = &[
+    Keyword::new("PASSWORD", PASSWORD),
+    Keyword::new("HASHED PASSWORD", HASHED_PASSWORD),
+    Keyword::new("LOGIN", LOGIN),
+    Keyword::new("SUPERUSER", SUPERUSER),
+    Keyword::new("OPTIONS", CUSTOM_OPTIONS),
+    Keyword::new("ACCESS TO DATACENTERS", ACCESS_TO_DATACENTERS),
+    Keyword::new("ACCESS TO ALL DATACENTERS", ACCESS_TO_ALL_DATACENTERS),
+];
+
+const BOOLEANS: &[Keyword] = &[
+    Keyword::new("TRUE", "Enables the option."),
+    Keyword::new("FALSE", "Disables the option."),
+];
+
+const WITH: &[Keyword] = &[Keyword::new("WITH", "Sets options of the role.")];
+
+const AND: &[Keyword] = &[Keyword::new("AND", "Sets another option of the role.")];
+
+/// Returns the name of the option given by `tokens`, e.g. `HASHED PASSWORD`
+fn option_name(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .take_while(|token| !token.is_punctuation("=") && token.is_identifier())
+        .map(|token| token.text.to_ascii_uppercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the part of an option name shared by options that exclude each other, i.e.
+/// `PASSWORD` and `HASHED PASSWORD`, and the `ACCESS TO` forms
+fn exclusion_key(name: &str) -> &str {
+    let name = name.trim_start_matches("HASHED ");
+    name.split(" TO ").next().unwrap_or(name)
+}
+
+/// Completes the options of `CREATE ROLE ... WITH` and `ALTER ROLE ... WITH`. Returns `None`
+/// for other statements.
+pub fn completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    let statement = &context.statement;
+    let tokens = statement.tokens;
+
+    match statement.kind() {
+        StatementKind::CreateRole | StatementKind::AlterRole => {}
+        _ => return None,
+    }
+
+    let Some(with) = statement.find_keyword("WITH") else {
+        let name = (2..tokens.len()).find(|&i| {
+            !["IF", "NOT", "EXISTS"]
+                .iter()
+                .any(|k| tokens[i].is_keyword(k))
+        });
+
+        return Some(match name {
+            // `ALTER ROLE <role>` names an existing role
+            None if statement.kind() == StatementKind::AlterRole => context.role_names(),
+            None => vec![],
+            Some(_) => context.keywords(WITH),
+        });
+    };
+
+    // Options are separated by top-level `AND`s
+    let mut first = with + 1;
+    let mut previous = vec![];
+
+    while let Some(and) = statement.find_keyword_from("AND", first) {
+        previous.push(option_name(&tokens[first..and]));
+        first = and + 1;
+    }
+
+    let option = &tokens[first..];
+
+    let items = match option {
+        [] => {
+            // Options can't be set twice
+            let remaining = OPTIONS
+                .iter()
+                .filter(|keyword| {
+                    let key = exclusion_key(keyword.label);
+                    !previous.iter().any(|set| exclusion_key(set) == key)
+                })
+                .copied()
+                .collect::<Vec<_>>();
+
+            context.keywords(&remaining)
+        }
+        [name] if name.is_keyword("HASHED") => {
+            context.keywords(&[Keyword::new("PASSWORD", HASHED_PASSWORD)])
+        }
+        [name, ..] if name.is_keyword("ACCESS") => match &option[1..] {
+            [] => context.keywords(&[
+                Keyword::new("TO DATACENTERS", ACCESS_TO_DATACENTERS),
+                Keyword::new("TO ALL DATACENTERS", ACCESS_TO_ALL_DATACENTERS),
+            ]),
+            [to] if to.is_keyword("TO") => context.keywords(&[
+                Keyword::new("DATACENTERS", ACCESS_TO_DATACENTERS),
+                Keyword::new("ALL DATACENTERS", ACCESS_TO_ALL_DATACENTERS),
+            ]),
+            [to, all] if to.is_keyword("TO") && all.is_keyword("ALL") => {
+                context.keywords(&[Keyword::new("DATACENTERS", ACCESS_TO_ALL_DATACENTERS)])
+            }
+            [to, all, _] if to.is_keyword("TO") && all.is_keyword("ALL") => context.keywords(AND),
+            [.., last] if last.is_punctuation("}") => context.keywords(AND),
+            _ => vec![],
+        },
+        [name, equals] if equals.is_punctuation("=") => {
+            match name.is_keyword("LOGIN") || name.is_keyword("SUPERUSER") {
+                true => context.keywords(BOOLEANS),
+                false => vec![],
+            }
+        }
+        [.., last] if last.is_punctuation("=") || last.is_punctuation("{") => vec![],
+        [_, equals, ..] if equals.is_punctuation("=") => {
+            let closed = match option.iter().rposition(|t| t.is_punctuation("{")) {
+                Some(open) => option[open..].iter().any(|t| t.is_punctuation("}")),
+                None => true,
+            };
+
+            match closed {
+                true => context.keywords(AND),
+                false => vec![],
+            }
+        }
+        _ => vec![],
+    };
+
+    Some(items)
+}
@@ -0,0 +1,211 @@
+//! Completion for the column lists of `CREATE TABLE`: the primary key's columns are those
+//! defined earlier in the body, e.g. `PRIMARY KEY ((tenant, id), created_at)`, and for the
+//! options of `CREATE TABLE` and `ALTER TABLE` after `WITH`.
+
+use tower_lsp::lsp_types::{CompletionItem, CompletionItemKind, CompletionTextEdit};
+
+use crate::{
+    keywords,
+    lexer::{Token, quote_identifier},
+    statement::{StatementKind, matching_bracket, split_commas},
+    target::Database,
+};
+
+use super::CompletionContext;
+
+/// The types of the columns proposed as clustering columns, rows usually being ordered by time
+const CLUSTERING_TYPES: &[&str] = &["timeuuid", "timestamp", "date", "time"];
+
+/// The options of tables among the options of `keywords::OPTIONS`
+const TABLE_OPTIONS: &[&str] = &[
+    "comment",
+    "compaction",
+    "compression",
+    "caching",
+    "default_time_to_live",
+    "gc_grace_seconds",
+    "bloom_filter_fp_chance",
+    "speculative_retry",
+    "additional_write_policy",
+    "memtable_flush_period_in_ms",
+    "read_repair",
+    "cdc",
+    "memtable",
+];
+
+/// The clauses of `CREATE TABLE` set along with its options
+const CREATE_CLAUSES: &[&str] = &["CLUSTERING ORDER BY", "COMPACT STORAGE"];
+
+/// Completes the options of a table after `WITH` or `AND`, with those of the target's database
+/// and the clauses `CREATE TABLE` sets alike. Returns `None` elsewhere.
+fn option_completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    let kind = context.statement.kind();
+
+    if !matches!(kind, StatementKind::CreateTable | StatementKind::AlterTable) {
+        return None;
+    }
+
+    context.statement.find_keyword("WITH")?;
+
+    let last = context.tokens().last()?;
+    if !(last.is_keyword("WITH") || last.is_keyword("AND")) {
+        return None;
+    }
+
+    let mut options = keywords::OPTIONS
+        .iter()
+        .filter(|option| TABLE_OPTIONS.contains(&option.label))
+        .copied()
+        .collect::<Vec<_>>();
+
+    if context.targets(Database::Scylla) {
+        options.extend(keywords::SCYLLA_OPTIONS);
+    }
+
+    let mut items = context.literals(&options);
+
+    if kind == StatementKind::CreateTable {
+        let mut clauses = keywords::CLAUSES
+            .iter()
+            .filter(|clause| CREATE_CLAUSES.contains(&clause.label))
+            .copied()
+            .collect::<Vec<_>>();
+
+        if context.targets(Database::Dse) {
+            clauses.extend(keywords::DSE_CLAUSES);
+        }
+
+        items.extend(context.keywords(&clauses));
+    }
+
+    Some(items)
+}
+
+/// The columns defined by the definitions of a table's body, with their type
+fn defined_columns(tokens: &[Token]) -> Vec<(String, String)> {
+    split_commas(tokens)
+        .into_iter()
+        .filter_map(|definition| match definition {
+            [name, data_type @ ..] if !data_type.is_empty() => {
+                let data_type = data_type
+                    .iter()
+                    .take_while(|t| !t.is_keyword("PRIMARY") && !t.is_keyword("STATIC"))
+                    .map(|t| t.text)
+                    .collect::<String>();
+
+                Some((name.identifier()?, data_type))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The primary key proposed for `columns`: the first column as the partition key, clustered
+/// by the time columns, e.g. `((id), created_at)`
+fn proposed_primary_key(columns: &[(String, String)]) -> Option<String> {
+    let ((partition_key, _), rest) = columns.split_first()?;
+
+    let clustering = rest
+        .iter()
+        .filter(|(_, data_type)| {
+            CLUSTERING_TYPES.contains(&data_type.to_ascii_lowercase().as_str())
+        })
+        .map(|(name, _)| quote_identifier(name))
+        .collect::<Vec<_>>();
+
+    let partition_key = quote_identifier(partition_key);
+
+    if clustering.is_empty() {
+        return Some(format!("({partition_key})"));
+    }
+
+    Some(format!("(({partition_key}), {})", clustering.join(", ")))
+}
+
+/// Completes a definition of the table's body: `PRIMARY KEY` along with the key proposed for
+/// the columns defined before it, unless one of them is already the primary key
+fn definition_completions(context: &CompletionContext, body: &[Token]) -> Vec<CompletionItem> {
+    let primary_key = keywords::CLAUSES
+        .iter()
+        .filter(|keyword| keyword.label == "PRIMARY KEY")
+        .copied()
+        .collect::<Vec<_>>();
+
+    let mut items = context.keywords(&primary_key);
+
+    if body.iter().any(|t| t.is_keyword("PRIMARY")) {
+        return items;
+    }
+
+    let Some(key) = proposed_primary_key(&defined_columns(body)) else {
+        return items;
+    };
+
+    let proposed = items.first().cloned().map(|mut item| {
+        item.label = format!("{} {key}", item.label);
+        item.kind = Some(CompletionItemKind::SNIPPET);
+
+        if let Some(CompletionTextEdit::Edit(edit)) = &mut item.text_edit {
+            edit.new_text = format!("{} {key}", edit.new_text);
+        }
+
+        item
+    });
+
+    items.splice(0..0, proposed);
+    items
+}
+
+/// Completes the columns of `PRIMARY KEY (...)` with those defined before it, or `PRIMARY KEY`
+/// itself where a definition starts, and the options after `WITH`. Returns `None` elsewhere.
+pub fn completions(context: &CompletionContext) -> Option<Vec<CompletionItem>> {
+    if let Some(items) = option_completions(context) {
+        return Some(items);
+    }
+
+    if context.statement.kind() != StatementKind::CreateTable {
+        return None;
+    }
+
+    let tokens = context.tokens();
+    let body = tokens.iter().position(|t| t.is_punctuation("("))?;
+
+    let primary_key = (0..tokens.len()).rfind(|&i| {
+        matches!(
+            &tokens[i..],
+            [primary, key, open, ..]
+                if primary.is_keyword("PRIMARY") && key.is_keyword("KEY") && open.is_punctuation("(")
+        )
+    });
+
+    let Some(primary_key) = primary_key else {
+        // A definition starts after the body's parenthesis or a comma, rather than within a
+        // type such as `map<text, int>`
+        let starts_definition = matching_bracket(tokens, body).is_none()
+            && split_commas(&tokens[body + 1..])
+                .last()
+                .is_none_or(|definition| definition.is_empty());
+
+        return starts_definition.then(|| definition_completions(context, &tokens[body + 1..]));
+    };
+
+    // The primary key's parentheses must still be open at the cursor
+    if matching_bracket(tokens, primary_key + 2).is_some() {
+        return None;
+    }
+
+    let last = tokens.last()?;
+
+    if !(last.is_punctuation("(") || last.is_punctuation(",")) {
+        return Some(vec![]);
+    }
+
+    let items = defined_columns(&tokens[body + 1..primary_key])
+        .into_iter()
+        .flat_map(|(name, data_type)| {
+            context.names([(None, name)], CompletionItemKind::FIELD, &data_type)
+        })
+        .collect();
+
+    Some(items)
+}
@@ -0,0 +1,329 @@
+//! The server settings, layered from the settings the client sends and the settings files: a
+//! `[settings]` table in `.cql-lsp.toml` or a `cql-lsp.json`, in the user's config directory
+//! and at the root of the workspace, e.g.
+//!
+//! ```toml
+//! [settings]
+//! dialect = "cqlsh"
+//! tombstoneSeverity = "error"
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tower_lsp::lsp_types::{DiagnosticSeverity, Url};
+
+use crate::{
+    cluster::profile::PROFILES_FILE, formatting::Style, statement::Dialect, target::Target,
+    uri::EMBEDDED_SCHEME,
+};
+
+/// The name of the JSON settings file, which overrides the `[settings]` of `.cql-lsp.toml` in
+/// the same directory
+pub const SETTINGS_FILE: &str = "cql-lsp.json";
+
+/// The case in which keywords are inserted by completion items, and written as the lint of
+/// `keywordCaseSeverity` expects
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeywordCase {
+    #[default]
+    Upper,
+    Lower,
+    /// Follow the case of whatever the user has typed so far, falling back to upper case when
+    /// nothing (or a mix of cases) has been typed
+    Preserve,
+}
+
+impl KeywordCase {
+    /// Applies the case to `keyword` given the `typed` text the user already entered
+    pub fn apply(self, keyword: &str, typed: &str) -> String {
+        let case = match self {
+            KeywordCase::Preserve => {
+                let has_lower = typed.chars().any(|c| c.is_ascii_lowercase());
+                let has_upper = typed.chars().any(|c| c.is_ascii_uppercase());
+
+                match (has_lower, has_upper) {
+                    (true, false) => KeywordCase::Lower,
+                    _ => KeywordCase::Upper,
+                }
+            }
+            case => case,
+        };
+
+        match case {
+            KeywordCase::Lower => keyword.to_ascii_lowercase(),
+            _ => keyword.to_ascii_uppercase(),
+        }
+    }
+}
+
+/// The severity of the diagnostics of a group of lints, or `off` to disable them
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Off,
+    Hint,
+    Information,
+    #[default]
+    Warning,
+    Error,
+}
+
+impl LintSeverity {
+    /// The severity of the diagnostics, or `None` when they're disabled
+    pub fn diagnostic_severity(self) -> Option<DiagnosticSeverity> {
+        match self {
+            LintSeverity::Off => None,
+            LintSeverity::Hint => Some(DiagnosticSeverity::HINT),
+            LintSeverity::Information => Some(DiagnosticSeverity::INFORMATION),
+            LintSeverity::Warning => Some(DiagnosticSeverity::WARNING),
+            LintSeverity::Error => Some(DiagnosticSeverity::ERROR),
+        }
+    }
+}
+
+/// Server settings, sent by the client either as `initializationOptions` or through
+/// `workspace/didChangeConfiguration`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Config {
+    pub keyword_case: KeywordCase,
+    /// Whether the document is formatted through `textDocument/willSaveWaitUntil`
+    pub format_on_save: bool,
+    /// Spaces per indentation level when formatting on save, where the client doesn't send
+    /// formatting options
+    pub tab_size: u32,
+    /// Whether the formatter aligns the types of the column definitions of `CREATE TABLE` and
+    /// `CREATE TYPE`
+    pub align_columns: bool,
+    /// The width past which the formatter wraps the lists of `IN` and the tuples of `VALUES`,
+    /// if any
+    pub line_width: Option<usize>,
+    /// Whether documents are read as plain CQL or as cqlsh scripts
+    pub dialect: Dialect,
+    /// The database and version the documents are meant for, if known
+    pub target: Option<Target>,
+    /// The directory of the migrations, relative to the workspace folders unless absolute
+    pub migrations_directory: Option<String>,
+    /// The severity of the lints of statements writing tombstones, such as inserted nulls
+    pub tombstone_severity: LintSeverity,
+    /// The severity of the lints of `DROP` and `TRUNCATE` statements in the files meant for the
+    /// cluster of a protected profile
+    pub destructive_severity: LintSeverity,
+    /// The severity of the lints of reserved keywords not written in `keyword_case`
+    pub keyword_case_severity: LintSeverity,
+    /// The language ids of the documents read as CQL
+    pub language_ids: Vec<String>,
+    /// Whether the client may open the CQL embedded in other files, e.g. the query strings of
+    /// Java or Python code, as documents of the `cql-embedded` scheme
+    pub embedded_documents: bool,
+    /// Whether the server registers for `notebookDocument/sync`, so that the client sends the
+    /// notebooks with cells of `language_ids`
+    pub notebooks: bool,
+    /// The size in bytes above which documents and workspace files are only given the
+    /// essential features, e.g. generated dumps: large documents get no inlay hints and are
+    /// checked for diagnostics in chunks on open and save, and of large files only the schema
+    /// is indexed
+    pub large_file_size: usize,
+    /// The memory in bytes the open documents may take, past which those changed the longest
+    /// ago drop what's derived from their text
+    pub document_memory_limit: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            keyword_case: KeywordCase::default(),
+            format_on_save: false,
+            tab_size: 4,
+            align_columns: false,
+            line_width: None,
+            dialect: Dialect::default(),
+            target: None,
+            migrations_directory: None,
+            tombstone_severity: LintSeverity::default(),
+            destructive_severity: LintSeverity::default(),
+            keyword_case_severity: LintSeverity::Off,
+            language_ids: vec!["cql".into()],
+            embedded_documents: false,
+            notebooks: false,
+            large_file_size: 1024 * 1024,
+            document_memory_limit: 256 * 1024 * 1024,
+        }
+    }
+}
+
+impl Config {
+    /// Parses the settings sent by the client. Clients commonly nest the settings under a
+    /// section named after the language, so both `{ "cql": { ... } }` and `{ ... }` are accepted.
+    /// Unknown settings are ignored, and malformed ones fall back to their defaults, returned
+    /// as errors alongside the settings.
+    pub fn from_value(value: Value) -> (Self, Vec<anyhow::Error>) {
+        let Value::Object(settings) = unnest(value) else {
+            return (
+                Self::default(),
+                vec![anyhow!("The settings aren't an object")],
+            );
+        };
+
+        // Adding the settings one by one finds those that don't deserialize, which would
+        // otherwise take the valid ones down with them
+        let mut valid = Map::new();
+        let mut errors = vec![];
+
+        for (key, value) in settings {
+            valid.insert(key.clone(), value);
+
+            if let Err(error) = serde_json::from_value::<Self>(Value::Object(valid.clone())) {
+                valid.remove(&key);
+                errors.push(anyhow!("Invalid setting `{key}`: {error}"));
+            }
+        }
+
+        let config = serde_json::from_value(Value::Object(valid)).unwrap_or_default();
+
+        (config, errors)
+    }
+
+    /// Reads the settings of the client, `client`, over those of the user's settings files and
+    /// under those of the first workspace folder having settings files, which the project
+    /// shares. Returns the settings with the errors of the files that couldn't be read, which
+    /// are left out, and of the settings that are malformed, which keep their defaults.
+    pub fn load(client: &Value, roots: &[PathBuf]) -> (Self, Vec<anyhow::Error>) {
+        let mut errors = vec![];
+        let mut read = |directory: &Path| {
+            read_files(directory).unwrap_or_else(|error| {
+                errors.push(error);
+                vec![]
+            })
+        };
+
+        let mut layers = user_directory()
+            .map(|directory| read(&directory))
+            .unwrap_or_default();
+
+        layers.push(unnest(client.clone()));
+
+        if let Some(workspace) = roots
+            .iter()
+            .map(|root| read(root))
+            .find(|layers| !layers.is_empty())
+        {
+            layers.extend(workspace);
+        }
+
+        let mut settings = Value::Object(Default::default());
+
+        for layer in layers {
+            merge(&mut settings, layer);
+        }
+
+        let (config, invalid) = Self::from_value(settings);
+        errors.extend(invalid);
+
+        (config, errors)
+    }
+
+    /// The style of the formatter, besides the options of the client's requests
+    pub fn format_style(&self) -> Style {
+        Style {
+            align_columns: self.align_columns,
+            line_width: self.line_width,
+        }
+    }
+
+    /// Whether a document or file of `size` bytes only gets the essential features
+    pub fn is_large(&self, size: usize) -> bool {
+        size > self.large_file_size
+    }
+
+    /// Whether the document at `uri`, opened with `language_id`, is read as CQL: the embedded
+    /// documents are whatever their language, once opted into
+    pub fn is_cql_document(&self, uri: &Url, language_id: &str) -> bool {
+        match uri.scheme() == EMBEDDED_SCHEME {
+            true => self.embedded_documents,
+            false => self.language_ids.iter().any(|id| id == language_id),
+        }
+    }
+}
+
+/// The settings nested under a `cql` section, if any
+fn unnest(value: Value) -> Value {
+    match value {
+        Value::Object(mut map) if map.contains_key("cql") => map.remove("cql").unwrap(),
+        value => value,
+    }
+}
+
+/// Sets the settings of `layer` on `settings`, merging the objects both have
+fn merge(settings: &mut Value, layer: Value) {
+    match (settings, layer) {
+        // Clients without settings send none
+        (_, Value::Null) => {}
+        (Value::Object(settings), Value::Object(layer)) => {
+            for (key, value) in layer {
+                match settings.get_mut(&key) {
+                    Some(setting) => merge(setting, value),
+                    None => {
+                        settings.insert(key, value);
+                    }
+                }
+            }
+        }
+        (settings, layer) => *settings = layer,
+    }
+}
+
+/// `$XDG_CONFIG_HOME/cql-lsp`, falling back to `~/.config/cql-lsp`, or `%APPDATA%\cql-lsp` on
+/// Windows
+pub fn user_directory() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(base.join("cql-lsp"))
+}
+
+/// The part of `.cql-lsp.toml` holding settings, besides the connection profiles
+#[derive(Debug, Deserialize)]
+struct TomlSettings {
+    #[serde(default)]
+    settings: Option<Value>,
+}
+
+/// The settings of the files in `directory`, lowest precedence first: the `[settings]` of
+/// `.cql-lsp.toml`, then `cql-lsp.json`
+fn read_files(directory: &Path) -> Result<Vec<Value>> {
+    let mut layers = vec![];
+
+    let path = directory.join(PROFILES_FILE);
+
+    if path.is_file() {
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read {}", path.display()))?;
+        let file: TomlSettings =
+            toml::from_str(&text).with_context(|| format!("Invalid {}", path.display()))?;
+
+        layers.extend(file.settings);
+    }
+
+    let path = directory.join(SETTINGS_FILE);
+
+    if path.is_file() {
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read {}", path.display()))?;
+
+        layers.push(
+            serde_json::from_str(&text).with_context(|| format!("Invalid {}", path.display()))?,
+        );
+    }
+
+    Ok(layers)
+}
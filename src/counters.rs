@@ -0,0 +1,164 @@
+//! The rules of counter tables: besides its primary key, a table has either counters only or no
+//! counter at all, and its counters are only written by incrementing them with `UPDATE`, never
+//! with `INSERT` nor a TTL. Counters in the primary key are linted with the other key columns.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    cql_type::CqlType,
+    diagnostics::{Lint, Problem},
+    lexer::Token,
+    schema::{self, Table},
+    statement::{Statement, StatementKind, split_comma_ranges},
+    type_definition,
+};
+
+const COUNTER_MIXED: Lint = Lint {
+    code: "counter-mixed",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/types.html#counters",
+};
+
+const COUNTER_INSERT: Lint = Lint {
+    code: "counter-insert",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/types.html#counters",
+};
+
+pub const COUNTER_TTL: Lint = Lint {
+    code: "counter-ttl",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/types.html#counters",
+};
+
+fn error(token: &Token, message: String, lint: Lint) -> Problem {
+    Problem {
+        span: token.start..token.end,
+        severity: DiagnosticSeverity::ERROR,
+        message,
+        lint: Some(lint),
+    }
+}
+
+/// The columns defined by a `CREATE TABLE` or the `ADD` of an `ALTER TABLE`, as their name's
+/// token and their type
+fn defined_columns<'a>(statement: &Statement<'a>) -> Vec<(&'a Token<'a>, CqlType)> {
+    let Some(range) = type_definition::definitions(statement) else {
+        return vec![];
+    };
+
+    let tokens = &statement.tokens[range];
+
+    split_comma_ranges(tokens)
+        .into_iter()
+        .filter_map(|part| {
+            let definition = &tokens[part];
+            let name = definition.first().filter(|name| name.is_identifier())?;
+            let data_type = CqlType::parse(definition, &mut 1)?;
+
+            Some((name, data_type))
+        })
+        .collect()
+}
+
+/// The counters of a `CREATE TABLE` defining other regular columns too
+fn check_create(statement: &Statement, current_keyspace: Option<&str>) -> Vec<Problem> {
+    let Some(table) = schema::parse_create_table(statement, &current_keyspace.map(str::to_string))
+    else {
+        return vec![];
+    };
+
+    let regular = defined_columns(statement)
+        .into_iter()
+        .filter(|(name, _)| {
+            name.identifier()
+                .is_some_and(|name| !table.is_primary_key(&name))
+        })
+        .collect::<Vec<_>>();
+
+    let Some((other, _)) = regular
+        .iter()
+        .find(|(_, data_type)| !data_type.is_counter())
+    else {
+        return vec![];
+    };
+
+    regular
+        .iter()
+        .filter(|(_, data_type)| data_type.is_counter())
+        .map(|(name, _)| {
+            error(
+                name,
+                format!(
+                    "`{}` is a counter, but `{}` isn't: a table's columns besides its primary key are either all counters or none",
+                    name.text, other.text
+                ),
+                COUNTER_MIXED,
+            )
+        })
+        .collect()
+}
+
+/// The columns an `ALTER TABLE` adds to `table` that don't fit in with its other regular
+/// columns: counters among other columns, or other columns among counters
+fn check_add(statement: &Statement, table: &Table) -> Vec<Problem> {
+    let Some(existing) = table
+        .columns
+        .iter()
+        .find(|column| !table.is_primary_key(&column.name))
+    else {
+        return vec![];
+    };
+
+    let counters = existing.data_type.is_counter();
+
+    defined_columns(statement)
+        .into_iter()
+        .filter(|(_, data_type)| data_type.is_counter() != counters)
+        .map(|(name, data_type)| {
+            let message = match counters {
+                true => format!(
+                    "`{}` is a counter table: `{}` must be a counter too, not a `{data_type}`",
+                    table.name, name.text
+                ),
+                false => format!(
+                    "`{}` has regular columns that aren't counters, such as `{}`: counters can't be added to it",
+                    table.name, existing.name
+                ),
+            };
+
+            error(name, message, COUNTER_MIXED)
+        })
+        .collect()
+}
+
+/// Checks `statement` against the rules of counter tables, `table` being the one it operates
+/// on if known
+pub fn check(
+    statement: &Statement,
+    table: Option<&Table>,
+    current_keyspace: Option<&str>,
+) -> Vec<Problem> {
+    match statement.kind() {
+        StatementKind::CreateTable => check_create(statement, current_keyspace),
+        StatementKind::AlterTable => match table {
+            Some(table) => check_add(statement, table),
+            None => vec![],
+        },
+        StatementKind::Insert => {
+            let Some((table, counter)) = table.and_then(|table| {
+                let counter = table.columns.iter().find(|c| c.data_type.is_counter())?;
+                Some((table, &counter.name))
+            }) else {
+                return vec![];
+            };
+
+            vec![error(
+                &statement.tokens[0],
+                format!(
+                    "`{}` is a counter table: counters can't be inserted, only incremented with `UPDATE`, e.g. `UPDATE {} SET {counter} = {counter} + 1 WHERE ...`",
+                    table.name, table.name
+                ),
+                COUNTER_INSERT,
+            )]
+        }
+        _ => vec![],
+    }
+}
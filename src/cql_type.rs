@@ -0,0 +1,259 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::lexer::{Token, TokenKind};
+
+pub const NATIVE_TYPES: &[&str] = &[
+    "ascii",
+    "bigint",
+    "blob",
+    "boolean",
+    "counter",
+    "date",
+    "decimal",
+    "double",
+    "duration",
+    "float",
+    "inet",
+    "int",
+    "smallint",
+    "text",
+    "time",
+    "timestamp",
+    "timeuuid",
+    "tinyint",
+    "uuid",
+    "varchar",
+    "varint",
+];
+
+/// Types taking type parameters between `<` and `>`
+pub const TYPE_CONSTRUCTORS: &[&str] = &["frozen", "list", "map", "set", "tuple", "vector"];
+
+/// A CQL data type, e.g. `int` or `map<text, frozen<list<address>>>`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CqlType {
+    Native(String),
+    List(Box<CqlType>),
+    Set(Box<CqlType>),
+    Map(Box<CqlType>, Box<CqlType>),
+    Tuple(Vec<CqlType>),
+    Vector(Box<CqlType>, u32),
+    Frozen(Box<CqlType>),
+    UserDefined {
+        keyspace: Option<String>,
+        name: String,
+    },
+    /// A custom type given by its Java class name, e.g. `'org.apache.cassandra.db.marshal.DateType'`
+    Custom(String),
+}
+
+/// Parses the comma-separated type parameters between `<` and `>`, starting at the `<`
+fn parse_parameters(tokens: &[Token], i: &mut usize) -> Option<Vec<CqlType>> {
+    if !tokens.get(*i)?.is_punctuation("<") {
+        return None;
+    }
+    *i += 1;
+
+    let mut parameters = vec![];
+
+    loop {
+        parameters.push(CqlType::parse(tokens, i)?);
+
+        let token = tokens.get(*i)?;
+        *i += 1;
+
+        match token.text {
+            "," => continue,
+            ">" => return Some(parameters),
+            _ => return None,
+        }
+    }
+}
+
+impl CqlType {
+    /// Parses a type starting at `tokens[*i]`, advancing `i` past it
+    pub fn parse(tokens: &[Token], i: &mut usize) -> Option<CqlType> {
+        let token = tokens.get(*i)?;
+
+        if token.kind == TokenKind::String {
+            *i += 1;
+            return Some(CqlType::Custom(token.string_value()?));
+        }
+
+        let name = token.identifier()?;
+        *i += 1;
+
+        let takes_parameters = token.kind == TokenKind::Word
+            && tokens
+                .get(*i)
+                .is_some_and(|token| token.is_punctuation("<"));
+
+        if takes_parameters {
+            match name.as_str() {
+                "list" | "set" | "frozen" => {
+                    let mut parameters = parse_parameters(tokens, i)?;
+
+                    if parameters.len() != 1 {
+                        return None;
+                    }
+
+                    let inner = Box::new(parameters.remove(0));
+
+                    return Some(match name.as_str() {
+                        "list" => CqlType::List(inner),
+                        "set" => CqlType::Set(inner),
+                        _ => CqlType::Frozen(inner),
+                    });
+                }
+                "map" => {
+                    let mut parameters = parse_parameters(tokens, i)?;
+
+                    if parameters.len() != 2 {
+                        return None;
+                    }
+
+                    let value = parameters.pop()?;
+                    let key = parameters.pop()?;
+
+                    return Some(CqlType::Map(Box::new(key), Box::new(value)));
+                }
+                "tuple" => return Some(CqlType::Tuple(parse_parameters(tokens, i)?)),
+                "vector" => {
+                    // `vector<float, 3>`: the dimension isn't a type, so it's parsed by hand
+                    *i += 1;
+                    let element = CqlType::parse(tokens, i)?;
+
+                    if !tokens.get(*i)?.is_punctuation(",") {
+                        return None;
+                    }
+
+                    let dimension = tokens.get(*i + 1)?.text.parse().ok()?;
+
+                    if !tokens.get(*i + 2)?.is_punctuation(">") {
+                        return None;
+                    }
+                    *i += 3;
+
+                    return Some(CqlType::Vector(Box::new(element), dimension));
+                }
+                _ => return None,
+            }
+        }
+
+        if token.kind == TokenKind::Word && NATIVE_TYPES.contains(&name.as_str()) {
+            return Some(CqlType::Native(name));
+        }
+
+        // Anything else names a user-defined type, possibly qualified with its keyspace
+        let qualified = tokens.get(*i).is_some_and(|t| t.is_punctuation("."))
+            && tokens.get(*i + 1).is_some_and(|t| t.is_identifier());
+
+        match qualified {
+            true => {
+                let name = tokens[*i + 1].identifier()?;
+                let keyspace = token.identifier();
+                *i += 2;
+
+                Some(CqlType::UserDefined { keyspace, name })
+            }
+            false => Some(CqlType::UserDefined {
+                keyspace: None,
+                name,
+            }),
+        }
+    }
+
+    /// Returns the type with any outer `frozen<>` removed
+    pub fn unfrozen(&self) -> &CqlType {
+        match self {
+            CqlType::Frozen(inner) => inner.unfrozen(),
+            other => other,
+        }
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        matches!(self, CqlType::Frozen(_))
+    }
+
+    pub fn is_native(&self, name: &str) -> bool {
+        matches!(self.unfrozen(), CqlType::Native(native) if native == name)
+    }
+
+    pub fn is_counter(&self) -> bool {
+        self.is_native("counter")
+    }
+
+    pub fn is_collection(&self) -> bool {
+        matches!(
+            self.unfrozen(),
+            CqlType::List(_) | CqlType::Set(_) | CqlType::Map(..)
+        )
+    }
+
+    /// Returns the type of the elements of a list, set or vector, or of the values of a map
+    pub fn element_type(&self) -> Option<&CqlType> {
+        match self.unfrozen() {
+            CqlType::List(element) | CqlType::Set(element) | CqlType::Vector(element, _) => {
+                Some(element)
+            }
+            CqlType::Map(_, value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the user-defined types the type is made of, e.g. `address` and `phone` in
+    /// `map<frozen<address>, list<frozen<phone>>>`, with their keyspace if qualified
+    pub fn user_types(&self) -> Vec<(Option<&str>, &str)> {
+        match self {
+            CqlType::UserDefined { keyspace, name } => vec![(keyspace.as_deref(), name)],
+            CqlType::List(inner)
+            | CqlType::Set(inner)
+            | CqlType::Vector(inner, _)
+            | CqlType::Frozen(inner) => inner.user_types(),
+            CqlType::Map(key, value) => [key.user_types(), value.user_types()].concat(),
+            CqlType::Tuple(elements) => elements.iter().flat_map(CqlType::user_types).collect(),
+            CqlType::Native(_) | CqlType::Custom(_) => vec![],
+        }
+    }
+
+    /// Returns the type of the keys of a map
+    pub fn key_type(&self) -> Option<&CqlType> {
+        match self.unfrozen() {
+            CqlType::Map(key, _) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CqlType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CqlType::Native(name) => write!(f, "{name}"),
+            CqlType::List(element) => write!(f, "list<{element}>"),
+            CqlType::Set(element) => write!(f, "set<{element}>"),
+            CqlType::Map(key, value) => write!(f, "map<{key}, {value}>"),
+            CqlType::Tuple(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(f, "tuple<{elements}>")
+            }
+            CqlType::Vector(element, dimension) => write!(f, "vector<{element}, {dimension}>"),
+            CqlType::Frozen(inner) => write!(f, "frozen<{inner}>"),
+            CqlType::UserDefined {
+                keyspace: Some(keyspace),
+                name,
+            } => write!(f, "{keyspace}.{name}"),
+            CqlType::UserDefined {
+                keyspace: None,
+                name,
+            } => write!(f, "{name}"),
+            CqlType::Custom(class) => write!(f, "'{class}'"),
+        }
+    }
+}
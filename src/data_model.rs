@@ -0,0 +1,149 @@
+//! Data modelling lints of `CREATE TABLE`: primary keys made of columns that can't or shouldn't
+//! identify rows.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    cql_type::CqlType,
+    diagnostics::{Lint, Problem},
+    lexer::Token,
+    schema::{self, Table},
+    statement::{self, Statement, StatementKind, matching_bracket},
+};
+
+const COUNTER_KEY: Lint = Lint {
+    code: "counter-key",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/types.html#counters",
+};
+
+pub const COLLECTION_KEY: Lint = Lint {
+    code: "collection-key",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/types.html#collections",
+};
+
+const LOW_CARDINALITY_PARTITION_KEY: Lint = Lint {
+    code: "low-cardinality-partition-key",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/ddl.html#partition-key",
+};
+
+/// The column definitions of a `CREATE TABLE`, leaving out its `PRIMARY KEY (...)`
+fn definitions<'a>(statement: &Statement<'a>) -> Vec<&'a [Token<'a>]> {
+    let tokens = statement.tokens;
+
+    let Some(open) = tokens.iter().position(|t| t.is_punctuation("(")) else {
+        return vec![];
+    };
+    let close = matching_bracket(tokens, open).unwrap_or(tokens.len());
+
+    statement::split_commas(&tokens[open + 1..close])
+        .into_iter()
+        .filter(|definition| {
+            !matches!(definition, [primary, key, ..] if primary.is_keyword("PRIMARY") && key.is_keyword("KEY"))
+        })
+        .collect()
+}
+
+/// What makes `data_type` unfit for a key column, and the lint finding it
+fn unfit_key_type(data_type: &CqlType) -> Option<(String, Lint)> {
+    let reason = match data_type {
+        _ if data_type.is_counter() => (
+            "counters can't be part of the primary key, as their value changes with each update"
+                .into(),
+            COUNTER_KEY,
+        ),
+        CqlType::Frozen(inner) if data_type.is_collection() => (
+            format!(
+                "`{inner}` makes a poor key: rows are only found by the whole collection, which bloats every key it's part of"
+            ),
+            COLLECTION_KEY,
+        ),
+        _ => return None,
+    };
+
+    Some(reason)
+}
+
+fn warning(token: &Token, message: String, lint: Lint) -> Problem {
+    Problem {
+        span: token.start..token.end,
+        severity: DiagnosticSeverity::WARNING,
+        message,
+        lint: Some(lint),
+    }
+}
+
+/// Checks the primary key of `table`, defined by `columns`
+fn check_key(table: &Table, columns: &[(&Token, &CqlType)], problems: &mut Vec<Problem>) {
+    let definition = |name: &String| {
+        columns
+            .iter()
+            .find(|(token, _)| token.identifier().as_ref() == Some(name))
+    };
+
+    let key = table.partition_key.iter().chain(&table.clustering_key);
+
+    for name in key {
+        let Some((token, data_type)) = definition(name) else {
+            continue;
+        };
+
+        if let Some((reason, lint)) = unfit_key_type(data_type) {
+            problems.push(warning(
+                token,
+                format!("Key column `{name}`: {reason}"),
+                lint,
+            ));
+        }
+    }
+
+    // Few distinct partitions concentrate the data on a few nodes
+    if let [name] = table.partition_key.as_slice()
+        && let Some((token, data_type)) = definition(name)
+    {
+        let partitions = match data_type.unfrozen() {
+            CqlType::Native(native) if native == "boolean" => Some(2),
+            CqlType::Native(native) if native == "tinyint" => Some(256),
+            _ => None,
+        };
+
+        if let Some(partitions) = partitions {
+            problems.push(warning(
+                token,
+                format!(
+                    "`{name}` is the only partition key column and a `{data_type}`: the table has at most {partitions} partitions, each stored on the same few nodes, which become hot spots"
+                ),
+                LOW_CARDINALITY_PARTITION_KEY,
+            ));
+        }
+    }
+}
+
+/// Checks a `CREATE TABLE` for data modelling problems, `current_keyspace` being the one it's
+/// created in unless qualified
+pub fn check(statement: &Statement, current_keyspace: Option<&str>) -> Vec<Problem> {
+    if statement.kind() != StatementKind::CreateTable {
+        return vec![];
+    }
+
+    let Some(table) = schema::parse_create_table(statement, &current_keyspace.map(str::to_string))
+    else {
+        return vec![];
+    };
+
+    let definitions = definitions(statement);
+
+    let mut problems = vec![];
+
+    let columns = definitions
+        .iter()
+        .filter_map(|definition| {
+            let name = definition.first()?;
+            let column = table.column(&name.identifier()?)?;
+            Some((name, &column.data_type))
+        })
+        .collect::<Vec<_>>();
+
+    check_key(&table, &columns, &mut problems);
+
+    problems
+}
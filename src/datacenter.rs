@@ -0,0 +1,140 @@
+//! The datacenters named by statements: the keys of a `NetworkTopologyStrategy` replication
+//! map, e.g. `{'class': 'NetworkTopologyStrategy', 'eu-west': 3}`, and the datacenters of a
+//! role's `ACCESS TO DATACENTERS { ... }`. Once the datacenters of a cluster are known, naming
+//! another is a typo: Cassandra rejects it in a replication map, and access to it grants none.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    lexer::Token,
+    schema::Schema,
+    statement::{Statement, StatementKind, matching_bracket, split_commas},
+};
+
+/// The keys of a replication map that aren't datacenters
+const REPLICATION_OPTIONS: &[&str] = &["class", "replication_factor"];
+
+/// A map or set of datacenters, as far as it's been typed
+#[derive(Debug)]
+pub struct Datacenters<'a> {
+    /// The entries, e.g. `'eu-west': 3` of a replication map or `'eu-west'` of a role's access
+    pub entries: Vec<&'a [Token<'a>]>,
+    /// Whether the entries are those of a replication map, keyed by datacenter
+    pub replication: bool,
+    /// Whether its braces are closed
+    pub closed: bool,
+}
+
+impl<'a> Datacenters<'a> {
+    /// The replication strategy of a replication map, without its package, e.g.
+    /// `NetworkTopologyStrategy`
+    pub fn class(&self) -> Option<String> {
+        let class = self.entries.iter().find_map(|entry| match entry {
+            [key, colon, value, ..] if colon.is_punctuation(":") => {
+                (key.string_value()? == "class").then(|| value.string_value())?
+            }
+            _ => None,
+        })?;
+
+        Some(match class.rsplit_once('.') {
+            Some((_, class)) => class.to_string(),
+            None => class,
+        })
+    }
+
+    /// The tokens naming datacenters. Only `NetworkTopologyStrategy` keys its replication map
+    /// by datacenter.
+    pub fn names(&self) -> Vec<&'a Token<'a>> {
+        if self.replication && self.class().as_deref() != Some("NetworkTopologyStrategy") {
+            return vec![];
+        }
+
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.first())
+            .filter(|token| {
+                token
+                    .string_value()
+                    .is_some_and(|name| !REPLICATION_OPTIONS.contains(&name.as_str()))
+            })
+            .collect()
+    }
+}
+
+/// Parses the replication map of a `CREATE KEYSPACE` or `ALTER KEYSPACE`, or the datacenters
+/// of a `CREATE ROLE` or `ALTER ROLE` given access to some, if any
+pub fn parse<'a>(statement: &Statement<'a>) -> Option<Datacenters<'a>> {
+    let tokens = statement.tokens;
+
+    let replication = match statement.kind() {
+        StatementKind::CreateKeyspace | StatementKind::AlterKeyspace => true,
+        StatementKind::CreateRole | StatementKind::AlterRole => false,
+        _ => return None,
+    };
+
+    let open = (0..tokens.len()).find_map(|i| match &tokens[i..] {
+        [name, equals, open, ..]
+            if replication
+                && name.text.eq_ignore_ascii_case("replication")
+                && equals.is_punctuation("=")
+                && open.is_punctuation("{") =>
+        {
+            Some(i + 2)
+        }
+        [access, to, datacenters, open, ..]
+            if !replication
+                && access.is_keyword("ACCESS")
+                && to.is_keyword("TO")
+                && datacenters.is_keyword("DATACENTERS")
+                && open.is_punctuation("{") =>
+        {
+            Some(i + 3)
+        }
+        _ => None,
+    })?;
+
+    let close = matching_bracket(tokens, open);
+
+    Some(Datacenters {
+        entries: split_commas(&tokens[open + 1..close.unwrap_or(tokens.len())]),
+        replication,
+        closed: close.is_some(),
+    })
+}
+
+/// Checks that the datacenters a statement names exist, once the datacenters of a cluster are
+/// known
+pub fn check(statement: &Statement, schema: &Schema) -> Vec<Problem> {
+    if schema.datacenters.is_empty() {
+        return vec![];
+    }
+
+    // Entries of open braces are still being typed
+    let Some(datacenters) = parse(statement).filter(|datacenters| datacenters.closed) else {
+        return vec![];
+    };
+
+    datacenters
+        .names()
+        .into_iter()
+        .filter_map(|token| {
+            let name = token.string_value()?;
+
+            (!schema.datacenters.contains(&name)).then(|| Problem {
+                span: token.start..token.end,
+                severity: DiagnosticSeverity::WARNING,
+                message: format!(
+                    "Datacenter `{name}` doesn't exist on the cluster, whose datacenters are {}",
+                    schema
+                        .datacenters
+                        .iter()
+                        .map(|name| format!("`{name}`"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                lint: None,
+            })
+        })
+        .collect()
+}
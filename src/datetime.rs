@@ -0,0 +1,197 @@
+//! Dates, times and timestamps: formatted like cqlsh does, and timestamp literals parsed as
+//! Cassandra does, e.g. `'2024-03-01 12:00:00+0000'`.
+
+/// Converts days since the Unix epoch to a year, month and day of the proleptic Gregorian
+/// calendar (see <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+pub fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// `HH:MM:SS.nnnnnnnnn`
+pub fn format_time(nanoseconds: i64) -> String {
+    let seconds = nanoseconds.div_euclid(1_000_000_000);
+
+    format!(
+        "{:02}:{:02}:{:02}.{:09}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        nanoseconds.rem_euclid(1_000_000_000)
+    )
+}
+
+/// Like cqlsh, e.g. `2024-01-31 12:30:00.000000+0000`
+pub fn format_timestamp(milliseconds: i64) -> String {
+    let days = milliseconds.div_euclid(86_400_000);
+    let time = milliseconds.rem_euclid(86_400_000) * 1_000_000;
+
+    format!("{} {}+0000", format_date(days), &format_time(time)[..15])
+}
+
+/// Converts a year, month and day of the proleptic Gregorian calendar to days since the Unix
+/// epoch (see <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The number of days of `month` in `year` of the proleptic Gregorian calendar
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Parses the `n` digits starting `text`, advancing it past them
+fn digits(text: &mut &str, n: usize) -> Option<i64> {
+    let head = text
+        .get(..n)
+        .filter(|head| head.bytes().all(|b| b.is_ascii_digit()))?;
+    *text = &text[n..];
+
+    head.parse().ok()
+}
+
+/// Parses a timestamp string as Cassandra does, `yyyy-mm-dd`, optionally followed by a time
+/// `HH:MM[:SS[.fff]]` after a space or `T`, and a time zone `Z`, `+hhmm` or `+hh:mm`. Returns
+/// the milliseconds since the Unix epoch, UTC if the zone isn't given, with whether it was.
+pub fn parse_timestamp(text: &str) -> Option<(i64, bool)> {
+    let mut rest = text;
+
+    let year = digits(&mut rest, 4)?;
+    rest = rest.strip_prefix('-')?;
+    let month = digits(&mut rest, 2)?;
+    rest = rest.strip_prefix('-')?;
+    let day = digits(&mut rest, 2)?;
+
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+
+    let mut milliseconds = days_from_civil(year, month as u32, day as u32) * 86_400_000;
+
+    if let Some(time) = rest.strip_prefix([' ', 'T']) {
+        rest = time;
+
+        let hours = digits(&mut rest, 2)?;
+        rest = rest.strip_prefix(':')?;
+        let minutes = digits(&mut rest, 2)?;
+
+        let seconds = match rest.strip_prefix(':') {
+            Some(seconds) => {
+                rest = seconds;
+                digits(&mut rest, 2)?
+            }
+            None => 0,
+        };
+
+        // Only milliseconds count of the fraction
+        let fraction = match rest.strip_prefix('.') {
+            Some(fraction) => {
+                let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+                rest = &fraction[len..];
+                format!("{:0<3}", &fraction[..len.min(3)])
+                    .parse::<i64>()
+                    .ok()?
+            }
+            None => 0,
+        };
+
+        if hours > 23 || minutes > 59 || seconds > 59 {
+            return None;
+        }
+
+        milliseconds += ((hours * 60 + minutes) * 60 + seconds) * 1000 + fraction;
+    }
+
+    let zoned = !rest.is_empty();
+
+    match rest {
+        "" | "Z" | "z" => {}
+        _ => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            rest = &rest[1..];
+
+            let hours = digits(&mut rest, 2)?;
+            rest = rest.strip_prefix(':').unwrap_or(rest);
+            let minutes = digits(&mut rest, 2)?;
+
+            if !rest.is_empty() {
+                return None;
+            }
+
+            milliseconds -= sign * (hours * 60 + minutes) * 60_000;
+        }
+    }
+
+    Some((milliseconds, zoned))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_follow_the_month() {
+        assert!(parse_timestamp("2024-02-29").is_some());
+        assert!(parse_timestamp("2000-02-29").is_some());
+        assert!(parse_timestamp("2024-04-30").is_some());
+        assert!(parse_timestamp("2024-12-31").is_some());
+
+        for invalid in [
+            "2024-02-30",
+            "2024-02-31",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-04-31",
+            "2024-11-31",
+            "2024-01-00",
+            "2024-13-01",
+        ] {
+            assert_eq!(parse_timestamp(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn timestamps_round_trip() {
+        let (milliseconds, zoned) = parse_timestamp("2024-03-01 12:30:05.250+0100").unwrap();
+
+        assert!(zoned);
+        assert_eq!(
+            format_timestamp(milliseconds),
+            "2024-03-01 11:30:05.250000+0000"
+        );
+        assert_eq!(parse_timestamp("1970-01-01"), Some((0, false)));
+    }
+}
@@ -0,0 +1,340 @@
+//! Diagnostics computed by replaying the document's statements against the schema they build.
+
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Range,
+};
+
+use tower_lsp::lsp_types::{CodeDescription, Diagnostic, DiagnosticSeverity, NumberOrString, Url};
+
+use crate::{
+    alter_table, assignment,
+    batch::{self, Boundary},
+    clustering_order,
+    config::Config,
+    counters, data_model, datacenter,
+    directives::Directives,
+    document::{PositionEncodingKind, TextDocument},
+    duplicates, features, frozen, function, grant, group_by, guardrails, identifier_case, index,
+    json, keyword_case, lexer, literal, materialized_view, reserved,
+    schema::Schema,
+    statement::{self, Statement, StatementKind},
+    static_columns, system_keyspaces, tombstones, using,
+};
+
+/// A problem found in the document, located by byte range so checks don't need to know about
+/// position encodings
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub span: Range<usize>,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+    /// The lint the problem was found by, for problems of data modelling or performance, and for
+    /// errors breaking a rule its link explains
+    pub lint: Option<Lint>,
+}
+
+/// A lint whose documentation explains the concern behind it
+#[derive(Debug, Clone, Copy)]
+pub struct Lint {
+    pub code: &'static str,
+    pub link: &'static str,
+}
+
+/// Checks a single statement against the schema defined by the statements before it
+fn check_statement(
+    statement: &Statement,
+    schema: &Schema,
+    current_keyspace: Option<&str>,
+    config: &Config,
+    protected: bool,
+) -> Vec<Problem> {
+    let tombstone_severity = config.tombstone_severity.diagnostic_severity();
+
+    let table = schema.statement_table(statement, current_keyspace);
+    let mut problems = reserved::check(statement, table);
+    problems.extend(duplicates::check(statement, schema, current_keyspace));
+    problems.extend(data_model::check(statement, current_keyspace));
+    problems.extend(clustering_order::check(statement));
+    problems.extend(counters::check(statement, table, current_keyspace));
+    problems.extend(static_columns::check(statement, table, current_keyspace));
+    problems.extend(system_keyspaces::check(
+        statement,
+        current_keyspace,
+        config.target,
+    ));
+    problems.extend(features::check(statement, config.target));
+    problems.extend(frozen::check(statement, config.target));
+
+    if let Some(severity) = config.keyword_case_severity.diagnostic_severity() {
+        problems.extend(keyword_case::check(
+            statement,
+            table,
+            config.keyword_case,
+            severity,
+        ));
+    }
+
+    if protected && let Some(severity) = config.destructive_severity.diagnostic_severity() {
+        problems.extend(guardrails::check(statement, severity));
+    }
+
+    if matches!(
+        statement.kind(),
+        StatementKind::Select
+            | StatementKind::Insert
+            | StatementKind::Update
+            | StatementKind::Delete
+    ) {
+        problems.extend(identifier_case::check(statement, table, schema));
+    }
+
+    problems.extend(match statement.kind() {
+        StatementKind::Select => match table {
+            Some(table) => group_by::check(statement, table),
+            None => vec![],
+        },
+        StatementKind::Insert | StatementKind::Update => {
+            let mut problems = json::check(statement, table);
+
+            if let Some(table) = table {
+                problems.extend(literal::check(statement, table, schema));
+                problems.extend(assignment::check(statement, table, schema));
+            }
+
+            if let Some((table, severity)) = table.zip(tombstone_severity) {
+                problems.extend(tombstones::check(statement, table, severity));
+            }
+
+            problems.extend(using::check(statement, table));
+            problems
+        }
+        StatementKind::Delete => {
+            let mut problems = using::check(statement, table);
+
+            if let Some((table, severity)) = table.zip(tombstone_severity) {
+                problems.extend(tombstones::check(statement, table, severity));
+            }
+
+            problems
+        }
+        StatementKind::CreateMaterializedView => {
+            let Some(view) = materialized_view::parse(statement) else {
+                return vec![];
+            };
+
+            match view.base_table(schema, current_keyspace) {
+                Some(base) => materialized_view::check(&view, base),
+                None => vec![],
+            }
+        }
+        StatementKind::CreateAggregate => {
+            let Some(aggregate) = function::parse_aggregate(statement) else {
+                return vec![];
+            };
+
+            // Functions can only be checked in keyspaces whose definition we've seen
+            let keyspace = aggregate
+                .name
+                .keyspace
+                .as_deref()
+                .or(current_keyspace)
+                .and_then(|keyspace| schema.keyspaces.get(keyspace));
+
+            match keyspace {
+                Some(keyspace) => function::check_aggregate(&aggregate, keyspace),
+                None => vec![],
+            }
+        }
+        StatementKind::Grant
+        | StatementKind::Revoke
+        | StatementKind::List
+        | StatementKind::Restrict
+        | StatementKind::Unrestrict => grant::check(statement, schema),
+        StatementKind::CreateKeyspace
+        | StatementKind::AlterKeyspace
+        | StatementKind::CreateRole
+        | StatementKind::AlterRole => datacenter::check(statement, schema),
+        StatementKind::AlterTable => match alter_table::parse(statement).zip(table) {
+            Some((alter, table)) => alter_table::check(&alter, table),
+            None => vec![],
+        },
+        StatementKind::CreateIndex => {
+            let Some(index) = index::parse(statement) else {
+                return vec![];
+            };
+
+            let table = index.table.as_ref().and_then(|name| {
+                let keyspace = name.keyspace.as_deref().or(current_keyspace);
+                schema.table(keyspace, &name.name)
+            });
+
+            match table {
+                Some(table) => index::check(&index, table),
+                None => vec![],
+            }
+        }
+        _ => vec![],
+    });
+
+    problems
+}
+
+/// Computes the diagnostics of `doc`, whose statements are replayed on top of `schema`: the
+/// schema defined by the rest of the workspace, or an empty one for document-local checks.
+/// `protected` is whether the document is meant for the cluster of a protected profile, whose
+/// destructive statements are flagged. Large documents aren't checked.
+pub fn diagnostics(
+    doc: &TextDocument,
+    mut schema: Schema,
+    config: &Config,
+    protected: bool,
+    position_encoding: PositionEncodingKind,
+) -> Vec<Diagnostic> {
+    // Large documents, e.g. generated dumps, would take too long to check on every change, and
+    // are checked in chunks instead by `bulk`
+    if config.is_large(doc.rope.len_bytes()) {
+        return vec![];
+    }
+
+    let text = doc.text();
+    let directives = Directives::parse(text, &lexer::tokenize_with_comments(text));
+    let tokens = lexer::tokenize(text);
+    let statements = statement::split(&tokens, doc.dialect(config.dialect));
+
+    let mut keyspace = schema.used_keyspace.clone();
+    let mut problems = replay(
+        &statements,
+        statements.len(),
+        &mut schema,
+        &mut keyspace,
+        config,
+        protected,
+    );
+
+    problems.retain(|problem| {
+        !directives.is_disabled(&problem.span, problem.lint.map(|lint| lint.code))
+    });
+    problems.extend(directives.problems);
+
+    into_diagnostics(problems, |span| {
+        doc.byte_range_to_range(span, position_encoding)
+    })
+}
+
+/// Checks the first `count` of `statements` in order, replaying them on top of `schema` with
+/// `keyspace` in use, which they're left at. The statements after them are only looked at to
+/// tell whether the last one is missing its `;`.
+pub(crate) fn replay(
+    statements: &[Statement],
+    count: usize,
+    schema: &mut Schema,
+    keyspace: &mut Option<String>,
+    config: &Config,
+    protected: bool,
+) -> Vec<Problem> {
+    let mut problems = vec![];
+    // The writes of the batch being read, if any
+    let mut batch: Option<Vec<Statement>> = None;
+
+    for (i, statement) in statements.iter().enumerate().take(count) {
+        // Reported on its last token, which the `;` goes after
+        if statement::missing_semicolon(statements, i)
+            && let Some(last) = statement.tokens.last()
+        {
+            problems.push(Problem {
+                span: last.start..last.end,
+                severity: DiagnosticSeverity::ERROR,
+                message: "Missing `;`: the statement runs into the next one".into(),
+                lint: None,
+            });
+        }
+
+        problems.extend(check_statement(
+            statement,
+            schema,
+            keyspace.as_deref(),
+            config,
+            protected,
+        ));
+
+        match batch::boundary(statement) {
+            Some(Boundary::Begin(write)) => {
+                if let Some(write) = &write {
+                    problems.extend(check_statement(
+                        write,
+                        schema,
+                        keyspace.as_deref(),
+                        config,
+                        protected,
+                    ));
+                }
+
+                batch = Some(write.into_iter().collect());
+            }
+            Some(Boundary::Apply) => {
+                if let Some(writes) = batch.take() {
+                    problems.extend(batch::check(&writes, schema, keyspace.as_deref()));
+                }
+            }
+            None => {
+                if let Some(writes) = &mut batch {
+                    writes.push(*statement);
+                }
+            }
+        }
+
+        schema.apply(statement, keyspace);
+    }
+
+    problems
+}
+
+/// Converts `problems` into diagnostics, `range` locating their spans
+pub(crate) fn into_diagnostics(
+    problems: Vec<Problem>,
+    range: impl Fn(Range<usize>) -> tower_lsp::lsp_types::Range,
+) -> Vec<Diagnostic> {
+    problems
+        .into_iter()
+        .map(|problem| Diagnostic {
+            range: range(problem.span),
+            severity: Some(problem.severity),
+            source: Some("cql-lsp".into()),
+            message: problem.message,
+            code: problem
+                .lint
+                .map(|lint| NumberOrString::String(lint.code.into())),
+            code_description: problem.lint.and_then(|lint| {
+                Some(CodeDescription {
+                    href: Url::parse(lint.link).ok()?,
+                })
+            }),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Identifies the diagnostics of the text made of `chunks` checked against the workspace
+/// schema of `schema_generation` with the lint settings of `config`, for clients pulling
+/// diagnostics to skip unchanged results
+pub fn result_id<'a>(
+    chunks: impl IntoIterator<Item = &'a str>,
+    schema_generation: u64,
+    config: &Config,
+) -> String {
+    let mut hasher = DefaultHasher::new();
+
+    // Written as raw bytes, so that how the text happens to be chunked doesn't matter
+    for chunk in chunks {
+        hasher.write(chunk.as_bytes());
+    }
+
+    config.tombstone_severity.hash(&mut hasher);
+    config.destructive_severity.hash(&mut hasher);
+    config.keyword_case_severity.hash(&mut hasher);
+    config.keyword_case.hash(&mut hasher);
+    config.target.hash(&mut hasher);
+
+    format!("{:x}-{schema_generation}", hasher.finish())
+}
@@ -0,0 +1,199 @@
+//! Directives overriding the settings for a single file, in comments starting with `cql-lsp:`,
+//! so that a migration can opt out of a lint without changing the settings of the workspace.
+//! Those in the line comments at the top of the file, before its first statement, apply to the
+//! whole file:
+//!
+//! ```cql
+//! -- cql-lsp: dialect=cqlsh
+//! -- cql-lsp: disable=tombstone-null,collection-key
+//! ```
+//!
+//! while `-- cql-lsp:disable-next-line tombstone-null` disables lints on the line after it, or
+//! every diagnostic of the line when no lint is named.
+
+use std::ops::Range;
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    lexer::{Token, TokenKind},
+    statement::Dialect,
+};
+
+pub const PREFIX: &str = "cql-lsp:";
+
+pub const DISABLE_NEXT_LINE: &str = "disable-next-line";
+
+/// The directives of a file
+#[derive(Debug, Default)]
+pub struct Directives {
+    /// The lints disabled in the whole file, by code
+    pub disabled: Vec<String>,
+    /// The byte ranges of the lines whose diagnostics are disabled, with the lints disabled,
+    /// empty when every diagnostic of the line is
+    pub disabled_lines: Vec<(Range<usize>, Vec<String>)>,
+    /// The directives that couldn't be read
+    pub problems: Vec<Problem>,
+}
+
+/// The text of a directive comment after its prefix, e.g. `dialect=cqlsh`
+fn directive(comment: &str) -> Option<&str> {
+    let body = comment
+        .strip_prefix("--")
+        .or_else(|| comment.strip_prefix("//"))?;
+
+    body.trim_start().strip_prefix(PREFIX).map(str::trim)
+}
+
+/// The lints a `disable-next-line` directive comment disables, empty when it disables every
+/// diagnostic of the line, or `None` for other comments
+pub fn next_line_lints(comment: &str) -> Option<Vec<String>> {
+    directive(comment)?
+        .strip_prefix(DISABLE_NEXT_LINE)
+        .map(lints)
+}
+
+/// Whether `line` may be part of the header of a file, whose directives apply to the file:
+/// blank, or a line comment
+pub fn in_header(line: &str) -> bool {
+    let line = line.trim_start();
+
+    line.is_empty() || line.starts_with("--") || line.starts_with("//")
+}
+
+fn parse_dialect(name: &str) -> Option<Dialect> {
+    match name {
+        "auto" => Some(Dialect::Auto),
+        "cql" => Some(Dialect::Cql),
+        "cqlsh" => Some(Dialect::Cqlsh),
+        _ => None,
+    }
+}
+
+/// Splits a list of lints, e.g. `tombstone-null,collection-key`
+fn lints(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|lint| !lint.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+impl Directives {
+    /// Reads the directives of the file whose text is `text`, tokenized with its comments as
+    /// `tokens`
+    pub fn parse(text: &str, tokens: &[Token]) -> Self {
+        let header = tokens
+            .iter()
+            .position(|token| token.kind != TokenKind::Comment)
+            .map_or(text.len(), |i| tokens[i].start);
+
+        Self::parse_after(text, tokens, header)
+    }
+
+    /// Reads the directives of a part of a file after its header, e.g. a chunk of a large file
+    /// checked piecewise, whose text is `text`, tokenized with its comments as `tokens`
+    pub fn parse_body(text: &str, tokens: &[Token]) -> Self {
+        Self::parse_after(text, tokens, 0)
+    }
+
+    /// Reads the directives of `text`, whose header ends at the byte `header`
+    fn parse_after(text: &str, tokens: &[Token], header: usize) -> Self {
+        let mut directives = Self::default();
+
+        for token in tokens {
+            if token.kind != TokenKind::Comment {
+                continue;
+            }
+
+            let Some(body) = directive(token.text) else {
+                continue;
+            };
+
+            if let Some(lints) = next_line_lints(token.text) {
+                let start = text[token.end..]
+                    .find('\n')
+                    .map_or(text.len(), |i| token.end + i + 1);
+                let end = text[start..].find('\n').map_or(text.len(), |i| start + i);
+
+                directives.disabled_lines.push((start..end, lints));
+                continue;
+            }
+
+            if token.start >= header {
+                directives.problems.push(Problem {
+                    span: token.start..token.end,
+                    severity: DiagnosticSeverity::WARNING,
+                    message: format!(
+                        "Only `{DISABLE_NEXT_LINE}` applies after the first statement: directives for the file go at its top"
+                    ),
+                    lint: None,
+                });
+                continue;
+            }
+
+            directives.read_header(body, token.start..token.end);
+        }
+
+        directives
+    }
+
+    /// Reads the directives of the header of a file, e.g. `dialect=cqlsh disable=counter-key`,
+    /// from the comment at `span`
+    fn read_header(&mut self, body: &str, span: Range<usize>) {
+        let problem = |message: String| Problem {
+            span: span.clone(),
+            severity: DiagnosticSeverity::WARNING,
+            message,
+            lint: None,
+        };
+
+        for setting in body.split_whitespace() {
+            let problem = match setting.split_once('=') {
+                // Read by `dialect`, as the whole document depends on it
+                Some(("dialect", name)) => match parse_dialect(name) {
+                    Some(_) => continue,
+                    None => problem(format!(
+                        "Unknown dialect `{name}`: expected `auto`, `cql` or `cqlsh`"
+                    )),
+                },
+                Some(("disable", list)) => {
+                    self.disabled.extend(lints(list));
+                    continue;
+                }
+                _ => problem(format!(
+                    "Unknown directive `{setting}`: expected `dialect=...` or `disable=...`"
+                )),
+            };
+
+            self.problems.push(problem);
+        }
+    }
+
+    /// Whether the problem at `span`, found by the lint `lint` if any, is disabled
+    pub fn is_disabled(&self, span: &Range<usize>, lint: Option<&str>) -> bool {
+        let disabled = |lints: &[String]| lint.is_some_and(|lint| lints.iter().any(|l| l == lint));
+
+        disabled(&self.disabled)
+            || self.disabled_lines.iter().any(|(line, lints)| {
+                line.contains(&span.start) && (lints.is_empty() || disabled(lints))
+            })
+    }
+}
+
+/// The dialect the file whose header is `header` is read in, `default` unless its directives
+/// set another
+pub fn dialect(header: &str, default: Dialect) -> Dialect {
+    header
+        .lines()
+        .take_while(|line| in_header(line))
+        .filter_map(|line| directive(line.trim_start()))
+        .flat_map(str::split_whitespace)
+        .filter_map(|setting| match setting.split_once('=')? {
+            ("dialect", name) => parse_dialect(name),
+            _ => None,
+        })
+        .last()
+        .unwrap_or(default)
+}
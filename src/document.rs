@@ -1,13 +1,75 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::{directives, statement::Dialect, uri};
 use anyhow::Result;
 use ropey::{Rope, RopeSlice};
 use thiserror::Error;
-use tower_lsp::lsp_types::{Position, TextDocumentContentChangeEvent};
-use tree_sitter::{InputEdit, Parser, Point, Tree};
+use tower_lsp::lsp_types::{Position, Range, TextDocumentContentChangeEvent, Url};
 
 pub struct TextDocument {
     pub rope: Rope,
-    pub tree: Option<Tree>,
-    parser: Parser,
+    /// The version the client numbered the text with, increasing with each change
+    pub version: i32,
+    /// When the document was last opened or changed, in changes to any document
+    changed: u64,
+    /// The text of the rope, built on first use after each change, so that the requests on a
+    /// version of the document share a single copy
+    text: OnceLock<String>,
+    /// The lines of the text, indexed on first use after each change
+    lines: OnceLock<LineIndex>,
+}
+
+/// Where the lines of a version of a document start, so that positions on them are converted
+/// to offsets and back without walking the rope. Lines of ASCII, which most are, have as many
+/// columns as bytes in any encoding; those of other characters are left to the rope.
+struct LineIndex {
+    /// The byte offset each line starts at
+    starts: Vec<usize>,
+    /// Whether each line, its line break included, is ASCII
+    ascii: Vec<bool>,
+}
+
+impl LineIndex {
+    /// Indexes the lines of `text`, broken where the rope breaks them, i.e. at `\r\n` and
+    /// every Unicode line break
+    fn new(text: &str) -> Self {
+        let mut starts = vec![0];
+        let mut ascii = vec![];
+        let mut line_ascii = true;
+
+        for (i, character) in text.char_indices() {
+            line_ascii &= character.is_ascii();
+
+            let ends_line = match character {
+                '\r' => !text[i + 1..].starts_with('\n'),
+                '\n' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}' => true,
+                _ => false,
+            };
+
+            if ends_line {
+                starts.push(i + character.len_utf8());
+                ascii.push(line_ascii);
+                line_ascii = true;
+            }
+        }
+
+        ascii.push(line_ascii);
+
+        Self { starts, ascii }
+    }
+
+    /// The line `byte_idx` is on, with the offset it starts at, if the line is ASCII
+    fn ascii_line(&self, byte_idx: usize) -> Option<(usize, usize)> {
+        let line = self.starts.partition_point(|&start| start <= byte_idx) - 1;
+
+        self.ascii[line].then(|| (line, self.starts[line]))
+    }
 }
 
 #[derive(Debug, Error)]
@@ -20,46 +82,257 @@ pub enum DocumentError {
 /// type that is unconvenient to deal with.
 #[derive(Debug, Clone, Copy)]
 pub enum PositionEncodingKind {
-    #[allow(dead_code)]
     UTF8,
     UTF16,
-    #[allow(dead_code)]
     UTF32,
 }
 
+/// Computes the character offset into `slice` (a single line) that `position` points at
+fn compute_char_idx(
+    position_encoding: PositionEncodingKind,
+    position: &Position,
+    slice: &RopeSlice,
+) -> Result<usize, DocumentError> {
+    match position_encoding {
+        PositionEncodingKind::UTF8 => slice.try_byte_to_char(position.character as usize),
+        PositionEncodingKind::UTF16 => slice.try_utf16_cu_to_char(position.character as usize),
+        PositionEncodingKind::UTF32 => Ok(position.character as usize),
+    }
+    .map_err(|_| DocumentError::PositionOutOfBounds(position.line, position.character))
+}
+
+/// Counts the changes to any document, ordering documents by when they last changed
+fn next_change() -> u64 {
+    static CHANGES: AtomicU64 = AtomicU64::new(0);
+
+    CHANGES.fetch_add(1, Ordering::Relaxed)
+}
+
+/// The open documents, keyed by their normalized URI so that whichever spelling of a file's URI
+/// a request uses finds its document. The URI each was opened with is kept, as the client's
+/// spelling is the one its diagnostics are published for.
+#[derive(Default)]
+pub struct Documents(HashMap<Url, (Url, TextDocument)>);
+
+impl Documents {
+    pub fn get(&self, uri: &Url) -> Option<&TextDocument> {
+        self.0.get(&uri::normalize(uri)).map(|(_, doc)| doc)
+    }
+
+    pub fn get_mut(&mut self, uri: &Url) -> Option<&mut TextDocument> {
+        self.0.get_mut(&uri::normalize(uri)).map(|(_, doc)| doc)
+    }
+
+    pub fn insert(&mut self, uri: Url, doc: TextDocument) {
+        self.0.insert(uri::normalize(&uri), (uri, doc));
+    }
+
+    pub fn remove(&mut self, uri: &Url) -> Option<TextDocument> {
+        self.0.remove(&uri::normalize(uri)).map(|(_, doc)| doc)
+    }
+
+    /// The URIs the documents were opened with
+    pub fn keys(&self) -> impl Iterator<Item = &Url> {
+        self.0.values().map(|(uri, _)| uri)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut TextDocument> {
+        self.0.values_mut().map(|(_, doc)| doc)
+    }
+}
+
+/// Has the documents changed the longest ago drop what's derived from their text until
+/// `documents` take at most `limit` bytes, or only their text is left
+pub fn limit_memory<'a>(documents: impl IntoIterator<Item = &'a mut TextDocument>, limit: usize) {
+    let mut documents = documents.into_iter().collect::<Vec<_>>();
+    let mut memory = documents.iter().map(|doc| doc.memory()).sum::<usize>();
+
+    documents.sort_by_key(|doc| doc.changed);
+
+    for doc in documents {
+        if memory <= limit {
+            break;
+        }
+
+        let before = doc.memory();
+        doc.compact();
+        memory -= before - doc.memory();
+    }
+}
+
+/// Converts a byte offset into `rope` into an LSP position. Offsets past the end of the rope are
+/// clamped to the end.
+pub fn byte_idx_to_position(
+    rope: &Rope,
+    byte_idx: usize,
+    position_encoding: PositionEncodingKind,
+) -> Position {
+    let char_idx = rope.byte_to_char(byte_idx.min(rope.len_bytes()));
+    let line_idx = rope.char_to_line(char_idx);
+    let line_char_idx = char_idx - rope.line_to_char(line_idx);
+
+    let character = match position_encoding {
+        PositionEncodingKind::UTF8 => rope.char_to_byte(char_idx) - rope.line_to_byte(line_idx),
+        PositionEncodingKind::UTF16 => rope.line(line_idx).char_to_utf16_cu(line_char_idx),
+        PositionEncodingKind::UTF32 => line_char_idx,
+    };
+
+    Position {
+        line: line_idx as u32,
+        character: character as u32,
+    }
+}
+
 impl TextDocument {
-    // Creates a rope, tree, and parser from a given text (CQL code)
+    // Creates a rope from a given text (CQL code)
     pub fn new(text: &str) -> Self {
-        let rope = Rope::from_str(text);
+        Self {
+            rope: Rope::from_str(text),
+            version: 0,
+            changed: next_change(),
+            text: OnceLock::from(text.to_string()),
+            lines: OnceLock::new(),
+        }
+    }
 
-        let mut parser = Parser::new();
+    /// The whole text of the document
+    pub fn text(&self) -> &str {
+        self.text.get_or_init(|| self.rope.to_string())
+    }
 
-        let language = tree_sitter_cql3::LANGUAGE;
+    fn lines(&self) -> &LineIndex {
+        self.lines.get_or_init(|| LineIndex::new(self.text()))
+    }
 
-        // Set parser language should always succeed, but we're required to provide an error
-        // message nevertheless
-        parser
-            .set_language(&language.into())
-            .expect("Could not load language for Tree-sitter parser");
+    /// The dialect the document is read in: `default`, the `dialect` setting, unless the
+    /// directives at its top set another
+    pub fn dialect(&self, default: Dialect) -> Dialect {
+        let header = self
+            .rope
+            .lines()
+            .map(|line| line.to_string())
+            .take_while(|line| directives::in_header(line))
+            .collect::<String>();
 
-        // parser will always return a tree if the language is set properly and no timeout was
-        // specified
-        let tree = parser
-            .parse(text, None)
-            .expect("Could not parse CQL code with Tree-sitter");
+        directives::dialect(&header, default)
+    }
 
-        Self {
-            rope,
-            tree: Some(tree),
-            parser,
+    /// Roughly the bytes the document takes: its text, and the copy of it and the index of
+    /// its lines if made
+    pub fn memory(&self) -> usize {
+        self.rope.len_bytes()
+            + self.text.get().map_or(0, String::len)
+            + self.lines.get().map_or(0, |lines| {
+                lines.starts.len() * (size_of::<usize>() + size_of::<bool>())
+            })
+    }
+
+    /// Drops the copy of the text and the index of its lines, made again on use
+    pub fn compact(&mut self) {
+        self.text.take();
+        self.lines.take();
+    }
+
+    /// Converts an LSP position into a character index into the rope
+    pub fn position_to_char_idx(
+        &self,
+        position: &Position,
+        position_encoding: PositionEncodingKind,
+    ) -> Result<usize, DocumentError> {
+        let line = self.rope.get_line(position.line as usize).ok_or(
+            DocumentError::PositionOutOfBounds(position.line, position.character),
+        )?;
+
+        let line_char_idx = compute_char_idx(position_encoding, position, &line)?;
+
+        Ok(self.rope.line_to_char(position.line as usize) + line_char_idx)
+    }
+
+    /// Converts an LSP position into a byte offset into the document
+    pub fn position_to_byte_idx(
+        &self,
+        position: &Position,
+        position_encoding: PositionEncodingKind,
+    ) -> Result<usize, DocumentError> {
+        let lines = self.lines();
+        let line = position.line as usize;
+        let out_of_bounds = DocumentError::PositionOutOfBounds(position.line, position.character);
+
+        if line >= lines.starts.len() {
+            return Err(out_of_bounds);
+        }
+
+        if !lines.ascii[line] {
+            let char_idx = self.position_to_char_idx(position, position_encoding)?;
+
+            return Ok(self.rope.char_to_byte(char_idx));
+        }
+
+        // Up to the end of the line, its line break included, as the rope allows
+        let end = lines
+            .starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.rope.len_bytes());
+        let byte_idx = lines.starts[line] + position.character as usize;
+
+        match byte_idx <= end {
+            true => Ok(byte_idx),
+            false => Err(out_of_bounds),
         }
     }
 
+    /// Converts a byte offset into the document into an LSP position. Offsets past the end of
+    /// the document are clamped to the end.
+    pub fn byte_idx_to_position(
+        &self,
+        byte_idx: usize,
+        position_encoding: PositionEncodingKind,
+    ) -> Position {
+        let byte_idx = byte_idx.min(self.rope.len_bytes());
+
+        match self.lines().ascii_line(byte_idx) {
+            Some((line, start)) => Position {
+                line: line as u32,
+                character: (byte_idx - start) as u32,
+            },
+            None => byte_idx_to_position(&self.rope, byte_idx, position_encoding),
+        }
+    }
+
+    /// Converts a byte range of the document into an LSP range
+    pub fn byte_range_to_range(
+        &self,
+        byte_range: std::ops::Range<usize>,
+        position_encoding: PositionEncodingKind,
+    ) -> Range {
+        Range {
+            start: self.byte_idx_to_position(byte_range.start, position_encoding),
+            end: self.byte_idx_to_position(byte_range.end, position_encoding),
+        }
+    }
+
+    /// Returns the text between the start of the line and the given position
+    pub fn line_prefix(
+        &self,
+        position: &Position,
+        position_encoding: PositionEncodingKind,
+    ) -> Result<String, DocumentError> {
+        let byte_idx = self.position_to_byte_idx(position, position_encoding)?;
+        let line_start = self.lines().starts[position.line as usize];
+
+        Ok(self.text()[line_start..byte_idx].to_string())
+    }
+
     pub fn apply_content_change(
         &mut self,
         change: TextDocumentContentChangeEvent,
         position_encoding: PositionEncodingKind,
     ) -> Result<(), DocumentError> {
+        self.text.take();
+        self.lines.take();
+        self.changed = next_change();
+
         match change.range {
             Some(range) => {
                 // Make sure start of the line position is behind the end of the line or if on
@@ -74,9 +347,6 @@ impl TextDocument {
                 let same_line = range.start.line == range.end.line;
                 let same_character = range.start.character == range.end.character;
 
-                let change_start_line_cu_idx = range.start.line as usize;
-                let change_end_line_cu_idx = range.start.line as usize;
-
                 // 1. Get the line at which the change starts
                 let change_start_line_idx = range.start.line as usize;
                 let change_start_line = match self.rope.get_line(change_start_line_idx) {
@@ -106,25 +376,6 @@ impl TextDocument {
                     },
                 };
 
-                fn compute_char_idx(
-                    position_encoding: PositionEncodingKind,
-                    position: &Position,
-                    slice: &RopeSlice,
-                ) -> Result<usize, DocumentError> {
-                    match position_encoding {
-                        PositionEncodingKind::UTF8 => {
-                            slice.try_byte_to_char(position.character as usize)
-                        }
-                        PositionEncodingKind::UTF16 => {
-                            slice.try_utf16_cu_to_char(position.character as usize)
-                        }
-                        PositionEncodingKind::UTF32 => Ok(position.character as usize),
-                    }
-                    .map_err(|_| {
-                        DocumentError::PositionOutOfBounds(position.line, position.character)
-                    })
-                }
-
                 // 3. Compute the character offset into the start/end line where the change
                 //    starts/ends
                 let change_start_line_char_idx =
@@ -134,7 +385,7 @@ impl TextDocument {
                     false => compute_char_idx(position_encoding, &range.end, &change_end_line)?,
                 };
 
-                // 4. Compute the character and byte offset into the document where the change
+                // 4. Compute the character offset into the document where the change
                 //    starts/ends
                 let change_start_doc_char_idx =
                     self.rope.line_to_char(change_start_line_idx) + change_start_line_char_idx;
@@ -144,81 +395,155 @@ impl TextDocument {
                     false => self.rope.line_to_char(change_end_line_idx) + change_end_line_char_idx,
                 };
 
-                let change_start_doc_byte_idx = self.rope.char_to_byte(change_start_doc_char_idx);
-                let change_end_doc_byte_idx = match same_line && same_character {
-                    true => change_start_doc_byte_idx,
-                    false => self.rope.char_to_byte(change_end_doc_char_idx),
-                };
-
-                // 5. Compute the byte offset into the start/end line where the change starts/end.
-                //    Required for tree-sitter
-                let change_start_line_byte_idx = match position_encoding {
-                    PositionEncodingKind::UTF8 => change_start_line_cu_idx,
-                    PositionEncodingKind::UTF16 => {
-                        change_end_line.char_to_utf16_cu(change_start_line_char_idx)
-                    }
-                    PositionEncodingKind::UTF32 => change_start_line_char_idx,
-                };
-                let change_end_line_byte_idx = match same_line && same_character {
-                    true => change_start_line_byte_idx,
-                    false => match position_encoding {
-                        PositionEncodingKind::UTF8 => change_end_line_cu_idx,
-                        PositionEncodingKind::UTF16 => {
-                            change_end_line.char_to_utf16_cu(change_end_line_char_idx)
-                        }
-                        PositionEncodingKind::UTF32 => change_end_line_char_idx,
-                    },
-                };
-
                 self.rope
                     .remove(change_start_doc_char_idx..change_end_doc_char_idx);
 
                 self.rope.insert(change_start_doc_char_idx, &change.text);
-
-                if let Some(tree) = &mut self.tree {
-                    // 6. Compute the byte index into the new end line where the change ends.
-                    //    Required for tree-sitter
-                    let change_new_end_line_idx = self
-                        .rope
-                        .byte_to_line(change_start_doc_byte_idx + change.text.len());
-                    let change_new_end_line_byte_idx =
-                        change_start_doc_byte_idx + change.text.len();
-
-                    // 7. Construct the tree-sitter edit. We stay mindful that tree-sitter
-                    //    Point::column is a byte offset
-                    let edit = InputEdit {
-                        start_byte: change_start_doc_byte_idx,
-                        old_end_byte: change_end_doc_byte_idx,
-                        new_end_byte: change_start_doc_byte_idx + change.text.len(),
-                        start_position: Point {
-                            row: change_start_line_idx,
-                            column: change_start_line_byte_idx,
-                        },
-                        old_end_position: Point {
-                            row: change_end_line_idx,
-                            column: change_end_line_byte_idx,
-                        },
-                        new_end_position: Point {
-                            row: change_new_end_line_idx,
-                            column: change_new_end_line_byte_idx,
-                        },
-                    };
-
-                    tree.edit(&edit);
-
-                    self.tree = Some(
-                        self.parser
-                            .parse(self.rope.to_string(), Some(tree))
-                            .expect("Could not construct a tree for the current edit"),
-                    );
-                };
             }
             None => {
                 self.rope = Rope::from_str(&change.text);
-                self.tree = self.parser.parse(&change.text, None);
+                self.text = OnceLock::from(change.text);
             }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENCODINGS: [PositionEncodingKind; 3] = [
+        PositionEncodingKind::UTF8,
+        PositionEncodingKind::UTF16,
+        PositionEncodingKind::UTF32,
+    ];
+
+    const TEXTS: &[&str] = &[
+        "",
+        "SELECT * FROM t;\nINSERT INTO t (a) VALUES (1);\n",
+        "a\r\nb\rc\n\r\nd",
+        "é\u{85}漢\u{2028}😀\u{2029}x\u{0B}y\u{0C}z",
+        "-- ascii\n'ü' then ascii\nSELECT '😀';\r\n",
+    ];
+
+    #[test]
+    fn lines_break_like_the_rope() {
+        for text in TEXTS {
+            let rope = Rope::from_str(text);
+            let lines = LineIndex::new(text);
+            let starts = (0..rope.len_lines())
+                .map(|line| rope.line_to_byte(line))
+                .collect::<Vec<_>>();
+
+            assert_eq!(lines.starts, starts, "{text:?}");
+            assert_eq!(lines.ascii.len(), starts.len(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn positions_match_the_rope() {
+        for text in TEXTS {
+            let doc = TextDocument::new(text);
+            let boundaries = (0..=text.len()).filter(|&i| text.is_char_boundary(i));
+
+            for byte_idx in boundaries {
+                for encoding in ENCODINGS {
+                    let position = doc.byte_idx_to_position(byte_idx, encoding);
+
+                    assert_eq!(
+                        position,
+                        byte_idx_to_position(&doc.rope, byte_idx, encoding),
+                        "{text:?} at {byte_idx}"
+                    );
+                    assert_eq!(
+                        doc.position_to_byte_idx(&position, encoding).unwrap(),
+                        byte_idx,
+                        "{text:?} at {position:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn positions_out_of_bounds() {
+        let doc = TextDocument::new("ab\ncd");
+        let position = |line, character| Position { line, character };
+
+        assert_eq!(
+            doc.position_to_byte_idx(&position(0, 3), PositionEncodingKind::UTF16)
+                .unwrap(),
+            3
+        );
+        assert!(
+            doc.position_to_byte_idx(&position(0, 4), PositionEncodingKind::UTF16)
+                .is_err()
+        );
+        assert!(
+            doc.position_to_byte_idx(&position(2, 0), PositionEncodingKind::UTF16)
+                .is_err()
+        );
+        assert_eq!(
+            doc.byte_idx_to_position(100, PositionEncodingKind::UTF16),
+            position(1, 2)
+        );
+    }
+
+    #[test]
+    fn changes_index_the_lines_again() {
+        let mut doc = TextDocument::new("SELECT *\nFROM t;");
+        let position = Position {
+            line: 1,
+            character: 4,
+        };
+
+        assert_eq!(
+            doc.line_prefix(&position, PositionEncodingKind::UTF16)
+                .unwrap(),
+            "FROM"
+        );
+
+        doc.apply_content_change(
+            TextDocumentContentChangeEvent {
+                range: Some(Range {
+                    start: Position {
+                        line: 0,
+                        character: 6,
+                    },
+                    end: Position {
+                        line: 0,
+                        character: 6,
+                    },
+                }),
+                range_length: None,
+                text: " 'é',\n  a\n ".into(),
+            },
+            PositionEncodingKind::UTF16,
+        )
+        .unwrap();
+
+        assert_eq!(doc.text(), "SELECT 'é',\n  a\n  *\nFROM t;");
+        assert_eq!(
+            doc.line_prefix(&position, PositionEncodingKind::UTF16)
+                .unwrap(),
+            "  a\n"
+        );
+        assert_eq!(
+            doc.byte_idx_to_position(doc.text().len(), PositionEncodingKind::UTF16),
+            Position {
+                line: 3,
+                character: 7
+            }
+        );
+
+        doc.compact();
+
+        assert_eq!(
+            doc.position_to_byte_idx(&position, PositionEncodingKind::UTF8)
+                .unwrap(),
+            17
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Links to the files run by cqlsh's `SOURCE 'file'` command, so that scripts split across
+//! files can be followed from one to the next.
+
+use std::path::{Path, PathBuf};
+
+use tower_lsp::lsp_types::{DocumentLink, Url};
+
+use crate::{
+    document::{PositionEncodingKind, TextDocument},
+    lexer::{self, TokenKind},
+    statement::{self, Dialect},
+};
+
+/// Resolves `path` like cqlsh does, expanding `~` to the home directory, with relative paths
+/// taken from `directory`, which documents that aren't files, e.g. unsaved ones, don't have
+fn resolve(path: &str, directory: Option<&Path>) -> Option<PathBuf> {
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var_os("HOME")?).join(rest),
+        None => PathBuf::from(path),
+    };
+
+    match path.is_absolute() {
+        true => Some(path),
+        false => Some(directory?.join(path)),
+    }
+}
+
+/// Links the file of every `SOURCE` command of the document at `uri`, relative paths being
+/// resolved from the document's directory
+pub fn document_links(
+    doc: &TextDocument,
+    uri: &Url,
+    dialect: Dialect,
+    position_encoding: PositionEncodingKind,
+) -> Vec<DocumentLink> {
+    let directory = uri
+        .to_file_path()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf));
+
+    let text = doc.text();
+    let tokens = lexer::tokenize(text);
+
+    statement::split(&tokens, dialect)
+        .iter()
+        .filter(|statement| statement.cqlsh_command() == Some("SOURCE"))
+        .filter_map(|statement| {
+            let file = statement.tokens.get(1)?;
+
+            if file.kind != TokenKind::String || file.text.starts_with("$$") {
+                return None;
+            }
+
+            let path = resolve(&file.string_value()?, directory.as_deref())?;
+            let target = Url::from_file_path(&path).ok()?;
+
+            // The link covers the path, not its quotes
+            let end = match file.text.len() > 1 && file.text.ends_with('\'') {
+                true => file.end - 1,
+                false => file.end,
+            };
+
+            Some(DocumentLink {
+                range: doc.byte_range_to_range(file.start + 1..end, position_encoding),
+                target: Some(target),
+                tooltip: Some(path.display().to_string()),
+                data: None,
+            })
+        })
+        .collect()
+}
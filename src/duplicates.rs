@@ -0,0 +1,128 @@
+//! Definitions given twice: columns of a `CREATE TABLE` or fields of a `CREATE TYPE` named
+//! alike, primary keys declared more than once, and tables or types created again where the
+//! workspace already defines them.
+
+use std::ops::Range;
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    lexer::Token,
+    schema::Schema,
+    statement::{
+        self, Statement, StatementKind, matching_bracket, parse_qualified_name, skip_if_exists,
+    },
+};
+
+fn error(span: Range<usize>, message: String) -> Problem {
+    Problem {
+        span,
+        severity: DiagnosticSeverity::ERROR,
+        message,
+        lint: None,
+    }
+}
+
+/// Whether `tokens` starts with `PRIMARY KEY`
+fn is_primary_key(tokens: &[Token]) -> bool {
+    matches!(tokens, [primary, key, ..] if primary.is_keyword("PRIMARY") && key.is_keyword("KEY"))
+}
+
+/// Checks the definitions between the parentheses of a `CREATE TABLE` or `CREATE TYPE`:
+/// names must be unique, and a table's primary key declared once
+fn check_definitions(body: &[Token], kind: &str, problems: &mut Vec<Problem>) {
+    let mut names = vec![];
+    let mut primary_keys = 0;
+
+    for definition in statement::split_commas(body) {
+        // `PRIMARY KEY (...)`, or `PRIMARY KEY` following a column's type
+        let primary_key = (0..definition.len())
+            .find(|&i| is_primary_key(&definition[i..]))
+            .map(|i| &definition[i..i + 2]);
+
+        if let Some(primary_key) = primary_key {
+            primary_keys += 1;
+
+            if primary_keys > 1 {
+                problems.push(error(
+                    primary_key[0].start..primary_key[1].end,
+                    "The primary key is already declared: a table has a single PRIMARY KEY, listing all its columns".into(),
+                ));
+            }
+        }
+
+        if is_primary_key(definition) {
+            continue;
+        }
+
+        let Some(name) = definition.first().filter(|_| definition.len() > 1) else {
+            continue;
+        };
+
+        let Some(identifier) = name.identifier() else {
+            continue;
+        };
+
+        if names.contains(&identifier) {
+            problems.push(error(
+                name.start..name.end,
+                format!("{kind} `{identifier}` is already defined"),
+            ));
+        } else {
+            names.push(identifier);
+        }
+    }
+}
+
+/// Checks a `CREATE TABLE` or `CREATE TYPE` for duplicate definitions, and for creating an
+/// object `schema` already has without `IF NOT EXISTS`
+pub fn check(
+    statement: &Statement,
+    schema: &Schema,
+    current_keyspace: Option<&str>,
+) -> Vec<Problem> {
+    let (object, member) = match statement.kind() {
+        StatementKind::CreateTable => ("Table", "Column"),
+        StatementKind::CreateType => ("Type", "Field"),
+        _ => return vec![],
+    };
+
+    let tokens = statement.tokens;
+    let mut i = skip_if_exists(tokens, 2);
+    let if_not_exists = i > 2;
+
+    let Some(name) = parse_qualified_name(tokens, &mut i) else {
+        return vec![];
+    };
+
+    let mut problems = vec![];
+
+    if tokens.get(i).is_some_and(|t| t.is_punctuation("(")) {
+        let close = matching_bracket(tokens, i).unwrap_or(tokens.len());
+        check_definitions(&tokens[i + 1..close], member, &mut problems);
+    }
+
+    let keyspace = name.keyspace.as_deref().or(current_keyspace);
+
+    let Some(keyspace) = keyspace.filter(|_| !if_not_exists) else {
+        return problems;
+    };
+
+    let exists = match statement.kind() {
+        StatementKind::CreateTable => schema.table(Some(keyspace), &name.name).is_some(),
+        _ => schema.user_type(Some(keyspace), &name.name).is_some(),
+    };
+
+    if exists {
+        problems.push(error(
+            name.start..name.end,
+            format!(
+                "{object} `{keyspace}.{}` is already defined, in this document or elsewhere in the workspace: creating it again fails unless IF NOT EXISTS is given",
+                name.name
+            ),
+        ));
+    }
+
+    problems
+}
@@ -0,0 +1,141 @@
+//! Duration literals, e.g. `1h30m` or `P1DT2H`, which Cassandra stores as months, days and
+//! nanoseconds, kept apart as months and days vary in length.
+
+/// The nanoseconds in a unit of the time of day
+const UNITS: &[(&str, i64)] = &[
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("µs", 1_000),
+    ("ns", 1),
+];
+
+/// Adds `value` of `unit` to the months, days and nanoseconds of `duration`
+fn add(duration: &mut (i64, i64, i64), value: i64, unit: &str) -> Option<()> {
+    let (months, days, nanoseconds) = duration;
+
+    match unit {
+        "y" => *months = months.checked_add(value.checked_mul(12)?)?,
+        "mo" => *months = months.checked_add(value)?,
+        "w" => *days = days.checked_add(value.checked_mul(7)?)?,
+        "d" => *days = days.checked_add(value)?,
+        _ => {
+            let (_, size) = UNITS.iter().find(|(name, _)| *name == unit)?;
+            *nanoseconds = nanoseconds.checked_add(value.checked_mul(*size)?)?;
+        }
+    }
+
+    Some(())
+}
+
+/// Parses a duration literal, e.g. `1h30m`, `-2d` or `P1DT2H`, into its months, days and
+/// nanoseconds
+pub fn parse(text: &str) -> Option<(i64, i64, i64)> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+
+    let mut duration = (0, 0, 0);
+
+    if let Some(iso) = text.strip_prefix('P') {
+        // `P1Y2M3W4DT5H6M7S`, where `M` is months before the `T` and minutes after it
+        let (date, time) = iso.split_once('T').unwrap_or((iso, ""));
+
+        for (part, units) in [(date, "YMWD"), (time, "HMS")] {
+            let mut rest = part;
+
+            while !rest.is_empty() {
+                let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+                let value = rest[..digits].parse().ok()?;
+                let designator = rest[digits..].chars().next()?;
+
+                let unit = match (units, designator) {
+                    ("YMWD", 'Y') => "y",
+                    ("YMWD", 'M') => "mo",
+                    ("YMWD", 'W') => "w",
+                    ("YMWD", 'D') => "d",
+                    ("HMS", 'H') => "h",
+                    ("HMS", 'M') => "m",
+                    ("HMS", 'S') => "s",
+                    _ => return None,
+                };
+
+                add(&mut duration, value, unit)?;
+                rest = &rest[digits + 1..];
+            }
+        }
+    } else {
+        let mut rest = text;
+
+        while !rest.is_empty() {
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            let value = rest[..digits].parse().ok()?;
+            let units = &rest[digits..];
+
+            // The longest unit matching, so `ms` isn't taken for `m`
+            let unit = ["mo", "ms", "us", "µs", "ns", "y", "w", "d", "h", "m", "s"]
+                .into_iter()
+                .find(|unit| {
+                    units
+                        .get(..unit.len())
+                        .is_some_and(|head| head.eq_ignore_ascii_case(unit))
+                })?;
+
+            add(&mut duration, value, unit)?;
+            rest = &units[unit.len()..];
+        }
+    }
+
+    let (months, days, nanoseconds) = duration;
+
+    Some(match negative {
+        true => (-months, -days, -nanoseconds),
+        false => (months, days, nanoseconds),
+    })
+}
+
+/// Like Cassandra, e.g. `1y2mo3d4h5m6s`
+pub fn format(months: i64, days: i64, nanoseconds: i64) -> String {
+    let negative = months < 0 || days < 0 || nanoseconds < 0;
+    let (months, days, mut nanoseconds) = (
+        months.unsigned_abs(),
+        days.unsigned_abs(),
+        nanoseconds.unsigned_abs(),
+    );
+
+    let mut text = String::new();
+
+    if negative {
+        text.push('-');
+    }
+
+    let mut unit = |value: u64, unit: &str| {
+        if value != 0 {
+            text.push_str(&format!("{value}{unit}"));
+        }
+    };
+
+    unit(months / 12, "y");
+    unit(months % 12, "mo");
+    unit(days, "d");
+
+    for (size, name) in [
+        (3_600_000_000_000, "h"),
+        (60_000_000_000, "m"),
+        (1_000_000_000, "s"),
+        (1_000_000, "ms"),
+        (1_000, "us"),
+        (1, "ns"),
+    ] {
+        unit(nanoseconds / size, name);
+        nanoseconds %= size;
+    }
+
+    match text.trim_start_matches('-').is_empty() {
+        true => "0s".to_string(),
+        false => text,
+    }
+}
@@ -0,0 +1,300 @@
+//! Features only some databases, or versions of them, have, flagged when the `target` setting
+//! names one without them, e.g. `GROUP BY` for Cassandra 3.0, `BYPASS CACHE` for Cassandra, or
+//! the extensions of DSE for other databases.
+//! The message names the first versions having the feature, so that it's clear whether
+//! upgrading is enough.
+
+use std::ops::Range;
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    index,
+    lexer::{Token, TokenKind, is_reserved},
+    statement::{Statement, StatementKind},
+    target::{Database, Target},
+    using,
+};
+
+/// A feature, with the first version of each database having it
+pub struct Feature {
+    name: &'static str,
+    since: &'static [(Database, (u32, u32))],
+}
+
+const GROUP_BY: Feature = Feature {
+    name: "`GROUP BY`",
+    since: &[
+        (Database::Cassandra, (3, 10)),
+        (Database::Scylla, (3, 2)),
+        (Database::Dse, (6, 0)),
+    ],
+};
+
+const PER_PARTITION_LIMIT: Feature = Feature {
+    name: "`PER PARTITION LIMIT`",
+    since: &[
+        (Database::Cassandra, (3, 6)),
+        (Database::Scylla, (2, 1)),
+        (Database::Dse, (5, 1)),
+    ],
+};
+
+const SAI: Feature = Feature {
+    name: "storage-attached indexes",
+    since: &[(Database::Cassandra, (5, 0)), (Database::Dse, (6, 8))],
+};
+
+const VECTOR: Feature = Feature {
+    name: "the `vector` type",
+    since: &[(Database::Cassandra, (5, 0)), (Database::Dse, (6, 9))],
+};
+
+const BYPASS_CACHE: Feature = Feature {
+    name: "`BYPASS CACHE`",
+    since: &[(Database::Scylla, (3, 1))],
+};
+
+const USING_TIMEOUT: Feature = Feature {
+    name: "`USING TIMEOUT`",
+    since: &[(Database::Scylla, (4, 4))],
+};
+
+const TOMBSTONE_GC: Feature = Feature {
+    name: "`tombstone_gc`",
+    since: &[(Database::Scylla, (5, 0))],
+};
+
+const PER_PARTITION_RATE_LIMIT: Feature = Feature {
+    name: "`per_partition_rate_limit`",
+    since: &[(Database::Scylla, (5, 1))],
+};
+
+const SEARCH_INDEX: Feature = Feature {
+    name: "DSE Search's index statements",
+    since: &[(Database::Dse, (5, 1))],
+};
+
+const SOLR_QUERY: Feature = Feature {
+    name: "`solr_query`",
+    since: &[(Database::Dse, (4, 6))],
+};
+
+const ROW_LEVEL_ACCESS: Feature = Feature {
+    name: "`RESTRICT` and `UNRESTRICT`",
+    since: &[(Database::Dse, (5, 1))],
+};
+
+const GRAPH: Feature = Feature {
+    name: "DSE Graph's labels and engine",
+    since: &[(Database::Dse, (6, 8))],
+};
+
+/// Also gates the completion of the durations they take
+pub const ARITHMETIC: Feature = Feature {
+    name: "arithmetic operators",
+    since: &[(Database::Cassandra, (4, 0))],
+};
+
+/// Checked along the other requirements of `frozen<>`
+pub const NON_FROZEN_UDT: Feature = Feature {
+    name: "non-frozen user-defined types",
+    since: &[
+        (Database::Cassandra, (3, 6)),
+        (Database::Scylla, (3, 2)),
+        (Database::Dse, (5, 1)),
+    ],
+};
+
+impl Feature {
+    pub fn is_supported(&self, target: Target) -> bool {
+        self.since
+            .iter()
+            .any(|&(database, version)| target.is_at_least(database, version))
+    }
+
+    /// Why `target` can't use the feature, e.g. "Cassandra 3.0 doesn't support `GROUP BY`: it
+    /// needs Cassandra 3.10 or later"
+    pub fn message(&self, target: Target) -> String {
+        let version = |(database, (major, minor)): &(Database, (u32, u32))| {
+            format!("{database} {major}.{minor} or later")
+        };
+
+        // Upgrading is the way when the target's database has the feature
+        let needed = match self.since.iter().find(|(db, _)| *db == target.database) {
+            Some(since) => version(since),
+            None => self
+                .since
+                .iter()
+                .map(version)
+                .collect::<Vec<_>>()
+                .join(", or "),
+        };
+
+        format!("{target} doesn't support {}: it needs {needed}", self.name)
+    }
+}
+
+/// The binary arithmetic operators among `tokens`, e.g. the `-` of `toTimestamp(now()) - 1d`,
+/// telling them apart from signs such as that of `id = -1` by the operand before them
+fn arithmetic<'a>(tokens: &'a [Token<'a>]) -> impl Iterator<Item = &'a Token<'a>> {
+    tokens
+        .windows(2)
+        .filter(|pair| {
+            let operand = match pair[0].kind {
+                TokenKind::Word => !is_reserved(pair[0].text),
+                TokenKind::Punctuation => pair[0].text == ")",
+                TokenKind::Comment => false,
+                _ => true,
+            };
+
+            operand
+                && pair[1].kind == TokenKind::Punctuation
+                && matches!(pair[1].text, "+" | "-" | "*" | "/" | "%")
+        })
+        .map(|pair| &pair[1])
+}
+
+/// The features `statement` uses, with where
+fn uses(statement: &Statement) -> Vec<(&'static Feature, Range<usize>)> {
+    let tokens = statement.tokens;
+    let mut uses = vec![];
+
+    // The span from the keyword `first` to the token after it, e.g. `GROUP BY`
+    let pair = |first: &str| {
+        let i = statement.find_keyword(first)?;
+
+        Some(tokens[i].start..tokens.get(i + 1).unwrap_or(&tokens[i]).end)
+    };
+
+    // The name of an option or pseudo-column set with `=`, e.g. `solr_query = '...'`
+    let setting = |name: &str| {
+        tokens
+            .windows(2)
+            .find(|pair| pair[0].is_keyword(name) && pair[1].is_punctuation("="))
+            .map(|pair| pair[0].start..pair[0].end)
+    };
+
+    match statement.kind() {
+        StatementKind::Select => {
+            let clauses = [
+                ("GROUP", &GROUP_BY),
+                ("PER", &PER_PARTITION_LIMIT),
+                ("BYPASS", &BYPASS_CACHE),
+            ];
+
+            for (keyword, feature) in clauses {
+                if let Some(span) = pair(keyword) {
+                    uses.push((feature, span));
+                }
+            }
+
+            uses.extend(setting("solr_query").map(|span| (&SOLR_QUERY, span)));
+
+            // Selectors, e.g. `SELECT ts + 1h`, after the modifiers that `*` may follow
+            let start = 1 + tokens[1..]
+                .iter()
+                .take_while(|token| token.is_keyword("JSON") || token.is_keyword("DISTINCT"))
+                .count();
+
+            if let Some(from) = statement.find_keyword("FROM")
+                && start < from
+            {
+                uses.extend(
+                    arithmetic(&tokens[start..from])
+                        .map(|operator| (&ARITHMETIC, operator.start..operator.end)),
+                );
+            }
+        }
+        StatementKind::CreateKeyspace | StatementKind::AlterKeyspace => {
+            uses.extend(setting("graph_engine").map(|span| (&GRAPH, span)));
+        }
+        StatementKind::CreateTable | StatementKind::AlterTable => {
+            uses.extend(setting("tombstone_gc").map(|span| (&TOMBSTONE_GC, span)));
+            uses.extend(
+                setting("per_partition_rate_limit").map(|span| (&PER_PARTITION_RATE_LIMIT, span)),
+            );
+
+            // Unlike a column named `vertex`, `VERTEX LABEL`
+            let label = tokens.windows(2).find(|pair| {
+                (pair[0].is_keyword("VERTEX") || pair[0].is_keyword("EDGE"))
+                    && pair[1].is_keyword("LABEL")
+            });
+
+            uses.extend(label.map(|pair| (&GRAPH, pair[0].start..pair[1].end)));
+        }
+        StatementKind::SearchIndex => {
+            if let Some(index) = statement.find_keyword("INDEX") {
+                uses.push((&SEARCH_INDEX, tokens[0].start..tokens[index].end));
+            }
+        }
+        StatementKind::Restrict | StatementKind::Unrestrict => {
+            uses.push((&ROW_LEVEL_ACCESS, tokens[0].start..tokens[0].end));
+        }
+        StatementKind::CreateIndex => {
+            if index::parse(statement).is_some_and(|index| index.is_sai())
+                && let Some(span) = pair("USING")
+            {
+                uses.push((&SAI, span));
+            }
+        }
+        _ => {}
+    }
+
+    // Relations, e.g. `WHERE ts > toTimestamp(now()) - 1d`, rather than the relative updates
+    // of `SET`, which every version has
+    if matches!(
+        statement.kind(),
+        StatementKind::Select | StatementKind::Update | StatementKind::Delete
+    ) && let Some(r#where) = statement.find_keyword("WHERE")
+    {
+        let end = statement
+            .find_keyword_from("IF", r#where)
+            .unwrap_or(tokens.len());
+
+        uses.extend(
+            arithmetic(&tokens[r#where + 1..end])
+                .map(|operator| (&ARITHMETIC, operator.start..operator.end)),
+        );
+    }
+
+    uses.extend(
+        using::options(statement)
+            .into_iter()
+            .filter(|(option, _)| option.is_keyword("TIMEOUT"))
+            .map(|(option, _)| (&USING_TIMEOUT, option.start..option.end)),
+    );
+
+    // `vector<float, 3>`, in column and field definitions as well as casts
+    uses.extend(
+        tokens
+            .windows(2)
+            .filter(|pair| {
+                pair[0].kind == TokenKind::Word
+                    && pair[0].text.eq_ignore_ascii_case("vector")
+                    && pair[1].is_punctuation("<")
+            })
+            .map(|pair| (&VECTOR, pair[0].start..pair[0].end)),
+    );
+
+    uses
+}
+
+/// Checks that `target` has the features `statement` uses
+pub fn check(statement: &Statement, target: Option<Target>) -> Vec<Problem> {
+    let Some(target) = target else {
+        return vec![];
+    };
+
+    uses(statement)
+        .into_iter()
+        .filter(|(feature, _)| !feature.is_supported(target))
+        .map(|(feature, span)| Problem {
+            span,
+            severity: DiagnosticSeverity::ERROR,
+            message: feature.message(target),
+            lint: None,
+        })
+        .collect()
+}
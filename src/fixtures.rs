@@ -0,0 +1,176 @@
+//! Literals generated for test data: random `uuid` and `timeuuid` values, text as a `blob`,
+//! and random values of any type.
+
+use std::{
+    hash::{BuildHasher, Hasher, RandomState},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{cql_type::CqlType, datetime, lexer::quote_identifier, schema::Schema};
+
+/// The 100-nanosecond intervals between the start of the Gregorian calendar, which version 1
+/// UUIDs count from, and the Unix epoch
+const GREGORIAN_OFFSET: u64 = 0x01B2_1DD2_1381_4000;
+
+/// 64 random bits, from the randomly keyed hasher of the standard library
+fn random_bits() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos()),
+    );
+
+    hasher.finish()
+}
+
+/// Formats the 128 bits of a UUID, e.g. `123e4567-e89b-12d3-a456-426614174000`
+fn format(high: u64, low: u64) -> String {
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+/// A random version 4 UUID, as `uuid()` returns
+pub fn uuid() -> String {
+    let high = (random_bits() & !0xf000) | 0x4000;
+    let low = (random_bits() & !(0b11 << 62)) | (0b10 << 62);
+
+    format(high, low)
+}
+
+/// A version 1 UUID of the current time, as `now()` returns, with a random clock sequence and
+/// node, marked as random by its multicast bit
+pub fn timeuuid() -> String {
+    let intervals = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| (elapsed.as_nanos() / 100) as u64)
+        + GREGORIAN_OFFSET;
+
+    let high = (intervals & 0xffff_ffff) << 32
+        | ((intervals >> 32) & 0xffff) << 16
+        | ((intervals >> 48) & 0x0fff)
+        | 0x1000;
+
+    let random = random_bits();
+    let clock_sequence = (random >> 48) & 0x3fff | 0x8000;
+    let node = random & 0xffff_ffff_ffff | 0x0100_0000_0000;
+
+    format(high, clock_sequence << 48 | node)
+}
+
+/// `text` as a `blob` literal of its UTF-8 bytes, e.g. `0x6869` for `hi`
+pub fn hex_blob(text: &str) -> String {
+    let hex = text
+        .bytes()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+
+    format!("0x{hex}")
+}
+
+/// A random number in `0..bound`
+fn below(bound: u64) -> u64 {
+    random_bits() % bound.max(1)
+}
+
+/// A random word of lower case letters and digits, e.g. `k3x9a0`
+fn word(len: usize) -> String {
+    (0..len)
+        .map(|_| char::from(b"abcdefghijklmnopqrstuvwxyz0123456789"[below(36) as usize]))
+        .collect()
+}
+
+/// A random literal of the native type `name`, or `None` for types without literals to make
+/// up, such as counters
+fn native(name: &str) -> Option<String> {
+    let signed = |bits: u32| (below(1 << bits) as i64 - (1 << (bits - 1))).to_string();
+
+    // Within the year before the Unix time 1,700,000,000, late 2023
+    let seconds = 1_700_000_000 - below(365 * 86_400) as i64;
+
+    Some(match name {
+        "ascii" | "text" | "varchar" => format!("'{}'", word(8)),
+        "tinyint" => signed(8),
+        "smallint" => signed(16),
+        "int" => signed(32),
+        "bigint" | "varint" => signed(48),
+        "float" | "double" | "decimal" => format!("{}.{:02}", below(10_000), below(100)),
+        "boolean" => (below(2) == 1).to_string(),
+        "uuid" => uuid(),
+        "timeuuid" => timeuuid(),
+        "timestamp" => format!(
+            "'{} {}+0000'",
+            datetime::format_date(seconds.div_euclid(86_400)),
+            &datetime::format_time(seconds.rem_euclid(86_400) * 1_000_000_000)[..8]
+        ),
+        "date" => format!("'{}'", datetime::format_date(seconds.div_euclid(86_400))),
+        "time" => format!(
+            "'{}'",
+            &datetime::format_time(seconds.rem_euclid(86_400) * 1_000_000_000)[..8]
+        ),
+        "blob" => format!("0x{:016x}", random_bits()),
+        "inet" => format!("'10.{}.{}.{}'", below(256), below(256), 1 + below(254)),
+        "duration" => format!("{}h{}m", below(24), below(60)),
+        _ => return None,
+    })
+}
+
+/// A random literal of `data_type`, the user-defined types it's made of being looked up in
+/// `schema` from `keyspace`, the table's. Returns `None` for types without literals to make up,
+/// such as counters and custom types.
+pub fn value(data_type: &CqlType, schema: &Schema, keyspace: &str) -> Option<String> {
+    let values = |element: &CqlType, n: u64| {
+        (0..n)
+            .map(|_| value(element, schema, keyspace))
+            .collect::<Option<Vec<_>>>()
+            .map(|values| values.join(", "))
+    };
+
+    Some(match data_type {
+        CqlType::Native(name) if name == "counter" => return None,
+        CqlType::Native(name) => native(name)?,
+        CqlType::List(element) => format!("[{}]", values(element, 1 + below(3))?),
+        CqlType::Set(element) => format!("{{{}}}", values(element, 1 + below(3))?),
+        CqlType::Map(key, element) => format!(
+            "{{{}: {}}}",
+            value(key, schema, keyspace)?,
+            value(element, schema, keyspace)?
+        ),
+        CqlType::Tuple(elements) => format!(
+            "({})",
+            elements
+                .iter()
+                .map(|element| value(element, schema, keyspace))
+                .collect::<Option<Vec<_>>>()?
+                .join(", ")
+        ),
+        CqlType::Vector(element, dimension) => {
+            format!("[{}]", values(element, u64::from(*dimension))?)
+        }
+        CqlType::Frozen(inner) => value(inner, schema, keyspace)?,
+        CqlType::UserDefined { .. } => {
+            let fields = schema
+                .resolve_user_type(data_type, keyspace)
+                .map(|user_type| {
+                    user_type
+                        .fields
+                        .iter()
+                        .filter_map(|field| {
+                            let field_value = value(&field.data_type, schema, keyspace)?;
+                            Some(format!("{}: {field_value}", quote_identifier(&field.name)))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            format!("{{{}}}", fields.join(", "))
+        }
+        CqlType::Custom(_) => return None,
+    })
+}
@@ -0,0 +1,934 @@
+//! A conservative, token-based formatter.
+//!
+//! Statements keep the line breaks they were written with, and only whitespace between tokens
+//! is rewritten, so that comments stay where they were and formatting twice changes nothing:
+//! - lines are indented by statement continuation and bracket nesting
+//! - spaces are normalized: none inside brackets or around `.`, one after `,` and around `=`,
+//!   and runs of whitespace collapse to a single space
+//! - blank lines within a statement collapse to a single one, keeping the groups they make
+//! - the lines of a block comment move along with its first
+//! - the column definitions of `CREATE TABLE` and `CREATE TYPE` go one per line, with the
+//!   closing parenthesis on its own line, and their types aligned when the style says so
+//! - the lists of `IN` and the tuples of `VALUES` wider than the line width of the style wrap,
+//!   filling their lines with items aligned after the opening parenthesis
+
+use tower_lsp::lsp_types::{FormattingOptions, Position, Range, TextEdit};
+
+use crate::{
+    cql_type::TYPE_CONSTRUCTORS,
+    document::{PositionEncodingKind, TextDocument},
+    lexer::{self, Token, TokenKind},
+    statement::{self, Dialect, Statement, StatementKind, matching_bracket},
+};
+
+/// The formatting settings besides those the client sends with each request
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Style {
+    /// Whether the types of the column definitions of `CREATE TABLE` and `CREATE TYPE` are
+    /// aligned, by padding the names before them
+    pub align_columns: bool,
+    /// The width past which the lists of `IN` and the tuples of `VALUES` wrap, if any
+    pub line_width: Option<usize>,
+}
+
+fn is_punctuation(token: &Token, punctuation: &[&str]) -> bool {
+    token.kind == TokenKind::Punctuation && punctuation.contains(&token.text)
+}
+
+fn is_line_comment(token: &Token) -> bool {
+    token.kind == TokenKind::Comment && !token.text.starts_with("/*")
+}
+
+/// Whether a space goes between `prev` and `next` on the same line, given whether the source
+/// had whitespace between them
+fn spaced(prev: &Token, next: &Token, had_space: bool) -> bool {
+    if next.kind == TokenKind::Comment {
+        return true;
+    }
+
+    if is_punctuation(next, &[",", ";", ")", "]"]) || is_punctuation(prev, &["(", "["]) {
+        return false;
+    }
+
+    // `ks.table`, but not `1 . 5`, which would lex as a single number without the spaces
+    if (is_punctuation(next, &["."]) && prev.is_identifier())
+        || (is_punctuation(prev, &["."]) && next.is_identifier())
+    {
+        return false;
+    }
+
+    if is_punctuation(prev, &[",", "="]) || is_punctuation(next, &["="]) {
+        return true;
+    }
+
+    had_space
+}
+
+/// Returns the indices of the tokens opening and closing the column definitions of a `CREATE
+/// TABLE` or `CREATE TYPE` statement, if complete
+fn definitions_body(tokens: &[Token], kind: StatementKind) -> Option<(usize, usize)> {
+    if !matches!(kind, StatementKind::CreateTable | StatementKind::CreateType) {
+        return None;
+    }
+
+    let open = tokens.iter().position(|token| token.is_punctuation("("))?;
+
+    // Comments don't count as brackets, so matching over them is fine
+    Some((open, matching_bracket(tokens, open)?))
+}
+
+/// The indices of the column names among the definitions between `open` and `close`, the
+/// first token of each but `PRIMARY KEY`
+fn column_names(tokens: &[Token], open: usize, close: usize) -> Vec<usize> {
+    statement::split_comma_ranges(&tokens[open + 1..close])
+        .into_iter()
+        .filter_map(|range| {
+            let first = range
+                .clone()
+                .map(|i| open + 1 + i)
+                .find(|&i| tokens[i].kind != TokenKind::Comment)?;
+
+            (!tokens[first].is_keyword("PRIMARY")).then_some(first)
+        })
+        .collect()
+}
+
+/// The column of the end of `text`, in characters
+fn column(text: &str) -> usize {
+    text.rsplit('\n').next().unwrap_or_default().chars().count()
+}
+
+/// The column of the end of the statement being formatted as `formatted`, whose first line
+/// starts at `first_column`
+fn line_column(formatted: &str, first_column: usize) -> usize {
+    match formatted.contains('\n') {
+        true => column(formatted),
+        false => first_column + column(formatted),
+    }
+}
+
+/// The width of `tokens` formatted on a single line. Block comments on several lines count
+/// their first, as the others move along with it, so that the width is the same once they moved.
+fn flat_width(tokens: &[Token]) -> usize {
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(i, token)| {
+            let space = i
+                .checked_sub(1)
+                .is_some_and(|prev| spaced(&tokens[prev], token, true));
+            let first_line = token.text.split('\n').next().unwrap_or_default();
+
+            usize::from(space) + first_line.chars().count()
+        })
+        .sum()
+}
+
+/// The index of the token ending the item of a list starting at `start`: the `,` after it, or
+/// the bracket closing the list
+fn item_end(tokens: &[Token], start: usize) -> usize {
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        if is_punctuation(token, &["(", "[", "{"]) {
+            depth += 1;
+        } else if is_punctuation(token, &[")", "]", "}"]) {
+            if depth == 0 {
+                return i;
+            }
+
+            depth -= 1;
+        } else if token.is_punctuation(",") && depth == 0 {
+            return i;
+        }
+    }
+
+    tokens.len() - 1
+}
+
+/// `end`, or the `;` right after it, which stays on its line and so takes its width too
+fn with_semicolon(tokens: &[Token], end: usize) -> usize {
+    match tokens.get(end + 1) {
+        Some(next) if next.is_punctuation(";") => end + 1,
+        _ => end,
+    }
+}
+
+/// A list of `IN` or tuple of `VALUES` being wrapped
+struct Wrap {
+    /// The number of brackets open inside the list
+    depth: usize,
+    /// What its wrapped lines start with, aligning them after the opening parenthesis
+    indent: String,
+}
+
+/// Shifts the lines of the block comment `comment` after its first as its first moved, from
+/// column `from` to `to`, so that they keep their alignment with it
+fn shift_comment(comment: &str, from: usize, to: usize) -> String {
+    let mut lines = comment.split('\n');
+    let mut shifted = lines.next().unwrap_or_default().to_string();
+
+    for line in lines {
+        shifted.push('\n');
+
+        if to >= from {
+            shifted.push_str(&" ".repeat(to - from));
+            shifted.push_str(line);
+        } else {
+            // Only the blanks are removed, however few there are
+            let blanks = line
+                .chars()
+                .take(from - to)
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .count();
+
+            shifted.push_str(&line[blanks..]);
+        }
+    }
+
+    shifted
+}
+
+/// Formats the tokens of a statement (comments included) read from `text`, starting at column
+/// `first_column` of its line
+fn format_tokens(
+    text: &str,
+    first_column: usize,
+    tokens: &[Token],
+    kind: StatementKind,
+    options: &FormattingOptions,
+    style: &Style,
+) -> String {
+    let indent = match options.insert_spaces {
+        true => " ".repeat(options.tab_size as usize),
+        false => "\t".to_string(),
+    };
+
+    let body = definitions_body(tokens, kind);
+
+    // The types of columns start past the longest name
+    let aligned = match body {
+        Some((open, close)) if style.align_columns => column_names(tokens, open, close),
+        _ => vec![],
+    };
+    let name_width = aligned
+        .iter()
+        .map(|&i| tokens[i].text.chars().count())
+        .max()
+        .unwrap_or_default();
+
+    let mut formatted = String::new();
+    // The indentation level of the line each open bracket is on
+    let mut brackets: Vec<usize> = vec![];
+    // How deep in type parameters, e.g. `map<text, int>`, whose commas don't separate columns
+    let mut type_parameters = 0;
+    let mut line_level = 0;
+    let mut pending_break = false;
+    let mut wraps: Vec<Wrap> = vec![];
+
+    for (i, token) in tokens.iter().enumerate() {
+        if let Some(prev) = i.checked_sub(1).map(|prev| &tokens[prev]) {
+            let between = &text[prev.end..token.start];
+            let wrap = wraps.last().filter(|wrap| wrap.depth == brackets.len());
+
+            // The lines of a wrapped list are filled anew, but comments keep their own lines
+            let had_newline = between.contains('\n')
+                && (wrap.is_none() || token.kind == TokenKind::Comment || is_line_comment(prev));
+
+            // A comment trailing a line stays on it, even when a break is due after it
+            let trailing_comment = token.kind == TokenKind::Comment && !had_newline;
+
+            let forced = match body {
+                Some((open, close)) => {
+                    i - 1 == open
+                        || i == close
+                        || (i - 1 > open
+                            && i - 1 < close
+                            && prev.is_punctuation(",")
+                            && brackets.len() == 1
+                            && type_parameters == 0)
+                }
+                None => false,
+            };
+
+            pending_break |= forced || is_line_comment(prev);
+
+            // The `;` stays at the end of the statement, unless it's commented out that way
+            let newline = (pending_break || had_newline)
+                && !trailing_comment
+                && (!token.is_punctuation(";") || is_line_comment(prev));
+
+            // An item wraps when it would end past the line width
+            let wraps_here = wrap.is_some() && prev.is_punctuation(",") && !trailing_comment && {
+                let width = flat_width(&tokens[i..=with_semicolon(tokens, item_end(tokens, i))]);
+
+                style.line_width.is_some_and(|line_width| {
+                    line_column(&formatted, first_column) + 1 + width > line_width
+                })
+            };
+
+            if let Some(wrap) = wrap.filter(|_| newline || wraps_here) {
+                pending_break = false;
+
+                formatted.push('\n');
+                formatted.push_str(&wrap.indent);
+            } else if newline {
+                pending_break = false;
+
+                line_level = match brackets.last() {
+                    Some(&level) if is_punctuation(token, &[")", "]", "}"]) => level,
+                    Some(&level) => level + 1,
+                    None => 1,
+                };
+
+                // A blank line groups the lines of a statement, as a run of them does
+                if between.matches('\n').count() > 1 {
+                    formatted.push('\n');
+                }
+
+                formatted.push('\n');
+                formatted.push_str(&indent.repeat(line_level));
+            } else if aligned.contains(&(i - 1)) && token.kind != TokenKind::Comment {
+                let padding = name_width - prev.text.chars().count() + 1;
+                formatted.push_str(&" ".repeat(padding));
+            } else if spaced(prev, token, !between.is_empty()) {
+                formatted.push(' ');
+            }
+        }
+
+        match token.kind == TokenKind::Comment && token.text.contains('\n') {
+            true => {
+                let from = column(&text[..token.start]);
+                let to = line_column(&formatted, first_column);
+
+                formatted.push_str(&shift_comment(token.text, from, to));
+            }
+            false => formatted.push_str(token.text),
+        }
+
+        if is_punctuation(token, &["(", "[", "{"]) {
+            brackets.push(line_level);
+
+            // Lists too wide for the line wrap, aligned after their parenthesis
+            if let Some(line_width) = style.line_width
+                && token.is_punctuation("(")
+                && i.checked_sub(1).is_some_and(|prev| {
+                    tokens[prev].is_keyword("IN") || tokens[prev].is_keyword("VALUES")
+                })
+                && let Some(close) = matching_bracket(tokens, i)
+            {
+                let column = line_column(&formatted, first_column);
+
+                if column + flat_width(&tokens[i + 1..=with_semicolon(tokens, close)]) > line_width
+                {
+                    let line = formatted.rsplit('\n').next().unwrap_or_default();
+                    let leading = match formatted.contains('\n') {
+                        true => &line[..line.len() - line.trim_start().len()],
+                        false => "",
+                    };
+
+                    wraps.push(Wrap {
+                        depth: brackets.len(),
+                        indent: format!(
+                            "{leading}{}",
+                            " ".repeat(column - leading.chars().count())
+                        ),
+                    });
+                }
+            }
+        } else if is_punctuation(token, &[")", "]", "}"]) {
+            brackets.pop();
+
+            if wraps.last().is_some_and(|wrap| brackets.len() < wrap.depth) {
+                wraps.pop();
+            }
+        } else if token.is_punctuation("<")
+            && i.checked_sub(1)
+                .is_some_and(|prev| TYPE_CONSTRUCTORS.iter().any(|t| tokens[prev].is_keyword(t)))
+        {
+            type_parameters += 1;
+        } else if token.is_punctuation(">") && type_parameters > 0 {
+            type_parameters -= 1;
+        }
+    }
+
+    formatted
+}
+
+/// The column of `start` in `text`, or for a statement starting on the line another ended on,
+/// after that one: `previous` is where it ended, and the column it ends at once formatted
+fn start_column(text: &str, start: usize, previous: Option<(usize, usize)>) -> usize {
+    match previous {
+        Some((end, column)) if !text[end..start].contains('\n') => {
+            column + text[end..start].chars().count()
+        }
+        _ => column(&text[..start]),
+    }
+}
+
+/// Returns the edit formatting `span` of the document, which holds a statement of `kind` or
+/// its beginning, or `None` when it's already formatted, along with the column it ends at once
+/// formatted. `previous` is where the statement before it ended, and the column it ends at once
+/// formatted, if it's formatted too.
+fn format_span(
+    doc: &TextDocument,
+    span: std::ops::Range<usize>,
+    previous: Option<(usize, usize)>,
+    kind: StatementKind,
+    options: &FormattingOptions,
+    style: &Style,
+    position_encoding: PositionEncodingKind,
+) -> (Option<TextEdit>, usize) {
+    let text = doc.text();
+    let tokens = lexer::tokenize_with_comments(&text[span.clone()])
+        .into_iter()
+        .map(|token| Token {
+            start: token.start + span.start,
+            end: token.end + span.start,
+            ..token
+        })
+        .collect::<Vec<_>>();
+
+    // Statements start at the beginning of their line
+    let line_start = text[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let start = match text[line_start..span.start].trim().is_empty() {
+        true => line_start,
+        false => span.start,
+    };
+
+    let first_column = start_column(text, start, previous);
+    let formatted = format_tokens(text, first_column, &tokens, kind, options, style);
+    let end_column = line_column(&formatted, first_column);
+
+    let edit = (formatted != text[start..span.end]).then(|| TextEdit {
+        range: doc.byte_range_to_range(start..span.end, position_encoding),
+        new_text: formatted,
+    });
+
+    (edit, end_column)
+}
+
+/// Formats the statement just completed by typing `ch` at `position`: a `;` terminating a
+/// statement, or a `)` closing the column definitions of `CREATE TABLE` or `CREATE TYPE`
+pub fn on_type_formatting(
+    doc: &TextDocument,
+    position: &Position,
+    ch: &str,
+    options: &FormattingOptions,
+    style: &Style,
+    dialect: Dialect,
+    position_encoding: PositionEncodingKind,
+) -> Vec<TextEdit> {
+    let Some(typed) = doc
+        .position_to_byte_idx(position, position_encoding)
+        .ok()
+        .and_then(|offset| offset.checked_sub(ch.len()))
+    else {
+        return vec![];
+    };
+
+    let text = doc.text();
+    let tokens = lexer::tokenize(text);
+    let statements = statement::split(&tokens, dialect);
+
+    let Some(statement) = statement::statement_at(&statements, typed).map(|i| &statements[i])
+    else {
+        return vec![];
+    };
+
+    let span = match ch {
+        ";" if statement.terminated && statement.end == typed + 1 => statement.start..statement.end,
+        // Only the statement up to the body is formatted, as anything after it is likely
+        // still being typed, or other statements merged into an unterminated one
+        ")" => match definitions_body(statement.tokens, statement.kind()) {
+            Some((_, close)) if statement.tokens[close].start == typed => {
+                statement.start..typed + 1
+            }
+            _ => return vec![],
+        },
+        _ => return vec![],
+    };
+
+    // The statement before it on its line, if any, stays as it is
+    format_span(
+        doc,
+        span,
+        None,
+        statement.kind(),
+        options,
+        style,
+        position_encoding,
+    )
+    .0
+    .into_iter()
+    .collect()
+}
+
+/// Formats the statements of the document for which `selected` holds
+fn format_statements(
+    doc: &TextDocument,
+    options: &FormattingOptions,
+    style: &Style,
+    dialect: Dialect,
+    position_encoding: PositionEncodingKind,
+    selected: impl Fn(&Statement) -> bool,
+) -> Vec<TextEdit> {
+    let text = doc.text();
+    let tokens = lexer::tokenize(text);
+
+    // Where the statement before ended, and the column it ends at once formatted, which the
+    // next one starts after when it's on the same line, so that formatting again keeps its
+    // lists wrapped or not
+    let mut previous = None;
+
+    statement::split(&tokens, dialect)
+        .into_iter()
+        .filter_map(|statement| {
+            let (edit, end_column) = match selected(&statement) {
+                true => format_span(
+                    doc,
+                    statement.start..statement.end,
+                    previous,
+                    statement.kind(),
+                    options,
+                    style,
+                    position_encoding,
+                ),
+                false => {
+                    let first_column = start_column(text, statement.start, previous);
+                    let statement_text = &text[statement.start..statement.end];
+
+                    (None, line_column(statement_text, first_column))
+                }
+            };
+
+            previous = Some((statement.end, end_column));
+            edit
+        })
+        .collect()
+}
+
+/// Formats every statement of the document
+pub fn document_formatting(
+    doc: &TextDocument,
+    options: &FormattingOptions,
+    style: &Style,
+    dialect: Dialect,
+    position_encoding: PositionEncodingKind,
+) -> Vec<TextEdit> {
+    format_statements(doc, options, style, dialect, position_encoding, |_| true)
+}
+
+/// Formats every statement overlapping `range`
+pub fn range_formatting(
+    doc: &TextDocument,
+    range: &Range,
+    options: &FormattingOptions,
+    style: &Style,
+    dialect: Dialect,
+    position_encoding: PositionEncodingKind,
+) -> Vec<TextEdit> {
+    let (Ok(start), Ok(end)) = (
+        doc.position_to_byte_idx(&range.start, position_encoding),
+        doc.position_to_byte_idx(&range.end, position_encoding),
+    ) else {
+        return vec![];
+    };
+
+    // A selection ending at the start of a line doesn't select that line's statement
+    format_statements(
+        doc,
+        options,
+        style,
+        dialect,
+        position_encoding,
+        |statement| statement.start < end.max(start + 1) && start < statement.end,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    //! Properties of the formatter checked on documents generated from a fixed seed: the
+    //! statements it's meant for, written with random whitespace, line breaks and comments
+    //! between their tokens
+
+    use super::*;
+
+    const CASES: usize = 500;
+
+    /// A xorshift generator, so that failures reproduce without a dependency
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+
+            (self.0 % n as u64) as usize
+        }
+
+        fn chance(&mut self, percent: usize) -> bool {
+            self.below(100) < percent
+        }
+
+        fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+            items[self.below(items.len())]
+        }
+    }
+
+    const NAMES: &[&str] = &["id", "name", "created_at", "v", "bucket", "last_login_time"];
+
+    const TYPES: &[&[&str]] = &[
+        &["int"],
+        &["text"],
+        &["timestamp"],
+        &["map", "<", "text", ",", "int", ">"],
+        &["frozen", "<", "list", "<", "int", ">", ">"],
+        &["set", "<", "uuid", ">"],
+    ];
+
+    const VALUES: &[&str] = &["1", "42", "'two'", "null", "3.5", "'x'", "0xCAFE", "?"];
+
+    const COMMENTS: &[&str] = &[
+        "-- a line comment\n",
+        "// another one\n",
+        "/* inline */",
+        "/* a block\n   comment on\n     several lines */",
+        "\n  /*\n   * starred\n   */\n",
+    ];
+
+    /// The tokens of a random `CREATE TABLE` or `CREATE TYPE`, with its column names
+    fn create(rng: &mut Rng) -> Vec<&'static str> {
+        let mut tokens = match rng.chance(70) {
+            true => vec!["CREATE", "TABLE", "ks", ".", "t", "("],
+            false => vec!["CREATE", "TYPE", "ks", ".", "address", "("],
+        };
+
+        let columns = 1 + rng.below(NAMES.len());
+
+        for (i, name) in NAMES[..columns].iter().enumerate() {
+            if i > 0 {
+                tokens.push(",");
+            }
+
+            tokens.push(name);
+            tokens.extend(TYPES[rng.below(TYPES.len())]);
+        }
+
+        if tokens[1] == "TABLE" && rng.chance(50) {
+            tokens.extend([",", "PRIMARY", "KEY", "(", "(", "id", ")", ")"]);
+        }
+
+        tokens.push(")");
+
+        if rng.chance(30) {
+            tokens.extend(["WITH", "comment", "=", "'users'"]);
+        }
+
+        tokens
+    }
+
+    /// The items of a list, `(1, 'two', ...)`, at least `min` of them
+    fn list(rng: &mut Rng, min: usize, tokens: &mut Vec<&'static str>) {
+        tokens.push("(");
+
+        for i in 0..min + rng.below(15) {
+            if i > 0 {
+                tokens.push(",");
+            }
+
+            tokens.push(rng.pick(VALUES));
+        }
+
+        tokens.push(")");
+    }
+
+    /// The tokens of a random statement
+    fn statement(rng: &mut Rng) -> Vec<&'static str> {
+        let mut tokens = match rng.below(4) {
+            0 => create(rng),
+            1 => {
+                let mut tokens = vec!["INSERT", "INTO", "ks", ".", "t", "(", "id", ",", "v", ")"];
+                tokens.push("VALUES");
+                list(rng, 1, &mut tokens);
+                tokens
+            }
+            2 => {
+                let mut tokens = vec!["SELECT", "*", "FROM", "t", "WHERE", "id", "IN"];
+                list(rng, 1, &mut tokens);
+                tokens
+            }
+            _ => vec![
+                "UPDATE", "ks", ".", "t", "SET", "v", "=", "1", ",", "name", "=", "'x'", "WHERE",
+                "id", "=", "3",
+            ],
+        };
+
+        tokens.push(";");
+        tokens
+    }
+
+    /// Writes `tokens` with random blanks, line breaks and, if `comments`, comments between them
+    fn write(rng: &mut Rng, tokens: &[&str], comments: bool, text: &mut String) {
+        for (i, token) in tokens.iter().enumerate() {
+            if i > 0 {
+                let punctuation = |token: &str| !token.starts_with(|c: char| c.is_alphanumeric());
+
+                let blanks = match rng.below(10) {
+                    0 if punctuation(tokens[i - 1]) || punctuation(token) => "",
+                    0..=4 => " ",
+                    5 => "   ",
+                    6 => "\t",
+                    7 => "\n  ",
+                    8 => "\n\n      ",
+                    _ => "\n\n\n",
+                };
+
+                text.push_str(blanks);
+
+                if comments && rng.chance(15) {
+                    text.push_str(&format!(" {} ", rng.pick(COMMENTS)));
+                }
+            }
+
+            text.push_str(token);
+        }
+    }
+
+    /// A random document of a few statements, with comments if `comments`
+    fn document(rng: &mut Rng, comments: bool) -> String {
+        let mut text = String::new();
+
+        for _ in 0..1 + rng.below(4) {
+            let tokens = statement(rng);
+            write(rng, &tokens, comments, &mut text);
+            text.push_str(rng.pick(&["\n", "\n\n", " "]));
+        }
+
+        text
+    }
+
+    fn options(rng: &mut Rng) -> FormattingOptions {
+        FormattingOptions {
+            tab_size: rng.pick(&["2", "4"]).parse().unwrap(),
+            insert_spaces: rng.chance(80),
+            ..Default::default()
+        }
+    }
+
+    fn style(rng: &mut Rng) -> Style {
+        Style {
+            align_columns: rng.chance(50),
+            line_width: rng.chance(50).then_some(40),
+        }
+    }
+
+    /// `text` with the edits formatting the whole document applied
+    fn format(text: &str, options: &FormattingOptions, style: &Style) -> String {
+        let doc = TextDocument::new(text);
+        let encoding = PositionEncodingKind::UTF8;
+        let mut formatted = text.to_string();
+
+        // From the end, so that the offsets of the edits before stay right
+        for edit in document_formatting(&doc, options, style, Dialect::Cql, encoding)
+            .iter()
+            .rev()
+        {
+            let start = doc
+                .position_to_byte_idx(&edit.range.start, encoding)
+                .unwrap();
+            let end = doc.position_to_byte_idx(&edit.range.end, encoding).unwrap();
+
+            formatted.replace_range(start..end, &edit.new_text);
+        }
+
+        formatted
+    }
+
+    /// The tokens of `text`, the lines of block comments without their indentation, which
+    /// moves along with the comment
+    fn tokens(text: &str) -> Vec<String> {
+        lexer::tokenize_with_comments(text)
+            .iter()
+            .map(|token| {
+                token
+                    .text
+                    .split('\n')
+                    .map(str::trim_start)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect()
+    }
+
+    /// Each token of `text` after the first, with the whitespace between it and the one before
+    fn gaps(text: &str) -> Vec<(&str, &str)> {
+        let tokens = lexer::tokenize_with_comments(text);
+
+        tokens
+            .windows(2)
+            .map(|pair| (&text[pair[0].end..pair[1].start], pair[1].text))
+            .collect()
+    }
+
+    fn is_blank(gap: &str) -> bool {
+        gap.matches('\n').count() > 1
+    }
+
+    #[test]
+    fn formatting_twice_changes_nothing() {
+        let mut rng = Rng(0x5eed);
+
+        for _ in 0..CASES {
+            let text = document(&mut rng, true);
+            let (options, style) = (options(&mut rng), style(&mut rng));
+            let formatted = format(&text, &options, &style);
+
+            assert_eq!(
+                format(&formatted, &options, &style),
+                formatted,
+                "formatting again changed\n{formatted}\nformatted from\n{text}\nwith {style:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn tokens_and_comments_are_kept() {
+        let mut rng = Rng(0xc0ffee);
+
+        for _ in 0..CASES {
+            let text = document(&mut rng, true);
+            let style = style(&mut rng);
+            let formatted = format(&text, &options(&mut rng), &style);
+
+            assert_eq!(
+                tokens(&formatted),
+                tokens(&text),
+                "tokens changed in\n{formatted}\nformatted from\n{text}\nwith {style:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn blank_lines_keep_their_groups() {
+        let mut rng = Rng(0xb1a2c);
+
+        for _ in 0..CASES {
+            let text = document(&mut rng, true);
+            let style = style(&mut rng);
+            let formatted = format(&text, &options(&mut rng), &style);
+
+            for ((before, token), (after, _)) in gaps(&text).into_iter().zip(gaps(&formatted)) {
+                // The `;` joins the end of its statement, whatever was between them
+                if token == ";" {
+                    continue;
+                }
+
+                // Only the lines of wrapped lists are filled anew, without their blank lines
+                if style.line_width.is_none() {
+                    assert!(
+                        !is_blank(before) || is_blank(after),
+                        "blank line lost in\n{formatted}\nformatted from\n{text}"
+                    );
+                }
+
+                assert!(
+                    !is_blank(after) || is_blank(before),
+                    "blank line added in\n{formatted}\nformatted from\n{text}"
+                );
+
+                // Runs of blank lines collapse, but for those between statements, left as is
+                assert!(
+                    after.matches('\n').count() <= 2 || after == before,
+                    "blank lines not collapsed in\n{formatted}\nformatted from\n{text}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn column_types_are_aligned() {
+        let mut rng = Rng(0xa119);
+        let style = Style {
+            align_columns: true,
+            line_width: None,
+        };
+
+        for _ in 0..CASES {
+            let tokens = create(&mut rng);
+            let mut text = String::new();
+            write(&mut rng, &tokens, true, &mut text);
+
+            let formatted = format(&text, &options(&mut rng), &style);
+
+            // Where the type starts on the lines starting with a column name
+            let columns = formatted
+                .lines()
+                .filter_map(|line| {
+                    let definition = line.trim_start();
+                    let name = NAMES.iter().find(|name| {
+                        definition
+                            .strip_prefix(**name)
+                            .is_some_and(|rest| rest.starts_with(' '))
+                    })?;
+                    let rest = &definition[name.len()..];
+                    let data_type = rest.trim_start();
+
+                    TYPES
+                        .iter()
+                        .any(|data_type_tokens| data_type.starts_with(data_type_tokens[0]))
+                        .then(|| line.len() - data_type.len())
+                })
+                .collect::<Vec<_>>();
+
+            assert!(
+                columns.windows(2).all(|pair| pair[0] == pair[1]),
+                "types not aligned in\n{formatted}\nformatted from\n{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn wide_lists_wrap_after_their_parenthesis() {
+        let mut rng = Rng(0x1157);
+        let line_width = 40;
+        let style = Style {
+            align_columns: false,
+            line_width: Some(line_width),
+        };
+
+        for _ in 0..CASES {
+            // Wider than the line wherever it starts, its items taking 3 characters at least
+            let mut tokens = vec!["SELECT", "*", "FROM", "t", "WHERE", "id", "IN"];
+            list(&mut rng, line_width / 2, &mut tokens);
+            tokens.push(";");
+
+            let mut text = String::new();
+            write(&mut rng, &tokens, false, &mut text);
+
+            let formatted = format(&text, &options(&mut rng), &style);
+            let lines = formatted.lines().collect::<Vec<_>>();
+            let open = lines.iter().position(|line| line.contains('(')).unwrap();
+            let indent = lines[open][..lines[open].find('(').unwrap() + 1]
+                .chars()
+                .count();
+
+            for line in &lines {
+                assert!(
+                    line.chars().count() <= line_width,
+                    "line wider than {line_width} in\n{formatted}\nformatted from\n{text}"
+                );
+            }
+
+            for line in &lines[open + 1..] {
+                let blanks = line.chars().take_while(|c| c.is_whitespace()).count();
+
+                assert_eq!(
+                    blanks, indent,
+                    "item not aligned after the parenthesis in\n{formatted}\nformatted from\n{text}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! The types that must be frozen where they're used: collections and user-defined types in the
+//! primary key or nested in a collection, user-defined types nested in another, and any
+//! user-defined type column for targets without non-frozen ones. Each comes with a quick fix
+//! wrapping the type in `frozen<>`.
+
+use std::ops::Range;
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    cql_type::CqlType,
+    data_model::COLLECTION_KEY,
+    diagnostics::{Lint, Problem},
+    features::NON_FROZEN_UDT,
+    lexer::Token,
+    schema,
+    statement::{Statement, StatementKind, split_comma_ranges},
+    target::Target,
+    type_definition,
+};
+
+const UNFROZEN_UDT_KEY: Lint = Lint {
+    code: "unfrozen-udt-key",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/types.html#udts",
+};
+
+const UNFROZEN_NESTED: Lint = Lint {
+    code: "unfrozen-nested",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/types.html#collections",
+};
+
+const UNFROZEN_UDT: Lint = Lint {
+    code: "unfrozen-udt",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/types.html#udts",
+};
+
+/// Where a type is used, which decides whether it must be frozen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Position {
+    Column,
+    Key,
+    /// A field of a user-defined type
+    Field,
+    ListElement,
+    SetElement,
+    MapKey,
+    MapValue,
+    /// Within `frozen<>`, a tuple or a vector, which freeze what they hold
+    Frozen,
+}
+
+impl Position {
+    fn describe(self) -> &'static str {
+        match self {
+            Position::ListElement => "a list element",
+            Position::SetElement => "a set element",
+            Position::MapKey => "a map key",
+            Position::MapValue => "a map value",
+            _ => "nested",
+        }
+    }
+}
+
+/// A type used unfrozen where it must be frozen
+struct Requirement {
+    span: Range<usize>,
+    message: String,
+    lint: Lint,
+}
+
+/// Why `data_type`, used at `position` in the definition of `name`, must be frozen
+fn requirement(
+    data_type: &CqlType,
+    position: Position,
+    name: &str,
+    target: Option<Target>,
+) -> Option<(String, Lint)> {
+    let is_user_type = matches!(data_type, CqlType::UserDefined { .. });
+    let is_collection = matches!(
+        data_type,
+        CqlType::List(_) | CqlType::Set(_) | CqlType::Map(..)
+    );
+
+    if !is_user_type && !is_collection {
+        return None;
+    }
+
+    let kind = match is_user_type {
+        true => "user-defined types",
+        false => "collections",
+    };
+
+    Some(match position {
+        Position::Key => (
+            format!(
+                "Key column `{name}`: `{data_type}` isn't frozen, which {kind} in the primary key must be"
+            ),
+            match is_user_type {
+                true => UNFROZEN_UDT_KEY,
+                false => COLLECTION_KEY,
+            },
+        ),
+        Position::ListElement | Position::SetElement | Position::MapKey | Position::MapValue => (
+            format!(
+                "`{data_type}` is {} of `{name}` and isn't frozen, which {kind} nested in a collection must be",
+                position.describe()
+            ),
+            UNFROZEN_NESTED,
+        ),
+        Position::Field if is_user_type => (
+            format!(
+                "`{data_type}` is the type of field `{name}` and isn't frozen, which user-defined types nested in another must be"
+            ),
+            UNFROZEN_NESTED,
+        ),
+        Position::Column if is_user_type => {
+            let target = target.filter(|&target| !NON_FROZEN_UDT.is_supported(target))?;
+
+            (
+                format!("Column `{name}`: {}", NON_FROZEN_UDT.message(target)),
+                UNFROZEN_UDT,
+            )
+        }
+        _ => return None,
+    })
+}
+
+/// Walks the type starting at `tokens[start]`, used at `position`, and the types it's made of,
+/// collecting those that must be frozen. Returns the index past the type.
+fn walk(
+    tokens: &[Token],
+    start: usize,
+    position: Position,
+    name: &str,
+    target: Option<Target>,
+    requirements: &mut Vec<Requirement>,
+) -> Option<usize> {
+    let mut end = start;
+    let data_type = CqlType::parse(tokens, &mut end)?;
+
+    // The position of each type parameter, the last one repeating for tuples
+    let parameters: &[Position] = match &data_type {
+        CqlType::List(_) => &[Position::ListElement],
+        CqlType::Set(_) => &[Position::SetElement],
+        CqlType::Map(..) => &[Position::MapKey, Position::MapValue],
+        CqlType::Frozen(_) | CqlType::Tuple(_) | CqlType::Vector(..) => &[Position::Frozen],
+        _ => &[],
+    };
+
+    if !parameters.is_empty() && tokens.get(start + 1).is_some_and(|t| t.is_punctuation("<")) {
+        let mut i = start + 2;
+
+        for k in 0.. {
+            let position = parameters[k.min(parameters.len() - 1)];
+
+            // The dimension of a vector isn't a type, and ends the walk
+            let Some(next) = walk(tokens, i, position, name, target, requirements) else {
+                break;
+            };
+
+            match tokens.get(next) {
+                Some(comma) if comma.is_punctuation(",") => i = next + 1,
+                _ => break,
+            }
+        }
+    }
+
+    if position != Position::Frozen
+        && let Some((message, lint)) = requirement(&data_type, position, name, target)
+    {
+        requirements.push(Requirement {
+            span: tokens[start].start..tokens[end - 1].end,
+            message,
+            lint,
+        });
+    }
+
+    Some(end)
+}
+
+/// The types of the column or field definitions of `statement` that must be frozen
+fn requirements(statement: &Statement, target: Option<Target>) -> Vec<Requirement> {
+    let Some(range) = type_definition::definitions(statement) else {
+        return vec![];
+    };
+
+    let key = match statement.kind() {
+        StatementKind::CreateTable => schema::parse_create_table(statement, &None)
+            .map(|table| [table.partition_key, table.clustering_key].concat())
+            .unwrap_or_default(),
+        _ => vec![],
+    };
+
+    let is_type = matches!(
+        statement.kind(),
+        StatementKind::CreateType | StatementKind::AlterType
+    );
+
+    let tokens = &statement.tokens[range];
+    let mut requirements = vec![];
+
+    for part in split_comma_ranges(tokens) {
+        let definition = &tokens[part];
+
+        if matches!(definition, [primary, key, ..] if primary.is_keyword("PRIMARY") && key.is_keyword("KEY"))
+        {
+            continue;
+        }
+
+        let Some(name) = definition.first().and_then(Token::identifier) else {
+            continue;
+        };
+
+        let position = match is_type {
+            true => Position::Field,
+            false if key.contains(&name) => Position::Key,
+            false => Position::Column,
+        };
+
+        walk(definition, 1, position, &name, target, &mut requirements);
+    }
+
+    requirements
+}
+
+/// Checks that the types `statement` defines columns or fields with are frozen where they must
+/// be, `target` deciding whether user-defined type columns must be
+pub fn check(statement: &Statement, target: Option<Target>) -> Vec<Problem> {
+    requirements(statement, target)
+        .into_iter()
+        .map(|requirement| Problem {
+            span: requirement.span,
+            severity: DiagnosticSeverity::WARNING,
+            message: requirement.message,
+            lint: Some(requirement.lint),
+        })
+        .collect()
+}
+
+/// The spans of the types of `statement` to wrap in `frozen<>`
+pub fn fixes(statement: &Statement, target: Option<Target>) -> Vec<Range<usize>> {
+    requirements(statement, target)
+        .into_iter()
+        .map(|requirement| requirement.span)
+        .collect()
+}
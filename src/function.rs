@@ -0,0 +1,230 @@
+//! `CREATE FUNCTION` and `CREATE AGGREGATE` parsing, and the checks Cassandra applies to the
+//! functions an aggregate is built from.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    cql_type::CqlType,
+    diagnostics::Problem,
+    lexer::Token,
+    schema::{Function, Keyspace, Schema},
+    statement::{
+        QualifiedName, Statement, StatementKind, matching_bracket, parse_qualified_name,
+        skip_if_exists, split_commas,
+    },
+};
+
+/// Parses the parenthesized list starting at `tokens[open]`, returning its comma-separated
+/// parts
+fn parenthesized<'a>(tokens: &'a [Token<'a>], open: usize) -> Option<Vec<&'a [Token<'a>]>> {
+    if !tokens.get(open)?.is_punctuation("(") {
+        return None;
+    }
+
+    let close = matching_bracket(tokens, open)?;
+
+    Some(split_commas(&tokens[open + 1..close]))
+}
+
+/// Parses the name of a `CREATE [OR REPLACE] FUNCTION|AGGREGATE [IF NOT EXISTS] name`
+/// statement, returning it with the index of the token following it
+fn parse_name(statement: &Statement, object: &str) -> Option<(QualifiedName, usize)> {
+    let mut i = skip_if_exists(statement.tokens, statement.find_keyword(object)? + 1);
+    let name = parse_qualified_name(statement.tokens, &mut i)?;
+
+    Some((name, i))
+}
+
+/// Parses `CREATE [OR REPLACE] FUNCTION [IF NOT EXISTS] name (arg type, ...) ... RETURNS type
+/// LANGUAGE language AS body`
+pub fn parse_function(
+    statement: &Statement,
+    current_keyspace: &Option<String>,
+) -> Option<Function> {
+    if statement.kind() != StatementKind::CreateFunction {
+        return None;
+    }
+
+    let tokens = statement.tokens;
+    let (name, open) = parse_name(statement, "FUNCTION")?;
+
+    let argument_types = parenthesized(tokens, open)?
+        .into_iter()
+        .filter(|argument| !argument.is_empty())
+        .map(|argument| CqlType::parse(argument, &mut 1))
+        .collect::<Option<Vec<_>>>()?;
+
+    // The return type follows the `RETURNS` after `ON NULL INPUT`, not the one in
+    // `RETURNS NULL ON NULL INPUT`
+    let return_type = statement
+        .find_keyword("INPUT")
+        .and_then(|input| statement.find_keyword_from("RETURNS", input))
+        .and_then(|returns| CqlType::parse(tokens, &mut (returns + 1)));
+
+    Some(Function {
+        keyspace: Schema::resolve_keyspace(name.keyspace, current_keyspace),
+        name: name.name,
+        argument_types,
+        return_type,
+    })
+}
+
+/// The parts of a `CREATE AGGREGATE` statement, as far as they've been typed
+#[derive(Debug)]
+pub struct AggregateDefinition<'a> {
+    pub name: QualifiedName,
+    pub argument_types: Vec<CqlType>,
+    pub state_function: Option<&'a Token<'a>>,
+    pub state_type: Option<CqlType>,
+    pub final_function: Option<&'a Token<'a>>,
+}
+
+/// Parses `CREATE [OR REPLACE] AGGREGATE [IF NOT EXISTS] name (type, ...) SFUNC function
+/// STYPE type [FINALFUNC function] [INITCOND value]`
+pub fn parse_aggregate<'a>(statement: &Statement<'a>) -> Option<AggregateDefinition<'a>> {
+    if statement.kind() != StatementKind::CreateAggregate {
+        return None;
+    }
+
+    let tokens = statement.tokens;
+    let (name, open) = parse_name(statement, "AGGREGATE")?;
+
+    let argument_types = parenthesized(tokens, open)?
+        .into_iter()
+        .filter(|argument| !argument.is_empty())
+        .map(|argument| CqlType::parse(argument, &mut 0))
+        .collect::<Option<Vec<_>>>()?;
+
+    let function = |keyword: &str| {
+        let i = statement.find_keyword(keyword)?;
+        tokens.get(i + 1).filter(|token| token.is_identifier())
+    };
+
+    let state_type = statement
+        .find_keyword("STYPE")
+        .and_then(|stype| CqlType::parse(tokens, &mut (stype + 1)));
+
+    Some(AggregateDefinition {
+        name,
+        argument_types,
+        state_function: function("SFUNC"),
+        state_type,
+        final_function: function("FINALFUNC"),
+    })
+}
+
+/// Whether two types are the same as far as function signatures go. Function arguments are
+/// implicitly frozen, so `frozen<list<int>>` and `list<int>` match.
+fn same_type(a: &CqlType, b: &CqlType) -> bool {
+    a.unfrozen() == b.unfrozen()
+}
+
+fn signature(types: &[CqlType]) -> String {
+    let types = types
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("({types})")
+}
+
+/// Returns the overload taking exactly `arguments`
+fn find_overload<'f>(overloads: &[&'f Function], arguments: &[CqlType]) -> Option<&'f Function> {
+    overloads.iter().copied().find(|function| {
+        function.argument_types.len() == arguments.len()
+            && function
+                .argument_types
+                .iter()
+                .zip(arguments)
+                .all(|(a, b)| same_type(a, b))
+    })
+}
+
+/// Checks that the state and final functions of an aggregate exist in `keyspace` with the
+/// signatures Cassandra requires
+pub fn check_aggregate(aggregate: &AggregateDefinition, keyspace: &Keyspace) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    let error = |token: &Token, message: String| Problem {
+        span: token.start..token.end,
+        severity: DiagnosticSeverity::ERROR,
+        message,
+        lint: None,
+    };
+
+    let Some(state_type) = &aggregate.state_type else {
+        return problems;
+    };
+
+    let overloads = |token: &Token| {
+        let name = token.identifier().unwrap_or_default();
+
+        keyspace
+            .functions
+            .iter()
+            .filter(|function| function.name == name)
+            .collect::<Vec<_>>()
+    };
+
+    if let Some(token) = aggregate.state_function {
+        // The state function takes the state followed by the aggregate arguments
+        let arguments = [state_type.clone()]
+            .into_iter()
+            .chain(aggregate.argument_types.iter().cloned())
+            .collect::<Vec<_>>();
+
+        let overloads = overloads(token);
+
+        if overloads.is_empty() {
+            problems.push(error(
+                token,
+                format!("Unknown state function `{}`", token.text),
+            ));
+        } else if let Some(function) = find_overload(&overloads, &arguments) {
+            if let Some(return_type) = &function.return_type
+                && !same_type(return_type, state_type)
+            {
+                problems.push(error(
+                    token,
+                    format!(
+                        "State function `{}` returns `{return_type}`, but the state type is `{state_type}`",
+                        token.text
+                    ),
+                ));
+            }
+        } else {
+            problems.push(error(
+                token,
+                format!(
+                    "State function `{}` must take the state type followed by the aggregate arguments {}",
+                    token.text,
+                    signature(&arguments)
+                ),
+            ));
+        }
+    }
+
+    if let Some(token) = aggregate.final_function {
+        let arguments = [state_type.clone()];
+        let overloads = overloads(token);
+
+        if overloads.is_empty() {
+            problems.push(error(
+                token,
+                format!("Unknown final function `{}`", token.text),
+            ));
+        } else if find_overload(&overloads, &arguments).is_none() {
+            problems.push(error(
+                token,
+                format!(
+                    "Final function `{}` must take the state type {}",
+                    token.text,
+                    signature(&arguments)
+                ),
+            ));
+        }
+    }
+
+    problems
+}
@@ -0,0 +1,394 @@
+//! Statement templates generated from table definitions.
+
+use std::collections::HashSet;
+
+use crate::{
+    cql_type::CqlType,
+    fixtures,
+    lexer::quote_identifier,
+    schema::{Index, IndexTarget, Keyspace, Schema, Table},
+};
+
+/// A value of `data_type` that's valid CQL, to be overwritten by the user
+fn placeholder(data_type: &CqlType) -> &'static str {
+    match data_type.unfrozen() {
+        CqlType::Native(name) => match name.as_str() {
+            "ascii" | "text" | "varchar" => "''",
+            "tinyint" | "smallint" | "int" | "bigint" | "varint" | "counter" => "0",
+            "float" | "double" | "decimal" => "0.0",
+            "boolean" => "false",
+            "uuid" => "uuid()",
+            "timeuuid" => "now()",
+            "timestamp" => "toTimestamp(now())",
+            "date" => "toDate(now())",
+            "time" => "'00:00:00'",
+            "blob" => "0x",
+            "inet" => "'127.0.0.1'",
+            "duration" => "1h",
+            _ => "null",
+        },
+        CqlType::List(_) | CqlType::Vector(..) => "[]",
+        CqlType::Set(_) | CqlType::Map(..) | CqlType::UserDefined { .. } => "{}",
+        CqlType::Tuple(_) => "()",
+        CqlType::Frozen(_) | CqlType::Custom(_) => "null",
+    }
+}
+
+/// A JSON value of `data_type` as `INSERT JSON` accepts it, to be overwritten by the user
+fn json_placeholder(data_type: &CqlType) -> &'static str {
+    match data_type.unfrozen() {
+        CqlType::Native(name) => match name.as_str() {
+            "tinyint" | "smallint" | "int" | "bigint" | "varint" | "counter" => "0",
+            "float" | "double" | "decimal" => "0.0",
+            "boolean" => "false",
+            "uuid" | "timeuuid" => "\"00000000-0000-0000-0000-000000000000\"",
+            "timestamp" => "\"1970-01-01 00:00:00+0000\"",
+            "date" => "\"1970-01-01\"",
+            "time" => "\"00:00:00\"",
+            "blob" => "\"0x\"",
+            "inet" => "\"127.0.0.1\"",
+            "duration" => "\"1h\"",
+            _ => "\"\"",
+        },
+        CqlType::List(_) | CqlType::Set(_) | CqlType::Vector(..) | CqlType::Tuple(_) => "[]",
+        CqlType::Map(..) | CqlType::UserDefined { .. } => "{}",
+        CqlType::Frozen(_) | CqlType::Custom(_) => "null",
+    }
+}
+
+/// Escapes the characters that have a meaning in LSP snippet placeholders
+pub fn escape_snippet(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('$', "\\$")
+        .replace('}', "\\}")
+}
+
+/// Returns the name of `table`, qualified with its keyspace when it has one
+pub fn qualified_name(table: &Table) -> String {
+    match table.keyspace.is_empty() {
+        true => quote_identifier(&table.name),
+        false => format!(
+            "{}.{}",
+            quote_identifier(&table.keyspace),
+            quote_identifier(&table.name)
+        ),
+    }
+}
+
+/// Formats `value` as the `n`th snippet tab stop, or as-is when not generating a snippet
+fn value(value: &str, n: usize, snippet: bool) -> String {
+    match snippet {
+        true => format!("${{{n}:{}}}", escape_snippet(value)),
+        false => value.to_string(),
+    }
+}
+
+/// Escapes generated text outside of placeholders when generating a snippet, since quoted
+/// identifiers may contain snippet syntax too
+fn text(text: String, snippet: bool) -> String {
+    match snippet {
+        true => escape_snippet(&text),
+        false => text,
+    }
+}
+
+/// Builds an `INSERT` of every column of `table`, with a placeholder value per column. With
+/// `snippet`, the values are LSP snippet tab stops.
+///
+/// Returns `None` for counter tables, which can only be written with `UPDATE`.
+pub fn insert_statement(table: &Table, snippet: bool) -> Option<String> {
+    if table
+        .columns
+        .iter()
+        .any(|column| column.data_type.is_counter())
+    {
+        return None;
+    }
+
+    let columns = table
+        .columns
+        .iter()
+        .map(|column| text(quote_identifier(&column.name), snippet))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let values = table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| value(placeholder(&column.data_type), i + 1, snippet))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Some(format!(
+        "INSERT INTO {} ({columns}) VALUES ({values});",
+        text(qualified_name(table), snippet)
+    ))
+}
+
+/// Builds up to `count` `INSERT`s of rows of `table` with random values, as test data, the
+/// user-defined types of its columns being looked up in `schema`. Rows have distinct primary
+/// keys, so fewer are built when its key has few values, e.g. a single `boolean`.
+///
+/// Returns `None` for counter tables, which can only be written with `UPDATE`.
+pub fn test_data(table: &Table, schema: &Schema, count: usize) -> Option<String> {
+    if table
+        .columns
+        .iter()
+        .any(|column| column.data_type.is_counter())
+    {
+        return None;
+    }
+
+    let mut keys = HashSet::new();
+    let mut statements = vec![];
+
+    // Each row gets a few tries at a key not taken yet
+    for _ in 0..count.saturating_mul(10) {
+        if statements.len() == count {
+            break;
+        }
+
+        // Columns without a value to make up are left out, if they aren't part of the key
+        let row = table
+            .columns
+            .iter()
+            .filter_map(|column| {
+                let value = fixtures::value(&column.data_type, schema, &table.keyspace)?;
+                Some((column, value))
+            })
+            .collect::<Vec<_>>();
+
+        let key = row
+            .iter()
+            .filter(|(column, _)| table.is_primary_key(&column.name))
+            .map(|(_, value)| value.clone())
+            .collect::<Vec<_>>();
+
+        let key_columns = table.partition_key.len() + table.clustering_key.len();
+
+        if key.len() < key_columns || !keys.insert(key) {
+            continue;
+        }
+
+        let (columns, values): (Vec<_>, Vec<_>) = row
+            .into_iter()
+            .map(|(column, value)| (quote_identifier(&column.name), value))
+            .unzip();
+
+        statements.push(format!(
+            "INSERT INTO {} ({}) VALUES ({});",
+            qualified_name(table),
+            columns.join(", "),
+            values.join(", ")
+        ));
+    }
+
+    Some(statements.join("\n"))
+}
+
+/// Builds the string literal of a JSON object of every column of `table`, as inserted with
+/// `INSERT INTO table JSON '...'`, with a placeholder value per column. With `snippet`, the
+/// values are LSP snippet tab stops.
+pub fn json_row(table: &Table, snippet: bool) -> String {
+    let fields = table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            // Case-sensitive names are double-quoted within the key
+            let key = quote_identifier(&column.name).replace('"', "\\\"");
+
+            format!(
+                "{}: {}",
+                text(format!("\"{key}\""), snippet),
+                value(json_placeholder(&column.data_type), i + 1, snippet)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("'{{{}}}'", fields.replace('\'', "''"))
+}
+
+/// Builds a `SELECT` of every column of `table` restricting each partition and clustering key
+/// column, with a placeholder value per key column. With `snippet`, the values are LSP snippet
+/// tab stops.
+pub fn select_statement(table: &Table, snippet: bool) -> String {
+    let columns = table
+        .columns
+        .iter()
+        .map(|column| text(quote_identifier(&column.name), snippet))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let restrictions = table
+        .partition_key
+        .iter()
+        .chain(&table.clustering_key)
+        .enumerate()
+        .map(|(i, name)| {
+            let placeholder = table
+                .column(name)
+                .map_or("null", |column| placeholder(&column.data_type));
+
+            format!(
+                "{} = {}",
+                text(quote_identifier(name), snippet),
+                value(placeholder, i + 1, snippet)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ");
+
+    let table = text(qualified_name(table), snippet);
+
+    match restrictions.is_empty() {
+        true => format!("SELECT {columns} FROM {table};"),
+        false => format!("SELECT {columns} FROM {table} WHERE {restrictions};"),
+    }
+}
+
+/// Formats options as the `WITH` clause of DDL, one option per line
+fn with_clause(options: &[(String, String)]) -> String {
+    let options = options
+        .iter()
+        .map(|(name, value)| match name.as_str() {
+            // Stored with the rest of their clause as their value
+            "clustering" | "compact" => format!("{} {value}", name.to_ascii_uppercase()),
+            _ => format!("{name} = {value}"),
+        })
+        .collect::<Vec<_>>();
+
+    match options.is_empty() {
+        true => String::new(),
+        false => format!(" WITH {}", options.join("\n    AND ")),
+    }
+}
+
+/// Builds the `CREATE KEYSPACE` of a keyspace with its options
+pub fn create_keyspace_statement(name: &str, keyspace: &Keyspace) -> String {
+    format!(
+        "CREATE KEYSPACE {}{};",
+        quote_identifier(name),
+        with_clause(&keyspace.options)
+    )
+}
+
+/// Builds the `CREATE TABLE` of `table`, with a line per column, its primary key and options
+pub fn create_table_statement(table: &Table) -> String {
+    let mut definitions = table
+        .columns
+        .iter()
+        .map(|column| {
+            let name = quote_identifier(&column.name);
+
+            match table.is_static(&column.name) {
+                true => format!("{name} {} STATIC", column.data_type),
+                false => format!("{name} {}", column.data_type),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let names = |names: &[String]| {
+        names
+            .iter()
+            .map(|name| quote_identifier(name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let partition_key = match table.partition_key.len() {
+        1 => names(&table.partition_key),
+        _ => format!("({})", names(&table.partition_key)),
+    };
+
+    definitions.push(match table.clustering_key.is_empty() {
+        true => format!("PRIMARY KEY ({partition_key})"),
+        false => format!(
+            "PRIMARY KEY ({partition_key}, {})",
+            names(&table.clustering_key)
+        ),
+    });
+
+    format!(
+        "CREATE TABLE {} (\n    {}\n){};",
+        qualified_name(table),
+        definitions.join(",\n    "),
+        with_clause(&table.options)
+    )
+}
+
+/// The name Cassandra gives an index created without one
+pub fn index_name(table: &Table, index: &Index) -> String {
+    index
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("{}_{}_idx", table.name, index.column))
+}
+
+/// Builds the `CREATE INDEX` of an index of `table`
+pub fn create_index_statement(table: &Table, index: &Index) -> String {
+    let column = quote_identifier(&index.column);
+    let target = match index.target {
+        IndexTarget::Column => column,
+        IndexTarget::Keys => format!("KEYS({column})"),
+        IndexTarget::Values => format!("VALUES({column})"),
+        IndexTarget::Entries => format!("ENTRIES({column})"),
+        IndexTarget::Full => format!("FULL({column})"),
+    };
+
+    let name = match &index.name {
+        Some(name) => format!("{} ", quote_identifier(name)),
+        None => String::new(),
+    };
+
+    let using = match &index.class {
+        Some(class) => format!(" USING '{}'", class.replace('\'', "''")),
+        None => String::new(),
+    };
+
+    format!(
+        "CREATE INDEX {name}ON {} ({target}){using};",
+        qualified_name(table)
+    )
+}
+
+/// Builds the DDL creating `table` and its indexes
+pub fn describe_table(table: &Table) -> String {
+    let mut statements = vec![create_table_statement(table)];
+
+    statements.extend(
+        table
+            .indexes
+            .iter()
+            .map(|index| create_index_statement(table, index)),
+    );
+
+    statements.join("\n\n")
+}
+
+/// Builds the DDL creating the keyspace named `name` and its tables. The definitions of views
+/// and functions aren't known, so they're only named in comments.
+pub fn describe_keyspace(name: &str, keyspace: &Keyspace) -> String {
+    let mut statements = vec![create_keyspace_statement(name, keyspace)];
+
+    statements.extend(keyspace.tables.values().map(describe_table));
+
+    for view in keyspace.views.values() {
+        statements.push(format!(
+            "-- Materialized view {} (definition unknown)",
+            qualified_name(view)
+        ));
+    }
+
+    for function in &keyspace.functions {
+        statements.push(format!(
+            "-- Function {}.{} (definition unknown)",
+            quote_identifier(name),
+            quote_identifier(&function.name)
+        ));
+    }
+
+    statements.join("\n\n")
+}
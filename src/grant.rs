@@ -0,0 +1,65 @@
+//! The roles of `GRANT`, `REVOKE` and `LIST`, and of DSE's `RESTRICT` and `UNRESTRICT`,
+//! checked against the roles of the connected cluster: granting to a role that doesn't exist
+//! fails.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    lexer::Token,
+    schema::{Schema, role_name},
+    statement::{Statement, StatementKind},
+};
+
+/// The tokens naming roles in `statement`: the role receiving, losing or listed, and the role
+/// granted or revoked in `GRANT role TO role`
+fn roles<'a>(statement: &Statement<'a>) -> Vec<&'a Token<'a>> {
+    let tokens = statement.tokens;
+
+    let target = match statement.kind() {
+        StatementKind::Grant | StatementKind::Restrict => "TO",
+        StatementKind::Revoke | StatementKind::Unrestrict => "FROM",
+        StatementKind::List => "OF",
+        _ => return vec![],
+    };
+
+    let mut roles = vec![];
+
+    if let Some(i) = statement.find_keyword(target) {
+        roles.extend(tokens.get(i + 1));
+
+        // Roles are granted to roles without a resource
+        if matches!(
+            statement.kind(),
+            StatementKind::Grant | StatementKind::Revoke
+        ) && statement.find_keyword("ON").is_none()
+            && i == 2
+        {
+            roles.extend(tokens.get(1));
+        }
+    }
+
+    roles
+}
+
+/// Checks that the roles of a `GRANT`, `REVOKE`, `LIST`, `RESTRICT` or `UNRESTRICT` exist, once the roles of a cluster
+/// are known
+pub fn check(statement: &Statement, schema: &Schema) -> Vec<Problem> {
+    if !schema.cluster_roles {
+        return vec![];
+    }
+
+    roles(statement)
+        .into_iter()
+        .filter_map(|token| {
+            let role = role_name(token)?;
+
+            (!schema.roles.contains(&role)).then(|| Problem {
+                span: token.start..token.end,
+                severity: DiagnosticSeverity::WARNING,
+                message: format!("Role `{role}` doesn't exist on the cluster"),
+                lint: None,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,114 @@
+//! `GROUP BY` of `SELECT` statements, which Cassandra only allows on a prefix of the primary
+//! key: the partition key columns, then clustering columns in their order.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    lexer::Token,
+    schema::Table,
+    statement::{Statement, split_comma_ranges},
+};
+
+/// The keywords of the clauses that may follow `GROUP BY`
+const FOLLOWING_CLAUSES: &[&str] = &["ORDER", "PER", "LIMIT", "ALLOW"];
+
+/// The columns grouped by, as far as they've been typed
+pub fn columns<'a>(statement: &Statement<'a>) -> Vec<&'a Token<'a>> {
+    let Some(group) = statement.find_keyword("GROUP") else {
+        return vec![];
+    };
+
+    let tokens = statement.tokens;
+    let start = group + 2;
+    let end = FOLLOWING_CLAUSES
+        .iter()
+        .filter_map(|keyword| statement.find_keyword_from(keyword, start))
+        .min()
+        .unwrap_or(tokens.len());
+
+    let Some(list) = tokens.get(start..end) else {
+        return vec![];
+    };
+
+    split_comma_ranges(list)
+        .into_iter()
+        .filter_map(|part| match &list[part] {
+            [column] if column.is_identifier() => Some(column),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The columns of the primary key of `table`, in order
+pub fn primary_key(table: &Table) -> Vec<&String> {
+    table
+        .partition_key
+        .iter()
+        .chain(&table.clustering_key)
+        .collect()
+}
+
+/// The columns restricted with `=` in the `WHERE` clause, which `GROUP BY` may skip
+fn restricted_by_equality(statement: &Statement) -> Vec<String> {
+    let Some(r#where) = statement.find_keyword("WHERE") else {
+        return vec![];
+    };
+
+    statement.tokens[r#where + 1..]
+        .windows(2)
+        .filter(|w| w[1].is_punctuation("="))
+        .filter_map(|w| w[0].identifier())
+        .collect()
+}
+
+/// Checks the `GROUP BY` of a `SELECT` of `table`, reporting columns outside the primary key
+/// and columns out of its order
+pub fn check(statement: &Statement, table: &Table) -> Vec<Problem> {
+    let key = primary_key(table);
+    let restricted = restricted_by_equality(statement);
+
+    let mut problems = vec![];
+    let mut next = 0;
+
+    for token in columns(statement) {
+        let Some(column) = token.identifier() else {
+            continue;
+        };
+
+        let error = |message: String| Problem {
+            span: token.start..token.end,
+            severity: DiagnosticSeverity::ERROR,
+            message,
+            lint: None,
+        };
+
+        let Some(position) = key.iter().position(|name| **name == column) else {
+            if table.column(&column).is_some() {
+                problems.push(error(format!(
+                    "Cannot group by `{column}`: GROUP BY only accepts primary key columns"
+                )));
+            }
+            continue;
+        };
+
+        // Key columns may only be skipped when restricted to a single value
+        let skipped = key[next.min(position)..position]
+            .iter()
+            .find(|name| !restricted.contains(name));
+
+        if position < next {
+            problems.push(error(format!(
+                "Cannot group by `{column}` here: GROUP BY columns must follow the order of the primary key"
+            )));
+        } else if let Some(skipped) = skipped {
+            problems.push(error(format!(
+                "Cannot group by `{column}` without grouping by `{skipped}` first, unless `{skipped}` is restricted with `=`"
+            )));
+        }
+
+        next = next.max(position + 1);
+    }
+
+    problems
+}
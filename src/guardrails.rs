@@ -0,0 +1,96 @@
+//! Guardrails for statements run on a cluster with `cql.execute`: a cap on the rows a `SELECT`
+//! returns, and the statements that need the user's confirmation on protected profiles, which
+//! are flagged ahead of time in the files meant for them.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::{Lint, Problem},
+    statement::{Statement, StatementKind},
+};
+
+const DROP: Lint = Lint {
+    code: "destructive-drop",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/ddl.html",
+};
+
+const TRUNCATE: Lint = Lint {
+    code: "destructive-truncate",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/ddl.html#truncate-statement",
+};
+
+/// Returns the text of `statement` in `text`, with a `LIMIT` of `row_limit` added when it's a
+/// `SELECT` without one (`PER PARTITION LIMIT` not counting), so that a forgotten `WHERE`
+/// doesn't fetch the whole table. The added limit is returned too, 0 adding none.
+pub fn statement_text(statement: &Statement, text: &str, row_limit: u64) -> (String, Option<u64>) {
+    let (Some(first), Some(last)) = (statement.tokens.first(), statement.tokens.last()) else {
+        return (String::new(), None);
+    };
+
+    let whole = text[first.start..last.end].to_string();
+
+    if row_limit == 0 || statement.kind() != StatementKind::Select {
+        return (whole, None);
+    }
+
+    let limited = std::iter::successors(statement.find_keyword("LIMIT"), |&i| {
+        statement.find_keyword_from("LIMIT", i + 1)
+    })
+    .any(|i| i == 0 || !statement.tokens[i - 1].is_keyword("PARTITION"));
+
+    if limited {
+        return (whole, None);
+    }
+
+    // `ALLOW FILTERING` comes last, after the limit
+    let text = match statement.find_keyword("ALLOW") {
+        Some(i) => {
+            let allow = statement.tokens[i].start;
+
+            format!(
+                "{} LIMIT {row_limit} {}",
+                text[first.start..allow].trim_end(),
+                &text[allow..last.end]
+            )
+        }
+        None => format!("{whole} LIMIT {row_limit}"),
+    };
+
+    (text, Some(row_limit))
+}
+
+/// Describes what makes `statement` destructive, if it drops an object, truncates a table, or
+/// deletes without an `IF` condition guarding it
+pub fn destructive(statement: &Statement) -> Option<&'static str> {
+    match statement.kind() {
+        StatementKind::Drop => Some("DROP"),
+        StatementKind::Truncate => Some("TRUNCATE"),
+        StatementKind::Delete if statement.find_keyword("IF").is_none() => {
+            Some("DELETE without an IF condition")
+        }
+        _ => None,
+    }
+}
+
+/// Flags `DROP` and `TRUNCATE` statements, which lose data for good, in a file meant for the
+/// cluster of a protected profile
+pub fn check(statement: &Statement, severity: DiagnosticSeverity) -> Vec<Problem> {
+    let (lint, loses) = match statement.kind() {
+        StatementKind::Drop => (DROP, "drops the object and its data"),
+        StatementKind::Truncate => (TRUNCATE, "deletes every row of the table"),
+        _ => return vec![],
+    };
+
+    let (Some(first), Some(last)) = (statement.tokens.first(), statement.tokens.last()) else {
+        return vec![];
+    };
+
+    vec![Problem {
+        span: first.start..last.end,
+        severity,
+        message: format!(
+            "This statement {loses}, and this file is meant for a protected cluster: running it asks for confirmation"
+        ),
+        lint: Some(lint),
+    }]
+}
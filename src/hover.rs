@@ -0,0 +1,396 @@
+//! Hover documentation of the keywords of CQL, from the keyword database, of the types of
+//! columns, expanded down to the fields of their user-defined types, of the tables of the
+//! system keyspaces, and of duration and timestamp literals as Cassandra stores them.
+
+use std::ops::Range;
+
+use tower_lsp::lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position};
+
+use crate::{
+    bind_markers,
+    cql_type::CqlType,
+    datetime,
+    document::{PositionEncodingKind, TextDocument},
+    duration,
+    keywords::{self, Keyword, OPTIONS, SCYLLA_OPTIONS},
+    lexer::{self, Token, TokenKind},
+    schema::{self, Schema, Table},
+    statement::{
+        self, Dialect, Statement, StatementKind, parse_qualified_name, skip_if_exists,
+        split_comma_ranges,
+    },
+    system_keyspaces, type_definition,
+};
+
+/// Whether the tokens starting at `start` spell `keyword`, word for word
+fn spells(tokens: &[Token], start: usize, keyword: &Keyword) -> bool {
+    let words = keyword.label.split(' ').collect::<Vec<_>>();
+
+    tokens
+        .get(start..start + words.len())
+        .is_some_and(|tokens| {
+            tokens.iter().zip(&words).all(|(token, word)| {
+                matches!(token.kind, TokenKind::Word | TokenKind::Punctuation)
+                    && token.text.eq_ignore_ascii_case(word)
+            })
+        })
+}
+
+/// Finds the longest keyword spelt by tokens including the one at `index`, returning it with
+/// the index of its first token and its number of tokens
+fn keyword_at(tokens: &[Token], index: usize) -> Option<(&'static Keyword, usize, usize)> {
+    keywords::all()
+        .filter_map(|keyword| {
+            let len = keyword.label.split(' ').count();
+            let start = (index.saturating_sub(len - 1)..=index)
+                .find(|&start| spells(tokens, start, keyword))?;
+
+            // Options are only options where they're set, not e.g. a column named `comment`
+            let is_option = OPTIONS
+                .iter()
+                .chain(SCYLLA_OPTIONS)
+                .any(|option| option.label == keyword.label);
+            if is_option
+                && !tokens
+                    .get(start + len)
+                    .is_some_and(|t| t.is_punctuation("="))
+            {
+                return None;
+            }
+
+            Some((keyword, start, len))
+        })
+        .max_by_key(|(_, start, len)| (*len, std::cmp::Reverse(*start)))
+}
+
+/// The column or field defined by the definition of `CREATE TABLE`, `CREATE TYPE` or an `ADD`
+/// around the token at `index`, with the index range of the tokens of its name and type, if the
+/// token is one of them
+fn defined_column(statement: &Statement, index: usize) -> Option<(String, CqlType, Range<usize>)> {
+    let range = type_definition::definitions(statement)?;
+
+    if !range.contains(&index) {
+        return None;
+    }
+
+    let part = split_comma_ranges(&statement.tokens[range.clone()])
+        .into_iter()
+        .map(|part| part.start + range.start..part.end + range.start)
+        .find(|part| part.contains(&index))?;
+
+    let definition = &statement.tokens[part.clone()];
+
+    if definition.first()?.is_keyword("PRIMARY") {
+        return None;
+    }
+
+    let name = definition.first()?.identifier()?;
+    let mut end = 1;
+    let data_type = CqlType::parse(definition, &mut end)?;
+    let tokens = part.start..part.start + end;
+
+    tokens.contains(&index).then_some((name, data_type, tokens))
+}
+
+/// Appends the parts of `data_type` to `lines`, one per line indented by `depth`, each expanded
+/// in turn: the elements of collections and tuples, and the fields of user-defined types, looked
+/// up in `keyspace` when unqualified. `expanding` holds the user-defined types being expanded,
+/// which a type can't contain again.
+fn expand(
+    data_type: &CqlType,
+    schema: &Schema,
+    keyspace: &str,
+    depth: usize,
+    expanding: &mut Vec<String>,
+    lines: &mut Vec<String>,
+) {
+    let mut keyspace = keyspace;
+
+    let parts = match data_type.unfrozen() {
+        CqlType::List(element) | CqlType::Set(element) | CqlType::Vector(element, _) => {
+            vec![("element".to_string(), element.as_ref())]
+        }
+        CqlType::Map(key, value) => vec![
+            ("key".to_string(), key.as_ref()),
+            ("value".to_string(), value.as_ref()),
+        ],
+        CqlType::Tuple(elements) => elements
+            .iter()
+            .enumerate()
+            .map(|(i, element)| (i.to_string(), element))
+            .collect(),
+        CqlType::UserDefined { .. } => {
+            let Some(user_type) = schema.resolve_user_type(data_type, keyspace) else {
+                return;
+            };
+
+            let qualified = format!("{}.{}", user_type.keyspace, user_type.name);
+            if expanding.contains(&qualified) {
+                return;
+            }
+
+            expanding.push(qualified);
+            keyspace = &user_type.keyspace;
+
+            user_type
+                .fields
+                .iter()
+                .map(|field| (field.name.clone(), &field.data_type))
+                .collect()
+        }
+        CqlType::Native(_) | CqlType::Custom(_) | CqlType::Frozen(_) => vec![],
+    };
+
+    for (label, part) in parts {
+        lines.push(format!("{}{label}: {part}", "  ".repeat(depth)));
+        expand(part, schema, keyspace, depth + 1, expanding, lines);
+    }
+
+    if matches!(data_type.unfrozen(), CqlType::UserDefined { .. }) {
+        expanding.pop();
+    }
+}
+
+/// Describes the type of the column or field named, or defined, by the token at `index`, or
+/// the system table named there, returning the description with the byte range it describes
+fn type_hover(
+    statement: &Statement,
+    index: usize,
+    schema: &Schema,
+    keyspace: Option<&str>,
+) -> Option<(String, Range<usize>)> {
+    let tokens = statement.tokens;
+
+    let (name, data_type, range, keyspace, documentation) = match defined_column(statement, index) {
+        Some((name, data_type, range)) => {
+            // Unqualified types are those of the keyspace of the table or type
+            let created = match statement.kind() {
+                StatementKind::CreateTable
+                | StatementKind::CreateType
+                | StatementKind::AlterTable
+                | StatementKind::AlterType => {
+                    parse_qualified_name(tokens, &mut skip_if_exists(tokens, 2))
+                }
+                _ => None,
+            };
+
+            let range = tokens[range.start].start..tokens[range.end - 1].end;
+
+            (
+                name,
+                data_type,
+                range,
+                created
+                    .and_then(|created| created.keyspace)
+                    .or(keyspace.map(String::from)),
+                None,
+            )
+        }
+        None => {
+            let token = &tokens[index];
+            let name = token.identifier()?;
+            let table = schema.statement_table(statement, keyspace)?;
+            let target = statement.target_table()?;
+
+            // The table's own name, or a function named like a column
+            let is_table = target.start <= token.start && token.end <= target.end;
+            let is_call = tokens.get(index + 1).is_some_and(|t| t.is_punctuation("("));
+
+            // The tables of the system keyspaces are described by the bundled documentation
+            if is_table {
+                let documentation =
+                    system_keyspaces::documentation(&table.keyspace, &table.name, None)?;
+
+                return Some((
+                    format!(
+                        "```cql\n{}.{}\n```\n\n{documentation}",
+                        table.keyspace, table.name
+                    ),
+                    target.start..target.end,
+                ));
+            }
+
+            if is_call {
+                return None;
+            }
+
+            let column = table.column(&name)?;
+
+            (
+                name,
+                column.data_type.clone(),
+                token.start..token.end,
+                Some(table.keyspace.clone()),
+                system_keyspaces::documentation(&table.keyspace, &table.name, Some(&column.name)),
+            )
+        }
+    };
+
+    let mut value = format!("```cql\n{name} {data_type}\n```");
+
+    let mut lines = vec![];
+    let keyspace = Schema::resolve_keyspace(keyspace, &None);
+    expand(&data_type, schema, &keyspace, 1, &mut vec![], &mut lines);
+
+    if !lines.is_empty() {
+        value.push_str(&format!(
+            "\n\n```text\n{data_type}\n{}\n```",
+            lines.join("\n")
+        ));
+    }
+
+    if let Some(documentation) = documentation {
+        value.push_str(&format!("\n\n{documentation}"));
+    }
+
+    Some((value, range))
+}
+
+/// Describes the duration literal `token` normalized, as the months, days and nanoseconds
+/// Cassandra stores, e.g. `90m` as `1h30m`
+fn duration_hover(token: &Token) -> Option<(String, Range<usize>)> {
+    if token.kind != TokenKind::Duration {
+        return None;
+    }
+
+    let (months, days, nanoseconds) = duration::parse(token.text)?;
+
+    let count = |value: i64, unit: &str| match value.abs() {
+        1 => format!("{value} {unit}"),
+        _ => format!("{value} {unit}s"),
+    };
+
+    Some((
+        format!(
+            "```cql\n{}\n```\n\nA `duration` of {}, {} and {}, kept apart as months and days vary in length",
+            duration::format(months, days, nanoseconds),
+            count(months, "month"),
+            count(days, "day"),
+            count(nanoseconds, "nanosecond"),
+        ),
+        token.start..token.end,
+    ))
+}
+
+/// Describes the timestamp literal at `index`, a value of a `timestamp` column of `table` or
+/// of `USING TIMESTAMP`, as both a date and time in UTC and milliseconds since the Unix epoch,
+/// flagging numbers that look like seconds or microseconds
+fn timestamp_hover(
+    statement: &Statement,
+    index: usize,
+    table: Option<&Table>,
+) -> Option<(String, Range<usize>)> {
+    let tokens = statement.tokens;
+    let token = &tokens[index];
+
+    // The write time of `USING TIMESTAMP` is in microseconds
+    if index > 0 && tokens[index - 1].is_keyword("TIMESTAMP") {
+        let microseconds = token.text.parse::<i64>().ok()?;
+
+        return Some((
+            format!(
+                "```cql\n{}\n```\n\nThe write time, {microseconds} microseconds since the Unix epoch",
+                datetime::format_timestamp(microseconds.div_euclid(1000))
+            ),
+            token.start..token.end,
+        ));
+    }
+
+    let data_type = bind_markers::value_type(statement, index, table)?;
+
+    if !data_type.is_native("timestamp") {
+        return None;
+    }
+
+    let value = match token.kind {
+        TokenKind::String => {
+            let (milliseconds, zoned) = datetime::parse_timestamp(&token.string_value()?)?;
+
+            let zone = match zoned {
+                true => "",
+                false => {
+                    "\n\nWithout a time zone, it's read in the coordinator's, taken as UTC here"
+                }
+            };
+
+            format!(
+                "```cql\n{}\n```\n\n{milliseconds} milliseconds since the Unix epoch{zone}",
+                datetime::format_timestamp(milliseconds)
+            )
+        }
+        TokenKind::Number => {
+            let milliseconds = token.text.parse::<i64>().ok()?;
+
+            // Before 1973 in milliseconds but within 5000 years in seconds, or after 5138 in
+            // milliseconds: likely seconds or microseconds
+            let hint = match milliseconds.unsigned_abs() {
+                1..100_000_000_000 => format!(
+                    "\n\nTimestamps count milliseconds: if this is in seconds, it's `{}`",
+                    milliseconds.saturating_mul(1000)
+                ),
+                100_000_000_000_000.. => format!(
+                    "\n\nTimestamps count milliseconds: if this is in microseconds, it's `{}`",
+                    milliseconds / 1000
+                ),
+                _ => String::new(),
+            };
+
+            format!(
+                "```cql\n{}\n```\n\n{milliseconds} milliseconds since the Unix epoch{hint}",
+                datetime::format_timestamp(milliseconds)
+            )
+        }
+        _ => return None,
+    };
+
+    Some((value, token.start..token.end))
+}
+
+/// Describes the duration or timestamp literal at `position` in `doc`, the type of the column
+/// there, checked against `schema`, or the keyword there
+pub fn hover(
+    doc: &TextDocument,
+    position: &Position,
+    schema: Schema,
+    dialect: Dialect,
+    position_encoding: PositionEncodingKind,
+) -> Option<Hover> {
+    let offset = doc.position_to_byte_idx(position, position_encoding).ok()?;
+
+    let text = doc.text();
+    let tokens = lexer::tokenize(text);
+    let statements = statement::split(&tokens, dialect);
+    let i = statement::statement_at(&statements, offset)?;
+    let statement = &statements[i];
+
+    let index = statement
+        .tokens
+        .iter()
+        .position(|token| token.start <= offset && offset < token.end)?;
+
+    let schema = schema.with_statements(&statements);
+    let keyspace = schema::keyspace_at(&statements, i).or(schema.used_keyspace.clone());
+
+    let table = schema.statement_table(statement, keyspace.as_deref());
+
+    let (value, range) = duration_hover(&statement.tokens[index])
+        .or_else(|| timestamp_hover(statement, index, table))
+        .or_else(|| type_hover(statement, index, &schema, keyspace.as_deref()))
+        .or_else(|| {
+            let (keyword, start, len) = keyword_at(statement.tokens, index)?;
+            let range = statement.tokens[start].start..statement.tokens[start + len - 1].end;
+
+            Some((
+                format!("```cql\n{}\n```\n\n{}", keyword.label, keyword.markdown()),
+                range,
+            ))
+        })?;
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(doc.byte_range_to_range(range, position_encoding)),
+    })
+}
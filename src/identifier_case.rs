@@ -0,0 +1,98 @@
+//! Identifiers written with a different case or quoting than the object they're meant to name,
+//! e.g. `users` for a table created as `"Users"`: unquoted identifiers are folded to lower
+//! case while quoted ones are case-sensitive, so these don't name the object at all.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    keywords,
+    lexer::{Token, TokenKind, is_reserved, quote_identifier},
+    schema::{Schema, Table},
+    statement::Statement,
+};
+
+/// Finds the one of `names` equal to `name` but for case, unless `name` is one of them
+fn differently_cased<'a>(
+    name: &str,
+    mut names: impl Iterator<Item = &'a String> + Clone,
+) -> Option<&'a String> {
+    if names.clone().any(|candidate| candidate == name) {
+        return None;
+    }
+
+    names.find(|candidate| candidate.eq_ignore_ascii_case(name))
+}
+
+fn warning(token: &Token, kind: &str, name: &str) -> Problem {
+    Problem {
+        span: token.start..token.end,
+        severity: DiagnosticSeverity::WARNING,
+        message: format!(
+            "`{}` doesn't name {kind} `{name}`: identifiers are case-sensitive when quoted and lower case otherwise, so it must be written `{}`",
+            token.text,
+            quote_identifier(name)
+        ),
+        lint: None,
+    }
+}
+
+/// Whether the unquoted word `token` is a keyword rather than an identifier
+fn is_keyword(token: &Token) -> bool {
+    token.kind == TokenKind::Word
+        && (is_reserved(token.text)
+            || keywords::all().any(|keyword| keyword.label.eq_ignore_ascii_case(token.text)))
+}
+
+/// Checks the names of the keyspace and table a DML statement operates on, when it doesn't
+/// resolve to a table
+fn check_table(statement: &Statement, schema: &Schema) -> Option<Problem> {
+    let name = statement.target_table()?;
+    let tokens = statement.tokens;
+
+    // Unqualified names are looked up in every keyspace, as they don't resolve in the current one
+    let keyspace = match &name.keyspace {
+        Some(keyspace) => {
+            let token = tokens.iter().find(|t| t.start == name.start)?;
+
+            if let Some(actual) = differently_cased(keyspace, schema.keyspaces.keys()) {
+                return Some(warning(token, "keyspace", actual));
+            }
+
+            Some(keyspace.as_str())
+        }
+        None => None,
+    };
+
+    let keyspaces = schema
+        .keyspaces
+        .iter()
+        .filter(|(name, _)| keyspace.is_none_or(|keyspace| keyspace == *name))
+        .map(|(_, keyspace)| keyspace);
+
+    let token = tokens.iter().rfind(|t| t.end == name.end)?;
+    let names = keyspaces.flat_map(|keyspace| keyspace.tables.keys().chain(keyspace.views.keys()));
+    let actual = differently_cased(&name.name, names)?;
+
+    Some(warning(token, "table", actual))
+}
+
+/// Checks the identifiers of a DML statement against the names of the keyspace, table and
+/// columns they're likely meant to name, `table` being the one it operates on if it resolves
+pub fn check(statement: &Statement, table: Option<&Table>, schema: &Schema) -> Vec<Problem> {
+    let Some(table) = table else {
+        return check_table(statement, schema).into_iter().collect();
+    };
+
+    let columns = table.columns.iter().map(|column| &column.name);
+
+    statement
+        .tokens
+        .iter()
+        .filter(|token| !is_keyword(token))
+        .filter_map(|token| {
+            let actual = differently_cased(&token.identifier()?, columns.clone())?;
+            Some(warning(token, "column", actual))
+        })
+        .collect()
+}
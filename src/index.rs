@@ -0,0 +1,189 @@
+//! `CREATE INDEX` parsing and lints for indexes that are likely to perform poorly.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    lexer::Token,
+    schema::{Index, IndexTarget, Table},
+    statement::{QualifiedName, Statement, StatementKind, matching_bracket, parse_qualified_name},
+};
+
+/// The parts of a `CREATE [CUSTOM] INDEX` statement, as far as they've been typed
+#[derive(Debug)]
+pub struct IndexDefinition<'a> {
+    pub name: Option<String>,
+    pub table: Option<QualifiedName>,
+    pub target: IndexTarget,
+    /// The indexed column
+    pub column: Option<&'a Token<'a>>,
+    /// The index class given with `USING`
+    pub class: Option<String>,
+}
+
+impl IndexDefinition<'_> {
+    /// Whether this is a storage-attached index, which unlike legacy secondary indexes copes
+    /// with high-cardinality columns
+    pub fn is_sai(&self) -> bool {
+        self.class.as_deref().is_some_and(is_sai_class)
+    }
+
+    pub fn to_index(&self) -> Option<Index> {
+        Some(Index {
+            name: self.name.clone(),
+            column: self.column?.identifier()?,
+            target: self.target,
+            class: self.class.clone(),
+        })
+    }
+}
+
+pub fn is_sai_class(class: &str) -> bool {
+    class.eq_ignore_ascii_case("sai")
+        || class.eq_ignore_ascii_case("StorageAttachedIndex")
+        || class.ends_with(".StorageAttachedIndex")
+}
+
+/// Whether `class` is the SSTable-attached secondary index, which like SAI supports `LIKE`
+pub fn is_sasi_class(class: &str) -> bool {
+    class.ends_with("SASIIndex")
+}
+
+/// Parses `CREATE [CUSTOM] INDEX [IF NOT EXISTS] [name] ON table ([KEYS|VALUES|ENTRIES|FULL]
+/// (column) | column) [USING 'class' [WITH OPTIONS = {...}]]`
+pub fn parse<'a>(statement: &Statement<'a>) -> Option<IndexDefinition<'a>> {
+    if statement.kind() != StatementKind::CreateIndex {
+        return None;
+    }
+
+    let tokens = statement.tokens;
+    let on = statement.find_keyword("ON");
+
+    // Everything between `INDEX [IF NOT EXISTS]` and `ON` is the index name
+    let name = on.and_then(|on| {
+        tokens[..on]
+            .last()
+            .filter(|token| {
+                !["INDEX", "EXISTS"].iter().any(|k| token.is_keyword(k)) && token.is_identifier()
+            })?
+            .identifier()
+    });
+
+    let mut i = on.map_or(tokens.len(), |on| on + 1);
+    let table = parse_qualified_name(tokens, &mut i);
+
+    let (target, column) = match tokens.get(i) {
+        Some(open) if open.is_punctuation("(") => {
+            let close = matching_bracket(tokens, i).unwrap_or(tokens.len());
+            let inner = &tokens[i + 1..close];
+
+            let target = match inner.first() {
+                Some(t) if t.is_keyword("KEYS") => IndexTarget::Keys,
+                Some(t) if t.is_keyword("VALUES") => IndexTarget::Values,
+                Some(t) if t.is_keyword("ENTRIES") => IndexTarget::Entries,
+                Some(t) if t.is_keyword("FULL") => IndexTarget::Full,
+                _ => IndexTarget::Column,
+            };
+
+            let column = match target {
+                IndexTarget::Column => inner.first(),
+                _ => inner.get(2),
+            };
+
+            (target, column.filter(|token| token.is_identifier()))
+        }
+        _ => (IndexTarget::Column, None),
+    };
+
+    let class = statement
+        .find_keyword("USING")
+        .and_then(|using| tokens.get(using + 1)?.string_value());
+
+    Some(IndexDefinition {
+        name,
+        table,
+        target,
+        column,
+        class,
+    })
+}
+
+/// Types whose values are (nearly) unique per row. A legacy secondary index on such a column
+/// has an entry per row on every node, and every query has to ask all of them.
+const HIGH_CARDINALITY_TYPES: &[&str] = &["uuid", "timeuuid", "timestamp", "blob"];
+
+/// Lints an index definition against the table it indexes
+pub fn check(index: &IndexDefinition, table: &Table) -> Vec<Problem> {
+    let Some(token) = index.column else {
+        return vec![];
+    };
+
+    let Some(name) = token.identifier() else {
+        return vec![];
+    };
+
+    let problem = |severity, message: String| Problem {
+        span: token.start..token.end,
+        severity,
+        message,
+        lint: None,
+    };
+
+    let Some(column) = table.column(&name) else {
+        return vec![problem(
+            DiagnosticSeverity::ERROR,
+            format!("Unknown column `{name}` in table `{}`", table.name),
+        )];
+    };
+
+    let data_type = &column.data_type;
+    let mut problems = vec![];
+
+    let same_class = |class: &Option<String>| match (class, &index.class) {
+        (Some(existing), Some(new)) => {
+            existing == new || (is_sai_class(existing) && is_sai_class(new))
+        }
+        (existing, new) => existing.is_none() && new.is_none(),
+    };
+
+    let duplicate = table.indexes.iter().find(|existing| {
+        existing.column == name && existing.target == index.target && same_class(&existing.class)
+    });
+
+    if let Some(duplicate) = duplicate {
+        let message = match &duplicate.name {
+            Some(existing) => format!("Duplicate of the existing index `{existing}` on `{name}`"),
+            None => format!("Duplicate of an existing index on `{name}`"),
+        };
+
+        problems.push(problem(DiagnosticSeverity::ERROR, message));
+    }
+
+    if data_type.is_frozen() && data_type.is_collection() {
+        let message = match index.target {
+            IndexTarget::Full => format!(
+                "`{name}` is a frozen collection: the index only matches whole `{data_type}` values"
+            ),
+            _ => format!(
+                "`{name}` is a frozen collection, which can only be indexed with `FULL({name})`, matching whole values only"
+            ),
+        };
+
+        problems.push(problem(DiagnosticSeverity::WARNING, message));
+    }
+
+    let high_cardinality = HIGH_CARDINALITY_TYPES
+        .iter()
+        .any(|native| data_type.is_native(native));
+
+    if high_cardinality && !index.is_sai() {
+        problems.push(problem(
+            DiagnosticSeverity::WARNING,
+            format!(
+                "`{name}` is a `{data_type}` column, likely unique per row: a secondary index on it makes every query contact all nodes. Consider a table keyed by `{name}`, or a storage-attached index (`USING 'sai'`)"
+            ),
+        ));
+    }
+
+    problems
+}
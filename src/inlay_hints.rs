@@ -0,0 +1,125 @@
+use tower_lsp::lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, Range};
+
+use crate::{
+    bind_markers,
+    document::{PositionEncodingKind, TextDocument},
+    lexer::{self, TokenKind},
+    schema::{self, Schema},
+    statement::{self, Dialect, Statement},
+};
+
+fn hint(
+    doc: &TextDocument,
+    byte_idx: usize,
+    label: String,
+    kind: InlayHintKind,
+    position_encoding: PositionEncodingKind,
+) -> InlayHint {
+    InlayHint {
+        position: doc.byte_idx_to_position(byte_idx, position_encoding),
+        label: InlayHintLabel::String(label),
+        kind: Some(kind),
+        text_edits: None,
+        tooltip: None,
+        padding_left: None,
+        padding_right: Some(kind == InlayHintKind::PARAMETER),
+        data: None,
+    }
+}
+
+/// `id:`, `name:` hints before each value of an `INSERT ... VALUES (...)` tuple, so values can
+/// be matched with their column without counting commas
+fn insert_column_hints(
+    doc: &TextDocument,
+    statement: &Statement,
+    position_encoding: PositionEncodingKind,
+) -> Vec<InlayHint> {
+    let tokens = statement.tokens;
+
+    let Some(insert) = statement.insert_values() else {
+        return vec![];
+    };
+
+    insert
+        .columns
+        .into_iter()
+        .zip(insert.values)
+        .filter_map(|(column, value)| {
+            let column = tokens.get(column.start)?.identifier()?;
+            let value = tokens.get(value.start)?;
+
+            // `:name` already says which column it binds to
+            let redundant = value.kind == TokenKind::NamedBindMarker
+                && bind_markers::marker_name(value).as_deref() == Some(column.as_str());
+
+            (!redundant).then(|| {
+                hint(
+                    doc,
+                    value.start,
+                    format!("{column}:"),
+                    InlayHintKind::PARAMETER,
+                    position_encoding,
+                )
+            })
+        })
+        .collect()
+}
+
+/// `: type` hints after bind markers whose bound column type is known
+fn bind_marker_hints(
+    doc: &TextDocument,
+    statement: &Statement,
+    table: Option<&schema::Table>,
+    position_encoding: PositionEncodingKind,
+) -> Vec<InlayHint> {
+    bind_markers::statement_bind_markers(statement, table)
+        .into_iter()
+        .filter_map(|marker| {
+            let data_type = marker.data_type?;
+
+            Some(hint(
+                doc,
+                marker.span.end,
+                format!(": {data_type}"),
+                InlayHintKind::TYPE,
+                position_encoding,
+            ))
+        })
+        .collect()
+}
+
+/// Computes the inlay hints for the statements intersecting `range`
+pub fn inlay_hints(
+    doc: &TextDocument,
+    range: Range,
+    dialect: Dialect,
+    position_encoding: PositionEncodingKind,
+) -> Vec<InlayHint> {
+    let text = doc.text();
+    let tokens = lexer::tokenize(text);
+    let statements = statement::split(&tokens, dialect);
+    let schema = Schema::from_statements(&statements);
+
+    let start = doc
+        .position_to_byte_idx(&range.start, position_encoding)
+        .unwrap_or(0);
+    let end = doc
+        .position_to_byte_idx(&range.end, position_encoding)
+        .unwrap_or(text.len());
+
+    let mut hints = vec![];
+
+    for (i, statement) in statements.iter().enumerate() {
+        if statement.end < start || statement.start > end {
+            continue;
+        }
+
+        let keyspace = schema::keyspace_at(&statements, i);
+        let table = schema.statement_table(statement, keyspace.as_deref());
+
+        hints.extend(insert_column_hints(doc, statement, position_encoding));
+        hints.extend(bind_marker_hints(doc, statement, table, position_encoding));
+    }
+
+    hints
+}
@@ -0,0 +1,122 @@
+//! JSON support of CQL: rows inserted with `INSERT INTO t JSON '{...}'` and values given with
+//! `fromJson('...')`, whose literals are checked to be well-formed JSON, and whose keys are
+//! checked to be columns of the table.
+
+use serde_json::Value;
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    lexer::{Token, TokenKind},
+    schema::Table,
+    statement::{Statement, StatementKind},
+};
+
+/// The column a key of an inserted JSON object names: keys are case-insensitive like unquoted
+/// identifiers, unless double-quoted within the key, e.g. `"\"Name\""`
+fn key_column(key: &str) -> String {
+    match key.strip_prefix('"').and_then(|key| key.strip_suffix('"')) {
+        Some(quoted) => quoted.replace("\"\"", "\""),
+        None => key.to_lowercase(),
+    }
+}
+
+/// The JSON literal of an `INSERT ... JSON`, unless the row is a bind marker
+fn inserted_row<'a>(statement: &Statement<'a>) -> Option<&'a Token<'a>> {
+    if statement.kind() != StatementKind::Insert {
+        return None;
+    }
+
+    let into = statement.find_keyword("INTO")?;
+    let json = statement.find_keyword_from("JSON", into)?;
+
+    statement
+        .tokens
+        .get(json + 1)
+        .filter(|token| token.kind == TokenKind::String)
+}
+
+/// Parses the JSON of a string literal, reporting malformed JSON. Unterminated literals, still
+/// being typed, aren't checked.
+fn parse(token: &Token, problems: &mut Vec<Problem>) -> Option<Value> {
+    let terminated = token.text.len() > 1 && token.text.ends_with(['\'', '$']);
+
+    if !terminated {
+        return None;
+    }
+
+    match serde_json::from_str(&token.string_value()?) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            problems.push(Problem {
+                span: token.start..token.end,
+                severity: DiagnosticSeverity::ERROR,
+                message: format!("Invalid JSON: {e}"),
+                lint: None,
+            });
+
+            None
+        }
+    }
+}
+
+/// Checks the JSON literals of an `INSERT` or `UPDATE`, and the keys of an inserted row
+/// against the columns of `table` when it's known
+pub fn check(statement: &Statement, table: Option<&Table>) -> Vec<Problem> {
+    let mut problems = vec![];
+    let tokens = statement.tokens;
+
+    for (i, token) in tokens.iter().enumerate() {
+        let from_json = token.is_keyword("fromJson")
+            && tokens.get(i + 1).is_some_and(|t| t.is_punctuation("("));
+
+        if from_json && let Some(value) = tokens.get(i + 2).filter(|t| t.kind == TokenKind::String)
+        {
+            parse(value, &mut problems);
+        }
+    }
+
+    let Some(row) = inserted_row(statement) else {
+        return problems;
+    };
+
+    let Some(value) = parse(row, &mut problems) else {
+        return problems;
+    };
+
+    let Value::Object(object) = value else {
+        problems.push(Problem {
+            span: row.start..row.end,
+            severity: DiagnosticSeverity::ERROR,
+            message: "INSERT JSON expects a JSON object mapping column names to values".into(),
+            lint: None,
+        });
+
+        return problems;
+    };
+
+    let Some(table) = table else {
+        return problems;
+    };
+
+    for key in object.keys() {
+        if table.column(&key_column(key)).is_some() {
+            continue;
+        }
+
+        // Point at the key itself when it's written plainly
+        let span = match row.text.find(&format!("\"{key}\"")) {
+            Some(at) => row.start + at..row.start + at + key.len() + 2,
+            None => row.start..row.end,
+        };
+
+        problems.push(Problem {
+            span,
+            severity: DiagnosticSeverity::WARNING,
+            message: format!("`{key}` isn't a column of table `{}`", table.name),
+            lint: None,
+        });
+    }
+
+    problems
+}
@@ -0,0 +1,69 @@
+//! The case of keywords, which the `keywordCase` setting completion inserts them in is
+//! enforced for, e.g. `select * From users` where `SELECT * FROM users` is expected. Only
+//! reserved keywords are checked, as the others may just as well be names.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    config::KeywordCase,
+    diagnostics::{Lint, Problem},
+    lexer::{Token, TokenKind, is_reserved},
+    reserved,
+    schema::Table,
+    statement::Statement,
+};
+
+const LINT: Lint = Lint {
+    code: "keyword-case",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/definitions.html#identifiers",
+};
+
+/// The reserved keywords of `statement` not written in `case`, with their text in it, `table`
+/// being the one it operates on if known. Keywords of any case are fine when preserved.
+pub fn fixes<'a>(
+    statement: &Statement<'a>,
+    table: Option<&Table>,
+    case: KeywordCase,
+) -> Vec<(&'a Token<'a>, String)> {
+    if case == KeywordCase::Preserve {
+        return vec![];
+    }
+
+    // Reserved keywords used as identifiers need quoting rather than another case
+    let identifiers = reserved::identifiers(statement, table);
+
+    statement
+        .tokens
+        .iter()
+        .filter(|token| token.kind == TokenKind::Word && is_reserved(token.text))
+        .filter(|token| !identifiers.iter().any(|name| std::ptr::eq(*name, *token)))
+        .filter_map(|token| {
+            let fixed = case.apply(token.text, "");
+
+            (fixed != token.text).then_some((token, fixed))
+        })
+        .collect()
+}
+
+/// Checks that the keywords of `statement` are written in `case`
+pub fn check(
+    statement: &Statement,
+    table: Option<&Table>,
+    case: KeywordCase,
+    severity: DiagnosticSeverity,
+) -> Vec<Problem> {
+    let expected = match case {
+        KeywordCase::Lower => "lower case",
+        _ => "upper case",
+    };
+
+    fixes(statement, table, case)
+        .into_iter()
+        .map(|(token, fixed)| Problem {
+            span: token.start..token.end,
+            severity,
+            message: format!("Keyword `{}` isn't {expected}: `{fixed}`", token.text),
+            lint: Some(LINT),
+        })
+        .collect()
+}
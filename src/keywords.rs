@@ -0,0 +1,671 @@
+//! The keywords of CQL: the statements, their clauses, operators and options, each with a
+//! short description and a link to the CQL reference. Completion offers them and hover
+//! describes them. Those of DataStax Enterprise and ScyllaDB are only offered when they're the
+//! target.
+
+/// A (possibly multi-word) keyword and its documentation
+#[derive(Debug, Clone, Copy)]
+pub struct Keyword {
+    pub label: &'static str,
+    pub documentation: &'static str,
+    pub deprecated: bool,
+    /// The reference documentation of the keyword
+    pub link: Option<&'static str>,
+}
+
+impl Keyword {
+    pub const fn new(label: &'static str, documentation: &'static str) -> Self {
+        Self {
+            label,
+            documentation,
+            deprecated: false,
+            link: None,
+        }
+    }
+
+    pub const fn link(self, link: &'static str) -> Self {
+        Self {
+            link: Some(link),
+            ..self
+        }
+    }
+
+    pub const fn deprecated(self) -> Self {
+        Self {
+            deprecated: true,
+            ..self
+        }
+    }
+
+    /// The documentation in Markdown, followed by the link to the reference if any
+    pub fn markdown(&self) -> String {
+        match self.link {
+            Some(link) => format!("{}\n\n[Reference]({link})", self.documentation),
+            None => self.documentation.to_string(),
+        }
+    }
+}
+
+/// The page of the CQL reference of the Apache Cassandra documentation named `page`
+macro_rules! reference {
+    ($page:literal) => {
+        concat!(
+            "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/",
+            $page
+        )
+    };
+}
+
+const DDL: &str = reference!("ddl.html");
+const DML: &str = reference!("dml.html");
+const TYPES: &str = reference!("types.html");
+const INDEXES: &str = reference!("indexes.html");
+const VIEWS: &str = reference!("mvs.html");
+const SECURITY: &str = reference!("security.html");
+const FUNCTIONS: &str = reference!("functions.html");
+const TRIGGERS: &str = reference!("triggers.html");
+const JSON: &str = reference!("json.html");
+
+/// The statements, named by their leading keywords
+pub const STATEMENTS: &[Keyword] = &[
+    Keyword::new(
+        "CREATE KEYSPACE",
+        "Creates a keyspace, the namespace of tables, types and functions that sets their replication.\n\n```cql\nCREATE KEYSPACE ks WITH replication = {'class': 'NetworkTopologyStrategy', 'dc1': 3};\n```",
+    )
+    .link(DDL),
+    Keyword::new(
+        "CREATE TABLE",
+        "Creates a new table in the selected keyspace. Use `IF NOT EXISTS` to suppress the error message if the table already exists; no table is created.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "CREATE TYPE",
+        concat!(
+            "Creates a custom data type in the keyspace that contains one or more fields of related information, such as address (street, city, state, and postal code).\n",
+            "\nThe scope of a user-defined type (UDT) is keyspace-wide.\n",
+            ">[!WARNING]IMPORTANT\n",
+            ">UDTs cannot contain counter fields."
+        ),
+    )
+    .link(TYPES),
+    Keyword::new(
+        "CREATE MATERIALIZED VIEW",
+        concat!(
+            "Creates a materialized view: a table the server keeps up to date with the rows of a base table, keyed differently so the data can be queried by other columns.\n",
+            "\nEvery column of the view primary key must be restricted with `IS NOT NULL`, and the view primary key must contain the primary key columns of the base table plus at most one other column."
+        ),
+    )
+    .link(VIEWS),
+    Keyword::new(
+        "CREATE FUNCTION",
+        "Creates a user-defined function (UDF) in Java, which can be used in `SELECT`, `INSERT` and `UPDATE` statements. User-defined functions must be enabled in `cassandra.yaml`.",
+    )
+    .link(FUNCTIONS),
+    Keyword::new(
+        "CREATE AGGREGATE",
+        "Creates a user-defined aggregate (UDA) from a state function, called for each row, and an optional final function computing the result.",
+    )
+    .link(FUNCTIONS),
+    Keyword::new(
+        "CREATE INDEX",
+        "Creates a secondary index on a column of a table, so the table can be queried by that column.",
+    )
+    .link(INDEXES),
+    Keyword::new(
+        "CREATE CUSTOM INDEX",
+        "Creates an index implemented by a custom class given with `USING`, e.g. a storage-attached index (`USING 'sai'`).",
+    )
+    .link(INDEXES),
+    Keyword::new(
+        "CREATE ROLE",
+        "Creates a role, which can log in, be granted permissions, and be granted to other roles.",
+    )
+    .link(SECURITY),
+    Keyword::new(
+        "CREATE USER",
+        concat!(
+            "`CREATE USER` is deprecated and included for backwards compatibility only. Authentication and authorization for DataStax Enterprise 5.0 and later are based on `ROLES`, and use `CREATE ROLE` instead.\n",
+            "`CREATE USER` defines a new database user account. By default users accounts do not have superuser status. Only a [superuser](https://docs.datastax.com/en/glossary/index.html#superuser) can issue `CREATE USER` requests. See [CREATE ROLE](https://docs.datastax.com/en/cql/hcd/reference/cql-commands/create-role.html) for more information about `SUPERUSER` and `NOSUPERUSER`.\n",
+            "User accounts are required for logging in under [internal authentication](https://docs.datastax.com/en/dse/6.9/securing/authorization-authentication/enable-unified-authentication.html) and authorization.\n",
+            "Enclose the user name in single quotation marks if it contains non-alphanumeric characters. You cannot recreate an existing user. To change the superuser status, password or hashed password, use [ALTER USER](https://docs.datastax.com/en/cql/hcd/reference/cql-commands/alter-user.html)."
+        ),
+    )
+    .link(SECURITY)
+    .deprecated(),
+    Keyword::new(
+        "CREATE TRIGGER",
+        "Attaches a trigger, a Java class on the servers' classpath, run on every write to a table.",
+    )
+    .link(TRIGGERS),
+    Keyword::new(
+        "ALTER KEYSPACE",
+        "Changes the replication or `durable_writes` of a keyspace. Run a repair after changing the replication so that data moves to its new replicas.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "ALTER TABLE",
+        "Adds, drops or renames columns of a table, or changes its options. Primary key columns can't be added or dropped.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "ALTER TYPE",
+        "Adds fields to a user-defined type, or renames them.",
+    )
+    .link(TYPES),
+    Keyword::new(
+        "ALTER MATERIALIZED VIEW",
+        "Changes the table options of a materialized view.",
+    )
+    .link(VIEWS),
+    Keyword::new(
+        "ALTER ROLE",
+        "Changes the password, login, superuser status or options of a role.",
+    )
+    .link(SECURITY),
+    Keyword::new(
+        "ALTER USER",
+        "Changes the password or superuser status of a user. Deprecated in favour of `ALTER ROLE`.",
+    )
+    .link(SECURITY)
+    .deprecated(),
+    Keyword::new(
+        "DROP KEYSPACE",
+        "Drops a keyspace and everything in it: tables, types, functions and their data.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "DROP TABLE",
+        "Drops a table and all its data. A snapshot is taken first if `auto_snapshot` is enabled.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "DROP TYPE",
+        "Drops a user-defined type. Fails while a table, function or other type uses it.",
+    )
+    .link(TYPES),
+    Keyword::new(
+        "DROP MATERIALIZED VIEW",
+        "Drops a materialized view, leaving its base table untouched.",
+    )
+    .link(VIEWS),
+    Keyword::new("DROP INDEX", "Drops a secondary index.").link(INDEXES),
+    Keyword::new(
+        "DROP FUNCTION",
+        "Drops a user-defined function. Overloads are told apart by their argument types, e.g. `DROP FUNCTION f(int)`.",
+    )
+    .link(FUNCTIONS),
+    Keyword::new("DROP AGGREGATE", "Drops a user-defined aggregate.").link(FUNCTIONS),
+    Keyword::new("DROP ROLE", "Drops a role, revoking it from the roles granted it.").link(SECURITY),
+    Keyword::new(
+        "DROP USER",
+        "Drops a user. Deprecated in favour of `DROP ROLE`.",
+    )
+    .link(SECURITY)
+    .deprecated(),
+    Keyword::new("DROP TRIGGER", "Detaches a trigger from a table.").link(TRIGGERS),
+    Keyword::new(
+        "SELECT",
+        "Reads rows of a table or materialized view.\n\n```cql\nSELECT columns FROM table WHERE relations [GROUP BY ...] [ORDER BY ...] [LIMIT n] [ALLOW FILTERING];\n```",
+    )
+    .link(DML),
+    Keyword::new(
+        "INSERT INTO",
+        "Writes a row. Like `UPDATE`, it overwrites the row if it exists, unless `IF NOT EXISTS` is given.\n\n```cql\nINSERT INTO table (columns) VALUES (values) [IF NOT EXISTS] [USING TTL n];\n```",
+    )
+    .link(DML),
+    Keyword::new(
+        "UPDATE",
+        "Writes columns of the rows selected by their primary key, creating them if they don't exist.\n\n```cql\nUPDATE table [USING TTL n] SET assignments WHERE relations [IF conditions];\n```",
+    )
+    .link(DML),
+    Keyword::new(
+        "DELETE",
+        "Deletes rows, or columns of rows, by writing tombstones.\n\n```cql\nDELETE [columns] FROM table [USING TIMESTAMP t] WHERE relations [IF conditions];\n```",
+    )
+    .link(DML),
+    Keyword::new(
+        "BEGIN BATCH",
+        "Groups writes so that they're all eventually applied, through the batch log. A batch spanning several partitions is slower than the writes run one by one.",
+    )
+    .link(DML),
+    Keyword::new(
+        "BEGIN UNLOGGED BATCH",
+        "Groups writes without the batch log: faster, but some writes may be applied and others not. Best for writes to a single partition.",
+    )
+    .link(DML),
+    Keyword::new(
+        "BEGIN COUNTER BATCH",
+        "Groups updates of counter columns, which can't be mixed with other writes.",
+    )
+    .link(DML),
+    Keyword::new("APPLY BATCH", "Ends a batch.").link(DML),
+    Keyword::new(
+        "TRUNCATE",
+        "Removes every row of a table, on every node. Fails unless all nodes are up.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "USE",
+        "Selects the keyspace of the tables, types and functions named without one in the next statements.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "GRANT",
+        "Grants a permission on a resource to a role, or a role to another role.\n\n```cql\nGRANT SELECT ON KEYSPACE ks TO analyst;\n```",
+    )
+    .link(SECURITY),
+    Keyword::new(
+        "REVOKE",
+        "Revokes a permission on a resource from a role, or a role from another role.",
+    )
+    .link(SECURITY),
+    Keyword::new(
+        "LIST ROLES",
+        "Lists the roles, or those granted to a role with `OF role`.",
+    )
+    .link(SECURITY),
+    Keyword::new(
+        "LIST USERS",
+        "Lists the users. Deprecated in favour of `LIST ROLES`.",
+    )
+    .link(SECURITY)
+    .deprecated(),
+    Keyword::new(
+        "LIST ALL PERMISSIONS",
+        "Lists the permissions granted, optionally on a resource with `ON` and to a role with `OF`.",
+    )
+    .link(SECURITY),
+];
+
+/// The keywords of the clauses within statements
+pub const CLAUSES: &[Keyword] = &[
+    Keyword::new(
+        "IF NOT EXISTS",
+        "Does nothing if the object or row already exists, instead of failing. On `INSERT`, makes it a lightweight transaction.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "IF EXISTS",
+        "Does nothing if the object or row doesn't exist, instead of failing. On `UPDATE` and `DELETE`, makes it a lightweight transaction.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "WITH",
+        "Sets the options of the object, joined with `AND`.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "PRIMARY KEY",
+        "The partition key, in parentheses if composite, followed by the clustering columns.\n\n```cql\nPRIMARY KEY ((tenant, id), created_at)\n```",
+    )
+    .link(DDL),
+    Keyword::new(
+        "CLUSTERING ORDER BY",
+        "The order in which the rows of a partition are stored, by clustering column.\n\n```cql\nWITH CLUSTERING ORDER BY (created_at DESC)\n```",
+    )
+    .link(DDL),
+    Keyword::new(
+        "COMPACT STORAGE",
+        "The storage format of Thrift tables. Removed in Cassandra 4.0.",
+    )
+    .link(DDL)
+    .deprecated(),
+    Keyword::new(
+        "STATIC",
+        "A column shared by all the rows of a partition.",
+    )
+    .link(DDL),
+    Keyword::new("ADD", "Adds columns, or fields of a type.").link(DDL),
+    Keyword::new("DROP", "Drops columns.").link(DDL),
+    Keyword::new(
+        "RENAME",
+        "Renames a primary key column of a table, or a field of a type: `RENAME old TO new`.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "FROM",
+        "The table or materialized view to read or delete from.",
+    )
+    .link(DML),
+    Keyword::new(
+        "WHERE",
+        "Restricts the rows by their primary key, or by indexed columns.",
+    )
+    .link(DML),
+    Keyword::new("AND", "Joins relations, options or `USING` parameters.").link(DML),
+    Keyword::new(
+        "DISTINCT",
+        "Returns each partition key once. Only partition key and static columns can be selected.",
+    )
+    .link(DML),
+    Keyword::new(
+        "JSON",
+        "Reads each row as a single JSON-encoded map, or writes a row from one with `INSERT INTO table JSON '...'`.",
+    )
+    .link(JSON),
+    Keyword::new("AS", "Names the selected column in the results.").link(DML),
+    Keyword::new(
+        "GROUP BY",
+        "Groups the rows by a prefix of the primary key, for aggregate functions.",
+    )
+    .link(DML),
+    Keyword::new(
+        "ORDER BY",
+        "Orders the rows of a partition by clustering columns, in their order or its reverse.",
+    )
+    .link(DML),
+    Keyword::new("ASC", "In ascending order.").link(DML),
+    Keyword::new("DESC", "In descending order.").link(DML),
+    Keyword::new(
+        "PER PARTITION LIMIT",
+        "The most rows returned per partition.",
+    )
+    .link(DML),
+    Keyword::new("LIMIT", "The most rows returned.").link(DML),
+    Keyword::new(
+        "ALLOW FILTERING",
+        "Runs the query even though it filters rows server-side, reading more than it returns. Slow on large tables.",
+    )
+    .link(DML),
+    Keyword::new("VALUES", "The values of the columns, in the order of the column list.").link(DML),
+    Keyword::new("SET", "The columns to update and their new values.").link(DML),
+    Keyword::new(
+        "IF",
+        "Only writes if the conditions hold, as a lightweight transaction.",
+    )
+    .link(DML),
+    Keyword::new(
+        "USING TTL",
+        "The time to live of the written values, in seconds.",
+    )
+    .link(DML),
+    Keyword::new(
+        "USING TIMESTAMP",
+        "The write timestamp, in microseconds since the Unix epoch.",
+    )
+    .link(DML),
+    Keyword::new(
+        "DEFAULT NULL",
+        "Leaves the columns missing from an `INSERT JSON` null. The default.",
+    )
+    .link(JSON),
+    Keyword::new(
+        "DEFAULT UNSET",
+        "Leaves the columns missing from an `INSERT JSON` unchanged.",
+    )
+    .link(JSON),
+    Keyword::new(
+        "ON",
+        "The table of an index or trigger, or the resource of a permission.",
+    )
+    .link(INDEXES),
+    Keyword::new("TO", "The role granted a permission or role.").link(SECURITY),
+    Keyword::new(
+        "OF",
+        "The role whose roles or permissions are listed.",
+    )
+    .link(SECURITY),
+    Keyword::new(
+        "NORECURSIVE",
+        "Only lists what was granted to the role itself, not through the roles granted to it.",
+    )
+    .link(SECURITY),
+    Keyword::new(
+        "CALLED ON NULL INPUT",
+        "The function is called when an argument is null.",
+    )
+    .link(FUNCTIONS),
+    Keyword::new(
+        "RETURNS NULL ON NULL INPUT",
+        "The function returns null without being called when an argument is null.",
+    )
+    .link(FUNCTIONS),
+    Keyword::new("RETURNS", "The type returned by the function.").link(FUNCTIONS),
+    Keyword::new(
+        "LANGUAGE",
+        "The language of the function's body, e.g. `java`.",
+    )
+    .link(FUNCTIONS),
+    Keyword::new("SFUNC", "The state function of an aggregate, called for each row.").link(FUNCTIONS),
+    Keyword::new("STYPE", "The type of the state of an aggregate.").link(FUNCTIONS),
+    Keyword::new(
+        "FINALFUNC",
+        "The function computing the result of an aggregate from its final state.",
+    )
+    .link(FUNCTIONS),
+    Keyword::new("INITCOND", "The initial state of an aggregate.").link(FUNCTIONS),
+    Keyword::new(
+        "OR REPLACE",
+        "Replaces the function or aggregate if it already exists.",
+    )
+    .link(FUNCTIONS),
+];
+
+/// The operators of relations, conditions and assignments
+pub const OPERATORS: &[Keyword] = &[
+    Keyword::new("=", "Equal to.").link(DML),
+    Keyword::new("!=", "Not equal to. Only valid in the conditions of `IF`.").link(DML),
+    Keyword::new(
+        "<",
+        "Less than. On clustering columns, or on tokens with `token(...)`.",
+    )
+    .link(DML),
+    Keyword::new("<=", "Less than or equal to.").link(DML),
+    Keyword::new(">", "Greater than.").link(DML),
+    Keyword::new(">=", "Greater than or equal to.").link(DML),
+    Keyword::new(
+        "IN",
+        "`column IN (value, ...)`\n\nMatches any of the values.",
+    )
+    .link(DML),
+    Keyword::new(
+        "CONTAINS",
+        "`column CONTAINS value`\n\nMatches collections holding the value. Needs an index on the collection's values.",
+    )
+    .link(DML),
+    Keyword::new(
+        "CONTAINS KEY",
+        "`column CONTAINS KEY key`\n\nMatches maps holding the key. Needs an index on the map's keys.",
+    )
+    .link(DML),
+    Keyword::new(
+        "LIKE",
+        "`column LIKE 'pattern%'`\n\nMatches text by pattern. Needs a SASI index.",
+    )
+    .link(DML),
+    Keyword::new(
+        "IS NOT NULL",
+        "Restricts the columns of a materialized view's primary key, which can't be null.",
+    )
+    .link(VIEWS),
+    Keyword::new(
+        "ANN OF",
+        "`ORDER BY column ANN OF [...] LIMIT n`\n\nOrders rows by the similarity of a vector column to the given vector, for approximate nearest neighbour search. Needs a storage-attached index on the column.",
+    )
+    .link(DML),
+];
+
+/// The options set with `WITH`, as `option = value`
+pub const OPTIONS: &[Keyword] = &[
+    Keyword::new(
+        "replication",
+        "How the keyspace is replicated: `{'class': 'NetworkTopologyStrategy', 'dc1': 3}`, with a replication factor per datacenter.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "durable_writes",
+        "Whether writes to the keyspace go through the commit log. Disabling it risks losing data.",
+    )
+    .link(DDL),
+    Keyword::new("comment", "A free-form description of the table.").link(DDL),
+    Keyword::new(
+        "compaction",
+        "The compaction strategy and its options, e.g. `{'class': 'UnifiedCompactionStrategy'}`.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "compression",
+        "How SSTables are compressed, e.g. `{'class': 'LZ4Compressor', 'chunk_length_in_kb': 16}`.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "caching",
+        "What is cached: `{'keys': 'ALL', 'rows_per_partition': 'NONE'}`.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "default_time_to_live",
+        "The TTL of values written without one, in seconds. `0` for none.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "gc_grace_seconds",
+        "How long tombstones are kept before being purged, in seconds. Nodes must be repaired more often than that, or deleted data may come back.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "bloom_filter_fp_chance",
+        "The false positive rate of the SSTables' bloom filters. Lower uses more memory.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "speculative_retry",
+        "When to query another replica while waiting for a slow one, e.g. `'99p'` or `'50ms'`.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "additional_write_policy",
+        "When to send writes to an additional replica, like `speculative_retry` for reads.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "memtable_flush_period_in_ms",
+        "How often memtables are flushed, in milliseconds. `0` to only flush when full.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "read_repair",
+        "`'BLOCKING'` to repair inconsistent replicas during reads, or `'NONE'`.",
+    )
+    .link(DDL),
+    Keyword::new(
+        "cdc",
+        "Whether changes to the table are kept for change data capture.",
+    )
+    .link(DDL),
+    Keyword::new("memtable", "The memtable configuration of the table.").link(DDL),
+    Keyword::new(
+        "superuser",
+        "Whether the role is a superuser, with every permission.",
+    )
+    .link(SECURITY),
+    Keyword::new("login", "Whether the role can log in.").link(SECURITY),
+    Keyword::new("password", "The password the role logs in with.").link(SECURITY),
+    Keyword::new(
+        "options",
+        "Options for a custom authenticator, as a map.",
+    )
+    .link(SECURITY),
+];
+
+/// The statements of DataStax Enterprise: DSE Search's indexes and row-level access control
+pub const DSE_STATEMENTS: &[Keyword] = &[
+    Keyword::new(
+        "CREATE SEARCH INDEX",
+        "Creates a DSE Search index on a table, so that it can be queried with `solr_query`.\n\n```cql\nCREATE SEARCH INDEX ON ks.users WITH COLUMNS name, email;\n```",
+    ),
+    Keyword::new(
+        "ALTER SEARCH INDEX CONFIG",
+        "Changes the pending configuration of a search index, applied by `RELOAD SEARCH INDEX`.",
+    ),
+    Keyword::new(
+        "ALTER SEARCH INDEX SCHEMA",
+        "Changes the pending schema of a search index, e.g. adding a field, applied by `RELOAD SEARCH INDEX`.",
+    ),
+    Keyword::new(
+        "RELOAD SEARCH INDEX",
+        "Applies the pending configuration and schema of a search index.",
+    ),
+    Keyword::new(
+        "REBUILD SEARCH INDEX",
+        "Reindexes the data of a table in its search index.",
+    ),
+    Keyword::new(
+        "COMMIT SEARCH INDEX",
+        "Makes the writes indexed so far searchable.",
+    ),
+    Keyword::new("DROP SEARCH INDEX", "Drops the search index of a table."),
+    Keyword::new(
+        "RESTRICT",
+        "`RESTRICT permission ON resource TO role`\n\nDenies a permission on a resource to a role, even if granted through another role.",
+    ),
+    Keyword::new(
+        "UNRESTRICT",
+        "`UNRESTRICT permission ON resource FROM role`\n\nLifts a restriction of a role.",
+    ),
+    Keyword::new(
+        "RESTRICT ROWS",
+        "`RESTRICT ROWS ON table USING column`\n\nSets the text column of the partition key matched against the filters granted with `GRANT ... ON 'filter' ROWS IN table`, for row-level access control.",
+    ),
+    Keyword::new(
+        "UNRESTRICT ROWS",
+        "`UNRESTRICT ROWS ON table`\n\nRemoves the row-level access control of a table.",
+    ),
+];
+
+/// The clauses of DataStax Enterprise
+pub const DSE_CLAUSES: &[Keyword] = &[
+    Keyword::new(
+        "VERTEX LABEL",
+        "`WITH VERTEX LABEL name`\n\nMakes the table the vertices of a label of the keyspace's DSE Graph, the keyspace having `graph_engine = 'Core'`.",
+    ),
+    Keyword::new(
+        "EDGE LABEL",
+        "`WITH EDGE LABEL name FROM person(id) TO software(id)`\n\nMakes the table the edges of a label between two vertex labels of the keyspace's DSE Graph.",
+    ),
+];
+
+/// The clauses of ScyllaDB
+pub const SCYLLA_CLAUSES: &[Keyword] = &[
+    Keyword::new(
+        "BYPASS CACHE",
+        "`SELECT ... BYPASS CACHE`\n\nReads without going through the row cache nor filling it, for scans that would evict the rows other queries read.",
+    ),
+    Keyword::new(
+        "USING TIMEOUT",
+        "`USING TIMEOUT 500ms`\n\nHow long the statement may take to run, overriding the server's timeout.",
+    ),
+];
+
+/// The table options of ScyllaDB
+pub const SCYLLA_OPTIONS: &[Keyword] = &[
+    Keyword::new(
+        "per_partition_rate_limit",
+        "The most reads and writes per second each partition takes, beyond which they're rejected: `{'max_reads_per_second': 100, 'max_writes_per_second': 100}`.",
+    ),
+    Keyword::new(
+        "tombstone_gc",
+        "When tombstones may be purged: `{'mode': 'repair'}` once repaired rather than after `gc_grace_seconds`, or `'timeout'`, `'immediate'` or `'disabled'`.",
+    ),
+    Keyword::new(
+        "paxos_grace_seconds",
+        "How long the state of lightweight transactions is kept, in seconds.",
+    ),
+    Keyword::new(
+        "synchronous_updates",
+        "Whether the writes to the base table of a materialized view wait for the view to be updated.",
+    ),
+];
+
+/// Every keyword, statements first
+pub fn all() -> impl Iterator<Item = &'static Keyword> {
+    STATEMENTS
+        .iter()
+        .chain(DSE_STATEMENTS)
+        .chain(CLAUSES)
+        .chain(DSE_CLAUSES)
+        .chain(SCYLLA_CLAUSES)
+        .chain(OPERATORS)
+        .chain(OPTIONS)
+        .chain(SCYLLA_OPTIONS)
+}
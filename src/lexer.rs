@@ -0,0 +1,553 @@
+//! A small hand-written CQL tokenizer.
+//!
+//! Tree-sitter gives us a full syntax tree, but most editor features run while the statement
+//! under the cursor is incomplete, where the tree degrades into ERROR nodes. The token stream
+//! produced here stays meaningful no matter how broken the input is, which makes it the
+//! foundation for context detection and the lightweight statement analysis.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Keywords and unquoted identifiers, which CQL doesn't lexically distinguish
+    Word,
+    /// `"Identifier"`
+    QuotedIdentifier,
+    /// `'text'` or `$$text$$`
+    String,
+    /// `42`, `1.5`, `3e10`
+    Number,
+    /// `0xCAFE`
+    Hex,
+    /// `123e4567-e89b-12d3-a456-426614174000`
+    Uuid,
+    /// `1h30m`, `P1D`
+    Duration,
+    /// `?`
+    BindMarker,
+    /// `:name`
+    NamedBindMarker,
+    /// Operators and punctuation, e.g. `(`, `,`, `<=`
+    Punctuation,
+    /// `-- ...`, `// ...` and `/* ... */`
+    Comment,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Byte offset of the first byte of the token
+    pub start: usize,
+    /// Byte offset one past the last byte of the token
+    pub end: usize,
+    /// Whether no other token comes before this one on its line
+    pub first_on_line: bool,
+}
+
+impl Token<'_> {
+    /// Whether this is an unquoted word equal to `keyword`, ignoring case
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    pub fn is_punctuation(&self, punctuation: &str) -> bool {
+        self.kind == TokenKind::Punctuation && self.text == punctuation
+    }
+
+    pub fn is_identifier(&self) -> bool {
+        matches!(self.kind, TokenKind::Word | TokenKind::QuotedIdentifier)
+    }
+
+    /// Returns the identifier this token denotes, following CQL's case rules: unquoted
+    /// identifiers are case-insensitive and folded to lower case, quoted ones are taken as-is.
+    pub fn identifier(&self) -> Option<String> {
+        match self.kind {
+            TokenKind::Word => Some(self.text.to_ascii_lowercase()),
+            TokenKind::QuotedIdentifier => Some(unquote(self.text, '"')),
+            _ => None,
+        }
+    }
+
+    /// Returns the content of a string literal with its quotes removed and escapes resolved
+    pub fn string_value(&self) -> Option<String> {
+        if self.kind != TokenKind::String {
+            return None;
+        }
+
+        match self.text.strip_prefix("$$") {
+            Some(rest) => Some(rest.strip_suffix("$$").unwrap_or(rest).to_string()),
+            None => Some(unquote(self.text, '\'')),
+        }
+    }
+}
+
+/// Strips the surrounding `quote`s of `text` and collapses doubled quotes
+fn unquote(text: &str, quote: char) -> String {
+    let inner = text.strip_prefix(quote).unwrap_or(text);
+    let inner = match inner.strip_suffix(quote) {
+        Some(stripped) if !inner.is_empty() => stripped,
+        _ => inner,
+    };
+
+    inner.replace(&format!("{quote}{quote}"), &quote.to_string())
+}
+
+/// The keywords that can't be used as identifiers unless double-quoted
+pub const RESERVED_KEYWORDS: &[&str] = &[
+    "ADD",
+    "ALLOW",
+    "ALTER",
+    "AND",
+    "APPLY",
+    "ASC",
+    "AUTHORIZE",
+    "BATCH",
+    "BEGIN",
+    "BY",
+    "COLUMNFAMILY",
+    "CREATE",
+    "DELETE",
+    "DESC",
+    "DESCRIBE",
+    "DROP",
+    "ENTRIES",
+    "EXECUTE",
+    "FROM",
+    "FULL",
+    "GRANT",
+    "IF",
+    "IN",
+    "INDEX",
+    "INFINITY",
+    "INSERT",
+    "INTO",
+    "IS",
+    "KEYSPACE",
+    "LIMIT",
+    "MATERIALIZED",
+    "MODIFY",
+    "NAN",
+    "NORECURSIVE",
+    "NOT",
+    "NULL",
+    "OF",
+    "ON",
+    "OR",
+    "ORDER",
+    "PRIMARY",
+    "RENAME",
+    "REPLACE",
+    "REVOKE",
+    "SCHEMA",
+    "SELECT",
+    "SET",
+    "TABLE",
+    "TO",
+    "TOKEN",
+    "TRUNCATE",
+    "UNLOGGED",
+    "UNSET",
+    "UPDATE",
+    "USE",
+    "USING",
+    "VIEW",
+    "WHERE",
+    "WITH",
+];
+
+/// Whether `word` is a reserved keyword, in any case
+pub fn is_reserved(word: &str) -> bool {
+    RESERVED_KEYWORDS
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+/// Returns `name` as it must be written in CQL to denote the identifier `name`: as-is when it's
+/// a valid lower case unquoted identifier, double-quoted otherwise, e.g. when it's upper case or
+/// a reserved keyword
+pub fn quote_identifier(name: &str) -> String {
+    let unquoted = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !is_reserved(name);
+
+    match unquoted {
+        true => name.to_string(),
+        false => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+const DURATION_UNITS: &[&str] = &["y", "mo", "w", "d", "h", "ms", "m", "s", "us", "µs", "ns"];
+
+/// Returns the length of the UUID starting at the beginning of `text`, if any
+fn uuid_len(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let groups = [8, 4, 4, 4, 12];
+    let mut i = 0;
+
+    for (n, group) in groups.iter().enumerate() {
+        if n > 0 {
+            if bytes.get(i) != Some(&b'-') {
+                return None;
+            }
+            i += 1;
+        }
+
+        for _ in 0..*group {
+            if !bytes.get(i)?.is_ascii_hexdigit() {
+                return None;
+            }
+            i += 1;
+        }
+    }
+
+    match bytes.get(i) {
+        Some(b) if b.is_ascii_alphanumeric() || *b == b'_' => None,
+        _ => Some(i),
+    }
+}
+
+/// Returns the length of the duration literal (e.g. `1h30m`) starting at the beginning of
+/// `text`, if any
+fn duration_len(text: &str) -> Option<usize> {
+    let mut i = 0;
+    let mut groups = 0;
+
+    loop {
+        let digits = text[i..].bytes().take_while(u8::is_ascii_digit).count();
+
+        if digits == 0 {
+            break;
+        }
+
+        let rest = &text[i + digits..];
+        let unit = DURATION_UNITS.iter().find(|unit| {
+            rest.get(..unit.len())
+                .is_some_and(|head| head.eq_ignore_ascii_case(unit))
+        })?;
+
+        i += digits + unit.len();
+        groups += 1;
+    }
+
+    let continues_word = text[i..]
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+    (groups > 0 && !continues_word).then_some(i)
+}
+
+/// Returns the length of the ISO 8601 duration (e.g. `P1DT2H`) starting at the beginning of
+/// `text`, if any
+fn iso_duration_len(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+
+    if bytes.first() != Some(&b'P') {
+        return None;
+    }
+
+    let len = 1 + bytes[1..]
+        .iter()
+        .take_while(|b| b.is_ascii_alphanumeric())
+        .count();
+    let body = &text[1..len];
+
+    let valid = body.bytes().any(|b| b.is_ascii_digit())
+        && body.ends_with(|c: char| c.is_ascii_alphabetic())
+        && body
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b"YMWDTHS".contains(&b));
+
+    valid.then_some(len)
+}
+
+fn is_word_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_word_continue(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Tokenizes `text`, including comments
+pub fn tokenize_with_comments(text: &str) -> Vec<Token<'_>> {
+    let bytes = text.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &text[i..];
+        let c = rest.chars().next().unwrap();
+
+        if c.is_whitespace() {
+            i += c.len_utf8();
+            continue;
+        }
+
+        let (kind, len) = if rest.starts_with("--") || rest.starts_with("//") {
+            (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            let len = body.find("*/").map_or(rest.len(), |end| end + 4);
+            (TokenKind::Comment, len)
+        } else if c == '\'' || c == '"' {
+            // Quotes are escaped by doubling them, so a doubled quote doesn't end the token
+            let mut end = 1;
+            loop {
+                match rest[end..].find(c) {
+                    Some(found) if rest[end + found + 1..].starts_with(c) => end += found + 2,
+                    Some(found) => break end += found + 1,
+                    None => break end = rest.len(),
+                }
+            }
+
+            let kind = match c {
+                '\'' => TokenKind::String,
+                _ => TokenKind::QuotedIdentifier,
+            };
+
+            (kind, end)
+        } else if let Some(body) = rest.strip_prefix("$$") {
+            let len = body.find("$$").map_or(rest.len(), |end| end + 4);
+            (TokenKind::String, len)
+        } else if let Some(len) = uuid_len(rest) {
+            (TokenKind::Uuid, len)
+        } else if rest.starts_with("0x") || rest.starts_with("0X") {
+            let len = 2 + rest[2..].bytes().take_while(u8::is_ascii_hexdigit).count();
+            (TokenKind::Hex, len)
+        } else if let Some(len) = duration_len(rest) {
+            (TokenKind::Duration, len)
+        } else if c.is_ascii_digit() {
+            let mut len = rest.bytes().take_while(u8::is_ascii_digit).count();
+
+            if rest[len..].starts_with('.') {
+                len += 1 + rest[len + 1..]
+                    .bytes()
+                    .take_while(u8::is_ascii_digit)
+                    .count();
+            }
+
+            if rest[len..].starts_with(['e', 'E']) {
+                let sign = usize::from(rest[len + 1..].starts_with(['+', '-']));
+                let exponent = rest[len + 1 + sign..]
+                    .bytes()
+                    .take_while(u8::is_ascii_digit)
+                    .count();
+
+                if exponent > 0 {
+                    len += 1 + sign + exponent;
+                }
+            }
+
+            (TokenKind::Number, len)
+        } else if let Some(len) = iso_duration_len(rest) {
+            (TokenKind::Duration, len)
+        } else if is_word_start(c) {
+            let len = rest
+                .char_indices()
+                .find(|(_, c)| !is_word_continue(*c))
+                .map_or(rest.len(), |(idx, _)| idx);
+            (TokenKind::Word, len)
+        } else if c == '?' {
+            (TokenKind::BindMarker, 1)
+        } else if c == ':' && rest[1..].starts_with(is_word_start) {
+            let len = 1 + rest[1..]
+                .char_indices()
+                .find(|(_, c)| !is_word_continue(*c))
+                .map_or(rest.len() - 1, |(idx, _)| idx);
+            (TokenKind::NamedBindMarker, len)
+        } else if c == ':' && rest[1..].starts_with('"') {
+            let len = 2 + rest[2..].find('"').map_or(rest.len() - 2, |end| end + 1);
+            (TokenKind::NamedBindMarker, len)
+        } else if ["<=", ">=", "!="].iter().any(|op| rest.starts_with(op)) {
+            (TokenKind::Punctuation, 2)
+        } else if "(),;.[]{}*=<>+-/%:!".contains(c) {
+            (TokenKind::Punctuation, 1)
+        } else {
+            (TokenKind::Unknown, c.len_utf8())
+        };
+
+        let first_on_line = tokens
+            .last()
+            .is_none_or(|prev: &Token| text[prev.end..i].contains('\n'));
+
+        tokens.push(Token {
+            kind,
+            text: &text[i..i + len],
+            start: i,
+            end: i + len,
+            first_on_line,
+        });
+
+        i += len;
+    }
+
+    tokens
+}
+
+/// Tokenizes `text`, skipping comments
+pub fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = tokenize_with_comments(text);
+    tokens.retain(|token| token.kind != TokenKind::Comment);
+
+    // The line breaks before a token may have been inside or after the comments skipped
+    for i in 0..tokens.len() {
+        tokens[i].first_on_line = i == 0 || text[tokens[i - 1].end..tokens[i].start].contains('\n');
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The kind and text of the tokens of `text`, comments included
+    fn lex(text: &str) -> Vec<(TokenKind, &str)> {
+        tokenize_with_comments(text)
+            .iter()
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    #[test]
+    fn literals() {
+        use TokenKind::*;
+
+        assert_eq!(
+            lex("'it''s' $$a 'b'$$ \"Name\" 42 -1.5 3e10 2.5E-3 0xCAFE"),
+            [
+                (String, "'it''s'"),
+                (String, "$$a 'b'$$"),
+                (QuotedIdentifier, "\"Name\""),
+                (Number, "42"),
+                (Punctuation, "-"),
+                (Number, "1.5"),
+                (Number, "3e10"),
+                (Number, "2.5E-3"),
+                (Hex, "0xCAFE"),
+            ]
+        );
+        assert_eq!(
+            lex("123e4567-e89b-12d3-a456-426614174000 1h30m 2mo 5µs P1DT2H P"),
+            [
+                (Uuid, "123e4567-e89b-12d3-a456-426614174000"),
+                (Duration, "1h30m"),
+                (Duration, "2mo"),
+                (Duration, "5µs"),
+                (Duration, "P1DT2H"),
+                (Word, "P"),
+            ]
+        );
+    }
+
+    #[test]
+    fn bind_markers() {
+        use TokenKind::*;
+
+        assert_eq!(
+            lex("a = ? AND b IN :ids AND c = :\"Quoted Name\" AND d = :"),
+            [
+                (Word, "a"),
+                (Punctuation, "="),
+                (BindMarker, "?"),
+                (Word, "AND"),
+                (Word, "b"),
+                (Word, "IN"),
+                (NamedBindMarker, ":ids"),
+                (Word, "AND"),
+                (Word, "c"),
+                (Punctuation, "="),
+                (NamedBindMarker, ":\"Quoted Name\""),
+                (Word, "AND"),
+                (Word, "d"),
+                (Punctuation, "="),
+                (Punctuation, ":"),
+            ]
+        );
+    }
+
+    #[test]
+    fn punctuation_and_unknown() {
+        use TokenKind::*;
+
+        assert_eq!(
+            lex("a<=b!=c>d<e;#"),
+            [
+                (Word, "a"),
+                (Punctuation, "<="),
+                (Word, "b"),
+                (Punctuation, "!="),
+                (Word, "c"),
+                (Punctuation, ">"),
+                (Word, "d"),
+                (Punctuation, "<"),
+                (Word, "e"),
+                (Punctuation, ";"),
+                (Unknown, "#"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_tokens_run_to_the_end() {
+        use TokenKind::*;
+
+        assert_eq!(lex("'open"), [(String, "'open")]);
+        assert_eq!(lex("$$open"), [(String, "$$open")]);
+        assert_eq!(lex("\"open"), [(QuotedIdentifier, "\"open")]);
+        assert_eq!(lex("/* open"), [(Comment, "/* open")]);
+    }
+
+    #[test]
+    fn comments_and_lines() {
+        let text = "SELECT -- a\n/* b\n */ a // c\nFROM t; /* d */ WHERE";
+
+        assert_eq!(
+            lex(text)
+                .iter()
+                .filter(|(kind, _)| *kind == TokenKind::Comment)
+                .map(|(_, text)| *text)
+                .collect::<Vec<_>>(),
+            ["-- a", "/* b\n */", "// c", "/* d */"]
+        );
+
+        // A line break in a skipped comment still puts the next token first on its line
+        let first = tokenize(text)
+            .iter()
+            .filter(|token| token.first_on_line)
+            .map(|token| token.text)
+            .collect::<Vec<_>>();
+
+        assert_eq!(first, ["SELECT", "a", "FROM"]);
+    }
+
+    #[test]
+    fn offsets_are_bytes() {
+        let tokens = tokenize("é = 'ü'");
+
+        assert_eq!((tokens[0].start, tokens[0].end), (0, 2));
+        assert_eq!((tokens[2].start, tokens[2].end), (5, 9));
+    }
+
+    #[test]
+    fn identifiers_and_strings() {
+        let tokens = tokenize("MyTable \"My\"\"Table\" 'a''b' $$c$$");
+
+        assert_eq!(tokens[0].identifier().as_deref(), Some("mytable"));
+        assert_eq!(tokens[1].identifier().as_deref(), Some("My\"Table"));
+        assert_eq!(tokens[2].string_value().as_deref(), Some("a'b"));
+        assert_eq!(tokens[3].string_value().as_deref(), Some("c"));
+        assert_eq!(tokens[2].identifier(), None);
+    }
+
+    #[test]
+    fn quoted_identifiers() {
+        assert_eq!(quote_identifier("users"), "users");
+        assert_eq!(quote_identifier("Users"), "\"Users\"");
+        assert_eq!(quote_identifier("order"), "\"order\"");
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+        assert_eq!(quote_identifier("1st"), "\"1st\"");
+    }
+}
@@ -0,0 +1,72 @@
+//! The analysis of CQL behind the language server: documents and their statements, the schema
+//! they define, completion, diagnostics, formatting and the other editor features, and the
+//! connection to clusters. The `cql-lsp` binary serves these over the Language Server
+//! Protocol or checks files with `cql-lsp check`, and other tools (CI linters, migration tools, editors embedding the analysis) can
+//! call them directly.
+
+mod alter_table;
+mod analysis;
+mod assignment;
+mod batch;
+pub mod bind_markers;
+pub mod bulk;
+pub mod capabilities;
+pub mod check;
+pub mod cluster;
+mod clustering_order;
+pub mod code_actions;
+pub mod code_lens;
+pub mod completion;
+pub mod config;
+mod counters;
+pub mod cql_type;
+mod data_model;
+mod datacenter;
+mod datetime;
+pub mod diagnostics;
+mod directives;
+pub mod document;
+pub mod document_links;
+mod duplicates;
+mod duration;
+mod features;
+mod fixtures;
+pub mod formatting;
+mod frozen;
+mod function;
+pub mod generate;
+mod grant;
+mod group_by;
+pub mod guardrails;
+pub mod hover;
+mod identifier_case;
+mod index;
+pub mod inlay_hints;
+mod json;
+mod keyword_case;
+pub mod keywords;
+pub mod lexer;
+mod literal;
+pub mod lsp_ext;
+mod materialized_view;
+pub mod migration;
+pub mod notebook;
+pub mod parse;
+pub mod parser;
+mod progress;
+mod reserved;
+#[cfg(feature = "cluster")]
+pub mod results;
+pub mod schema;
+pub mod schema_diff;
+pub mod semantic_tokens;
+pub mod server;
+pub mod statement;
+mod static_columns;
+mod system_keyspaces;
+pub mod target;
+mod tombstones;
+pub mod type_definition;
+pub mod uri;
+mod using;
+pub mod workspace;
@@ -0,0 +1,517 @@
+//! Collection, tuple and user-defined type literals, checked against the type of the column
+//! they're a value of: the brackets must be those of the type, and each element a value of the
+//! element type.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    cql_type::CqlType,
+    diagnostics::Problem,
+    lexer::{Token, TokenKind},
+    schema::{Schema, Table, UserType},
+    statement::{Statement, matching_bracket, split_comma_ranges},
+};
+
+/// Whether the single-token literal `token` is a value of the native type `name`, or `None`
+/// when it can't be told, e.g. for a bind marker or `null`
+fn fits_native(token: &Token, name: &str) -> Option<bool> {
+    let fits = match token.kind {
+        TokenKind::String => matches!(
+            name,
+            "ascii" | "text" | "varchar" | "inet" | "date" | "time" | "timestamp" | "duration"
+        ),
+        TokenKind::Number if token.text.contains(['.', 'e', 'E']) => {
+            matches!(name, "float" | "double" | "decimal")
+        }
+        TokenKind::Number => matches!(
+            name,
+            "tinyint"
+                | "smallint"
+                | "int"
+                | "bigint"
+                | "varint"
+                | "counter"
+                | "float"
+                | "double"
+                | "decimal"
+                | "timestamp"
+                | "date"
+                | "time"
+        ),
+        TokenKind::Uuid => matches!(name, "uuid" | "timeuuid"),
+        TokenKind::Hex => name == "blob",
+        TokenKind::Duration => name == "duration",
+        TokenKind::Word if token.is_keyword("true") || token.is_keyword("false") => {
+            name == "boolean"
+        }
+        TokenKind::Word if token.is_keyword("NaN") || token.is_keyword("Infinity") => {
+            matches!(name, "float" | "double")
+        }
+        _ => return None,
+    };
+
+    Some(fits)
+}
+
+/// How a literal of `data_type` is written, for messages
+fn literal_form(data_type: &CqlType) -> &'static str {
+    match data_type.unfrozen() {
+        CqlType::List(_) | CqlType::Vector(..) => "`[...]`",
+        CqlType::Set(_) => "`{...}`",
+        CqlType::Map(..) => "`{key: value, ...}`",
+        CqlType::Tuple(_) => "`(...)`",
+        CqlType::UserDefined { .. } => "`{field: value, ...}`",
+        CqlType::Native(_) | CqlType::Frozen(_) | CqlType::Custom(_) => "a single value",
+    }
+}
+
+/// Splits a map entry or a field of a user-defined type literal at its top-level `:`
+fn split_entry<'a, 'b>(tokens: &'a [Token<'b>]) -> Option<(&'a [Token<'b>], &'a [Token<'b>])> {
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token.text {
+            "(" | "[" | "{" if token.kind == TokenKind::Punctuation => depth += 1,
+            ")" | "]" | "}" if token.kind == TokenKind::Punctuation => depth -= 1,
+            ":" if token.kind == TokenKind::Punctuation && depth == 0 => {
+                return Some((&tokens[..i], &tokens[i + 1..]));
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Checks values against types, resolving user-defined types in the schema
+struct Checker<'a> {
+    schema: &'a Schema,
+    /// The keyspace unqualified user-defined types are looked up in
+    keyspace: &'a str,
+    problems: Vec<Problem>,
+}
+
+impl Checker<'_> {
+    fn error(&mut self, tokens: &[Token], message: String) {
+        let (Some(first), Some(last)) = (tokens.first(), tokens.last()) else {
+            return;
+        };
+
+        self.problems.push(Problem {
+            span: first.start..last.end,
+            severity: DiagnosticSeverity::ERROR,
+            message,
+            lint: None,
+        });
+    }
+
+    /// Checks the elements of the literal between brackets `inner` against `element`
+    fn check_elements(&mut self, inner: &[Token], element: &CqlType) {
+        for part in split_comma_ranges(inner) {
+            self.check(&inner[part], element);
+        }
+    }
+
+    /// Checks the value made of `tokens` against `data_type`. Values other than literals, such
+    /// as function calls, arithmetic or bind markers, aren't checked.
+    fn check(&mut self, tokens: &[Token], data_type: &CqlType) {
+        let Some(open) = tokens.first() else {
+            return;
+        };
+
+        let data_type = data_type.unfrozen();
+
+        let bracketed = open.kind == TokenKind::Punctuation
+            && matches!(open.text, "[" | "{" | "(")
+            && matching_bracket(tokens, 0) == Some(tokens.len() - 1);
+
+        if !bracketed {
+            let fits = match (tokens, data_type) {
+                ([token], CqlType::Native(name)) => fits_native(token, name),
+                ([_], CqlType::Custom(_)) => None,
+                // A scalar literal given for a collection, tuple or user-defined type
+                ([token], _) => fits_native(token, "").map(|_| false),
+                _ => None,
+            };
+
+            if fits == Some(false) {
+                self.error(
+                    tokens,
+                    format!(
+                        "Expected a value of type `{data_type}`, found `{}`",
+                        open.text
+                    ),
+                );
+            }
+
+            return;
+        }
+
+        let inner = &tokens[1..tokens.len() - 1];
+
+        match (open.text, data_type) {
+            ("[", CqlType::List(element) | CqlType::Vector(element, _)) => {
+                self.check_elements(inner, element);
+            }
+            ("{", CqlType::Set(element)) => {
+                for part in split_comma_ranges(inner) {
+                    let element_tokens = &inner[part];
+
+                    match split_entry(element_tokens) {
+                        Some(_) => self.error(
+                            element_tokens,
+                            format!(
+                                "`{data_type}` is a set: its elements aren't `key: value` entries"
+                            ),
+                        ),
+                        None => self.check(element_tokens, element),
+                    }
+                }
+            }
+            ("{", CqlType::Map(key, value)) => {
+                for part in split_comma_ranges(inner) {
+                    let entry = &inner[part];
+
+                    match split_entry(entry) {
+                        Some((entry_key, entry_value)) => {
+                            self.check(entry_key, key);
+                            self.check(entry_value, value);
+                        }
+                        None => self.error(
+                            entry,
+                            format!("`{data_type}` is a map: its entries are written `key: value`"),
+                        ),
+                    }
+                }
+            }
+            ("{", CqlType::UserDefined { .. }) => {
+                let Some(user_type) = self.schema.resolve_user_type(data_type, self.keyspace)
+                else {
+                    return;
+                };
+
+                self.check_fields(inner, user_type);
+            }
+            ("(", CqlType::Tuple(elements)) => {
+                let parts = split_comma_ranges(inner);
+
+                if parts.len() != elements.len() {
+                    self.error(
+                        tokens,
+                        format!(
+                            "`{data_type}` has {} elements, but {} are given",
+                            elements.len(),
+                            parts.len()
+                        ),
+                    );
+                }
+
+                for (part, element) in parts.into_iter().zip(elements) {
+                    self.check(&inner[part], element);
+                }
+            }
+            // `(...)` may be a parenthesized expression
+            ("(", _) => {}
+            _ => self.error(
+                tokens,
+                format!(
+                    "Expected a value of type `{data_type}`, written {}",
+                    literal_form(data_type)
+                ),
+            ),
+        }
+    }
+
+    /// Checks the `field: value` entries of a literal of `user_type`
+    fn check_fields(&mut self, inner: &[Token], user_type: &UserType) {
+        for part in split_comma_ranges(inner) {
+            let entry = &inner[part];
+
+            let Some(([name], value)) = split_entry(entry) else {
+                self.error(
+                    entry,
+                    format!(
+                        "`{}` is a user-defined type: its fields are written `field: value`",
+                        user_type.name
+                    ),
+                );
+                continue;
+            };
+
+            let Some(field) = name.identifier().and_then(|name| user_type.field(&name)) else {
+                self.error(
+                    std::slice::from_ref(name),
+                    format!("Type `{}` has no field `{}`", user_type.name, name.text),
+                );
+                continue;
+            };
+
+            self.check(value, &field.data_type);
+        }
+    }
+}
+
+/// The value of each column assigned a value by an `INSERT` or the `SET` of an `UPDATE`, as the
+/// column name and the value's tokens
+fn assignments<'a, 'b>(statement: &'a Statement<'b>) -> Vec<(String, &'a [Token<'b>])> {
+    let tokens = statement.tokens;
+
+    if let Some(insert) = statement.insert_values() {
+        return insert
+            .columns
+            .iter()
+            .zip(&insert.values)
+            .filter_map(|(column, value)| {
+                Some((
+                    tokens.get(column.start)?.identifier()?,
+                    &tokens[value.clone()],
+                ))
+            })
+            .collect();
+    }
+
+    let (Some(set), Some(r#where)) = (
+        statement.find_keyword("SET"),
+        statement.find_keyword("WHERE"),
+    ) else {
+        return vec![];
+    };
+
+    let list = &tokens[set + 1..r#where.max(set + 1)];
+
+    split_comma_ranges(list)
+        .into_iter()
+        .filter_map(|part| match &list[part] {
+            [column, equals, value @ ..] if equals.is_punctuation("=") => {
+                Some((column.identifier()?, value))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks the literals assigned to the columns of `table` by an `INSERT` or `UPDATE`
+pub fn check(statement: &Statement, table: &Table, schema: &Schema) -> Vec<Problem> {
+    let mut checker = Checker {
+        schema,
+        keyspace: &table.keyspace,
+        problems: vec![],
+    };
+
+    for (column, value) in assignments(statement) {
+        if let Some(column) = table.column(&column) {
+            checker.check(value, &column.data_type);
+        }
+    }
+
+    checker.problems
+}
+
+/// Checks the value made of `tokens` against `data_type`, unqualified user-defined types being
+/// looked up in `keyspace`
+pub fn check_value(
+    tokens: &[Token],
+    data_type: &CqlType,
+    schema: &Schema,
+    keyspace: &str,
+) -> Vec<Problem> {
+    let mut checker = Checker {
+        schema,
+        keyspace,
+        problems: vec![],
+    };
+
+    checker.check(tokens, data_type);
+    checker.problems
+}
+
+/// When the name of a field of a user-defined type literal is expected at the end of `tokens`,
+/// the beginning of a value of `data_type`, returns the type with the fields already given.
+/// `keyspace` is the one unqualified user-defined types are looked up in.
+pub fn expected_field<'a>(
+    tokens: &[Token],
+    data_type: &'a CqlType,
+    schema: &'a Schema,
+    keyspace: &str,
+) -> Option<(&'a UserType, Vec<String>)> {
+    let open = tokens.first()?;
+
+    if open.kind != TokenKind::Punctuation
+        || !matches!(open.text, "[" | "{" | "(")
+        || matching_bracket(tokens, 0).is_some()
+    {
+        return None;
+    }
+
+    let inner = &tokens[1..];
+    let parts = split_comma_ranges(inner);
+    // After a comma, the last part is the empty element yet to be typed
+    let current = parts.last().map_or(&[][..], |part| &inner[part.clone()]);
+
+    let data_type = data_type.unfrozen();
+
+    match data_type {
+        CqlType::List(element) | CqlType::Set(element) | CqlType::Vector(element, _) => {
+            expected_field(current, element, schema, keyspace)
+        }
+        CqlType::Map(key, value) => match split_entry(current) {
+            Some((_, entry_value)) => expected_field(entry_value, value, schema, keyspace),
+            None => expected_field(current, key, schema, keyspace),
+        },
+        CqlType::Tuple(elements) => {
+            let element = elements.get(parts.len().saturating_sub(1))?;
+            expected_field(current, element, schema, keyspace)
+        }
+        CqlType::UserDefined { .. } => {
+            let user_type = schema.resolve_user_type(data_type, keyspace)?;
+
+            match split_entry(current) {
+                Some(([name], value)) => {
+                    let field = user_type.field(&name.identifier()?)?;
+                    expected_field(value, &field.data_type, schema, keyspace)
+                }
+                Some(_) => None,
+                None if current.is_empty() => {
+                    let given = parts
+                        .into_iter()
+                        .filter_map(|part| inner[part].first()?.identifier())
+                        .collect();
+
+                    Some((user_type, given))
+                }
+                None => None,
+            }
+        }
+        CqlType::Native(_) | CqlType::Frozen(_) | CqlType::Custom(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer, statement};
+
+    const SCHEMA: &str = "
+        CREATE TYPE ks.address (street text, zip int);
+        CREATE TABLE ks.users (id int PRIMARY KEY, home frozen<address>);
+    ";
+
+    fn schema() -> Schema {
+        let tokens = lexer::tokenize(SCHEMA);
+        let mut schema = Schema::default();
+        let mut keyspace = None;
+
+        for statement in statement::split(&tokens, Default::default()) {
+            schema.apply(&statement, &mut keyspace);
+        }
+
+        schema
+    }
+
+    /// The messages of the problems of `value` as a value of the type written `data_type`
+    fn problems(value: &str, data_type: &str) -> Vec<String> {
+        let data_type = CqlType::parse(&lexer::tokenize(data_type), &mut 0).unwrap();
+
+        check_value(&lexer::tokenize(value), &data_type, &schema(), "ks")
+            .into_iter()
+            .map(|problem| problem.message)
+            .collect()
+    }
+
+    #[test]
+    fn valid_literals() {
+        for (value, data_type) in [
+            ("[1, 2]", "list<int>"),
+            ("{'a', 'b'}", "set<text>"),
+            ("{'a': [1.5], 'b': []}", "map<text, frozen<list<double>>>"),
+            ("(1, 'a', 0xCAFE)", "tuple<int, text, blob>"),
+            ("[?, :id, null]", "list<uuid>"),
+            ("{street: 'Main', zip: 1234}", "address"),
+            (
+                "{'2024-01-01': {street: 'Main'}}",
+                "map<date, frozen<address>>",
+            ),
+            ("(1 + 2)", "int"),
+        ] {
+            assert!(
+                problems(value, data_type).is_empty(),
+                "{value} as {data_type}"
+            );
+        }
+    }
+
+    #[test]
+    fn brackets_follow_the_type() {
+        assert_eq!(
+            problems("{1, 2}", "list<int>"),
+            ["Expected a value of type `list<int>`, written `[...]`"]
+        );
+        assert_eq!(
+            problems("{1: 2}", "set<int>"),
+            ["`set<int>` is a set: its elements aren't `key: value` entries"]
+        );
+        assert_eq!(
+            problems("{1, 2}", "map<int, int>"),
+            [
+                "`map<int, int>` is a map: its entries are written `key: value`",
+                "`map<int, int>` is a map: its entries are written `key: value`",
+            ]
+        );
+        assert_eq!(
+            problems("'a'", "set<text>"),
+            ["Expected a value of type `set<text>`, found `'a'`"]
+        );
+    }
+
+    #[test]
+    fn elements_are_checked() {
+        assert_eq!(
+            problems("['a', 1]", "list<int>"),
+            ["Expected a value of type `int`, found `'a'`"]
+        );
+        assert_eq!(
+            problems("{'a': [1, 'b']}", "map<text, frozen<list<int>>>"),
+            ["Expected a value of type `int`, found `'b'`"]
+        );
+        assert_eq!(
+            problems("(1, 2)", "tuple<int, text, int>"),
+            [
+                "`tuple<int, text, int>` has 3 elements, but 2 are given",
+                "Expected a value of type `text`, found `2`",
+            ]
+        );
+    }
+
+    #[test]
+    fn fields_are_checked() {
+        assert_eq!(
+            problems("{street: 1, city: 'Paris'}", "address"),
+            [
+                "Expected a value of type `text`, found `1`",
+                "Type `address` has no field `city`",
+            ]
+        );
+        assert_eq!(
+            problems("{'Main'}", "address"),
+            ["`address` is a user-defined type: its fields are written `field: value`"]
+        );
+    }
+
+    #[test]
+    fn expected_fields() {
+        let schema = schema();
+        let data_type = CqlType::parse(&lexer::tokenize("list<frozen<address>>"), &mut 0).unwrap();
+        let expected = |value: &str| {
+            expected_field(&lexer::tokenize(value), &data_type, &schema, "ks")
+                .map(|(user_type, given)| (user_type.name.clone(), given))
+        };
+
+        assert_eq!(expected("[{"), Some(("address".into(), vec![])));
+        assert_eq!(
+            expected("[{street: 'a', "),
+            Some(("address".into(), vec!["street".into()]))
+        );
+        assert_eq!(expected("[{street: "), None);
+        assert_eq!(expected("[{street: 'a'}"), None);
+    }
+}
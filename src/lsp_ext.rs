@@ -0,0 +1,630 @@
+//! Custom LSP extensions implemented by the server, in the spirit of rust-analyzer's `lsp_ext`.
+
+use serde::{Deserialize, Serialize};
+use tower_lsp::lsp_types::{
+    Range, TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+    TextDocumentPositionParams, Url, VersionedTextDocumentIdentifier, notification::Notification,
+    request::Request,
+};
+
+use crate::{
+    cluster::consistency::Consistency,
+    migration::Target,
+    schema::{IndexTarget, Schema, Table},
+    schema_diff::Difference,
+};
+
+#[cfg(feature = "cluster")]
+use crate::results::ResultFormat;
+
+/// Lists the bind markers of the statement under the cursor, resolved to the columns they
+/// bind to. Intended for driver authors writing prepared statements.
+pub enum BindMarkers {}
+
+impl Request for BindMarkers {
+    type Params = TextDocumentPositionParams;
+    type Result = Vec<BindMarkerInfo>;
+    const METHOD: &'static str = "cql/bindMarkers";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BindMarkerInfo {
+    /// Zero-based position of the marker in the statement
+    pub index: usize,
+    /// Name of a `:name` marker, absent for `?`
+    pub name: Option<String>,
+    pub range: Range,
+    /// Column (or pseudo-column such as `[ttl]`) the marker binds to, if it could be resolved
+    pub column: Option<String>,
+    /// CQL type of the bound value, when the schema is known
+    #[serde(rename = "type")]
+    pub data_type: Option<String>,
+}
+
+/// `workspace/executeCommand` command returning an `INSERT` of every column of a table as a
+/// snippet, with a tab stop per value
+pub const GENERATE_INSERT: &str = "cql.generateInsert";
+
+/// `workspace/executeCommand` command returning a `SELECT` of every column of a table by
+/// primary key as a snippet, with a tab stop per key value
+pub const GENERATE_SELECT: &str = "cql.generateSelect";
+
+/// `workspace/executeCommand` command returning `INSERT`s of rows of a table with random values
+/// and distinct primary keys, for clients to open as a new document of test data
+pub const GENERATE_TEST_DATA: &str = "cql.generateTestData";
+
+/// `workspace/executeCommand` command describing the DML statement under the cursor in
+/// Markdown, given as [`TextDocumentPositionParams`]: the partitions and rows it reaches,
+/// whether it needs `ALLOW FILTERING` or an index, and what its consistency level means for it
+pub const ANALYZE_STATEMENT: &str = "cql.analyzeStatement";
+
+/// `workspace/executeCommand` command connecting to the cluster of a connection profile, whose
+/// schema is then known to completion and diagnostics
+pub const CONNECT: &str = "cql.connect";
+
+/// `workspace/executeCommand` command closing the connection to the cluster
+pub const DISCONNECT: &str = "cql.disconnect";
+
+/// `workspace/executeCommand` command comparing the schema defined by the workspace's files
+/// with the schema of the connected cluster, returning the differences as
+/// [`SchemaDifference`]s
+pub const SCHEMA_DIFF: &str = "cql.schemaDiff";
+
+/// `workspace/executeCommand` command returning the DDL that brings the connected cluster in
+/// line with the workspace's files, or the other way around, for clients to open as a new
+/// migration file
+pub const GENERATE_MIGRATION: &str = "cql.generateMigration";
+
+/// `workspace/executeCommand` command returning the DDL of a keyspace or table of the
+/// connected cluster, like cqlsh's `DESCRIBE`, for clients to show in a read-only document
+pub const DESCRIBE: &str = "cql.describe";
+
+/// `workspace/executeCommand` command running the statement under the cursor on the connected
+/// cluster, returning an [`ExecuteResult`] whose document clients fetch with [`ResultContent`]
+pub const EXECUTE: &str = "cql.execute";
+
+/// `workspace/executeCommand` command returning the statements run with [`EXECUTE`] in the
+/// workspace, newest first, as [`crate::cluster::history::HistoryEntry`]s
+pub const HISTORY: &str = "cql.history";
+
+/// The commands the server executes, besides the cluster ones
+pub const COMMANDS: &[&str] = &[
+    GENERATE_INSERT,
+    GENERATE_SELECT,
+    GENERATE_TEST_DATA,
+    ANALYZE_STATEMENT,
+];
+
+/// The commands of the cluster subsystem, only available with the `cluster` feature
+pub const CLUSTER_COMMANDS: &[&str] = &[
+    CONNECT,
+    DISCONNECT,
+    SCHEMA_DIFF,
+    GENERATE_MIGRATION,
+    DESCRIBE,
+    EXECUTE,
+    HISTORY,
+];
+
+/// Arguments of the statement generation commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateStatementParams {
+    pub text_document: TextDocumentIdentifier,
+    /// Keyspace of the table, if it's not to be looked up in every keyspace of the document
+    pub keyspace: Option<String>,
+    pub table: String,
+}
+
+/// Arguments of [`GENERATE_TEST_DATA`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateTestDataParams {
+    pub text_document: TextDocumentIdentifier,
+    /// Keyspace of the table, if it's not to be looked up in every keyspace of the schema
+    pub keyspace: Option<String>,
+    pub table: String,
+    /// Number of rows
+    pub count: usize,
+}
+
+/// Arguments of [`CONNECT`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectParams {
+    /// Name of a profile of the workspace's `.cql-lsp.toml`
+    pub profile: String,
+}
+
+/// Arguments of [`GENERATE_MIGRATION`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateMigrationParams {
+    /// The side the migration is to be applied to, the cluster by default
+    #[serde(default)]
+    pub target: Target,
+}
+
+/// Arguments of [`DESCRIBE`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DescribeParams {
+    /// `keyspace` or `keyspace.table`, quoted like in CQL where needed
+    pub name: String,
+}
+
+/// Arguments of [`EXECUTE`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteParams {
+    #[serde(flatten)]
+    pub target: ExecuteTarget,
+    /// Overrides the consistency level of the profile and of the document's `CONSISTENCY`
+    /// directives
+    pub consistency: Option<Consistency>,
+    /// Overrides the serial consistency level, like `consistency`
+    pub serial_consistency: Option<Consistency>,
+    /// Traces the statement, returning its [`Trace`] with the result
+    #[serde(default)]
+    pub trace: bool,
+}
+
+/// The statement under the cursor of a document, or a statement given as is, e.g. one from the
+/// [`HISTORY`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ExecuteTarget {
+    Position(TextDocumentPositionParams),
+    #[serde(rename_all = "camelCase")]
+    Statement {
+        statement: String,
+        keyspace: Option<String>,
+    },
+}
+
+/// Arguments of [`HISTORY`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryParams {
+    /// Only the statements run on the cluster of this profile
+    pub profile: Option<String>,
+    /// At most this many statements
+    pub limit: Option<usize>,
+}
+
+/// Result of [`EXECUTE`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteResult {
+    /// The `cql-result://` document holding the result set
+    pub uri: Url,
+    /// The number of rows, absent for statements that return none, like `INSERT`
+    pub rows: Option<usize>,
+    /// The `LIMIT` added to a `SELECT` that had none, from the profile's `row_limit`
+    pub limit: Option<u64>,
+    /// The trace of the statement, when it was run with `trace`
+    pub trace: Option<Trace>,
+}
+
+/// The trace of a statement run with [`EXECUTE`], read from the `system_traces` keyspace of the
+/// cluster
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trace {
+    pub session_id: String,
+    /// The node that coordinated the statement
+    pub coordinator: String,
+    /// The other nodes that took part, e.g. the replicas read from
+    pub replicas: Vec<String>,
+    /// Milliseconds since the Unix epoch
+    pub started_at: i64,
+    /// The time the coordinator took, in microseconds
+    pub duration_us: Option<i64>,
+    pub events: Vec<TraceEvent>,
+}
+
+/// A step of the execution of a traced statement, on one of its nodes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceEvent {
+    pub activity: String,
+    /// The node the step ran on
+    pub source: String,
+    /// The time from the start of the statement on that node, in microseconds
+    pub elapsed_us: Option<i64>,
+    pub thread: String,
+}
+
+/// Returns the content of a `cql-result://` document, i.e. the result set of a statement run
+/// with [`EXECUTE`] rendered in the requested format. Only the latest results are kept.
+#[cfg(feature = "cluster")]
+pub enum ResultContent {}
+
+#[cfg(feature = "cluster")]
+impl Request for ResultContent {
+    type Params = ResultContentParams;
+    type Result = String;
+    const METHOD: &'static str = "cql/resultContent";
+}
+
+#[cfg(feature = "cluster")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultContentParams {
+    pub uri: Url,
+    /// `"table"` (cqlsh's aligned text, the default), `"csv"` or `"json"`
+    #[serde(default)]
+    pub format: ResultFormat,
+}
+
+/// A difference between the schema defined by the workspace and the cluster's
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaDifference {
+    /// `"missing"` for objects missing from the cluster, `"extra"` for objects the workspace
+    /// doesn't define, `"mismatch"` for objects defined differently
+    pub kind: String,
+    /// `"keyspace"`, `"table"`, `"view"`, `"column"`, `"index"`, `"function"`, `"columnType"`,
+    /// `"primaryKey"` or `"option"`
+    pub object: String,
+    pub keyspace: String,
+    pub table: Option<String>,
+    /// The name of the column, index, function or option
+    pub name: Option<String>,
+    /// The definitions of a mismatched object in the workspace and on the cluster
+    pub workspace: Option<String>,
+    pub cluster: Option<String>,
+    pub message: String,
+}
+
+/// Returns the known schema as a tree of keyspaces, tables and columns, for client-side
+/// schema explorers
+pub enum SchemaTree {}
+
+impl Request for SchemaTree {
+    type Params = SchemaTreeParams;
+    type Result = Vec<KeyspaceNode>;
+    const METHOD: &'static str = "cql/schemaTree";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaTreeParams {
+    /// A document whose own statements are replayed on top of the workspace schema, so that
+    /// unsaved changes show up
+    pub text_document: Option<TextDocumentIdentifier>,
+}
+
+/// Sent by the server whenever the schema model changes, so clients can invalidate their schema
+/// views and cached completions
+pub enum SchemaUpdated {}
+
+impl Notification for SchemaUpdated {
+    type Params = SchemaUpdatedParams;
+    const METHOD: &'static str = "cql/schemaUpdated";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaUpdatedParams {
+    /// The file whose change updated the schema, absent when the whole workspace was indexed
+    /// or the cluster schema changed
+    pub uri: Option<Url>,
+}
+
+/// LSP 3.18's `textDocument/inlineCompletion`, which the protocol types the server is built on
+/// predate: the continuation of the text being typed, shown by the client as gray text, e.g.
+/// ` KEY ((id), created_at)` after `PRIMARY` in the body of a table
+pub enum InlineCompletion {}
+
+impl Request for InlineCompletion {
+    type Params = InlineCompletionParams;
+    type Result = Option<Vec<InlineCompletionItem>>;
+    const METHOD: &'static str = "textDocument/inlineCompletion";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineCompletionParams {
+    #[serde(flatten)]
+    pub text_document_position: TextDocumentPositionParams,
+    pub context: InlineCompletionContext,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineCompletionContext {
+    /// 1 when requested explicitly, 2 when requested automatically as the user types
+    pub trigger_kind: u32,
+    /// The item selected in the completion popup, which the continuation must agree with
+    pub selected_completion_info: Option<SelectedCompletionInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectedCompletionInfo {
+    /// The range the selected item replaces
+    pub range: Range,
+    /// The text the selected item inserts
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineCompletionItem {
+    /// The text replacing `range`, starting with the text typed there
+    pub insert_text: String,
+    pub range: Option<Range>,
+}
+
+/// LSP 3.17's `notebookDocument/didOpen`, which like the other notebook notifications the
+/// protocol types the server is built on predate. Sent once the server registered for
+/// `notebookDocument/sync`, with the text documents of the notebook's cells.
+pub enum DidOpenNotebookDocument {}
+
+impl Notification for DidOpenNotebookDocument {
+    type Params = DidOpenNotebookDocumentParams;
+    const METHOD: &'static str = "notebookDocument/didOpen";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidOpenNotebookDocumentParams {
+    pub notebook_document: NotebookDocument,
+    pub cell_text_documents: Vec<TextDocumentItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookDocument {
+    pub uri: Url,
+    pub notebook_type: String,
+    pub version: i32,
+    pub cells: Vec<NotebookCell>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookCell {
+    /// 1 for Markup cells, 2 for code cells
+    pub kind: u32,
+    /// The text document of the cell
+    pub document: Url,
+}
+
+/// LSP 3.17's `notebookDocument/didChange`
+pub enum DidChangeNotebookDocument {}
+
+impl Notification for DidChangeNotebookDocument {
+    type Params = DidChangeNotebookDocumentParams;
+    const METHOD: &'static str = "notebookDocument/didChange";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidChangeNotebookDocumentParams {
+    pub notebook_document: VersionedTextDocumentIdentifier,
+    pub change: NotebookDocumentChangeEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookDocumentChangeEvent {
+    pub cells: Option<NotebookCellsChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookCellsChange {
+    /// Cells added, moved or removed
+    pub structure: Option<NotebookCellsStructureChange>,
+    /// Changes to the text of cells
+    pub text_content: Option<Vec<NotebookCellTextChange>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookCellsStructureChange {
+    pub array: NotebookCellArrayChange,
+    /// The text documents of the cells added
+    pub did_open: Option<Vec<TextDocumentItem>>,
+    /// The text documents of the cells removed
+    pub did_close: Option<Vec<TextDocumentIdentifier>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookCellArrayChange {
+    pub start: usize,
+    pub delete_count: usize,
+    /// The cells replacing those deleted
+    pub cells: Option<Vec<NotebookCell>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookCellTextChange {
+    pub document: VersionedTextDocumentIdentifier,
+    pub changes: Vec<TextDocumentContentChangeEvent>,
+}
+
+/// LSP 3.17's `notebookDocument/didClose`
+pub enum DidCloseNotebookDocument {}
+
+impl Notification for DidCloseNotebookDocument {
+    type Params = DidCloseNotebookDocumentParams;
+    const METHOD: &'static str = "notebookDocument/didClose";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidCloseNotebookDocumentParams {
+    pub notebook_document: TextDocumentIdentifier,
+    pub cell_text_documents: Vec<TextDocumentIdentifier>,
+}
+
+/// Sent by the server to ask the user for a value, e.g. a password that the connection profile
+/// doesn't store, like VS Code's `window.showInputBox`. The result is `null` when the user
+/// cancels.
+pub enum ShowInputBox {}
+
+impl Request for ShowInputBox {
+    type Params = ShowInputBoxParams;
+    type Result = Option<String>;
+    const METHOD: &'static str = "cql/showInputBox";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShowInputBoxParams {
+    pub prompt: String,
+    /// Whether the input is to be hidden while typed
+    pub password: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyspaceNode {
+    pub name: String,
+    pub tables: Vec<TableNode>,
+    pub views: Vec<TableNode>,
+    pub functions: Vec<FunctionNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableNode {
+    pub name: String,
+    pub columns: Vec<ColumnNode>,
+    pub indexes: Vec<IndexNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnNode {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub data_type: String,
+    /// `"partitionKey"`, `"clustering"` or `"regular"`
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexNode {
+    pub name: Option<String>,
+    pub column: String,
+    /// `"column"`, `"keys"`, `"values"`, `"entries"` or `"full"`
+    pub target: String,
+    pub class: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionNode {
+    pub name: String,
+    pub argument_types: Vec<String>,
+    pub return_type: Option<String>,
+}
+
+impl TableNode {
+    fn new(table: &Table) -> Self {
+        let kind = |name: &String| {
+            if table.partition_key.contains(name) {
+                "partitionKey"
+            } else if table.clustering_key.contains(name) {
+                "clustering"
+            } else {
+                "regular"
+            }
+        };
+
+        let target = |target: IndexTarget| match target {
+            IndexTarget::Column => "column",
+            IndexTarget::Keys => "keys",
+            IndexTarget::Values => "values",
+            IndexTarget::Entries => "entries",
+            IndexTarget::Full => "full",
+        };
+
+        Self {
+            name: table.name.clone(),
+            columns: table
+                .columns
+                .iter()
+                .map(|column| ColumnNode {
+                    name: column.name.clone(),
+                    data_type: column.data_type.to_string(),
+                    kind: kind(&column.name).to_string(),
+                })
+                .collect(),
+            indexes: table
+                .indexes
+                .iter()
+                .map(|index| IndexNode {
+                    name: index.name.clone(),
+                    column: index.column.clone(),
+                    target: target(index.target).to_string(),
+                    class: index.class.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl KeyspaceNode {
+    /// Builds the tree of every keyspace of `schema`. Objects created without a keyspace are
+    /// listed under a keyspace with an empty name.
+    pub fn tree(schema: &Schema) -> Vec<KeyspaceNode> {
+        schema
+            .keyspaces
+            .iter()
+            .map(|(name, keyspace)| KeyspaceNode {
+                name: name.clone(),
+                tables: keyspace.tables.values().map(TableNode::new).collect(),
+                views: keyspace.views.values().map(TableNode::new).collect(),
+                functions: keyspace
+                    .functions
+                    .iter()
+                    .map(|function| FunctionNode {
+                        name: function.name.clone(),
+                        argument_types: function
+                            .argument_types
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect(),
+                        return_type: function.return_type.as_ref().map(ToString::to_string),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+impl SchemaDifference {
+    pub fn new(difference: &Difference) -> Self {
+        let (kind, object, name) = match difference {
+            Difference::Missing(object) => ("missing", object.kind(), object.name()),
+            Difference::Extra(object) => ("extra", object.kind(), object.name()),
+            Difference::ColumnType { workspace, .. } => {
+                ("mismatch", "columnType", Some(workspace.name.clone()))
+            }
+            Difference::PrimaryKey { .. } => ("mismatch", "primaryKey", None),
+            Difference::Option { name, .. } => ("mismatch", "option", Some(name.to_string())),
+        };
+
+        let (workspace, cluster) = difference.values().unzip();
+
+        Self {
+            kind: kind.to_string(),
+            object: object.to_string(),
+            keyspace: difference.keyspace().to_string(),
+            table: difference.table().map(str::to_string),
+            name,
+            workspace,
+            cluster,
+            message: difference.message(),
+        }
+    }
+}
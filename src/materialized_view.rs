@@ -0,0 +1,231 @@
+//! `CREATE MATERIALIZED VIEW` parsing and the checks Cassandra applies to view definitions.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::Problem,
+    lexer::Token,
+    schema::{Column, Schema, Table, parse_primary_key},
+    statement::{QualifiedName, Statement, StatementKind, parse_qualified_name},
+};
+
+/// The parts of a `CREATE MATERIALIZED VIEW` statement, as far as they've been typed
+#[derive(Debug)]
+pub struct ViewDefinition<'a> {
+    pub name: QualifiedName,
+    pub base: Option<QualifiedName>,
+    /// The selected columns, `None` for `SELECT *`
+    pub columns: Option<Vec<&'a Token<'a>>>,
+    /// The columns restricted with `IS NOT NULL` in the `WHERE` clause
+    pub not_null: Vec<String>,
+    pub partition_key: Vec<&'a Token<'a>>,
+    pub clustering_key: Vec<&'a Token<'a>>,
+}
+
+impl ViewDefinition<'_> {
+    pub fn primary_key(&self) -> impl Iterator<Item = &Token<'_>> {
+        self.partition_key
+            .iter()
+            .chain(&self.clustering_key)
+            .map(|token| &**token)
+    }
+
+    /// Resolves the base table in `schema`, using `current_keyspace` for an unqualified name
+    pub fn base_table<'s>(
+        &self,
+        schema: &'s Schema,
+        current_keyspace: Option<&str>,
+    ) -> Option<&'s Table> {
+        let base = self.base.as_ref()?;
+        let keyspace = base.keyspace.as_deref().or(current_keyspace);
+
+        schema
+            .table(keyspace, &base.name)
+            .or_else(|| schema.table(None, &base.name))
+    }
+
+    /// Builds the table-like model of the view from its base table
+    pub fn to_table(&self, base: &Table, keyspace: String) -> Table {
+        let selected = |column: &&Column| match &self.columns {
+            Some(columns) => columns
+                .iter()
+                .any(|token| token.identifier().as_deref() == Some(column.name.as_str())),
+            None => true,
+        };
+
+        let names = |tokens: &[&Token]| tokens.iter().filter_map(|t| t.identifier()).collect();
+
+        Table {
+            keyspace,
+            name: self.name.name.clone(),
+            columns: base.columns.iter().filter(selected).cloned().collect(),
+            partition_key: names(&self.partition_key),
+            clustering_key: names(&self.clustering_key),
+            static_columns: vec![],
+            options: vec![],
+            indexes: vec![],
+        }
+    }
+}
+
+/// Parses `CREATE MATERIALIZED VIEW [IF NOT EXISTS] name AS SELECT ... FROM base WHERE ...
+/// PRIMARY KEY (...)`
+pub fn parse<'a>(statement: &Statement<'a>) -> Option<ViewDefinition<'a>> {
+    if statement.kind() != StatementKind::CreateMaterializedView {
+        return None;
+    }
+
+    let tokens = statement.tokens;
+
+    let mut i = 3;
+    while tokens
+        .get(i)
+        .is_some_and(|t| ["IF", "NOT", "EXISTS"].iter().any(|k| t.is_keyword(k)))
+    {
+        i += 1;
+    }
+
+    let name = parse_qualified_name(tokens, &mut i)?;
+
+    let select = statement.find_keyword("SELECT");
+    let from = statement.find_keyword("FROM");
+    let r#where = statement.find_keyword("WHERE");
+    let primary = statement.find_keyword("PRIMARY");
+
+    let columns = select.map(|select| {
+        let end = from.unwrap_or(tokens.len());
+        let list = &tokens[select + 1..end.max(select + 1)];
+
+        match list {
+            [star] if star.is_punctuation("*") => None,
+            _ => Some(
+                list.iter()
+                    .filter(|token| token.is_identifier())
+                    .collect::<Vec<_>>(),
+            ),
+        }
+    });
+
+    let base = from.and_then(|from| parse_qualified_name(tokens, &mut (from + 1)));
+
+    let not_null = r#where
+        .map(|r#where| {
+            let end = primary.unwrap_or(tokens.len());
+
+            tokens[r#where + 1..end.max(r#where + 1)]
+                .windows(4)
+                .filter(|w| {
+                    w[1].is_keyword("IS") && w[2].is_keyword("NOT") && w[3].is_keyword("NULL")
+                })
+                .filter_map(|w| w[0].identifier())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let (partition_key, clustering_key) = primary
+        .filter(|&primary| tokens.get(primary + 1).is_some_and(|t| t.is_keyword("KEY")))
+        .and_then(|primary| parse_primary_key(tokens, primary + 2))
+        .unwrap_or_default();
+
+    Some(ViewDefinition {
+        name,
+        base,
+        columns: columns.flatten(),
+        not_null,
+        partition_key,
+        clustering_key,
+    })
+}
+
+/// Checks a view definition against its base table, reporting the mistakes Cassandra rejects
+/// view definitions for
+pub fn check(view: &ViewDefinition, base: &Table) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    let error = |token: &Token, message: String| Problem {
+        span: token.start..token.end,
+        severity: DiagnosticSeverity::ERROR,
+        message,
+        lint: None,
+    };
+
+    let base_key = base
+        .partition_key
+        .iter()
+        .chain(&base.clustering_key)
+        .collect::<Vec<_>>();
+
+    let mut non_key_columns = vec![];
+
+    for token in view.primary_key() {
+        let Some(column) = token.identifier() else {
+            continue;
+        };
+
+        if base.column(&column).is_none() {
+            problems.push(error(
+                token,
+                format!("Unknown column `{column}` in base table `{}`", base.name),
+            ));
+            continue;
+        }
+
+        if !view.not_null.contains(&column) {
+            problems.push(error(
+                token,
+                format!(
+                    "Primary key column `{column}` must be restricted with `{column} IS NOT NULL` in the WHERE clause"
+                ),
+            ));
+        }
+
+        if !base_key.contains(&&column) {
+            non_key_columns.push(token);
+        }
+    }
+
+    // Cassandra allows a single non-primary-key column of the base table in the view key
+    if non_key_columns.len() > 1 {
+        let names = non_key_columns
+            .iter()
+            .filter_map(|token| token.identifier())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        for token in &non_key_columns {
+            problems.push(error(
+                token,
+                format!(
+                    "Cannot include more than one non-primary key column of the base table in the view primary key (got {names})"
+                ),
+            ));
+        }
+    }
+
+    // Each base primary key column must be part of the view primary key, so that every view
+    // row maps to exactly one base row
+    let view_key = view
+        .primary_key()
+        .filter_map(Token::identifier)
+        .collect::<Vec<_>>();
+
+    let missing = base_key
+        .iter()
+        .filter(|column| !view_key.contains(column))
+        .map(|column| column.as_str())
+        .collect::<Vec<_>>();
+
+    if !missing.is_empty()
+        && let Some(first) = view.primary_key().next()
+    {
+        problems.push(error(
+            first,
+            format!(
+                "The view primary key must include the base table primary key columns (missing {})",
+                missing.join(", ")
+            ),
+        ));
+    }
+
+    problems
+}
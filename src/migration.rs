@@ -0,0 +1,195 @@
+//! Migration scripts generated from a schema diff, bringing the cluster in line with the
+//! workspace's DDL, or the workspace in line with the cluster.
+//!
+//! Statements that drop objects are commented out, to be reviewed before they lose data, and
+//! differences DDL can't fix, like a changed primary key, are left as comments.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    generate::{
+        create_index_statement, create_keyspace_statement, describe_table, index_name,
+        qualified_name,
+    },
+    lexer::quote_identifier,
+    schema_diff::{Difference, Object},
+};
+
+/// The side a migration script is to be applied to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Target {
+    /// Statements to run on the cluster so that it matches the workspace
+    #[default]
+    Cluster,
+    /// Statements to add to the workspace so that it matches the cluster
+    Workspace,
+}
+
+#[derive(Debug, Default)]
+struct Script {
+    creates: Vec<String>,
+    alters: Vec<String>,
+    drops: Vec<String>,
+}
+
+impl Script {
+    fn create(&mut self, object: &Object) {
+        match object {
+            Object::Keyspace(name, keyspace) => {
+                match keyspace.options.is_empty() {
+                    true => self.creates.push(format!(
+                        "-- Keyspace {name} has no replication to create it with"
+                    )),
+                    false => self.creates.push(create_keyspace_statement(name, keyspace)),
+                }
+
+                self.creates
+                    .extend(keyspace.tables.values().map(describe_table));
+
+                for object in keyspace
+                    .views
+                    .values()
+                    .map(Object::View)
+                    .chain(keyspace.functions.iter().map(Object::Function))
+                {
+                    self.create(&object);
+                }
+            }
+            Object::Table(table) => self.creates.push(describe_table(table)),
+            Object::Column(table, column) => self.alters.push(format!(
+                "ALTER TABLE {} ADD {} {};",
+                qualified_name(table),
+                quote_identifier(&column.name),
+                column.data_type
+            )),
+            Object::Index(table, index) => self.creates.push(create_index_statement(table, index)),
+            // Neither the `SELECT` of a view nor the body of a function is part of the model
+            Object::View(_) | Object::Function(_) => self.creates.push(format!(
+                "-- Create the {} by hand, as its definition isn't known",
+                object.describe()
+            )),
+        }
+    }
+
+    fn drop(&mut self, object: &Object) {
+        let statement = match object {
+            Object::Keyspace(name, _) => format!("DROP KEYSPACE {};", quote_identifier(name)),
+            Object::Table(table) => format!("DROP TABLE {};", qualified_name(table)),
+            Object::View(view) => format!("DROP MATERIALIZED VIEW {};", qualified_name(view)),
+            Object::Column(table, column) => format!(
+                "ALTER TABLE {} DROP {};",
+                qualified_name(table),
+                quote_identifier(&column.name)
+            ),
+            Object::Index(table, index) => format!(
+                "DROP INDEX {}.{};",
+                quote_identifier(&table.keyspace),
+                quote_identifier(&index_name(table, index))
+            ),
+            Object::Function(function) => format!(
+                "DROP FUNCTION {}.{}({});",
+                quote_identifier(&function.keyspace),
+                quote_identifier(&function.name),
+                function
+                    .argument_types
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+
+        self.drops.push(format!("-- {statement}"));
+    }
+
+    fn text(self, target: Target) -> String {
+        if self.creates.is_empty() && self.alters.is_empty() && self.drops.is_empty() {
+            return match target {
+                Target::Cluster => "-- The cluster matches the workspace\n".to_string(),
+                Target::Workspace => "-- The workspace matches the cluster\n".to_string(),
+            };
+        }
+
+        let mut sections = vec![];
+
+        if !self.creates.is_empty() || !self.alters.is_empty() {
+            sections.push(
+                self.creates
+                    .into_iter()
+                    .chain(self.alters)
+                    .collect::<Vec<_>>()
+                    .join("\n\n"),
+            );
+        }
+
+        if !self.drops.is_empty() {
+            sections.push(format!(
+                "-- Dropping loses data: uncomment after review\n{}",
+                self.drops.join("\n")
+            ));
+        }
+
+        format!("{}\n", sections.join("\n\n"))
+    }
+}
+
+/// Generates the statements turning the other side of `differences` into `target`
+pub fn script(differences: &[Difference], target: Target) -> String {
+    let mut script = Script::default();
+
+    for difference in differences {
+        match (difference, target) {
+            (Difference::Missing(object), Target::Cluster)
+            | (Difference::Extra(object), Target::Workspace) => script.create(object),
+            (Difference::Extra(object), Target::Cluster)
+            | (Difference::Missing(object), Target::Workspace) => script.drop(object),
+            (Difference::ColumnType { .. } | Difference::PrimaryKey { .. }, _) => {
+                script.alters.push(format!(
+                    "-- {}, which takes recreating the table to change",
+                    difference.message()
+                ));
+            }
+            (
+                Difference::Option {
+                    keyspace,
+                    table,
+                    name,
+                    workspace,
+                    cluster,
+                },
+                _,
+            ) => {
+                if *name == "clustering" {
+                    script.alters.push(format!(
+                        "-- {}, which takes recreating the table to change",
+                        difference.message()
+                    ));
+                    continue;
+                }
+
+                // The value of the side the other is brought in line with
+                let value = match target {
+                    Target::Cluster => workspace,
+                    Target::Workspace => cluster,
+                };
+
+                let statement = match table {
+                    Some(table) => format!(
+                        "ALTER TABLE {}.{} WITH {name} = {value};",
+                        quote_identifier(keyspace),
+                        quote_identifier(table)
+                    ),
+                    None => format!(
+                        "ALTER KEYSPACE {} WITH {name} = {value};",
+                        quote_identifier(keyspace)
+                    ),
+                };
+
+                script.alters.push(statement);
+            }
+        }
+    }
+
+    script.text(target)
+}
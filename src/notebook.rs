@@ -0,0 +1,86 @@
+//! Notebooks of CQL cells, e.g. Jupyter notebooks run by a CQL kernel. Each cell is a text
+//! document of its own, checked against the schema the cells before it leave: the objects they
+//! create and the keyspace they select with `USE`.
+
+use std::collections::HashMap;
+
+use tower_lsp::lsp_types::Url;
+
+use crate::{
+    document::Documents,
+    lexer,
+    schema::{self, Schema},
+    statement::{self, Dialect},
+};
+
+/// The open notebooks, with the text documents of their cells in order
+#[derive(Debug, Default)]
+pub struct Notebooks {
+    notebooks: HashMap<Url, Vec<Url>>,
+}
+
+impl Notebooks {
+    pub fn open(&mut self, uri: Url, cells: Vec<Url>) {
+        self.notebooks.insert(uri, cells);
+    }
+
+    /// Forgets the notebook at `uri`, returning its cells
+    pub fn close(&mut self, uri: &Url) -> Vec<Url> {
+        self.notebooks.remove(uri).unwrap_or_default()
+    }
+
+    /// The cells of the notebook at `uri`, in order
+    pub fn cells(&self, uri: &Url) -> &[Url] {
+        self.notebooks.get(uri).map_or(&[], Vec::as_slice)
+    }
+
+    /// Replaces the `delete_count` cells of the notebook at `uri` from `start` on with `cells`
+    pub fn splice(&mut self, uri: &Url, start: usize, delete_count: usize, cells: Vec<Url>) {
+        let Some(notebook) = self.notebooks.get_mut(uri) else {
+            return;
+        };
+
+        let start = start.min(notebook.len());
+        let end = (start + delete_count).min(notebook.len());
+
+        notebook.splice(start..end, cells);
+    }
+
+    /// The cells before `cell` in its notebook, none if it isn't the cell of one
+    pub fn earlier_cells(&self, cell: &Url) -> &[Url] {
+        self.notebooks
+            .values()
+            .find_map(|cells| {
+                let i = cells.iter().position(|uri| uri == cell)?;
+                Some(&cells[..i])
+            })
+            .unwrap_or(&[])
+    }
+
+    /// Builds the schema `uri` is checked against from the workspace's `schema`: that of a cell
+    /// has the statements of the cells before it replayed on top, the keyspace they select with
+    /// `USE` carrying over to it. `documents` holds the cells read as CQL.
+    pub fn schema(
+        &self,
+        uri: &Url,
+        mut schema: Schema,
+        documents: &Documents,
+        dialect: Dialect,
+    ) -> Schema {
+        for cell in self.earlier_cells(uri) {
+            let Some(doc) = documents.get(cell) else {
+                continue;
+            };
+
+            let text = doc.text();
+            let tokens = lexer::tokenize(text);
+            let statements = statement::split(&tokens, doc.dialect(dialect));
+
+            let used_keyspace = schema::keyspace_at(&statements, statements.len());
+            schema = schema.with_statements(&statements);
+            schema.used_keyspace = used_keyspace.or(schema.used_keyspace);
+        }
+
+        schema
+    }
+}
@@ -0,0 +1,191 @@
+//! The `cql-lsp parse` command, printing the syntax tree the grammar gives a file, for
+//! debugging the grammar and for bug reports: as tree-sitter's S-expression, or as a concrete
+//! syntax tree with a node per line, with its field, byte range and text. The text the grammar
+//! couldn't parse is listed on the standard error.
+
+use std::{
+    fs,
+    io::{self, Read},
+};
+
+use anyhow::{Context, Result, bail};
+use tree_sitter::{Tree, TreeCursor};
+
+use crate::parser::{self, NodeKind};
+
+const USAGE: &str = "Usage: cql-lsp parse [--sexp|--cst] [<file>|-]";
+
+/// How the tree is printed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rendering {
+    /// tree-sitter's S-expression of the named nodes, e.g. `(source_file (statement ...))`
+    #[default]
+    Sexp,
+    /// Every node, anonymous ones included, indented by depth
+    Cst,
+}
+
+/// Calls `visit` with a cursor on every node of `tree` in document order, along with its depth
+fn walk(tree: &Tree, mut visit: impl FnMut(&TreeCursor, usize)) {
+    let mut cursor = tree.walk();
+    let mut depth = 0;
+
+    loop {
+        visit(&cursor, depth);
+
+        if cursor.goto_first_child() {
+            depth += 1;
+            continue;
+        }
+
+        // Up to the nearest ancestor with a next sibling
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return;
+            }
+
+            depth -= 1;
+        }
+    }
+}
+
+/// Renders every node of `tree`, parsed from `text`, on its own line, indented by depth, e.g.
+/// `table: identifier [14..19] "users"`. Anonymous nodes, such as keywords, are quoted, and
+/// nodes the parser made up to recover from errors are marked.
+pub fn cst(tree: &Tree, text: &str) -> String {
+    let mut rendering = String::new();
+
+    walk(tree, |cursor, depth| {
+        let node = cursor.node();
+        let field = cursor
+            .field_name()
+            .map_or(String::new(), |field| format!("{field}: "));
+
+        let kind = match node.is_named() {
+            true => node.kind().to_string(),
+            false => format!("{:?}", node.kind()),
+        };
+
+        let mut line = format!(
+            "{}{field}{kind} [{}..{}]",
+            "  ".repeat(depth),
+            node.start_byte(),
+            node.end_byte()
+        );
+
+        if NodeKind::of(&node) == NodeKind::Missing {
+            line.push_str(" MISSING");
+        } else if node.is_named() && node.child_count() == 0 {
+            line.push_str(&format!(" {:?}", &text[node.byte_range()]));
+        }
+
+        rendering.push_str(&line);
+        rendering.push('\n');
+    });
+
+    rendering
+}
+
+/// The errors of `tree`, parsed from `text`: the text the grammar couldn't parse, and the
+/// tokens it expected but didn't find, e.g. `12..20: couldn't parse "SELEC *"`
+pub fn errors(tree: &Tree, text: &str) -> Vec<String> {
+    let mut errors = vec![];
+
+    walk(tree, |cursor, _| {
+        let node = cursor.node();
+        let range = node.byte_range();
+
+        match NodeKind::of(&node) {
+            NodeKind::Error => errors.push(format!(
+                "{}..{}: couldn't parse {:?}",
+                range.start,
+                range.end,
+                &text[range.clone()]
+            )),
+            NodeKind::Missing => errors.push(format!("{}: missing {}", range.start, node.kind())),
+            NodeKind::Statement | NodeKind::Other => {}
+        }
+    });
+
+    errors
+}
+
+/// Renders the tree `text` parses into, along with its errors
+pub fn parse(text: &str, rendering: Rendering) -> (String, Vec<String>) {
+    let tree = parser::new()
+        .parse(text, None)
+        .expect("Could not parse CQL code with Tree-sitter");
+
+    let rendered = match rendering {
+        Rendering::Sexp => format!("{}\n", tree.root_node().to_sexp()),
+        Rendering::Cst => cst(&tree, text),
+    };
+
+    (rendered, errors(&tree, text))
+}
+
+/// Reads the arguments of `cql-lsp parse`: the rendering, and the path of the file, `None` for
+/// the standard input
+fn parse_args(args: &[String]) -> Result<(Rendering, Option<&String>)> {
+    let mut rendering = Rendering::default();
+    let mut path = None;
+
+    for arg in args {
+        match arg.as_str() {
+            "--sexp" => rendering = Rendering::Sexp,
+            "--cst" => rendering = Rendering::Cst,
+            option if option.starts_with("--") => bail!("Unknown option `{option}`"),
+            _ if path.is_some() => bail!("Only one file is parsed at a time"),
+            _ => path = Some(arg),
+        }
+    }
+
+    Ok((rendering, path.filter(|path| *path != "-")))
+}
+
+/// Reads the file at `path`, or the standard input
+fn read(path: Option<&String>) -> Result<String> {
+    let Some(path) = path else {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .context("Couldn't read the standard input")?;
+
+        return Ok(text);
+    };
+
+    fs::read_to_string(path).with_context(|| format!("Couldn't read {path}"))
+}
+
+/// Runs `cql-lsp parse` with the command line arguments after `parse`, and tells the exit
+/// code: 1 when the tree has errors, 2 when the arguments were wrong or the file couldn't be
+/// read
+pub fn run(args: &[String]) -> u8 {
+    let (rendering, path) = match parse_args(args) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!("error: {error:#}\n{USAGE}");
+            return 2;
+        }
+    };
+
+    let text = match read(path) {
+        Ok(text) => text,
+        Err(error) => {
+            eprintln!("error: {error:#}");
+            return 2;
+        }
+    };
+
+    let (rendered, errors) = parse(&text, rendering);
+    print!("{rendered}");
+
+    for error in &errors {
+        eprintln!("error: {error}");
+    }
+
+    match errors.is_empty() {
+        true => 0,
+        false => 1,
+    }
+}
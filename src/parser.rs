@@ -0,0 +1,49 @@
+//! The tree-sitter grammar of CQL the documents are parsed with. Features go through this
+//! module rather than the grammar's crate, so that they all see the same tree, and match its
+//! nodes by [`NodeKind`] rather than the grammar's own names for them.
+
+use tree_sitter::{Language, Node, Parser};
+
+/// The kinds of nodes the features look for in the tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// A whole statement
+    Statement,
+    /// Text the grammar couldn't parse, e.g. the statement being typed
+    Error,
+    /// A token the grammar expected but didn't find, made up to recover, e.g. a missing `;`
+    Missing,
+    Other,
+}
+
+impl NodeKind {
+    pub fn of(node: &Node) -> Self {
+        if node.is_missing() {
+            return NodeKind::Missing;
+        }
+
+        match node.kind() {
+            "statement" => NodeKind::Statement,
+            "ERROR" => NodeKind::Error,
+            _ => NodeKind::Other,
+        }
+    }
+}
+
+/// The grammar of CQL
+pub fn language() -> Language {
+    tree_sitter_cql3::LANGUAGE.into()
+}
+
+/// Creates a parser of CQL
+pub fn new() -> Parser {
+    let mut parser = Parser::new();
+
+    // Set parser language should always succeed, but we're required to provide an error
+    // message nevertheless
+    parser
+        .set_language(&language())
+        .expect("Could not load language for Tree-sitter parser");
+
+    parser
+}
@@ -0,0 +1,100 @@
+//! Server-initiated `window/workDoneProgress` reporting, for work that makes features briefly
+//! unavailable, such as indexing the workspace.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use tower_lsp::{
+    Client,
+    lsp_types::{
+        NumberOrString, ProgressParams, ProgressParamsValue, WorkDoneProgress,
+        WorkDoneProgressBegin, WorkDoneProgressCreateParams, WorkDoneProgressEnd,
+        WorkDoneProgressReport, notification::Progress as ProgressNotification,
+        request::WorkDoneProgressCreate,
+    },
+};
+
+/// Makes progress tokens unique for the lifetime of the server
+static NEXT_TOKEN: AtomicU32 = AtomicU32::new(0);
+
+/// A progress shown by the client, or nothing when the client doesn't support progress
+pub struct Progress<'a> {
+    client: &'a Client,
+    token: Option<NumberOrString>,
+    /// The last percentage reported, to avoid flooding the client with identical reports
+    percentage: Option<u32>,
+}
+
+impl<'a> Progress<'a> {
+    /// Asks the client to create a progress titled `title`, when `supported`
+    pub async fn begin(client: &'a Client, supported: bool, title: &str) -> Self {
+        let mut progress = Self {
+            client,
+            token: None,
+            percentage: None,
+        };
+
+        if !supported {
+            return progress;
+        }
+
+        let token = NumberOrString::String(format!(
+            "cql-lsp/{}",
+            NEXT_TOKEN.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let created = client
+            .send_request::<WorkDoneProgressCreate>(WorkDoneProgressCreateParams {
+                token: token.clone(),
+            })
+            .await;
+
+        if created.is_ok() {
+            progress.token = Some(token);
+            progress
+                .notify(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                    title: title.to_string(),
+                    percentage: Some(0),
+                    ..Default::default()
+                }))
+                .await;
+        }
+
+        progress
+    }
+
+    async fn notify(&self, value: WorkDoneProgress) {
+        let Some(token) = &self.token else {
+            return;
+        };
+
+        self.client
+            .send_notification::<ProgressNotification>(ProgressParams {
+                token: token.clone(),
+                value: ProgressParamsValue::WorkDone(value),
+            })
+            .await;
+    }
+
+    /// Reports that `done` out of `total` steps are done, e.g. "42/180 files"
+    pub async fn report(&mut self, done: usize, total: usize, unit: &str) {
+        let percentage = (done * 100 / total.max(1)) as u32;
+
+        if self.percentage == Some(percentage) {
+            return;
+        }
+
+        self.percentage = Some(percentage);
+
+        self.notify(WorkDoneProgress::Report(WorkDoneProgressReport {
+            message: Some(format!("{done}/{total} {unit}")),
+            percentage: Some(percentage),
+            ..Default::default()
+        }))
+        .await;
+    }
+
+    pub async fn end(self) {
+        self.notify(WorkDoneProgress::End(WorkDoneProgressEnd { message: None }))
+            .await;
+    }
+}
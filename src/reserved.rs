@@ -0,0 +1,115 @@
+//! Reserved keywords used as identifiers, e.g. a column named `order`, which Cassandra only
+//! accepts double-quoted.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    cql_type::TYPE_CONSTRUCTORS,
+    diagnostics::Problem,
+    lexer::{Token, TokenKind, is_reserved, quote_identifier},
+    schema::Table,
+    statement::{self, Statement, StatementKind, matching_bracket},
+};
+
+/// The names defined by a `CREATE TABLE` or `CREATE TYPE`: the object's own, then its columns'
+/// or fields'
+fn defined_names<'a>(statement: &Statement<'a>) -> Vec<&'a Token<'a>> {
+    let tokens = statement.tokens;
+
+    let Some(open) = tokens.iter().position(|t| t.is_punctuation("(")) else {
+        return vec![];
+    };
+    let close = matching_bracket(tokens, open).unwrap_or(tokens.len());
+
+    // The last word before the parenthesis, `ks.name` being qualified
+    let name = open.checked_sub(1).map(|i| &tokens[i]);
+
+    let definitions = statement::split_commas(&tokens[open + 1..close])
+        .into_iter()
+        .filter(|definition| {
+            !matches!(definition, [primary, key, ..] if primary.is_keyword("PRIMARY") && key.is_keyword("KEY"))
+        })
+        .filter_map(|definition| match definition {
+            [name, _, ..] => Some(name),
+            _ => None,
+        });
+
+    name.into_iter().chain(definitions).collect()
+}
+
+/// The reserved keywords written unquoted where an identifier is expected: names defined by
+/// `CREATE TABLE` and `CREATE TYPE`, the table of DML statements, and references to the columns of `table` followed by
+/// punctuation, e.g. `SET order = 1`, which keywords used as keywords aren't
+pub fn identifiers<'a>(statement: &Statement<'a>, table: Option<&Table>) -> Vec<&'a Token<'a>> {
+    let tokens = statement.tokens;
+
+    let defined = match statement.kind() {
+        StatementKind::CreateTable | StatementKind::CreateType => defined_names(statement),
+        // The table written or read
+        StatementKind::Select
+        | StatementKind::Insert
+        | StatementKind::Update
+        | StatementKind::Delete => statement
+            .target_table()
+            .and_then(|name| tokens.iter().rfind(|t| t.end == name.end))
+            .into_iter()
+            .collect(),
+        _ => vec![],
+    };
+
+    let columns = defined
+        .iter()
+        .filter_map(|token| token.identifier())
+        .chain(
+            table
+                .into_iter()
+                .flat_map(|table| table.columns.iter().map(|column| column.name.clone())),
+        )
+        .collect::<Vec<_>>();
+
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(i, token)| {
+            if token.kind != TokenKind::Word || !is_reserved(token.text) {
+                return false;
+            }
+
+            // `(order DESC)` in `CLUSTERING ORDER BY` too, but not the types of `set<int>`
+            let referenced = tokens.get(i + 1).is_none_or(|next| {
+                let parameters = next.is_punctuation("<")
+                    && TYPE_CONSTRUCTORS.iter().any(|name| token.is_keyword(name));
+
+                (next.kind == TokenKind::Punctuation && !next.is_punctuation("(") && !parameters)
+                    || next.is_keyword("ASC")
+                    || next.is_keyword("DESC")
+            }) && columns.contains(&token.text.to_lowercase());
+
+            referenced || defined.iter().any(|name| std::ptr::eq(*name, *token))
+        })
+        .map(|(_, token)| token)
+        .collect()
+}
+
+/// Checks `statement` for reserved keywords used as identifiers, `table` being the one it
+/// operates on if known
+pub fn check(statement: &Statement, table: Option<&Table>) -> Vec<Problem> {
+    identifiers(statement, table)
+        .into_iter()
+        .map(|token| Problem {
+            span: token.start..token.end,
+            severity: DiagnosticSeverity::ERROR,
+            message: format!(
+                "`{}` is a reserved keyword: it must be double-quoted to be used as an identifier, i.e. `{}`",
+                token.text,
+                quoted(token)
+            ),
+            lint: None,
+        })
+        .collect()
+}
+
+/// The double-quoted form of the unquoted identifier `token`, denoting the same lower case name
+pub fn quoted(token: &Token) -> String {
+    quote_identifier(&token.text.to_lowercase())
+}
@@ -0,0 +1,201 @@
+//! The results of statements executed on the cluster, served as `cql-result://` documents so that
+//! clients show them without each rendering result sets their own way.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use tower_lsp::lsp_types::Url;
+
+use crate::cluster::value::Value;
+
+/// The scheme of the URIs results are served under
+pub const SCHEME: &str = "cql-result";
+
+/// How many results are kept for clients to fetch, the oldest being dropped first
+const KEPT_RESULTS: usize = 20;
+
+/// The outcome of executing a statement
+#[derive(Debug, Clone)]
+pub struct ResultSet {
+    /// The columns, empty for statements that return no rows, like `INSERT`
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// The format a result set is rendered in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResultFormat {
+    /// An aligned text table, like cqlsh prints
+    #[default]
+    Table,
+    Csv,
+    Json,
+}
+
+/// Whether values are right-aligned in tables, like cqlsh does with numbers
+fn right_aligned(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Integer(_) | Value::Number(_) | Value::Float(_) | Value::Double(_)
+    )
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+impl ResultSet {
+    pub fn render(&self, format: ResultFormat) -> String {
+        match format {
+            ResultFormat::Table => self.table(),
+            ResultFormat::Csv => self.csv(),
+            ResultFormat::Json => self.json(),
+        }
+    }
+
+    fn table(&self) -> String {
+        if self.columns.is_empty() {
+            return "OK\n".to_string();
+        }
+
+        let cells = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let widths = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                cells
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .chain([column])
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        let header = self
+            .columns
+            .iter()
+            .zip(&widths)
+            .map(|(column, &width)| format!(" {column:>width$} "))
+            .collect::<Vec<_>>()
+            .join("|")
+            .trim_end()
+            .to_string();
+
+        let separator = widths
+            .iter()
+            .map(|width| "-".repeat(width + 2))
+            .collect::<Vec<_>>()
+            .join("+");
+
+        let mut lines = vec![header, separator];
+
+        for (row, values) in cells.iter().zip(&self.rows) {
+            let line = row
+                .iter()
+                .zip(values)
+                .zip(&widths)
+                .map(|((cell, value), &width)| match right_aligned(value) {
+                    true => format!(" {cell:>width$} "),
+                    false => format!(" {cell:<width$} "),
+                })
+                .collect::<Vec<_>>()
+                .join("|");
+
+            lines.push(line.trim_end().to_string());
+        }
+
+        format!("{}\n\n({} rows)\n", lines.join("\n"), self.rows.len())
+    }
+
+    /// RFC 4180 CSV with a header line, `null` being an empty field
+    fn csv(&self) -> String {
+        let mut lines = vec![];
+
+        if !self.columns.is_empty() {
+            lines.push(
+                self.columns
+                    .iter()
+                    .map(|column| csv_field(column))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+        }
+
+        for row in &self.rows {
+            let line = row
+                .iter()
+                .map(|value| match value {
+                    Value::Null => String::new(),
+                    value => csv_field(&value.to_string()),
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+
+            lines.push(line);
+        }
+
+        lines.iter().map(|line| format!("{line}\r\n")).collect()
+    }
+
+    /// An array with an object per row, whose keys are in the order of the columns
+    fn json(&self) -> String {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .cloned()
+                    .zip(row.iter().map(Value::to_json))
+                    .collect::<serde_json::Map<_, _>>()
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_string_pretty(&rows).unwrap_or_default() + "\n"
+    }
+}
+
+/// The latest results, by URI
+#[derive(Debug, Default)]
+pub struct ResultStore {
+    next_id: u64,
+    results: VecDeque<(Url, ResultSet)>,
+}
+
+impl ResultStore {
+    /// Keeps `result`, returning the URI it's served under
+    pub fn insert(&mut self, result: ResultSet) -> Url {
+        self.next_id += 1;
+
+        let uri = Url::parse(&format!("{SCHEME}://results/{}", self.next_id))
+            .expect("result URIs are valid");
+
+        if self.results.len() == KEPT_RESULTS {
+            self.results.pop_front();
+        }
+
+        self.results.push_back((uri.clone(), result));
+
+        uri
+    }
+
+    pub fn get(&self, uri: &Url) -> Option<&ResultSet> {
+        self.results
+            .iter()
+            .find(|(kept, _)| kept == uri)
+            .map(|(_, result)| result)
+    }
+}
@@ -0,0 +1,780 @@
+//! A model of the keyspaces, tables and types known to the server, built by replaying DDL
+//! statements in order.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cql_type::CqlType,
+    function, index,
+    lexer::{Token, TokenKind},
+    materialized_view,
+    statement::{
+        self, Statement, StatementKind, matching_bracket, parse_qualified_name, skip_if_exists,
+    },
+    system_keyspaces,
+};
+
+/// Objects created without a keyspace and without a preceding `USE` are filed under this
+/// keyspace name
+pub const NO_KEYSPACE: &str = "";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Column {
+    pub name: String,
+    pub data_type: CqlType,
+}
+
+/// What an index on a column indexes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IndexTarget {
+    /// A plain `(column)`, which for a non-frozen collection means its values
+    Column,
+    Keys,
+    Values,
+    Entries,
+    Full,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Index {
+    pub name: Option<String>,
+    pub column: String,
+    pub target: IndexTarget,
+    /// The index class given with `USING`, e.g. `'sai'`
+    pub class: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Table {
+    pub keyspace: String,
+    pub name: String,
+    pub columns: Vec<Column>,
+    pub partition_key: Vec<String>,
+    pub clustering_key: Vec<String>,
+    /// The columns declared `STATIC`, shared by the rows of a partition
+    #[serde(default)]
+    pub static_columns: Vec<String>,
+    /// Table options given in the `WITH` clause, as option name and raw value text
+    pub options: Vec<(String, String)>,
+    pub indexes: Vec<Index>,
+}
+
+/// A user-defined type created with `CREATE TYPE`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserType {
+    pub keyspace: String,
+    pub name: String,
+    pub fields: Vec<Column>,
+}
+
+/// A user-defined function, identified by its name and argument types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Function {
+    pub keyspace: String,
+    pub name: String,
+    pub argument_types: Vec<CqlType>,
+    pub return_type: Option<CqlType>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Keyspace {
+    /// Keyspace options given in the `WITH` clause, as option name and raw value text
+    pub options: Vec<(String, String)>,
+    pub tables: BTreeMap<String, Table>,
+    /// Materialized views, modelled as the tables they can be queried as
+    pub views: BTreeMap<String, Table>,
+    /// User-defined functions, including every overload of a name
+    pub functions: Vec<Function>,
+    #[serde(default)]
+    pub types: BTreeMap<String, UserType>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Schema {
+    pub keyspaces: BTreeMap<String, Keyspace>,
+    /// Roles (and legacy users) created with `CREATE ROLE` or `CREATE USER`
+    pub roles: BTreeSet<String>,
+    /// Whether `roles` holds the roles of a cluster, read with its schema, so that a role
+    /// missing from them doesn't exist
+    #[serde(default)]
+    pub cluster_roles: bool,
+    /// The datacenters of the cluster the schema was read from, empty for other schemas
+    #[serde(default)]
+    pub datacenters: BTreeSet<String>,
+    /// The keyspace selected with `USE` before the statements replayed on top of the schema,
+    /// e.g. by the earlier cells of a notebook
+    #[serde(skip)]
+    pub used_keyspace: Option<String>,
+}
+
+impl Keyspace {
+    /// Looks up a table, or a materialized view by the table it can be queried as
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.get(name).or_else(|| self.views.get(name))
+    }
+}
+
+impl Table {
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    /// Whether `name` is a column of the partition key or a clustering column
+    pub fn is_primary_key(&self, name: &str) -> bool {
+        self.partition_key
+            .iter()
+            .chain(&self.clustering_key)
+            .any(|column| column == name)
+    }
+
+    pub fn is_static(&self, name: &str) -> bool {
+        self.static_columns.iter().any(|column| column == name)
+    }
+}
+
+impl UserType {
+    pub fn field(&self, name: &str) -> Option<&Column> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// Joins the text of `tokens`, used to keep option values around for display
+pub fn tokens_text(tokens: &[Token]) -> String {
+    let mut text = String::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        let glued = i == 0
+            || token.is_punctuation(",")
+            || token.is_punctuation(":")
+            || token.is_punctuation(".")
+            || tokens[i - 1].is_punctuation(".")
+            || token.is_punctuation(")")
+            || token.is_punctuation("}")
+            || tokens[i - 1].is_punctuation("(")
+            || tokens[i - 1].is_punctuation("{");
+
+        if !glued {
+            text.push(' ');
+        }
+
+        text.push_str(token.text);
+    }
+
+    text
+}
+
+/// Returns the role a token names. Roles are identifiers, but may also be given as string
+/// literals, which legacy user names commonly are.
+pub fn role_name(token: &Token) -> Option<String> {
+    token.identifier().or_else(|| token.string_value())
+}
+
+/// Parses `name = value AND name = value ...` options, where the options start at `tokens[i]`
+fn parse_options(tokens: &[Token], mut i: usize) -> Vec<(String, String)> {
+    let mut options = vec![];
+
+    while i < tokens.len() {
+        if tokens[i].is_keyword("AND") {
+            i += 1;
+            continue;
+        }
+
+        // Options that aren't `name = value` pairs, e.g. `CLUSTERING ORDER BY (...)` or
+        // `COMPACT STORAGE`, are stored with the rest of the clause as their value
+        let Some(name) = tokens[i].identifier() else {
+            i += 1;
+            continue;
+        };
+
+        let end = (i + 1..tokens.len())
+            .find(|&j| tokens[j].is_keyword("AND") && bracket_depth(&tokens[i..j]) == 0)
+            .unwrap_or(tokens.len());
+
+        let value_start = match tokens.get(i + 1) {
+            Some(token) if token.is_punctuation("=") => i + 2,
+            _ => i + 1,
+        };
+
+        options.push((name, tokens_text(&tokens[value_start.min(end)..end])));
+        i = end;
+    }
+
+    options
+}
+
+fn bracket_depth(tokens: &[Token]) -> i32 {
+    tokens.iter().fold(0, |depth, token| match token.text {
+        "(" | "[" | "{" if token.kind == TokenKind::Punctuation => depth + 1,
+        ")" | "]" | "}" if token.kind == TokenKind::Punctuation => depth - 1,
+        _ => depth,
+    })
+}
+
+/// Parses the columns of a `PRIMARY KEY (...)` definition, where `open` is the index of the
+/// opening parenthesis. Returns the tokens naming the partition key and clustering columns.
+pub fn parse_primary_key<'a>(
+    tokens: &'a [Token<'a>],
+    open: usize,
+) -> Option<(Vec<&'a Token<'a>>, Vec<&'a Token<'a>>)> {
+    if !tokens.get(open)?.is_punctuation("(") {
+        return None;
+    }
+
+    let close = matching_bracket(tokens, open).unwrap_or(tokens.len());
+    let parts = statement::split_commas(&tokens[open + 1..close]);
+
+    let mut parts = parts.into_iter();
+    let first = parts.next()?;
+
+    let partition_key = match first.first() {
+        Some(token) if token.is_punctuation("(") => {
+            first.iter().filter(|token| token.is_identifier()).collect()
+        }
+        Some(token) if token.is_identifier() => vec![token],
+        _ => return None,
+    };
+
+    let clustering_key = parts
+        .filter_map(|part| part.first().filter(|token| token.is_identifier()))
+        .collect();
+
+    Some((partition_key, clustering_key))
+}
+
+/// The names of the columns given by `tokens`
+fn column_names(tokens: &[&Token]) -> Vec<String> {
+    tokens
+        .iter()
+        .filter_map(|token| token.identifier())
+        .collect()
+}
+
+impl Schema {
+    /// Builds a schema by replaying `statements` in order
+    pub fn from_statements(statements: &[Statement]) -> Self {
+        Schema::default().with_statements(statements)
+    }
+
+    /// Replays `statements` in order on top of this schema
+    pub fn with_statements(mut self, statements: &[Statement]) -> Self {
+        let mut keyspace = self.used_keyspace.clone();
+
+        for statement in statements {
+            self.apply(statement, &mut keyspace);
+        }
+
+        self
+    }
+
+    fn keyspace_mut(&mut self, name: &str) -> &mut Keyspace {
+        self.keyspaces.entry(name.to_string()).or_default()
+    }
+
+    /// Resolves the keyspace an object named with `keyspace` lives in, given the keyspace
+    /// selected with `USE`
+    pub fn resolve_keyspace(keyspace: Option<String>, current: &Option<String>) -> String {
+        keyspace
+            .or_else(|| current.clone())
+            .unwrap_or_else(|| NO_KEYSPACE.to_string())
+    }
+
+    /// Applies the effects of a single statement on the schema. `current_keyspace` is the
+    /// keyspace selected with `USE`, which is updated when the statement is a `USE` statement.
+    pub fn apply(&mut self, statement: &Statement, current_keyspace: &mut Option<String>) {
+        let tokens = statement.tokens;
+
+        match statement.kind() {
+            StatementKind::Use => {
+                if let Some(name) = tokens.get(1).and_then(Token::identifier) {
+                    *current_keyspace = Some(name);
+                }
+            }
+            StatementKind::CreateKeyspace => {
+                let mut i = skip_if_exists(tokens, 2);
+                let Some(name) = parse_qualified_name(tokens, &mut i) else {
+                    return;
+                };
+
+                let options = match tokens.get(i) {
+                    Some(token) if token.is_keyword("WITH") => parse_options(tokens, i + 1),
+                    _ => vec![],
+                };
+
+                let keyspace = self.keyspace_mut(&name.name);
+                keyspace.options = options;
+            }
+            StatementKind::CreateTable => {
+                if let Some(table) = parse_create_table(statement, current_keyspace) {
+                    self.keyspace_mut(&table.keyspace)
+                        .tables
+                        .insert(table.name.clone(), table);
+                }
+            }
+            StatementKind::AlterTable => self.apply_alter_table(statement, current_keyspace),
+            StatementKind::CreateType => {
+                if let Some(user_type) = parse_create_type(statement, current_keyspace) {
+                    self.keyspace_mut(&user_type.keyspace)
+                        .types
+                        .insert(user_type.name.clone(), user_type);
+                }
+            }
+            StatementKind::AlterType => self.apply_alter_type(statement, current_keyspace),
+            StatementKind::CreateMaterializedView => {
+                let Some(view) = materialized_view::parse(statement) else {
+                    return;
+                };
+
+                let Some(base) = view.base_table(self, current_keyspace.as_deref()) else {
+                    return;
+                };
+
+                let keyspace = Self::resolve_keyspace(view.name.keyspace.clone(), current_keyspace);
+                let view = view.to_table(base, keyspace);
+
+                self.keyspace_mut(&view.keyspace)
+                    .views
+                    .insert(view.name.clone(), view);
+            }
+            StatementKind::CreateIndex => {
+                let Some(definition) = index::parse(statement) else {
+                    return;
+                };
+
+                let (Some(name), Some(index)) = (&definition.table, definition.to_index()) else {
+                    return;
+                };
+
+                let keyspace = Self::resolve_keyspace(name.keyspace.clone(), current_keyspace);
+
+                if let Some(table) = self
+                    .keyspaces
+                    .get_mut(&keyspace)
+                    .and_then(|keyspace| keyspace.tables.get_mut(&name.name))
+                {
+                    table.indexes.push(index);
+                }
+            }
+            StatementKind::CreateFunction => {
+                let Some(created) = function::parse_function(statement, current_keyspace) else {
+                    return;
+                };
+
+                let functions = &mut self.keyspace_mut(&created.keyspace).functions;
+
+                // `CREATE OR REPLACE` replaces the overload with the same argument types
+                functions.retain(|function| {
+                    function.name != created.name
+                        || function.argument_types != created.argument_types
+                });
+                functions.push(created);
+            }
+            StatementKind::CreateRole | StatementKind::CreateUser => {
+                let i = skip_if_exists(tokens, 2);
+
+                if let Some(name) = tokens.get(i).and_then(role_name) {
+                    self.roles.insert(name);
+                }
+            }
+            StatementKind::Drop => {
+                let Some(object) = tokens.get(1) else {
+                    return;
+                };
+
+                if object.is_keyword("ROLE") || object.is_keyword("USER") {
+                    let i = skip_if_exists(tokens, 2);
+
+                    if let Some(name) = tokens.get(i).and_then(role_name) {
+                        self.roles.remove(&name);
+                    }
+
+                    return;
+                }
+
+                // `DROP MATERIALIZED VIEW` names its object with two words
+                let is_view = object.is_keyword("MATERIALIZED");
+
+                let mut i = skip_if_exists(tokens, if is_view { 3 } else { 2 });
+                let Some(name) = parse_qualified_name(tokens, &mut i) else {
+                    return;
+                };
+
+                if object.is_keyword("KEYSPACE") || object.is_keyword("SCHEMA") {
+                    self.keyspaces.remove(&name.name);
+                    return;
+                }
+
+                let keyspace = Self::resolve_keyspace(name.keyspace, current_keyspace);
+                let Some(keyspace) = self.keyspaces.get_mut(&keyspace) else {
+                    return;
+                };
+
+                if object.is_keyword("TABLE") || object.is_keyword("COLUMNFAMILY") {
+                    keyspace.tables.remove(&name.name);
+                } else if is_view {
+                    keyspace.views.remove(&name.name);
+                } else if object.is_keyword("FUNCTION") {
+                    // Without argument types, `DROP FUNCTION` drops the only overload
+                    let argument_types =
+                        tokens.get(i).filter(|t| t.is_punctuation("(")).map(|_| {
+                            let close = matching_bracket(tokens, i).unwrap_or(tokens.len());
+
+                            statement::split_commas(&tokens[i + 1..close])
+                                .into_iter()
+                                .filter_map(|argument| CqlType::parse(argument, &mut 0))
+                                .collect::<Vec<_>>()
+                        });
+
+                    keyspace.functions.retain(|function| {
+                        function.name != name.name
+                            || argument_types
+                                .as_ref()
+                                .is_some_and(|types| *types != function.argument_types)
+                    });
+                } else if object.is_keyword("TYPE") {
+                    keyspace.types.remove(&name.name);
+                } else if object.is_keyword("INDEX") {
+                    for table in keyspace.tables.values_mut() {
+                        table
+                            .indexes
+                            .retain(|index| index.name.as_ref() != Some(&name.name));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_alter_table(&mut self, statement: &Statement, current_keyspace: &Option<String>) {
+        let tokens = statement.tokens;
+        let mut i = skip_if_exists(tokens, 2);
+        let Some(name) = parse_qualified_name(tokens, &mut i) else {
+            return;
+        };
+
+        let keyspace = Self::resolve_keyspace(name.keyspace, current_keyspace);
+        let Some(table) = self
+            .keyspaces
+            .get_mut(&keyspace)
+            .and_then(|keyspace| keyspace.tables.get_mut(&name.name))
+        else {
+            return;
+        };
+
+        let Some(operation) = tokens.get(i) else {
+            return;
+        };
+
+        let i = skip_if_exists(tokens, i + 1);
+
+        if operation.is_keyword("ADD") {
+            // Either `ADD a int, b text` or `ADD (a int, b text)`
+            let definitions = match tokens.get(i) {
+                Some(token) if token.is_punctuation("(") => match matching_bracket(tokens, i) {
+                    Some(close) => &tokens[i + 1..close],
+                    None => &tokens[i + 1..],
+                },
+                _ => &tokens[i..],
+            };
+
+            for definition in statement::split_commas(definitions) {
+                if let Some(column) = parse_column(definition) {
+                    table
+                        .columns
+                        .retain(|existing| existing.name != column.name);
+                    table.static_columns.retain(|name| *name != column.name);
+
+                    if is_static(definition) {
+                        table.static_columns.push(column.name.clone());
+                    }
+
+                    table.columns.push(column);
+                }
+            }
+        } else if operation.is_keyword("DROP") {
+            let names = match tokens.get(i) {
+                Some(token) if token.is_punctuation("(") => match matching_bracket(tokens, i) {
+                    Some(close) => &tokens[i + 1..close],
+                    None => &tokens[i + 1..],
+                },
+                _ => &tokens[i..(i + 1).min(tokens.len())],
+            };
+
+            // Skips the drops `alter_table::check` rejects, leaving the primary key whole
+            for name in names.iter().filter_map(Token::identifier) {
+                if table.is_primary_key(&name) {
+                    continue;
+                }
+
+                table.columns.retain(|column| column.name != name);
+                table.static_columns.retain(|column| *column != name);
+            }
+        } else if operation.is_keyword("RENAME") {
+            // Only primary key columns can be renamed, so the others keep their names
+            for (from, to) in parse_renames(tokens, i) {
+                if !table.is_primary_key(&from) {
+                    continue;
+                }
+
+                if let Some(column) = table.columns.iter_mut().find(|c| c.name == from) {
+                    column.name = to.clone();
+                }
+
+                for key in table
+                    .partition_key
+                    .iter_mut()
+                    .chain(table.clustering_key.iter_mut())
+                {
+                    if *key == from {
+                        *key = to.clone();
+                    }
+                }
+            }
+        } else if operation.is_keyword("WITH") {
+            for (name, value) in parse_options(tokens, i) {
+                table.options.retain(|(existing, _)| *existing != name);
+                table.options.push((name, value));
+            }
+        }
+    }
+
+    fn apply_alter_type(&mut self, statement: &Statement, current_keyspace: &Option<String>) {
+        let tokens = statement.tokens;
+        let mut i = skip_if_exists(tokens, 2);
+        let Some(name) = parse_qualified_name(tokens, &mut i) else {
+            return;
+        };
+
+        let keyspace = Self::resolve_keyspace(name.keyspace, current_keyspace);
+        let Some(user_type) = self
+            .keyspaces
+            .get_mut(&keyspace)
+            .and_then(|keyspace| keyspace.types.get_mut(&name.name))
+        else {
+            return;
+        };
+
+        let Some(operation) = tokens.get(i) else {
+            return;
+        };
+
+        let i = skip_if_exists(tokens, i + 1);
+
+        if operation.is_keyword("ADD") {
+            if let Some(field) = parse_column(&tokens[i.min(tokens.len())..]) {
+                user_type
+                    .fields
+                    .retain(|existing| existing.name != field.name);
+                user_type.fields.push(field);
+            }
+        } else if operation.is_keyword("RENAME") {
+            for (from, to) in parse_renames(tokens, i) {
+                if let Some(field) = user_type.fields.iter_mut().find(|f| f.name == from) {
+                    field.name = to;
+                }
+            }
+        }
+    }
+
+    /// Looks up a user-defined type. Without an explicit keyspace, the first type with a
+    /// matching name in any keyspace is returned.
+    pub fn user_type(&self, keyspace: Option<&str>, name: &str) -> Option<&UserType> {
+        match keyspace {
+            Some(keyspace) => self.keyspaces.get(keyspace)?.types.get(name),
+            None => self
+                .keyspaces
+                .values()
+                .find_map(|keyspace| keyspace.types.get(name)),
+        }
+    }
+
+    /// Resolves `data_type` to the user-defined type it names, if any, unqualified names being
+    /// looked up in `keyspace`, the keyspace of the table or type using it
+    pub fn resolve_user_type(&self, data_type: &CqlType, keyspace: &str) -> Option<&UserType> {
+        let CqlType::UserDefined {
+            keyspace: type_keyspace,
+            name,
+        } = data_type.unfrozen()
+        else {
+            return None;
+        };
+
+        self.user_type(Some(type_keyspace.as_deref().unwrap_or(keyspace)), name)
+            .or_else(|| self.user_type(None, name))
+    }
+
+    /// Looks up a table or materialized view. Without an explicit keyspace, the first table
+    /// with a matching name in any keyspace is returned. The tables of the system keyspaces
+    /// are only found in their keyspace.
+    pub fn table(&self, keyspace: Option<&str>, name: &str) -> Option<&Table> {
+        match keyspace {
+            Some(keyspace) => match self.keyspaces.get(keyspace) {
+                Some(keyspace) => keyspace.table(name),
+                None => system_keyspaces::keyspace(keyspace)?.table(name),
+            },
+            None => self
+                .keyspaces
+                .values()
+                .find_map(|keyspace| keyspace.table(name)),
+        }
+    }
+
+    /// Resolves the table a DML statement operates on, using `current_keyspace` for
+    /// unqualified table names
+    pub fn statement_table(
+        &self,
+        statement: &Statement,
+        current_keyspace: Option<&str>,
+    ) -> Option<&Table> {
+        let target = statement.target_table()?;
+        let keyspace = target.keyspace.as_deref().or(current_keyspace);
+
+        self.table(keyspace, &target.name)
+            .or_else(|| self.table(None, &target.name))
+    }
+}
+
+/// Returns the keyspace selected by the last `USE` statement before `statements[index]`
+pub fn keyspace_at(statements: &[Statement], index: usize) -> Option<String> {
+    statements[..index.min(statements.len())]
+        .iter()
+        .rev()
+        .find(|statement| statement.kind() == StatementKind::Use)
+        .and_then(|statement| statement.tokens.get(1)?.identifier())
+}
+
+/// Parses `a TO b AND c TO d` starting at `tokens[i]`
+fn parse_renames(tokens: &[Token], mut i: usize) -> Vec<(String, String)> {
+    let mut renames = vec![];
+
+    while let (Some(from), Some(to_keyword), Some(to)) =
+        (tokens.get(i), tokens.get(i + 1), tokens.get(i + 2))
+    {
+        if !to_keyword.is_keyword("TO") {
+            break;
+        }
+
+        if let (Some(from), Some(to)) = (from.identifier(), to.identifier()) {
+            renames.push((from, to));
+        }
+
+        i += 3;
+
+        match tokens.get(i) {
+            Some(token) if token.is_keyword("AND") => i += 1,
+            _ => break,
+        }
+    }
+
+    renames
+}
+
+/// Parses a column definition such as `name text STATIC`
+fn parse_column(definition: &[Token]) -> Option<Column> {
+    let name_token = definition.first()?;
+    let name = name_token.identifier()?;
+    let mut i = 1;
+    let data_type = CqlType::parse(definition, &mut i)?;
+
+    Some(Column { name, data_type })
+}
+
+/// Whether a column definition declares the column `STATIC`, e.g. `balance int STATIC`
+pub fn is_static(definition: &[Token]) -> bool {
+    definition
+        .iter()
+        .skip(1)
+        .any(|token| token.is_keyword("STATIC"))
+}
+
+fn parse_create_type(statement: &Statement, current_keyspace: &Option<String>) -> Option<UserType> {
+    let tokens = statement.tokens;
+    let mut i = skip_if_exists(tokens, 2);
+    let name = parse_qualified_name(tokens, &mut i)?;
+
+    if !tokens.get(i)?.is_punctuation("(") {
+        return None;
+    }
+
+    let close = matching_bracket(tokens, i).unwrap_or(tokens.len());
+
+    Some(UserType {
+        keyspace: Schema::resolve_keyspace(name.keyspace, current_keyspace),
+        name: name.name,
+        fields: statement::split_commas(&tokens[i + 1..close])
+            .into_iter()
+            .filter_map(parse_column)
+            .collect(),
+    })
+}
+
+/// Parses the table a `CREATE TABLE` statement defines, in `current_keyspace` unless qualified
+pub fn parse_create_table(
+    statement: &Statement,
+    current_keyspace: &Option<String>,
+) -> Option<Table> {
+    let tokens = statement.tokens;
+    let mut i = skip_if_exists(tokens, 2);
+    let name = parse_qualified_name(tokens, &mut i)?;
+
+    if !tokens.get(i)?.is_punctuation("(") {
+        return None;
+    }
+
+    let close = matching_bracket(tokens, i).unwrap_or(tokens.len());
+    let body = &tokens[i + 1..close.min(tokens.len())];
+
+    let mut columns = vec![];
+    let mut partition_key = vec![];
+    let mut clustering_key = vec![];
+    let mut static_columns = vec![];
+
+    for definition in statement::split_commas(body) {
+        let is_primary_key_definition = definition.first().is_some_and(|t| t.is_keyword("PRIMARY"))
+            && definition.get(1).is_some_and(|t| t.is_keyword("KEY"));
+
+        if is_primary_key_definition {
+            if let Some((partition, clustering)) = parse_primary_key(definition, 2) {
+                partition_key = column_names(&partition);
+                clustering_key = column_names(&clustering);
+            }
+            continue;
+        }
+
+        let Some(column) = parse_column(definition) else {
+            continue;
+        };
+
+        // `id uuid PRIMARY KEY`
+        let inline_primary_key = definition
+            .windows(2)
+            .any(|pair| pair[0].is_keyword("PRIMARY") && pair[1].is_keyword("KEY"));
+
+        if inline_primary_key {
+            partition_key = vec![column.name.clone()];
+        }
+
+        if is_static(definition) {
+            static_columns.push(column.name.clone());
+        }
+
+        columns.push(column);
+    }
+
+    let options = match tokens.get(close + 1) {
+        Some(token) if token.is_keyword("WITH") => parse_options(tokens, close + 2),
+        _ => vec![],
+    };
+
+    Some(Table {
+        keyspace: Schema::resolve_keyspace(name.keyspace, current_keyspace),
+        name: name.name,
+        columns,
+        partition_key,
+        clustering_key,
+        static_columns,
+        options,
+        indexes: vec![],
+    })
+}
@@ -0,0 +1,542 @@
+//! Compares the schema defined by the workspace's DDL with the schema of a cluster, to find the
+//! drift between migration files and what's actually deployed.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    cql_type::CqlType,
+    lexer::{self, Token},
+    schema::{
+        Column, Function, Index, IndexTarget, Keyspace, NO_KEYSPACE, Schema, Table, tokens_text,
+    },
+    statement::split_commas,
+};
+
+/// A schema object defined on one side only
+#[derive(Debug, Clone, Copy)]
+pub enum Object<'a> {
+    Keyspace(&'a str, &'a Keyspace),
+    Table(&'a Table),
+    View(&'a Table),
+    Column(&'a Table, &'a Column),
+    Index(&'a Table, &'a Index),
+    Function(&'a Function),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Difference<'a> {
+    /// Defined by the workspace, but missing from the cluster
+    Missing(Object<'a>),
+    /// On the cluster, but not defined by the workspace
+    Extra(Object<'a>),
+    ColumnType {
+        table: &'a Table,
+        workspace: &'a Column,
+        cluster: &'a Column,
+    },
+    PrimaryKey {
+        workspace: &'a Table,
+        cluster: &'a Table,
+    },
+    /// An option of a keyspace, or of a table when `table` is given
+    Option {
+        keyspace: &'a str,
+        table: Option<&'a str>,
+        name: &'a str,
+        workspace: &'a str,
+        cluster: &'a str,
+    },
+}
+
+impl Object<'_> {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Object::Keyspace(..) => "keyspace",
+            Object::Table(_) => "table",
+            Object::View(_) => "view",
+            Object::Column(..) => "column",
+            Object::Index(..) => "index",
+            Object::Function(_) => "function",
+        }
+    }
+
+    pub fn keyspace(&self) -> &str {
+        match self {
+            Object::Keyspace(name, _) => name,
+            Object::Table(table)
+            | Object::View(table)
+            | Object::Column(table, _)
+            | Object::Index(table, _) => &table.keyspace,
+            Object::Function(function) => &function.keyspace,
+        }
+    }
+
+    /// The table the object belongs to, or is
+    pub fn table(&self) -> Option<&str> {
+        match self {
+            Object::Table(table)
+            | Object::View(table)
+            | Object::Column(table, _)
+            | Object::Index(table, _) => Some(&table.name),
+            Object::Keyspace(..) | Object::Function(_) => None,
+        }
+    }
+
+    /// The name of an object within a table, or of a function with its argument types
+    pub fn name(&self) -> Option<String> {
+        match self {
+            Object::Column(_, column) => Some(column.name.clone()),
+            Object::Index(_, index) => Some(index_description(index)),
+            Object::Function(function) => Some(function_signature(function)),
+            Object::Keyspace(..) | Object::Table(_) | Object::View(_) => None,
+        }
+    }
+
+    /// Names the object in messages, e.g. `column ks.users.email`
+    pub fn describe(&self) -> String {
+        let mut path = vec![self.keyspace().to_string()];
+        path.extend(self.table().map(str::to_string));
+        path.extend(self.name());
+
+        format!("{} {}", self.kind(), path.join("."))
+    }
+}
+
+impl Difference<'_> {
+    pub fn keyspace(&self) -> &str {
+        match self {
+            Difference::Missing(object) | Difference::Extra(object) => object.keyspace(),
+            Difference::ColumnType { table, .. } => &table.keyspace,
+            Difference::PrimaryKey { workspace, .. } => &workspace.keyspace,
+            Difference::Option { keyspace, .. } => keyspace,
+        }
+    }
+
+    pub fn table(&self) -> Option<&str> {
+        match self {
+            Difference::Missing(object) | Difference::Extra(object) => object.table(),
+            Difference::ColumnType { table, .. } => Some(&table.name),
+            Difference::PrimaryKey { workspace, .. } => Some(&workspace.name),
+            Difference::Option { table, .. } => *table,
+        }
+    }
+
+    /// The definitions on the workspace side and on the cluster side, for differences between
+    /// both
+    pub fn values(&self) -> Option<(String, String)> {
+        match self {
+            Difference::Missing(_) | Difference::Extra(_) => None,
+            Difference::ColumnType {
+                workspace, cluster, ..
+            } => Some((
+                workspace.data_type.to_string(),
+                cluster.data_type.to_string(),
+            )),
+            Difference::PrimaryKey { workspace, cluster } => {
+                Some((primary_key(workspace), primary_key(cluster)))
+            }
+            Difference::Option {
+                workspace, cluster, ..
+            } => Some((workspace.to_string(), cluster.to_string())),
+        }
+    }
+
+    /// Describes the difference to the user
+    pub fn message(&self) -> String {
+        let qualified = |table: &Table| format!("{}.{}", table.keyspace, table.name);
+
+        let subject = match self {
+            Difference::Missing(object) => {
+                return format!("The {} is missing from the cluster", object.describe());
+            }
+            Difference::Extra(object) => {
+                return format!("The {} isn't defined in the workspace", object.describe());
+            }
+            Difference::ColumnType {
+                table, workspace, ..
+            } => format!("The type of column {}.{}", qualified(table), workspace.name),
+            Difference::PrimaryKey { workspace, .. } => {
+                format!("The primary key of {}", qualified(workspace))
+            }
+            Difference::Option {
+                keyspace,
+                table,
+                name,
+                ..
+            } => match table {
+                Some(table) => format!("Option {name} of table {keyspace}.{table}"),
+                None => format!("Option {name} of keyspace {keyspace}"),
+            },
+        };
+
+        let (mut workspace, cluster) = self.values().unwrap_or_default();
+
+        if workspace.is_empty() {
+            workspace = "unset".to_string();
+        }
+
+        format!("{subject} is {workspace} in the workspace, but {cluster} on the cluster")
+    }
+}
+
+/// `((a, b), c)`
+fn primary_key(table: &Table) -> String {
+    let partition_key = table.partition_key.join(", ");
+
+    match table.clustering_key.is_empty() {
+        true => format!("(({partition_key}))"),
+        false => format!("(({partition_key}), {})", table.clustering_key.join(", ")),
+    }
+}
+
+/// The name of an index, or what it indexes when it's unnamed, e.g. `keys(attributes)`
+fn index_description(index: &Index) -> String {
+    let target = |function| format!("{function}({})", index.column);
+
+    match (&index.name, index.target) {
+        (Some(name), _) => name.clone(),
+        (None, IndexTarget::Column) => index.column.clone(),
+        (None, IndexTarget::Keys) => target("keys"),
+        (None, IndexTarget::Values) => target("values"),
+        (None, IndexTarget::Entries) => target("entries"),
+        (None, IndexTarget::Full) => target("full"),
+    }
+}
+
+fn function_signature(function: &Function) -> String {
+    let argument_types = function
+        .argument_types
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("{}({argument_types})", function.name)
+}
+
+/// Spells a type the way the cluster reports it: `varchar` is an alias of `text`, and user-defined
+/// types are always in the keyspace of the table
+fn canonical_type(data_type: &CqlType) -> CqlType {
+    let canonical = |inner: &CqlType| Box::new(canonical_type(inner));
+
+    match data_type {
+        CqlType::Native(name) if name == "varchar" => CqlType::Native("text".to_string()),
+        CqlType::List(element) => CqlType::List(canonical(element)),
+        CqlType::Set(element) => CqlType::Set(canonical(element)),
+        CqlType::Map(key, value) => CqlType::Map(canonical(key), canonical(value)),
+        CqlType::Tuple(elements) => CqlType::Tuple(elements.iter().map(canonical_type).collect()),
+        CqlType::Vector(element, dimension) => CqlType::Vector(canonical(element), *dimension),
+        CqlType::Frozen(inner) => CqlType::Frozen(canonical(inner)),
+        CqlType::UserDefined { name, .. } => CqlType::UserDefined {
+            keyspace: None,
+            name: name.clone(),
+        },
+        other => other.clone(),
+    }
+}
+
+/// The value of an option with the spelling differences between DDL and the cluster ironed
+/// out: quotes around numbers and booleans, and package names of classes
+fn scalar(tokens: &[Token]) -> String {
+    let text = match tokens {
+        [token] => token
+            .string_value()
+            .unwrap_or_else(|| token.text.to_ascii_lowercase()),
+        tokens => return tokens_text(tokens),
+    };
+
+    match text.parse::<f64>() {
+        Ok(number) => number.to_string(),
+        Err(_) if text.eq_ignore_ascii_case("true") || text.eq_ignore_ascii_case("false") => {
+            text.to_ascii_lowercase()
+        }
+        Err(_) => text,
+    }
+}
+
+/// Parses a map literal option, e.g. `{'class': 'SimpleStrategy', 'replication_factor': 3}`
+fn map_option(tokens: &[Token]) -> Option<BTreeMap<String, String>> {
+    let (open, rest) = tokens.split_first()?;
+    let (close, entries) = rest.split_last()?;
+
+    if !open.is_punctuation("{") || !close.is_punctuation("}") {
+        return None;
+    }
+
+    split_commas(entries)
+        .into_iter()
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let colon = entry.iter().position(|token| token.is_punctuation(":"))?;
+            let key = scalar(&entry[..colon]);
+            let mut value = scalar(&entry[colon + 1..]);
+
+            // `SimpleStrategy` and `org.apache.cassandra.locator.SimpleStrategy` are the same
+            if key == "class"
+                && let Some((_, class)) = value.rsplit_once('.')
+            {
+                value = class.to_string();
+            }
+
+            Some((key, value))
+        })
+        .collect()
+}
+
+/// The direction of each clustering column in a `ORDER BY (a DESC, b ASC)` option value
+fn clustering_order(tokens: &[Token]) -> BTreeMap<String, bool> {
+    let Some(open) = tokens.iter().position(|token| token.is_punctuation("(")) else {
+        return BTreeMap::new();
+    };
+
+    split_commas(&tokens[open + 1..])
+        .into_iter()
+        .filter_map(|column| {
+            let name = column.first()?.identifier()?;
+            let descending = column.iter().any(|token| token.is_keyword("DESC"));
+
+            Some((name, descending))
+        })
+        .collect()
+}
+
+/// Whether the value the workspace gives an option matches the cluster's. Map options only
+/// need to match on the keys the workspace sets, since the cluster fills in the defaults,
+/// except for `replication`, which the cluster stores as given.
+fn option_matches(name: &str, workspace: &str, cluster: &str, clustering_key: &[String]) -> bool {
+    let workspace = lexer::tokenize(workspace);
+    let cluster = lexer::tokenize(cluster);
+
+    if name == "clustering" {
+        let (workspace, cluster) = (clustering_order(&workspace), clustering_order(&cluster));
+
+        // Columns left out of the clause are in ascending order
+        return clustering_key.iter().all(|column| {
+            workspace.get(column).copied().unwrap_or_default()
+                == cluster.get(column).copied().unwrap_or_default()
+        });
+    }
+
+    match (map_option(&workspace), map_option(&cluster)) {
+        (Some(workspace), Some(cluster)) if name == "replication" => workspace == cluster,
+        (Some(workspace), Some(cluster)) => workspace
+            .iter()
+            .all(|(key, value)| cluster.get(key) == Some(value)),
+        _ => scalar(&workspace) == scalar(&cluster),
+    }
+}
+
+/// Compares the options the workspace gives with the cluster's, ignoring options the cluster
+/// doesn't report
+fn diff_options<'a>(
+    differences: &mut Vec<Difference<'a>>,
+    keyspace: &'a str,
+    table: Option<&'a Table>,
+    workspace: &'a [(String, String)],
+    cluster: &'a [(String, String)],
+) {
+    let clustering_key = table.map(|table| table.clustering_key.as_slice());
+
+    // Without a `CLUSTERING ORDER BY` clause, clustering columns are in ascending order, which
+    // the cluster reports like any other order
+    let implicit_clustering = match table {
+        Some(_) if !workspace.iter().any(|(name, _)| name == "clustering") => {
+            Some(("clustering", ""))
+        }
+        _ => None,
+    };
+
+    let options = workspace
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .chain(implicit_clustering);
+
+    for (name, value) in options {
+        let reported = cluster
+            .iter()
+            .find(|(cluster_name, _)| cluster_name == name)
+            .map(|(_, value)| value.as_str());
+
+        // Only keyspaces with durable writes turned off report the option
+        let reported = match (reported, name, table) {
+            (None, "durable_writes", None) => Some("true"),
+            (reported, ..) => reported,
+        };
+
+        let Some(reported) = reported else {
+            continue;
+        };
+
+        if !option_matches(name, value, reported, clustering_key.unwrap_or_default()) {
+            differences.push(Difference::Option {
+                keyspace,
+                table: table.map(|table| table.name.as_str()),
+                name,
+                workspace: value,
+                cluster: reported,
+            });
+        }
+    }
+}
+
+fn diff_tables<'a>(
+    differences: &mut Vec<Difference<'a>>,
+    workspace: &'a Table,
+    cluster: &'a Table,
+) {
+    for column in &workspace.columns {
+        match cluster.column(&column.name) {
+            Some(cluster_column)
+                if canonical_type(&column.data_type)
+                    != canonical_type(&cluster_column.data_type) =>
+            {
+                differences.push(Difference::ColumnType {
+                    table: workspace,
+                    workspace: column,
+                    cluster: cluster_column,
+                });
+            }
+            Some(_) => {}
+            None => differences.push(Difference::Missing(Object::Column(workspace, column))),
+        }
+    }
+
+    for column in &cluster.columns {
+        if workspace.column(&column.name).is_none() {
+            differences.push(Difference::Extra(Object::Column(cluster, column)));
+        }
+    }
+
+    if workspace.partition_key != cluster.partition_key
+        || workspace.clustering_key != cluster.clustering_key
+    {
+        differences.push(Difference::PrimaryKey { workspace, cluster });
+    }
+
+    let same_index = |a: &Index, b: &Index| a.column == b.column && a.target == b.target;
+
+    for index in &workspace.indexes {
+        if !cluster.indexes.iter().any(|other| same_index(index, other)) {
+            differences.push(Difference::Missing(Object::Index(workspace, index)));
+        }
+    }
+
+    for index in &cluster.indexes {
+        if !workspace
+            .indexes
+            .iter()
+            .any(|other| same_index(index, other))
+        {
+            differences.push(Difference::Extra(Object::Index(cluster, index)));
+        }
+    }
+}
+
+fn diff_keyspaces<'a>(
+    differences: &mut Vec<Difference<'a>>,
+    name: &'a str,
+    workspace: &'a Keyspace,
+    cluster: &'a Keyspace,
+) {
+    diff_options(
+        differences,
+        name,
+        None,
+        &workspace.options,
+        &cluster.options,
+    );
+
+    let views = [
+        (&workspace.tables, &cluster.tables, false),
+        (&workspace.views, &cluster.views, true),
+    ];
+
+    for (workspace_tables, cluster_tables, view) in views {
+        let object = |table| match view {
+            true => Object::View(table),
+            false => Object::Table(table),
+        };
+
+        for (table_name, table) in workspace_tables {
+            match cluster_tables.get(table_name) {
+                Some(cluster_table) => {
+                    diff_tables(differences, table, cluster_table);
+
+                    if !view {
+                        diff_options(
+                            differences,
+                            name,
+                            Some(table),
+                            &table.options,
+                            &cluster_table.options,
+                        );
+                    }
+                }
+                None => differences.push(Difference::Missing(object(table))),
+            }
+        }
+
+        for (table_name, table) in cluster_tables {
+            if !workspace_tables.contains_key(table_name) {
+                differences.push(Difference::Extra(object(table)));
+            }
+        }
+    }
+
+    let same_function = |a: &Function, b: &Function| {
+        a.name == b.name
+            && a.argument_types.len() == b.argument_types.len()
+            && a.argument_types
+                .iter()
+                .zip(&b.argument_types)
+                .all(|(a, b)| canonical_type(a) == canonical_type(b))
+    };
+
+    for function in &workspace.functions {
+        if !cluster
+            .functions
+            .iter()
+            .any(|other| same_function(function, other))
+        {
+            differences.push(Difference::Missing(Object::Function(function)));
+        }
+    }
+
+    for function in &cluster.functions {
+        if !workspace
+            .functions
+            .iter()
+            .any(|other| same_function(function, other))
+        {
+            differences.push(Difference::Extra(Object::Function(function)));
+        }
+    }
+}
+
+/// Lists the differences between the schema defined by the workspace and the schema of the
+/// cluster, keyspace by keyspace. Objects created without a keyspace can't be compared, and
+/// are left out.
+pub fn diff<'a>(workspace: &'a Schema, cluster: &'a Schema) -> Vec<Difference<'a>> {
+    let mut differences = vec![];
+
+    for (name, keyspace) in &workspace.keyspaces {
+        if name == NO_KEYSPACE {
+            continue;
+        }
+
+        match cluster.keyspaces.get(name) {
+            Some(cluster_keyspace) => {
+                diff_keyspaces(&mut differences, name, keyspace, cluster_keyspace);
+            }
+            None => differences.push(Difference::Missing(Object::Keyspace(name, keyspace))),
+        }
+    }
+
+    for (name, keyspace) in &cluster.keyspaces {
+        if !workspace.keyspaces.contains_key(name) {
+            differences.push(Difference::Extra(Object::Keyspace(name, keyspace)));
+        }
+    }
+
+    differences
+}
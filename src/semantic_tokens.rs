@@ -0,0 +1,342 @@
+//! Semantic tokens: the keywords, schema objects, literals and comments of a document,
+//! classified against the schema so that e.g. a table and a column are told apart where the
+//! editor's grammar only sees words. The tokens of each document's last response are kept, for
+//! clients asking for the delta from them after an edit to receive only the changed runs.
+
+use std::collections::HashMap;
+
+use tower_lsp::lsp_types::{
+    Range, SemanticToken, SemanticTokenType, SemanticTokensEdit, SemanticTokensLegend, Url,
+};
+
+use crate::{
+    cql_type::NATIVE_TYPES,
+    document::{PositionEncodingKind, TextDocument},
+    keywords,
+    lexer::{self, Token, TokenKind, is_reserved},
+    schema::{self, Schema, Table},
+    statement::{self, Dialect},
+};
+
+/// The token types of the legend, indexed by the tokens' `token_type`
+const TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::NAMESPACE,
+    SemanticTokenType::CLASS,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::STRING,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::OPERATOR,
+    SemanticTokenType::COMMENT,
+];
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: vec![],
+    }
+}
+
+/// The index of `token_type` in the legend
+fn index(token_type: &SemanticTokenType) -> u32 {
+    TOKEN_TYPES
+        .iter()
+        .position(|t| t == token_type)
+        .unwrap_or_default() as u32
+}
+
+/// Whether `word` is part of a keyword of the keyword database, e.g. `KEY` of `PRIMARY KEY`
+fn is_keyword(word: &str) -> bool {
+    is_reserved(word)
+        || keywords::all().any(|keyword| {
+            keyword
+                .label
+                .split(' ')
+                .any(|part| part.eq_ignore_ascii_case(word))
+        })
+}
+
+/// Classifies the identifier `tokens[i]` of a statement on `table` against the schema: a
+/// keyspace qualifying what follows it, a table, user-defined type or function of a keyspace,
+/// a column of the table, a keyspace, or a keyword where it's none of those
+fn classify_identifier(
+    tokens: &[Token],
+    i: usize,
+    schema: &Schema,
+    keyspace: Option<&str>,
+    table: Option<&Table>,
+) -> SemanticTokenType {
+    let token = &tokens[i];
+    let Some(name) = token.identifier() else {
+        return SemanticTokenType::VARIABLE;
+    };
+
+    let unquoted = token.kind == TokenKind::Word;
+
+    if unquoted && is_reserved(&name) {
+        return SemanticTokenType::KEYWORD;
+    }
+
+    let followed_by = |punctuation| {
+        tokens
+            .get(i + 1)
+            .is_some_and(|t| t.is_punctuation(punctuation))
+    };
+
+    if followed_by(".") && schema.keyspaces.contains_key(&name) {
+        return SemanticTokenType::NAMESPACE;
+    }
+
+    // `ks.name`
+    let qualifier = i
+        .checked_sub(2)
+        .filter(|_| tokens[i - 1].is_punctuation("."))
+        .and_then(|q| tokens[q].identifier());
+
+    if let Some(qualifier) = &qualifier
+        && let Some(qualifier) = schema.keyspaces.get(qualifier)
+    {
+        if qualifier.table(&name).is_some() {
+            return SemanticTokenType::CLASS;
+        }
+
+        if qualifier.types.contains_key(&name) {
+            return SemanticTokenType::TYPE;
+        }
+
+        if qualifier.functions.iter().any(|f| f.name == name) {
+            return SemanticTokenType::FUNCTION;
+        }
+    }
+
+    if table.is_some_and(|table| table.column(&name).is_some()) {
+        return SemanticTokenType::PROPERTY;
+    }
+
+    if schema.table(keyspace, &name).is_some() || schema.table(None, &name).is_some() {
+        return SemanticTokenType::CLASS;
+    }
+
+    if schema.user_type(keyspace, &name).is_some() || schema.user_type(None, &name).is_some() {
+        return SemanticTokenType::TYPE;
+    }
+
+    // `USE ks`, `CREATE KEYSPACE ks`
+    if schema.keyspaces.contains_key(&name) {
+        return SemanticTokenType::NAMESPACE;
+    }
+
+    if !unquoted {
+        return SemanticTokenType::VARIABLE;
+    }
+
+    if NATIVE_TYPES.contains(&name.as_str()) {
+        SemanticTokenType::TYPE
+    } else if is_keyword(&name) {
+        SemanticTokenType::KEYWORD
+    } else if followed_by("(") {
+        SemanticTokenType::FUNCTION
+    } else {
+        SemanticTokenType::VARIABLE
+    }
+}
+
+/// The type of the literals, bind markers and operators among the tokens, none for brackets
+/// and separators
+fn classify_literal(token: &Token) -> Option<SemanticTokenType> {
+    match token.kind {
+        TokenKind::String => Some(SemanticTokenType::STRING),
+        TokenKind::Number | TokenKind::Hex | TokenKind::Uuid | TokenKind::Duration => {
+            Some(SemanticTokenType::NUMBER)
+        }
+        TokenKind::BindMarker | TokenKind::NamedBindMarker => Some(SemanticTokenType::PARAMETER),
+        TokenKind::Punctuation
+            if !matches!(
+                token.text,
+                "(" | ")" | "[" | "]" | "{" | "}" | "<" | ">" | "," | ";" | "." | ":"
+            ) =>
+        {
+            Some(SemanticTokenType::OPERATOR)
+        }
+        _ => None,
+    }
+}
+
+/// The length of `text` in the units of `position_encoding`
+fn length(text: &str, position_encoding: PositionEncodingKind) -> u32 {
+    match position_encoding {
+        PositionEncodingKind::UTF8 => text.len() as u32,
+        PositionEncodingKind::UTF16 => text.encode_utf16().count() as u32,
+        PositionEncodingKind::UTF32 => text.chars().count() as u32,
+    }
+}
+
+/// Computes the semantic tokens of `doc` checked against `schema`, those within `range` only
+/// when given, encoded relative to each other as the protocol has them. Tokens spanning lines,
+/// such as block comments and `$$` strings, are split into one per line.
+pub fn semantic_tokens(
+    doc: &TextDocument,
+    schema: Schema,
+    range: Option<Range>,
+    dialect: Dialect,
+    position_encoding: PositionEncodingKind,
+) -> Vec<SemanticToken> {
+    let text = doc.text();
+    let tokens = lexer::tokenize(text);
+    let statements = statement::split(&tokens, dialect);
+    let schema = schema.with_statements(&statements);
+
+    let bytes = range.map(|range| {
+        let start = doc
+            .position_to_byte_idx(&range.start, position_encoding)
+            .unwrap_or(0);
+        let end = doc
+            .position_to_byte_idx(&range.end, position_encoding)
+            .unwrap_or(text.len());
+
+        start..end
+    });
+
+    let in_range = |token: &Token| {
+        bytes
+            .as_ref()
+            .is_none_or(|bytes| token.end > bytes.start && token.start < bytes.end)
+    };
+
+    let mut classified = vec![];
+
+    for (i, statement) in statements.iter().enumerate() {
+        let keyspace = schema::keyspace_at(&statements, i).or(schema.used_keyspace.clone());
+        let table = schema.statement_table(statement, keyspace.as_deref());
+
+        for (j, token) in statement.tokens.iter().enumerate() {
+            if !in_range(token) {
+                continue;
+            }
+
+            let token_type = match token.is_identifier() {
+                true => Some(classify_identifier(
+                    statement.tokens,
+                    j,
+                    &schema,
+                    keyspace.as_deref(),
+                    table,
+                )),
+                false => classify_literal(token),
+            };
+
+            classified.extend(token_type.map(|t| (token.start, token.end, t)));
+        }
+    }
+
+    // Comments aren't part of the statements
+    classified.extend(
+        lexer::tokenize_with_comments(text)
+            .into_iter()
+            .filter(|token| token.kind == TokenKind::Comment && in_range(token))
+            .map(|token| (token.start, token.end, SemanticTokenType::COMMENT)),
+    );
+
+    classified.sort_by_key(|(start, ..)| *start);
+
+    let mut encoded = vec![];
+    let mut previous = (0, 0);
+
+    for (start, end, token_type) in classified {
+        let mut line_start = start;
+
+        for line in text[start..end].split_inclusive('\n') {
+            let position = doc.byte_idx_to_position(line_start, position_encoding);
+            let content = line.trim_end_matches(['\r', '\n']);
+            line_start += line.len();
+
+            if content.is_empty() {
+                continue;
+            }
+
+            let (previous_line, previous_character) = previous;
+            let delta_line = position.line - previous_line;
+
+            encoded.push(SemanticToken {
+                delta_line,
+                delta_start: match delta_line {
+                    0 => position.character - previous_character,
+                    _ => position.character,
+                },
+                length: length(content, position_encoding),
+                token_type: index(&token_type),
+                token_modifiers_bitset: 0,
+            });
+
+            previous = (position.line, position.character);
+        }
+    }
+
+    encoded
+}
+
+/// The edit turning `previous` into `current`: the run of tokens between their common prefix
+/// and suffix, replaced. Its offsets count the five integers of each token, as the protocol
+/// has them. Returns no edit if the tokens are the same.
+pub fn edits(previous: &[SemanticToken], current: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let prefix = previous
+        .iter()
+        .zip(current)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let suffix = previous[prefix..]
+        .iter()
+        .rev()
+        .zip(current[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let deleted = previous.len() - prefix - suffix;
+    let inserted = &current[prefix..current.len() - suffix];
+
+    if deleted == 0 && inserted.is_empty() {
+        return vec![];
+    }
+
+    vec![SemanticTokensEdit {
+        start: prefix as u32 * 5,
+        delete_count: deleted as u32 * 5,
+        data: Some(inserted.to_vec()),
+    }]
+}
+
+/// The tokens last sent for each document, under the result id they were sent with
+#[derive(Debug, Default)]
+pub struct SemanticTokensCache {
+    results: HashMap<Url, (String, Vec<SemanticToken>)>,
+    next_result_id: u64,
+}
+
+impl SemanticTokensCache {
+    /// Remembers `tokens` as those last sent for `uri`, returning the result id to send them
+    /// with
+    pub fn insert(&mut self, uri: Url, tokens: Vec<SemanticToken>) -> String {
+        let result_id = self.next_result_id.to_string();
+        self.next_result_id += 1;
+
+        self.results.insert(uri, (result_id.clone(), tokens));
+        result_id
+    }
+
+    /// The tokens last sent for `uri`, if they were sent with `result_id`
+    pub fn get(&self, uri: &Url, result_id: &str) -> Option<&[SemanticToken]> {
+        self.results
+            .get(uri)
+            .filter(|(id, _)| id == result_id)
+            .map(|(_, tokens)| tokens.as_slice())
+    }
+
+    pub fn remove(&mut self, uri: &Url) {
+        self.results.remove(uri);
+    }
+}
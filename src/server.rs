@@ -0,0 +1,1658 @@
+//! The language server: the Language Server Protocol handlers serving the analysis, over any
+//! transport, be it the standard streams of the `cql-lsp` binary or the streams of an editor
+//! embedding the server.
+
+#[cfg(feature = "cluster")]
+mod cluster;
+mod notebook;
+mod panic;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::RwLock,
+};
+use tower_lsp::{
+    Client, LanguageServer, LspService, Server,
+    jsonrpc::{Error, Result},
+    lsp_types::{
+        CodeActionParams, CodeActionProviderCapability, CodeActionResponse, CodeLens,
+        CodeLensOptions, CodeLensParams, CompletionOptions, CompletionParams, CompletionResponse,
+        Diagnostic, DiagnosticOptions, DiagnosticServerCapabilities, DiagnosticSeverity,
+        DidChangeConfigurationParams, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+        DidChangeWatchedFilesRegistrationOptions, DidCloseTextDocumentParams,
+        DidOpenTextDocumentParams, DidSaveTextDocumentParams, DocumentDiagnosticParams,
+        DocumentDiagnosticReport, DocumentDiagnosticReportResult, DocumentLink,
+        DocumentLinkOptions, DocumentLinkParams, DocumentOnTypeFormattingOptions,
+        DocumentOnTypeFormattingParams, DocumentRangeFormattingParams, ExecuteCommandOptions,
+        ExecuteCommandParams, FileChangeType, FileSystemWatcher, FormattingOptions,
+        FullDocumentDiagnosticReport, GlobPattern, Hover, HoverParams, HoverProviderCapability,
+        InitializeParams, InitializeResult, InitializedParams, InlayHint, InlayHintParams,
+        MessageType, OneOf, Range, Registration, RelatedFullDocumentDiagnosticReport,
+        RelatedUnchangedDocumentDiagnosticReport, RelativePattern, SemanticToken, SemanticTokens,
+        SemanticTokensDelta, SemanticTokensDeltaParams, SemanticTokensFullDeltaResult,
+        SemanticTokensFullOptions, SemanticTokensOptions, SemanticTokensParams,
+        SemanticTokensRangeParams, SemanticTokensRangeResult, SemanticTokensResult,
+        SemanticTokensServerCapabilities, ServerCapabilities, TextDocumentPositionParams,
+        TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+        TextDocumentSyncSaveOptions, TextEdit, TypeDefinitionProviderCapability,
+        UnchangedDocumentDiagnosticReport, Url, WillSaveTextDocumentParams,
+        WorkspaceDiagnosticParams, WorkspaceDiagnosticReport, WorkspaceDiagnosticReportResult,
+        WorkspaceDocumentDiagnosticReport, WorkspaceFullDocumentDiagnosticReport,
+        WorkspaceUnchangedDocumentDiagnosticReport,
+        notification::Notification,
+        request::{GotoTypeDefinitionParams, GotoTypeDefinitionResponse, Request},
+    },
+};
+
+use crate::{
+    analysis, bind_markers, bulk,
+    capabilities::ClientSupport,
+    cluster::{
+        consistency::{self, Consistencies},
+        profile::{self, PROFILES_FILE},
+    },
+    code_actions, code_lens, completion,
+    config::{self, Config, SETTINGS_FILE},
+    diagnostics,
+    document::{self, Documents, PositionEncodingKind, TextDocument},
+    document_links, formatting, generate, hover, inlay_hints, lexer,
+    lsp_ext::{
+        self, BindMarkerInfo, BindMarkers, DidChangeNotebookDocument, DidCloseNotebookDocument,
+        DidOpenNotebookDocument, GenerateStatementParams, GenerateTestDataParams, InlineCompletion,
+        InlineCompletionItem, InlineCompletionParams, KeyspaceNode, SchemaTree, SchemaTreeParams,
+        SchemaUpdated, SchemaUpdatedParams,
+    },
+    notebook::Notebooks,
+    progress::Progress,
+    schema::{self, Schema},
+    semantic_tokens::{self, SemanticTokensCache},
+    statement::{self, StatementKind},
+    type_definition, uri,
+    workspace::WorkspaceIndex,
+};
+
+#[cfg(feature = "cluster")]
+use crate::{
+    cluster::{Cluster, history::History},
+    lsp_ext::ResultContent,
+    results::ResultStore,
+};
+
+struct Backend {
+    client: Client,
+    map: Arc<RwLock<Documents>>, // uri -> document
+    config: Arc<RwLock<Config>>,
+    /// The settings last sent by the client, which the settings files are layered with
+    client_settings: Arc<RwLock<Value>>,
+    workspace: Arc<RwLock<WorkspaceIndex>>,
+    /// Whether the client pulls diagnostics with `textDocument/diagnostic`, in which case they
+    /// aren't pushed
+    pull_diagnostics: Arc<AtomicBool>,
+    /// Whether the client shows server-initiated `window/workDoneProgress`
+    work_done_progress: Arc<AtomicBool>,
+    /// What the client supports of completion, hover and registration, which responses are
+    /// adapted to
+    client_support: Arc<RwLock<ClientSupport>>,
+    /// The open notebooks, whose cells are among the documents
+    notebooks: Arc<RwLock<Notebooks>>,
+    /// The semantic tokens last sent for each document, which deltas are computed from
+    semantic_tokens: Arc<RwLock<SemanticTokensCache>>,
+    /// The cluster connected to with `cql.connect`
+    #[cfg(feature = "cluster")]
+    cluster: Arc<RwLock<Option<Cluster>>>,
+    /// The latest results of `cql.execute`
+    #[cfg(feature = "cluster")]
+    results: Arc<RwLock<ResultStore>>,
+    /// The statements run with `cql.execute` in the workspace
+    #[cfg(feature = "cluster")]
+    history: Arc<RwLock<History>>,
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        let folders = match params.workspace_folders {
+            Some(folders) => folders.into_iter().map(|folder| folder.uri).collect(),
+            None => params.root_uri.into_iter().collect::<Vec<_>>(),
+        };
+        let roots = folders
+            .iter()
+            .filter_map(|uri| uri.to_file_path().ok())
+            .collect::<Vec<_>>();
+
+        let settings = params.initialization_options.unwrap_or_default();
+        let (config, errors) = Config::load(&settings, &roots);
+        *self.client_settings.write().await = settings;
+        *self.config.write().await = config;
+        self.report_settings_errors(errors).await;
+
+        #[cfg(feature = "cluster")]
+        {
+            *self.history.write().await = History::load(&roots);
+        }
+        let mut workspace = WorkspaceIndex::new(roots);
+        workspace.configure(&*self.config.read().await);
+        *self.workspace.write().await = workspace;
+
+        let pull_diagnostics = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .is_some_and(|text_document| text_document.diagnostic.is_some());
+        self.pull_diagnostics
+            .store(pull_diagnostics, Ordering::Relaxed);
+
+        let work_done_progress = params
+            .capabilities
+            .window
+            .as_ref()
+            .and_then(|window| window.work_done_progress)
+            .unwrap_or(false);
+        self.work_done_progress
+            .store(work_done_progress, Ordering::Relaxed);
+
+        *self.client_support.write().await = ClientSupport::new(&params.capabilities);
+
+        #[allow(unused_mut)]
+        let mut commands = lsp_ext::COMMANDS.to_vec();
+        #[cfg(feature = "cluster")]
+        commands.extend(lsp_ext::CLUSTER_COMMANDS);
+
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Options(
+                    TextDocumentSyncOptions {
+                        open_close: Some(true),
+                        change: Some(TextDocumentSyncKind::FULL),
+                        will_save_wait_until: Some(true),
+                        save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                        ..Default::default()
+                    },
+                )),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(
+                        completion::TRIGGER_CHARACTERS
+                            .iter()
+                            .map(|trigger| trigger.to_string())
+                            .collect(),
+                    ),
+                    resolve_provider: Some(false),
+                    ..Default::default()
+                }),
+                inlay_hint_provider: Some(OneOf::Left(true)),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: semantic_tokens::legend(),
+                            range: Some(true),
+                            full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                            work_done_progress_options: Default::default(),
+                        },
+                    ),
+                ),
+                document_range_formatting_provider: Some(OneOf::Left(true)),
+                document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                    first_trigger_character: ";".into(),
+                    more_trigger_character: Some(vec![")".into()]),
+                }),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                code_lens_provider: Some(CodeLensOptions {
+                    resolve_provider: Some(false),
+                }),
+                type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                document_link_provider: Some(DocumentLinkOptions {
+                    resolve_provider: Some(false),
+                    work_done_progress_options: Default::default(),
+                }),
+                diagnostic_provider: Some(DiagnosticServerCapabilities::Options(
+                    DiagnosticOptions {
+                        identifier: Some("cql-lsp".into()),
+                        inter_file_dependencies: true,
+                        workspace_diagnostics: true,
+                        ..Default::default()
+                    },
+                )),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: commands.iter().map(|c| c.to_string()).collect(),
+                    ..Default::default()
+                }),
+                // `inlineCompletionProvider` isn't a field of these protocol types yet
+                experimental: Some(serde_json::json!({ "inlineCompletionProvider": true })),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    async fn initialized(&self, _: InitializedParams) {
+        self.client
+            .log_message(MessageType::INFO, "server initialized!")
+            .await;
+
+        self.register_capabilities().await;
+
+        self.index_workspace().await;
+        #[cfg(feature = "cluster")]
+        self.connect_default_profile().await;
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let uri = params.text_document.uri;
+
+        self.client
+            .log_message(MessageType::INFO, format!("Open URI: {}", uri))
+            .await;
+
+        // Documents of other languages are left to their own servers
+        if !self
+            .config
+            .read()
+            .await
+            .is_cql_document(&uri, &params.text_document.language_id)
+        {
+            return;
+        }
+
+        let text = params.text_document.text.clone();
+
+        let mut wr = self.map.write().await;
+        let config = self.config.read().await.clone();
+
+        let mut doc = TextDocument::new(&text);
+        doc.version = params.text_document.version;
+
+        // Large documents keep no copy of their text between requests, only their rope
+        let large = config.is_large(text.len());
+
+        if large {
+            doc.compact();
+        }
+
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            wr.insert(uri, doc);
+            document::limit_memory(wr.values_mut(), config.document_memory_limit);
+            return;
+        }
+
+        let workspace = self.workspace.read().await;
+        let diagnostics = diagnostics::diagnostics(
+            &doc,
+            workspace.schema(Some(&uri)),
+            &config,
+            workspace.is_protected(&uri),
+            PositionEncodingKind::UTF16,
+        );
+        drop(workspace);
+
+        wr.insert(uri.clone(), doc);
+        document::limit_memory(wr.values_mut(), config.document_memory_limit);
+        drop(wr);
+
+        match large {
+            true => self.validate_large(uri).await,
+            false => {
+                self.publish_diagnostics(uri, diagnostics, params.text_document.version)
+                    .await
+            }
+        }
+    }
+
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        let uri = params.text_document.uri;
+
+        let rd = self.map.read().await;
+
+        // Only the documents read as CQL are open
+        let Some(doc) = rd.get(&uri) else {
+            return;
+        };
+
+        let text = params.text.unwrap_or_else(|| doc.rope.to_string());
+        drop(rd);
+
+        let schema_changed = self.workspace.write().await.update(&uri, text);
+
+        if schema_changed {
+            self.client
+                .send_notification::<SchemaUpdated>(SchemaUpdatedParams {
+                    uri: Some(uri.clone()),
+                })
+                .await;
+        }
+
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            // Pulled results depend on the workspace schema, so they're all stale now
+            if schema_changed {
+                let _ = self.client.workspace_diagnostic_refresh().await;
+            }
+
+            return;
+        }
+
+        // Other documents may use the objects the saved file defines
+        let uris = match schema_changed {
+            true => self.map.read().await.keys().cloned().collect(),
+            false => vec![uri],
+        };
+
+        self.validate(uris).await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+
+        let mut wr = self.map.write().await;
+
+        wr.remove(&uri);
+        drop(wr);
+
+        self.semantic_tokens.write().await.remove(&uri);
+
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            return;
+        }
+
+        // Diagnostics of closed documents would otherwise linger in the editor
+        self.client.publish_diagnostics(uri, vec![], None).await;
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        let uri = params.text_document.uri;
+
+        let mut wr = self.map.write().await;
+
+        let Some(doc) = wr.get_mut(&uri) else {
+            return;
+        };
+
+        for change in params.content_changes {
+            doc.apply_content_change(change, PositionEncodingKind::UTF16)
+                .unwrap();
+        }
+
+        doc.version = params.text_document.version;
+
+        let config = self.config.read().await.clone();
+
+        let large = config.is_large(doc.rope.len_bytes());
+
+        if large {
+            doc.compact();
+        }
+
+        // Replaying the rest of the workspace is left to `did_open` and `did_save`, keeping
+        // keystrokes cheap. Large documents keep the diagnostics `validate_large` published until
+        // they're saved and validated again.
+        let diagnostics = match self.pull_diagnostics.load(Ordering::Relaxed) || large {
+            true => None,
+            false => Some(diagnostics::diagnostics(
+                doc,
+                Schema::default(),
+                &config,
+                self.workspace.read().await.is_protected(&uri),
+                PositionEncodingKind::UTF16,
+            )),
+        };
+
+        document::limit_memory(wr.values_mut(), config.document_memory_limit);
+        drop(wr);
+
+        let Some(diagnostics) = diagnostics else {
+            return;
+        };
+
+        self.publish_diagnostics(uri, diagnostics, params.text_document.version)
+            .await;
+    }
+
+    async fn will_save_wait_until(
+        &self,
+        params: WillSaveTextDocumentParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let config = self.config.read().await;
+
+        if !config.format_on_save {
+            return Ok(None);
+        }
+
+        let rd = self.map.read().await;
+
+        let Some(doc) = rd.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+
+        let options = FormattingOptions {
+            tab_size: config.tab_size,
+            insert_spaces: true,
+            ..Default::default()
+        };
+
+        Ok(Some(formatting::document_formatting(
+            doc,
+            &options,
+            &config.format_style(),
+            doc.dialect(config.dialect),
+            PositionEncodingKind::UTF16,
+        )))
+    }
+
+    async fn diagnostic(
+        &self,
+        params: DocumentDiagnosticParams,
+    ) -> Result<DocumentDiagnosticReportResult> {
+        let uri = params.text_document.uri;
+
+        let rd = self.map.read().await;
+        let workspace = self.workspace.read().await;
+
+        let Some(doc) = rd.get(&uri) else {
+            return Ok(DocumentDiagnosticReportResult::Report(
+                DocumentDiagnosticReport::Full(Default::default()),
+            ));
+        };
+
+        let config = self.config.read().await.clone();
+        let notebooks = self.notebooks.read().await;
+
+        // The diagnostics of a notebook's cell change with the cells before it
+        let earlier_cells = notebooks
+            .earlier_cells(&uri)
+            .iter()
+            .filter_map(|cell| rd.get(cell));
+        let chunks = earlier_cells
+            .flat_map(|cell| cell.rope.chunks())
+            .chain(doc.rope.chunks());
+        let result_id = diagnostics::result_id(chunks, workspace.generation(), &config);
+
+        if params.previous_result_id.as_ref() == Some(&result_id) {
+            return Ok(DocumentDiagnosticReportResult::Report(
+                DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+                    related_documents: None,
+                    unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+                        result_id,
+                    },
+                }),
+            ));
+        }
+
+        let schema = notebooks.schema(&uri, workspace.schema(Some(&uri)), &rd, config.dialect);
+        let protected = workspace.is_protected(&uri);
+
+        let items = match config.is_large(doc.rope.len_bytes()) {
+            true => {
+                let chunks = doc.rope.chunks().map(str::to_string);
+                let validation = bulk::Validation::new(
+                    chunks,
+                    schema,
+                    &config,
+                    protected,
+                    PositionEncodingKind::UTF16,
+                );
+
+                limit_diagnostics(
+                    validation
+                        .flatten()
+                        .take(bulk::MAX_DIAGNOSTICS + 1)
+                        .collect(),
+                )
+            }
+            false => diagnostics::diagnostics(
+                doc,
+                schema,
+                &config,
+                protected,
+                PositionEncodingKind::UTF16,
+            ),
+        };
+
+        Ok(DocumentDiagnosticReportResult::Report(
+            DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+                related_documents: None,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                    result_id: Some(result_id),
+                    items,
+                },
+            }),
+        ))
+    }
+
+    async fn workspace_diagnostic(
+        &self,
+        params: WorkspaceDiagnosticParams,
+    ) -> Result<WorkspaceDiagnosticReportResult> {
+        let rd = self.map.read().await;
+        let workspace = self.workspace.read().await;
+        let config = self.config.read().await.clone();
+
+        let previous_result_ids = params
+            .previous_result_ids
+            .into_iter()
+            .map(|previous| (uri::normalize(&previous.uri), previous.value))
+            .collect::<HashMap<_, _>>();
+
+        // Open documents are covered by `textDocument/diagnostic`
+        let open = rd.keys().map(uri::normalize).collect::<Vec<_>>();
+        let items = workspace
+            .files()
+            .filter(|(uri, _)| !open.contains(uri))
+            .map(|(uri, text)| {
+                let result_id = diagnostics::result_id([text], workspace.generation(), &config);
+
+                if previous_result_ids.get(uri) == Some(&result_id) {
+                    return WorkspaceDocumentDiagnosticReport::Unchanged(
+                        WorkspaceUnchangedDocumentDiagnosticReport {
+                            uri: uri.clone(),
+                            version: None,
+                            unchanged_document_diagnostic_report:
+                                UnchangedDocumentDiagnosticReport { result_id },
+                        },
+                    );
+                }
+
+                let doc = TextDocument::new(text);
+                let items = diagnostics::diagnostics(
+                    &doc,
+                    workspace.schema(Some(uri)),
+                    &config,
+                    workspace.is_protected(uri),
+                    PositionEncodingKind::UTF16,
+                );
+
+                WorkspaceDocumentDiagnosticReport::Full(WorkspaceFullDocumentDiagnosticReport {
+                    uri: uri.clone(),
+                    version: None,
+                    full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                        result_id: Some(result_id),
+                        items,
+                    },
+                })
+            })
+            .collect();
+
+        Ok(WorkspaceDiagnosticReportResult::Report(
+            WorkspaceDiagnosticReport { items },
+        ))
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let open = self
+            .map
+            .read()
+            .await
+            .keys()
+            .map(uri::normalize)
+            .collect::<Vec<_>>();
+        let mut schema_changed = false;
+        let mut profiles_changed = false;
+        let mut settings_changed = false;
+
+        for change in params.changes {
+            let name = change
+                .uri
+                .path_segments()
+                .and_then(|mut segments| segments.next_back());
+
+            // The profiles file holds settings too
+            if name == Some(PROFILES_FILE) {
+                profiles_changed = true;
+                settings_changed = true;
+                continue;
+            }
+
+            if name == Some(SETTINGS_FILE) {
+                settings_changed = true;
+                continue;
+            }
+
+            // Open documents are indexed as they're saved
+            if open.contains(&uri::normalize(&change.uri)) {
+                continue;
+            }
+
+            let mut workspace = self.workspace.write().await;
+
+            schema_changed |= match change.typ {
+                FileChangeType::DELETED => workspace.remove(&change.uri),
+                _ => match change.uri.to_file_path().map(std::fs::read_to_string) {
+                    Ok(Ok(text)) => workspace.update(&change.uri, text),
+                    _ => false,
+                },
+            };
+        }
+
+        if profiles_changed {
+            self.load_protected_paths().await;
+        }
+
+        if settings_changed && self.reload_config().await {
+            return;
+        }
+
+        if schema_changed {
+            self.schema_changed().await;
+        } else if profiles_changed {
+            self.refresh_diagnostics().await;
+        }
+    }
+
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        // Clients notifying through the dynamic registration may leave the settings out
+        if params.settings.is_null() {
+            return;
+        }
+
+        *self.client_settings.write().await = params.settings;
+        self.reload_config().await;
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let uri = params.text_document_position.text_document.uri;
+
+        let position = params.text_document_position.position;
+
+        let trigger = params
+            .context
+            .as_ref()
+            .and_then(|context| context.trigger_character.as_deref());
+
+        let rd = self.map.read().await;
+
+        let Some(doc) = rd.get(&uri) else {
+            return Ok(None);
+        };
+
+        let config = self.config.read().await;
+        let schema = self.notebooks.read().await.schema(
+            &uri,
+            self.workspace.read().await.schema(Some(&uri)),
+            &rd,
+            config.dialect,
+        );
+
+        let mut completions = completion::completions(
+            doc,
+            schema,
+            position,
+            &config,
+            trigger,
+            PositionEncodingKind::UTF16,
+        );
+
+        self.client_support
+            .read()
+            .await
+            .adapt_completions(&mut completions);
+
+        Ok(Some(CompletionResponse::Array(completions)))
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let rd = self.map.read().await;
+
+        let Some(doc) = rd.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+
+        let config = self.config.read().await;
+
+        if config.is_large(doc.rope.len_bytes()) {
+            return Ok(None);
+        }
+
+        Ok(Some(inlay_hints::inlay_hints(
+            doc,
+            params.range,
+            doc.dialect(config.dialect),
+            PositionEncodingKind::UTF16,
+        )))
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        let uri = &params.text_document.uri;
+        let rd = self.map.read().await;
+
+        let Some(doc) = rd.get(uri) else {
+            return Ok(None);
+        };
+
+        let config = self.config.read().await;
+
+        if config.is_large(doc.rope.len_bytes()) {
+            return Ok(None);
+        }
+
+        let schema = self.notebooks.read().await.schema(
+            uri,
+            self.workspace.read().await.schema(Some(uri)),
+            &rd,
+            config.dialect,
+        );
+
+        Ok(Some(code_lens::code_lenses(
+            doc,
+            schema,
+            doc.dialect(config.dialect),
+            PositionEncodingKind::UTF16,
+        )))
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        let uri = params.text_document.uri;
+
+        let Some(data) = self.document_semantic_tokens(&uri, None).await else {
+            return Ok(None);
+        };
+
+        let result_id = self.semantic_tokens.write().await.insert(uri, data.clone());
+
+        Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: Some(result_id),
+            data,
+        })))
+    }
+
+    async fn semantic_tokens_full_delta(
+        &self,
+        params: SemanticTokensDeltaParams,
+    ) -> Result<Option<SemanticTokensFullDeltaResult>> {
+        let uri = params.text_document.uri;
+
+        let Some(data) = self.document_semantic_tokens(&uri, None).await else {
+            return Ok(None);
+        };
+
+        let mut cache = self.semantic_tokens.write().await;
+
+        // Without the tokens the client has, all of them are sent again
+        let edits = cache
+            .get(&uri, &params.previous_result_id)
+            .map(|previous| semantic_tokens::edits(previous, &data));
+
+        let result_id = cache.insert(uri, data.clone());
+
+        Ok(Some(match edits {
+            Some(edits) => SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                result_id: Some(result_id),
+                edits,
+            }),
+            None => SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+                result_id: Some(result_id),
+                data,
+            }),
+        }))
+    }
+
+    async fn semantic_tokens_range(
+        &self,
+        params: SemanticTokensRangeParams,
+    ) -> Result<Option<SemanticTokensRangeResult>> {
+        let data = self
+            .document_semantic_tokens(&params.text_document.uri, Some(params.range))
+            .await;
+
+        Ok(data.map(|data| {
+            SemanticTokensRangeResult::Tokens(SemanticTokens {
+                result_id: None,
+                data,
+            })
+        }))
+    }
+
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let rd = self.map.read().await;
+
+        let Some(doc) = rd.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+
+        let config = self.config.read().await;
+
+        Ok(Some(formatting::range_formatting(
+            doc,
+            &params.range,
+            &params.options,
+            &config.format_style(),
+            doc.dialect(config.dialect),
+            PositionEncodingKind::UTF16,
+        )))
+    }
+
+    async fn on_type_formatting(
+        &self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let rd = self.map.read().await;
+
+        let Some(doc) = rd.get(&params.text_document_position.text_document.uri) else {
+            return Ok(None);
+        };
+
+        let config = self.config.read().await;
+
+        Ok(Some(formatting::on_type_formatting(
+            doc,
+            &params.text_document_position.position,
+            &params.ch,
+            &params.options,
+            &config.format_style(),
+            doc.dialect(config.dialect),
+            PositionEncodingKind::UTF16,
+        )))
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let rd = self.map.read().await;
+
+        let Some(doc) = rd.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+
+        let config = self.config.read().await;
+        let dialect = doc.dialect(config.dialect);
+
+        let mut actions = code_actions::code_actions(
+            doc,
+            &params.text_document.uri,
+            params.range,
+            dialect,
+            config.target,
+            PositionEncodingKind::UTF16,
+        );
+
+        actions.extend(code_actions::suppressions(
+            doc,
+            &params.text_document.uri,
+            &params.context.diagnostics,
+            PositionEncodingKind::UTF16,
+        ));
+
+        actions.extend(code_actions::keyword_case_fixes(
+            doc,
+            &params.text_document.uri,
+            &params.context.diagnostics,
+            config.keyword_case,
+            dialect,
+            PositionEncodingKind::UTF16,
+        ));
+
+        Ok(Some(actions))
+    }
+
+    async fn goto_type_definition(
+        &self,
+        params: GotoTypeDefinitionParams,
+    ) -> Result<Option<GotoTypeDefinitionResponse>> {
+        let uri = params.text_document_position_params.text_document.uri;
+
+        let rd = self.map.read().await;
+
+        let Some(doc) = rd.get(&uri) else {
+            return Ok(None);
+        };
+
+        let dialect = doc.dialect(self.config.read().await.dialect);
+        let workspace = self.workspace.read().await;
+
+        let locations = type_definition::type_definition(
+            doc,
+            &uri,
+            &params.text_document_position_params.position,
+            workspace.files(),
+            workspace.schema(Some(&uri)),
+            dialect,
+            PositionEncodingKind::UTF16,
+        );
+
+        Ok(match locations.len() {
+            0 => None,
+            1 => locations
+                .into_iter()
+                .next()
+                .map(GotoTypeDefinitionResponse::Scalar),
+            _ => Some(GotoTypeDefinitionResponse::Array(locations)),
+        })
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let rd = self.map.read().await;
+
+        let uri = &params.text_document_position_params.text_document.uri;
+
+        let Some(doc) = rd.get(uri) else {
+            return Ok(None);
+        };
+
+        let dialect = doc.dialect(self.config.read().await.dialect);
+        let schema = self.notebooks.read().await.schema(
+            uri,
+            self.workspace.read().await.schema(Some(uri)),
+            &rd,
+            dialect,
+        );
+
+        let mut hover = hover::hover(
+            doc,
+            &params.text_document_position_params.position,
+            schema,
+            dialect,
+            PositionEncodingKind::UTF16,
+        );
+
+        if let Some(hover) = &mut hover {
+            self.client_support.read().await.adapt_hover(hover);
+        }
+
+        Ok(hover)
+    }
+
+    async fn document_link(&self, params: DocumentLinkParams) -> Result<Option<Vec<DocumentLink>>> {
+        let rd = self.map.read().await;
+
+        let Some(doc) = rd.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+
+        let dialect = doc.dialect(self.config.read().await.dialect);
+
+        Ok(Some(document_links::document_links(
+            doc,
+            &params.text_document.uri,
+            dialect,
+            PositionEncodingKind::UTF16,
+        )))
+    }
+
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<Value>> {
+        match params.command.as_str() {
+            command @ (lsp_ext::GENERATE_INSERT | lsp_ext::GENERATE_SELECT) => {
+                let params = command_params::<GenerateStatementParams>(params.arguments)?;
+                let rd = self.map.read().await;
+
+                let Some(doc) = rd.get(&params.text_document.uri) else {
+                    return Ok(None);
+                };
+
+                let text = doc.text();
+                let tokens = lexer::tokenize(text);
+                let dialect = doc.dialect(self.config.read().await.dialect);
+                let statements = statement::split(&tokens, dialect);
+                let schema = Schema::from_statements(&statements);
+
+                let Some(table) = schema.table(params.keyspace.as_deref(), &params.table) else {
+                    return Ok(None);
+                };
+
+                let generated = match command {
+                    lsp_ext::GENERATE_INSERT => generate::insert_statement(table, true),
+                    _ => Some(generate::select_statement(table, true)),
+                };
+
+                Ok(generated.map(Value::String))
+            }
+            lsp_ext::GENERATE_TEST_DATA => {
+                let params = command_params::<GenerateTestDataParams>(params.arguments)?;
+                let rd = self.map.read().await;
+
+                let Some(doc) = rd.get(&params.text_document.uri) else {
+                    return Ok(None);
+                };
+
+                // The workspace's schema, for user-defined types defined in other files
+                let dialect = self.config.read().await.dialect;
+                let schema = self.notebooks.read().await.schema(
+                    &params.text_document.uri,
+                    self.workspace
+                        .read()
+                        .await
+                        .schema(Some(&params.text_document.uri)),
+                    &rd,
+                    dialect,
+                );
+
+                let text = doc.text();
+                let tokens = lexer::tokenize(text);
+                let statements = statement::split(&tokens, doc.dialect(dialect));
+                let schema = schema.with_statements(&statements);
+
+                let Some(table) = schema.table(params.keyspace.as_deref(), &params.table) else {
+                    return Err(Error::invalid_params(format!(
+                        "`{}` isn't in the schema",
+                        params.table
+                    )));
+                };
+
+                let Some(data) = generate::test_data(table, &schema, params.count) else {
+                    return Err(Error::invalid_params(format!(
+                        "`{}` is a counter table, which can't be written with INSERT",
+                        table.name
+                    )));
+                };
+
+                Ok(Some(Value::String(data)))
+            }
+            lsp_ext::ANALYZE_STATEMENT => {
+                let params = command_params::<TextDocumentPositionParams>(params.arguments)?;
+                let rd = self.map.read().await;
+
+                let Some(doc) = rd.get(&params.text_document.uri) else {
+                    return Ok(None);
+                };
+
+                let offset = doc
+                    .position_to_byte_idx(&params.position, PositionEncodingKind::UTF16)
+                    .map_err(|error| Error::invalid_params(error.to_string()))?;
+
+                let dialect = self.config.read().await.dialect;
+                let schema = self.notebooks.read().await.schema(
+                    &params.text_document.uri,
+                    self.workspace
+                        .read()
+                        .await
+                        .schema(Some(&params.text_document.uri)),
+                    &rd,
+                    dialect,
+                );
+
+                let text = doc.text();
+                let tokens = lexer::tokenize(text);
+                let statements = statement::split(&tokens, doc.dialect(dialect));
+                let schema = schema.with_statements(&statements);
+
+                let Some(i) = statement::statement_at(&statements, offset) else {
+                    return Err(Error::invalid_params("No statement at the cursor"));
+                };
+
+                let statement = &statements[i];
+
+                if !matches!(
+                    statement.kind(),
+                    StatementKind::Select
+                        | StatementKind::Insert
+                        | StatementKind::Update
+                        | StatementKind::Delete
+                ) {
+                    return Err(Error::invalid_params(
+                        "Only SELECT, INSERT, UPDATE and DELETE statements are analyzed",
+                    ));
+                }
+
+                let keyspace = schema::keyspace_at(&statements, i).or(schema.used_keyspace.clone());
+
+                let Some(table) = schema.statement_table(statement, keyspace.as_deref()) else {
+                    return Err(Error::invalid_params(
+                        "The statement's table isn't in the schema",
+                    ));
+                };
+
+                let consistencies = consistency::at(&statements, i, Consistencies::default());
+
+                Ok(Some(Value::String(analysis::analyze(
+                    statement,
+                    table,
+                    consistencies,
+                ))))
+            }
+            #[cfg(feature = "cluster")]
+            command if lsp_ext::CLUSTER_COMMANDS.contains(&command) => {
+                self.execute_cluster_command(command, params.arguments)
+                    .await
+            }
+            command => Err(Error::invalid_params(format!("Unknown command {command}"))),
+        }
+    }
+}
+
+/// Cuts the diagnostics of a large document down to [`bulk::MAX_DIAGNOSTICS`], noting at its
+/// top that others were left out
+fn limit_diagnostics(mut diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    if diagnostics.len() <= bulk::MAX_DIAGNOSTICS {
+        return diagnostics;
+    }
+
+    diagnostics.truncate(bulk::MAX_DIAGNOSTICS);
+    diagnostics.push(Diagnostic {
+        severity: Some(DiagnosticSeverity::INFORMATION),
+        source: Some("cql-lsp".into()),
+        message: format!(
+            "Only the first {} problems of this large document are reported",
+            bulk::MAX_DIAGNOSTICS
+        ),
+        ..Default::default()
+    });
+
+    diagnostics
+}
+
+/// Deserializes the single argument object of a `workspace/executeCommand` command
+fn command_params<T: DeserializeOwned>(arguments: Vec<Value>) -> Result<T> {
+    let argument = arguments
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| Value::Object(Default::default()));
+
+    serde_json::from_value(argument).map_err(|error| Error::invalid_params(error.to_string()))
+}
+
+impl Backend {
+    /// Indexes the `.cql` files of the workspace, then re-validates open documents against the
+    /// schema they define
+    async fn index_workspace(&self) {
+        self.load_protected_paths().await;
+
+        let files = self.workspace.read().await.workspace_files();
+
+        let mut progress = Progress::begin(
+            &self.client,
+            self.work_done_progress.load(Ordering::Relaxed),
+            "Indexing CQL workspace",
+        )
+        .await;
+
+        for (i, path) in files.iter().enumerate() {
+            self.workspace.write().await.index_file(path);
+            progress.report(i + 1, files.len(), "files").await;
+        }
+
+        progress.end().await;
+
+        self.schema_changed().await;
+    }
+
+    /// Reads the settings again, from the client's and the settings files, and refreshes what
+    /// depends on those that changed. Returns whether everything was refreshed.
+    async fn reload_config(&self) -> bool {
+        let settings = self.client_settings.read().await.clone();
+        let roots = self.workspace.read().await.roots().to_vec();
+        let (config, errors) = Config::load(&settings, &roots);
+        self.report_settings_errors(errors).await;
+
+        let schema_changed = self.workspace.write().await.configure(&config);
+        let lints_changed = {
+            let previous = self.config.read().await;
+
+            previous.tombstone_severity != config.tombstone_severity
+                || previous.destructive_severity != config.destructive_severity
+        };
+        *self.config.write().await = config;
+
+        if schema_changed {
+            self.schema_changed().await;
+        } else if lints_changed {
+            self.refresh_diagnostics().await;
+        }
+
+        schema_changed
+    }
+
+    /// Shows the user the settings files that couldn't be read
+    async fn report_settings_errors(&self, errors: Vec<anyhow::Error>) {
+        for error in errors {
+            self.client
+                .show_message(MessageType::WARNING, format!("{error:#}"))
+                .await;
+        }
+    }
+
+    /// Reads the files meant for the clusters of protected profiles from the profiles file. A
+    /// malformed file is reported when connecting, rather than on every read.
+    async fn load_protected_paths(&self) {
+        let mut workspace = self.workspace.write().await;
+        let paths = profile::protected_paths(workspace.roots()).unwrap_or_default();
+
+        workspace.set_protected_paths(paths);
+    }
+
+    /// Registers for the notifications the client only sends once asked to at runtime: changes
+    /// to `.cql` files made outside the editor, and to the settings, those of the user's config
+    /// directory included if the client watches files outside the workspace
+    async fn register_capabilities(&self) {
+        let support = *self.client_support.read().await;
+        let mut registrations = vec![];
+
+        if support.watched_files_registration {
+            let mut options = DidChangeWatchedFilesRegistrationOptions {
+                watchers: vec![
+                    FileSystemWatcher {
+                        glob_pattern: GlobPattern::String("**/*.cql".into()),
+                        kind: None,
+                    },
+                    FileSystemWatcher {
+                        glob_pattern: GlobPattern::String(format!("**/{PROFILES_FILE}")),
+                        kind: None,
+                    },
+                    FileSystemWatcher {
+                        glob_pattern: GlobPattern::String(format!("**/{SETTINGS_FILE}")),
+                        kind: None,
+                    },
+                ],
+            };
+
+            if support.watched_files_relative_patterns
+                && let Some(directory) = config::user_directory()
+                && let Ok(base_uri) = Url::from_directory_path(directory)
+            {
+                options.watchers.push(FileSystemWatcher {
+                    glob_pattern: GlobPattern::Relative(RelativePattern {
+                        base_uri: OneOf::Right(base_uri),
+                        pattern: format!("{{{SETTINGS_FILE},{PROFILES_FILE}}}"),
+                    }),
+                    kind: None,
+                });
+            }
+
+            registrations.push(Registration {
+                id: "cql-watched-files".into(),
+                method: "workspace/didChangeWatchedFiles".into(),
+                register_options: serde_json::to_value(options).ok(),
+            });
+        }
+
+        if support.configuration_registration {
+            registrations.push(Registration {
+                id: "cql-configuration".into(),
+                method: "workspace/didChangeConfiguration".into(),
+                register_options: None,
+            });
+        }
+
+        let config = self.config.read().await;
+
+        if config.notebooks {
+            let cells = config
+                .language_ids
+                .iter()
+                .map(|language| serde_json::json!({ "language": language }))
+                .collect::<Vec<_>>();
+
+            registrations.push(Registration {
+                id: "cql-notebooks".into(),
+                method: "notebookDocument/sync".into(),
+                register_options: Some(serde_json::json!({
+                    "notebookSelector": [{ "cells": cells }],
+                })),
+            });
+        }
+
+        drop(config);
+
+        if registrations.is_empty() {
+            return;
+        }
+
+        if let Err(error) = self.client.register_capability(registrations).await {
+            self.client
+                .log_message(
+                    MessageType::WARNING,
+                    format!("Couldn't register for notifications: {error}"),
+                )
+                .await;
+        }
+    }
+
+    /// Tells the client the schema changed as a whole, and re-validates the documents checked
+    /// against it
+    async fn schema_changed(&self) {
+        self.client
+            .send_notification::<SchemaUpdated>(SchemaUpdatedParams { uri: None })
+            .await;
+
+        self.refresh_diagnostics().await;
+
+        let support = *self.client_support.read().await;
+
+        // Tables, types and columns are told apart by the schema
+        if support.semantic_tokens_refresh {
+            let _ = self.client.semantic_tokens_refresh().await;
+        }
+
+        // The lenses name the tables statements resolve to
+        if support.code_lens_refresh {
+            let _ = self.client.code_lens_refresh().await;
+        }
+    }
+
+    /// Computes the semantic tokens of the document at `uri`, those within `range` only when
+    /// given, against the schema its diagnostics are checked against. Large documents have none.
+    async fn document_semantic_tokens(
+        &self,
+        uri: &Url,
+        range: Option<Range>,
+    ) -> Option<Vec<SemanticToken>> {
+        let rd = self.map.read().await;
+        let doc = rd.get(uri)?;
+        let config = self.config.read().await;
+
+        if config.is_large(doc.rope.len_bytes()) {
+            return None;
+        }
+
+        let schema = self.notebooks.read().await.schema(
+            uri,
+            self.workspace.read().await.schema(Some(uri)),
+            &rd,
+            config.dialect,
+        );
+
+        Some(semantic_tokens::semantic_tokens(
+            doc,
+            schema,
+            range,
+            doc.dialect(config.dialect),
+            PositionEncodingKind::UTF16,
+        ))
+    }
+
+    /// Has the diagnostics of every document computed again, by asking clients pulling them to
+    /// pull them again, or by publishing them
+    async fn refresh_diagnostics(&self) {
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            let _ = self.client.workspace_diagnostic_refresh().await;
+        } else {
+            let uris = self.map.read().await.keys().cloned().collect();
+            self.validate(uris).await;
+        }
+    }
+
+    /// Publishes the diagnostics of the open documents among `uris`, checked against the schema
+    /// defined by the rest of the workspace, and the earlier cells of their notebook if any
+    async fn validate(&self, uris: Vec<Url>) {
+        let rd = self.map.read().await;
+        let workspace = self.workspace.read().await;
+        let notebooks = self.notebooks.read().await;
+        let config = self.config.read().await.clone();
+
+        let (large, uris): (Vec<_>, Vec<_>) = uris.into_iter().partition(|uri| {
+            rd.get(uri)
+                .is_some_and(|doc| config.is_large(doc.rope.len_bytes()))
+        });
+
+        let diagnostics = uris
+            .into_iter()
+            .filter_map(|uri| {
+                let doc = rd.get(&uri)?;
+                let schema =
+                    notebooks.schema(&uri, workspace.schema(Some(&uri)), &rd, config.dialect);
+                let diagnostics = diagnostics::diagnostics(
+                    doc,
+                    schema,
+                    &config,
+                    workspace.is_protected(&uri),
+                    PositionEncodingKind::UTF16,
+                );
+
+                Some((uri, diagnostics, doc.version))
+            })
+            .collect::<Vec<_>>();
+
+        drop(notebooks);
+        drop(workspace);
+        drop(rd);
+
+        for (uri, diagnostics, version) in diagnostics {
+            self.publish_diagnostics(uri, diagnostics, version).await;
+        }
+
+        for uri in large {
+            self.validate_large(uri).await;
+        }
+    }
+
+    /// Validates the large document at `uri` chunk by chunk, publishing its diagnostics as
+    /// they're found, until it changes or closes. Its text is shared with the document rather
+    /// than copied, and its statements are dropped once checked.
+    async fn validate_large(&self, uri: Url) {
+        let Some((rope, version)) = self
+            .map
+            .read()
+            .await
+            .get(&uri)
+            .map(|doc| (doc.rope.clone(), doc.version))
+        else {
+            return;
+        };
+
+        let config = self.config.read().await.clone();
+        let workspace = self.workspace.read().await;
+        let schema = workspace.schema(Some(&uri));
+        let protected = workspace.is_protected(&uri);
+        drop(workspace);
+
+        let mut progress = Progress::begin(
+            &self.client,
+            self.work_done_progress.load(Ordering::Relaxed),
+            "Validating large CQL document",
+        )
+        .await;
+
+        let chunks = rope.chunks().map(str::to_string);
+        let mut validation = bulk::Validation::new(
+            chunks,
+            schema,
+            &config,
+            protected,
+            PositionEncodingKind::UTF16,
+        );
+        let mut diagnostics = vec![];
+
+        while let Some(found) = validation.next() {
+            let current = self.map.read().await.get(&uri).map(|doc| doc.version);
+
+            if current != Some(version) {
+                break;
+            }
+
+            progress
+                .report(validation.checked() >> 20, rope.len_bytes() >> 20, "MiB")
+                .await;
+
+            if found.is_empty() {
+                continue;
+            }
+
+            diagnostics.extend(found);
+
+            if diagnostics.len() > bulk::MAX_DIAGNOSTICS {
+                break;
+            }
+
+            self.publish_diagnostics(uri.clone(), diagnostics.clone(), version)
+                .await;
+        }
+
+        progress.end().await;
+
+        // The last report, clearing the diagnostics of a previous version if none were found
+        self.publish_diagnostics(uri, limit_diagnostics(diagnostics), version)
+            .await;
+    }
+
+    /// Publishes the diagnostics computed against `version` of the document at `uri`, unless
+    /// it changed or closed since, as they'd show at shifted positions. The next change
+    /// publishes its own.
+    async fn publish_diagnostics(&self, uri: Url, diagnostics: Vec<Diagnostic>, version: i32) {
+        let current = self.map.read().await.get(&uri).map(|doc| doc.version);
+
+        if current != Some(version) {
+            return;
+        }
+
+        self.client
+            .publish_diagnostics(uri, diagnostics, Some(version))
+            .await;
+    }
+
+    async fn bind_markers(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Vec<BindMarkerInfo>> {
+        let rd = self.map.read().await;
+
+        let Some(doc) = rd.get(&params.text_document.uri) else {
+            return Ok(vec![]);
+        };
+
+        let Ok(offset) = doc.position_to_byte_idx(&params.position, PositionEncodingKind::UTF16)
+        else {
+            return Ok(vec![]);
+        };
+
+        let text = doc.text();
+        let tokens = lexer::tokenize(text);
+        let dialect = doc.dialect(self.config.read().await.dialect);
+        let statements = statement::split(&tokens, dialect);
+        let schema = Schema::from_statements(&statements);
+
+        let Some(i) = statement::statement_at(&statements, offset) else {
+            return Ok(vec![]);
+        };
+
+        let keyspace = schema::keyspace_at(&statements, i);
+        let table = schema.statement_table(&statements[i], keyspace.as_deref());
+
+        let markers = bind_markers::statement_bind_markers(&statements[i], table)
+            .into_iter()
+            .map(|marker| BindMarkerInfo {
+                index: marker.index,
+                name: marker.name,
+                range: doc.byte_range_to_range(marker.span, PositionEncodingKind::UTF16),
+                column: marker.column,
+                data_type: marker.data_type.map(|data_type| data_type.to_string()),
+            })
+            .collect();
+
+        Ok(markers)
+    }
+
+    async fn inline_completion(
+        &self,
+        params: InlineCompletionParams,
+    ) -> Result<Option<Vec<InlineCompletionItem>>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+
+        let rd = self.map.read().await;
+
+        let Some(doc) = rd.get(&uri) else {
+            return Ok(None);
+        };
+
+        let config = self.config.read().await;
+        let schema = self.notebooks.read().await.schema(
+            &uri,
+            self.workspace.read().await.schema(Some(&uri)),
+            &rd,
+            config.dialect,
+        );
+
+        let Some(edit) = completion::inline_completion(
+            doc,
+            schema,
+            position,
+            &config,
+            PositionEncodingKind::UTF16,
+        ) else {
+            return Ok(None);
+        };
+
+        // The continuation must agree with the item selected in the completion popup
+        if let Some(selected) = params.context.selected_completion_info
+            && !edit
+                .new_text
+                .to_ascii_lowercase()
+                .starts_with(&selected.text.to_ascii_lowercase())
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(vec![InlineCompletionItem {
+            insert_text: edit.new_text,
+            range: Some(edit.range),
+        }]))
+    }
+
+    async fn schema_tree(&self, params: SchemaTreeParams) -> Result<Vec<KeyspaceNode>> {
+        let Some(uri) = params.text_document.map(|text_document| text_document.uri) else {
+            let schema = self.workspace.read().await.schema(None);
+            return Ok(KeyspaceNode::tree(&schema));
+        };
+
+        let mut schema = self.workspace.read().await.schema(Some(&uri));
+
+        if let Some(doc) = self.map.read().await.get(&uri) {
+            let text = doc.text();
+            let tokens = lexer::tokenize(text);
+            let dialect = doc.dialect(self.config.read().await.dialect);
+
+            schema = schema.with_statements(&statement::split(&tokens, dialect));
+        }
+
+        Ok(KeyspaceNode::tree(&schema))
+    }
+}
+
+/// Serves the language server over `input` and `output` until the client exits
+pub async fn serve<I, O>(input: I, output: O)
+where
+    I: AsyncRead + Unpin,
+    O: AsyncWrite,
+{
+    let builder = LspService::build(|client| Backend {
+        client,
+        map: Default::default(),
+        config: Default::default(),
+        client_settings: Default::default(),
+        workspace: Default::default(),
+        pull_diagnostics: Default::default(),
+        work_done_progress: Default::default(),
+        client_support: Default::default(),
+        notebooks: Default::default(),
+        semantic_tokens: Default::default(),
+        #[cfg(feature = "cluster")]
+        cluster: Default::default(),
+        #[cfg(feature = "cluster")]
+        results: Default::default(),
+        #[cfg(feature = "cluster")]
+        history: Default::default(),
+    })
+    .custom_method(BindMarkers::METHOD, Backend::bind_markers)
+    .custom_method(InlineCompletion::METHOD, Backend::inline_completion)
+    .custom_method(DidOpenNotebookDocument::METHOD, Backend::did_open_notebook)
+    .custom_method(
+        DidChangeNotebookDocument::METHOD,
+        Backend::did_change_notebook,
+    )
+    .custom_method(
+        DidCloseNotebookDocument::METHOD,
+        Backend::did_close_notebook,
+    )
+    .custom_method(SchemaTree::METHOD, Backend::schema_tree);
+
+    #[cfg(feature = "cluster")]
+    let builder = builder.custom_method(ResultContent::METHOD, Backend::result_content);
+
+    let (service, socket) = builder.finish();
+
+    Server::new(input, output, socket)
+        .serve(panic::CatchPanic::new(service))
+        .await;
+}
@@ -0,0 +1,450 @@
+//! The commands of the cluster subsystem: connecting to a cluster, executing statements on it
+//! and comparing its schema with the workspace's.
+
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::Value;
+use tower_lsp::{
+    jsonrpc::{Error, ErrorCode, Result},
+    lsp_types::{MessageActionItem, MessageType},
+};
+
+use crate::{
+    cluster::{self, Cluster, consistency, history::HistoryEntry},
+    directives,
+    document::PositionEncodingKind,
+    guardrails, lexer,
+    lsp_ext::{
+        self, ConnectParams, DescribeParams, ExecuteParams, ExecuteResult, ExecuteTarget,
+        GenerateMigrationParams, HistoryParams, ResultContentParams, SchemaDifference,
+    },
+    migration,
+    progress::Progress,
+    schema::{self, Schema},
+    schema_diff, statement,
+};
+
+use super::{Backend, command_params};
+
+/// Reports an error of the cluster subsystem to the client
+fn request_failed(error: anyhow::Error) -> Error {
+    Error {
+        code: ErrorCode::InternalError,
+        message: format!("{error:#}").into(),
+        data: None,
+    }
+}
+
+impl Backend {
+    /// Runs the cluster command `command`, one of [`lsp_ext::CLUSTER_COMMANDS`]
+    pub(super) async fn execute_cluster_command(
+        &self,
+        command: &str,
+        arguments: Vec<Value>,
+    ) -> Result<Option<Value>> {
+        match command {
+            lsp_ext::CONNECT => {
+                let params = command_params::<ConnectParams>(arguments)?;
+
+                self.connect(&params.profile)
+                    .await
+                    .map_err(request_failed)?;
+
+                Ok(None)
+            }
+            lsp_ext::DISCONNECT => {
+                self.disconnect().await;
+
+                Ok(None)
+            }
+            lsp_ext::SCHEMA_DIFF => {
+                let (workspace, cluster) = self.compared_schemas().await.map_err(request_failed)?;
+
+                let differences = schema_diff::diff(&workspace, &cluster)
+                    .iter()
+                    .map(SchemaDifference::new)
+                    .collect::<Vec<_>>();
+
+                Ok(serde_json::to_value(differences).ok())
+            }
+            lsp_ext::GENERATE_MIGRATION => {
+                let params = command_params::<GenerateMigrationParams>(arguments)?;
+                let (workspace, cluster) = self.compared_schemas().await.map_err(request_failed)?;
+
+                let differences = schema_diff::diff(&workspace, &cluster);
+
+                Ok(Some(Value::String(migration::script(
+                    &differences,
+                    params.target,
+                ))))
+            }
+            lsp_ext::DESCRIBE => {
+                let params = command_params::<DescribeParams>(arguments)?;
+
+                let tokens = lexer::tokenize(&params.name);
+                let mut i = 0;
+
+                let name = statement::parse_qualified_name(&tokens, &mut i)
+                    .filter(|_| i == tokens.len())
+                    .ok_or_else(|| {
+                        Error::invalid_params(format!("Invalid name {}", params.name))
+                    })?;
+
+                let (keyspace, table) = match name.keyspace {
+                    Some(keyspace) => (keyspace, Some(name.name)),
+                    None => (name.name, None),
+                };
+
+                let cluster = self.cluster.read().await;
+                let cluster = cluster
+                    .as_ref()
+                    .ok_or_else(|| request_failed(anyhow::anyhow!("Not connected to a cluster")))?;
+
+                let ddl = cluster
+                    .describe(&keyspace, table.as_deref())
+                    .await
+                    .map_err(request_failed)?;
+
+                Ok(Some(Value::String(ddl)))
+            }
+            lsp_ext::EXECUTE => {
+                let params = command_params::<ExecuteParams>(arguments)?;
+                let result = self.execute(params).await?;
+
+                Ok(serde_json::to_value(result).ok())
+            }
+            lsp_ext::HISTORY => {
+                let params = command_params::<HistoryParams>(arguments)?;
+                let history = self.history.read().await;
+
+                let entries = history
+                    .entries()
+                    .iter()
+                    .rev()
+                    .filter(|entry| {
+                        params
+                            .profile
+                            .as_ref()
+                            .is_none_or(|profile| &entry.profile == profile)
+                    })
+                    .take(params.limit.unwrap_or(usize::MAX))
+                    .collect::<Vec<_>>();
+
+                Ok(serde_json::to_value(entries).ok())
+            }
+            command => Err(Error::invalid_params(format!("Unknown command {command}"))),
+        }
+    }
+
+    /// Connects with the default profile of the workspace, if it has one
+    pub(super) async fn connect_default_profile(&self) {
+        let roots = self.workspace.read().await.roots().to_vec();
+
+        let connected = match cluster::profile::default_profile(&roots) {
+            Ok(Some(name)) => self.connect(&name).await,
+            Ok(None) => return,
+            Err(error) => Err(error),
+        };
+
+        if let Err(error) = connected {
+            self.client
+                .show_message(MessageType::WARNING, format!("{error:#}"))
+                .await;
+        }
+    }
+
+    /// Connects to the cluster of the profile named `name`, replacing any current connection,
+    /// and reads its schema. A cached schema of the cluster is used in the meantime.
+    pub(super) async fn connect(&self, name: &str) -> anyhow::Result<()> {
+        let roots = self.workspace.read().await.roots().to_vec();
+        let (profile, directory) = cluster::profile::load(&roots, name)?;
+
+        let ttl = Duration::from_secs(profile.schema_cache_hours * 60 * 60);
+
+        if let Some(schema) = cluster::cache::load(&directory, name, ttl) {
+            *self.cluster.write().await = None;
+            self.workspace.write().await.set_cluster_schema(schema);
+            self.schema_changed().await;
+        }
+
+        let cluster = Cluster::connect(name, &profile, &directory, &self.client).await?;
+
+        let progress = Progress::begin(
+            &self.client,
+            self.work_done_progress.load(Ordering::Relaxed),
+            "Refreshing cluster schema",
+        )
+        .await;
+
+        let schema = cluster.schema().await;
+        progress.end().await;
+        let schema = schema?;
+
+        if profile.schema_cache_hours > 0
+            && let Err(error) = cluster::cache::save(&directory, name, schema.clone())
+        {
+            self.client
+                .log_message(
+                    MessageType::WARNING,
+                    format!("Couldn't cache the schema of {name}: {error:#}"),
+                )
+                .await;
+        }
+
+        self.workspace.write().await.set_cluster_schema(schema);
+        *self.cluster.write().await = Some(cluster);
+
+        self.client
+            .show_message(MessageType::INFO, format!("Connected to {name}"))
+            .await;
+
+        self.schema_changed().await;
+
+        Ok(())
+    }
+
+    /// Closes the connection to the cluster, forgetting its schema, which may also be a cached
+    /// schema left by a connection that failed
+    pub(super) async fn disconnect(&self) {
+        let cluster = self.cluster.write().await.take();
+
+        self.workspace
+            .write()
+            .await
+            .set_cluster_schema(Schema::default());
+
+        if let Some(cluster) = cluster {
+            self.client
+                .show_message(
+                    MessageType::INFO,
+                    format!("Disconnected from {}", cluster.profile),
+                )
+                .await;
+        }
+
+        self.schema_changed().await;
+    }
+
+    /// Returns the schema defined by the workspace's files, and the schema the cluster has
+    /// right now, to be compared
+    pub(super) async fn compared_schemas(&self) -> anyhow::Result<(Schema, Schema)> {
+        let cluster = self.cluster.read().await;
+        let cluster = cluster
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Not connected to a cluster"))?;
+
+        let cluster_schema = cluster.schema().await?;
+        let workspace_schema = self.workspace.read().await.files_schema();
+
+        Ok((workspace_schema, cluster_schema))
+    }
+
+    /// Runs the statement at `params.position` on the cluster, once the user confirms it if it's
+    /// destructive and the profile is protected, and keeps its result for `cql/resultContent`
+    pub(super) async fn execute(&self, params: ExecuteParams) -> Result<ExecuteResult> {
+        let (profile, row_limit, protected, consistencies) = {
+            let cluster = self.cluster.read().await;
+            let cluster = cluster
+                .as_ref()
+                .ok_or_else(|| request_failed(anyhow::anyhow!("Not connected to a cluster")))?;
+
+            (
+                cluster.profile.clone(),
+                cluster.row_limit,
+                cluster.protected,
+                cluster.consistencies,
+            )
+        };
+
+        // Statements of the files meant for a protected profile are guarded on any cluster
+        let (text, offset, keyspace, protected_file) = match params.target {
+            ExecuteTarget::Position(params) => {
+                let rd = self.map.read().await;
+
+                let doc = rd.get(&params.text_document.uri).ok_or_else(|| {
+                    Error::invalid_params(format!("Unknown document {}", params.text_document.uri))
+                })?;
+
+                let offset = doc
+                    .position_to_byte_idx(&params.position, PositionEncodingKind::UTF16)
+                    .map_err(|_| Error::invalid_params("Invalid position"))?;
+
+                let protected_file = self
+                    .workspace
+                    .read()
+                    .await
+                    .is_protected(&params.text_document.uri);
+
+                (doc.rope.to_string(), offset, None, protected_file)
+            }
+            ExecuteTarget::Statement {
+                statement,
+                keyspace,
+            } => {
+                let offset = statement.len() - statement.trim_start().len();
+
+                (statement, offset, keyspace, false)
+            }
+        };
+
+        let dialect = directives::dialect(&text, self.config.read().await.dialect);
+
+        let (statement, limit, destructive, keyspace, mut consistencies) = {
+            let tokens = lexer::tokenize(&text);
+            let statements = statement::split(&tokens, dialect);
+
+            let Some(i) = statement::statement_at(&statements, offset)
+                .filter(|&i| !statements[i].tokens.is_empty())
+            else {
+                return Err(Error::invalid_params("No statement at the cursor"));
+            };
+
+            // Only `DESCRIBE` is understood by the cluster too, since Cassandra 4.0
+            if let Some(command) = statements[i].cqlsh_command()
+                && !matches!(command, "DESC" | "DESCRIBE")
+            {
+                return Err(Error::invalid_params(format!(
+                    "{command} is a cqlsh command, which the cluster doesn't run"
+                )));
+            }
+
+            let (statement, limit) = guardrails::statement_text(&statements[i], &text, row_limit);
+
+            (
+                statement,
+                limit,
+                guardrails::destructive(&statements[i]),
+                keyspace.or_else(|| schema::keyspace_at(&statements, i)),
+                consistency::at(&statements, i, consistencies),
+            )
+        };
+
+        if let Some(consistency) = params.consistency {
+            consistencies.consistency = consistency;
+        }
+
+        if let Some(serial) = params.serial_consistency {
+            consistencies.serial = Some(serial);
+        }
+
+        if (protected || protected_file)
+            && let Some(destructive) = destructive
+        {
+            let run = MessageActionItem {
+                title: "Run".to_string(),
+                properties: Default::default(),
+            };
+            let cancel = MessageActionItem {
+                title: "Cancel".to_string(),
+                properties: Default::default(),
+            };
+
+            let message = match protected {
+                true => format!("{profile} is protected. Run this {destructive}?"),
+                false => format!(
+                    "This file is meant for a protected cluster. Run this {destructive} on {profile}?"
+                ),
+            };
+            let message = format!("{message}\n\n{statement}");
+
+            let choice = self
+                .client
+                .show_message_request(
+                    MessageType::WARNING,
+                    message,
+                    Some(vec![run.clone(), cancel]),
+                )
+                .await?;
+
+            if choice.is_none_or(|choice| choice.title != run.title) {
+                return Err(request_failed(anyhow::anyhow!("Cancelled by the user")));
+            }
+        }
+
+        let result = {
+            let cluster = self.cluster.read().await;
+            let cluster = cluster
+                .as_ref()
+                .ok_or_else(|| request_failed(anyhow::anyhow!("Not connected to a cluster")))?;
+
+            let started = Instant::now();
+            let result = cluster
+                .execute(&statement, keyspace.as_deref(), consistencies, params.trace)
+                .await;
+
+            let entry = HistoryEntry {
+                statement,
+                keyspace,
+                profile,
+                consistency: consistencies.consistency,
+                serial_consistency: consistencies.serial,
+                executed_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64,
+                duration_ms: started.elapsed().as_millis() as u64,
+                rows: result
+                    .as_ref()
+                    .ok()
+                    .map(|(result, _)| result)
+                    .filter(|result| !result.columns.is_empty())
+                    .map(|result| result.rows.len()),
+                error: result.as_ref().err().map(|error| format!("{error:#}")),
+            };
+
+            if let Err(error) = self.history.write().await.record(entry) {
+                self.client
+                    .log_message(
+                        MessageType::WARNING,
+                        format!("Couldn't save the query history: {error:#}"),
+                    )
+                    .await;
+            }
+
+            result.map_err(request_failed)?
+        };
+
+        let (result, trace) = result;
+
+        let trace = match trace {
+            Some(Err(error)) => {
+                self.client
+                    .log_message(
+                        MessageType::WARNING,
+                        format!("Couldn't read the trace of the statement: {error:#}"),
+                    )
+                    .await;
+
+                None
+            }
+            Some(Ok(trace)) => Some(trace),
+            None => None,
+        };
+
+        let rows = (!result.columns.is_empty()).then_some(result.rows.len());
+        let uri = self.results.write().await.insert(result);
+
+        Ok(ExecuteResult {
+            uri,
+            rows,
+            limit,
+            trace,
+        })
+    }
+
+    pub(super) async fn result_content(&self, params: ResultContentParams) -> Result<String> {
+        let results = self.results.read().await;
+
+        let result = results.get(&params.uri).ok_or_else(|| {
+            Error::invalid_params(format!(
+                "Unknown result {}, which may no longer be kept",
+                params.uri
+            ))
+        })?;
+
+        Ok(result.render(params.format))
+    }
+}
@@ -0,0 +1,137 @@
+//! The notebook synchronization notifications: the cells of a notebook are kept among the
+//! documents, and checked again whenever the notebook changes, since each depends on the cells
+//! before it.
+
+use std::sync::atomic::Ordering;
+
+use tower_lsp::lsp_types::{TextDocumentIdentifier, TextDocumentItem, Url};
+
+use crate::{
+    document::{PositionEncodingKind, TextDocument},
+    lsp_ext::{
+        DidChangeNotebookDocumentParams, DidCloseNotebookDocumentParams,
+        DidOpenNotebookDocumentParams,
+    },
+};
+
+use super::Backend;
+
+impl Backend {
+    pub(super) async fn did_open_notebook(&self, params: DidOpenNotebookDocumentParams) {
+        let notebook = params.notebook_document;
+        let cells = notebook
+            .cells
+            .into_iter()
+            .map(|cell| cell.document)
+            .collect::<Vec<_>>();
+
+        self.notebooks
+            .write()
+            .await
+            .open(notebook.uri, cells.clone());
+        self.open_cells(params.cell_text_documents).await;
+
+        self.notebook_changed(cells).await;
+    }
+
+    pub(super) async fn did_change_notebook(&self, params: DidChangeNotebookDocumentParams) {
+        let uri = params.notebook_document.uri;
+
+        let Some(change) = params.change.cells else {
+            return;
+        };
+
+        if let Some(structure) = change.structure {
+            let array = structure.array;
+            let cells = array.cells.unwrap_or_default();
+
+            self.notebooks.write().await.splice(
+                &uri,
+                array.start,
+                array.delete_count,
+                cells.into_iter().map(|cell| cell.document).collect(),
+            );
+
+            self.open_cells(structure.did_open.unwrap_or_default())
+                .await;
+            self.close_cells(structure.did_close.unwrap_or_default())
+                .await;
+        }
+
+        let mut wr = self.map.write().await;
+
+        for cell in change.text_content.unwrap_or_default() {
+            let Some(doc) = wr.get_mut(&cell.document.uri) else {
+                continue;
+            };
+
+            for change in cell.changes {
+                doc.apply_content_change(change, PositionEncodingKind::UTF16)
+                    .unwrap();
+            }
+
+            doc.version = cell.document.version;
+        }
+
+        drop(wr);
+
+        let cells = self.notebooks.read().await.cells(&uri).to_vec();
+        self.notebook_changed(cells).await;
+    }
+
+    pub(super) async fn did_close_notebook(&self, params: DidCloseNotebookDocumentParams) {
+        self.notebooks
+            .write()
+            .await
+            .close(&params.notebook_document.uri);
+
+        self.close_cells(params.cell_text_documents).await;
+    }
+
+    /// Opens the text documents of cells, those of other languages being left out like other
+    /// documents
+    async fn open_cells(&self, cells: Vec<TextDocumentItem>) {
+        let config = self.config.read().await;
+        let mut wr = self.map.write().await;
+
+        for cell in cells {
+            if config.is_cql_document(&cell.uri, &cell.language_id) {
+                let mut doc = TextDocument::new(&cell.text);
+                doc.version = cell.version;
+
+                wr.insert(cell.uri, doc);
+            }
+        }
+    }
+
+    async fn close_cells(&self, cells: Vec<TextDocumentIdentifier>) {
+        let mut wr = self.map.write().await;
+
+        for cell in &cells {
+            wr.remove(&cell.uri);
+        }
+
+        drop(wr);
+
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            return;
+        }
+
+        for cell in cells {
+            self.client
+                .publish_diagnostics(cell.uri, vec![], None)
+                .await;
+        }
+    }
+
+    /// Has the diagnostics of the notebook's `cells` computed again, as a change to one cell
+    /// may change those of the cells after it
+    async fn notebook_changed(&self, cells: Vec<Url>) {
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            let _ = self.client.workspace_diagnostic_refresh().await;
+            return;
+        }
+
+        self.validate(cells).await;
+    }
+}
@@ -0,0 +1,113 @@
+//! Isolation of the handlers' panics: a panic while serving one request, e.g. on a statement
+//! shaped in a way the completion engine didn't expect, fails that request alone rather than
+//! unwinding through the server and losing every open document with it.
+
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    borrow::Cow,
+    cell::RefCell,
+    future::{self, Future},
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::Once,
+    task::{Context, Poll},
+};
+
+use tower_lsp::{
+    ExitedError,
+    jsonrpc::{Error, ErrorCode, Request, Response},
+};
+use tower_service::Service;
+
+thread_local! {
+    /// The message of the last panic on this thread, with where it happened
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Logs panics to the standard error, which clients show as the server's log, with their
+/// backtrace whether or not `RUST_BACKTRACE` is set, and remembers their message for the error
+/// response of the request they failed
+fn install_hook() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        panic::set_hook(Box::new(|info| {
+            eprintln!("{info}\n{}", Backtrace::force_capture());
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(info.to_string()));
+        }));
+    });
+}
+
+/// The message a panic was raised with, should the hook not have seen it
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panicked".to_string())
+}
+
+/// Wraps the language server's service so that a request whose handler panics is answered with
+/// an internal error, and a notification whose handler panics is dropped
+pub(super) struct CatchPanic<S> {
+    service: S,
+}
+
+impl<S> CatchPanic<S> {
+    pub(super) fn new(service: S) -> Self {
+        install_hook();
+        Self { service }
+    }
+}
+
+impl<S> Service<Request> for CatchPanic<S>
+where
+    S: Service<Request, Response = Option<Response>, Error = ExitedError>,
+    S::Future: Send + Unpin + 'static,
+{
+    type Response = Option<Response>;
+    type Error = ExitedError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let method = request.method().to_string();
+        let id = request.id().cloned();
+        let mut response = self.service.call(request);
+
+        Box::pin(async move {
+            let result = future::poll_fn(|cx| {
+                match panic::catch_unwind(AssertUnwindSafe(|| Pin::new(&mut response).poll(cx))) {
+                    Ok(poll) => poll.map(Ok),
+                    Err(payload) => Poll::Ready(Err(payload)),
+                }
+            })
+            .await;
+
+            let payload = match result {
+                Ok(response) => return response,
+                Err(payload) => payload,
+            };
+
+            let message = LAST_PANIC
+                .with(|last| last.borrow_mut().take())
+                .unwrap_or_else(|| payload_message(payload.as_ref()));
+
+            let Some(id) = id else {
+                return Ok(None);
+            };
+
+            let error = Error {
+                code: ErrorCode::InternalError,
+                message: Cow::Owned(format!("{method} failed: {message}")),
+                data: None,
+            };
+
+            Ok(Some(Response::from_error(id, error)))
+        })
+    }
+}
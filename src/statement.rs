@@ -0,0 +1,641 @@
+//! Statement-level views over the token stream produced by [`crate::lexer`].
+//!
+//! Statements are split from the tokens rather than from the syntax tree, so that a statement
+//! the grammar can't parse, typically the one being typed, never takes the rest of the document
+//! into its error. Every `;` outside strings and comments ends a statement, even in brackets
+//! left open, and a statement missing its `;` ends at the next line plainly starting one.
+
+use std::ops::Range;
+
+use serde::Deserialize;
+
+use crate::{
+    batch::{self, Boundary},
+    lexer::{Token, TokenKind},
+};
+
+/// The commands cqlsh runs itself rather than sending them to the cluster. They end at the end
+/// of their line, a semicolon being optional.
+pub const CQLSH_COMMANDS: &[&str] = &[
+    "CAPTURE",
+    "CLEAR",
+    "CLS",
+    "CONSISTENCY",
+    "COPY",
+    "DESC",
+    "DESCRIBE",
+    "EXIT",
+    "EXPAND",
+    "HELP",
+    "LOGIN",
+    "PAGING",
+    "QUIT",
+    "SERIAL CONSISTENCY",
+    "SHOW",
+    "SOURCE",
+    "TRACING",
+];
+
+/// Whether documents are plain CQL or scripts for cqlsh, whose commands take no semicolon
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dialect {
+    /// cqlsh for documents with a line starting with one of its commands, CQL otherwise
+    #[default]
+    Auto,
+    Cql,
+    Cqlsh,
+}
+
+impl Dialect {
+    /// Whether the document made of `tokens` is read as a cqlsh script
+    pub fn is_cqlsh(self, tokens: &[Token]) -> bool {
+        match self {
+            Dialect::Cql => false,
+            Dialect::Cqlsh => true,
+            // `DESCRIBE` is CQL too since Cassandra 4.0, so it doesn't make a script
+            Dialect::Auto => split_statements(tokens, true).iter().any(|statement| {
+                statement
+                    .cqlsh_command()
+                    .is_some_and(|command| !matches!(command, "DESC" | "DESCRIBE"))
+            }),
+        }
+    }
+}
+
+/// Returns the cqlsh command `tokens` start with
+fn cqlsh_command(tokens: &[Token]) -> Option<&'static str> {
+    CQLSH_COMMANDS.iter().copied().find(|command| {
+        let words = command.split(' ').collect::<Vec<_>>();
+
+        tokens.len() >= words.len()
+            && words
+                .iter()
+                .zip(tokens)
+                .all(|(word, token)| token.is_keyword(word))
+    })
+}
+
+/// A single CQL statement, i.e. the tokens between two semicolons
+#[derive(Debug, Clone, Copy)]
+pub struct Statement<'a> {
+    /// The statement tokens, excluding the terminating semicolon
+    pub tokens: &'a [Token<'a>],
+    /// Byte offset where the statement starts
+    pub start: usize,
+    /// Byte offset where the statement ends, including the terminating semicolon if any
+    pub end: usize,
+    pub terminated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementKind {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Batch,
+    Use,
+    Truncate,
+    CreateKeyspace,
+    CreateTable,
+    CreateType,
+    CreateIndex,
+    CreateMaterializedView,
+    CreateFunction,
+    CreateAggregate,
+    CreateRole,
+    CreateUser,
+    CreateTrigger,
+    AlterKeyspace,
+    AlterTable,
+    AlterType,
+    AlterMaterializedView,
+    AlterRole,
+    AlterUser,
+    Drop,
+    Grant,
+    Revoke,
+    List,
+    /// DSE's `RESTRICT`, denying a permission or setting the column of row-level access control
+    Restrict,
+    Unrestrict,
+    /// The statements of DSE Search's indexes, e.g. `CREATE SEARCH INDEX`
+    SearchIndex,
+    Other,
+}
+
+/// Splits `tokens` into statements on semicolons, and in cqlsh scripts at the end of the lines
+/// of cqlsh commands. A statement missing its semicolon ends at the next line plainly starting
+/// a statement, so that one statement being typed doesn't swallow the rest of the document.
+pub fn split<'a>(tokens: &'a [Token<'a>], dialect: Dialect) -> Vec<Statement<'a>> {
+    split_statements(tokens, dialect.is_cqlsh(tokens))
+}
+
+/// Whether a statement plainly starts at `tokens[i]`, the first token of its line, so that a
+/// statement before it missing its semicolon (likely the one being typed) ends there rather
+/// than swallowing it
+fn starts_statement(tokens: &[Token], i: usize) -> bool {
+    let is = |i: usize, keywords: &[&str]| {
+        tokens
+            .get(i)
+            .is_some_and(|token| keywords.iter().any(|keyword| token.is_keyword(keyword)))
+    };
+
+    // The query of `CREATE MATERIALIZED VIEW ... AS`, and permissions like `GRANT SELECT`
+    if i > 0 && is(i - 1, &["AS", "GRANT", "REVOKE", "RESTRICT", "UNRESTRICT"]) {
+        return false;
+    }
+
+    // Unlike `ALTER TABLE t DROP column`, these are followed by the kind of object: a reserved
+    // keyword, which can't be the name of a column
+    let reserved = &["KEYSPACE", "SCHEMA", "TABLE", "COLUMNFAMILY", "OR"];
+
+    // Or one that may name a column too, e.g. `ALTER TABLE t DROP user` or `ALTER type TYPE
+    // text`, then followed on the same line by what can't follow a column: the rest of the
+    // kind, a name, `IF` or the `ON` of `CREATE INDEX ON`
+    let unreserved = |i: usize| match &tokens[i..] {
+        [kind, next, ..] if !next.first_on_line => match kind.text.to_uppercase().as_str() {
+            "CUSTOM" | "SEARCH" => next.is_keyword("INDEX"),
+            "MATERIALIZED" => next.is_keyword("VIEW"),
+            "TYPE" | "INDEX" | "FUNCTION" | "AGGREGATE" | "ROLE" | "USER" | "TRIGGER" => {
+                matches!(next.kind, TokenKind::Word | TokenKind::QuotedIdentifier)
+                    && !next.is_keyword("TYPE")
+                    && !next.is_keyword("USING")
+            }
+            _ => false,
+        },
+        _ => false,
+    };
+
+    (is(
+        i,
+        &["CREATE", "ALTER", "DROP", "COMMIT", "RELOAD", "REBUILD"],
+    ) && (is(i + 1, reserved) || unreserved(i + 1)))
+        || (is(i, &["INSERT"]) && is(i + 1, &["INTO"]))
+        || (is(i, &["BEGIN"]) && is(i + 1, &["BATCH", "UNLOGGED", "COUNTER"]))
+        || is(
+            i,
+            &[
+                "SELECT",
+                "UPDATE",
+                "DELETE",
+                "TRUNCATE",
+                "USE",
+                "GRANT",
+                "REVOKE",
+                "RESTRICT",
+                "UNRESTRICT",
+            ],
+        )
+}
+
+fn split_statements<'a>(tokens: &'a [Token<'a>], cqlsh: bool) -> Vec<Statement<'a>> {
+    let mut statements = vec![];
+    let mut first = 0;
+    let mut command = false;
+
+    for (i, token) in tokens.iter().enumerate() {
+        if i == first {
+            command = cqlsh && cqlsh_command(&tokens[first..]).is_some();
+        } else if token.first_on_line && (command || starts_statement(tokens, i)) {
+            statements.push(Statement {
+                tokens: &tokens[first..i],
+                start: tokens[first].start,
+                end: tokens[i - 1].end,
+                terminated: false,
+            });
+            first = i;
+            command = cqlsh && cqlsh_command(&tokens[first..]).is_some();
+        }
+
+        // Semicolons only occur in strings and comments otherwise, so one nested in brackets
+        // ends a statement whose brackets were left open
+        if token.is_punctuation(";") {
+            statements.push(Statement {
+                tokens: &tokens[first..i],
+                start: tokens[first].start,
+                end: token.end,
+                terminated: true,
+            });
+            first = i + 1;
+        }
+    }
+
+    if first < tokens.len() {
+        statements.push(Statement {
+            tokens: &tokens[first..],
+            start: tokens[first].start,
+            end: tokens[tokens.len() - 1].end,
+            terminated: false,
+        });
+    }
+
+    statements
+}
+
+/// Returns the index of the statement containing the byte `offset`. An offset right after a
+/// statement's last token (e.g. the cursor at the end of an unterminated statement) still
+/// belongs to it, while an offset right after a semicolon belongs to the next statement.
+pub fn statement_at(statements: &[Statement], offset: usize) -> Option<usize> {
+    statements.iter().position(|statement| {
+        offset >= statement.start
+            && (offset < statement.end || (!statement.terminated && offset == statement.end))
+    })
+}
+
+/// Whether `statements[i]` is missing the `;` ending it, the next statement starting right
+/// after it. cqlsh commands end with their line, and `BEGIN BATCH` before the writes of the
+/// batch takes none.
+pub fn missing_semicolon(statements: &[Statement], i: usize) -> bool {
+    let statement = &statements[i];
+
+    !statement.terminated
+        && i + 1 < statements.len()
+        && statement.cqlsh_command().is_none()
+        && !matches!(batch::boundary(statement), Some(Boundary::Begin(None)))
+}
+
+/// Returns the index of the bracket closing the one opened at `open`
+pub fn matching_bracket(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        if token.kind != TokenKind::Punctuation {
+            continue;
+        }
+
+        match token.text {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Splits `tokens` on commas that aren't nested in brackets or angle brackets (so that
+/// `map<text, int>` stays whole), returning the index ranges of the parts
+pub fn split_comma_ranges(tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut first = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Punctuation {
+            continue;
+        }
+
+        match token.text {
+            "(" | "[" | "{" | "<" => depth += 1,
+            ")" | "]" | "}" | ">" => depth -= 1,
+            "," if depth == 0 => {
+                parts.push(first..i);
+                first = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    if first < tokens.len() || !parts.is_empty() {
+        parts.push(first..tokens.len());
+    }
+
+    parts
+}
+
+/// Like [`split_comma_ranges`], but returns the parts themselves
+pub fn split_commas<'a>(tokens: &'a [Token<'a>]) -> Vec<&'a [Token<'a>]> {
+    split_comma_ranges(tokens)
+        .into_iter()
+        .map(|range| &tokens[range])
+        .collect()
+}
+
+/// The column list and `VALUES` tuple of an `INSERT` statement, as token index ranges into
+/// the statement tokens
+#[derive(Debug, Clone)]
+pub struct InsertValues {
+    pub columns: Vec<Range<usize>>,
+    pub values: Vec<Range<usize>>,
+}
+
+/// A possibly keyspace-qualified object name, e.g. `ks.users`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QualifiedName {
+    pub keyspace: Option<String>,
+    pub name: String,
+    /// Byte range of the whole name
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Returns the index just past an optional `IF NOT EXISTS` or `IF EXISTS` at `i`
+pub fn skip_if_exists(tokens: &[Token], i: usize) -> usize {
+    let at = |offset: usize, keyword: &str| {
+        tokens
+            .get(i + offset)
+            .is_some_and(|t| t.is_keyword(keyword))
+    };
+
+    if at(0, "IF") && at(1, "NOT") && at(2, "EXISTS") {
+        i + 3
+    } else if at(0, "IF") && at(1, "EXISTS") {
+        i + 2
+    } else {
+        i
+    }
+}
+
+/// Parses a (possibly qualified) name starting at `tokens[*i]`, advancing `i` past it
+pub fn parse_qualified_name(tokens: &[Token], i: &mut usize) -> Option<QualifiedName> {
+    let first = tokens.get(*i).filter(|token| token.is_identifier())?;
+
+    let qualified = tokens.get(*i + 1).is_some_and(|t| t.is_punctuation("."))
+        && tokens.get(*i + 2).is_some_and(|t| t.is_identifier());
+
+    let name = match qualified {
+        true => {
+            let second = &tokens[*i + 2];
+            *i += 3;
+
+            QualifiedName {
+                keyspace: first.identifier(),
+                name: second.identifier()?,
+                start: first.start,
+                end: second.end,
+            }
+        }
+        false => {
+            *i += 1;
+
+            QualifiedName {
+                keyspace: None,
+                name: first.identifier()?,
+                start: first.start,
+                end: first.end,
+            }
+        }
+    };
+
+    Some(name)
+}
+
+impl<'a> Statement<'a> {
+    /// Returns the index of the first top-level token that is the keyword `keyword`
+    pub fn find_keyword(&self, keyword: &str) -> Option<usize> {
+        self.find_keyword_from(keyword, 0)
+    }
+
+    /// Like [`Statement::find_keyword`], but starts searching at the token index `from`
+    pub fn find_keyword_from(&self, keyword: &str, from: usize) -> Option<usize> {
+        let mut depth = 0;
+
+        for (i, token) in self.tokens.iter().enumerate() {
+            if token.kind == TokenKind::Punctuation {
+                match token.text {
+                    "(" | "[" | "{" => depth += 1,
+                    ")" | "]" | "}" => depth -= 1,
+                    _ => {}
+                }
+            }
+
+            if i >= from && depth == 0 && token.is_keyword(keyword) {
+                return Some(i);
+            }
+        }
+
+        None
+    }
+
+    /// Returns the cqlsh command the statement is, as listed in [`CQLSH_COMMANDS`]
+    pub fn cqlsh_command(&self) -> Option<&'static str> {
+        cqlsh_command(self.tokens)
+    }
+
+    pub fn kind(&self) -> StatementKind {
+        // `CREATE` and `DROP` may be followed by `OR REPLACE` or `CUSTOM`, which we skip to get
+        // to the object being created
+        let words = self
+            .tokens
+            .iter()
+            .take_while(|token| token.kind == TokenKind::Word)
+            .filter(|token| {
+                !["OR", "REPLACE", "CUSTOM"]
+                    .iter()
+                    .any(|word| token.is_keyword(word))
+            })
+            .take(2)
+            .map(|token| token.text.to_ascii_uppercase())
+            .collect::<Vec<_>>();
+
+        let words = words.iter().map(String::as_str).collect::<Vec<_>>();
+
+        match words.as_slice() {
+            ["SELECT", ..] => StatementKind::Select,
+            ["INSERT", ..] => StatementKind::Insert,
+            ["UPDATE", ..] => StatementKind::Update,
+            ["DELETE", ..] => StatementKind::Delete,
+            ["BEGIN", ..] | ["APPLY", "BATCH"] => StatementKind::Batch,
+            ["USE", ..] => StatementKind::Use,
+            ["TRUNCATE", ..] => StatementKind::Truncate,
+            ["CREATE", "KEYSPACE" | "SCHEMA"] => StatementKind::CreateKeyspace,
+            ["CREATE", "TABLE" | "COLUMNFAMILY"] => StatementKind::CreateTable,
+            ["CREATE", "TYPE"] => StatementKind::CreateType,
+            ["CREATE", "INDEX"] => StatementKind::CreateIndex,
+            ["CREATE", "MATERIALIZED"] => StatementKind::CreateMaterializedView,
+            ["CREATE", "FUNCTION"] => StatementKind::CreateFunction,
+            ["CREATE", "AGGREGATE"] => StatementKind::CreateAggregate,
+            ["CREATE", "ROLE"] => StatementKind::CreateRole,
+            ["CREATE", "USER"] => StatementKind::CreateUser,
+            ["CREATE", "TRIGGER"] => StatementKind::CreateTrigger,
+            ["ALTER", "KEYSPACE" | "SCHEMA"] => StatementKind::AlterKeyspace,
+            ["ALTER", "TABLE" | "COLUMNFAMILY"] => StatementKind::AlterTable,
+            ["ALTER", "TYPE"] => StatementKind::AlterType,
+            ["ALTER", "MATERIALIZED"] => StatementKind::AlterMaterializedView,
+            ["ALTER", "ROLE"] => StatementKind::AlterRole,
+            ["ALTER", "USER"] => StatementKind::AlterUser,
+            [
+                "CREATE" | "ALTER" | "DROP" | "COMMIT" | "RELOAD" | "REBUILD",
+                "SEARCH",
+            ] => StatementKind::SearchIndex,
+            ["DROP", ..] => StatementKind::Drop,
+            ["GRANT", ..] => StatementKind::Grant,
+            ["REVOKE", ..] => StatementKind::Revoke,
+            ["LIST", ..] => StatementKind::List,
+            ["RESTRICT", ..] => StatementKind::Restrict,
+            ["UNRESTRICT", ..] => StatementKind::Unrestrict,
+            _ => StatementKind::Other,
+        }
+    }
+
+    /// Returns the table a statement operates on: the target of `INSERT INTO`, `UPDATE`, the
+    /// `FROM` clause of `SELECT` and `DELETE`, the table created or altered by DDL, or that of
+    /// DSE's search indexes and row-level access control
+    pub fn target_table(&self) -> Option<QualifiedName> {
+        let mut i = match self.kind() {
+            StatementKind::Insert => self.find_keyword("INTO")? + 1,
+            StatementKind::Update => 1,
+            StatementKind::Select | StatementKind::Delete => self.find_keyword("FROM")? + 1,
+            StatementKind::CreateTable | StatementKind::AlterTable => {
+                skip_if_exists(self.tokens, 2)
+            }
+            StatementKind::SearchIndex => self.find_keyword("ON")? + 1,
+            StatementKind::Restrict | StatementKind::Unrestrict
+                if self.tokens.get(1).is_some_and(|t| t.is_keyword("ROWS")) =>
+            {
+                self.find_keyword("ON")? + 1
+            }
+            _ => return None,
+        };
+
+        parse_qualified_name(self.tokens, &mut i)
+    }
+
+    /// Returns the column list and values of an `INSERT INTO t (...) VALUES (...)` statement.
+    /// An unterminated values tuple (the user is still typing) extends to the end of the
+    /// statement.
+    pub fn insert_values(&self) -> Option<InsertValues> {
+        if self.kind() != StatementKind::Insert {
+            return None;
+        }
+
+        let tokens = self.tokens;
+        let into = self.find_keyword("INTO")?;
+        let columns_open = (into + 1..tokens.len()).find(|&i| tokens[i].is_punctuation("("))?;
+        let columns_close = matching_bracket(tokens, columns_open)?;
+        let values = self.find_keyword_from("VALUES", columns_close)?;
+
+        if !tokens.get(values + 1)?.is_punctuation("(") {
+            return None;
+        }
+
+        let values_start = values + 2;
+        let values_close = matching_bracket(tokens, values + 1).unwrap_or(tokens.len());
+
+        let offset =
+            |start: usize| move |range: Range<usize>| range.start + start..range.end + start;
+
+        Some(InsertValues {
+            columns: split_comma_ranges(&tokens[columns_open + 1..columns_close])
+                .into_iter()
+                .map(offset(columns_open + 1))
+                .collect(),
+            values: split_comma_ranges(&tokens[values_start..values_close])
+                .into_iter()
+                .map(offset(values_start))
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer;
+
+    /// The text of the statements of `text`, split as plain CQL or as a cqlsh script
+    fn split_text(text: &str, cqlsh: bool) -> Vec<&str> {
+        split_statements(&lexer::tokenize(text), cqlsh)
+            .iter()
+            .map(|statement| &text[statement.start..statement.end])
+            .collect()
+    }
+
+    #[test]
+    fn semicolons_end_statements() {
+        assert_eq!(
+            split_text(
+                "USE ks; SELECT * FROM t;\nINSERT INTO t (a) VALUES (';');",
+                false
+            ),
+            [
+                "USE ks;",
+                "SELECT * FROM t;",
+                "INSERT INTO t (a) VALUES (';');"
+            ]
+        );
+    }
+
+    #[test]
+    fn semicolon_ends_open_brackets() {
+        assert_eq!(
+            split_text("INSERT INTO t (a, b) VALUES (1;\nSELECT * FROM t;", false),
+            ["INSERT INTO t (a, b) VALUES (1;", "SELECT * FROM t;"]
+        );
+    }
+
+    #[test]
+    fn missing_semicolon_ends_at_next_statement() {
+        assert_eq!(
+            split_text(
+                "SELECT * FROM t WHERE\nINSERT INTO t (a) VALUES (1);",
+                false
+            ),
+            ["SELECT * FROM t WHERE", "INSERT INTO t (a) VALUES (1);"]
+        );
+        assert_eq!(
+            split_text(
+                "SELECT * FROM t\nDROP TABLE t;\nSELECT * FROM u\nCREATE INDEX ON u (v);",
+                false
+            ),
+            [
+                "SELECT * FROM t",
+                "DROP TABLE t;",
+                "SELECT * FROM u",
+                "CREATE INDEX ON u (v);"
+            ]
+        );
+    }
+
+    #[test]
+    fn columns_named_like_objects_stay_in_alter_table() {
+        for column in ["user", "role", "type", "index", "function", "trigger"] {
+            let text = format!("ALTER TABLE t\nDROP {column};");
+            assert_eq!(split_text(&text, false), [text.as_str()]);
+
+            let text = format!("ALTER TABLE t\nDROP ({column}, v);");
+            assert_eq!(split_text(&text, false), [text.as_str()]);
+        }
+
+        let text = "ALTER TABLE t\nALTER type TYPE text;";
+        assert_eq!(split_text(text, false), [text]);
+    }
+
+    #[test]
+    fn view_query_stays_in_view() {
+        let text =
+            "CREATE MATERIALIZED VIEW v AS\nSELECT * FROM t\nWHERE a IS NOT NULL PRIMARY KEY (a);";
+        assert_eq!(split_text(text, false), [text]);
+    }
+
+    #[test]
+    fn permissions_stay_in_grant() {
+        for text in [
+            "GRANT\nSELECT ON KEYSPACE ks TO r;",
+            "REVOKE\nUPDATE ON t FROM r;",
+            "RESTRICT\nSELECT ON t TO r;",
+        ] {
+            assert_eq!(split_text(text, false), [text]);
+        }
+    }
+
+    #[test]
+    fn cqlsh_commands_end_with_their_line() {
+        assert_eq!(
+            split_text("CONSISTENCY QUORUM\nPAGING OFF", true),
+            ["CONSISTENCY QUORUM", "PAGING OFF"]
+        );
+        assert_eq!(
+            split_text("CONSISTENCY QUORUM\nPAGING OFF", false),
+            ["CONSISTENCY QUORUM\nPAGING OFF"]
+        );
+    }
+}
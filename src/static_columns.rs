@@ -0,0 +1,195 @@
+//! The rules of static columns, which hold a single value per partition: they need clustering
+//! columns to be shared by, and writes touching only them address the partition, not a row, so
+//! they can't restrict clustering columns.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    diagnostics::{Lint, Problem},
+    lexer::Token,
+    schema::{self, Table},
+    statement::{Statement, StatementKind, split_comma_ranges},
+    type_definition,
+};
+
+const STATIC_WITHOUT_CLUSTERING: Lint = Lint {
+    code: "static-without-clustering",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/ddl.html#static-columns",
+};
+
+const STATIC_ONLY_CLUSTERING: Lint = Lint {
+    code: "static-only-clustering",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/ddl.html#static-columns",
+};
+
+fn error(token: &Token, message: String, lint: Lint) -> Problem {
+    Problem {
+        span: token.start..token.end,
+        severity: DiagnosticSeverity::ERROR,
+        message,
+        lint: Some(lint),
+    }
+}
+
+/// The names of the columns a `CREATE TABLE` or the `ADD` of an `ALTER TABLE` declares `STATIC`
+fn static_definitions<'a>(statement: &Statement<'a>) -> Vec<&'a Token<'a>> {
+    let Some(range) = type_definition::definitions(statement) else {
+        return vec![];
+    };
+
+    let tokens = &statement.tokens[range];
+
+    split_comma_ranges(tokens)
+        .into_iter()
+        .filter_map(|part| {
+            let definition = &tokens[part];
+
+            schema::is_static(definition)
+                .then(|| definition.first())
+                .flatten()
+        })
+        .collect()
+}
+
+/// The static columns defined for `table`, which has no clustering columns
+fn check_definitions(statement: &Statement, table: &Table) -> Vec<Problem> {
+    if !table.clustering_key.is_empty() {
+        return vec![];
+    }
+
+    static_definitions(statement)
+        .into_iter()
+        .map(|name| {
+            error(
+                name,
+                format!(
+                    "`{}` is static, but `{}` has no clustering columns: a partition has a single row, so static columns are pointless and Cassandra rejects them",
+                    name.text, table.name
+                ),
+                STATIC_WITHOUT_CLUSTERING,
+            )
+        })
+        .collect()
+}
+
+/// The columns an `UPDATE` sets or a `DELETE` deletes, none for a `DELETE` of whole rows
+fn written_columns<'a>(statement: &Statement<'a>) -> Vec<&'a Token<'a>> {
+    let tokens = statement.tokens;
+
+    let list = match statement.kind() {
+        StatementKind::Update => {
+            let Some(set) = statement.find_keyword("SET") else {
+                return vec![];
+            };
+            let end = statement
+                .find_keyword_from("WHERE", set)
+                .unwrap_or(tokens.len());
+
+            &tokens[set + 1..end]
+        }
+        StatementKind::Delete => {
+            let end = statement.find_keyword("FROM").unwrap_or(tokens.len());
+
+            &tokens[1..end]
+        }
+        _ => return vec![],
+    };
+
+    // `tags = tags + {'new'}`, `items[0]`, `address.city`: the column comes first
+    split_comma_ranges(list)
+        .into_iter()
+        .filter_map(|part| list[part].first())
+        .collect()
+}
+
+/// The names of the clustering columns of `table` the `WHERE` clause of `statement`
+/// restricts, including those of tuples such as `(a, b) > (1, 2)`
+fn restricted_clustering<'a>(statement: &Statement<'a>, table: &Table) -> Vec<&'a Token<'a>> {
+    let Some(r#where) = statement.find_keyword("WHERE") else {
+        return vec![];
+    };
+
+    let end = statement
+        .find_keyword_from("IF", r#where)
+        .unwrap_or(statement.tokens.len());
+    let tokens = &statement.tokens[r#where + 1..end];
+
+    tokens
+        .windows(2)
+        .filter(|pair| {
+            let next = &pair[1];
+
+            matches!(next.text, "=" | "<" | "<=" | ">" | ">=" | "," | ")")
+                || next.is_keyword("IN")
+                || next.is_keyword("CONTAINS")
+        })
+        .map(|pair| &pair[0])
+        .filter(|token| {
+            token
+                .identifier()
+                .is_some_and(|name| table.clustering_key.contains(&name))
+        })
+        .collect()
+}
+
+/// An `UPDATE` or `DELETE` writing only static columns of `table` while restricting its
+/// clustering columns
+fn check_write(statement: &Statement, table: &Table) -> Vec<Problem> {
+    let written = written_columns(statement);
+
+    let only_static = !written.is_empty()
+        && written.iter().all(|column| {
+            column
+                .identifier()
+                .is_some_and(|name| table.is_static(&name))
+        });
+
+    if !only_static {
+        return vec![];
+    }
+
+    let verb = match statement.kind() {
+        StatementKind::Update => "sets",
+        _ => "deletes",
+    };
+
+    restricted_clustering(statement, table)
+        .into_iter()
+        .map(|column| {
+            error(
+                column,
+                format!(
+                    "`{}` is a clustering column, but this statement only {verb} static columns, which belong to the whole partition: restrict the partition key alone",
+                    column.text
+                ),
+                STATIC_ONLY_CLUSTERING,
+            )
+        })
+        .collect()
+}
+
+/// Checks `statement` against the rules of static columns, `table` being the one it operates on
+/// if known
+pub fn check(
+    statement: &Statement,
+    table: Option<&Table>,
+    current_keyspace: Option<&str>,
+) -> Vec<Problem> {
+    match statement.kind() {
+        StatementKind::CreateTable => {
+            match schema::parse_create_table(statement, &current_keyspace.map(str::to_string)) {
+                Some(table) => check_definitions(statement, &table),
+                None => vec![],
+            }
+        }
+        StatementKind::AlterTable => match table {
+            Some(table) => check_definitions(statement, table),
+            None => vec![],
+        },
+        StatementKind::Update | StatementKind::Delete => match table {
+            Some(table) => check_write(statement, table),
+            None => vec![],
+        },
+        _ => vec![],
+    }
+}
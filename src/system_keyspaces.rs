@@ -0,0 +1,1100 @@
+//! The tables of the keyspaces Cassandra keeps its own state in, `system`, `system_schema`,
+//! `system_auth`, and the virtual `system_views` and `system_virtual_schema`, bundled so that
+//! the queries of operational scripts get their columns completed and described without a
+//! connection. Cluster schemas leave these keyspaces out, and they're only looked up by name,
+//! e.g. `system.local`, so that an unqualified `peers` is never taken for `system.peers`.
+//!
+//! The tables of `system_views` and `system_virtual_schema` are virtual: read from the node's
+//! memory, they can't be written to, and only exist as of Cassandra 4.0.
+
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    cql_type::CqlType,
+    diagnostics::Problem,
+    lexer,
+    schema::{Column, Keyspace, Table},
+    statement::{Statement, StatementKind},
+    target::Target,
+};
+
+/// The keyspaces of virtual tables
+const VIRTUAL_KEYSPACES: &[&str] = &["system_views", "system_virtual_schema"];
+
+/// A table of a system keyspace, as of Cassandra 4.0 and later
+struct SystemTable {
+    keyspace: &'static str,
+    name: &'static str,
+    documentation: &'static str,
+    partition_key: &'static [&'static str],
+    clustering_key: &'static [&'static str],
+    /// The columns as their name, type and documentation
+    columns: &'static [(&'static str, &'static str, &'static str)],
+}
+
+/// The columns every table describing the schema objects of a keyspace starts with
+const KEYSPACE_NAME: (&str, &str, &str) = (
+    "keyspace_name",
+    "text",
+    "The keyspace the object belongs to.",
+);
+
+const TABLE_NAME: (&str, &str, &str) = ("table_name", "text", "The name of the table.");
+
+/// The columns of the latency tables of `system_views`
+const LATENCY: &[(&str, &str, &str)] = &[
+    KEYSPACE_NAME,
+    TABLE_NAME,
+    ("count", "bigint", "The number of requests measured."),
+    ("max_ms", "double", "The highest latency, in milliseconds."),
+    (
+        "median_ms",
+        "double",
+        "The median latency, in milliseconds.",
+    ),
+    (
+        "p99th_ms",
+        "double",
+        "The 99th percentile latency, in milliseconds.",
+    ),
+    (
+        "per_second",
+        "double",
+        "The recent rate of requests, per second.",
+    ),
+];
+
+/// The columns of the per-read histograms of `system_views`
+const PER_READ: &[(&str, &str, &str)] = &[
+    KEYSPACE_NAME,
+    TABLE_NAME,
+    ("count", "bigint", "The number of reads measured."),
+    ("max", "double", "The highest count of a read."),
+    ("median", "double", "The median count of a read."),
+    ("p99th", "double", "The 99th percentile count of a read."),
+];
+
+/// The columns of the tables of `system_schema` and `system_virtual_schema` describing columns
+const COLUMNS: &[(&str, &str, &str)] = &[
+    KEYSPACE_NAME,
+    TABLE_NAME,
+    ("column_name", "text", "The name of the column."),
+    (
+        "clustering_order",
+        "text",
+        "`asc` or `desc` for clustering columns, `none` for the others.",
+    ),
+    (
+        "column_name_bytes",
+        "blob",
+        "The name of the column, as bytes.",
+    ),
+    (
+        "kind",
+        "text",
+        "`partition_key`, `clustering`, `regular` or `static`.",
+    ),
+    (
+        "position",
+        "int",
+        "The position of the column in the partition key or among the clustering columns, -1 for the others.",
+    ),
+    ("type", "text", "The type of the column, as written in CQL."),
+];
+
+const TABLES: &[SystemTable] = &[
+    SystemTable {
+        keyspace: "system",
+        name: "local",
+        documentation: "The node the query is sent to: its identity, addresses, tokens and versions.",
+        partition_key: &["key"],
+        clustering_key: &[],
+        columns: &[
+            ("key", "text", "Always `local`."),
+            (
+                "bootstrapped",
+                "text",
+                "`COMPLETED` once the node has joined the ring.",
+            ),
+            (
+                "broadcast_address",
+                "inet",
+                "The address the node gives the other nodes.",
+            ),
+            (
+                "broadcast_port",
+                "int",
+                "The port the node gives the other nodes.",
+            ),
+            ("cluster_name", "text", "The name of the cluster."),
+            ("cql_version", "text", "The version of CQL the node speaks."),
+            ("data_center", "text", "The datacenter of the node."),
+            (
+                "gossip_generation",
+                "int",
+                "The generation of the node's gossip state, bumped on each restart.",
+            ),
+            ("host_id", "uuid", "The identifier of the node."),
+            (
+                "listen_address",
+                "inet",
+                "The address the node listens on for the other nodes.",
+            ),
+            (
+                "listen_port",
+                "int",
+                "The port the node listens on for the other nodes.",
+            ),
+            (
+                "native_protocol_version",
+                "text",
+                "The highest version of the native protocol the node speaks.",
+            ),
+            (
+                "partitioner",
+                "text",
+                "The class of the partitioner hashing partition keys to tokens.",
+            ),
+            ("rack", "text", "The rack of the node."),
+            (
+                "release_version",
+                "text",
+                "The version of Cassandra the node runs.",
+            ),
+            (
+                "rpc_address",
+                "inet",
+                "The address the node listens on for clients.",
+            ),
+            (
+                "rpc_port",
+                "int",
+                "The port the node listens on for clients.",
+            ),
+            (
+                "schema_version",
+                "uuid",
+                "The version of the schema the node has, the same on every node once it's agreed on.",
+            ),
+            ("tokens", "set<text>", "The tokens the node owns."),
+            (
+                "truncated_at",
+                "map<uuid, blob>",
+                "When the tables were last truncated, by table id.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system",
+        name: "peers_v2",
+        documentation: "The other nodes of the cluster, as the node sees them, with their ports.",
+        partition_key: &["peer"],
+        clustering_key: &["peer_port"],
+        columns: &[
+            ("peer", "inet", "The address of the node."),
+            ("peer_port", "int", "The port of the node."),
+            ("data_center", "text", "The datacenter of the node."),
+            ("host_id", "uuid", "The identifier of the node."),
+            (
+                "native_address",
+                "inet",
+                "The address the node listens on for clients.",
+            ),
+            (
+                "native_port",
+                "int",
+                "The port the node listens on for clients.",
+            ),
+            (
+                "preferred_ip",
+                "inet",
+                "The address the other nodes prefer to reach the node at.",
+            ),
+            (
+                "preferred_port",
+                "int",
+                "The port the other nodes prefer to reach the node at.",
+            ),
+            ("rack", "text", "The rack of the node."),
+            (
+                "release_version",
+                "text",
+                "The version of Cassandra the node runs.",
+            ),
+            (
+                "schema_version",
+                "uuid",
+                "The version of the schema the node has.",
+            ),
+            ("tokens", "set<text>", "The tokens the node owns."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system",
+        name: "peers",
+        documentation: "The other nodes of the cluster, as the node sees them. Superseded by `peers_v2`, which has their ports.",
+        partition_key: &["peer"],
+        clustering_key: &[],
+        columns: &[
+            ("peer", "inet", "The address of the node."),
+            ("data_center", "text", "The datacenter of the node."),
+            ("host_id", "uuid", "The identifier of the node."),
+            (
+                "preferred_ip",
+                "inet",
+                "The address the other nodes prefer to reach the node at.",
+            ),
+            ("rack", "text", "The rack of the node."),
+            (
+                "release_version",
+                "text",
+                "The version of Cassandra the node runs.",
+            ),
+            (
+                "rpc_address",
+                "inet",
+                "The address the node listens on for clients.",
+            ),
+            (
+                "schema_version",
+                "uuid",
+                "The version of the schema the node has.",
+            ),
+            ("tokens", "set<text>", "The tokens the node owns."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system",
+        name: "size_estimates",
+        documentation: "Estimates of the partitions of the tables in each token range the node owns, refreshed every few minutes.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name", "range_start", "range_end"],
+        columns: &[
+            KEYSPACE_NAME,
+            TABLE_NAME,
+            ("range_start", "text", "The first token of the range."),
+            ("range_end", "text", "The last token of the range."),
+            (
+                "mean_partition_size",
+                "bigint",
+                "The mean size of a partition in the range, in bytes.",
+            ),
+            (
+                "partitions_count",
+                "bigint",
+                "The estimated number of partitions in the range.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system",
+        name: "compaction_history",
+        documentation: "The compactions the node ran, kept for a week.",
+        partition_key: &["id"],
+        clustering_key: &[],
+        columns: &[
+            ("id", "uuid", "The identifier of the compaction."),
+            ("bytes_in", "bigint", "The size of the compacted SSTables."),
+            ("bytes_out", "bigint", "The size of the SSTables written."),
+            ("columnfamily_name", "text", "The table compacted."),
+            ("compacted_at", "timestamp", "When the compaction finished."),
+            ("keyspace_name", "text", "The keyspace of the table."),
+            (
+                "rows_merged",
+                "map<int, bigint>",
+                "How many rows were merged from how many SSTables.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system",
+        name: "built_views",
+        documentation: "The materialized views whose initial build finished on the node.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["view_name"],
+        columns: &[
+            KEYSPACE_NAME,
+            ("view_name", "text", "The name of the view."),
+            (
+                "status_replicated",
+                "boolean",
+                "Whether the build status was replicated to `system_distributed`.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system",
+        name: "prepared_statements",
+        documentation: "The statements prepared on the node, prepared again when it restarts.",
+        partition_key: &["prepared_id"],
+        clustering_key: &[],
+        columns: &[
+            (
+                "prepared_id",
+                "blob",
+                "The identifier of the prepared statement.",
+            ),
+            (
+                "logged_keyspace",
+                "text",
+                "The keyspace the statement was prepared in.",
+            ),
+            ("query_string", "text", "The text of the statement."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_schema",
+        name: "keyspaces",
+        documentation: "The keyspaces of the cluster.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &[],
+        columns: &[
+            ("keyspace_name", "text", "The name of the keyspace."),
+            (
+                "durable_writes",
+                "boolean",
+                "Whether writes go through the commit log.",
+            ),
+            (
+                "replication",
+                "frozen<map<text, text>>",
+                "The replication strategy, as its `class` and options, e.g. the replication factor of each datacenter.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_schema",
+        name: "tables",
+        documentation: "The tables of the keyspaces, with their options.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name"],
+        columns: &[
+            KEYSPACE_NAME,
+            TABLE_NAME,
+            (
+                "additional_write_policy",
+                "text",
+                "When writes are sent to another replica, as `speculative_retry` for reads.",
+            ),
+            (
+                "bloom_filter_fp_chance",
+                "double",
+                "The false positive chance of the bloom filters of the SSTables.",
+            ),
+            (
+                "caching",
+                "frozen<map<text, text>>",
+                "What is cached: `keys` and `rows_per_partition`.",
+            ),
+            (
+                "cdc",
+                "boolean",
+                "Whether changes are captured to the CDC log.",
+            ),
+            ("comment", "text", "The comment of the table."),
+            (
+                "compaction",
+                "frozen<map<text, text>>",
+                "The compaction strategy, as its `class` and options.",
+            ),
+            (
+                "compression",
+                "frozen<map<text, text>>",
+                "The compression of the SSTables, as its `class` and options.",
+            ),
+            (
+                "crc_check_chance",
+                "double",
+                "The chance that the checksum of compressed data is checked on reads.",
+            ),
+            (
+                "default_time_to_live",
+                "int",
+                "The TTL of the writes that don't give one, in seconds.",
+            ),
+            (
+                "extensions",
+                "frozen<map<text, blob>>",
+                "The options of extensions.",
+            ),
+            (
+                "flags",
+                "frozen<set<text>>",
+                "The kind of table, e.g. `compound` or `counter`.",
+            ),
+            (
+                "gc_grace_seconds",
+                "int",
+                "How long tombstones are kept before they may be purged, in seconds.",
+            ),
+            ("id", "uuid", "The identifier of the table."),
+            (
+                "max_index_interval",
+                "int",
+                "The most partitions between entries of the partition summary.",
+            ),
+            (
+                "memtable_flush_period_in_ms",
+                "int",
+                "How often the memtable is flushed, 0 for only when it's full.",
+            ),
+            (
+                "min_index_interval",
+                "int",
+                "The fewest partitions between entries of the partition summary.",
+            ),
+            (
+                "read_repair",
+                "text",
+                "`BLOCKING` or `NONE`: whether reads repair the replicas they find inconsistent.",
+            ),
+            (
+                "speculative_retry",
+                "text",
+                "When reads are retried on another replica, e.g. `99p`.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_schema",
+        name: "columns",
+        documentation: "The columns of the tables and views.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name", "column_name"],
+        columns: COLUMNS,
+    },
+    SystemTable {
+        keyspace: "system_schema",
+        name: "dropped_columns",
+        documentation: "The columns dropped from the tables, whose data SSTables may still hold.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name", "column_name"],
+        columns: &[
+            KEYSPACE_NAME,
+            TABLE_NAME,
+            ("column_name", "text", "The name of the column."),
+            ("dropped_time", "timestamp", "When the column was dropped."),
+            ("kind", "text", "`regular` or `static`."),
+            ("type", "text", "The type the column had."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_schema",
+        name: "views",
+        documentation: "The materialized views of the keyspaces.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["view_name"],
+        columns: &[
+            KEYSPACE_NAME,
+            ("view_name", "text", "The name of the view."),
+            (
+                "base_table_id",
+                "uuid",
+                "The identifier of the table the view selects from.",
+            ),
+            (
+                "base_table_name",
+                "text",
+                "The name of the table the view selects from.",
+            ),
+            ("comment", "text", "The comment of the view."),
+            (
+                "gc_grace_seconds",
+                "int",
+                "How long tombstones are kept before they may be purged, in seconds.",
+            ),
+            ("id", "uuid", "The identifier of the view."),
+            (
+                "include_all_columns",
+                "boolean",
+                "Whether the view selects every column, `SELECT *`.",
+            ),
+            (
+                "where_clause",
+                "text",
+                "The `WHERE` clause of the view's query.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_schema",
+        name: "indexes",
+        documentation: "The secondary indexes of the tables.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name", "index_name"],
+        columns: &[
+            KEYSPACE_NAME,
+            TABLE_NAME,
+            ("index_name", "text", "The name of the index."),
+            (
+                "kind",
+                "text",
+                "`COMPOSITES`, `KEYS` or `CUSTOM` for indexes of another class, such as SAI.",
+            ),
+            (
+                "options",
+                "frozen<map<text, text>>",
+                "The options of the index: its `target` column, and its `class_name` when custom.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_schema",
+        name: "types",
+        documentation: "The user-defined types of the keyspaces.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["type_name"],
+        columns: &[
+            KEYSPACE_NAME,
+            ("type_name", "text", "The name of the type."),
+            (
+                "field_names",
+                "frozen<list<text>>",
+                "The names of the fields, in order.",
+            ),
+            (
+                "field_types",
+                "frozen<list<text>>",
+                "The types of the fields, in the order of their names.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_schema",
+        name: "functions",
+        documentation: "The user-defined functions of the keyspaces.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["function_name", "argument_types"],
+        columns: &[
+            KEYSPACE_NAME,
+            ("function_name", "text", "The name of the function."),
+            (
+                "argument_types",
+                "frozen<list<text>>",
+                "The types of the arguments, telling overloads apart.",
+            ),
+            (
+                "argument_names",
+                "frozen<list<text>>",
+                "The names of the arguments.",
+            ),
+            ("body", "text", "The code of the function."),
+            (
+                "called_on_null_input",
+                "boolean",
+                "Whether the function is called when an argument is null, rather than returning null.",
+            ),
+            ("language", "text", "The language of the body, e.g. `java`."),
+            ("return_type", "text", "The type of the result."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_schema",
+        name: "aggregates",
+        documentation: "The user-defined aggregates of the keyspaces.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["aggregate_name", "argument_types"],
+        columns: &[
+            KEYSPACE_NAME,
+            ("aggregate_name", "text", "The name of the aggregate."),
+            (
+                "argument_types",
+                "frozen<list<text>>",
+                "The types of the arguments, telling overloads apart.",
+            ),
+            (
+                "final_func",
+                "text",
+                "The function turning the final state into the result.",
+            ),
+            ("initcond", "text", "The initial state."),
+            ("return_type", "text", "The type of the result."),
+            (
+                "state_func",
+                "text",
+                "The function updating the state with each row.",
+            ),
+            ("state_type", "text", "The type of the state."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_schema",
+        name: "triggers",
+        documentation: "The triggers of the tables.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name", "trigger_name"],
+        columns: &[
+            KEYSPACE_NAME,
+            TABLE_NAME,
+            ("trigger_name", "text", "The name of the trigger."),
+            (
+                "options",
+                "frozen<map<text, text>>",
+                "The options of the trigger: its `class`.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_auth",
+        name: "roles",
+        documentation: "The roles of the cluster.",
+        partition_key: &["role"],
+        clustering_key: &[],
+        columns: &[
+            ("role", "text", "The name of the role."),
+            (
+                "can_login",
+                "boolean",
+                "Whether the role may log in, as a user.",
+            ),
+            (
+                "is_superuser",
+                "boolean",
+                "Whether the role has every permission.",
+            ),
+            ("member_of", "set<text>", "The roles granted to the role."),
+            ("salted_hash", "text", "The hash of the role's password."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_auth",
+        name: "role_members",
+        documentation: "The roles granted to each role, by the role granted.",
+        partition_key: &["role"],
+        clustering_key: &["member"],
+        columns: &[
+            ("role", "text", "The role granted."),
+            ("member", "text", "The role it's granted to."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_auth",
+        name: "role_permissions",
+        documentation: "The permissions granted to the roles on each resource.",
+        partition_key: &["role"],
+        clustering_key: &["resource"],
+        columns: &[
+            ("role", "text", "The role granted the permissions."),
+            ("resource", "text", "The resource, e.g. `data/ks/table`."),
+            (
+                "permissions",
+                "set<text>",
+                "The permissions granted, e.g. `SELECT`.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_auth",
+        name: "resource_role_permissons_index",
+        documentation: "The roles granted permissions on each resource, the index of `role_permissions` by resource.",
+        partition_key: &["resource"],
+        clustering_key: &["role"],
+        columns: &[
+            ("resource", "text", "The resource."),
+            ("role", "text", "A role granted permissions on it."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_auth",
+        name: "network_permissions",
+        documentation: "The datacenters the roles may access, given with `ACCESS TO DATACENTERS`.",
+        partition_key: &["role"],
+        clustering_key: &[],
+        columns: &[
+            ("role", "text", "The role."),
+            (
+                "dcs",
+                "frozen<set<text>>",
+                "The datacenters the role may access.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "clients",
+        documentation: "The clients connected to the node.",
+        partition_key: &["address"],
+        clustering_key: &["port"],
+        columns: &[
+            ("address", "inet", "The address of the client."),
+            ("port", "int", "The port of the client."),
+            (
+                "client_options",
+                "frozen<map<text, text>>",
+                "The options the client sent on connecting.",
+            ),
+            (
+                "connection_stage",
+                "text",
+                "The stage of the connection, e.g. `ready`.",
+            ),
+            ("driver_name", "text", "The name of the client's driver."),
+            (
+                "driver_version",
+                "text",
+                "The version of the client's driver.",
+            ),
+            ("hostname", "text", "The host name of the client."),
+            ("keyspace_name", "text", "The keyspace the connection uses."),
+            (
+                "protocol_version",
+                "int",
+                "The version of the native protocol the client speaks.",
+            ),
+            (
+                "request_count",
+                "bigint",
+                "The number of requests sent on the connection.",
+            ),
+            (
+                "ssl_cipher_suite",
+                "text",
+                "The cipher suite of the connection, if encrypted.",
+            ),
+            (
+                "ssl_enabled",
+                "boolean",
+                "Whether the connection is encrypted.",
+            ),
+            (
+                "ssl_protocol",
+                "text",
+                "The protocol of the connection, if encrypted.",
+            ),
+            ("username", "text", "The role the client logged in as."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "settings",
+        documentation: "The settings of the node, from `cassandra.yaml` and those changed since it started.",
+        partition_key: &["name"],
+        clustering_key: &[],
+        columns: &[
+            ("name", "text", "The name of the setting."),
+            ("value", "text", "Its value."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "system_properties",
+        documentation: "The system properties and environment variables of the node that concern Cassandra.",
+        partition_key: &["name"],
+        clustering_key: &[],
+        columns: &[
+            ("name", "text", "The name of the property."),
+            ("value", "text", "Its value."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "thread_pools",
+        documentation: "The thread pools of the node, where a backlog of pending or blocked tasks shows a bottleneck.",
+        partition_key: &["name"],
+        clustering_key: &[],
+        columns: &[
+            ("name", "text", "The name of the thread pool."),
+            ("active_tasks", "int", "The tasks running."),
+            (
+                "active_tasks_limit",
+                "int",
+                "The most tasks that may run at once.",
+            ),
+            (
+                "blocked_tasks",
+                "bigint",
+                "The tasks blocked, waiting for room in the queue.",
+            ),
+            (
+                "blocked_tasks_all_time",
+                "bigint",
+                "The tasks ever blocked.",
+            ),
+            ("completed_tasks", "bigint", "The tasks completed."),
+            ("pending_tasks", "int", "The tasks queued."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "caches",
+        documentation: "The caches of the node, with their hit ratios.",
+        partition_key: &["name"],
+        clustering_key: &[],
+        columns: &[
+            ("name", "text", "The name of the cache."),
+            (
+                "capacity_bytes",
+                "bigint",
+                "The most the cache may hold, in bytes.",
+            ),
+            ("entry_count", "int", "The entries cached."),
+            ("hit_count", "bigint", "The requests served from the cache."),
+            (
+                "hit_ratio",
+                "double",
+                "The share of the requests served from the cache.",
+            ),
+            (
+                "recent_hit_rate_per_second",
+                "bigint",
+                "The recent rate of hits, per second.",
+            ),
+            (
+                "recent_request_rate_per_second",
+                "bigint",
+                "The recent rate of requests, per second.",
+            ),
+            ("request_count", "bigint", "The requests of the cache."),
+            ("size_bytes", "bigint", "The size of the cache, in bytes."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "sstable_tasks",
+        documentation: "The compactions, cleanups and other tasks on SSTables running on the node.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name", "task_id"],
+        columns: &[
+            KEYSPACE_NAME,
+            TABLE_NAME,
+            ("task_id", "timeuuid", "The identifier of the task."),
+            ("kind", "text", "The kind of task, e.g. `compaction`."),
+            ("progress", "bigint", "How far the task is, in `unit`."),
+            ("total", "bigint", "How far the task goes, in `unit`."),
+            ("unit", "text", "The unit of the progress, e.g. `bytes`."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "disk_usage",
+        documentation: "The disk space the tables take on the node.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name"],
+        columns: &[
+            KEYSPACE_NAME,
+            TABLE_NAME,
+            (
+                "mebibytes",
+                "bigint",
+                "The space taken by the table, in mebibytes.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "max_partition_size",
+        documentation: "The size of the largest partition of the tables on the node.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name"],
+        columns: &[
+            KEYSPACE_NAME,
+            TABLE_NAME,
+            (
+                "mebibytes",
+                "bigint",
+                "The size of the largest partition, in mebibytes.",
+            ),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "local_read_latency",
+        documentation: "The latency of the reads of the tables on the node, as a replica.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name"],
+        columns: LATENCY,
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "local_write_latency",
+        documentation: "The latency of the writes of the tables on the node, as a replica.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name"],
+        columns: LATENCY,
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "local_scan_latency",
+        documentation: "The latency of the range reads of the tables on the node, as a replica.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name"],
+        columns: LATENCY,
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "coordinator_read_latency",
+        documentation: "The latency of the reads of the tables the node coordinated.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name"],
+        columns: LATENCY,
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "coordinator_write_latency",
+        documentation: "The latency of the writes of the tables the node coordinated.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name"],
+        columns: LATENCY,
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "coordinator_scan_latency",
+        documentation: "The latency of the range reads of the tables the node coordinated.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name"],
+        columns: LATENCY,
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "tombstones_per_read",
+        documentation: "The tombstones the reads of the tables scanned on the node.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name"],
+        columns: PER_READ,
+    },
+    SystemTable {
+        keyspace: "system_views",
+        name: "rows_per_read",
+        documentation: "The rows the reads of the tables scanned on the node.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name"],
+        columns: PER_READ,
+    },
+    SystemTable {
+        keyspace: "system_virtual_schema",
+        name: "keyspaces",
+        documentation: "The virtual keyspaces of the node.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &[],
+        columns: &[("keyspace_name", "text", "The name of the keyspace.")],
+    },
+    SystemTable {
+        keyspace: "system_virtual_schema",
+        name: "tables",
+        documentation: "The virtual tables of the node.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name"],
+        columns: &[
+            KEYSPACE_NAME,
+            TABLE_NAME,
+            ("comment", "text", "What the table holds."),
+        ],
+    },
+    SystemTable {
+        keyspace: "system_virtual_schema",
+        name: "columns",
+        documentation: "The columns of the virtual tables.",
+        partition_key: &["keyspace_name"],
+        clustering_key: &["table_name", "column_name"],
+        columns: COLUMNS,
+    },
+];
+
+static KEYSPACES: LazyLock<BTreeMap<String, Keyspace>> = LazyLock::new(|| {
+    let mut keyspaces = BTreeMap::<String, Keyspace>::new();
+
+    for table in TABLES {
+        let columns = table
+            .columns
+            .iter()
+            .map(|(name, data_type, _)| Column {
+                name: name.to_string(),
+                data_type: CqlType::parse(&lexer::tokenize(data_type), &mut 0)
+                    .unwrap_or_else(|| CqlType::Custom(data_type.to_string())),
+            })
+            .collect();
+
+        keyspaces
+            .entry(table.keyspace.to_string())
+            .or_default()
+            .tables
+            .insert(
+                table.name.to_string(),
+                Table {
+                    keyspace: table.keyspace.to_string(),
+                    name: table.name.to_string(),
+                    columns,
+                    partition_key: table.partition_key.iter().map(|c| c.to_string()).collect(),
+                    clustering_key: table.clustering_key.iter().map(|c| c.to_string()).collect(),
+                    static_columns: vec![],
+                    options: vec![],
+                    indexes: vec![],
+                },
+            );
+    }
+
+    keyspaces
+});
+
+/// The system keyspace named `name`, if it's one
+pub fn keyspace(name: &str) -> Option<&'static Keyspace> {
+    KEYSPACES.get(name)
+}
+
+fn system_table(keyspace: &str, table: &str) -> Option<&'static SystemTable> {
+    TABLES
+        .iter()
+        .find(|system| system.keyspace == keyspace && system.name == table)
+}
+
+/// The documentation of the system table `table` of `keyspace`, or of its column `column`
+pub fn documentation(keyspace: &str, table: &str, column: Option<&str>) -> Option<&'static str> {
+    let table = system_table(keyspace, table)?;
+
+    match column {
+        Some(column) => table
+            .columns
+            .iter()
+            .find(|(name, ..)| *name == column)
+            .map(|(.., documentation)| *documentation),
+        None => Some(table.documentation),
+    }
+}
+
+/// Whether `keyspace` holds virtual tables
+pub fn is_virtual(keyspace: &str) -> bool {
+    VIRTUAL_KEYSPACES.contains(&keyspace)
+}
+
+/// Checks the statements on virtual tables, `current_keyspace` being the keyspace selected
+/// with `USE`: writing to them fails, and `target` may not have them
+pub fn check(
+    statement: &Statement,
+    current_keyspace: Option<&str>,
+    target: Option<Target>,
+) -> Vec<Problem> {
+    let Some(table) = statement.target_table() else {
+        return vec![];
+    };
+
+    let Some(keyspace) = table
+        .keyspace
+        .as_deref()
+        .or(current_keyspace)
+        .filter(|keyspace| is_virtual(keyspace))
+    else {
+        return vec![];
+    };
+
+    let message = match statement.kind() {
+        StatementKind::Insert | StatementKind::Update | StatementKind::Delete => format!(
+            "`{keyspace}.{}` is a virtual table, which can't be written to",
+            table.name
+        ),
+        _ => match target.filter(|target| !target.has_virtual_tables()) {
+            Some(target) => format!(
+                "`{keyspace}` holds virtual tables, which {target} doesn't have: they need Cassandra 4.0 or later"
+            ),
+            None => return vec![],
+        },
+    };
+
+    vec![Problem {
+        span: table.start..table.end,
+        severity: DiagnosticSeverity::ERROR,
+        message,
+        lint: None,
+    }]
+}
@@ -0,0 +1,93 @@
+//! The database the documents are meant for, and its version if known, e.g. `cassandra-4.1`,
+//! which decides the syntax and system tables available to them. Without a target, whatever
+//! some database supports is accepted.
+
+use std::fmt;
+
+use serde::Deserialize;
+
+/// A database speaking CQL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Database {
+    Cassandra,
+    Scylla,
+    /// DataStax Enterprise
+    Dse,
+}
+
+/// The database and version of the cluster the documents are meant for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Target {
+    pub database: Database,
+    /// The major and minor version, the latest when not given
+    pub version: Option<(u32, u32)>,
+}
+
+impl TryFrom<String> for Target {
+    type Error = String;
+
+    fn try_from(target: String) -> Result<Self, Self::Error> {
+        let (name, version) = match target.split_once('-') {
+            Some((name, version)) => (name, Some(version)),
+            None => (target.as_str(), None),
+        };
+
+        let database = match name.to_ascii_lowercase().as_str() {
+            "cassandra" => Database::Cassandra,
+            "scylla" | "scylladb" => Database::Scylla,
+            "dse" => Database::Dse,
+            _ => {
+                return Err(format!(
+                    "unknown database `{name}`: expected `cassandra`, `scylla` or `dse`"
+                ));
+            }
+        };
+
+        let version = version
+            .map(|version| {
+                let mut parts = version.split('.').map(str::parse::<u32>);
+
+                match (parts.next(), parts.next()) {
+                    (Some(Ok(major)), None) => Ok((major, 0)),
+                    (Some(Ok(major)), Some(Ok(minor))) => Ok((major, minor)),
+                    _ => Err(format!("invalid version `{version}`, e.g. `4.1`")),
+                }
+            })
+            .transpose()?;
+
+        Ok(Target { database, version })
+    }
+}
+
+impl fmt::Display for Database {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Database::Cassandra => "Cassandra",
+            Database::Scylla => "ScyllaDB",
+            Database::Dse => "DSE",
+        })
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.version {
+            Some((major, minor)) => write!(f, "{} {major}.{minor}", self.database),
+            None => write!(f, "{}", self.database),
+        }
+    }
+}
+
+impl Target {
+    /// Whether the target is `database` at `version` or later
+    pub fn is_at_least(&self, database: Database, version: (u32, u32)) -> bool {
+        self.database == database && self.version.is_none_or(|target| target >= version)
+    }
+
+    /// Whether the target has the virtual tables of `system_views` and
+    /// `system_virtual_schema`, which Cassandra 4.0 introduced
+    pub fn has_virtual_tables(&self) -> bool {
+        self.is_at_least(Database::Cassandra, (4, 0)) || self.is_at_least(Database::Dse, (6, 8))
+    }
+}
@@ -0,0 +1,182 @@
+//! Writes that silently create tombstones: explicit nulls, overwritten collections and range
+//! deletes. Tombstones are read until compaction purges them, so tables written this way get
+//! slower to read, up to queries failing on `tombstone_failure_threshold`.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    cql_type::CqlType,
+    diagnostics::{Lint, Problem},
+    lexer::Token,
+    schema::Table,
+    statement::{Statement, StatementKind, split_comma_ranges},
+};
+
+const NULL_VALUE: Lint = Lint {
+    code: "tombstone-null",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/dml.html#insert-statement",
+};
+
+const COLLECTION_OVERWRITE: Lint = Lint {
+    code: "tombstone-collection-overwrite",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/types.html#collections",
+};
+
+const RANGE_DELETE: Lint = Lint {
+    code: "tombstone-range-delete",
+    link: "https://cassandra.apache.org/doc/latest/cassandra/developing/cql/dml.html#delete_statement",
+};
+
+/// The values written by an `INSERT` or the `SET` of an `UPDATE` that replace a column's
+/// value, as the column name token and the value's tokens
+fn written_values<'a>(statement: &Statement<'a>) -> Vec<(&'a Token<'a>, &'a [Token<'a>])> {
+    let tokens = statement.tokens;
+
+    if let Some(insert) = statement.insert_values() {
+        return insert
+            .columns
+            .iter()
+            .zip(&insert.values)
+            .filter_map(|(column, value)| Some((tokens.get(column.start)?, &tokens[value.clone()])))
+            .collect();
+    }
+
+    if statement.kind() != StatementKind::Update {
+        return vec![];
+    }
+
+    let Some(set) = statement.find_keyword("SET") else {
+        return vec![];
+    };
+
+    let end = statement
+        .find_keyword_from("WHERE", set)
+        .unwrap_or(tokens.len());
+    let list = &tokens[set + 1..end];
+
+    split_comma_ranges(list)
+        .into_iter()
+        .filter_map(|part| match &list[part] {
+            [column, equals, value @ ..] if equals.is_punctuation("=") => Some((column, value)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether `value` is a single value replacing the whole column, rather than an update
+/// relative to it such as `tags = tags + {...}`
+fn replaces(value: &[Token]) -> bool {
+    !value
+        .iter()
+        .any(|token| token.is_punctuation("+") || token.is_punctuation("-"))
+}
+
+/// The clustering columns restricted by the `WHERE` clause of a `DELETE`, and whether any is
+/// restricted to a range rather than a value
+fn clustering_restrictions(statement: &Statement, table: &Table) -> (usize, bool) {
+    let Some(r#where) = statement.find_keyword("WHERE") else {
+        return (0, false);
+    };
+
+    let end = statement
+        .find_keyword_from("IF", r#where)
+        .unwrap_or(statement.tokens.len());
+    let tokens = &statement.tokens[r#where + 1..end];
+
+    let mut restricted = vec![];
+    let mut range = false;
+
+    for pair in tokens.windows(2) {
+        let Some(column) = pair[0].identifier() else {
+            continue;
+        };
+
+        if !table.clustering_key.contains(&column) {
+            continue;
+        }
+
+        match pair[1].text {
+            "<" | "<=" | ">" | ">=" => range = true,
+            "=" => {}
+            _ => continue,
+        }
+
+        if !restricted.contains(&column) {
+            restricted.push(column);
+        }
+    }
+
+    (restricted.len(), range)
+}
+
+/// Checks a write to `table` for tombstones it creates, reported with `severity`
+pub fn check(statement: &Statement, table: &Table, severity: DiagnosticSeverity) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    let mut push = |span: std::ops::Range<usize>, message: String, lint: Lint| {
+        problems.push(Problem {
+            span,
+            severity,
+            message,
+            lint: Some(lint),
+        });
+    };
+
+    if statement.kind() == StatementKind::Delete {
+        let (restricted, range) = clustering_restrictions(statement, table);
+        let partial = restricted > 0 && restricted < table.clustering_key.len();
+
+        if (range || partial)
+            && let Some(delete) = statement.tokens.first()
+        {
+            push(
+                delete.start..delete.end,
+                format!(
+                    "Deleting a range of the rows of a partition of `{}` writes a range tombstone, which reads of the partition scan until it's compacted away",
+                    table.name
+                ),
+                RANGE_DELETE,
+            );
+        }
+
+        return problems;
+    }
+
+    for (column, value) in written_values(statement) {
+        let Some(definition) = column.identifier().and_then(|name| table.column(&name)) else {
+            continue;
+        };
+
+        let name = &definition.name;
+
+        if let [null] = value
+            && null.is_keyword("null")
+        {
+            push(
+                null.start..null.end,
+                format!(
+                    "Writing null to `{name}` writes a tombstone: leave the column out instead, or bind `unset` to prepared statements"
+                ),
+                NULL_VALUE,
+            );
+            continue;
+        }
+
+        let collection = matches!(
+            definition.data_type,
+            CqlType::List(_) | CqlType::Set(_) | CqlType::Map(..)
+        );
+
+        if collection && !value.is_empty() && replaces(value) {
+            push(
+                value[0].start..value[value.len() - 1].end,
+                format!(
+                    "Replacing the non-frozen collection `{name}` writes a tombstone deleting its previous elements: add to it with `{name} = {name} + ...` if it only grows"
+                ),
+                COLLECTION_OVERWRITE,
+            );
+        }
+    }
+
+    problems
+}
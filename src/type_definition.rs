@@ -0,0 +1,228 @@
+//! Go to type definition: from a column of a user-defined type, where it's defined or used in
+//! DML, or from a value of such a column, to the `CREATE TYPE` statement of the type.
+
+use std::ops::Range;
+
+use tower_lsp::lsp_types::{Location, Position, Url};
+
+use crate::{
+    cql_type::CqlType,
+    document::{PositionEncodingKind, TextDocument},
+    lexer::{self, Token},
+    schema::{self, Schema},
+    statement::{
+        self, Dialect, Statement, StatementKind, matching_bracket, parse_qualified_name,
+        skip_if_exists, split_comma_ranges,
+    },
+    uri,
+};
+
+/// The token index range of the column or field definitions of `CREATE TABLE`, `CREATE TYPE`,
+/// or the `ADD` of `ALTER TABLE` and `ALTER TYPE`
+pub fn definitions(statement: &Statement) -> Option<Range<usize>> {
+    let tokens = statement.tokens;
+
+    let start = match statement.kind() {
+        StatementKind::CreateTable | StatementKind::CreateType => {
+            tokens.iter().position(|token| token.is_punctuation("("))?
+        }
+        StatementKind::AlterTable | StatementKind::AlterType => {
+            let start = skip_if_exists(tokens, statement.find_keyword("ADD")? + 1);
+
+            if !tokens.get(start)?.is_punctuation("(") {
+                return Some(start..tokens.len());
+            }
+
+            start
+        }
+        _ => return None,
+    };
+
+    let end = matching_bracket(tokens, start).unwrap_or(tokens.len());
+
+    Some(start + 1..end)
+}
+
+/// The type of the column or field defined around the token at `index`
+fn defined_type(statement: &Statement, index: usize) -> Option<CqlType> {
+    let range = definitions(statement)?;
+
+    if !range.contains(&index) {
+        return None;
+    }
+
+    let part = split_comma_ranges(&statement.tokens[range.clone()])
+        .into_iter()
+        .map(|part| part.start + range.start..part.end + range.start)
+        .find(|part| part.contains(&index))?;
+
+    let definition = &statement.tokens[part];
+
+    if definition.first()?.is_keyword("PRIMARY") {
+        return None;
+    }
+
+    CqlType::parse(definition, &mut 1)
+}
+
+/// The type of the column named or given a value at the token at `index` of a DML statement
+fn used_type(
+    statement: &Statement,
+    index: usize,
+    schema: &Schema,
+    keyspace: Option<&str>,
+) -> Option<CqlType> {
+    let table = schema.statement_table(statement, keyspace)?;
+    let tokens = statement.tokens;
+
+    // A value of an `INSERT` goes in the column at the same position
+    if let Some(insert) = statement.insert_values()
+        && let Some(i) = insert
+            .values
+            .iter()
+            .position(|value| value.contains(&index))
+    {
+        let column = insert.columns.get(i)?;
+        let name = tokens.get(column.start)?.identifier()?;
+
+        return Some(table.column(&name)?.data_type.clone());
+    }
+
+    // Otherwise the nearest column named before the value, e.g. in `SET home = {...}`
+    tokens[..=index]
+        .iter()
+        .rev()
+        .filter_map(Token::identifier)
+        .find_map(|name| table.column(&name))
+        .map(|column| column.data_type.clone())
+}
+
+/// The byte ranges of the names of the `CREATE TYPE` statements among `statements` creating
+/// the type `name`, in `keyspace` if given
+fn creations(statements: &[Statement], keyspace: Option<&str>, name: &str) -> Vec<Range<usize>> {
+    statements
+        .iter()
+        .enumerate()
+        .filter(|(_, statement)| statement.kind() == StatementKind::CreateType)
+        .filter_map(|(i, statement)| {
+            let mut at = skip_if_exists(statement.tokens, 2);
+            let created = parse_qualified_name(statement.tokens, &mut at)?;
+            let created_keyspace = created
+                .keyspace
+                .clone()
+                .or_else(|| schema::keyspace_at(statements, i));
+
+            let matches = created.name == name
+                && keyspace.is_none_or(|keyspace| created_keyspace.as_deref() == Some(keyspace));
+
+            matches.then_some(created.start..created.end)
+        })
+        .collect()
+}
+
+/// Finds the `CREATE TYPE` statements of the user-defined types of the column at `position` in
+/// `doc`, in the document itself or in the other `files` of the workspace. `schema` is the
+/// schema the document's statements are replayed on.
+pub fn type_definition<'a>(
+    doc: &TextDocument,
+    uri: &Url,
+    position: &Position,
+    files: impl IntoIterator<Item = (&'a Url, &'a str)>,
+    schema: Schema,
+    dialect: Dialect,
+    position_encoding: PositionEncodingKind,
+) -> Vec<Location> {
+    let Ok(offset) = doc.position_to_byte_idx(position, position_encoding) else {
+        return vec![];
+    };
+
+    let text = doc.text();
+    let tokens = lexer::tokenize(text);
+    let statements = statement::split(&tokens, dialect);
+    let schema = schema.with_statements(&statements);
+
+    let Some(i) = statement::statement_at(&statements, offset) else {
+        return vec![];
+    };
+
+    let statement = &statements[i];
+    let Some(index) = statement
+        .tokens
+        .iter()
+        .position(|token| token.start <= offset && offset < token.end)
+        .or_else(|| {
+            statement
+                .tokens
+                .iter()
+                .position(|token| token.end == offset)
+        })
+    else {
+        return vec![];
+    };
+
+    let keyspace = schema::keyspace_at(&statements, i);
+
+    let data_type = match statement.kind() {
+        StatementKind::Select
+        | StatementKind::Insert
+        | StatementKind::Update
+        | StatementKind::Delete => used_type(statement, index, &schema, keyspace.as_deref()),
+        _ => defined_type(statement, index),
+    };
+
+    let Some(data_type) = data_type else {
+        return vec![];
+    };
+
+    let mut types = data_type.user_types();
+
+    // On a type of `map<frozen<address>, frozen<phone>>`, only that one
+    let under_cursor = statement.tokens[index].identifier();
+    if types
+        .iter()
+        .any(|(_, name)| Some(*name) == under_cursor.as_deref())
+    {
+        types.retain(|(_, name)| Some(*name) == under_cursor.as_deref());
+    }
+
+    // The workspace's files are keyed by their normalized URI
+    let normalized = uri::normalize(uri);
+    let files = files
+        .into_iter()
+        .filter(|(file_uri, _)| **file_uri != normalized)
+        .collect::<Vec<_>>();
+
+    let mut locations = vec![];
+
+    for (type_keyspace, name) in types {
+        let local = creations(&statements, type_keyspace.or(keyspace.as_deref()), name);
+
+        if !local.is_empty() {
+            locations.extend(local.into_iter().map(|range| Location {
+                uri: uri.clone(),
+                range: doc.byte_range_to_range(range, position_encoding),
+            }));
+
+            continue;
+        }
+
+        // Types of other files are matched by name alone when unqualified, as `USE` doesn't
+        // carry over from one file to the next
+        for (file_uri, file_text) in &files {
+            let file_tokens = lexer::tokenize(file_text);
+            let file_statements = statement::split(&file_tokens, dialect);
+            let file_doc = TextDocument::new(file_text);
+
+            locations.extend(
+                creations(&file_statements, type_keyspace, name)
+                    .into_iter()
+                    .map(|range| Location {
+                        uri: (*file_uri).clone(),
+                        range: file_doc.byte_range_to_range(range, position_encoding),
+                    }),
+            );
+        }
+    }
+
+    locations
+}
@@ -0,0 +1,64 @@
+//! Document URIs as keys. Clients may spell a file's URI differently than the server derives
+//! it from the file's path, e.g. VS Code sends `file:///c%3A/src/schema.cql` on Windows where
+//! the path gives `file:///C:/src/schema.cql`, and documents needn't be files at all, e.g.
+//! unsaved `untitled:Untitled-1` documents or those of virtual file systems.
+
+use tower_lsp::lsp_types::Url;
+
+/// The scheme of the documents holding the CQL embedded in other files, which clients open
+/// when the `embeddedDocuments` setting is on, e.g. `cql-embedded:///src/UserDao.java/42`
+pub const EMBEDDED_SCHEME: &str = "cql-embedded";
+
+/// Whether `uri` names a file on disk
+pub fn is_file(uri: &Url) -> bool {
+    uri.scheme() == "file"
+}
+
+/// Decodes the `%XX` escapes of `text`
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) if bytes[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The canonical spelling of `uri`, so that two spellings of a file's URI compare equal: the
+/// path of file URIs is encoded the same way whatever the client escaped, and a Windows drive
+/// letter is lower case and followed by a plain colon. Other URIs are returned as they are.
+pub fn normalize(uri: &Url) -> Url {
+    if !is_file(uri) {
+        return uri.clone();
+    }
+
+    let mut path = percent_decode(uri.path());
+
+    // `/C:/src`
+    if let [b'/', letter, b':', ..] = path.as_bytes()
+        && letter.is_ascii_alphabetic()
+    {
+        path[1..2].make_ascii_lowercase();
+    }
+
+    let mut normalized = uri.clone();
+    normalized.set_path(&path);
+    normalized
+}
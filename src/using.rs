@@ -0,0 +1,167 @@
+//! The `USING TTL` and `USING TIMESTAMP` of `INSERT`, `UPDATE` and `DELETE`, whose literal
+//! values are checked to be in range, and whose TTL is checked to be allowed at all, along with
+//! ScyllaDB's `USING TIMEOUT`, whose value is checked to be a duration.
+
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use crate::{
+    counters,
+    diagnostics::Problem,
+    lexer::{Token, TokenKind},
+    schema::Table,
+    statement::{Statement, StatementKind},
+};
+
+/// The longest time to live Cassandra accepts, in seconds: 20 years
+pub const MAX_TTL: u64 = 20 * 365 * 24 * 60 * 60;
+
+/// Whether `table` has counter columns, which can't be given a time to live
+pub fn is_counter_table(table: &Table) -> bool {
+    table
+        .columns
+        .iter()
+        .any(|column| column.data_type.is_counter())
+}
+
+/// The options of the `USING` clause, as the option's keyword and its value's tokens
+pub fn options<'a>(statement: &Statement<'a>) -> Vec<(&'a Token<'a>, &'a [Token<'a>])> {
+    let Some(using) = statement.find_keyword("USING") else {
+        return vec![];
+    };
+
+    let tokens = &statement.tokens[using + 1..];
+    let mut options = vec![];
+    let mut i = 0;
+
+    while let Some(option) = tokens.get(i) {
+        if !["TTL", "TIMESTAMP", "TIMEOUT"]
+            .iter()
+            .any(|keyword| option.is_keyword(keyword))
+        {
+            break;
+        }
+
+        // The value is a number, possibly negative, or a bind marker
+        let len = match tokens.get(i + 1) {
+            Some(minus) if minus.is_punctuation("-") => 2,
+            _ => 1,
+        };
+        let value = &tokens[(i + 1).min(tokens.len())..(i + 1 + len).min(tokens.len())];
+        options.push((option, value));
+
+        match tokens.get(i + 1 + len) {
+            Some(and) if and.is_keyword("AND") => i += 2 + len,
+            _ => break,
+        }
+    }
+
+    options
+}
+
+/// The value of an integer literal, negative or not, or `None` for anything else, e.g. a bind
+/// marker
+fn integer(value: &[Token]) -> Option<Result<i128, ()>> {
+    let (negative, number) = match value {
+        [minus, number] if minus.is_punctuation("-") => (true, number),
+        [number] => (false, number),
+        _ => return None,
+    };
+
+    if number.kind != TokenKind::Number {
+        return None;
+    }
+
+    let Ok(number) = number.text.parse::<i128>() else {
+        return Some(Err(()));
+    };
+
+    Some(Ok(if negative { -number } else { number }))
+}
+
+/// Checks the `USING` options of an `INSERT`, `UPDATE` or `DELETE` of `table`, when it's known:
+/// TTLs must be between 0 and 20 years, timestamps integers, and neither `DELETE` nor counter
+/// tables take a TTL
+pub fn check(statement: &Statement, table: Option<&Table>) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    for (option, value) in options(statement) {
+        let error = |tokens: &[Token], message: String| Problem {
+            span: tokens.first().map_or(option.start, |t| t.start)
+                ..tokens.last().map_or(option.end, |t| t.end),
+            severity: DiagnosticSeverity::ERROR,
+            message,
+            lint: None,
+        };
+
+        if option.is_keyword("TIMEOUT") {
+            if !value.is_empty()
+                && !matches!(
+                    value,
+                    [duration] if matches!(
+                        duration.kind,
+                        TokenKind::Duration | TokenKind::BindMarker | TokenKind::NamedBindMarker
+                    )
+                )
+            {
+                problems.push(error(
+                    value,
+                    "The timeout must be a duration, e.g. `500ms`".into(),
+                ));
+            }
+
+            continue;
+        }
+
+        if option.is_keyword("TIMESTAMP") {
+            if integer(value) == Some(Err(())) {
+                problems.push(error(
+                    value,
+                    "The timestamp must be an integer number of microseconds since the Unix epoch"
+                        .into(),
+                ));
+            }
+
+            continue;
+        }
+
+        if statement.kind() == StatementKind::Delete {
+            problems.push(error(
+                std::slice::from_ref(option),
+                "DELETE doesn't take a TTL, only USING TIMESTAMP".into(),
+            ));
+            continue;
+        }
+
+        if let Some(table) = table.filter(|table| is_counter_table(table)) {
+            problems.push(Problem {
+                lint: Some(counters::COUNTER_TTL),
+                ..error(
+                    std::slice::from_ref(option),
+                    format!(
+                        "`{}` is a counter table: counters can't be given a TTL",
+                        table.name
+                    ),
+                )
+            });
+            continue;
+        }
+
+        match integer(value) {
+            Some(Ok(ttl)) if ttl < 0 => problems.push(error(
+                value,
+                format!("The TTL can't be negative, found {ttl}"),
+            )),
+            Some(Ok(ttl)) if ttl > i128::from(MAX_TTL) => problems.push(error(
+                value,
+                format!("The TTL can be at most {MAX_TTL} seconds (20 years), found {ttl}"),
+            )),
+            Some(Err(())) => problems.push(error(
+                value,
+                "The TTL must be an integer number of seconds".into(),
+            )),
+            _ => {}
+        }
+    }
+
+    problems
+}
@@ -0,0 +1,343 @@
+//! An index of the `.cql` files of the workspace, so that tables and types created in one file
+//! (e.g. an earlier migration) are known in the others, and files that aren't open can be
+//! checked too.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use tower_lsp::lsp_types::Url;
+
+use crate::{
+    config::Config,
+    directives, lexer,
+    schema::Schema,
+    statement::{self, Dialect, Statement, StatementKind},
+    uri,
+};
+
+/// Directories that never hold schema files worth indexing
+const SKIPPED_DIRECTORIES: &[&str] = &["node_modules", "target"];
+
+/// Whether `statement` creates, alters or drops schema objects
+fn is_ddl(statement: &Statement) -> bool {
+    !matches!(
+        statement.kind(),
+        StatementKind::Select
+            | StatementKind::Insert
+            | StatementKind::Update
+            | StatementKind::Delete
+            | StatementKind::Batch
+            | StatementKind::Use
+            | StatementKind::Truncate
+            | StatementKind::Grant
+            | StatementKind::Revoke
+            | StatementKind::List
+            | StatementKind::Restrict
+            | StatementKind::Unrestrict
+            | StatementKind::SearchIndex
+            | StatementKind::Other
+    )
+}
+
+/// Collects the `.cql` files under `directory`, skipping hidden directories
+fn collect_files(directory: PathBuf, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(&directory) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        if path.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRECTORIES.contains(&name.as_ref()) {
+                collect_files(path, files);
+            }
+        } else if path.extension().is_some_and(|extension| extension == "cql") {
+            files.push(path);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct IndexedFile {
+    /// The saved text of the file, or only its schema statements if it's large
+    text: String,
+    defines_schema: bool,
+    /// Whether the file was too large to keep whole, e.g. a generated dump
+    abridged: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct WorkspaceIndex {
+    /// The workspace folders given by the client
+    roots: Vec<PathBuf>,
+    /// Files defining schema objects are replayed in URI order, after the migrations
+    files: BTreeMap<Url, IndexedFile>,
+    /// The directories holding the migrations, which are replayed in the order of the numbers
+    /// their names start with
+    migrations: Vec<PathBuf>,
+    /// The schema of the connected cluster, which the indexed files are replayed on top of
+    cluster_schema: Schema,
+    /// How the files are split into statements
+    dialect: Dialect,
+    /// The size in bytes above which files are abridged to their schema statements
+    large_file_size: usize,
+    /// The files and directories meant for the clusters of protected profiles
+    protected_paths: Vec<PathBuf>,
+    /// Bumped whenever the schema defined by the indexed files or the cluster changes, so
+    /// results derived from it can tell whether they're stale
+    generation: u64,
+}
+
+impl WorkspaceIndex {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        Self {
+            roots,
+            files: BTreeMap::new(),
+            migrations: vec![],
+            cluster_schema: Schema::default(),
+            dialect: Dialect::default(),
+            large_file_size: Config::default().large_file_size,
+            protected_paths: vec![],
+            generation: 0,
+        }
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Replaces the schema of the connected cluster, empty when disconnected
+    pub fn set_cluster_schema(&mut self, schema: Schema) {
+        self.cluster_schema = schema;
+        self.generation += 1;
+    }
+
+    /// Replaces the files and directories meant for the clusters of protected profiles
+    pub fn set_protected_paths(&mut self, paths: Vec<PathBuf>) {
+        if paths != self.protected_paths {
+            self.protected_paths = paths;
+            // The diagnostics of their files change with them
+            self.generation += 1;
+        }
+    }
+
+    /// Whether the document at `uri` is meant for the cluster of a protected profile, being
+    /// one of its files or in one of its directories
+    pub fn is_protected(&self, uri: &Url) -> bool {
+        let Ok(path) = uri.to_file_path() else {
+            return false;
+        };
+
+        self.protected_paths
+            .iter()
+            .any(|protected| path.starts_with(protected))
+    }
+
+    /// Applies the settings changing how the files are read and replayed, returning whether the
+    /// schema may have changed
+    pub fn configure(&mut self, config: &Config) -> bool {
+        // Relative directories are looked up in every workspace folder
+        let migrations = match &config.migrations_directory {
+            Some(directory) => self.roots.iter().map(|root| root.join(directory)).collect(),
+            None => vec![],
+        };
+
+        // Files indexed since are abridged past the new size
+        self.large_file_size = config.large_file_size;
+
+        if config.dialect == self.dialect && migrations == self.migrations {
+            return false;
+        }
+
+        if config.dialect != self.dialect {
+            self.dialect = config.dialect;
+
+            for file in self.files.values_mut() {
+                let tokens = lexer::tokenize(&file.text);
+                let dialect = directives::dialect(&file.text, self.dialect);
+                file.defines_schema = statement::split(&tokens, dialect).iter().any(is_ddl);
+            }
+        }
+
+        self.migrations = migrations;
+        self.generation += 1;
+
+        true
+    }
+
+    /// Where the file at `uri` comes in the migrations, if it's one: by the number its name
+    /// starts with, files without one coming last, then by path
+    fn migration_order(&self, uri: &Url) -> Option<(u64, PathBuf)> {
+        let path = uri.to_file_path().ok()?;
+        let relative = self
+            .migrations
+            .iter()
+            .find_map(|directory| path.strip_prefix(directory).ok())?;
+
+        let name = path.file_name()?.to_string_lossy();
+        let digits = name.len() - name.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let number = name[..digits].parse().unwrap_or(u64::MAX);
+
+        Some((number, relative.to_path_buf()))
+    }
+
+    /// Lists the `.cql` files under the workspace folders
+    pub fn workspace_files(&self) -> Vec<PathBuf> {
+        let mut files = vec![];
+
+        for root in &self.roots {
+            collect_files(root.clone(), &mut files);
+        }
+
+        files
+    }
+
+    /// Indexes the file at `path` as saved on disk
+    pub fn index_file(&mut self, path: &Path) {
+        if let (Ok(uri), Ok(text)) = (Url::from_file_path(path), fs::read_to_string(path)) {
+            self.update(&uri, text);
+        }
+    }
+
+    /// Updates the indexed text of the file at `uri`, returning whether the schema changed,
+    /// i.e. whether the file changed and defines or used to define schema objects. Documents
+    /// that aren't files, e.g. unsaved ones, aren't part of the workspace. Of a large file,
+    /// such as a dump of data, only the statements defining the schema are kept, as they're
+    /// replayed for every request.
+    pub fn update(&mut self, uri: &Url, text: String) -> bool {
+        if !uri::is_file(uri) {
+            return false;
+        }
+
+        let tokens = lexer::tokenize(&text);
+        let statements = statement::split(&tokens, directives::dialect(&text, self.dialect));
+        let defines_schema = statements.iter().any(is_ddl);
+        let abridged = text.len() > self.large_file_size;
+
+        let text = match abridged {
+            true => statements
+                .iter()
+                .filter(|statement| is_ddl(statement) || statement.kind() == StatementKind::Use)
+                .map(|statement| match statement.terminated {
+                    true => format!("{}\n", &text[statement.start..statement.end]),
+                    false => format!("{};\n", &text[statement.start..statement.end]),
+                })
+                .collect(),
+            false => text,
+        };
+
+        let previous = self.files.insert(
+            uri::normalize(uri),
+            IndexedFile {
+                text: text.clone(),
+                defines_schema,
+                abridged,
+            },
+        );
+
+        let changed = match previous {
+            Some(previous) => (previous.defines_schema || defines_schema) && previous.text != text,
+            None => defines_schema,
+        };
+
+        if changed {
+            self.generation += 1;
+        }
+
+        changed
+    }
+
+    /// Drops the file at `uri` from the index, e.g. once deleted, returning whether the schema
+    /// changed
+    pub fn remove(&mut self, uri: &Url) -> bool {
+        let changed = self
+            .files
+            .remove(&uri::normalize(uri))
+            .is_some_and(|file| file.defines_schema);
+
+        if changed {
+            self.generation += 1;
+        }
+
+        changed
+    }
+
+    /// The indexed files with their saved text, but for the large ones, which only define
+    /// the schema
+    pub fn files(&self) -> impl Iterator<Item = (&Url, &str)> {
+        self.files
+            .iter()
+            .filter(|(_, file)| !file.abridged)
+            .map(|(uri, file)| (uri, file.text.as_str()))
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Builds the schema defined by the cluster and every indexed file but `excluded`,
+    /// typically the document being checked, whose own statements are replayed on top
+    pub fn schema(&self, excluded: Option<&Url>) -> Schema {
+        self.replay(self.cluster_schema.clone(), excluded)
+    }
+
+    /// Builds the schema defined by the indexed files alone, leaving out the cluster's
+    pub fn files_schema(&self) -> Schema {
+        self.replay(Schema::default(), None)
+    }
+
+    /// Replays the indexed files but `excluded` on top of `schema`: the migrations in order,
+    /// then the other files. When `excluded` is a migration, only the migrations before it are
+    /// replayed, as it runs on the schema they leave.
+    fn replay(&self, mut schema: Schema, excluded: Option<&Url>) -> Schema {
+        let excluded = excluded.map(uri::normalize);
+        let mut migrations = vec![];
+        let mut others = vec![];
+
+        for (uri, file) in &self.files {
+            if Some(uri) == excluded.as_ref() || !file.defines_schema {
+                continue;
+            }
+
+            match self.migration_order(uri) {
+                Some(order) => migrations.push((order, file)),
+                None => others.push(file),
+            }
+        }
+
+        migrations.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let files = match excluded.and_then(|uri| self.migration_order(&uri)) {
+            Some(excluded) => migrations
+                .into_iter()
+                .filter(|(order, _)| *order < excluded)
+                .map(|(_, file)| file)
+                .collect::<Vec<_>>(),
+            None => migrations
+                .into_iter()
+                .map(|(_, file)| file)
+                .chain(others)
+                .collect(),
+        };
+
+        for file in files {
+            let tokens = lexer::tokenize(&file.text);
+            // `USE` doesn't carry over from one file to the next
+            let mut keyspace = None;
+
+            let dialect = directives::dialect(&file.text, self.dialect);
+
+            for statement in statement::split(&tokens, dialect) {
+                schema.apply(&statement, &mut keyspace);
+            }
+        }
+
+        schema
+    }
+}